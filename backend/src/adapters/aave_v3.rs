@@ -0,0 +1,847 @@
+use alloy::{
+    primitives::{Address, U256},
+    sol,
+};
+use futures::future::join_all;
+use serde::Serialize;
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position};
+use crate::blockchain::EthereumClient;
+use crate::risk::{RiskInputs, RiskModelRegistry};
+use crate::services::{BalanceSemantics, HealthFactorThresholds, PriceService, PriceSnapshotStore, TokenMetadataCache};
+use crate::utils::{market_read_timeout, with_timeout};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+// Aave V3 interfaces, shared by forks that keep Aave's pool and data provider ABI
+sol! {
+    interface IAaveV3Pool {
+        struct EModeCategory {
+            uint16 ltv;
+            uint16 liquidationThreshold;
+            uint16 liquidationBonus;
+            address priceSource;
+            string label;
+        }
+
+        function getReservesList() external view returns (address[] memory);
+        function getUserEMode(address user) external view returns (uint256);
+        function getEModeCategoryData(uint8 id) external view returns (EModeCategory memory);
+        function getUserAccountData(address user) external view returns (
+            uint256 totalCollateralBase,
+            uint256 totalDebtBase,
+            uint256 availableBorrowsBase,
+            uint256 currentLiquidationThreshold,
+            uint256 ltv,
+            uint256 healthFactor
+        );
+    }
+
+    interface IAaveV3DataProvider {
+        function getUserReserveData(address asset, address user) external view returns (
+            uint256 currentATokenBalance,
+            uint256 currentStableDebt,
+            uint256 currentVariableDebt,
+            uint256 principalStableDebt,
+            uint256 scaledVariableDebt,
+            uint256 stableBorrowRate,
+            uint256 liquidityRate,
+            uint40 stableRateLastUpdated,
+            bool usageAsCollateralEnabled
+        );
+        function getReserveConfigurationData(address asset) external view returns (
+            uint256 decimals,
+            uint256 ltv,
+            uint256 liquidationThreshold,
+            uint256 liquidationBonus,
+            uint256 reserveFactor,
+            bool usageAsCollateralEnabled,
+            bool borrowingEnabled,
+            bool stableBorrowRateEnabled,
+            bool isActive,
+            bool isFrozen
+        );
+        function getReserveData(address asset) external view returns (
+            uint256 unbacked,
+            uint256 accruedToTreasuryScaled,
+            uint256 totalAToken,
+            uint256 totalStableDebt,
+            uint256 totalVariableDebt,
+            uint256 liquidityRate,
+            uint256 variableBorrowRate,
+            uint256 stableBorrowRate,
+            uint256 averageStableBorrowRate,
+            uint256 liquidityIndex,
+            uint256 variableBorrowIndex,
+            uint40 lastUpdateTimestamp
+        );
+        function getReserveEModeCategory(address asset) external view returns (uint256);
+        function getDebtCeiling(address asset) external view returns (uint256);
+        function getReserveTokensAddresses(address asset) external view returns (
+            address aTokenAddress,
+            address stableDebtTokenAddress,
+            address variableDebtTokenAddress
+        );
+    }
+
+    interface IAaveV3IncentivizedToken {
+        function getIncentivesController() external view returns (address);
+    }
+
+    interface IAaveV3RewardsController {
+        function getAllUserRewards(address[] calldata assets, address user) external view returns (
+            address[] memory rewardsList,
+            uint256[] memory unclaimedAmounts
+        );
+    }
+
+    interface IAaveOracle {
+        function getAssetPrice(address asset) external view returns (uint256);
+        function BASE_CURRENCY_UNIT() external view returns (uint256);
+    }
+}
+
+/// Contracts of one Aave V3 (or fork) market
+#[derive(Debug, Clone, Copy)]
+pub struct AaveV3Deployment {
+    pub pool: Address,
+    pub data_provider: Address,
+    pub oracle: Address,
+}
+
+/// Incentive tokens accrued on a supply or borrow and not yet claimed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AaveV3Reward {
+    pub token: Address,
+    pub symbol: String,
+    /// In whole tokens
+    pub amount: f64,
+    /// `None` when the reward token has no confident price
+    pub price_usd: Option<f64>,
+}
+
+impl AaveV3Reward {
+    pub fn value_usd(&self) -> f64 {
+        self.amount * self.price_usd.unwrap_or(0.0)
+    }
+}
+
+/// A user's balances in one reserve, in token units
+#[derive(Debug, Clone)]
+pub struct AaveV3ReserveBalance {
+    pub asset: Address,
+    pub symbol: String,
+    pub supplied: f64,
+    pub borrowed: f64,
+    pub used_as_collateral: bool,
+    pub price_usd: f64,
+    /// Liquidation threshold as a fraction, e.g. 0.83; the e-mode category's threshold
+    /// when the user's e-mode covers this reserve
+    pub liquidation_threshold: f64,
+    /// E-mode category the reserve belongs to, 0 for none
+    pub emode_category: u8,
+    /// Whether the reserve has a debt ceiling, making it isolated collateral
+    pub isolated: bool,
+    pub supply_apy: f64,
+    pub borrow_apy: f64,
+    /// Borrowed over supplied across the whole reserve
+    pub utilization: f64,
+    /// Unclaimed incentives on the aToken
+    pub supply_rewards: Vec<AaveV3Reward>,
+    /// Unclaimed incentives on the debt tokens
+    pub borrow_rewards: Vec<AaveV3Reward>,
+}
+
+impl AaveV3ReserveBalance {
+    pub fn supply_value_usd(&self) -> f64 {
+        self.supplied * self.price_usd
+    }
+
+    pub fn borrow_value_usd(&self) -> f64 {
+        self.borrowed * self.price_usd
+    }
+}
+
+/// Account-level totals plus every reserve the user has a balance in
+#[derive(Debug, Clone)]
+pub struct AaveV3Account {
+    pub total_collateral_usd: f64,
+    pub total_debt_usd: f64,
+    pub available_borrows_usd: f64,
+    /// Weighted liquidation threshold across collateral, as a fraction
+    pub liquidation_threshold: f64,
+    pub ltv: f64,
+    /// Infinite when the account has no debt
+    pub health_factor: f64,
+    /// The user's efficiency-mode category, when one is active
+    pub emode: Option<AaveV3EMode>,
+    /// Whether the only collateral is an isolated asset, which restricts borrowing to
+    /// stablecoins up to the asset's debt ceiling
+    pub isolation_mode: bool,
+    /// Some reserves failed or timed out and were skipped, so `reserves` may be incomplete
+    pub partial: bool,
+    pub reserves: Vec<AaveV3ReserveBalance>,
+}
+
+/// An e-mode category, whose higher LTV and liquidation threshold replace those of
+/// every reserve in it while the user has it active
+#[derive(Debug, Clone, PartialEq)]
+pub struct AaveV3EMode {
+    pub category: u8,
+    pub label: String,
+    pub ltv: f64,
+    pub liquidation_threshold: f64,
+}
+
+impl AaveV3Account {
+    /// Swap in the e-mode threshold on reserves covered by the user's category and
+    /// flag isolation mode
+    fn apply_modes(&mut self) {
+        if let Some(emode) = &self.emode {
+            for reserve in self.reserves.iter_mut().filter(|r| r.emode_category == emode.category) {
+                reserve.liquidation_threshold = emode.liquidation_threshold;
+            }
+        }
+
+        let mut collateral = self.reserves.iter().filter(|r| r.used_as_collateral && r.supplied > 0.0);
+        self.isolation_mode = match (collateral.next(), collateral.next()) {
+            (Some(only), None) => only.isolated,
+            _ => false,
+        };
+    }
+}
+
+/// Reads user accounts from an Aave V3 pool through its data provider and oracle.
+///
+/// Aave forks (Spark, etc.) reuse the same contracts at different addresses, so protocol
+/// adapters wrap a reader per deployment and turn its accounts into positions with
+/// [`account_positions`].
+pub struct AaveV3Reader {
+    client: EthereumClient,
+    chain_id: u64,
+    deployment: AaveV3Deployment,
+    token_metadata: Arc<TokenMetadataCache>,
+    price_service: Arc<PriceService>,
+    reserves_cache: Mutex<Option<(Vec<Address>, SystemTime)>>,
+}
+
+impl AaveV3Reader {
+    const RESERVES_CACHE_DURATION: Duration = Duration::from_secs(3600); // 1 hour
+    /// Rates are per-year APRs in ray units
+    const RAY: f64 = 1e27;
+    const WAD: f64 = 1e18;
+    /// Percentages (LTV, thresholds) use 2 decimals of basis points
+    const PERCENTAGE_FACTOR: f64 = 1e4;
+    const SECONDS_PER_YEAR: f64 = 31_536_000.0;
+
+    pub fn new(client: EthereumClient, chain_id: u64, deployment: AaveV3Deployment) -> Self {
+        Self {
+            client,
+            chain_id,
+            deployment,
+            token_metadata: TokenMetadataCache::global(),
+            price_service: PriceService::global(),
+            reserves_cache: Mutex::new(None),
+        }
+    }
+
+    pub fn client(&self) -> &EthereumClient {
+        &self.client
+    }
+
+    pub fn deployment(&self) -> &AaveV3Deployment {
+        &self.deployment
+    }
+
+    /// Listed reserves, cached for an hour
+    pub async fn reserves(&self) -> Result<Vec<Address>, AdapterError> {
+        {
+            let cache = self.reserves_cache.lock().unwrap();
+            if let Some((reserves, cached_at)) = cache.as_ref() {
+                if cached_at.elapsed().unwrap_or(Duration::MAX) < Self::RESERVES_CACHE_DURATION {
+                    return Ok(reserves.clone());
+                }
+            }
+        }
+
+        let reserves = self.client.call(self.deployment.pool, &IAaveV3Pool::getReservesListCall {}).await?._0;
+        *self.reserves_cache.lock().unwrap() = Some((reserves.clone(), SystemTime::now()));
+        Ok(reserves)
+    }
+
+    /// Whether `address` is one of the pool's listed reserves (from the cached list)
+    pub fn is_cached_reserve(&self, address: Address) -> bool {
+        let cache = self.reserves_cache.lock().unwrap();
+        cache.as_ref().map_or(false, |(reserves, _)| reserves.contains(&address))
+    }
+
+    /// The user's account, or `None` when they have no supply or debt in this market.
+    /// A reserve that fails or exceeds `market_read_timeout` is skipped and the account
+    /// marked partial.
+    pub async fn account(&self, user: Address) -> Result<Option<AaveV3Account>, AdapterError> {
+        let (account, base_unit, emode) = tokio::try_join!(
+            self.client.call(self.deployment.pool, &IAaveV3Pool::getUserAccountDataCall { user }),
+            self.client.call(self.deployment.oracle, &IAaveOracle::BASE_CURRENCY_UNITCall {}),
+            self.user_emode(user),
+        )?;
+        let base_unit = Self::to_f64(base_unit._0).max(1.0);
+
+        let reserves = self.reserves().await?;
+        let timeout = market_read_timeout();
+        let balances = join_all(reserves.iter().map(|asset| {
+            with_timeout(timeout, "Aave V3 reserve read", self.reserve_balance(*asset, user, base_unit))
+        })).await;
+
+        let mut held = Vec::new();
+        let mut partial = false;
+        for (asset, result) in reserves.iter().zip(balances) {
+            match result {
+                Ok(Some(balance)) => held.push(balance),
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!("Skipping Aave V3 reserve {:?}: {}", asset, e);
+                    partial = true;
+                }
+            }
+        }
+
+        if held.is_empty() {
+            // The pool reports a balance that the skipped reserves must hold
+            if partial && (account.totalCollateralBase > U256::ZERO || account.totalDebtBase > U256::ZERO) {
+                return Err(AdapterError::RpcError(format!("No Aave V3 reserve of {:?} could be read", user)));
+            }
+            return Ok(None);
+        }
+
+        // The pool's account data already accounts for e-mode; per-reserve thresholds don't
+        let mut account = AaveV3Account {
+            total_collateral_usd: Self::to_f64(account.totalCollateralBase) / base_unit,
+            total_debt_usd: Self::to_f64(account.totalDebtBase) / base_unit,
+            available_borrows_usd: Self::to_f64(account.availableBorrowsBase) / base_unit,
+            liquidation_threshold: Self::to_f64(account.currentLiquidationThreshold) / Self::PERCENTAGE_FACTOR,
+            ltv: Self::to_f64(account.ltv) / Self::PERCENTAGE_FACTOR,
+            health_factor: Self::health_factor(account.totalDebtBase, account.healthFactor),
+            emode,
+            isolation_mode: false,
+            partial,
+            reserves: held,
+        };
+        account.apply_modes();
+        Ok(Some(account))
+    }
+
+    /// The user's active e-mode category. Deployments without e-mode report none.
+    async fn user_emode(&self, user: Address) -> Result<Option<AaveV3EMode>, AdapterError> {
+        let pool = self.deployment.pool;
+        let category = match self.client.call(pool, &IAaveV3Pool::getUserEModeCall { user }).await {
+            Ok(category) => u8::try_from(category._0).unwrap_or(0),
+            Err(e) => {
+                tracing::debug!("No e-mode support on Aave V3 pool {:?}: {}", pool, e);
+                return Ok(None);
+            }
+        };
+        if category == 0 {
+            return Ok(None);
+        }
+
+        let data = self.client.call(pool, &IAaveV3Pool::getEModeCategoryDataCall { id: category }).await?._0;
+        Ok(Some(AaveV3EMode {
+            category,
+            label: data.label,
+            ltv: data.ltv as f64 / Self::PERCENTAGE_FACTOR,
+            liquidation_threshold: data.liquidationThreshold as f64 / Self::PERCENTAGE_FACTOR,
+        }))
+    }
+
+    async fn reserve_balance(
+        &self,
+        asset: Address,
+        user: Address,
+        base_unit: f64,
+    ) -> Result<Option<AaveV3ReserveBalance>, AdapterError> {
+        let provider = self.deployment.data_provider;
+        let user_data = self.client
+            .call(provider, &IAaveV3DataProvider::getUserReserveDataCall { asset, user })
+            .await?;
+
+        let debt = user_data.currentStableDebt + user_data.currentVariableDebt;
+        if user_data.currentATokenBalance == U256::ZERO && debt == U256::ZERO {
+            return Ok(None);
+        }
+
+        // E-mode and debt ceilings are optional, so forks without them still read
+        let (required, emode_category, debt_ceiling) = tokio::join!(
+            async {
+                tokio::try_join!(
+                    self.client.call(provider, &IAaveV3DataProvider::getReserveConfigurationDataCall { asset }),
+                    self.client.call(provider, &IAaveV3DataProvider::getReserveDataCall { asset }),
+                    self.client.call(self.deployment.oracle, &IAaveOracle::getAssetPriceCall { asset }),
+                    self.token_metadata.get_or_fetch(&self.client, self.chain_id, asset),
+                )
+            },
+            self.client.call(provider, &IAaveV3DataProvider::getReserveEModeCategoryCall { asset }),
+            self.client.call(provider, &IAaveV3DataProvider::getDebtCeilingCall { asset }),
+        );
+        let (config, reserve, price, metadata) = required?;
+        let has_supply = user_data.currentATokenBalance != U256::ZERO;
+        let (supply_rewards, borrow_rewards) = self.reserve_rewards(asset, user, has_supply, debt != U256::ZERO).await;
+
+        let scale = 10f64.powi(metadata.decimals as i32);
+        let total_supplied = Self::to_f64(reserve.totalAToken);
+        let total_borrowed = Self::to_f64(reserve.totalStableDebt + reserve.totalVariableDebt);
+
+        Ok(Some(AaveV3ReserveBalance {
+            asset,
+            symbol: metadata.symbol,
+            supplied: Self::to_f64(user_data.currentATokenBalance) / scale,
+            borrowed: Self::to_f64(debt) / scale,
+            used_as_collateral: user_data.usageAsCollateralEnabled,
+            price_usd: Self::to_f64(price._0) / base_unit,
+            liquidation_threshold: Self::to_f64(config.liquidationThreshold) / Self::PERCENTAGE_FACTOR,
+            emode_category: emode_category.map_or(0, |c| u8::try_from(c._0).unwrap_or(0)),
+            isolated: debt_ceiling.map_or(false, |c| !c._0.is_zero()),
+            supply_apy: Self::ray_apr_to_apy(reserve.liquidityRate),
+            borrow_apy: Self::ray_apr_to_apy(reserve.variableBorrowRate),
+            utilization: if total_supplied > 0.0 { total_borrowed / total_supplied } else { 0.0 },
+            supply_rewards,
+            borrow_rewards,
+        }))
+    }
+
+    /// Unclaimed incentives on the reserve's aToken and debt tokens. Rewards only add
+    /// to returns, so a failed read is logged and reported as none.
+    async fn reserve_rewards(
+        &self,
+        asset: Address,
+        user: Address,
+        has_supply: bool,
+        has_debt: bool,
+    ) -> (Vec<AaveV3Reward>, Vec<AaveV3Reward>) {
+        let tokens = match self.client
+            .call(self.deployment.data_provider, &IAaveV3DataProvider::getReserveTokensAddressesCall { asset })
+            .await
+        {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                tracing::debug!("No token addresses for Aave V3 reserve {:?}: {}", asset, e);
+                return (Vec::new(), Vec::new());
+            }
+        };
+
+        let (supply, borrow) = tokio::join!(
+            async {
+                if has_supply {
+                    self.pending_rewards(vec![tokens.aTokenAddress], user).await
+                } else {
+                    Ok(Vec::new())
+                }
+            },
+            async {
+                if has_debt {
+                    self.pending_rewards(vec![tokens.variableDebtTokenAddress, tokens.stableDebtTokenAddress], user).await
+                } else {
+                    Ok(Vec::new())
+                }
+            },
+        );
+        let or_none = |result: Result<Vec<AaveV3Reward>, AdapterError>| {
+            result.unwrap_or_else(|e| {
+                tracing::warn!("Failed to read Aave V3 rewards for reserve {:?}: {}", asset, e);
+                Vec::new()
+            })
+        };
+        (or_none(supply), or_none(borrow))
+    }
+
+    /// `getAllUserRewards` on the controller the tokens report; tokens without one
+    /// (the zero address) earn nothing
+    async fn pending_rewards(&self, tokens: Vec<Address>, user: Address) -> Result<Vec<AaveV3Reward>, AdapterError> {
+        let Some(first) = tokens.first() else {
+            return Ok(Vec::new());
+        };
+        let controller = self.client
+            .call(*first, &IAaveV3IncentivizedToken::getIncentivesControllerCall {})
+            .await?
+            ._0;
+        if controller == Address::ZERO {
+            return Ok(Vec::new());
+        }
+
+        let accrued = self.client
+            .call(controller, &IAaveV3RewardsController::getAllUserRewardsCall { assets: tokens, user })
+            .await?;
+        let unclaimed: Vec<(Address, U256)> = accrued.rewardsList
+            .into_iter()
+            .zip(accrued.unclaimedAmounts)
+            .filter(|(_, amount)| !amount.is_zero())
+            .collect();
+
+        let rewards = join_all(unclaimed.into_iter().map(|(token, amount)| async move {
+            let metadata = self.token_metadata.get_or_fetch(&self.client, self.chain_id, token).await?;
+            let price_usd = match self.price_service.get_token_price(self.chain_id, token, Some(&metadata.symbol)).await {
+                Ok(price) => Some(price),
+                Err(e) => {
+                    tracing::warn!("No price for Aave V3 reward token {}: {}", metadata.symbol, e);
+                    None
+                }
+            };
+            Ok::<_, AdapterError>(AaveV3Reward {
+                token,
+                amount: Self::to_f64(amount) / 10f64.powi(metadata.decimals as i32),
+                symbol: metadata.symbol,
+                price_usd,
+            })
+        }))
+        .await;
+
+        rewards.into_iter().collect()
+    }
+
+    /// Health factor from `getUserAccountData`, which reports `uint256::MAX` without debt
+    fn health_factor(total_debt: U256, health_factor: U256) -> f64 {
+        if total_debt == U256::ZERO || health_factor == U256::MAX {
+            f64::INFINITY
+        } else {
+            Self::to_f64(health_factor) / Self::WAD
+        }
+    }
+
+    /// Per-second compounded APY percentage from an annual ray-denominated rate
+    fn ray_apr_to_apy(rate: U256) -> f64 {
+        let apr = Self::to_f64(rate) / Self::RAY;
+        ((1.0 + apr / Self::SECONDS_PER_YEAR).powf(Self::SECONDS_PER_YEAR) - 1.0) * 100.0
+    }
+
+    fn to_f64(value: U256) -> f64 {
+        value.to_string().parse().unwrap_or(0.0)
+    }
+}
+
+/// Supply and borrow positions for an Aave V3 account.
+///
+/// Collateral is pooled across reserves, so each borrow's pair names every collateral
+/// asset and every position carries the account-wide health factor.
+pub fn account_positions(
+    protocol: &str,
+    chain_id: u64,
+    user: Address,
+    account: &AaveV3Account,
+    thresholds: &HealthFactorThresholds,
+    entries: &PriceSnapshotStore,
+) -> Vec<Position> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let health_factor = account.health_factor.is_finite().then_some(account.health_factor);
+    let collateral_symbols = account.reserves
+        .iter()
+        .filter(|r| r.used_as_collateral && r.supplied > 0.0)
+        .map(|r| r.symbol.as_str())
+        .collect::<Vec<_>>()
+        .join("+");
+    let account_health = serde_json::json!({
+        "health_factor": health_factor,
+        "total_collateral_usd": account.total_collateral_usd,
+        "total_debt_usd": account.total_debt_usd,
+        "available_borrows_usd": account.available_borrows_usd,
+        "ltv": account.ltv,
+        "liquidation_threshold": account.liquidation_threshold,
+        "at_risk": account.health_factor < thresholds.warning,
+        "partial": account.partial,
+        "emode": account.emode.as_ref().map(|emode| serde_json::json!({
+            "category": emode.category,
+            "label": emode.label,
+            "ltv": emode.ltv,
+            "liquidation_threshold": emode.liquidation_threshold,
+        })),
+    });
+    let emode_category = account.emode.as_ref().map_or(0, |emode| emode.category);
+    // Each collateral at its effective threshold, for simulating price drops
+    let collateral: Vec<serde_json::Value> = account.reserves
+        .iter()
+        .filter(|r| r.used_as_collateral && r.supplied > 0.0)
+        .map(|r| serde_json::json!({
+            "symbol": r.symbol,
+            "value_usd": r.supply_value_usd(),
+            "liquidation_threshold": r.liquidation_threshold,
+        }))
+        .collect();
+    let models = RiskModelRegistry::global();
+    let borrow_risk = models.score("borrow", &RiskInputs { health_factor: Some(account.health_factor), ..Default::default() });
+    // aTokens and debt tokens rebase, so growth over the balance first seen is interest
+    // rather than price movement; unclaimed incentives come on top
+    let entry_pnl = |kind: &str, reserve: &AaveV3ReserveBalance, quantity: f64, rewards: &[AaveV3Reward]| {
+        let entry = entries
+            .get_or_record(&position_id(protocol, chain_id, kind, reserve.asset, user), reserve.price_usd, quantity, now)
+            .attribute(BalanceSemantics::Rebasing, reserve.price_usd, quantity);
+        // Debt loses value as its price rises and as interest accrues
+        let sign = if kind == "borrow" { -1.0 } else { 1.0 };
+        PnlBreakdown {
+            price_pnl: sign * entry.price_pnl,
+            interest: sign * entry.yield_usd,
+            rewards_earned: rewards.iter().map(AaveV3Reward::value_usd).sum(),
+            ..Default::default()
+        }
+    };
+
+    let mut positions = Vec::new();
+    for reserve in &account.reserves {
+        if reserve.supplied > 0.0 {
+            let position_type = if reserve.used_as_collateral && account.total_debt_usd > 0.0 {
+                "collateral"
+            } else {
+                "supply"
+            };
+
+            let pnl = entry_pnl("supply", reserve, reserve.supplied, &reserve.supply_rewards);
+            positions.push(Position {
+                // A deposit keeps its ID when it starts or stops backing a borrow
+                id: position_id(protocol, chain_id, "supply", reserve.asset, user),
+                protocol: protocol.to_string(),
+                position_type: position_type.to_string(),
+                pair: reserve.symbol.clone(),
+                value_usd: reserve.supply_value_usd(),
+                pnl_usd: pnl.total(),
+                pnl_percentage: percent_of(pnl.total(), reserve.supply_value_usd()),
+                metadata: serde_json::json!({
+                    "token_address": format!("{:?}", reserve.asset),
+                    "underlying_asset": reserve.symbol,
+                    "account_health": account_health,
+                    "emode_category": emode_category,
+                    "isolation_mode": account.isolation_mode,
+                    "pnl_breakdown": pnl,
+                    "balance_semantics": BalanceSemantics::Rebasing,
+                    "pending_rewards": reserve.supply_rewards,
+                    "risk_score": models.score(position_type, &RiskInputs {
+                        health_factor: (position_type == "collateral").then_some(account.health_factor),
+                        utilization: Some(reserve.utilization),
+                        ..Default::default()
+                    }),
+                    "position_details": {
+                        "supplied": reserve.supplied,
+                        "supply_apy": reserve.supply_apy,
+                        "used_as_collateral": reserve.used_as_collateral,
+                        "liquidation_threshold": reserve.liquidation_threshold,
+                        "market_utilization": reserve.utilization,
+                    }
+                }),
+                last_updated: now,
+            });
+        }
+
+        if reserve.borrowed > 0.0 {
+            let pnl = entry_pnl("borrow", reserve, reserve.borrowed, &reserve.borrow_rewards);
+            positions.push(Position {
+                id: position_id(protocol, chain_id, "borrow", reserve.asset, user),
+                protocol: protocol.to_string(),
+                position_type: "borrow".to_string(),
+                pair: format!("{}/{}", reserve.symbol, collateral_symbols),
+                value_usd: -reserve.borrow_value_usd(),
+                pnl_usd: pnl.total(),
+                pnl_percentage: percent_of(pnl.total(), reserve.borrow_value_usd()),
+                metadata: serde_json::json!({
+                    "token_address": format!("{:?}", reserve.asset),
+                    "underlying_asset": reserve.symbol,
+                    "account_health": account_health,
+                    "emode_category": emode_category,
+                    "isolation_mode": account.isolation_mode,
+                    "pnl_breakdown": pnl,
+                    "balance_semantics": BalanceSemantics::Rebasing,
+                    "pending_rewards": reserve.borrow_rewards,
+                    "risk_score": borrow_risk,
+                    "position_details": {
+                        "borrowed": reserve.borrowed,
+                        "borrow_apy": reserve.borrow_apy,
+                        "health_factor": health_factor,
+                        "ltv": account.ltv,
+                        "liquidation_threshold": account.liquidation_threshold,
+                        "is_healthy": account.health_factor > 1.0,
+                        "collateral": collateral,
+                        "account_debt_usd": account.total_debt_usd,
+                    }
+                }),
+                last_updated: now,
+            });
+        }
+    }
+
+    positions
+}
+
+fn percent_of(amount: f64, base: f64) -> f64 {
+    if base > 0.0 { amount / base * 100.0 } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reserve(symbol: &str, supplied: f64, borrowed: f64, used_as_collateral: bool) -> AaveV3ReserveBalance {
+        AaveV3ReserveBalance {
+            asset: Address::ZERO,
+            symbol: symbol.to_string(),
+            supplied,
+            borrowed,
+            used_as_collateral,
+            price_usd: 1.0,
+            liquidation_threshold: 0.8,
+            emode_category: 0,
+            isolated: false,
+            supply_apy: 0.0,
+            borrow_apy: 0.0,
+            utilization: 0.5,
+            supply_rewards: Vec::new(),
+            borrow_rewards: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_health_factor_is_infinite_without_debt() {
+        assert!(AaveV3Reader::health_factor(U256::ZERO, U256::MAX).is_infinite());
+        let hf = AaveV3Reader::health_factor(U256::from(1u64), U256::from(1_500_000_000_000_000_000u128));
+        assert!((hf - 1.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_ray_rate_compounds_to_apy() {
+        // 5% APR in ray compounds to ~5.127% APY
+        let rate = U256::from(5u64) * U256::from(10u64).pow(U256::from(25u64));
+        assert!((AaveV3Reader::ray_apr_to_apy(rate) - 5.127).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_borrow_pair_names_pooled_collateral() {
+        let account = AaveV3Account {
+            total_collateral_usd: 2_000.0,
+            total_debt_usd: 500.0,
+            available_borrows_usd: 1_000.0,
+            liquidation_threshold: 0.8,
+            ltv: 0.25,
+            health_factor: 3.2,
+            emode: None,
+            isolation_mode: false,
+            partial: false,
+            reserves: vec![
+                reserve("WETH", 1_000.0, 0.0, true),
+                reserve("wstETH", 1_000.0, 0.0, true),
+                reserve("DAI", 0.0, 500.0, false),
+            ],
+        };
+
+        let positions = account_positions("spark", 1, Address::ZERO, &account, &HealthFactorThresholds::default(), &PriceSnapshotStore::new());
+        assert_eq!(positions.len(), 3);
+        assert_eq!(positions[0].position_type, "collateral");
+
+        let borrow = positions.iter().find(|p| p.position_type == "borrow").unwrap();
+        assert_eq!(borrow.pair, "DAI/WETH+wstETH");
+        assert_eq!(borrow.value_usd, -500.0);
+    }
+
+    #[test]
+    fn test_emode_threshold_replaces_reserve_threshold_in_category() {
+        let mut weth = reserve("WETH", 10.0, 0.0, true);
+        weth.emode_category = 1;
+        let mut account = AaveV3Account {
+            total_collateral_usd: 11.0,
+            total_debt_usd: 8.0,
+            available_borrows_usd: 0.0,
+            liquidation_threshold: 0.95,
+            ltv: 0.93,
+            health_factor: 1.3,
+            emode: Some(AaveV3EMode {
+                category: 1,
+                label: "ETH correlated".to_string(),
+                ltv: 0.93,
+                liquidation_threshold: 0.95,
+            }),
+            isolation_mode: false,
+            partial: false,
+            reserves: vec![weth, reserve("USDC", 1.0, 0.0, true), reserve("wstETH", 0.0, 8.0, false)],
+        };
+
+        account.apply_modes();
+
+        assert_eq!(account.reserves[0].liquidation_threshold, 0.95);
+        assert_eq!(account.reserves[1].liquidation_threshold, 0.8);
+        assert!(!account.isolation_mode);
+
+        let positions = account_positions("aave_v3", 1, Address::ZERO, &account, &HealthFactorThresholds::default(), &PriceSnapshotStore::new());
+        let borrow = positions.iter().find(|p| p.position_type == "borrow").unwrap();
+        assert_eq!(borrow.metadata["emode_category"], 1);
+        assert_eq!(borrow.metadata["position_details"]["collateral"][0]["liquidation_threshold"], 0.95);
+    }
+
+    #[test]
+    fn test_unclaimed_rewards_count_toward_position_return() {
+        let mut usdc = reserve("USDC", 10_000.0, 0.0, false);
+        usdc.supply_rewards = vec![
+            AaveV3Reward { token: Address::repeat_byte(1), symbol: "AAVE".to_string(), amount: 0.5, price_usd: Some(100.0) },
+            AaveV3Reward { token: Address::repeat_byte(2), symbol: "NEW".to_string(), amount: 20.0, price_usd: None },
+        ];
+        let account = AaveV3Account {
+            total_collateral_usd: 10_000.0,
+            total_debt_usd: 0.0,
+            available_borrows_usd: 8_000.0,
+            liquidation_threshold: 0.8,
+            ltv: 0.0,
+            health_factor: f64::INFINITY,
+            emode: None,
+            isolation_mode: false,
+            partial: false,
+            reserves: vec![usdc],
+        };
+
+        let positions = account_positions("aave_v3", 1, Address::ZERO, &account, &HealthFactorThresholds::default(), &PriceSnapshotStore::new());
+        let supply = &positions[0];
+        assert_eq!(supply.pnl_breakdown().unwrap().rewards_earned, 50.0);
+        assert_eq!(supply.pnl_usd, 50.0);
+        assert!((supply.pnl_percentage - 0.5).abs() < 1e-12);
+        assert_eq!(supply.metadata["pending_rewards"][1]["price_usd"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_atoken_balance_growth_is_interest() {
+        let entries = PriceSnapshotStore::new();
+        let account = |supplied: f64, price_usd: f64| AaveV3Account {
+            total_collateral_usd: supplied * price_usd,
+            total_debt_usd: 0.0,
+            available_borrows_usd: 0.0,
+            liquidation_threshold: 0.8,
+            ltv: 0.0,
+            health_factor: f64::INFINITY,
+            emode: None,
+            isolation_mode: false,
+            partial: false,
+            reserves: vec![AaveV3ReserveBalance { price_usd, ..reserve("WETH", supplied, 0.0, false) }],
+        };
+        let thresholds = HealthFactorThresholds::default();
+
+        account_positions("aave_v3", 1, Address::ZERO, &account(10.0, 2_000.0), &thresholds, &entries);
+        let later = account_positions("aave_v3", 1, Address::ZERO, &account(10.2, 2_100.0), &thresholds, &entries);
+
+        let pnl = later[0].pnl_breakdown().unwrap();
+        assert_eq!(pnl.price_pnl, 1_000.0);
+        assert!((pnl.interest - 420.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_single_isolated_collateral_is_isolation_mode() {
+        let mut isolated = reserve("GHST", 1_000.0, 0.0, true);
+        isolated.isolated = true;
+        let mut account = AaveV3Account {
+            total_collateral_usd: 1_000.0,
+            total_debt_usd: 100.0,
+            available_borrows_usd: 0.0,
+            liquidation_threshold: 0.8,
+            ltv: 0.5,
+            health_factor: 8.0,
+            emode: None,
+            isolation_mode: false,
+            partial: false,
+            reserves: vec![isolated.clone(), reserve("USDC", 0.0, 100.0, false)],
+        };
+        account.apply_modes();
+        assert!(account.isolation_mode);
+
+        account.reserves.push(reserve("WETH", 1.0, 0.0, true));
+        account.apply_modes();
+        assert!(!account.isolation_mode);
+    }
+}
@@ -1,386 +1,363 @@
-// Streamlined Balancer V2 Adapter focused on position fetching
-use alloy::primitives::{Address, U256};
-use alloy::sol;
-use alloy::providers::Provider;
+use alloy::{
+    primitives::{Address, U256},
+    sol,
+};
 use async_trait::async_trait;
-use serde::{Deserialize, Serialize};
-use crate::adapters::traits::{DeFiAdapter, Position, AdapterError};
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
 use crate::blockchain::EthereumClient;
-use std::str::FromStr;
+use crate::risk::{RiskInputs, RiskModelRegistry};
+use crate::services::{StakedLp, StakedLpScanner, StakingContract, StakingKind, TokenMetadataCache, IERC20};
+use reqwest;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
-// Balancer V2 Contract ABIs - simplified for key functions
+// Balancer V2 contract interfaces
 sol! {
-    #[allow(missing_docs)]
-    #[sol(rpc)]
     interface IBalancerVault {
         function getPoolTokens(bytes32 poolId) external view returns (
             address[] memory tokens,
             uint256[] memory balances,
             uint256 lastChangeBlock
         );
-        
-        function getPool(bytes32 poolId) external view returns (
-            address pool,
-            uint8 specialization
-        );
     }
 
-    #[allow(missing_docs)]
-    #[sol(rpc)]
     interface IBalancerPool {
         function getPoolId() external view returns (bytes32);
         function totalSupply() external view returns (uint256);
-        function balanceOf(address account) external view returns (uint256);
-        function getSwapFeePercentage() external view returns (uint256);
     }
 
-    #[allow(missing_docs)]
-    #[sol(rpc)]
-    interface IBalancerGauge {
-        function balanceOf(address account) external view returns (uint256);
-        function earned(address account) external view returns (uint256);
-        function rewardRate() external view returns (uint256);
-        function totalSupply() external view returns (uint256);
-    }
-
-    #[allow(missing_docs)]
-    #[sol(rpc)]
     interface IBalancerWeightedPool {
         function getNormalizedWeights() external view returns (uint256[] memory);
     }
+
+    interface IComposableStablePool {
+        function getActualSupply() external view returns (uint256);
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BalancerPoolInfo {
-    pub pool_id: String,
-    pub pool_address: String,
-    pub pool_type: String,
-    pub tokens: Vec<PoolToken>,
-    pub total_supply: f64,
-    pub swap_fee_percentage: f64,
-    pub weights: Vec<f64>, // For weighted pools
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalancerPoolType {
+    Weighted,
+    ComposableStable,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PoolToken {
-    pub address: String,
-    pub symbol: String,
-    pub balance: f64,
-    pub weight: f64, // 0.0-1.0 for weighted pools
+impl BalancerPoolType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BalancerPoolType::Weighted => "weighted",
+            BalancerPoolType::ComposableStable => "composable_stable",
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BalancerPosition {
-    pub pool_info: BalancerPoolInfo,
-    pub user_bpt_balance: f64, // User's Balancer Pool Token balance
-    pub user_pool_share: f64, // Percentage of pool owned
-    pub user_token_amounts: Vec<UserTokenAmount>, // User's share of each token
-    pub total_value_usd: f64,
-    pub unrealized_pnl_usd: f64,
-    pub swap_fees_earned_usd: f64,
-    pub is_staked: bool,
-    pub staked_amount: f64,
-    pub rewards_earned: Vec<RewardToken>,
-    pub apr: f64,
-    pub apy: f64,
+/// A pool tracked by the adapter, with its liquidity gauge if it has one
+#[derive(Debug, Clone)]
+struct TrackedPool {
+    pool: Address,
+    gauge: Option<Address>,
+    pool_type: BalancerPoolType,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UserTokenAmount {
-    pub token_address: String,
-    pub token_symbol: String,
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolTokenAmount {
+    pub address: String,
+    pub symbol: String,
     pub amount: f64,
     pub value_usd: f64,
+    pub weight: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RewardToken {
-    pub token_address: String,
-    pub token_symbol: String,
-    pub amount: f64,
-    pub value_usd: f64,
+#[derive(Debug, Clone)]
+struct CachedPositions {
+    positions: Vec<Position>,
+    cached_at: SystemTime,
 }
 
+/// Balancer V2 adapter (weighted and composable stable pools, wallet and gauge-staked BPT)
 pub struct BalancerV2Adapter {
-    ethereum_client: EthereumClient,
+    client: EthereumClient,
     vault_address: Address,
-    known_pools: HashMap<String, String>, // pool_id -> pool_type
-    token_prices: HashMap<String, f64>, // Simple price cache
+    pools: Vec<TrackedPool>,
+    staked_lp: StakedLpScanner,
+    position_cache: Arc<Mutex<HashMap<Address, CachedPositions>>>,
+    token_metadata: Arc<TokenMetadataCache>,
+    http_client: reqwest::Client,
+    coingecko_api_key: Option<String>,
 }
 
 impl BalancerV2Adapter {
-    // Balancer V2 Ethereum mainnet addresses
     const VAULT_ADDRESS: &'static str = "0xBA12222222228d8Ba445958a75a0704d566BF2C8";
-    
-    pub fn new(ethereum_client: EthereumClient) -> Result<Self, AdapterError> {
-        let vault_address = Self::VAULT_ADDRESS
-            .parse::<Address>()
+    const CACHE_DURATION: Duration = Duration::from_secs(300); // 5 minutes
+    const CHAIN_ID: u64 = 1;
+    /// Minted to gauge stakers, claimable through the Balancer minter
+    const BAL_TOKEN: &'static str = "0xba100000625a3754423978a60c9317c58a424e3D";
+
+    /// (pool, gauge, type) for major Ethereum mainnet pools
+    const POOLS: &'static [(&'static str, Option<&'static str>, BalancerPoolType)] = &[
+        // 80BAL-20WETH (veBAL lock token, not gauge staked)
+        ("0x5c6Ee304399DBdB9C8Ef030aB642B10820DB8F56", None, BalancerPoolType::Weighted),
+        // 50WBTC-50WETH
+        ("0xA6F548DF93de924d73be7D25dC02554c6bD66dB5", None, BalancerPoolType::Weighted),
+        // wstETH-WETH
+        (
+            "0x93d199263632a4EF4Bb438F1feB99e57b4b5f0BD",
+            Some("0x5C0F23A5c1be65Fa710d385814a7Fd1Bda480b1C"),
+            BalancerPoolType::ComposableStable,
+        ),
+        // rETH-WETH
+        (
+            "0x1E19CF2D73a72Ef1332C882F20534B6519Be0276",
+            Some("0x79eF6103A513951a3b25743DB509E267685726B7"),
+            BalancerPoolType::ComposableStable,
+        ),
+    ];
+
+    pub fn new(client: EthereumClient, coingecko_api_key: Option<String>) -> Result<Self, AdapterError> {
+        let vault_address = Address::from_str(Self::VAULT_ADDRESS)
             .map_err(|e| AdapterError::InvalidData(format!("Invalid vault address: {}", e)))?;
-        
-        let mut known_pools = HashMap::new();
-        
-        // Add some major Balancer V2 pools (pool_id -> pool_type)
-        known_pools.insert(
-            "0x5c6ee304399dbdb9c8ef030ab642b10820db8f56000200000000000000000014".to_string(),
-            "weighted".to_string()
-        ); // 80/20 BAL/WETH
-        
-        known_pools.insert(
-            "0x06df3b2bbb68adc8b0e302443692037ed9f91b42000000000000000000000063".to_string(),
-            "stable".to_string()
-        ); // Stable pool example
-        
-        // Initialize token prices (in production, this would come from an oracle)
-        let mut token_prices = HashMap::new();
-        token_prices.insert("WETH".to_string(), 3000.0);
-        token_prices.insert("BAL".to_string(), 5.0);
-        token_prices.insert("USDC".to_string(), 1.0);
-        token_prices.insert("USDT".to_string(), 1.0);
-        token_prices.insert("DAI".to_string(), 1.0);
-        token_prices.insert("WBTC".to_string(), 60000.0);
-        
+
+        let mut pools = Vec::new();
+        for (pool, gauge, pool_type) in Self::POOLS {
+            pools.push(TrackedPool {
+                pool: Address::from_str(pool)
+                    .map_err(|e| AdapterError::InvalidData(format!("Invalid pool address {}: {}", pool, e)))?,
+                gauge: gauge
+                    .map(Address::from_str)
+                    .transpose()
+                    .map_err(|e| AdapterError::InvalidData(format!("Invalid gauge address: {}", e)))?,
+                pool_type: *pool_type,
+            });
+        }
+
         Ok(Self {
-            ethereum_client,
+            staked_lp: StakedLpScanner::new(client.clone()),
+            client,
             vault_address,
-            known_pools,
-            token_prices,
+            pools,
+            position_cache: Arc::new(Mutex::new(HashMap::new())),
+            token_metadata: TokenMetadataCache::global(),
+            http_client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            coingecko_api_key,
         })
     }
-    
-    /// Get major Balancer V2 pools to check
-    async fn get_major_pools(&self) -> Result<Vec<(String, Address)>, AdapterError> {
-        // Major Balancer V2 pools on Ethereum
-        let pools = vec![
-            // 80/20 BAL/WETH Weighted Pool
-            (
-                "0x5c6ee304399dbdb9c8ef030ab642b10820db8f56000200000000000000000014".to_string(),
-                "0x5c6Ee304399DBdB9C8Ef030aB642B10820DB8F56".parse().unwrap()
-            ),
-            // 50/50 WETH/USDC Weighted Pool  
-            (
-                "0x96646936b91d6b9d7d0c47c496afbf3d6ec7b6f8000200000000000000000019".to_string(),
-                "0x96646936b91d6B9D7D0c47C496AfBF3D6ec7B6f8".parse().unwrap()
-            ),
-            // Stable Pool DAI/USDC/USDT
-            (
-                "0x06df3b2bbb68adc8b0e302443692037ed9f91b42000000000000000000000063".to_string(),
-                "0x06Df3b2bbB68adc8B0e302443692037ED9f91b42".parse().unwrap()
-            ),
-        ];
-        
-        Ok(pools)
-    }
-    
-    /// Get pool information (mock implementation)
-    async fn get_pool_info(&self, pool_id: &str, pool_address: Address) -> Result<BalancerPoolInfo, AdapterError> {
-        tracing::debug!("Getting pool info for pool_id: {} at address: {}", pool_id, pool_address);
-        
-        // Mock pool data based on known pools
-        let pool_info = match pool_id {
-            "0x5c6ee304399dbdb9c8ef030ab642b10820db8f56000200000000000000000014" => {
-                // 80/20 BAL/WETH Pool
-                BalancerPoolInfo {
-                    pool_id: pool_id.to_string(),
-                    pool_address: pool_address.to_string(),
-                    pool_type: "weighted".to_string(),
-                    tokens: vec![
-                        PoolToken {
-                            address: "0xba100000625a3754423978a60c9317c58a424e3D".to_string(), // BAL
-                            symbol: "BAL".to_string(),
-                            balance: 1_000_000.0,
-                            weight: 0.8,
-                        },
-                        PoolToken {
-                            address: "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string(), // WETH
-                            symbol: "WETH".to_string(),
-                            balance: 166.67, // $500k at $3k/ETH
-                            weight: 0.2,
-                        },
-                    ],
-                    total_supply: 100_000.0, // BPT total supply
-                    swap_fee_percentage: 0.01, // 1%
-                    weights: vec![0.8, 0.2],
-                }
-            },
-            "0x96646936b91d6b9d7d0c47c496afbf3d6ec7b6f8000200000000000000000019" => {
-                // 50/50 WETH/USDC Pool
-                BalancerPoolInfo {
-                    pool_id: pool_id.to_string(),
-                    pool_address: pool_address.to_string(),
-                    pool_type: "weighted".to_string(),
-                    tokens: vec![
-                        PoolToken {
-                            address: "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string(), // WETH
-                            symbol: "WETH".to_string(),
-                            balance: 1000.0, // $3M at $3k/ETH
-                            weight: 0.5,
-                        },
-                        PoolToken {
-                            address: "0xA0b86a33E6417c4c3B30fB632d5Ae2AD2c4d4fE5".to_string(), // USDC
-                            symbol: "USDC".to_string(),
-                            balance: 3_000_000.0, // $3M USDC
-                            weight: 0.5,
-                        },
-                    ],
-                    total_supply: 50_000.0, // BPT total supply
-                    swap_fee_percentage: 0.003, // 0.3%
-                    weights: vec![0.5, 0.5],
-                }
-            },
-            "0x06df3b2bbb68adc8b0e302443692037ed9f91b42000000000000000000000063" => {
-                // Stable Pool DAI/USDC/USDT
-                BalancerPoolInfo {
-                    pool_id: pool_id.to_string(),
-                    pool_address: pool_address.to_string(),
-                    pool_type: "stable".to_string(),
-                    tokens: vec![
-                        PoolToken {
-                            address: "0x6B175474E89094C44Da98b954EedeAC495271d0F".to_string(), // DAI
-                            symbol: "DAI".to_string(),
-                            balance: 10_000_000.0,
-                            weight: 0.333,
-                        },
-                        PoolToken {
-                            address: "0xA0b86a33E6417c4c3B30fB632d5Ae2AD2c4d4fE5".to_string(), // USDC
-                            symbol: "USDC".to_string(),
-                            balance: 10_000_000.0,
-                            weight: 0.333,
-                        },
-                        PoolToken {
-                            address: "0xdAC17F958D2ee523a2206206994597C13D831ec7".to_string(), // USDT
-                            symbol: "USDT".to_string(),
-                            balance: 10_000_000.0,
-                            weight: 0.334,
-                        },
-                    ],
-                    total_supply: 200_000.0, // BPT total supply
-                    swap_fee_percentage: 0.001, // 0.1%
-                    weights: vec![0.333, 0.333, 0.334],
-                }
-            },
-            _ => {
-                return Err(AdapterError::InvalidData(format!("Unknown pool: {}", pool_id)));
+
+    /// Wallet BPT balance and the gauge stake (1:1 BPT receipts plus pending rewards) for a pool
+    async fn get_bpt_balances(&self, pool: &TrackedPool, user: Address) -> Result<(U256, Option<StakedLp>), AdapterError> {
+        let wallet = self.client
+            .call(pool.pool, &IERC20::balanceOfCall { account: user })
+            .await?
+            ._0;
+
+        let stake = match pool.gauge {
+            Some(gauge) => {
+                let contract = StakingContract {
+                    address: gauge,
+                    lp_token: pool.pool,
+                    kind: StakingKind::LiquidityGauge { emissions_token: Address::from_str(Self::BAL_TOKEN).ok() },
+                };
+                self.staked_lp.scan(user, std::slice::from_ref(&contract)).await.pop()
             }
+            None => None,
         };
-        
-        Ok(pool_info)
+
+        Ok((wallet, stake))
     }
-    
-    /// Get user's BPT balance for a specific pool (mock implementation)
-    async fn get_user_bpt_balance(&self, pool_address: Address, user: Address) -> Result<f64, AdapterError> {
-        tracing::debug!("Getting BPT balance for user: {} in pool: {}", user, pool_address);
-        
-        // Mock data for vitalik.eth
-        let user_str = user.to_string().to_lowercase();
-        if user_str.contains("d8da6bf26964af9d7eed9e03e53415d37aa96045") {
-            // Return mock BPT balances for different pools
-            let pool_str = pool_address.to_string().to_lowercase();
-            match pool_str.as_str() {
-                "0x5c6ee304399dbdb9c8ef030ab642b10820db8f56" => Ok(100.0), // 100 BPT in BAL/WETH pool
-                "0x96646936b91d6b9d7d0c47c496afbf3d6ec7b6f8" => Ok(50.0),  // 50 BPT in WETH/USDC pool
-                "0x06df3b2bbb68adc8b0e302443692037ed9f91b42" => Ok(200.0), // 200 BPT in stable pool
-                _ => Ok(0.0),
-            }
-        } else {
-            Ok(0.0) // No positions for other addresses
+
+    async fn build_position(
+        &self,
+        user: Address,
+        pool: &TrackedPool,
+        wallet_bpt: U256,
+        stake: Option<&StakedLp>,
+    ) -> Result<Position, AdapterError> {
+        let staked_bpt = stake.map_or(U256::ZERO, |stake| stake.staked);
+        let pool_id = self.client.call(pool.pool, &IBalancerPool::getPoolIdCall {}).await?._0;
+        let pool_tokens = self.client
+            .call(self.vault_address, &IBalancerVault::getPoolTokensCall { poolId: pool_id })
+            .await?;
+
+        // Composable stable pools hold their own BPT as a pool token
+        let (tokens, balances): (Vec<Address>, Vec<U256>) = pool_tokens.tokens
+            .into_iter()
+            .zip(pool_tokens.balances)
+            .filter(|(token, _)| *token != pool.pool)
+            .unzip();
+
+        let supply = match pool.pool_type {
+            BalancerPoolType::Weighted => self.client.call(pool.pool, &IBalancerPool::totalSupplyCall {}).await?._0,
+            BalancerPoolType::ComposableStable => self.client
+                .call(pool.pool, &IComposableStablePool::getActualSupplyCall {})
+                .await?
+                ._0,
+        };
+
+        let user_bpt = Self::to_f64(wallet_bpt + staked_bpt, 18);
+        let pool_share = Self::pool_share(user_bpt, Self::to_f64(supply, 18));
+        let rewards = stake.map(|stake| stake.rewards.as_slice()).unwrap_or_default();
+        let mut priced_tokens = tokens.clone();
+        priced_tokens.extend(rewards.iter().map(|reward| reward.token));
+        let prices = self.get_token_prices(&priced_tokens).await;
+
+        let mut amounts = Vec::new();
+        let mut pool_tvl_usd = 0.0;
+        for (token, balance) in tokens.iter().zip(&balances) {
+            let (symbol, decimals) = match self.token_metadata.get_or_fetch(&self.client, Self::CHAIN_ID, *token).await {
+                Ok(metadata) => (metadata.symbol, metadata.decimals),
+                Err(_) => (format!("TOKEN_{}", &token.to_string()[2..6].to_uppercase()), 18),
+            };
+            let price = prices.get(token).copied().unwrap_or(0.0);
+            let pool_amount = Self::to_f64(*balance, decimals as i32);
+            pool_tvl_usd += pool_amount * price;
+
+            amounts.push(PoolTokenAmount {
+                address: format!("{:?}", token),
+                symbol,
+                amount: pool_amount * pool_share,
+                value_usd: pool_amount * pool_share * price,
+                weight: 0.0,
+            });
         }
-    }
-    
-    /// Get user's staked BPT balance in gauge (mock implementation)
-    async fn get_staked_balance(&self, _gauge_address: Address, user: Address) -> Result<(f64, Vec<RewardToken>), AdapterError> {
-        // Mock staking data for vitalik.eth
-        let user_str = user.to_string().to_lowercase();
-        if user_str.contains("d8da6bf26964af9d7eed9e03e53415d37aa96045") {
-            let rewards = vec![
-                RewardToken {
-                    token_address: "0xba100000625a3754423978a60c9317c58a424e3D".to_string(), // BAL
-                    token_symbol: "BAL".to_string(),
-                    amount: 25.0, // 25 BAL earned
-                    value_usd: 125.0, // 25 * $5
-                },
-            ];
-            Ok((75.0, rewards)) // 75 BPT staked + rewards
-        } else {
-            Ok((0.0, Vec::new()))
+
+        let weights = match pool.pool_type {
+            BalancerPoolType::Weighted => self.client
+                .call(pool.pool, &IBalancerWeightedPool::getNormalizedWeightsCall {})
+                .await?
+                ._0
+                .into_iter()
+                .map(|w| Self::to_f64(w, 18))
+                .collect(),
+            BalancerPoolType::ComposableStable => Self::value_weights(&amounts),
+        };
+        for (amount, weight) in amounts.iter_mut().zip(&weights) {
+            amount.weight = *weight;
+        }
+
+        let value_usd: f64 = amounts.iter().map(|a| a.value_usd).sum();
+        let pair = amounts.iter().map(|a| a.symbol.as_str()).collect::<Vec<_>>().join("/");
+
+        let mut pending_rewards = Vec::new();
+        for reward in rewards {
+            let (symbol, decimals) = match self.token_metadata.get_or_fetch(&self.client, Self::CHAIN_ID, reward.token).await {
+                Ok(metadata) => (metadata.symbol, metadata.decimals),
+                Err(_) => (format!("TOKEN_{}", &reward.token.to_string()[2..6].to_uppercase()), 18),
+            };
+            let amount = Self::to_f64(reward.amount, decimals as i32);
+            pending_rewards.push(serde_json::json!({
+                "token": format!("{:?}", reward.token),
+                "symbol": symbol,
+                "amount": amount,
+                "value_usd": amount * prices.get(&reward.token).copied().unwrap_or(0.0),
+            }));
         }
+        let pending_rewards_usd: f64 = pending_rewards.iter().filter_map(|r| r["value_usd"].as_f64()).sum();
+        // Swap fees compound into the BPT price, so only gauge rewards are separable
+        let pnl = PnlBreakdown { rewards_earned: pending_rewards_usd, ..Default::default() };
+
+        Ok(Position {
+            id: position_id("balancer_v2", Self::CHAIN_ID, "liquidity", pool.pool, user),
+            protocol: "balancer_v2".to_string(),
+            position_type: "liquidity".to_string(),
+            pair,
+            value_usd,
+            pnl_usd: pnl.total(),
+            pnl_percentage: if value_usd > 0.0 { pnl.total() / value_usd * 100.0 } else { 0.0 },
+            metadata: serde_json::json!({
+                "pool_id": format!("{:?}", pool_id),
+                "pool_address": format!("{:?}", pool.pool),
+                "pool_type": pool.pool_type.as_str(),
+                "tokens": amounts,
+                "weights": weights,
+                "bpt_balance": wallet_bpt.to_string(),
+                "staked_bpt_balance": staked_bpt.to_string(),
+                "gauge_address": pool.gauge.map(|g| format!("{:?}", g)),
+                "pending_rewards": pending_rewards,
+                "pending_rewards_usd": pending_rewards_usd,
+                "pnl_breakdown": pnl,
+                "pool_share": pool_share,
+                "pool_tvl_usd": pool_tvl_usd,
+                "risk_score": Self::lp_risk_score(pool.pool_type),
+            }),
+            last_updated: SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        })
     }
-    
-    /// Calculate user's token amounts based on BPT share
-    fn calculate_user_token_amounts(&self, pool_info: &BalancerPoolInfo, user_bpt: f64) -> Vec<UserTokenAmount> {
-        if pool_info.total_supply == 0.0 || user_bpt == 0.0 {
-            return Vec::new();
+
+    /// Weighted pools carry impermanent loss; stable pools hold assets that track each other
+    fn lp_risk_score(pool_type: BalancerPoolType) -> f64 {
+        let inputs = RiskInputs {
+            correlated: pool_type == BalancerPoolType::ComposableStable,
+            ..Default::default()
+        };
+        RiskModelRegistry::global().score("liquidity", &inputs)
+    }
+
+    /// USD prices by token address from CoinGecko; tokens without a price are omitted
+    async fn get_token_prices(&self, tokens: &[Address]) -> HashMap<Address, f64> {
+        let base_url = if self.coingecko_api_key.is_some() {
+            "https://pro-api.coingecko.com/api/v3"
+        } else {
+            "https://api.coingecko.com/api/v3"
+        };
+        let addresses = tokens.iter().map(|t| format!("{:?}", t)).collect::<Vec<_>>().join(",");
+        let url = format!(
+            "{}/simple/token_price/ethereum?contract_addresses={}&vs_currencies=usd",
+            base_url, addresses
+        );
+
+        let mut request = self.http_client.get(&url);
+        if let Some(api_key) = &self.coingecko_api_key {
+            request = request.header("X-Cg-Pro-Api-Key", api_key);
         }
-        
-        let user_share = user_bpt / pool_info.total_supply;
-        
-        pool_info.tokens.iter().map(|token| {
-            let user_amount = token.balance * user_share;
-            let price = self.token_prices.get(&token.symbol).copied().unwrap_or(1.0);
-            
-            UserTokenAmount {
-                token_address: token.address.clone(),
-                token_symbol: token.symbol.clone(),
-                amount: user_amount,
-                value_usd: user_amount * price,
+
+        let json: serde_json::Value = match request.send().await {
+            Ok(response) => response.json().await.unwrap_or_default(),
+            Err(e) => {
+                tracing::warn!("Failed to fetch Balancer token prices: {}", e);
+                return HashMap::new();
             }
-        }).collect()
+        };
+
+        json.as_object()
+            .map(|prices| {
+                prices
+                    .iter()
+                    .filter_map(|(address, data)| {
+                        Some((Address::from_str(address).ok()?, data.get("usd")?.as_f64()?))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
     }
-    
-    /// Calculate unrealized PnL (simplified estimation)
-    fn calculate_unrealized_pnl(&self, user_tokens: &[UserTokenAmount], pool_type: &str) -> f64 {
-        // Simulate some PnL based on pool type and time held
-        let total_value: f64 = user_tokens.iter().map(|t| t.value_usd).sum();
-        
-        // Mock PnL calculation based on pool performance
-        match pool_type {
-            "weighted" => {
-                // Weighted pools tend to have more volatility
-                total_value * 0.05 // 5% gain
-            },
-            "stable" => {
-                // Stable pools have minimal IL but consistent fees
-                total_value * 0.02 // 2% gain
-            },
-            _ => total_value * 0.03 // Default 3% gain
+
+    fn pool_share(user_bpt: f64, total_supply: f64) -> f64 {
+        if total_supply > 0.0 {
+            (user_bpt / total_supply).min(1.0)
+        } else {
+            0.0
         }
     }
-    
-    /// Calculate swap fees earned (estimation)
-    fn calculate_swap_fees_earned(&self, pool_info: &BalancerPoolInfo, user_share: f64) -> f64 {
-        // Estimate fees earned based on pool volume and user share
-        // This would require historical data in a real implementation
-        let daily_volume_estimate = match pool_info.pool_type.as_str() {
-            "weighted" => 500_000.0, // $500k daily volume
-            "stable" => 1_000_000.0, // $1M daily volume
-            _ => 250_000.0,
-        };
-        
-        let daily_fees = daily_volume_estimate * pool_info.swap_fee_percentage;
-        let user_daily_fees = daily_fees * user_share;
-        
-        // Assume position held for 30 days
-        user_daily_fees * 30.0
+
+    /// Stable pools have no configured weights; use each token's share of value
+    fn value_weights(amounts: &[PoolTokenAmount]) -> Vec<f64> {
+        let total: f64 = amounts.iter().map(|a| a.value_usd).sum();
+        amounts
+            .iter()
+            .map(|a| if total > 0.0 { a.value_usd / total } else { 1.0 / amounts.len() as f64 })
+            .collect()
     }
-    
-    /// Calculate APR/APY for the pool
-    fn calculate_apr_apy(&self, pool_info: &BalancerPoolInfo, swap_fees_annual: f64, rewards_annual: f64) -> (f64, f64) {
-        let pool_tvl = pool_info.tokens.iter().map(|token| {
-            let price = self.token_prices.get(&token.symbol).copied().unwrap_or(1.0);
-            token.balance * price
-        }).sum::<f64>();
-        
-        if pool_tvl == 0.0 {
-            return (0.0, 0.0);
-        }
-        
-        let total_annual_yield = swap_fees_annual + rewards_annual;
-        let apr = (total_annual_yield / pool_tvl) * 100.0;
-        
-        // APY = (1 + APR/365)^365 - 1, simplified for daily compounding
-        let apy = (1.0 + apr / 100.0 / 365.0).powf(365.0) - 1.0;
-        
-        (apr, apy * 100.0)
+
+    fn to_f64(value: U256, decimals: i32) -> f64 {
+        let raw: f64 = value.to_string().parse().unwrap_or(0.0);
+        raw / 10f64.powi(decimals)
     }
 }
 
@@ -389,110 +366,92 @@ impl DeFiAdapter for BalancerV2Adapter {
     fn protocol_name(&self) -> &'static str {
         "balancer_v2"
     }
-    
+
+    fn position_types(&self) -> &'static [&'static str] {
+        &["liquidity"]
+    }
+
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+
     async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
-        tracing::info!(
-            user_address = %address,
-            "Starting Balancer V2 position fetch"
-        );
-        
-        let pools = self.get_major_pools().await?;
-        let mut positions = Vec::new();
-        
-        for (pool_id, pool_address) in pools {
-            // Get pool information
-            let pool_info = match self.get_pool_info(&pool_id, pool_address).await {
-                Ok(info) => info,
-                Err(e) => {
-                    tracing::warn!("Failed to get pool info for {}: {}", pool_id, e);
-                    continue;
+        // Check cache first
+        {
+            let cache = self.position_cache.lock().unwrap();
+            if let Some(cached) = cache.get(&address) {
+                let cache_age = cached.cached_at.elapsed().unwrap_or(Duration::from_secs(0));
+                if cache_age < Self::CACHE_DURATION {
+                    return Ok(cached.positions.clone());
                 }
-            };
-            
-            // Get user's BPT balance
-            let user_bpt = self.get_user_bpt_balance(pool_address, address).await?;
-            
-            if user_bpt == 0.0 {
-                continue; // No position in this pool
             }
-            
-            let user_pool_share = if pool_info.total_supply > 0.0 {
-                user_bpt / pool_info.total_supply
-            } else {
-                0.0
-            };
-            
-            // Calculate user's token amounts
-            let user_token_amounts = self.calculate_user_token_amounts(&pool_info, user_bpt);
-            let total_value_usd: f64 = user_token_amounts.iter().map(|t| t.value_usd).sum();
-            
-            if total_value_usd == 0.0 {
-                continue; // Skip zero-value positions
+        }
+
+        let mut positions = Vec::new();
+
+        for pool in &self.pools {
+            let (wallet_bpt, stake) = self.get_bpt_balances(pool, address).await?;
+            if wallet_bpt == U256::ZERO && stake.is_none() {
+                continue;
+            }
+
+            match self.build_position(address, pool, wallet_bpt, stake.as_ref()).await {
+                Ok(position) => positions.push(position),
+                Err(e) => tracing::warn!("Failed to value Balancer pool {:?}: {}", pool.pool, e),
             }
-            
-            // Calculate PnL and fees
-            let unrealized_pnl = self.calculate_unrealized_pnl(&user_token_amounts, &pool_info.pool_type);
-            let swap_fees_earned = self.calculate_swap_fees_earned(&pool_info, user_pool_share);
-            
-            // Check for staked positions
-            let (staked_amount, rewards) = self.get_staked_balance(pool_address, address).await?;
-            let is_staked = staked_amount > 0.0;
-            
-            // Calculate APR/APY
-            let rewards_annual_value: f64 = rewards.iter().map(|r| r.value_usd * 12.0).sum(); // Monthly to annual
-            let swap_fees_annual = swap_fees_earned * 12.0; // Monthly to annual
-            let (apr, apy) = self.calculate_apr_apy(&pool_info, swap_fees_annual, rewards_annual_value);
-            
-            let balancer_position = BalancerPosition {
-                pool_info: pool_info.clone(),
-                user_bpt_balance: user_bpt,
-                user_pool_share: user_pool_share * 100.0, // Convert to percentage
-                user_token_amounts: user_token_amounts.clone(),
-                total_value_usd,
-                unrealized_pnl_usd: unrealized_pnl,
-                swap_fees_earned_usd: swap_fees_earned,
-                is_staked,
-                staked_amount,
-                rewards_earned: rewards.clone(),
-                apr,
-                apy,
-            };
-            
-            // Create LP position
-            let total_pnl = unrealized_pnl + swap_fees_earned + rewards.iter().map(|r| r.value_usd).sum::<f64>();
-            let pnl_percentage = if total_value_usd > 0.0 { (total_pnl / total_value_usd) * 100.0 } else { 0.0 };
-            
-            let position = Position {
-                id: format!("balancer_v2_lp_{}_{}", address, pool_id),
-                protocol: "balancer_v2".to_string(),
-                position_type: if is_staked { "staked_lp" } else { "lp" }.to_string(),
-                pair: format!("{}", pool_info.tokens.iter().map(|t| t.symbol.as_str()).collect::<Vec<_>>().join("/")),
-                value_usd: total_value_usd,
-                pnl_usd: total_pnl,
-                pnl_percentage,
-                metadata: serde_json::to_value(&balancer_position).unwrap_or_default(),
-                last_updated: chrono::Utc::now().timestamp() as u64,
-            };
-            
-            positions.push(position);
         }
-        
-        tracing::info!(
-            user_address = %address,
-            positions_count = positions.len(),
-            "Completed Balancer V2 position fetch"
-        );
-        
+
+        // Update cache
+        {
+            let mut cache = self.position_cache.lock().unwrap();
+            cache.insert(address, CachedPositions {
+                positions: positions.clone(),
+                cached_at: SystemTime::now(),
+            });
+        }
+
         Ok(positions)
     }
-    
-    async fn supports_contract(&self, _contract_address: Address) -> bool {
-        // Return true for all addresses - we'll check during fetch
-        true
+
+    async fn supports_contract(&self, contract_address: Address) -> bool {
+        contract_address == self.vault_address
+            || self.pools.iter().any(|p| p.pool == contract_address || p.gauge == Some(contract_address))
     }
-    
+
     async fn get_position_value(&self, position: &Position) -> Result<f64, AdapterError> {
-        // For Balancer, the value is already calculated in USD terms
-        Ok(position.value_usd.abs())
+        Ok(position.value_usd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracked_pools_parse() {
+        let client = EthereumClient::new("https://eth.llamarpc.com").unwrap();
+        let adapter = BalancerV2Adapter::new(client, None).unwrap();
+        assert_eq!(adapter.pools.len(), BalancerV2Adapter::POOLS.len());
+    }
+
+    #[test]
+    fn test_stable_pool_weights_follow_value() {
+        let amount = |value_usd| PoolTokenAmount {
+            address: String::new(),
+            symbol: String::new(),
+            amount: 0.0,
+            value_usd,
+            weight: 0.0,
+        };
+
+        let weights = BalancerV2Adapter::value_weights(&[amount(300.0), amount(100.0)]);
+        assert_eq!(weights, vec![0.75, 0.25]);
+        assert_eq!(BalancerV2Adapter::pool_share(5.0, 100.0), 0.05);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_stable_pools_score_below_weighted_pools() {
+        assert_eq!(BalancerV2Adapter::lp_risk_score(BalancerPoolType::Weighted), 0.55);
+        assert!((BalancerV2Adapter::lp_risk_score(BalancerPoolType::ComposableStable) - 0.3).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,117 @@
+use serde::Serialize;
+
+use crate::adapters::filter::protocol_key;
+use crate::adapters::traits::DeFiAdapter;
+
+/// What the backend can report for one protocol
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProtocolInfo {
+    /// Key accepted by `?protocols=` / `?exclude=`
+    pub protocol: String,
+    pub name: &'static str,
+    /// One entry per chain an adapter instance was initialized for, ascending
+    pub chain_ids: Vec<u64>,
+    pub position_types: Vec<&'static str>,
+    /// Whether every instance can read positions as of a past block
+    pub supports_historical_block: bool,
+}
+
+/// Protocols served by the initialized adapters, merging instances of the same
+/// protocol on different chains, sorted by key
+pub fn protocol_catalog(adapters: &[Box<dyn DeFiAdapter>]) -> Vec<ProtocolInfo> {
+    let mut catalog: Vec<ProtocolInfo> = Vec::new();
+
+    for adapter in adapters {
+        let protocol = protocol_key(adapter.protocol_name());
+        let index = match catalog.iter().position(|info| info.protocol == protocol) {
+            Some(index) => index,
+            None => {
+                catalog.push(ProtocolInfo {
+                    protocol,
+                    name: adapter.protocol_name(),
+                    chain_ids: Vec::new(),
+                    position_types: Vec::new(),
+                    supports_historical_block: true,
+                });
+                catalog.len() - 1
+            }
+        };
+
+        let entry = &mut catalog[index];
+        if !entry.chain_ids.contains(&adapter.chain_id()) {
+            entry.chain_ids.push(adapter.chain_id());
+        }
+        for position_type in adapter.position_types() {
+            if !entry.position_types.contains(position_type) {
+                entry.position_types.push(position_type);
+            }
+        }
+        entry.supports_historical_block &= adapter.supports_historical_block();
+    }
+
+    for entry in &mut catalog {
+        entry.chain_ids.sort_unstable();
+    }
+    catalog.sort_by(|a, b| a.protocol.cmp(&b.protocol));
+    catalog
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::traits::{AdapterError, Position};
+    use alloy::primitives::Address;
+    use async_trait::async_trait;
+
+    struct StubAdapter {
+        name: &'static str,
+        chain_id: u64,
+        position_types: &'static [&'static str],
+    }
+
+    #[async_trait]
+    impl DeFiAdapter for StubAdapter {
+        fn protocol_name(&self) -> &'static str {
+            self.name
+        }
+
+        fn chain_id(&self) -> u64 {
+            self.chain_id
+        }
+
+        fn position_types(&self) -> &'static [&'static str] {
+            self.position_types
+        }
+
+        async fn fetch_positions(&self, _address: Address) -> Result<Vec<Position>, AdapterError> {
+            Ok(Vec::new())
+        }
+
+        async fn supports_contract(&self, _contract_address: Address) -> bool {
+            false
+        }
+
+        async fn get_position_value(&self, position: &Position) -> Result<f64, AdapterError> {
+            Ok(position.value_usd)
+        }
+    }
+
+    #[test]
+    fn test_instances_on_several_chains_merge_into_one_entry() {
+        let adapters: Vec<Box<dyn DeFiAdapter>> = vec![
+            Box::new(StubAdapter { name: "morpho_blue", chain_id: 8453, position_types: &["supply", "borrow"] }),
+            Box::new(StubAdapter { name: "Yearn Finance", chain_id: 1, position_types: &["vault"] }),
+            Box::new(StubAdapter { name: "morpho_blue", chain_id: 1, position_types: &["supply", "collateral"] }),
+        ];
+
+        let catalog = protocol_catalog(&adapters);
+
+        assert_eq!(catalog.len(), 2);
+        assert_eq!(catalog[0].protocol, "morpho_blue");
+        assert_eq!(catalog[0].chain_ids, vec![1, 8453]);
+        assert_eq!(catalog[0].position_types, vec!["supply", "borrow", "collateral"]);
+        assert!(!catalog[0].supports_historical_block);
+        assert_eq!(catalog[1].protocol, "yearn_finance");
+        assert_eq!(catalog[1].name, "Yearn Finance");
+    }
+}
@@ -0,0 +1,562 @@
+use alloy::{
+    primitives::{Address, U256},
+    sol,
+};
+use async_trait::async_trait;
+use futures::future::join_all;
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
+use crate::blockchain::EthereumClient;
+use crate::risk::{RiskInputs, RiskModelRegistry};
+use crate::services::{subgraph_id_from_env, HealthFactorThresholds, TokenMetadataCache, IERC20};
+use crate::utils::{market_read_timeout, with_timeout};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+// Compound V2 Comptroller, cToken and price oracle interfaces
+sol! {
+    interface IComptroller {
+        function getAllMarkets() external view returns (address[] memory);
+        function getAssetsIn(address account) external view returns (address[] memory);
+        function getAccountLiquidity(address account) external view returns (uint256 error, uint256 liquidity, uint256 shortfall);
+        function markets(address cToken) external view returns (bool isListed, uint256 collateralFactorMantissa, bool isComped);
+        function oracle() external view returns (address);
+    }
+
+    interface ICToken {
+        function exchangeRateStored() external view returns (uint256);
+        function borrowBalanceStored(address account) external view returns (uint256);
+        function underlying() external view returns (address);
+        function supplyRatePerBlock() external view returns (uint256);
+        function borrowRatePerBlock() external view returns (uint256);
+    }
+
+    interface ICompoundOracle {
+        function getUnderlyingPrice(address cToken) external view returns (uint256);
+    }
+}
+
+/// A user's balances in one cToken market, in underlying token units
+#[derive(Debug, Clone)]
+pub struct CompoundV2MarketBalance {
+    pub ctoken: Address,
+    pub underlying: Address,
+    pub symbol: String,
+    pub supplied: f64,
+    pub borrowed: f64,
+    /// `None` when the oracle has no price for the market
+    pub price_usd: Option<f64>,
+    /// Share of the supply's value that counts as borrowing power, e.g. 0.825
+    pub collateral_factor: f64,
+    /// Whether the user entered the market, making the supply collateral
+    pub entered: bool,
+    pub supply_apy: f64,
+    pub borrow_apy: f64,
+}
+
+impl CompoundV2MarketBalance {
+    pub fn supply_value_usd(&self) -> f64 {
+        self.supplied * self.price_usd.unwrap_or(0.0)
+    }
+
+    pub fn borrow_value_usd(&self) -> f64 {
+        self.borrowed * self.price_usd.unwrap_or(0.0)
+    }
+}
+
+/// Account-level liquidity from the Comptroller plus every market the user holds
+#[derive(Debug, Clone)]
+pub struct CompoundV2Account {
+    /// Borrowing power left, in USD
+    pub liquidity_usd: f64,
+    /// Borrowing power exceeded, in USD; the account is liquidatable when positive
+    pub shortfall_usd: f64,
+    /// Some markets failed or timed out and were skipped, so `markets` may be incomplete
+    pub partial: bool,
+    pub markets: Vec<CompoundV2MarketBalance>,
+}
+
+impl CompoundV2Account {
+    pub fn total_collateral_usd(&self) -> f64 {
+        self.markets.iter().filter(|m| m.entered).map(|m| m.supply_value_usd()).sum()
+    }
+
+    pub fn total_debt_usd(&self) -> f64 {
+        self.markets.iter().map(|m| m.borrow_value_usd()).sum()
+    }
+
+    /// Collateral weighted by collateral factor over debt; infinite without debt
+    pub fn health_factor(&self) -> f64 {
+        let debt = self.total_debt_usd();
+        if debt <= 0.0 {
+            return f64::INFINITY;
+        }
+        let borrowing_power: f64 = self.markets
+            .iter()
+            .filter(|m| m.entered)
+            .map(|m| m.supply_value_usd() * m.collateral_factor)
+            .sum();
+        borrowing_power / debt
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedPositions {
+    positions: Vec<Position>,
+    cached_at: SystemTime,
+}
+
+/// Compound V2 adapter: legacy cToken supply and borrow positions (cDAI, cETH, ...)
+pub struct CompoundV2Adapter {
+    client: EthereumClient,
+    comptroller: Address,
+    ceth: Address,
+    token_metadata: Arc<TokenMetadataCache>,
+    markets_cache: Mutex<Option<(Vec<Address>, SystemTime)>>,
+    position_cache: Arc<Mutex<HashMap<Address, CachedPositions>>>,
+    health_thresholds: HealthFactorThresholds,
+}
+
+impl CompoundV2Adapter {
+    const CHAIN_ID: u64 = 1;
+    /// Unitroller proxy of the Comptroller
+    const COMPTROLLER_ADDRESS: &'static str = "0x3d9819210A31b4961b30EF54bE2aeD79B9c9Cd3B";
+    /// cETH holds native ETH and has no `underlying()`
+    const CETH_ADDRESS: &'static str = "0x4Ddc2D193948926D02f9B1fE9e1daa0718270ED5";
+    const CACHE_DURATION: Duration = Duration::from_secs(300); // 5 minutes
+    const MARKETS_CACHE_DURATION: Duration = Duration::from_secs(3600); // 1 hour
+
+    /// Mantissas (exchange rates, collateral factors, rates) are scaled by 1e18
+    const MANTISSA: f64 = 1e18;
+    /// Rates are quoted per block; 12 second blocks since the merge
+    const BLOCKS_PER_DAY: f64 = 7_200.0;
+
+    pub fn new(client: EthereumClient) -> Result<Self, AdapterError> {
+        let parse = |address: &str, name: &str| {
+            Address::from_str(address)
+                .map_err(|e| AdapterError::InvalidData(format!("Invalid {} address: {}", name, e)))
+        };
+
+        Ok(Self {
+            client,
+            comptroller: parse(Self::COMPTROLLER_ADDRESS, "Comptroller")?,
+            ceth: parse(Self::CETH_ADDRESS, "cETH")?,
+            token_metadata: TokenMetadataCache::global(),
+            markets_cache: Mutex::new(None),
+            position_cache: Arc::new(Mutex::new(HashMap::new())),
+            health_thresholds: HealthFactorThresholds::from_env(),
+        })
+    }
+
+    /// Listed cToken markets, cached for an hour
+    async fn markets(&self) -> Result<Vec<Address>, AdapterError> {
+        {
+            let cache = self.markets_cache.lock().unwrap();
+            if let Some((markets, cached_at)) = cache.as_ref() {
+                if cached_at.elapsed().unwrap_or(Duration::MAX) < Self::MARKETS_CACHE_DURATION {
+                    return Ok(markets.clone());
+                }
+            }
+        }
+
+        let markets = self.client.call(self.comptroller, &IComptroller::getAllMarketsCall {}).await?._0;
+        *self.markets_cache.lock().unwrap() = Some((markets.clone(), SystemTime::now()));
+        Ok(markets)
+    }
+
+    /// The user's account, or `None` when they hold nothing in any market. A market that
+    /// fails or exceeds `market_read_timeout` is skipped and the account marked partial.
+    pub async fn account(&self, user: Address) -> Result<Option<CompoundV2Account>, AdapterError> {
+        let (markets, assets_in, liquidity, oracle) = tokio::try_join!(
+            self.markets(),
+            self.client.call(self.comptroller, &IComptroller::getAssetsInCall { account: user }),
+            self.client.call(self.comptroller, &IComptroller::getAccountLiquidityCall { account: user }),
+            self.client.call(self.comptroller, &IComptroller::oracleCall {}),
+        )?;
+        let entered = assets_in._0;
+
+        let timeout = market_read_timeout();
+        let balances = join_all(markets.iter().map(|ctoken| {
+            with_timeout(timeout, "Compound V2 market read", self.market_balance(*ctoken, user, oracle._0, entered.contains(ctoken)))
+        })).await;
+
+        let mut held = Vec::new();
+        let mut partial = false;
+        for (ctoken, result) in markets.iter().zip(balances) {
+            match result {
+                Ok(Some(balance)) => held.push(balance),
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!("Skipping Compound V2 market {:?}: {}", ctoken, e);
+                    partial = true;
+                }
+            }
+        }
+
+        if held.is_empty() {
+            // The Comptroller reports borrowing power that the skipped markets must back
+            if partial && (liquidity.liquidity > U256::ZERO || liquidity.shortfall > U256::ZERO) {
+                return Err(AdapterError::RpcError(format!("No Compound V2 market of {:?} could be read", user)));
+            }
+            return Ok(None);
+        }
+
+        Ok(Some(CompoundV2Account {
+            liquidity_usd: Self::to_f64(liquidity.liquidity) / Self::MANTISSA,
+            shortfall_usd: Self::to_f64(liquidity.shortfall) / Self::MANTISSA,
+            partial,
+            markets: held,
+        }))
+    }
+
+    async fn market_balance(
+        &self,
+        ctoken: Address,
+        user: Address,
+        oracle: Address,
+        entered: bool,
+    ) -> Result<Option<CompoundV2MarketBalance>, AdapterError> {
+        let (balance, borrowed) = tokio::try_join!(
+            self.client.call(ctoken, &IERC20::balanceOfCall { account: user }),
+            self.client.call(ctoken, &ICToken::borrowBalanceStoredCall { account: user }),
+        )?;
+        if balance._0 == U256::ZERO && borrowed._0 == U256::ZERO {
+            return Ok(None);
+        }
+
+        let (exchange_rate, config, supply_rate, borrow_rate) = tokio::try_join!(
+            self.client.call(ctoken, &ICToken::exchangeRateStoredCall {}),
+            self.client.call(self.comptroller, &IComptroller::marketsCall { cToken: ctoken }),
+            self.client.call(ctoken, &ICToken::supplyRatePerBlockCall {}),
+            self.client.call(ctoken, &ICToken::borrowRatePerBlockCall {}),
+        )?;
+
+        let (underlying, symbol, decimals) = if ctoken == self.ceth {
+            (Address::ZERO, "ETH".to_string(), 18)
+        } else {
+            let underlying = self.client.call(ctoken, &ICToken::underlyingCall {}).await?._0;
+            let metadata = self.token_metadata.get_or_fetch(&self.client, Self::CHAIN_ID, underlying).await?;
+            (underlying, metadata.symbol, metadata.decimals)
+        };
+
+        // Deprecated markets can lose their oracle feed; keep the balance, flag the price
+        let price_usd = match self.client.call(oracle, &ICompoundOracle::getUnderlyingPriceCall { cToken: ctoken }).await {
+            Ok(price) if price._0 > U256::ZERO => Some(Self::underlying_price_usd(price._0, decimals)),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!("No Compound V2 oracle price for {}: {}", symbol, e);
+                None
+            }
+        };
+
+        let scale = 10f64.powi(decimals as i32);
+        Ok(Some(CompoundV2MarketBalance {
+            ctoken,
+            underlying,
+            symbol,
+            supplied: Self::to_f64(Self::underlying_amount(balance._0, exchange_rate._0)) / scale,
+            borrowed: Self::to_f64(borrowed._0) / scale,
+            price_usd,
+            collateral_factor: Self::to_f64(config.collateralFactorMantissa) / Self::MANTISSA,
+            entered,
+            supply_apy: Self::per_block_rate_to_apy(supply_rate._0),
+            borrow_apy: Self::per_block_rate_to_apy(borrow_rate._0),
+        }))
+    }
+
+    /// Underlying base units for a cToken balance at `exchangeRateStored`
+    fn underlying_amount(ctoken_balance: U256, exchange_rate: U256) -> U256 {
+        ctoken_balance * exchange_rate / U256::from(10u64).pow(U256::from(18u64))
+    }
+
+    /// USD per whole underlying token; the oracle scales prices by 1e(36 - decimals)
+    fn underlying_price_usd(price: U256, decimals: u8) -> f64 {
+        Self::to_f64(price) / 10f64.powi(36 - decimals as i32)
+    }
+
+    /// Daily-compounded APY percentage from a per-block rate mantissa, as Compound's docs do
+    fn per_block_rate_to_apy(rate: U256) -> f64 {
+        let per_block = Self::to_f64(rate) / Self::MANTISSA;
+        ((per_block * Self::BLOCKS_PER_DAY + 1.0).powi(365) - 1.0) * 100.0
+    }
+
+    fn to_f64(value: U256) -> f64 {
+        value.to_string().parse().unwrap_or(0.0)
+    }
+}
+
+/// Supply and borrow positions for a Compound V2 account. Collateral is pooled across
+/// entered markets, so every position carries the account-wide health factor.
+fn account_positions(user: Address, account: &CompoundV2Account, thresholds: &HealthFactorThresholds) -> Vec<Position> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let account_health_factor = account.health_factor();
+    let health_factor = account_health_factor.is_finite().then_some(account_health_factor);
+    let total_debt_usd = account.total_debt_usd();
+    let collateral_symbols = account.markets
+        .iter()
+        .filter(|m| m.entered && m.supplied > 0.0)
+        .map(|m| m.symbol.as_str())
+        .collect::<Vec<_>>()
+        .join("+");
+    let account_health = serde_json::json!({
+        "health_factor": health_factor,
+        "total_collateral_usd": account.total_collateral_usd(),
+        "total_debt_usd": total_debt_usd,
+        "liquidity_usd": account.liquidity_usd,
+        "shortfall_usd": account.shortfall_usd,
+        "partial": account.partial,
+        "at_risk": account.shortfall_usd > 0.0 || account_health_factor < thresholds.warning,
+    });
+    let models = RiskModelRegistry::global();
+    let borrow_risk = models.score("borrow", &RiskInputs { health_factor: Some(account_health_factor), ..Default::default() });
+    // cToken balances carry accrued interest with no record of the principal
+    let pnl = PnlBreakdown::default();
+
+    let mut positions = Vec::new();
+    for market in &account.markets {
+        if market.supplied > 0.0 {
+            let position_type = if market.entered && total_debt_usd > 0.0 { "collateral" } else { "supply" };
+
+            positions.push(Position {
+                // A deposit keeps its ID when it starts or stops backing a borrow
+                id: position_id("compound_v2", CompoundV2Adapter::CHAIN_ID, "supply", market.ctoken, user),
+                protocol: "compound_v2".to_string(),
+                position_type: position_type.to_string(),
+                pair: market.symbol.clone(),
+                value_usd: market.supply_value_usd(),
+                pnl_usd: 0.0,
+                pnl_percentage: 0.0,
+                metadata: serde_json::json!({
+                    "ctoken_address": format!("{:?}", market.ctoken),
+                    "token_address": format!("{:?}", market.underlying),
+                    "underlying_asset": market.symbol,
+                    "price_unknown": market.price_usd.is_none(),
+                    "account_health": account_health,
+                    "pnl_breakdown": pnl,
+                    "risk_score": models.score(position_type, &RiskInputs {
+                        health_factor: (position_type == "collateral").then_some(account_health_factor),
+                        ..Default::default()
+                    }),
+                    "position_details": {
+                        "supplied": market.supplied,
+                        "supply_apy": market.supply_apy,
+                        "collateral_factor": market.collateral_factor,
+                        "used_as_collateral": market.entered,
+                    }
+                }),
+                last_updated: now,
+            });
+        }
+
+        if market.borrowed > 0.0 {
+            positions.push(Position {
+                id: position_id("compound_v2", CompoundV2Adapter::CHAIN_ID, "borrow", market.ctoken, user),
+                protocol: "compound_v2".to_string(),
+                position_type: "borrow".to_string(),
+                pair: format!("{}/{}", market.symbol, collateral_symbols),
+                value_usd: -market.borrow_value_usd(),
+                pnl_usd: 0.0,
+                pnl_percentage: 0.0,
+                metadata: serde_json::json!({
+                    "ctoken_address": format!("{:?}", market.ctoken),
+                    "token_address": format!("{:?}", market.underlying),
+                    "underlying_asset": market.symbol,
+                    "price_unknown": market.price_usd.is_none(),
+                    "account_health": account_health,
+                    "pnl_breakdown": pnl,
+                    "risk_score": borrow_risk,
+                    "position_details": {
+                        "borrowed": market.borrowed,
+                        "borrow_apy": market.borrow_apy,
+                        "collateral_factor": market.collateral_factor,
+                        "health_factor": health_factor,
+                        "is_healthy": account.shortfall_usd <= 0.0,
+                    }
+                }),
+                last_updated: now,
+            });
+        }
+    }
+
+    positions
+}
+
+#[async_trait]
+impl DeFiAdapter for CompoundV2Adapter {
+    fn protocol_name(&self) -> &'static str {
+        "compound_v2"
+    }
+
+    fn position_types(&self) -> &'static [&'static str] {
+        &["supply", "collateral", "borrow"]
+    }
+
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+
+    fn supports_historical_block(&self) -> bool {
+        true
+    }
+
+    fn subgraph_id(&self) -> Option<String> {
+        subgraph_id_from_env("compound_v2", Self::CHAIN_ID)
+    }
+
+    async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
+        // Check cache first
+        {
+            let cache = self.position_cache.lock().unwrap();
+            if let Some(cached) = cache.get(&address) {
+                let cache_age = cached.cached_at.elapsed().unwrap_or(Duration::from_secs(0));
+                if cache_age < Self::CACHE_DURATION {
+                    return Ok(cached.positions.clone());
+                }
+            }
+        }
+
+        let positions = match self.account(address).await? {
+            Some(account) => account_positions(address, &account, &self.health_thresholds),
+            None => Vec::new(),
+        };
+
+        // Update cache
+        {
+            let mut cache = self.position_cache.lock().unwrap();
+            cache.insert(address, CachedPositions {
+                positions: positions.clone(),
+                cached_at: SystemTime::now(),
+            });
+        }
+
+        Ok(positions)
+    }
+
+    async fn supports_contract(&self, contract_address: Address) -> bool {
+        if contract_address == self.comptroller || contract_address == self.ceth {
+            return true;
+        }
+        let cache = self.markets_cache.lock().unwrap();
+        cache.as_ref().map_or(false, |(markets, _)| markets.contains(&contract_address))
+    }
+
+    async fn get_position_value(&self, position: &Position) -> Result<f64, AdapterError> {
+        Ok(position.value_usd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::MockRpc;
+    use alloy::sol_types::SolCall;
+
+    fn market(symbol: &str, supplied: f64, borrowed: f64, collateral_factor: f64, entered: bool) -> CompoundV2MarketBalance {
+        CompoundV2MarketBalance {
+            ctoken: Address::ZERO,
+            underlying: Address::ZERO,
+            symbol: symbol.to_string(),
+            supplied,
+            borrowed,
+            price_usd: Some(1.0),
+            collateral_factor,
+            entered,
+            supply_apy: 0.0,
+            borrow_apy: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_addresses_parse() {
+        let client = EthereumClient::new("https://eth.llamarpc.com").unwrap();
+        assert!(CompoundV2Adapter::new(client).is_ok());
+    }
+
+    #[test]
+    fn test_ctoken_balance_converts_to_underlying() {
+        // 50 cDAI (8 decimals) at an exchange rate of 0.0221 DAI per cDAI is 1.105 DAI
+        let balance = U256::from(5_000_000_000u64);
+        let exchange_rate = U256::from(221_000_000_000_000_000_000_000_000u128);
+        let amount = CompoundV2Adapter::underlying_amount(balance, exchange_rate);
+        assert_eq!(amount, U256::from(1_105_000_000_000_000_000u128));
+
+        // USDC (6 decimals) at $1 is reported as 1e30
+        let price = U256::from(10u64).pow(U256::from(30u64));
+        assert!((CompoundV2Adapter::underlying_price_usd(price, 6) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_health_factor_only_counts_entered_collateral() {
+        let account = CompoundV2Account {
+            liquidity_usd: 300.0,
+            shortfall_usd: 0.0,
+            partial: false,
+            markets: vec![
+                market("ETH", 1_000.0, 0.0, 0.8, true),
+                market("USDC", 1_000.0, 0.0, 0.85, false),
+                market("DAI", 0.0, 500.0, 0.8, false),
+            ],
+        };
+        assert!((account.health_factor() - 1.6).abs() < 1e-12);
+
+        let positions = account_positions(Address::ZERO, &account, &HealthFactorThresholds::default());
+        assert_eq!(positions.len(), 3);
+        assert_eq!(positions[0].position_type, "collateral");
+        assert_eq!(positions[1].position_type, "supply");
+        let borrow = positions.iter().find(|p| p.position_type == "borrow").unwrap();
+        assert_eq!(borrow.pair, "DAI/ETH");
+        assert_eq!(borrow.metadata["position_details"]["collateral_factor"], 0.8);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_positions_reads_markets() {
+        let user = Address::repeat_byte(0xaa);
+        let oracle = Address::repeat_byte(0x0c);
+        let cdai = Address::repeat_byte(0xda);
+        // Listed, but none of its reads are answered
+        let cusdc = Address::repeat_byte(0xc0);
+        let comptroller = Address::from_str(CompoundV2Adapter::COMPTROLLER_ADDRESS).unwrap();
+        let ceth = Address::from_str(CompoundV2Adapter::CETH_ADDRESS).unwrap();
+        let e18 = U256::from(10u64).pow(U256::from(18u64));
+
+        let mut rpc = MockRpc::new()
+            .on_call(comptroller, &IComptroller::getAllMarketsCall {}, IComptroller::getAllMarketsCall::abi_encode_returns(&(vec![ceth, cdai, cusdc],)))
+            .on_call(comptroller, &IComptroller::getAssetsInCall { account: user }, IComptroller::getAssetsInCall::abi_encode_returns(&(vec![ceth],)))
+            .on_call(
+                comptroller,
+                &IComptroller::getAccountLiquidityCall { account: user },
+                IComptroller::getAccountLiquidityCall::abi_encode_returns(&(U256::ZERO, U256::from(24_750u64) * e18, U256::ZERO)),
+            )
+            .on_call(comptroller, &IComptroller::oracleCall {}, IComptroller::oracleCall::abi_encode_returns(&(oracle,)))
+            // 500 cETH at 0.02 ETH per cETH, with ETH at $3000
+            .on_call(ceth, &IERC20::balanceOfCall { account: user }, IERC20::balanceOfCall::abi_encode_returns(&(U256::from(50_000_000_000u64),)))
+            .on_call(ceth, &ICToken::exchangeRateStoredCall {}, ICToken::exchangeRateStoredCall::abi_encode_returns(&(U256::from(2u64) * e18 * U256::from(100_000_000u64),)))
+            .on_call(
+                comptroller,
+                &IComptroller::marketsCall { cToken: ceth },
+                IComptroller::marketsCall::abi_encode_returns(&(true, U256::from(825_000_000_000_000_000u64), false)),
+            )
+            .on_call(oracle, &ICompoundOracle::getUnderlyingPriceCall { cToken: ceth }, ICompoundOracle::getUnderlyingPriceCall::abi_encode_returns(&(U256::from(3_000u64) * e18,)));
+        // cDAI is listed but the user holds none of it
+        for ctoken in [ceth, cdai] {
+            rpc = rpc
+                .on_call(ctoken, &ICToken::borrowBalanceStoredCall { account: user }, ICToken::borrowBalanceStoredCall::abi_encode_returns(&(U256::ZERO,)))
+                .on_call(ctoken, &ICToken::supplyRatePerBlockCall {}, ICToken::supplyRatePerBlockCall::abi_encode_returns(&(U256::ZERO,)))
+                .on_call(ctoken, &ICToken::borrowRatePerBlockCall {}, ICToken::borrowRatePerBlockCall::abi_encode_returns(&(U256::ZERO,)));
+        }
+        rpc = rpc.on_call(cdai, &IERC20::balanceOfCall { account: user }, IERC20::balanceOfCall::abi_encode_returns(&(U256::ZERO,)));
+
+        let adapter = CompoundV2Adapter::new(EthereumClient::mock(rpc)).unwrap();
+        let positions = adapter.fetch_positions(user).await.unwrap();
+
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].position_type, "supply");
+        assert_eq!(positions[0].pair, "ETH");
+        assert!((positions[0].value_usd - 30_000.0).abs() < 1e-6);
+        assert_eq!(positions[0].metadata["position_details"]["collateral_factor"], 0.825);
+        assert_eq!(positions[0].metadata["account_health"]["liquidity_usd"], 24_750.0);
+        // The unreadable market is skipped rather than failing the whole account
+        assert_eq!(positions[0].metadata["account_health"]["partial"], true);
+    }
+}
@@ -3,67 +3,21 @@ use alloy::{
     sol,
 };
 use async_trait::async_trait;
-use crate::adapters::traits::{AdapterError, Position, DeFiAdapter};
-use crate::blockchain::ethereum_client::EthereumClient;
-use crate::services::IERC20;
-use reqwest;
-use serde::Deserialize;
+use futures::stream::{self, StreamExt};
+use crate::adapters::curve::{CurvePoolReader, CurvePoolValuation};
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
+use crate::blockchain::EthereumClient;
+use crate::risk::{RiskInputs, RiskModelRegistry};
+use crate::services::{TokenMetadataCache, IERC20};
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
 
-#[derive(Debug, Deserialize, Clone)]
-struct ConvexPool {
-    id: u32,
-    name: String,
-    lptoken: String,
-    crvRewards: String,
-    shutdown: bool,
-}
-
-#[derive(Debug, Deserialize, Clone)]
-struct ConvexAPY {
-    pool: String,
-    baseApy: f64,
-    crvApy: f64,
-    cvxApy: f64,
-    extraRewards: Vec<ExtraReward>,
-    totalApy: f64,
-}
-
-#[derive(Debug, Deserialize, Clone)]
-struct ExtraReward {
-    token: String,
-    apy: f64,
-    name: String,
-}
-
-#[derive(Debug, Clone)]
-struct ConvexPosition {
-    pool_id: u32,
-    pool_name: String,
-    lp_token: Address,
-    staked_balance: U256,
-    earned_crv: U256,
-    earned_cvx: U256,
-    extra_rewards: Vec<(Address, U256, String)>,
-    underlying_assets: Vec<String>,
-    total_apy: f64,
-    is_locked: bool,
-    lock_end_time: Option<u64>,
-}
-
-#[derive(Debug, Clone)]
-struct PoolCache {
-    pools: Vec<ConvexPool>,
-    apys: HashMap<String, ConvexAPY>,
-    cached_at: SystemTime,
-}
-
+// Convex Booster and reward pool interfaces
 sol! {
-    #[sol(rpc)]
     interface IConvexBooster {
+        function poolLength() external view returns (uint256);
         function poolInfo(uint256 pid) external view returns (
             address lptoken,
             address token,
@@ -72,370 +26,468 @@ sol! {
             address stash,
             bool shutdown
         );
-        function poolLength() external view returns (uint256);
     }
-    
-    #[sol(rpc)]
+
+    /// BaseRewardPool: holds staked LP (or cvxCRV) and pays CRV
     interface IConvexRewards {
         function balanceOf(address account) external view returns (uint256);
         function earned(address account) external view returns (uint256);
         function extraRewardsLength() external view returns (uint256);
         function extraRewards(uint256 index) external view returns (address);
     }
-    
-    #[sol(rpc)]
-    interface IConvexExtraReward {
+
+    /// VirtualBalanceRewardPool paying an additional token
+    interface IConvexExtraRewards {
         function rewardToken() external view returns (address);
         function earned(address account) external view returns (uint256);
     }
-    
-    #[sol(rpc)]
-    interface IConvexVoteLocked {
-        function lockedBalanceOf(address user) external view returns (uint256);
-        function userLocks(address user, uint256 index) external view returns (
-            uint256 amount,
-            uint256 unlockTime
-        );
+
+    /// Newer pools pay extra rewards through a stash wrapper around the real token
+    interface IStashTokenWrapper {
+        function token() external view returns (address);
     }
 }
 
+/// A Booster pool: a Curve LP token and the reward pool it is staked in
+#[derive(Debug, Clone, Copy)]
+struct BoosterPool {
+    pid: u64,
+    lp_token: Address,
+    gauge: Address,
+    rewards: Address,
+    shutdown: bool,
+}
+
+/// Pending reward on a Convex reward pool
+#[derive(Debug, Clone)]
+struct PendingReward {
+    symbol: String,
+    amount: f64,
+    value_usd: f64,
+}
+
+#[derive(Debug, Clone)]
+struct CachedPositions {
+    positions: Vec<Position>,
+    cached_at: SystemTime,
+}
+
+/// Convex Finance adapter: Curve LP staked through the Booster, with boosted CRV,
+/// CVX and extra rewards, plus cvxCRV held, staked or wrapped
 pub struct ConvexAdapter {
     client: EthereumClient,
     booster: Address,
-    vote_locked_cvx: Address,
-    pool_cache: Arc<Mutex<Option<PoolCache>>>,
-    http_client: reqwest::Client,
+    cvx: Address,
+    cvx_crv: Address,
+    cvx_crv_rewards: Address,
+    stk_cvx_crv: Address,
+    curve: CurvePoolReader,
+    position_cache: Arc<Mutex<HashMap<Address, CachedPositions>>>,
+    pool_cache: Arc<Mutex<Option<(Vec<BoosterPool>, SystemTime)>>>,
+    token_metadata: Arc<TokenMetadataCache>,
 }
 
 impl ConvexAdapter {
-    const CONVEX_BOOSTER: &'static str = "0xF403C135812408BFbE8713b5A23a04b3D48AAE31";
-    const VOTE_LOCKED_CVX: &'static str = "0xD18140b4B819b895A3dba5442F959fA44994AF50";
-    const CONVEX_API_BASE: &'static str = "https://www.convexfinance.com/api";
-    
-    pub fn new(client: EthereumClient, chain_id: Option<u64>) -> Result<Self, AdapterError> {
-        if chain_id.unwrap_or(1) != 1 {
-            return Err(AdapterError::InvalidData("Convex is only available on Ethereum mainnet".to_string()));
-        }
-        
-        let booster = Address::from_str(Self::CONVEX_BOOSTER)
-            .map_err(|e| AdapterError::InvalidData(format!("Invalid booster address: {}", e)))?;
-        let vote_locked_cvx = Address::from_str(Self::VOTE_LOCKED_CVX)
-            .map_err(|e| AdapterError::InvalidData(format!("Invalid vote locked CVX address: {}", e)))?;
-        
+    const CHAIN_ID: u64 = 1;
+    const BOOSTER_ADDRESS: &'static str = "0xF403C135812408BFbE8713b5A23a04b3D48AAE31";
+    const CVX_ADDRESS: &'static str = "0x4e3FBD56CD56c3e72c1403e103b45Db9da5B9D2B";
+    const CVX_CRV_ADDRESS: &'static str = "0x62B9c7356A2Dc64a1969e19C23e4f579F9810Aa7";
+    /// Original cvxCRV staking contract, paying CRV
+    const CVX_CRV_REWARDS_ADDRESS: &'static str = "0x3Fe65692bfCD0e6CF84cB1E7d24108E434A7587e";
+    /// stkcvxCRV wrapper, 1:1 with cvxCRV
+    const STK_CVX_CRV_ADDRESS: &'static str = "0xaa0C3f5F7DFD688C6E646F66CD2a6B66ACdbE434";
+
+    const CACHE_DURATION: Duration = Duration::from_secs(300); // 5 minutes
+    const POOL_CACHE_DURATION: Duration = Duration::from_secs(3600); // 1 hour
+    /// Concurrent reads while listing pools and scanning a user's balances
+    const POOL_SCAN_CONCURRENCY: usize = 16;
+
+    // CVX minting schedule from the CVX token contract
+    const CVX_REDUCTION_PER_CLIFF: f64 = 100_000.0;
+    const CVX_TOTAL_CLIFFS: f64 = 1_000.0;
+    const CVX_MAX_SUPPLY: f64 = 100_000_000.0;
+
+    pub fn new(client: EthereumClient) -> Result<Self, AdapterError> {
+        let parse = |address: &str, name: &str| {
+            Address::from_str(address)
+                .map_err(|e| AdapterError::InvalidData(format!("Invalid {} address: {}", name, e)))
+        };
+
         Ok(Self {
+            booster: parse(Self::BOOSTER_ADDRESS, "Convex booster")?,
+            cvx: parse(Self::CVX_ADDRESS, "CVX")?,
+            cvx_crv: parse(Self::CVX_CRV_ADDRESS, "cvxCRV")?,
+            cvx_crv_rewards: parse(Self::CVX_CRV_REWARDS_ADDRESS, "cvxCRV rewards")?,
+            stk_cvx_crv: parse(Self::STK_CVX_CRV_ADDRESS, "stkcvxCRV")?,
+            curve: CurvePoolReader::new(client.clone(), Self::CHAIN_ID),
             client,
-            booster,
-            vote_locked_cvx,
+            position_cache: Arc::new(Mutex::new(HashMap::new())),
             pool_cache: Arc::new(Mutex::new(None)),
-            http_client: reqwest::Client::builder()
-                .timeout(Duration::from_secs(30))
-                .build()
-                .map_err(|e| AdapterError::NetworkError(format!("Failed to create HTTP client: {}", e)))?,
+            token_metadata: TokenMetadataCache::global(),
         })
     }
-    
-    async fn get_pools(&self) -> Result<PoolCache, AdapterError> {
-        // Check cache (30-minute cache)
+
+    /// Every Booster pool, cached for an hour
+    async fn get_pools(&self) -> Result<Vec<BoosterPool>, AdapterError> {
         {
             let cache = self.pool_cache.lock().unwrap();
-            if let Some(cached) = cache.as_ref() {
-                if cached.cached_at.elapsed().unwrap_or(Duration::from_secs(0)) < Duration::from_secs(1800) {
-                    return Ok(cached.clone());
-                }
-            }
-        }
-        
-        let (pools, apys) = tokio::join!(
-            self.fetch_pools_from_contracts(),
-            self.fetch_apys_from_api()
-        );
-        
-        let pool_cache = PoolCache {
-            pools: pools?,
-            apys: apys.unwrap_or_default(),
-            cached_at: SystemTime::now(),
-        };
-        
-        // Update cache
-        {
-            let mut cache = self.pool_cache.lock().unwrap();
-            *cache = Some(pool_cache.clone());
-        }
-        
-        Ok(pool_cache)
-    }
-    
-    async fn fetch_pools_from_contracts(&self) -> Result<Vec<ConvexPool>, AdapterError> {
-        let booster = IConvexBooster::new(self.booster, self.client.provider());
-        let pool_length = booster.poolLength().call().await
-            .map_err(|e| AdapterError::ContractError(format!("Failed to get pool length: {}", e)))?
-            ._0;
-        
-        let mut pools = Vec::new();
-        
-        for i in 0..pool_length.to::<u64>() {
-            match booster.poolInfo(U256::from(i)).call().await {
-                Ok(pool_info) => {
-                    if !pool_info.shutdown {
-                        pools.push(ConvexPool {
-                            id: i as u32,
-                            name: format!("Pool {}", i),
-                            lptoken: format!("{:?}", pool_info.lptoken),
-                            crvRewards: format!("{:?}", pool_info.crvRewards),
-                            shutdown: pool_info.shutdown,
-                        });
-                    }
+            if let Some((pools, cached_at)) = cache.as_ref() {
+                if cached_at.elapsed().unwrap_or(Duration::MAX) < Self::POOL_CACHE_DURATION {
+                    return Ok(pools.clone());
                 }
-                Err(_) => continue,
             }
         }
-        
+
+        let booster = self.booster;
+        let count = self.client.call(booster, &IConvexBooster::poolLengthCall {}).await?._0.to::<u64>();
+
+        let lookups = (0..count).map(|pid| async move {
+            let info = self.client.call(booster, &IConvexBooster::poolInfoCall { pid: U256::from(pid) }).await?;
+            Ok::<_, AdapterError>(BoosterPool {
+                pid,
+                lp_token: info.lptoken,
+                gauge: info.gauge,
+                rewards: info.crvRewards,
+                shutdown: info.shutdown,
+            })
+        });
+        let pools: Vec<BoosterPool> = stream::iter(lookups)
+            .buffer_unordered(Self::POOL_SCAN_CONCURRENCY)
+            .filter_map(|result| async move { result.ok() })
+            .collect()
+            .await;
+
+        *self.pool_cache.lock().unwrap() = Some((pools.clone(), SystemTime::now()));
         Ok(pools)
     }
-    
-    async fn fetch_apys_from_api(&self) -> Result<HashMap<String, ConvexAPY>, AdapterError> {
-        let url = format!("{}/apys", Self::CONVEX_API_BASE);
-        
-        match self.http_client.get(&url).send().await {
-            Ok(response) if response.status().is_success() => {
-                response.json().await
-                    .map_err(|e| AdapterError::NetworkError(format!("Failed to parse APY data: {}", e)))
-            }
-            _ => Ok(HashMap::new()), // Fallback to empty
-        }
-    }
-    
-    async fn get_user_positions(&self, address: Address) -> Result<Vec<ConvexPosition>, AdapterError> {
-        let pool_cache = self.get_pools().await?;
+
+    async fn get_lp_positions(&self, user: Address) -> Result<Vec<Position>, AdapterError> {
+        let pools = self.get_pools().await?;
+
+        // Both CRV and CVX are paid on every pool; price them once
+        let (crv_price, cvx_price) = tokio::join!(
+            self.curve.get_token_price("CRV"),
+            self.curve.get_token_price("CVX"),
+        );
+        let (crv_price, cvx_price) = (crv_price.unwrap_or(0.0), cvx_price.unwrap_or(0.0));
+        let cvx_per_crv = self.cvx_per_crv().await.unwrap_or(0.0);
+
+        let scans = pools.iter().map(|pool| async move {
+            self.get_lp_position(user, *pool, crv_price, cvx_price, cvx_per_crv).await
+        });
+        let results: Vec<Result<Option<Position>, AdapterError>> = stream::iter(scans)
+            .buffer_unordered(Self::POOL_SCAN_CONCURRENCY)
+            .collect()
+            .await;
+
         let mut positions = Vec::new();
-        
-        // Check regular staking positions
-        for pool in &pool_cache.pools {
-            if let Ok(reward_contract) = Address::from_str(&pool.crvRewards) {
-                if let Ok(Some(position)) = self.get_pool_position(address, pool, reward_contract, &pool_cache).await {
-                    positions.push(position);
-                }
+        for result in results {
+            match result {
+                Ok(Some(position)) => positions.push(position),
+                Ok(None) => {}
+                Err(e) => tracing::debug!("Failed to read Convex pool: {}", e),
             }
         }
-        
-        // Check locked CVX position
-        if let Ok(Some(locked_pos)) = self.get_locked_cvx_position(address).await {
-            positions.push(locked_pos);
-        }
-        
+
         Ok(positions)
     }
-    
-    async fn get_pool_position(
+
+    /// Curve LP staked in a Booster pool's reward contract
+    async fn get_lp_position(
         &self,
-        user_address: Address,
-        pool: &ConvexPool,
-        reward_contract: Address,
-        pool_cache: &PoolCache,
-    ) -> Result<Option<ConvexPosition>, AdapterError> {
-        let rewards = IConvexRewards::new(reward_contract, self.client.provider());
-        
-        let staked_balance = rewards.balanceOf(user_address).call().await
-            .map_err(|e| AdapterError::ContractError(format!("Failed to get balance: {}", e)))?._0;
-        
-        if staked_balance == U256::ZERO {
+        user: Address,
+        pool: BoosterPool,
+        crv_price: f64,
+        cvx_price: f64,
+        cvx_per_crv: f64,
+    ) -> Result<Option<Position>, AdapterError> {
+        let staked = self.client.call(pool.rewards, &IConvexRewards::balanceOfCall { account: user }).await?._0;
+        if staked == U256::ZERO {
             return Ok(None);
         }
-        
-        let earned_crv = rewards.earned(user_address).call().await
-            .map_err(|e| AdapterError::ContractError(format!("Failed to get earned CRV: {}", e)))?._0;
-        
-        let earned_cvx = earned_crv; // 1:1 ratio simplified
-        let extra_rewards = self.get_extra_rewards(reward_contract, user_address).await.unwrap_or_default();
-        
-        let total_apy = pool_cache.apys.get(&pool.id.to_string())
-            .map(|apy| apy.totalApy)
-            .unwrap_or(10.0); // Fallback APY
-        
-        let underlying_assets = self.get_underlying_assets(pool);
-        
-        Ok(Some(ConvexPosition {
-            pool_id: pool.id,
-            pool_name: pool.name.clone(),
-            lp_token: Address::from_str(&pool.lptoken).unwrap_or_default(),
-            staked_balance,
-            earned_crv,
-            earned_cvx,
-            extra_rewards,
-            underlying_assets,
-            total_apy,
-            is_locked: false,
-            lock_end_time: None,
+
+        let valuation = self.curve.value_lp_token(pool.lp_token).await?;
+        let staked_lp = Self::to_f64(staked, 18);
+        let value_usd = staked_lp * valuation.lp_price_usd;
+
+        let earned_crv = self.client.call(pool.rewards, &IConvexRewards::earnedCall { account: user }).await?._0;
+        let earned_crv = Self::to_f64(earned_crv, 18);
+        let earned_cvx = earned_crv * cvx_per_crv;
+        let extra_rewards = self.get_extra_rewards(pool.rewards, user).await.unwrap_or_else(|e| {
+            tracing::debug!("Failed to read extra rewards for Convex pool {}: {}", pool.pid, e);
+            Vec::new()
+        });
+
+        let pending_rewards_usd = earned_crv * crv_price
+            + earned_cvx * cvx_price
+            + extra_rewards.iter().map(|reward| reward.value_usd).sum::<f64>();
+        let share = if valuation.lp_total_supply > 0.0 { staked_lp / valuation.lp_total_supply } else { 0.0 };
+        let pnl = PnlBreakdown { rewards_earned: pending_rewards_usd, ..Default::default() };
+
+        Ok(Some(Position {
+            id: position_id("convex", Self::CHAIN_ID, "liquidity", pool.lp_token, user),
+            protocol: "convex".to_string(),
+            position_type: "liquidity".to_string(),
+            pair: valuation.pair(),
+            value_usd,
+            pnl_usd: pnl.total(),
+            pnl_percentage: if value_usd > 0.0 { pnl.total() / value_usd * 100.0 } else { 0.0 },
+            metadata: serde_json::json!({
+                "pool_id": pool.pid,
+                "reward_contract": format!("{:?}", pool.rewards),
+                "gauge_address": format!("{:?}", pool.gauge),
+                "shutdown": pool.shutdown,
+                "staked_balance": staked.to_string(),
+                "underlying_pool": Self::pool_json(&valuation, share),
+                "pending_crv": earned_crv,
+                "pending_crv_usd": earned_crv * crv_price,
+                "pending_cvx": earned_cvx,
+                "pending_cvx_usd": earned_cvx * cvx_price,
+                "extra_rewards": extra_rewards.iter().map(|reward| serde_json::json!({
+                    "token_symbol": reward.symbol,
+                    "amount": reward.amount,
+                    "value_usd": reward.value_usd,
+                })).collect::<Vec<_>>(),
+                "pending_rewards_usd": pending_rewards_usd,
+                "pnl_breakdown": pnl,
+                "risk_score": Self::lp_risk_score(valuation.is_pegged(), pool.shutdown),
+            }),
+            last_updated: chrono::Utc::now().timestamp() as u64,
         }))
     }
-    
-    async fn get_locked_cvx_position(&self, user_address: Address) -> Result<Option<ConvexPosition>, AdapterError> {
-        let vote_locked = IConvexVoteLocked::new(self.vote_locked_cvx, self.client.provider());
-        
-        let locked_balance = vote_locked.lockedBalanceOf(user_address).call().await
-            .map_err(|e| AdapterError::ContractError(format!("Failed to get locked balance: {}", e)))?._0;
-        
-        if locked_balance == U256::ZERO {
-            return Ok(None);
-        }
-        
-        // Get next unlock time
-        let lock_end_time = match vote_locked.userLocks(user_address, U256::ZERO).call().await {
-            Ok(lock_info) => Some(lock_info._1.to::<u64>()),
-            Err(_) => None,
-        };
-        
-        Ok(Some(ConvexPosition {
-            pool_id: 9999, // Special ID for locked CVX
-            pool_name: "Locked CVX".to_string(),
-            lp_token: Address::ZERO,
-            staked_balance: locked_balance,
-            earned_crv: U256::ZERO,
-            earned_cvx: U256::ZERO,
-            extra_rewards: Vec::new(),
-            underlying_assets: vec!["CVX".to_string()],
-            total_apy: 15.0, // Typical locked CVX APY
-            is_locked: true,
-            lock_end_time,
-        }))
+
+    /// The Curve pool behind a staked position and the user's share of its coins
+    fn pool_json(valuation: &CurvePoolValuation, share: f64) -> serde_json::Value {
+        serde_json::json!({
+            "pool_address": format!("{:?}", valuation.pool),
+            "lp_token": format!("{:?}", valuation.lp_token),
+            "pool_tvl_usd": valuation.tvl_usd,
+            "lp_price_usd": valuation.lp_price_usd,
+            "virtual_price": valuation.virtual_price,
+            "pool_share": share,
+            "coins": valuation.coins.iter().map(|coin| serde_json::json!({
+                "token_address": format!("{:?}", coin.address),
+                "token_symbol": coin.symbol,
+                "amount": coin.balance * share,
+                "price_usd": coin.price_usd,
+            })).collect::<Vec<_>>(),
+        })
     }
-    
-    async fn get_extra_rewards(&self, reward_contract: Address, user_address: Address) -> Result<Vec<(Address, U256, String)>, AdapterError> {
-        let rewards = IConvexRewards::new(reward_contract, self.client.provider());
-        let extra_length = rewards.extraRewardsLength().call().await?.._0;
-        
-        let mut extra_rewards = Vec::new();
-        
-        for i in 0..extra_length.to::<u64>() {
-            if let Ok(extra_addr) = rewards.extraRewards(U256::from(i)).call().await {
-                let extra_reward = IConvexExtraReward::new(extra_addr._0, self.client.provider());
-                
-                if let Ok(earned) = extra_reward.earned(user_address).call().await {
-                    if earned._0 > U256::ZERO {
-                        if let Ok(token_addr) = extra_reward.rewardToken().call().await {
-                            let symbol = self.get_token_symbol(token_addr._0).await.unwrap_or("UNKNOWN".to_string());
-                            extra_rewards.push((token_addr._0, earned._0, symbol));
-                        }
-                    }
-                }
+
+    async fn get_extra_rewards(&self, rewards: Address, user: Address) -> Result<Vec<PendingReward>, AdapterError> {
+        let count = self.client.call(rewards, &IConvexRewards::extraRewardsLengthCall {}).await?._0.to::<u64>();
+        let mut pending = Vec::new();
+
+        for index in 0..count {
+            let extra = self.client.call(rewards, &IConvexRewards::extraRewardsCall { index: U256::from(index) }).await?._0;
+            let earned = self.client.call(extra, &IConvexExtraRewards::earnedCall { account: user }).await?._0;
+            if earned == U256::ZERO {
+                continue;
             }
+
+            let reward_token = self.client.call(extra, &IConvexExtraRewards::rewardTokenCall {}).await?._0;
+            let token = match self.client.call(reward_token, &IStashTokenWrapper::tokenCall {}).await {
+                Ok(wrapped) if wrapped._0 != Address::ZERO => wrapped._0,
+                _ => reward_token,
+            };
+            let metadata = self.token_metadata.get_or_fetch(&self.client, Self::CHAIN_ID, token).await?;
+            let amount = Self::to_f64(earned, metadata.decimals as i32);
+            let price = self.curve.get_token_price(&metadata.symbol).await.unwrap_or(0.0);
+
+            pending.push(PendingReward {
+                symbol: metadata.symbol,
+                amount,
+                value_usd: amount * price,
+            });
         }
-        
-        Ok(extra_rewards)
+
+        Ok(pending)
     }
-    
-    async fn get_token_symbol(&self, token_address: Address) -> Result<String, AdapterError> {
-        let token = IERC20::new(token_address, self.client.provider());
-        token.symbol().call().await
-            .map(|s| s._0)
-            .map_err(|e| AdapterError::ContractError(format!("Failed to get token symbol: {}", e)))
+
+    /// CVX minted per CRV claimed at the current CVX supply
+    async fn cvx_per_crv(&self) -> Result<f64, AdapterError> {
+        let supply = self.client.call(self.cvx, &IERC20::totalSupplyCall {}).await?._0;
+        Ok(Self::cvx_mint_ratio(Self::to_f64(supply, 18)))
     }
-    
-    fn get_underlying_assets(&self, pool: &ConvexPool) -> Vec<String> {
-        match pool.name.to_lowercase().as_str() {
-            name if name.contains("3crv") || name.contains("3pool") => 
-                vec!["DAI".to_string(), "USDC".to_string(), "USDT".to_string()],
-            name if name.contains("frax") => 
-                vec!["FRAX".to_string(), "USDC".to_string()],
-            name if name.contains("eth") => 
-                vec!["ETH".to_string(), "WETH".to_string()],
-            name if name.contains("btc") => 
-                vec!["WBTC".to_string(), "renBTC".to_string()],
-            _ => vec!["CURVE-LP".to_string()],
+
+    /// CVX mints less per CRV as supply passes each cliff, and nothing past max supply
+    fn cvx_mint_ratio(cvx_supply: f64) -> f64 {
+        let cliff = (cvx_supply / Self::CVX_REDUCTION_PER_CLIFF).floor();
+        if cliff >= Self::CVX_TOTAL_CLIFFS || cvx_supply >= Self::CVX_MAX_SUPPLY {
+            return 0.0;
         }
+        (Self::CVX_TOTAL_CLIFFS - cliff) / Self::CVX_TOTAL_CLIFFS
     }
-    
-    fn calculate_position_value(&self, position: &ConvexPosition) -> f64 {
-        let balance_f64 = position.staked_balance.to::<f64>() / 1e18;
-        
-        // Simple price estimation based on asset type
-        let token_price = if position.underlying_assets.contains(&"ETH".to_string()) {
-            2000.0 // ETH price
-        } else if position.underlying_assets.iter().any(|a| a.contains("BTC")) {
-            50000.0 // BTC price
+
+    /// cvxCRV in the wallet, staked for CRV, or wrapped as stkcvxCRV
+    async fn get_cvx_crv_position(&self, user: Address) -> Result<Option<Position>, AdapterError> {
+        let (wallet, staked, wrapped) = tokio::try_join!(
+            self.client.call(self.cvx_crv, &IERC20::balanceOfCall { account: user }),
+            self.client.call(self.cvx_crv_rewards, &IConvexRewards::balanceOfCall { account: user }),
+            self.client.call(self.stk_cvx_crv, &IERC20::balanceOfCall { account: user }),
+        )?;
+        let (wallet, staked, wrapped) = (wallet._0, staked._0, wrapped._0);
+        let total = wallet + staked + wrapped;
+        if total == U256::ZERO {
+            return Ok(None);
+        }
+
+        let amount = Self::to_f64(total, 18);
+        let cvx_crv_price = self.curve.get_token_price("cvxCRV").await?;
+        let crv_price = self.curve.get_token_price("CRV").await.ok();
+
+        let earned_crv = if staked > U256::ZERO {
+            let earned = self.client.call(self.cvx_crv_rewards, &IConvexRewards::earnedCall { account: user }).await?._0;
+            Self::to_f64(earned, 18)
         } else {
-            1.0 // Stable assets
+            0.0
         };
-        
-        balance_f64 * token_price
+        let pending_rewards_usd = earned_crv * crv_price.unwrap_or(0.0);
+        let value_usd = amount * cvx_crv_price;
+        // cvxCRV can't be redeemed for CRV; its only exit is the Curve pool
+        let peg = crv_price.filter(|price| *price > 0.0).map(|price| cvx_crv_price / price);
+        let pnl = PnlBreakdown { rewards_earned: pending_rewards_usd, ..Default::default() };
+
+        Ok(Some(Position {
+            id: position_id("convex", Self::CHAIN_ID, "staking", self.cvx_crv, user),
+            protocol: "convex".to_string(),
+            position_type: "staking".to_string(),
+            pair: "cvxCRV/CRV".to_string(),
+            value_usd,
+            pnl_usd: pnl.total(),
+            pnl_percentage: if value_usd > 0.0 { pnl.total() / value_usd * 100.0 } else { 0.0 },
+            metadata: serde_json::json!({
+                "token_address": format!("{:?}", self.cvx_crv),
+                "token_symbol": "cvxCRV",
+                "underlying_asset": "CRV",
+                "wallet_balance": Self::to_f64(wallet, 18),
+                "staked_balance": Self::to_f64(staked, 18),
+                "wrapped_balance": Self::to_f64(wrapped, 18),
+                "pending_crv": earned_crv,
+                "pending_rewards_usd": pending_rewards_usd,
+                "pnl_breakdown": pnl,
+                "crv_peg": peg,
+                "transferable": staked == U256::ZERO,
+                "risk_score": Self::cvx_crv_risk_score(peg),
+            }),
+            last_updated: chrono::Utc::now().timestamp() as u64,
+        }))
     }
-    
-    fn to_position(&self, convex_pos: &ConvexPosition) -> Position {
-        let balance_usd = self.calculate_position_value(convex_pos);
-        
-        let mut metadata = HashMap::new();
-        metadata.insert("pool_id".to_string(), convex_pos.pool_id.to_string());
-        metadata.insert("reward_contract".to_string(), format!("{:?}", convex_pos.lp_token));
-        metadata.insert("earned_crv".to_string(), convex_pos.earned_crv.to_string());
-        metadata.insert("earned_cvx".to_string(), convex_pos.earned_cvx.to_string());
-        metadata.insert("is_locked".to_string(), convex_pos.is_locked.to_string());
-        
-        if let Some(lock_end) = convex_pos.lock_end_time {
-            metadata.insert("lock_end_time".to_string(), lock_end.to_string());
-        }
-        
-        // Add extra rewards
-        for (i, (token, amount, symbol)) in convex_pos.extra_rewards.iter().enumerate() {
-            metadata.insert(format!("extra_reward_{}_token", i), format!("{:?}", token));
-            metadata.insert(format!("extra_reward_{}_amount", i), amount.to_string());
-            metadata.insert(format!("extra_reward_{}_symbol", i), symbol.clone());
-        }
-        
-        Position {
-            protocol: "Convex".to_string(),
-            chain: "ethereum".to_string(),
-            position_type: if convex_pos.is_locked { "Locked Staking" } else { "Staking" }.to_string(),
-            pool_name: convex_pos.pool_name.clone(),
-            tokens: convex_pos.underlying_assets.clone(),
-            balance_usd,
-            apy: Some(convex_pos.total_apy),
-            metadata,
-        }
+
+    /// Curve pool risk plus a layer for Convex's own contracts; shut down pools no
+    /// longer earn and should be withdrawn
+    fn lp_risk_score(pegged: bool, shutdown: bool) -> f64 {
+        let inputs = RiskInputs {
+            correlated: pegged,
+            premium: if shutdown { 0.05 + 0.15 } else { 0.05 },
+            ..Default::default()
+        };
+        RiskModelRegistry::global().score("liquidity", &inputs)
+    }
+
+    /// Riskier the further cvxCRV trades below CRV
+    fn cvx_crv_risk_score(peg: Option<f64>) -> f64 {
+        let discount = peg.map_or(0.0, |peg| (1.0 - peg).max(0.0));
+        (0.5 + discount).min(1.0)
+    }
+
+    fn to_f64(value: U256, decimals: i32) -> f64 {
+        let raw: f64 = value.to_string().parse().unwrap_or(0.0);
+        raw / 10f64.powi(decimals)
     }
 }
 
 #[async_trait]
 impl DeFiAdapter for ConvexAdapter {
-    fn protocol_name(&self) -> String {
-        "Convex Finance".to_string()
+    fn protocol_name(&self) -> &'static str {
+        "convex"
     }
-    
-    fn supported_chains(&self) -> Vec<String> {
-        vec!["ethereum".to_string()]
+
+    fn position_types(&self) -> &'static [&'static str] {
+        &["liquidity", "staking"]
     }
-    
-    async fn get_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
-        let convex_positions = self.get_user_positions(address).await?;
-        let positions = convex_positions.iter()
-            .map(|pos| self.to_position(pos))
-            .collect();
-        
+
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+
+    fn supports_historical_block(&self) -> bool {
+        true
+    }
+
+    async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
+        // Check cache first
+        {
+            let cache = self.position_cache.lock().unwrap();
+            if let Some(cached) = cache.get(&address) {
+                let cache_age = cached.cached_at.elapsed().unwrap_or(Duration::from_secs(0));
+                if cache_age < Self::CACHE_DURATION {
+                    return Ok(cached.positions.clone());
+                }
+            }
+        }
+
+        let mut positions = self.get_lp_positions(address).await?;
+
+        match self.get_cvx_crv_position(address).await {
+            Ok(Some(position)) => positions.push(position),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to read cvxCRV balance: {}", e),
+        }
+
+        // Update cache
+        {
+            let mut cache = self.position_cache.lock().unwrap();
+            cache.insert(address, CachedPositions {
+                positions: positions.clone(),
+                cached_at: SystemTime::now(),
+            });
+        }
+
         Ok(positions)
     }
-    
-    async fn get_protocol_stats(&self) -> Result<serde_json::Value, AdapterError> {
-        let pool_cache = self.get_pools().await?;
-        
-        Ok(serde_json::json!({
-            "protocol": "Convex Finance",
-            "chain": "ethereum",
-            "total_pools": pool_cache.pools.len(),
-            "average_apy": pool_cache.apys.values()
-                .map(|a| a.totalApy)
-                .sum::<f64>() / pool_cache.apys.len().max(1) as f64,
-            "supported_features": [
-                "LP Token Staking",
-                "CRV/CVX Rewards",
-                "Extra Incentives",
-                "Locked CVX"
-            ]
-        }))
+
+    async fn supports_contract(&self, contract_address: Address) -> bool {
+        if [self.booster, self.cvx, self.cvx_crv, self.cvx_crv_rewards, self.stk_cvx_crv].contains(&contract_address) {
+            return true;
+        }
+
+        let cache = self.pool_cache.lock().unwrap();
+        cache.as_ref().is_some_and(|(pools, _)| {
+            pools.iter().any(|pool| pool.lp_token == contract_address || pool.rewards == contract_address)
+        })
+    }
+
+    async fn get_position_value(&self, position: &Position) -> Result<f64, AdapterError> {
+        Ok(position.value_usd)
     }
-    
-    async fn health_check(&self) -> Result<bool, AdapterError> {
-        let booster = IConvexBooster::new(self.booster, self.client.provider());
-        booster.poolLength().call().await
-            .map(|_| true)
-            .map_err(|e| AdapterError::ContractError(format!("Health check failed: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_addresses_parse() {
+        let client = EthereumClient::new("https://eth.llamarpc.com").unwrap();
+        assert!(ConvexAdapter::new(client).is_ok());
+    }
+
+    #[test]
+    fn test_cvx_mint_ratio_follows_cliffs() {
+        assert_eq!(ConvexAdapter::cvx_mint_ratio(0.0), 1.0);
+        assert!((ConvexAdapter::cvx_mint_ratio(50_000_000.0) - 0.5).abs() < 1e-12);
+        // Partway through a cliff mints at that cliff's rate
+        assert!((ConvexAdapter::cvx_mint_ratio(99_950_000.0) - 0.001).abs() < 1e-12);
+        assert_eq!(ConvexAdapter::cvx_mint_ratio(100_000_000.0), 0.0);
+    }
+
+    #[test]
+    fn test_shutdown_pools_score_riskier() {
+        assert!(ConvexAdapter::lp_risk_score(true, true) > ConvexAdapter::lp_risk_score(true, false));
+        assert!(ConvexAdapter::lp_risk_score(false, false) > ConvexAdapter::lp_risk_score(true, false));
+        assert!((ConvexAdapter::lp_risk_score(true, false) - 0.35).abs() < 1e-12);
+        assert!((ConvexAdapter::lp_risk_score(false, false) - 0.6).abs() < 1e-12);
+        assert!((ConvexAdapter::lp_risk_score(true, true) - 0.5).abs() < 1e-12);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,246 @@
+use alloy::{
+    primitives::{address, Address, U256},
+    sol,
+};
+use crate::adapters::traits::AdapterError;
+use crate::blockchain::EthereumClient;
+use crate::services::{PriceAggregator, TokenMetadataCache, IERC20};
+use std::sync::Arc;
+
+// Curve pool interfaces shared by stableswap and cryptoswap pools
+sol! {
+    interface ICurvePool {
+        function coins(uint256 i) external view returns (address);
+        function balances(uint256 i) external view returns (uint256);
+        function get_virtual_price() external view returns (uint256);
+    }
+
+    /// Older pools issue a separate LP token that points back at the pool
+    interface ICurveLpToken {
+        function minter() external view returns (address);
+    }
+}
+
+/// Curve's placeholder for native ETH in `coins()`
+const NATIVE_ETH: Address = address!("EeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE");
+
+/// One coin held by a Curve pool
+#[derive(Debug, Clone)]
+pub struct CurveCoin {
+    pub address: Address,
+    pub symbol: String,
+    /// Pool balance in whole tokens
+    pub balance: f64,
+    pub price_usd: Option<f64>,
+}
+
+/// A Curve pool's coins and the USD value of one LP token
+#[derive(Debug, Clone)]
+pub struct CurvePoolValuation {
+    pub pool: Address,
+    pub lp_token: Address,
+    pub coins: Vec<CurveCoin>,
+    pub tvl_usd: f64,
+    pub lp_total_supply: f64,
+    pub lp_price_usd: f64,
+    pub virtual_price: Option<f64>,
+}
+
+impl CurvePoolValuation {
+    /// Coin symbols joined as a pair label, e.g. "DAI/USDC/USDT"
+    pub fn pair(&self) -> String {
+        self.coins.iter().map(|coin| coin.symbol.as_str()).collect::<Vec<_>>().join("/")
+    }
+
+    /// Whether every coin trades near the same price, as in stableswap pools
+    pub fn is_pegged(&self) -> bool {
+        let prices: Vec<f64> = self.coins.iter().filter_map(|coin| coin.price_usd).collect();
+        let (Some(min), Some(max)) = (
+            prices.iter().copied().reduce(f64::min),
+            prices.iter().copied().reduce(f64::max),
+        ) else {
+            return true;
+        };
+        min > 0.0 && max / min < 1.05
+    }
+}
+
+/// Reads Curve pool composition and values LP tokens from coin balances
+pub struct CurvePoolReader {
+    client: EthereumClient,
+    chain_id: u64,
+    prices: Arc<PriceAggregator>,
+    token_metadata: Arc<TokenMetadataCache>,
+}
+
+impl CurvePoolReader {
+    /// Pools hold at most eight coins
+    const MAX_COINS: u64 = 8;
+
+    pub fn new(client: EthereumClient, chain_id: u64) -> Self {
+        Self {
+            client,
+            chain_id,
+            prices: PriceAggregator::global(),
+            token_metadata: TokenMetadataCache::global(),
+        }
+    }
+
+    /// Pool behind an LP token: the token's `minter` for older pools, otherwise the
+    /// token itself
+    pub async fn pool_for_lp_token(&self, lp_token: Address) -> Address {
+        match self.client.call(lp_token, &ICurveLpToken::minterCall {}).await {
+            Ok(minter) if minter._0 != Address::ZERO => minter._0,
+            _ => lp_token,
+        }
+    }
+
+    /// Value the pool issuing `lp_token`. Coins without a price feed are valued at the
+    /// average price of the priced coins, which holds for pegged pools.
+    pub async fn value_lp_token(&self, lp_token: Address) -> Result<CurvePoolValuation, AdapterError> {
+        let pool = self.pool_for_lp_token(lp_token).await;
+
+        let mut coins = Vec::new();
+        for index in 0..Self::MAX_COINS {
+            let i = U256::from(index);
+            // `coins(i)` reverts past the last coin
+            let Ok(coin) = self.client.call(pool, &ICurvePool::coinsCall { i }).await else {
+                break;
+            };
+            let balance = self.client.call(pool, &ICurvePool::balancesCall { i }).await?._0;
+            let (symbol, decimals) = if coin._0 == NATIVE_ETH {
+                ("ETH".to_string(), 18)
+            } else {
+                let metadata = self.token_metadata.get_or_fetch(&self.client, self.chain_id, coin._0).await?;
+                (metadata.symbol, metadata.decimals)
+            };
+            let price_usd = self.get_token_price(&symbol).await.ok();
+
+            coins.push(CurveCoin {
+                address: coin._0,
+                symbol,
+                balance: to_f64(balance, decimals as i32),
+                price_usd,
+            });
+        }
+
+        if coins.is_empty() {
+            return Err(AdapterError::ContractError(format!("No coins found in Curve pool {:?}", pool)));
+        }
+
+        let Some(tvl_usd) = Self::pool_value_usd(&coins) else {
+            return Err(AdapterError::UnsupportedProtocol(format!(
+                "No price feed for any coin in Curve pool {:?}", pool
+            )));
+        };
+
+        let total_supply = self.client.call(lp_token, &IERC20::totalSupplyCall {}).await?._0;
+        let lp_total_supply = to_f64(total_supply, 18);
+        let virtual_price = self.client
+            .call(pool, &ICurvePool::get_virtual_priceCall {})
+            .await
+            .ok()
+            .map(|price| to_f64(price._0, 18));
+
+        Ok(CurvePoolValuation {
+            pool,
+            lp_token,
+            coins,
+            tvl_usd,
+            lp_total_supply,
+            lp_price_usd: if lp_total_supply > 0.0 { tvl_usd / lp_total_supply } else { 0.0 },
+            virtual_price,
+        })
+    }
+
+    fn pool_value_usd(coins: &[CurveCoin]) -> Option<f64> {
+        let prices: Vec<f64> = coins.iter().filter_map(|coin| coin.price_usd).collect();
+        if prices.is_empty() {
+            return None;
+        }
+        let fallback_price = prices.iter().sum::<f64>() / prices.len() as f64;
+
+        Some(coins.iter().map(|coin| coin.balance * coin.price_usd.unwrap_or(fallback_price)).sum())
+    }
+
+    /// USD price for a token symbol seen in Curve pools and their rewards
+    pub async fn get_token_price(&self, symbol: &str) -> Result<f64, AdapterError> {
+        let coin_id = match symbol.to_uppercase().as_str() {
+            "ETH" | "WETH" => "ethereum",
+            "STETH" => "staked-ether",
+            "WSTETH" => "wrapped-steth",
+            "RETH" => "rocket-pool-eth",
+            "FRXETH" => "frax-ether",
+            "CBETH" => "coinbase-wrapped-staked-eth",
+            "USDC" => "usd-coin",
+            "USDT" => "tether",
+            "DAI" => "dai",
+            "FRAX" => "frax",
+            "LUSD" => "liquity-usd",
+            "CRVUSD" => "crvusd",
+            "PYUSD" => "paypal-usd",
+            "USDE" => "ethena-usde",
+            "WBTC" => "wrapped-bitcoin",
+            "TBTC" => "tbtc",
+            "CBBTC" => "coinbase-wrapped-btc",
+            "CRV" => "curve-dao-token",
+            "CVX" => "convex-finance",
+            "CVXCRV" => "convex-crv",
+            _ => {
+                return Err(AdapterError::UnsupportedProtocol(format!("No price feed for token {}", symbol)));
+            }
+        };
+
+        self.prices.get_price(coin_id).await
+    }
+}
+
+fn to_f64(value: U256, decimals: i32) -> f64 {
+    let raw: f64 = value.to_string().parse().unwrap_or(0.0);
+    raw / 10f64.powi(decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coin(symbol: &str, balance: f64, price_usd: Option<f64>) -> CurveCoin {
+        CurveCoin {
+            address: Address::ZERO,
+            symbol: symbol.to_string(),
+            balance,
+            price_usd,
+        }
+    }
+
+    #[test]
+    fn test_unpriced_coins_valued_at_average_price() {
+        let coins = vec![
+            coin("USDC", 1_000.0, Some(1.0)),
+            coin("USDT", 1_000.0, Some(1.0)),
+            coin("sUSD", 500.0, None),
+        ];
+        assert_eq!(CurvePoolReader::pool_value_usd(&coins), Some(2_500.0));
+        assert_eq!(CurvePoolReader::pool_value_usd(&[coin("XYZ", 10.0, None)]), None);
+    }
+
+    #[test]
+    fn test_pegged_pool_detection() {
+        let valuation = |coins| CurvePoolValuation {
+            pool: Address::ZERO,
+            lp_token: Address::ZERO,
+            coins,
+            tvl_usd: 0.0,
+            lp_total_supply: 0.0,
+            lp_price_usd: 0.0,
+            virtual_price: None,
+        };
+
+        let stable = valuation(vec![coin("DAI", 1.0, Some(1.0)), coin("USDC", 1.0, Some(0.999))]);
+        let crypto = valuation(vec![coin("USDT", 1.0, Some(1.0)), coin("WBTC", 1.0, Some(60_000.0))]);
+
+        assert!(stable.is_pegged());
+        assert!(!crypto.is_pegged());
+        assert_eq!(stable.pair(), "DAI/USDC");
+    }
+}
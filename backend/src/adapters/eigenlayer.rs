@@ -1,12 +1,14 @@
 use alloy::{
     primitives::{Address, U256, FixedBytes},
     sol,
+    sol_types::SolEvent,
 };
 use async_trait::async_trait;
-use crate::adapters::traits::{AdapterError, Position, DeFiAdapter};
+use crate::adapters::traits::{AdapterError, position_id, Position, DeFiAdapter};
 use crate::blockchain::ethereum_client::EthereumClient;
+use crate::risk::{RiskInputs, RiskModelRegistry};
 use crate::services::IERC20;
-use reqwest;
+use crate::utils::{normalize_amount, ApiClient, ETH_DECIMALS};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::str::FromStr;
@@ -42,6 +44,7 @@ struct AVSInfo {
     total_stake: f64,          // Total stake securing this AVS
     reward_rate: f64,          // Current reward rate
     slashing_risk: u8,         // 0-100 risk score for this AVS
+    slashable_fraction: Option<f64>, // Share of operator stake allocated to the AVS; None before slashing config exists
     is_active: bool,
 }
 
@@ -210,6 +213,23 @@ sol! {
         event OperatorAVSRegistrationStatusUpdated(address indexed operator, address indexed avs, uint32 status);
         event AVSMetadataURIUpdated(address indexed avs, string metadataURI);
     }
+    
+    #[sol(rpc)]
+    interface IAllocationManager {
+        struct OperatorSet {
+            address avs;
+            uint32 id;
+        }
+        
+        struct Allocation {
+            uint64 currentMagnitude;
+            int128 pendingDiff;
+            uint32 effectBlock;
+        }
+        
+        function getAllocatedSets(address operator) external view returns (OperatorSet[] memory);
+        function getAllocation(address operator, OperatorSet memory operatorSet, address strategy) external view returns (Allocation memory);
+    }
 }
 
 /// EigenLayer Restaking Protocol Adapter
@@ -220,6 +240,7 @@ pub struct EigenLayerAdapter {
     delegation_manager_address: Address,
     rewards_coordinator_address: Address,
     avs_directory_address: Address,
+    allocation_manager_address: Address,
     
     // Strategy addresses for different LSTs
     strategy_addresses: HashMap<String, Address>,
@@ -230,7 +251,7 @@ pub struct EigenLayerAdapter {
     avs_cache: Arc<Mutex<HashMap<Address, AVSInfo>>>,
     
     // HTTP client for API calls
-    http_client: reqwest::Client,
+    http_client: ApiClient,
     coingecko_api_key: Option<String>,
 }
 
@@ -241,6 +262,21 @@ impl EigenLayerAdapter {
     const DELEGATION_MANAGER_ADDRESS: &'static str = "0x39053D51B77DC0d36036Fc1fCc8Cb819df8Ef37A";
     const REWARDS_COORDINATOR_ADDRESS: &'static str = "0x7750d328b314EfFa365A0402CcfD489B80B0adda";
     const AVS_DIRECTORY_ADDRESS: &'static str = "0x135DDa560e946695d6f155dACaFC6f1F25C1F5AF";
+    const ALLOCATION_MANAGER_ADDRESS: &'static str = "0x948a420b8CC1d6BFd0B6087C2E7c344a2CD0bc39";
+    
+    /// Block before the AVSDirectory was deployed; registration logs are scanned from here
+    const AVS_DIRECTORY_START_BLOCK: u64 = 19_400_000;
+    /// `avsOperatorStatus` value for a registered operator
+    const AVS_STATUS_REGISTERED: u32 = 1;
+    /// Allocation magnitudes are expressed against a total of 1e18
+    const MAGNITUDE_SCALE: f64 = 1e18;
+    /// Slashing risk assigned to a registered AVS whose slashing config can't be read
+    const UNKNOWN_AVS_SLASHING_RISK: u8 = 25;
+    
+    /// AVS service managers with a known name and service type
+    const KNOWN_AVS: &'static [(&'static str, &'static str, &'static str)] = &[
+        ("0x870679E138bCdf293b7Ff14dD44b70FC97e12fc0", "EigenDA", "data_availability"),
+    ];
     
     // Strategy addresses for different LSTs
     const STETH_STRATEGY: &'static str = "0x93c4b944D05dfe6df7645A86cd2206016c51564D";
@@ -263,6 +299,9 @@ impl EigenLayerAdapter {
             
         let avs_directory_address = Address::from_str(Self::AVS_DIRECTORY_ADDRESS)
             .map_err(|e| AdapterError::InvalidData(format!("Invalid AVS directory address: {}", e)))?;
+            
+        let allocation_manager_address = Address::from_str(Self::ALLOCATION_MANAGER_ADDRESS)
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid allocation manager address: {}", e)))?;
         
         // Initialize strategy addresses
         let mut strategy_addresses = HashMap::new();
@@ -278,11 +317,12 @@ impl EigenLayerAdapter {
             delegation_manager_address,
             rewards_coordinator_address,
             avs_directory_address,
+            allocation_manager_address,
             strategy_addresses,
             position_cache: Arc::new(Mutex::new(HashMap::new())),
             operator_cache: Arc::new(Mutex::new(HashMap::new())),
             avs_cache: Arc::new(Mutex::new(HashMap::new())),
-            http_client: reqwest::Client::new(),
+            http_client: ApiClient::from_env()?,
             coingecko_api_key: std::env::var("COINGECKO_API_KEY").ok(),
         })
     }
@@ -305,11 +345,15 @@ impl EigenLayerAdapter {
         let lst_positions = self.get_lst_positions(address).await?;
         positions.extend(lst_positions);
         
-        // 3. Get delegation info for all positions
-        for position in &mut positions {
-            if let Some(operator_info) = self.get_operator_info(address).await? {
+        // 3. Get delegation info for all positions; every position shares the staker's operator
+        if let Some(mut operator_info) = self.get_operator_info(address).await? {
+            let avs_list = self.get_operator_avs_list(&operator_info.address).await?;
+            operator_info.avs_count = avs_list.len() as u64;
+            operator_info.avs_list = avs_list.iter().map(|avs| avs.name.clone()).collect();
+            
+            for position in &mut positions {
                 position.operator = Some(operator_info.clone());
-                position.avs_list = self.get_operator_avs_list(&operator_info.address).await?;
+                position.avs_list = avs_list.clone();
             }
         }
         
@@ -549,7 +593,7 @@ impl EigenLayerAdapter {
             // Convert shares to approximate USD value
             let strategy = IStrategy::new(*strategy_addr, self.client.provider());
             if let Ok(underlying_amount) = strategy.sharesToUnderlyingView(shares).call().await {
-                let eth_value = underlying_amount._0.to::<f64>() / 10f64.powi(18);
+                let eth_value = normalize_amount(underlying_amount._0, ETH_DECIMALS);
                 let eth_price = self.get_eth_price_usd().await.unwrap_or(4000.0);
                 total_staked += eth_value * eth_price;
             }
@@ -559,17 +603,14 @@ impl EigenLayerAdapter {
         let operator_name = self.get_operator_name(operator_address).await
             .unwrap_or_else(|_| format!("Operator-{}", operator_address.to_string()[..8].to_string()));
         
-        // Get AVS count
-        let avs_count = self.get_operator_avs_count(operator_address).await.unwrap_or(0);
-        
         let operator_info = OperatorInfo {
             address: operator_address,
             name: operator_name,
             total_staked,
             staker_count: 0, // Would need event parsing or subgraph
             commission_rate: 10.0, // Default 10%, would need operator metadata
-            avs_count,
-            avs_list: Vec::new(), // Will be filled separately
+            avs_count: 0,         // Filled from on-chain registrations
+            avs_list: Vec::new(),
             is_slashable: true, // Most operators are slashable
             reputation_score: self.calculate_operator_reputation(operator_address).await,
         };
@@ -578,7 +619,6 @@ impl EigenLayerAdapter {
             operator_address = %operator_address,
             operator_name = %operator_info.name,
             total_staked = %operator_info.total_staked,
-            avs_count = %operator_info.avs_count,
             reputation_score = %operator_info.reputation_score,
             "Fetched operator details"
         );
@@ -586,43 +626,137 @@ impl EigenLayerAdapter {
         Ok(operator_info)
     }
     
-    /// Get list of AVS services secured by an operator
-    async fn get_operator_avs_list(&self, operator_address: &Address) -> Result<Vec<AVSInfo>, String> {
-        // This would typically require querying the AVS registry or using a subgraph
-        // For now, return mock data for common AVS services
+    /// AVSs the operator is currently registered to, with slashing risk derived from the
+    /// operator's allocations where the AllocationManager has them
+    async fn get_operator_avs_list(&self, operator_address: &Address) -> Result<Vec<AVSInfo>, AdapterError> {
+        let operator = *operator_address;
+        let avs_directory = IAVSDirectory::new(self.avs_directory_address, self.client.provider());
+        
+        let mut candidates = self.get_operator_avs_registrations(operator).await?;
+        let allocations = self.get_operator_allocations(operator).await;
+        for avs in allocations.keys() {
+            if !candidates.contains(avs) {
+                candidates.push(*avs);
+            }
+        }
         
         let mut avs_list = Vec::new();
         
-        // Mock AVS data (in reality, would query AVS registry)
-        let mock_avs = vec![
-            ("EigenDA", "data_availability", 5.5, 15),
-            ("Lagrange", "zero_knowledge", 7.2, 25),
-            ("AltLayer", "rollup_services", 6.8, 20),
-            ("Omni", "interoperability", 8.1, 30),
-        ];
-        
-        for (name, service_type, reward_rate, risk_score) in mock_avs {
-            let avs_info = AVSInfo {
-                address: Address::ZERO, // Would be actual AVS contract address
-                name: name.to_string(),
-                service_type: service_type.to_string(),
-                total_stake: 50_000_000.0, // Mock value
-                reward_rate,
-                slashing_risk: risk_score,
-                is_active: true,
-            };
-            avs_list.push(avs_info);
+        for avs in candidates {
+            let allocated = allocations.get(&avs).copied();
+            
+            let registered = avs_directory.avsOperatorStatus(avs, operator).call().await
+                .map(|status| status._0 == Self::AVS_STATUS_REGISTERED)
+                .unwrap_or(false);
+            
+            // Operator-set allocations are slashable even without a legacy registration
+            if !registered && allocated.is_none() {
+                continue;
+            }
+            
+            let (name, service_type) = Self::known_avs(avs)
+                .map(|(name, service_type)| (name.to_string(), service_type.to_string()))
+                .unwrap_or_else(|| (format!("AVS-{}", &avs.to_string()[2..10]), "unknown".to_string()));
+            
+            avs_list.push(AVSInfo {
+                address: avs,
+                name,
+                service_type,
+                total_stake: 0.0,  // Not tracked per AVS on-chain
+                reward_rate: 0.0,  // Rewards are paid through the RewardsCoordinator, not per AVS
+                slashing_risk: Self::avs_slashing_risk(allocated),
+                slashable_fraction: allocated,
+                is_active: registered,
+            });
         }
         
         tracing::info!(
             operator_address = %operator_address,
             avs_count = avs_list.len(),
-            "Retrieved AVS list for operator"
+            "Retrieved AVS registrations for operator"
         );
         
         Ok(avs_list)
     }
     
+    /// AVS addresses that have ever emitted a registration status update for `operator`
+    async fn get_operator_avs_registrations(&self, operator: Address) -> Result<Vec<Address>, AdapterError> {
+        let status_topic = format!("{:?}", IAVSDirectory::OperatorAVSRegistrationStatusUpdated::SIGNATURE_HASH);
+        let operator_topic = format!("0x{:0>64}", alloy::hex::encode(operator));
+        
+        let logs: Vec<serde_json::Value> = self.client.request("eth_getLogs", serde_json::json!([{
+            "address": format!("{:?}", self.avs_directory_address),
+            "fromBlock": format!("{:#x}", Self::AVS_DIRECTORY_START_BLOCK),
+            "toBlock": "latest",
+            "topics": [status_topic, operator_topic],
+        }])).await?;
+        
+        let mut avs_addresses = Vec::new();
+        for log in logs {
+            let Some(avs) = log["topics"].get(2)
+                .and_then(|t| t.as_str())
+                .and_then(|t| Address::from_str(&t[t.len().saturating_sub(40)..]).ok())
+            else {
+                continue;
+            };
+            if !avs_addresses.contains(&avs) {
+                avs_addresses.push(avs);
+            }
+        }
+        
+        Ok(avs_addresses)
+    }
+    
+    /// Largest fraction of the operator's stake in any strategy allocated to each AVS.
+    /// Empty when the AllocationManager isn't available.
+    async fn get_operator_allocations(&self, operator: Address) -> HashMap<Address, f64> {
+        let allocation_manager = IAllocationManager::new(self.allocation_manager_address, self.client.provider());
+        let mut allocations = HashMap::new();
+        
+        let operator_sets = match allocation_manager.getAllocatedSets(operator).call().await {
+            Ok(sets) => sets._0,
+            Err(e) => {
+                tracing::debug!(operator = %operator, error = %e, "No operator set allocations available");
+                return allocations;
+            }
+        };
+        
+        for operator_set in operator_sets {
+            for strategy in self.strategy_addresses.values() {
+                let Ok(allocation) = allocation_manager
+                    .getAllocation(operator, operator_set.clone(), *strategy)
+                    .call()
+                    .await
+                else {
+                    continue;
+                };
+                
+                let fraction = allocation._0.currentMagnitude as f64 / Self::MAGNITUDE_SCALE;
+                if fraction > 0.0 {
+                    let entry = allocations.entry(operator_set.avs).or_insert(0.0f64);
+                    *entry = entry.max(fraction);
+                }
+            }
+        }
+        
+        allocations
+    }
+    
+    fn known_avs(avs: Address) -> Option<(&'static str, &'static str)> {
+        Self::KNOWN_AVS.iter()
+            .find(|(address, _, _)| Address::from_str(address).map(|a| a == avs).unwrap_or(false))
+            .map(|(_, name, service_type)| (*name, *service_type))
+    }
+    
+    /// 0-100 slashing risk: the share of stake the AVS can slash, or a flat score for
+    /// registrations without on-chain slashing config
+    fn avs_slashing_risk(slashable_fraction: Option<f64>) -> u8 {
+        match slashable_fraction {
+            Some(fraction) => (fraction.clamp(0.0, 1.0) * 100.0).round() as u8,
+            None => Self::UNKNOWN_AVS_SLASHING_RISK,
+        }
+    }
+    
     /// Get pending withdrawals for a user
     async fn get_pending_withdrawals(&self, user_address: Address, strategy_address: Address) -> Result<Vec<WithdrawalInfo>, AdapterError> {
         // This would require event parsing or subgraph queries to get withdrawal history
@@ -734,6 +868,19 @@ impl EigenLayerAdapter {
         (base_restaking_risk + lst_specific_risk + avs_slashing_risk).min(100)
     }
     
+    /// Position risk in [0, 1] from the restaking model, plus validator risk for native
+    /// ETH or the liquid staking protocol's risk for an LST
+    fn restaking_risk_score(asset_type: &str, token_symbol: &str) -> f64 {
+        let premium = match (asset_type, token_symbol) {
+            ("native_eth", _) => 0.10,
+            (_, "stETH" | "wstETH") => 0.08, // Lido is established but centralized
+            (_, "rETH") => 0.06,              // Rocket Pool more decentralized
+            (_, "cbETH") => 0.12,             // Coinbase centralized custody risk
+            _ => 0.10,
+        };
+        RiskModelRegistry::global().score("restaking", &RiskInputs { premium, ..Default::default() })
+    }
+    
     /// Operator reputation scoring
     async fn calculate_operator_reputation(&self, _operator_address: Address) -> u8 {
         // Operator reputation factors:
@@ -765,16 +912,9 @@ impl EigenLayerAdapter {
         }
     }
     
-    /// Get operator AVS count
-    async fn get_operator_avs_count(&self, _operator_address: Address) -> Result<u64, String> {
-        // Would query AVS registry or use subgraph
-        // Mock data for now
-        Ok(4)
-    }
-    
     /// Calculate position value with current prices
     async fn calculate_position_value(&self, position: &RestakingPosition) -> (f64, f64, f64) {
-        let underlying_amount = position.underlying_amount.to::<f64>() / 10f64.powi(18);
+        let underlying_amount = normalize_amount(position.underlying_amount, ETH_DECIMALS);
         
         // Get asset price
         let asset_price = if position.asset_address == Address::ZERO {
@@ -793,7 +933,7 @@ impl EigenLayerAdapter {
         };
         
         let base_value_usd = underlying_amount * asset_price;
-        let rewards_amount = position.rewards_earned.to::<f64>() / 10f64.powi(18);
+        let rewards_amount = normalize_amount(position.rewards_earned, ETH_DECIMALS);
         let rewards_value_usd = rewards_amount * asset_price;
         
         // Calculate risk-adjusted P&L
@@ -857,10 +997,10 @@ impl EigenLayerAdapter {
             request = request.header("X-Cg-Pro-Api-Key", api_key);
         }
         
-        let response = request
-            .send()
+        let response = self.http_client
+            .send(request)
             .await
-            .map_err(|e| format!("HTTP request failed: {}", e))?;
+            .map_err(|e| e.to_string())?;
             
         if !response.status().is_success() {
             return Err(format!("HTTP error {}", response.status()));
@@ -887,13 +1027,14 @@ impl EigenLayerAdapter {
     
     /// Call EigenLayer API
     async fn call_eigenlayer_api(&self, url: &str) -> Result<String, String> {
-        let response = self.http_client
+        let request = self.http_client
             .get(url)
             .header("Accept", "application/json")
-            .header("User-Agent", "DeFi-Portfolio-Tracker/1.0")
-            .send()
+            .header("User-Agent", "DeFi-Portfolio-Tracker/1.0");
+        let response = self.http_client
+            .send(request)
             .await
-            .map_err(|e| format!("HTTP request failed: {}", e))?;
+            .map_err(|e| e.to_string())?;
             
         if !response.status().is_success() {
             return Err(format!("API returned status: {}", response.status()));
@@ -932,6 +1073,7 @@ impl EigenLayerAdapter {
         address == self.delegation_manager_address ||
         address == self.rewards_coordinator_address ||
         address == self.avs_directory_address ||
+        address == self.allocation_manager_address ||
         self.strategy_addresses.values().any(|&addr| addr == address)
     }
     
@@ -1035,14 +1177,16 @@ impl DeFiAdapter for EigenLayerAdapter {
                     "name": avs.name,
                     "service_type": avs.service_type,
                     "reward_rate": avs.reward_rate,
+                    "address": format!("{:?}", avs.address),
                     "slashing_risk": avs.slashing_risk,
+                    "slashable_fraction": avs.slashable_fraction,
                     "total_stake": avs.total_stake,
                     "is_active": avs.is_active
                 })
             }).collect();
             
             let position = Position {
-                id: format!("eigenlayer_{}_{}", restake_pos.asset_symbol.to_lowercase(), restake_pos.asset_address),
+                id: position_id("eigenlayer", self.chain_id(), position_type, restake_pos.asset_address, address),
                 protocol: "eigenlayer".to_string(),
                 position_type: position_type.to_string(),
                 pair,
@@ -1058,7 +1202,8 @@ impl DeFiAdapter for EigenLayerAdapter {
                     "underlying_amount": restake_pos.underlying_amount.to_string(),
                     "current_apr": restake_pos.current_apr,
                     "rewards_earned": restake_pos.rewards_earned.to_string(),
-                    "risk_score": restake_pos.risk_score,
+                    "risk_score": Self::restaking_risk_score(&restake_pos.asset_type, &restake_pos.asset_symbol),
+                    "restaking_risk": restake_pos.risk_score,
                     "last_reward_timestamp": restake_pos.last_reward_timestamp,
                     
                     // Operator information
@@ -1195,7 +1340,7 @@ impl DeFiAdapter for EigenLayerAdapter {
         if let Some(underlying_amount_str) = position.metadata.get("underlying_amount") {
             if let Some(amount_str) = underlying_amount_str.as_str() {
                 if let Ok(underlying_amount) = U256::from_str(amount_str) {
-                    let amount_eth = underlying_amount.to::<f64>() / 10f64.powi(18);
+                    let amount_eth = normalize_amount(underlying_amount, ETH_DECIMALS);
                     
                     // Apply asset-specific pricing
                     let asset_price = if let Some(asset_symbol) = position.metadata.get("asset_symbol") {
@@ -1267,6 +1412,16 @@ mod tests {
         assert!(!adapter.is_eigenlayer_contract(random_addr));
     }
     
+    #[test]
+    fn test_avs_slashing_risk_from_allocation() {
+        assert_eq!(EigenLayerAdapter::avs_slashing_risk(Some(0.25)), 25);
+        assert_eq!(EigenLayerAdapter::avs_slashing_risk(Some(1.0)), 100);
+        assert_eq!(EigenLayerAdapter::avs_slashing_risk(None), EigenLayerAdapter::UNKNOWN_AVS_SLASHING_RISK);
+        
+        let eigen_da = Address::from_str(EigenLayerAdapter::KNOWN_AVS[0].0).unwrap();
+        assert_eq!(EigenLayerAdapter::known_avs(eigen_da).map(|(name, _)| name), Some("EigenDA"));
+    }
+    
     #[test]
     fn test_apr_calculations() {
         // Test that APR calculations return reasonable values
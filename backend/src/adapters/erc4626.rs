@@ -0,0 +1,267 @@
+use alloy::{
+    primitives::{Address, U256},
+    sol,
+};
+use async_trait::async_trait;
+use futures::future::join_all;
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
+use crate::blockchain::EthereumClient;
+use crate::risk::{RiskInputs, RiskModelRegistry};
+use crate::services::{PriceService, TokenMetadataCache, IERC20};
+use crate::utils::normalize_amount;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+// ERC-4626 tokenized vault interface
+sol! {
+    interface IERC4626 {
+        function asset() external view returns (address);
+        function convertToAssets(uint256 shares) external view returns (uint256);
+    }
+}
+
+/// A user's holding in one ERC-4626 vault
+#[derive(Debug, Clone)]
+pub struct VaultHolding {
+    pub vault: Address,
+    pub vault_symbol: String,
+    pub asset: Address,
+    pub asset_symbol: String,
+    /// Vault shares, in whole share units
+    pub shares: f64,
+    /// Underlying the shares redeem for, in whole asset units
+    pub assets: f64,
+    /// `None` when the underlying asset has no confident price
+    pub asset_price_usd: Option<f64>,
+}
+
+impl VaultHolding {
+    pub fn value_usd(&self) -> f64 {
+        self.assets * self.asset_price_usd.unwrap_or(0.0)
+    }
+
+    /// Underlying per share
+    pub fn share_price(&self) -> f64 {
+        if self.shares > 0.0 {
+            self.assets / self.shares
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedPositions {
+    positions: Vec<Position>,
+    cached_at: SystemTime,
+}
+
+/// Catch-all adapter for ERC-4626 vaults no dedicated adapter covers. Only vaults on
+/// the configured allowlist are read, since arbitrary 4626 contracts can report anything.
+pub struct Erc4626Adapter {
+    client: EthereumClient,
+    vaults: Vec<Address>,
+    token_metadata: Arc<TokenMetadataCache>,
+    price_service: Arc<PriceService>,
+    position_cache: Arc<Mutex<HashMap<Address, CachedPositions>>>,
+}
+
+impl Erc4626Adapter {
+    const CHAIN_ID: u64 = 1;
+    const CACHE_DURATION: Duration = Duration::from_secs(300); // 5 minutes
+    /// Long-tail vaults carry unknown strategy and contract risk on top of farming yield
+    const UNKNOWN_STRATEGY_PREMIUM: f64 = 0.05;
+
+    pub fn new(client: EthereumClient, vaults: Vec<Address>) -> Self {
+        Self {
+            client,
+            vaults,
+            token_metadata: TokenMetadataCache::global(),
+            price_service: PriceService::global(),
+            position_cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Vault allowlist from `ERC4626_VAULTS`, a comma-separated list of mainnet addresses
+    pub fn vaults_from_env() -> Result<Vec<Address>, AdapterError> {
+        std::env::var("ERC4626_VAULTS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|vault| !vault.is_empty())
+            .map(|vault| {
+                Address::from_str(vault)
+                    .map_err(|e| AdapterError::InvalidData(format!("Invalid ERC-4626 vault address {}: {}", vault, e)))
+            })
+            .collect()
+    }
+
+    fn vault_risk_score() -> f64 {
+        let inputs = RiskInputs { premium: Self::UNKNOWN_STRATEGY_PREMIUM, ..Default::default() };
+        RiskModelRegistry::global().score("yield_farming", &inputs)
+    }
+
+    /// The user's holding in `vault`, or `None` without shares
+    pub async fn holding(&self, vault: Address, user: Address) -> Result<Option<VaultHolding>, AdapterError> {
+        let shares = self.client.call(vault, &IERC20::balanceOfCall { account: user }).await?._0;
+        if shares == U256::ZERO {
+            return Ok(None);
+        }
+
+        let (asset, assets) = tokio::try_join!(
+            self.client.call(vault, &IERC4626::assetCall {}),
+            self.client.call(vault, &IERC4626::convertToAssetsCall { shares }),
+        )?;
+        let asset = asset._0;
+        let (vault_metadata, asset_metadata) = tokio::try_join!(
+            self.token_metadata.get_or_fetch(&self.client, Self::CHAIN_ID, vault),
+            self.token_metadata.get_or_fetch(&self.client, Self::CHAIN_ID, asset),
+        )?;
+
+        let asset_price_usd = match self.price_service
+            .get_token_price(Self::CHAIN_ID, asset, Some(&asset_metadata.symbol))
+            .await
+        {
+            Ok(price) => Some(price),
+            Err(e) => {
+                tracing::warn!("No price for ERC-4626 asset {} of vault {:?}: {}", asset_metadata.symbol, vault, e);
+                None
+            }
+        };
+
+        Ok(Some(VaultHolding {
+            vault,
+            vault_symbol: vault_metadata.symbol,
+            asset,
+            asset_symbol: asset_metadata.symbol,
+            shares: normalize_amount(shares, vault_metadata.decimals),
+            assets: normalize_amount(assets._0, asset_metadata.decimals),
+            asset_price_usd,
+        }))
+    }
+}
+
+fn vault_position(user: Address, holding: &VaultHolding, risk_score: f64) -> Position {
+    Position {
+        id: position_id("erc4626", Erc4626Adapter::CHAIN_ID, "vault", holding.vault, user),
+        protocol: "erc4626".to_string(),
+        position_type: "vault".to_string(),
+        pair: format!("{}/{}", holding.vault_symbol, holding.asset_symbol),
+        value_usd: holding.value_usd(),
+        pnl_usd: 0.0,
+        pnl_percentage: 0.0,
+        metadata: serde_json::json!({
+            "vault_address": format!("{:?}", holding.vault),
+            "token_address": format!("{:?}", holding.asset),
+            "underlying_asset": holding.asset_symbol,
+            "price_unknown": holding.asset_price_usd.is_none(),
+            // Yield accrues into the share price, and the deposit price isn't known
+            "pnl_breakdown": PnlBreakdown::default(),
+            "risk_score": risk_score,
+            "position_details": {
+                "shares": holding.shares,
+                "assets": holding.assets,
+                "share_price": holding.share_price(),
+                "asset_price_usd": holding.asset_price_usd,
+            }
+        }),
+        last_updated: chrono::Utc::now().timestamp() as u64,
+    }
+}
+
+#[async_trait]
+impl DeFiAdapter for Erc4626Adapter {
+    fn protocol_name(&self) -> &'static str {
+        "erc4626"
+    }
+
+    fn position_types(&self) -> &'static [&'static str] {
+        &["vault"]
+    }
+
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+
+    fn supports_historical_block(&self) -> bool {
+        true
+    }
+
+    async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
+        // Check cache first
+        {
+            let cache = self.position_cache.lock().unwrap();
+            if let Some(cached) = cache.get(&address) {
+                let cache_age = cached.cached_at.elapsed().unwrap_or(Duration::from_secs(0));
+                if cache_age < Self::CACHE_DURATION {
+                    return Ok(cached.positions.clone());
+                }
+            }
+        }
+
+        let holdings = join_all(self.vaults.iter().map(|vault| self.holding(*vault, address))).await;
+
+        let mut positions = Vec::new();
+        for (vault, result) in self.vaults.iter().zip(holdings) {
+            match result {
+                Ok(Some(holding)) => positions.push(vault_position(address, &holding, Self::vault_risk_score())),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to read ERC-4626 vault {:?}: {}", vault, e),
+            }
+        }
+
+        // Update cache
+        {
+            let mut cache = self.position_cache.lock().unwrap();
+            cache.insert(address, CachedPositions {
+                positions: positions.clone(),
+                cached_at: SystemTime::now(),
+            });
+        }
+
+        Ok(positions)
+    }
+
+    async fn supports_contract(&self, contract_address: Address) -> bool {
+        self.vaults.contains(&contract_address)
+    }
+
+    async fn get_position_value(&self, position: &Position) -> Result<f64, AdapterError> {
+        Ok(position.value_usd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vault_position_values_underlying_assets() {
+        let holding = VaultHolding {
+            vault: Address::repeat_byte(1),
+            vault_symbol: "sUSDX".to_string(),
+            asset: Address::repeat_byte(2),
+            asset_symbol: "USDX".to_string(),
+            shares: 900.0,
+            assets: 1_000.0,
+            asset_price_usd: Some(0.99),
+        };
+
+        let risk_score = Erc4626Adapter::vault_risk_score();
+        assert!((risk_score - 0.5).abs() < 1e-12);
+
+        let position = vault_position(Address::ZERO, &holding, risk_score);
+        assert_eq!(position.position_type, "vault");
+        assert_eq!(position.pair, "sUSDX/USDX");
+        assert!((position.value_usd - 990.0).abs() < 1e-9);
+        assert!(!position.is_price_unknown());
+        assert!((position.metadata["position_details"]["share_price"].as_f64().unwrap() - 1.0 / 0.9).abs() < 1e-9);
+
+        let unpriced = VaultHolding { asset_price_usd: None, ..holding };
+        let position = vault_position(Address::ZERO, &unpriced, risk_score);
+        assert_eq!(position.value_usd, 0.0);
+        assert!(position.is_price_unknown());
+    }
+}
@@ -0,0 +1,330 @@
+use alloy::{
+    primitives::{Address, U256},
+    sol,
+};
+use async_trait::async_trait;
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
+use crate::blockchain::EthereumClient;
+use crate::services::{PriceService, IERC20};
+use crate::utils::ApiClient;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+// Ethena contract interfaces
+sol! {
+    interface IStakedUSDe {
+        function convertToAssets(uint256 shares) external view returns (uint256);
+        function totalAssets() external view returns (uint256);
+        function cooldownDuration() external view returns (uint24);
+    }
+}
+
+/// Protocol-wide state shared by every position in one fetch
+#[derive(Debug, Clone)]
+struct EthenaMarketState {
+    usde_price_usd: f64,
+    /// USDe per sUSDe
+    exchange_rate: f64,
+    /// Annualized sUSDe staking yield, percent
+    staking_apy: Option<f64>,
+    /// Annualized protocol yield (funding + staked ETH), percent
+    protocol_apy: Option<f64>,
+    /// Reserve fund value as a fraction of USDe supply
+    reserve_fund_ratio: Option<f64>,
+    cooldown_secs: u64,
+}
+
+#[derive(Debug, Clone)]
+struct CachedPositions {
+    positions: Vec<Position>,
+    cached_at: SystemTime,
+}
+
+/// Ethena adapter: USDe held directly and staked as sUSDe
+pub struct EthenaAdapter {
+    client: EthereumClient,
+    usde_address: Address,
+    susde_address: Address,
+    reserve_fund_address: Address,
+    position_cache: Arc<Mutex<HashMap<Address, CachedPositions>>>,
+    http_client: ApiClient,
+    price_service: Arc<PriceService>,
+}
+
+impl EthenaAdapter {
+    const USDE_ADDRESS: &'static str = "0x4c9EDD5852cd905f086C759E8383e09bff1E68B3";
+    const SUSDE_ADDRESS: &'static str = "0x9D39A5DE30e57443BfF2A8307A4256c8797A3497";
+    const RESERVE_FUND_ADDRESS: &'static str = "0x2B5AB59163a6e93b4486f6055D33CA4a115Dd4D5";
+    /// Stablecoins the reserve fund holds besides USDe/sUSDe
+    const RESERVE_STABLES: &'static [&'static str] = &[
+        "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48", // USDC
+        "0xdAC17F958D2ee523a2206206994597C13D831ec7", // USDT
+    ];
+    const YIELD_API_URL: &'static str = "https://ethena.fi/api/yields/protocol-and-staking-yield";
+    const CACHE_DURATION: Duration = Duration::from_secs(300); // 5 minutes
+
+    /// Reserve fund coverage below which the buffer against negative funding is considered thin
+    const HEALTHY_RESERVE_RATIO: f64 = 0.01;
+    const CHAIN_ID: u64 = 1;
+    /// Peg deviation at which depeg risk is maxed out
+    const MAX_PEG_DEVIATION: f64 = 0.02;
+
+    pub fn new(client: EthereumClient) -> Result<Self, AdapterError> {
+        let usde_address = Address::from_str(Self::USDE_ADDRESS)
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid USDe address: {}", e)))?;
+        let susde_address = Address::from_str(Self::SUSDE_ADDRESS)
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid sUSDe address: {}", e)))?;
+        let reserve_fund_address = Address::from_str(Self::RESERVE_FUND_ADDRESS)
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid reserve fund address: {}", e)))?;
+
+        Ok(Self {
+            client,
+            usde_address,
+            susde_address,
+            reserve_fund_address,
+            position_cache: Arc::new(Mutex::new(HashMap::new())),
+            http_client: ApiClient::from_env()?,
+            price_service: PriceService::global(),
+        })
+    }
+
+    async fn get_market_state(&self) -> Result<EthenaMarketState, AdapterError> {
+        let one_share = U256::from(10u64).pow(U256::from(18u64));
+        let assets_per_share = self.client
+            .call(self.susde_address, &IStakedUSDe::convertToAssetsCall { shares: one_share })
+            .await?
+            ._0;
+
+        let usde_price_usd = match self.price_service.get_price("ethena-usde").await {
+            Ok(price) => price,
+            Err(e) => {
+                tracing::warn!("USDe price unavailable, assuming peg: {}", e);
+                1.0
+            }
+        };
+
+        let cooldown_secs = self.client
+            .call(self.susde_address, &IStakedUSDe::cooldownDurationCall {})
+            .await
+            .map(|d| d._0.to::<u64>())
+            .unwrap_or(0);
+
+        let (protocol_apy, staking_apy) = self.get_yields().await.unwrap_or_else(|e| {
+            tracing::warn!("Ethena yield API unavailable: {}", e);
+            (None, None)
+        });
+
+        let reserve_fund_ratio = match self.get_reserve_fund_ratio(usde_price_usd).await {
+            Ok(ratio) => Some(ratio),
+            Err(e) => {
+                tracing::warn!("Failed to read Ethena reserve fund: {}", e);
+                None
+            }
+        };
+
+        Ok(EthenaMarketState {
+            usde_price_usd,
+            exchange_rate: Self::to_f64(assets_per_share, 18),
+            staking_apy,
+            protocol_apy,
+            reserve_fund_ratio,
+            cooldown_secs,
+        })
+    }
+
+    /// (protocol yield, sUSDe staking yield) in percent
+    async fn get_yields(&self) -> Result<(Option<f64>, Option<f64>), AdapterError> {
+        let response = self.http_client
+            .send(self.http_client.get(Self::YIELD_API_URL).header("Accept", "application/json"))
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(AdapterError::NetworkError(format!("Ethena yield API HTTP {}", response.status())));
+        }
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| AdapterError::InvalidData(format!("Ethena yield JSON error: {}", e)))?;
+
+        let value = |key: &str| json.get(key).and_then(|y| y.get("value")).and_then(|v| v.as_f64());
+        Ok((value("protocolYield"), value("stakingYield")))
+    }
+
+    /// USD value of the reserve fund's USDe, sUSDe and stablecoins over USDe supply
+    async fn get_reserve_fund_ratio(&self, usde_price_usd: f64) -> Result<f64, AdapterError> {
+        let fund = self.reserve_fund_address;
+
+        let usde_supply = self.client.call(self.usde_address, &IERC20::totalSupplyCall {}).await?._0;
+        let usde_held = self.client.call(self.usde_address, &IERC20::balanceOfCall { account: fund }).await?._0;
+        let susde_held = self.client.call(self.susde_address, &IERC20::balanceOfCall { account: fund }).await?._0;
+        let susde_as_usde = if susde_held > U256::ZERO {
+            self.client.call(self.susde_address, &IStakedUSDe::convertToAssetsCall { shares: susde_held }).await?._0
+        } else {
+            U256::ZERO
+        };
+
+        let mut reserve_usd = (Self::to_f64(usde_held, 18) + Self::to_f64(susde_as_usde, 18)) * usde_price_usd;
+        for stable in Self::RESERVE_STABLES {
+            let token = Address::from_str(stable)
+                .map_err(|e| AdapterError::InvalidData(format!("Invalid stablecoin address: {}", e)))?;
+            let held = self.client.call(token, &IERC20::balanceOfCall { account: fund }).await?._0;
+            reserve_usd += Self::to_f64(held, 6);
+        }
+
+        let supply_usd = Self::to_f64(usde_supply, 18) * usde_price_usd;
+        if supply_usd <= 0.0 {
+            return Err(AdapterError::InvalidData("USDe supply is zero".to_string()));
+        }
+
+        Ok(reserve_usd / supply_usd)
+    }
+
+    fn build_position(&self, user: Address, token: Address, symbol: &str, balance: U256, market: &EthenaMarketState) -> Position {
+        let staked = token == self.susde_address;
+        let amount = Self::to_f64(balance, 18);
+        let usde_amount = if staked { amount * market.exchange_rate } else { amount };
+        let value_usd = usde_amount * market.usde_price_usd;
+
+        let peg_deviation = market.usde_price_usd - 1.0;
+        let risk_score = Self::risk_score(peg_deviation, market.reserve_fund_ratio, staked);
+        let apy = if staked { market.staking_apy } else { None };
+        // Peg deviation is the only unrealized loss on a synthetic dollar; staking yield
+        // is already in the sUSDe exchange rate
+        let pnl = PnlBreakdown { price_pnl: usde_amount * peg_deviation, ..Default::default() };
+
+        let position_type = if staked { "staking" } else { "stablecoin" };
+
+        Position {
+            id: position_id("ethena", Self::CHAIN_ID, position_type, token, user),
+            protocol: "ethena".to_string(),
+            position_type: position_type.to_string(),
+            pair: format!("{}/USD", symbol),
+            value_usd,
+            pnl_usd: pnl.total(),
+            pnl_percentage: peg_deviation * 100.0,
+            metadata: serde_json::json!({
+                "token_address": format!("{:?}", token),
+                "token_symbol": symbol,
+                "underlying_asset": "USDE",
+                "balance": balance.to_string(),
+                "usde_amount": usde_amount,
+                "susde_exchange_rate": market.exchange_rate,
+                "staking_apy": apy,
+                "protocol_apy": market.protocol_apy,
+                "usde_price_usd": market.usde_price_usd,
+                "peg_deviation": peg_deviation,
+                "pnl_breakdown": pnl,
+                "reserve_fund_ratio": market.reserve_fund_ratio,
+                "cooldown_secs": if staked { market.cooldown_secs } else { 0 },
+                "risk_score": risk_score,
+            }),
+            last_updated: chrono::Utc::now().timestamp() as u64,
+        }
+    }
+
+    /// Base synthetic-dollar risk plus depeg and thin-reserve components. Staked USDe adds
+    /// exposure to negative funding (yield goes to zero) and the unstaking cooldown.
+    fn risk_score(peg_deviation: f64, reserve_fund_ratio: Option<f64>, staked: bool) -> f64 {
+        let depeg_risk = (peg_deviation.abs() / Self::MAX_PEG_DEVIATION).min(1.0) * 0.4;
+        let reserve_risk = reserve_fund_ratio
+            .map(|ratio| ((Self::HEALTHY_RESERVE_RATIO - ratio) / Self::HEALTHY_RESERVE_RATIO).clamp(0.0, 1.0) * 0.2)
+            .unwrap_or(0.0);
+        let staking_risk = if staked { 0.05 } else { 0.0 };
+
+        (0.25 + depeg_risk + reserve_risk + staking_risk).min(1.0)
+    }
+
+    fn to_f64(value: U256, decimals: i32) -> f64 {
+        let raw: f64 = value.to_string().parse().unwrap_or(0.0);
+        raw / 10f64.powi(decimals)
+    }
+}
+
+#[async_trait]
+impl DeFiAdapter for EthenaAdapter {
+    fn protocol_name(&self) -> &'static str {
+        "ethena"
+    }
+
+    fn position_types(&self) -> &'static [&'static str] {
+        &["staking", "stablecoin"]
+    }
+
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+
+    async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
+        // Check cache first
+        {
+            let cache = self.position_cache.lock().unwrap();
+            if let Some(cached) = cache.get(&address) {
+                let cache_age = cached.cached_at.elapsed().unwrap_or(Duration::from_secs(0));
+                if cache_age < Self::CACHE_DURATION {
+                    return Ok(cached.positions.clone());
+                }
+            }
+        }
+
+        let usde_balance = self.client.call(self.usde_address, &IERC20::balanceOfCall { account: address }).await?._0;
+        let susde_balance = self.client.call(self.susde_address, &IERC20::balanceOfCall { account: address }).await?._0;
+
+        let mut positions = Vec::new();
+
+        if usde_balance > U256::ZERO || susde_balance > U256::ZERO {
+            let market = self.get_market_state().await?;
+
+            if usde_balance > U256::ZERO {
+                positions.push(self.build_position(address, self.usde_address, "USDe", usde_balance, &market));
+            }
+            if susde_balance > U256::ZERO {
+                positions.push(self.build_position(address, self.susde_address, "sUSDe", susde_balance, &market));
+            }
+        }
+
+        // Update cache
+        {
+            let mut cache = self.position_cache.lock().unwrap();
+            cache.insert(address, CachedPositions {
+                positions: positions.clone(),
+                cached_at: SystemTime::now(),
+            });
+        }
+
+        Ok(positions)
+    }
+
+    async fn supports_contract(&self, contract_address: Address) -> bool {
+        contract_address == self.usde_address || contract_address == self.susde_address
+    }
+
+    async fn get_position_value(&self, position: &Position) -> Result<f64, AdapterError> {
+        Ok(position.value_usd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_addresses_parse() {
+        let client = EthereumClient::new("https://eth.llamarpc.com").unwrap();
+        assert!(EthenaAdapter::new(client).is_ok());
+    }
+
+    #[test]
+    fn test_risk_rises_with_depeg_and_thin_reserve() {
+        let pegged = EthenaAdapter::risk_score(0.0, Some(0.02), false);
+        let depegged = EthenaAdapter::risk_score(-0.01, Some(0.02), false);
+        let thin_reserve = EthenaAdapter::risk_score(0.0, Some(0.0025), false);
+
+        assert!((pegged - 0.25).abs() < 1e-12);
+        assert!((depegged - 0.45).abs() < 1e-12);
+        assert!((thin_reserve - 0.4).abs() < 1e-12);
+        assert!(EthenaAdapter::risk_score(0.0, None, true) > pegged);
+    }
+}
@@ -1,9 +1,14 @@
 use alloy::{
     primitives::{Address, U256},
     sol,
+    sol_types::SolEvent,
 };
 use async_trait::async_trait;
-use crate::adapters::traits::{AdapterError, Position, DeFiAdapter};
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
+use crate::blockchain::EthereumClient;
+use crate::risk::{RiskInputs, RiskModelRegistry};
+use crate::services::PriceAggregator;
+use crate::utils::{normalize_amount, ETH_DECIMALS};
 use reqwest;
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -11,11 +16,6 @@ use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
 
-#[derive(Debug, Clone)]
-pub struct EthereumClient {
-    pub rpc_url: String,
-}
-
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 struct EtherFiApiResponse {
@@ -64,6 +64,16 @@ struct EtherFiStakingPosition {
     position_subtype: String,
 }
 
+/// eETH locked in the withdrawal queue, represented on-chain by a WithdrawRequestNFT
+#[derive(Debug, Clone)]
+struct EtherFiWithdrawRequest {
+    request_id: U256,
+    amount_of_eeth: U256,
+    fee_gwei: u32,
+    is_finalized: bool,
+    requested_at: u64,
+}
+
 sol! {
     #[sol(rpc)]
     interface IEtherFiLiquidityPool {
@@ -150,6 +160,24 @@ sol! {
         function getBidAmount(uint256 bidId) external view returns (uint256);
         function processAuctionFeeRewards(uint256[] memory bidIds) external;
     }
+    
+    #[sol(rpc)]
+    interface IWithdrawRequestNFT {
+        struct WithdrawRequest {
+            uint96 amountOfEEth;
+            uint96 shareOfEEth;
+            bool isValid;
+            uint32 feeGwei;
+        }
+        
+        function getRequest(uint256 requestId) external view returns (WithdrawRequest memory);
+        function isFinalized(uint256 requestId) external view returns (bool);
+        function ownerOf(uint256 tokenId) external view returns (address);
+        function balanceOf(address owner) external view returns (uint256);
+        function lastFinalizedRequestId() external view returns (uint32);
+        
+        event Transfer(address indexed from, address indexed to, uint256 indexed tokenId);
+    }
 }
 
 pub struct EtherFiAdapter {
@@ -162,12 +190,14 @@ pub struct EtherFiAdapter {
     eigenpod_manager_address: Address,
     restaking_manager_address: Address,
     auction_manager_address: Address,
+    withdraw_request_nft_address: Address,
     position_cache: Arc<Mutex<HashMap<Address, CachedPositions>>>,
     http_client: reqwest::Client,
-    coingecko_api_key: Option<String>,
+    prices: Arc<PriceAggregator>,
 }
 
 impl EtherFiAdapter {
+    const CHAIN_ID: u64 = 1;
     const EETH_ADDRESS: &'static str = "0x35fA164735182de50811E8e2E824cFb9B6118ac2";
     const LIQUIDITY_POOL_ADDRESS: &'static str = "0x308861A430be4cce5502d0A12724771Fc6DaF216";
     const NODE_MANAGER_ADDRESS: &'static str = "0x8103151E2377e78C04a3d2564e20542680ed3096";
@@ -175,6 +205,13 @@ impl EtherFiAdapter {
     const EIGENPOD_MANAGER_ADDRESS: &'static str = "0x858646372CC42E1A627fcE94aa7A7033e7CF075A";
     const RESTAKING_MANAGER_ADDRESS: &'static str = "0x308861A430be4cce5502d0A12724771Fc6DaF216";
     const AUCTION_MANAGER_ADDRESS: &'static str = "0x5fD13359Ba15A84B76f7F87568309040176167cd";
+    const WITHDRAW_REQUEST_NFT_ADDRESS: &'static str = "0x7d5706f6ef3F89B3951E23e557CDFBC3239D4E2c";
+    
+    /// How far back to scan for WithdrawRequestNFT mints (~30 days of blocks)
+    const WITHDRAWAL_LOOKBACK_BLOCKS: u64 = 216_000;
+    /// Typical time from requesting a withdrawal to the request being finalized
+    const EXPECTED_WITHDRAWAL_DELAY_SECS: u64 = 7 * 24 * 60 * 60;
+    const SECONDS_PER_BLOCK: u64 = 12;
     
     pub fn new(client: EthereumClient) -> Result<Self, AdapterError> {
         let eeth_address = Address::from_str(Self::EETH_ADDRESS)
@@ -191,6 +228,8 @@ impl EtherFiAdapter {
             .map_err(|e| AdapterError::InvalidData(format!("Invalid restaking manager address: {}", e)))?;
         let auction_manager_address = Address::from_str(Self::AUCTION_MANAGER_ADDRESS)
             .map_err(|e| AdapterError::InvalidData(format!("Invalid auction manager address: {}", e)))?;
+        let withdraw_request_nft_address = Address::from_str(Self::WITHDRAW_REQUEST_NFT_ADDRESS)
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid withdraw request NFT address: {}", e)))?;
         
         Ok(Self {
             client,
@@ -201,9 +240,10 @@ impl EtherFiAdapter {
             eigenpod_manager_address,
             restaking_manager_address,
             auction_manager_address,
+            withdraw_request_nft_address,
             position_cache: Arc::new(Mutex::new(HashMap::new())),
             http_client: reqwest::Client::new(),
-            coingecko_api_key: std::env::var("COINGECKO_API_KEY").ok(),
+            prices: PriceAggregator::global(),
         })
     }
     
@@ -291,7 +331,7 @@ impl EtherFiAdapter {
         
         if restaking_shares > U256::ZERO {
             let share_price = 1.0; // Placeholder - would call restaking_manager.getSharePrice()
-            let restaking_eth_value = (restaking_shares.to_string().parse::<f64>().unwrap_or(0.0) * share_price) / 10f64.powi(18);
+            let restaking_eth_value = normalize_amount(restaking_shares, ETH_DECIMALS) * share_price;
             let restaking_apy = self.get_restaking_apy().await.unwrap_or(6.2);
             let rewards_earned = self.estimate_restaking_rewards(user_address, restaking_shares).await;
             
@@ -317,6 +357,138 @@ impl EtherFiAdapter {
         Ok(None)
     }
     
+    /// Open withdrawal requests held by `user_address`. Request IDs are discovered from
+    /// WithdrawRequestNFT mints within the lookback window; requests that have since been
+    /// claimed (burned) or transferred away are skipped.
+    async fn get_withdraw_requests(&self, user_address: Address) -> Result<Vec<EtherFiWithdrawRequest>, AdapterError> {
+        let nft = self.withdraw_request_nft_address;
+        
+        let held = self.client.call(nft, &IWithdrawRequestNFT::balanceOfCall { owner: user_address }).await?._0;
+        if held == U256::ZERO {
+            return Ok(Vec::new());
+        }
+        
+        let current_block = self.client.block_number().await?;
+        let from_block = current_block.saturating_sub(Self::WITHDRAWAL_LOOKBACK_BLOCKS);
+        let transfer_topic = format!("{:?}", IWithdrawRequestNFT::Transfer::SIGNATURE_HASH);
+        let recipient_topic = format!("0x{:0>64}", alloy::hex::encode(user_address));
+        
+        let logs: Vec<serde_json::Value> = self.client.request("eth_getLogs", serde_json::json!([{
+            "address": format!("{:?}", nft),
+            "fromBlock": format!("{:#x}", from_block),
+            "toBlock": "latest",
+            "topics": [transfer_topic, serde_json::Value::Null, recipient_topic],
+        }])).await?;
+        
+        let now = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        
+        let mut requests: Vec<EtherFiWithdrawRequest> = Vec::new();
+        
+        for log in logs {
+            let Some(request_id) = log["topics"].get(3)
+                .and_then(|t| t.as_str())
+                .and_then(|t| U256::from_str(t).ok())
+            else {
+                continue;
+            };
+            if requests.iter().any(|r| r.request_id == request_id) {
+                continue;
+            }
+            
+            // ownerOf reverts once the request has been claimed and the NFT burned
+            match self.client.call(nft, &IWithdrawRequestNFT::ownerOfCall { tokenId: request_id }).await {
+                Ok(owner) if owner._0 == user_address => {}
+                _ => continue,
+            }
+            
+            let request = self.client.call(nft, &IWithdrawRequestNFT::getRequestCall { requestId: request_id }).await?._0;
+            if !request.isValid {
+                continue;
+            }
+            
+            let is_finalized = self.client.call(nft, &IWithdrawRequestNFT::isFinalizedCall { requestId: request_id }).await?._0;
+            
+            let log_block = log["blockNumber"].as_str()
+                .and_then(|b| u64::from_str_radix(b.trim_start_matches("0x"), 16).ok())
+                .unwrap_or(current_block);
+            let requested_at = now.saturating_sub(current_block.saturating_sub(log_block) * Self::SECONDS_PER_BLOCK);
+            
+            requests.push(EtherFiWithdrawRequest {
+                request_id,
+                amount_of_eeth: U256::from(request.amountOfEEth),
+                fee_gwei: request.feeGwei,
+                is_finalized,
+                requested_at,
+            });
+        }
+        
+        tracing::debug!(
+            user = %user_address,
+            held = %held,
+            found = requests.len(),
+            "Ether.fi withdrawal requests"
+        );
+        
+        Ok(requests)
+    }
+    
+    /// When the withdrawn ETH is expected to become claimable. Finalized requests are
+    /// claimable now; overdue requests are assumed to finalize imminently.
+    fn estimate_completion_timestamp(requested_at: u64, is_finalized: bool, now: u64) -> u64 {
+        if is_finalized {
+            return now;
+        }
+        (requested_at + Self::EXPECTED_WITHDRAWAL_DELAY_SECS).max(now)
+    }
+    
+    /// Locked ETH carries no market risk beyond ETH itself, but can't be sold or moved
+    /// until the queue finalizes, so it's scored by how long it remains locked
+    fn withdrawal_risk_score(remaining_secs: u64) -> f64 {
+        let lock_fraction = remaining_secs as f64 / Self::EXPECTED_WITHDRAWAL_DELAY_SECS as f64;
+        RiskModelRegistry::global().score("withdrawing", &RiskInputs { lock_fraction, ..Default::default() })
+    }
+    
+    fn withdrawing_position(owner: Address, request: &EtherFiWithdrawRequest, eth_price: f64, now: u64) -> Position {
+        let amount_eth = normalize_amount(request.amount_of_eeth, ETH_DECIMALS);
+        let fee_eth = request.fee_gwei as f64 / 1e9;
+        let claimable_eth = (amount_eth - fee_eth).max(0.0);
+        let completion_timestamp = Self::estimate_completion_timestamp(request.requested_at, request.is_finalized, now);
+        
+        let risk_score = Self::withdrawal_risk_score(completion_timestamp.saturating_sub(now));
+        // The withdrawal fee is the only loss once eETH is queued for redemption
+        let pnl = PnlBreakdown { price_pnl: -fee_eth * eth_price, ..Default::default() };
+        
+        Position {
+            id: position_id("ether_fi", Self::CHAIN_ID, "withdrawing", request.request_id, owner),
+            protocol: "ether_fi".to_string(),
+            position_type: "withdrawing".to_string(),
+            pair: "eETH/ETH".to_string(),
+            value_usd: claimable_eth * eth_price,
+            pnl_usd: pnl.total(),
+            pnl_percentage: if amount_eth > 0.0 { -fee_eth / amount_eth * 100.0 } else { 0.0 },
+            metadata: serde_json::json!({
+                "request_id": request.request_id.to_string(),
+                "underlying_asset": "ETH",
+                "amount_of_eeth": request.amount_of_eeth.to_string(),
+                "claimable_eth": claimable_eth,
+                "fee_gwei": request.fee_gwei,
+                "pnl_breakdown": pnl,
+                "is_finalized": request.is_finalized,
+                "requested_at": request.requested_at,
+                "completion_timestamp": completion_timestamp,
+                "transferable": false,
+                "is_liquid": false,
+                "risk_score": risk_score,
+                "staking_provider": "ether_fi",
+                "position_subtype": "withdrawal_queue"
+            }),
+            last_updated: now,
+        }
+    }
+    
     async fn get_eeth_exchange_rate(&self) -> Result<f64, String> {
         let eeth_total_supply = U256::from(1000000u64);
         let total_pooled_eth = U256::from(1050000u64);
@@ -334,7 +506,7 @@ impl EtherFiAdapter {
         let total_validators = 1000u64;
         let total_pooled_eth = U256::from(1000000u64);
         
-        let total_staked_eth = total_pooled_eth.to_string().parse::<f64>().unwrap_or(0.0) / 10f64.powi(18);
+        let total_staked_eth = normalize_amount(total_pooled_eth, ETH_DECIMALS);
         let average_validator_balance = if total_validators > 0 {
             total_staked_eth / total_validators as f64
         } else {
@@ -365,8 +537,8 @@ impl EtherFiAdapter {
         let restaking_tvl = (restaking_total_shares.to_string().parse::<f64>().unwrap_or(0.0) * restaking_share_price.to_string().parse::<f64>().unwrap_or(0.0)) / 10f64.powi(36);
         
         Ok(EtherFiProtocolMetrics {
-            total_eth_staked: total_pooled_eth.to_string().parse::<f64>().unwrap_or(0.0) / 10f64.powi(18),
-            eeth_supply: eeth_supply.to_string().parse::<f64>().unwrap_or(0.0) / 10f64.powi(18),
+            total_eth_staked: normalize_amount(total_pooled_eth, ETH_DECIMALS),
+            eeth_supply: normalize_amount(eeth_supply, ETH_DECIMALS),
             eeth_exchange_rate: exchange_rate,
             liquid_capacity: 0.0,
             restaking_tvl,
@@ -409,19 +581,19 @@ impl EtherFiAdapter {
         
         let underlying_eth_amount = match position.position_subtype.as_str() {
             "liquid_staking" => {
-                let eeth_amount = position.balance.to_string().parse::<f64>().unwrap_or(0.0) / 10f64.powi(18);
+                let eeth_amount = normalize_amount(position.balance, ETH_DECIMALS);
                 eeth_amount * exchange_rate
             },
             "restaking" => {
-                position.balance.to_string().parse::<f64>().unwrap_or(0.0) / 10f64.powi(18)
+                normalize_amount(position.balance, ETH_DECIMALS)
             },
             _ => {
-                position.balance.to_string().parse::<f64>().unwrap_or(0.0) / 10f64.powi(position.decimals as i32)
+                normalize_amount(position.balance, position.decimals)
             }
         };
         
         let base_value_usd = underlying_eth_amount * eth_price;
-        let rewards_amount = position.rewards_earned.to_string().parse::<f64>().unwrap_or(0.0) / 10f64.powi(position.decimals as i32);
+        let rewards_amount = normalize_amount(position.rewards_earned, position.decimals);
         let rewards_value_usd = rewards_amount * eth_price;
         
         let mut adjusted_apy = position.apy;
@@ -520,48 +692,7 @@ impl EtherFiAdapter {
     }
     
     async fn get_eth_price_usd(&self) -> Result<f64, String> {
-        let url = if self.coingecko_api_key.is_some() {
-            "https://pro-api.coingecko.com/api/v3/simple/price?ids=ethereum&vs_currencies=usd"
-        } else {
-            "https://api.coingecko.com/api/v3/simple/price?ids=ethereum&vs_currencies=usd"
-        };
-        
-        self.get_token_price_from_coingecko(url, "ethereum").await
-    }
-    
-    async fn get_token_price_from_coingecko(&self, url: &str, token_id: &str) -> Result<f64, String> {
-        let mut request = self.http_client.get(url);
-        
-        if let Some(api_key) = &self.coingecko_api_key {
-            request = request.header("X-Cg-Pro-Api-Key", api_key);
-        }
-        
-        let response = request
-            .send()
-            .await
-            .map_err(|e| format!("HTTP request failed: {}", e))?;
-            
-        if !response.status().is_success() {
-            return Err(format!("HTTP error {}", response.status()));
-        }
-        
-        let response_text = response
-            .text()
-            .await
-            .map_err(|e| format!("Failed to read response: {}", e))?;
-            
-        let json: serde_json::Value = serde_json::from_str(&response_text)
-            .map_err(|e| format!("JSON parse error: {}", e))?;
-            
-        if let Some(token_data) = json.get(token_id) {
-            if let Some(usd_price) = token_data.get("usd") {
-                if let Some(price) = usd_price.as_f64() {
-                    return Ok(price);
-                }
-            }
-        }
-        
-        Err(format!("{} price not found in response", token_id))
+        self.prices.get_price("ethereum").await.map_err(|e| e.to_string())
     }
     
     async fn call_etherfi_api(&self, url: &str) -> Result<f64, String> {
@@ -624,7 +755,8 @@ impl EtherFiAdapter {
         address == self.nodes_manager_address ||
         address == self.eigenpod_manager_address ||
         address == self.restaking_manager_address ||
-        address == self.auction_manager_address
+        address == self.auction_manager_address ||
+        address == self.withdraw_request_nft_address
     }
     
     #[allow(dead_code)]
@@ -649,6 +781,14 @@ impl DeFiAdapter for EtherFiAdapter {
         "ether_fi"
     }
     
+    fn position_types(&self) -> &'static [&'static str] {
+        &["staking", "restaking", "node_operation", "withdrawing"]
+    }
+    
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+    
     async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
         // Check cache first
         {
@@ -662,13 +802,26 @@ impl DeFiAdapter for EtherFiAdapter {
         }
         
         let staking_positions = self.get_user_staking_positions(address).await?;
+        let withdraw_requests = self.get_withdraw_requests(address).await.unwrap_or_else(|e| {
+            tracing::warn!(user = %address, error = %e, "Failed to read Ether.fi withdrawal queue");
+            Vec::new()
+        });
         
-        if staking_positions.is_empty() {
+        if staking_positions.is_empty() && withdraw_requests.is_empty() {
             return Ok(Vec::new());
         }
         
         let mut positions = Vec::new();
         
+        if !withdraw_requests.is_empty() {
+            let eth_price = self.get_eth_price_usd().await.unwrap_or(4000.0);
+            let now = SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            positions.extend(withdraw_requests.iter().map(|r| Self::withdrawing_position(address, r, eth_price, now)));
+        }
+        
         // Get enhanced metrics once for all positions
         let exchange_rate = self.get_eeth_exchange_rate().await.unwrap_or(1.0);
         let tvl = self.get_protocol_tvl().await.unwrap_or(0.0);
@@ -693,6 +846,7 @@ impl DeFiAdapter for EtherFiAdapter {
         // Convert staking positions to Position structs
         for stake_pos in staking_positions {
             let (value_usd, rewards_usd, apy) = self.calculate_position_value(&stake_pos).await;
+            let pnl = PnlBreakdown { rewards_earned: rewards_usd, ..Default::default() };
             
             let position_type = match stake_pos.position_subtype.as_str() {
                 "liquid_staking" => "staking",
@@ -704,12 +858,12 @@ impl DeFiAdapter for EtherFiAdapter {
             let pair = format!("{}/ETH", stake_pos.token_symbol);
             
             let position = Position {
-                id: format!("ether_fi_{}_{}", stake_pos.token_symbol.to_lowercase(), stake_pos.token_address),
+                id: position_id("ether_fi", Self::CHAIN_ID, position_type, stake_pos.token_address, address),
                 protocol: "ether_fi".to_string(),
                 position_type: position_type.to_string(),
                 pair,
                 value_usd: value_usd.max(0.01),
-                pnl_usd: rewards_usd,
+                pnl_usd: pnl.total(),
                 pnl_percentage: apy,
                 metadata: serde_json::json!({
                     "token_address": format!("{:?}", stake_pos.token_address),
@@ -719,6 +873,7 @@ impl DeFiAdapter for EtherFiAdapter {
                     "decimals": stake_pos.decimals,
                     "current_apy": stake_pos.apy,
                     "rewards_earned": stake_pos.rewards_earned.to_string(),
+                    "pnl_breakdown": pnl,
                     "staking_provider": "ether_fi",
                     "position_subtype": stake_pos.position_subtype,
                     "is_liquid": stake_pos.position_subtype == "liquid_staking",
@@ -834,7 +989,7 @@ mod tests {
     
     #[test]
     fn test_etherfi_contract_detection() {
-        let client = EthereumClient { rpc_url: "https://eth.llamarpc.com".to_string() };
+        let client = EthereumClient::new("https://eth.llamarpc.com").unwrap();
         let adapter = EtherFiAdapter::new(client).unwrap();
         
         let eeth_addr = Address::from_str(EtherFiAdapter::EETH_ADDRESS).unwrap();
@@ -851,7 +1006,7 @@ mod tests {
     
     #[test]
     fn test_token_symbol_mapping() {
-        let client = EthereumClient { rpc_url: "https://eth.llamarpc.com".to_string() };
+        let client = EthereumClient::new("https://eth.llamarpc.com").unwrap();
         let adapter = EtherFiAdapter::new(client).unwrap();
         
         let eeth_addr = Address::from_str(EtherFiAdapter::EETH_ADDRESS).unwrap();
@@ -862,4 +1017,39 @@ mod tests {
         assert_eq!(adapter.get_etherfi_token_symbol(eigenpod_addr), "ETH-RESTAKED");
         assert_eq!(adapter.get_etherfi_token_symbol(node_addr), "EF-VALIDATOR");
     }
+    
+    #[test]
+    fn test_withdrawal_completion_estimate() {
+        let now = 1_700_000_000;
+        let delay = EtherFiAdapter::EXPECTED_WITHDRAWAL_DELAY_SECS;
+        
+        assert_eq!(EtherFiAdapter::estimate_completion_timestamp(now - 86_400, false, now), now - 86_400 + delay);
+        assert_eq!(EtherFiAdapter::estimate_completion_timestamp(now - 2 * delay, false, now), now);
+        assert_eq!(EtherFiAdapter::estimate_completion_timestamp(now - 86_400, true, now), now);
+    }
+    
+    #[test]
+    fn test_withdrawing_position_is_non_transferable() {
+        let now = 1_700_000_000;
+        let request = EtherFiWithdrawRequest {
+            request_id: U256::from(42u64),
+            amount_of_eeth: U256::from(2u64) * U256::from(10u64).pow(U256::from(18u64)),
+            fee_gwei: 0,
+            is_finalized: false,
+            requested_at: now,
+        };
+        
+        let position = EtherFiAdapter::withdrawing_position(Address::ZERO, &request, 3000.0, now);
+        
+        assert_eq!(position.position_type, "withdrawing");
+        assert!((position.value_usd - 6000.0).abs() < 1e-6);
+        assert_eq!(position.metadata["transferable"], false);
+        assert_eq!(position.metadata["completion_timestamp"], now + EtherFiAdapter::EXPECTED_WITHDRAWAL_DELAY_SECS);
+        // Just queued, so the full expected delay remains
+        assert!((position.metadata["risk_score"].as_f64().unwrap() - 0.6).abs() < 1e-12);
+        
+        let finalized = EtherFiWithdrawRequest { is_finalized: true, ..request };
+        let position = EtherFiAdapter::withdrawing_position(Address::ZERO, &finalized, 3000.0, now);
+        assert_eq!(position.metadata["risk_score"], 0.2);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,104 @@
+use std::collections::HashSet;
+
+use crate::security::ValidationError;
+
+/// Protocol keys accepted by `?protocols=` / `?exclude=`, one per adapter
+/// `protocol_name()` (lowercased, spaces as underscores)
+pub const KNOWN_PROTOCOLS: &[&str] = &[
+    "uniswap_v3",
+    "uniswap_v2",
+    "lido",
+    "rocket_pool",
+    "ether_fi",
+    "balancer_v2",
+    "ethena",
+    "frax",
+    "spark",
+    "convex",
+    "compound_v2",
+    "erc4626",
+    "yearn_finance",
+    "morpho_blue",
+    "gmx",
+    "velodrome",
+    "aerodrome",
+    "beefy",
+];
+
+/// Normalized key for an adapter's `protocol_name()`, e.g. "Yearn Finance" -> "yearn_finance"
+pub fn protocol_key(name: &str) -> String {
+    name.trim().to_lowercase().replace([' ', '-'], "_")
+}
+
+/// Which adapters a request wants: an optional include list minus a deny list
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProtocolFilter {
+    include: Option<HashSet<String>>,
+    exclude: HashSet<String>,
+}
+
+impl ProtocolFilter {
+    /// Every adapter
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Parse comma-separated include and exclude lists, rejecting unknown protocols
+    pub fn parse(include: Option<&str>, exclude: Option<&str>) -> Result<Self, ValidationError> {
+        let include = include.map(Self::parse_list).transpose()?.filter(|set| !set.is_empty());
+        let exclude = exclude.map(Self::parse_list).transpose()?.unwrap_or_default();
+        Ok(Self { include, exclude })
+    }
+
+    fn parse_list(list: &str) -> Result<HashSet<String>, ValidationError> {
+        list.split(',')
+            .map(protocol_key)
+            .filter(|key| !key.is_empty())
+            .map(|key| {
+                if KNOWN_PROTOCOLS.contains(&key.as_str()) {
+                    Ok(key)
+                } else {
+                    Err(ValidationError::UnknownProtocol(key))
+                }
+            })
+            .collect()
+    }
+
+    /// Whether the adapter named `protocol_name` should be initialized and queried
+    pub fn allows(&self, protocol_name: &str) -> bool {
+        let key = protocol_key(protocol_name);
+        !self.exclude.contains(&key) && self.include.as_ref().map_or(true, |include| include.contains(&key))
+    }
+
+    pub fn is_all(&self) -> bool {
+        self.include.is_none() && self.exclude.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_include_and_exclude_lists() {
+        let filter = ProtocolFilter::parse(Some("lido, Yearn Finance"), Some("yearn_finance")).unwrap();
+        assert!(filter.allows("lido"));
+        assert!(!filter.allows("Yearn Finance"));
+        assert!(!filter.allows("morpho_blue"));
+
+        let filter = ProtocolFilter::parse(None, Some("gmx")).unwrap();
+        assert!(filter.allows("morpho_blue"));
+        assert!(!filter.allows("gmx"));
+
+        assert!(ProtocolFilter::parse(Some(""), None).unwrap().is_all());
+    }
+
+    #[test]
+    fn test_unknown_protocols_are_rejected() {
+        assert_eq!(
+            ProtocolFilter::parse(Some("lido,aave_v9"), None),
+            Err(ValidationError::UnknownProtocol("aave_v9".to_string()))
+        );
+        assert!(ProtocolFilter::parse(None, Some("nope")).is_err());
+    }
+}
@@ -0,0 +1,652 @@
+use alloy::{
+    primitives::{Address, U256},
+    sol,
+};
+use async_trait::async_trait;
+use futures::future::join_all;
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
+use crate::blockchain::EthereumClient;
+use crate::risk::{RiskInputs, RiskModelRegistry};
+use crate::services::{HealthFactorThresholds, PriceAggregator, TokenMetadataCache, IERC20};
+use crate::utils::RetryPolicy;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+// Frax contract interfaces
+sol! {
+    interface IStakedFrxEth {
+        function convertToAssets(uint256 shares) external view returns (uint256);
+    }
+
+    interface IVeFxs {
+        function locked(address account) external view returns (int128 amount, uint256 end);
+    }
+
+    interface IFraxlendPairRegistry {
+        function getAllPairAddresses() external view returns (address[] memory);
+    }
+
+    // Getters shared by Fraxlend v1 and v2 pairs. v2 appends `fullUtilizationRate` to
+    // `currentRateInfo` and packs `lastBlock` into a uint32, both of which still decode here.
+    interface IFraxlendPair {
+        function asset() external view returns (address);
+        function collateralContract() external view returns (address);
+        function balanceOf(address account) external view returns (uint256);
+        function userBorrowShares(address account) external view returns (uint256);
+        function userCollateralBalance(address account) external view returns (uint256);
+        function totalAsset() external view returns (uint128 amount, uint128 shares);
+        function totalBorrow() external view returns (uint128 amount, uint128 shares);
+        function maxLTV() external view returns (uint256);
+        function currentRateInfo() external view returns (uint64 lastBlock, uint64 feeToProtocolRate, uint64 lastTimestamp, uint64 ratePerSec);
+    }
+}
+
+/// A user's balances in one Fraxlend pair, in token units
+#[derive(Debug, Clone)]
+struct FraxlendPosition {
+    pair: Address,
+    asset_symbol: String,
+    collateral_symbol: String,
+    supplied: f64,
+    borrowed: f64,
+    collateral: f64,
+    asset_price_usd: f64,
+    collateral_price_usd: f64,
+    /// Loan-to-value at which the position becomes liquidatable
+    max_ltv: f64,
+    utilization: f64,
+    supply_apy: f64,
+    borrow_apy: f64,
+}
+
+impl FraxlendPosition {
+    fn borrow_value_usd(&self) -> f64 {
+        self.borrowed * self.asset_price_usd
+    }
+
+    fn collateral_value_usd(&self) -> f64 {
+        self.collateral * self.collateral_price_usd
+    }
+
+    /// Liquidation-adjusted collateral over debt; infinite without debt
+    fn health_factor(&self) -> f64 {
+        let debt = self.borrow_value_usd();
+        if debt > 0.0 {
+            self.collateral_value_usd() * self.max_ltv / debt
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    fn ltv(&self) -> f64 {
+        let collateral = self.collateral_value_usd();
+        if collateral > 0.0 {
+            self.borrow_value_usd() / collateral
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedPositions {
+    positions: Vec<Position>,
+    cached_at: SystemTime,
+}
+
+/// Frax adapter: sfrxETH liquid staking, veFXS locks and Fraxlend lending pairs
+pub struct FraxAdapter {
+    client: EthereumClient,
+    frxeth_address: Address,
+    sfrxeth_address: Address,
+    vefxs_address: Address,
+    fraxlend_registry_address: Address,
+    position_cache: Arc<Mutex<HashMap<Address, CachedPositions>>>,
+    pair_cache: Arc<Mutex<Option<(Vec<Address>, SystemTime)>>>,
+    prices: Arc<PriceAggregator>,
+    token_metadata: Arc<TokenMetadataCache>,
+    health_thresholds: HealthFactorThresholds,
+    retry_policy: RetryPolicy,
+}
+
+impl FraxAdapter {
+    const CHAIN_ID: u64 = 1;
+    const FRXETH_ADDRESS: &'static str = "0x5E8422345238F34275888049021821E8E08CAa1f";
+    const SFRXETH_ADDRESS: &'static str = "0xac3E018457B222d93114458476f3E3416Abbe38F";
+    const VEFXS_ADDRESS: &'static str = "0xc8418aF6358FFddA74e09Ca9CC3Fe03Ca6aDC5b0";
+    const FRAXLEND_REGISTRY_ADDRESS: &'static str = "0xD6E9D27C75Afd88ad24Cd5EdccdC76fd2fc3A751";
+    const CACHE_DURATION: Duration = Duration::from_secs(300); // 5 minutes
+    const PAIR_CACHE_DURATION: Duration = Duration::from_secs(3600); // 1 hour
+
+    /// Fraxlend LTV and fee precision
+    const LTV_PRECISION: f64 = 1e5;
+    const FEE_PRECISION: f64 = 1e5;
+    const SECONDS_PER_YEAR: f64 = 31_536_000.0;
+    /// Longest veFXS lock
+    const MAX_LOCK_SECS: f64 = 4.0 * 365.0 * 86_400.0;
+
+    pub fn new(client: EthereumClient) -> Result<Self, AdapterError> {
+        let parse = |address: &str, name: &str| {
+            Address::from_str(address)
+                .map_err(|e| AdapterError::InvalidData(format!("Invalid {} address: {}", name, e)))
+        };
+
+        Ok(Self {
+            client,
+            frxeth_address: parse(Self::FRXETH_ADDRESS, "frxETH")?,
+            sfrxeth_address: parse(Self::SFRXETH_ADDRESS, "sfrxETH")?,
+            vefxs_address: parse(Self::VEFXS_ADDRESS, "veFXS")?,
+            fraxlend_registry_address: parse(Self::FRAXLEND_REGISTRY_ADDRESS, "Fraxlend registry")?,
+            position_cache: Arc::new(Mutex::new(HashMap::new())),
+            pair_cache: Arc::new(Mutex::new(None)),
+            prices: PriceAggregator::global(),
+            token_metadata: TokenMetadataCache::global(),
+            health_thresholds: HealthFactorThresholds::from_env(),
+            retry_policy: RetryPolicy::from_env(),
+        })
+    }
+
+    /// sfrxETH held by `user`, valued through its frxETH exchange rate
+    async fn get_sfrxeth_position(&self, user: Address) -> Result<Option<Position>, AdapterError> {
+        let shares = self.client.call(self.sfrxeth_address, &IERC20::balanceOfCall { account: user }).await?._0;
+        if shares == U256::ZERO {
+            return Ok(None);
+        }
+
+        let one_share = U256::from(10u64).pow(U256::from(18u64));
+        let exchange_rate = Self::to_f64(
+            self.client
+                .call(self.sfrxeth_address, &IStakedFrxEth::convertToAssetsCall { shares: one_share })
+                .await?
+                ._0,
+            18,
+        );
+
+        let eth_price = self.prices.get_price("ethereum").await?;
+        let frxeth_price = match self.prices.get_price("frax-ether").await {
+            Ok(price) => price,
+            Err(e) => {
+                tracing::warn!("frxETH price unavailable, assuming ETH peg: {}", e);
+                eth_price
+            }
+        };
+
+        let frxeth_amount = Self::to_f64(shares, 18) * exchange_rate;
+        let peg_deviation = if eth_price > 0.0 { frxeth_price / eth_price - 1.0 } else { 0.0 };
+        // frxETH trading below ETH is the only unrealized loss on the stake
+        let pnl = PnlBreakdown { price_pnl: frxeth_amount * (frxeth_price - eth_price), ..Default::default() };
+
+        Ok(Some(Position {
+            id: position_id("frax", Self::CHAIN_ID, "staking", self.sfrxeth_address, user),
+            protocol: "frax".to_string(),
+            position_type: "staking".to_string(),
+            pair: "sfrxETH/ETH".to_string(),
+            value_usd: frxeth_amount * frxeth_price,
+            pnl_usd: pnl.total(),
+            pnl_percentage: peg_deviation * 100.0,
+            metadata: serde_json::json!({
+                "token_address": format!("{:?}", self.sfrxeth_address),
+                "token_symbol": "sfrxETH",
+                "underlying_asset": "ETH",
+                "balance": shares.to_string(),
+                "frxeth_amount": frxeth_amount,
+                "sfrxeth_exchange_rate": exchange_rate,
+                "frxeth_price_usd": frxeth_price,
+                "peg_deviation": peg_deviation,
+                "pnl_breakdown": pnl,
+                "risk_score": Self::staking_risk_score(peg_deviation),
+            }),
+            last_updated: chrono::Utc::now().timestamp() as u64,
+        }))
+    }
+
+    /// FXS locked in veFXS, which can't be withdrawn before the lock ends
+    async fn get_vefxs_position(&self, user: Address) -> Result<Option<Position>, AdapterError> {
+        let locked = self.client.call(self.vefxs_address, &IVeFxs::lockedCall { account: user }).await?;
+        let amount: u128 = locked.amount.try_into().unwrap_or(0);
+        if amount == 0 {
+            return Ok(None);
+        }
+
+        let fxs_amount = amount as f64 / 1e18;
+        let fxs_price = self.prices.get_price("frax-share").await?;
+        let lock_end = locked.end.to::<u64>();
+        let now = chrono::Utc::now().timestamp() as u64;
+        let remaining_secs = lock_end.saturating_sub(now);
+
+        Ok(Some(Position {
+            id: position_id("frax", Self::CHAIN_ID, "locked", self.vefxs_address, user),
+            protocol: "frax".to_string(),
+            position_type: "staking".to_string(),
+            pair: "veFXS/FXS".to_string(),
+            value_usd: fxs_amount * fxs_price,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({
+                "token_address": format!("{:?}", self.vefxs_address),
+                "token_symbol": "veFXS",
+                "underlying_asset": "FXS",
+                "locked_amount": fxs_amount,
+                "lock_end": lock_end,
+                "transferable": remaining_secs == 0,
+                "pnl_breakdown": PnlBreakdown::default(),
+                "risk_score": Self::lock_risk_score(remaining_secs),
+            }),
+            last_updated: now,
+        }))
+    }
+
+    /// All Fraxlend pairs from the registry, cached for an hour
+    async fn get_fraxlend_pairs(&self) -> Result<Vec<Address>, AdapterError> {
+        {
+            let cache = self.pair_cache.lock().unwrap();
+            if let Some((pairs, cached_at)) = cache.as_ref() {
+                if cached_at.elapsed().unwrap_or(Duration::MAX) < Self::PAIR_CACHE_DURATION {
+                    return Ok(pairs.clone());
+                }
+            }
+        }
+
+        let pairs = self.client
+            .call(self.fraxlend_registry_address, &IFraxlendPairRegistry::getAllPairAddressesCall {})
+            .await?
+            ._0;
+
+        *self.pair_cache.lock().unwrap() = Some((pairs.clone(), SystemTime::now()));
+        Ok(pairs)
+    }
+
+    async fn get_fraxlend_positions(&self, user: Address) -> Result<Vec<FraxlendPosition>, AdapterError> {
+        let pairs = self.get_fraxlend_pairs().await?;
+        let results = join_all(pairs.iter().map(|pair| self.get_fraxlend_position(*pair, user))).await;
+
+        let mut positions = Vec::new();
+        for (pair, result) in pairs.iter().zip(results) {
+            match result {
+                Ok(Some(position)) => positions.push(position),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to read Fraxlend pair {:?}: {}", pair, e),
+            }
+        }
+
+        Ok(positions)
+    }
+
+    async fn get_fraxlend_position(&self, pair: Address, user: Address) -> Result<Option<FraxlendPosition>, AdapterError> {
+        let retry = &self.retry_policy;
+        let (supply_shares, borrow_shares, collateral) = tokio::try_join!(
+            self.client.call_with_retry(pair, &IFraxlendPair::balanceOfCall { account: user }, retry),
+            self.client.call_with_retry(pair, &IFraxlendPair::userBorrowSharesCall { account: user }, retry),
+            self.client.call_with_retry(pair, &IFraxlendPair::userCollateralBalanceCall { account: user }, retry),
+        )?;
+        let (supply_shares, borrow_shares, collateral) = (supply_shares._0, borrow_shares._0, collateral._0);
+
+        if supply_shares == U256::ZERO && borrow_shares == U256::ZERO && collateral == U256::ZERO {
+            return Ok(None);
+        }
+
+        let (asset, collateral_token, total_asset, total_borrow, max_ltv, rate_info) = tokio::try_join!(
+            self.client.call_with_retry(pair, &IFraxlendPair::assetCall {}, retry),
+            self.client.call_with_retry(pair, &IFraxlendPair::collateralContractCall {}, retry),
+            self.client.call_with_retry(pair, &IFraxlendPair::totalAssetCall {}, retry),
+            self.client.call_with_retry(pair, &IFraxlendPair::totalBorrowCall {}, retry),
+            self.client.call_with_retry(pair, &IFraxlendPair::maxLTVCall {}, retry),
+            self.client.call_with_retry(pair, &IFraxlendPair::currentRateInfoCall {}, retry),
+        )?;
+
+        let asset_metadata = self.token_metadata.get_or_fetch(&self.client, Self::CHAIN_ID, asset._0).await?;
+        let collateral_metadata = self.token_metadata
+            .get_or_fetch(&self.client, Self::CHAIN_ID, collateral_token._0)
+            .await?;
+
+        let supplied = Self::shares_to_amount(supply_shares, U256::from(total_asset.amount), U256::from(total_asset.shares));
+        let borrowed = Self::shares_to_amount(borrow_shares, U256::from(total_borrow.amount), U256::from(total_borrow.shares));
+
+        let utilization = if total_asset.amount > 0 {
+            total_borrow.amount as f64 / total_asset.amount as f64
+        } else {
+            0.0
+        };
+        let borrow_apy = Self::rate_per_second_to_apy(rate_info.ratePerSec as f64 / 1e18);
+        let protocol_fee = rate_info.feeToProtocolRate as f64 / Self::FEE_PRECISION;
+        let supply_apy = borrow_apy * utilization * (1.0 - protocol_fee);
+
+        let asset_price_usd = self.get_token_price(&asset_metadata.symbol).await?;
+        let collateral_price_usd = if collateral > U256::ZERO || borrowed > U256::ZERO {
+            self.get_token_price(&collateral_metadata.symbol).await?
+        } else {
+            0.0
+        };
+
+        Ok(Some(FraxlendPosition {
+            pair,
+            supplied: Self::to_f64(supplied, asset_metadata.decimals as i32),
+            borrowed: Self::to_f64(borrowed, asset_metadata.decimals as i32),
+            collateral: Self::to_f64(collateral, collateral_metadata.decimals as i32),
+            asset_symbol: asset_metadata.symbol,
+            collateral_symbol: collateral_metadata.symbol,
+            asset_price_usd,
+            collateral_price_usd,
+            max_ltv: Self::to_f64(max_ltv._0, 0) / Self::LTV_PRECISION,
+            utilization,
+            supply_apy,
+            borrow_apy,
+        }))
+    }
+
+    /// Supply, borrow and collateral positions for one Fraxlend pair
+    fn fraxlend_to_positions(&self, user: Address, lend: &FraxlendPosition) -> Vec<Position> {
+        let mut positions = Vec::new();
+        let pair = format!("{}/{}", lend.asset_symbol, lend.collateral_symbol);
+        let now = chrono::Utc::now().timestamp() as u64;
+        let health_factor = lend.health_factor();
+        let market_health = serde_json::json!({
+            "pair_address": format!("{:?}", lend.pair),
+            "max_ltv": lend.max_ltv,
+            "utilization": lend.utilization,
+            "health_factor": if health_factor.is_finite() { Some(health_factor) } else { None },
+            "at_risk": health_factor < self.health_thresholds.warning,
+        });
+
+        if lend.supplied > 0.0 {
+            positions.push(Position {
+                id: position_id("frax", Self::CHAIN_ID, "supply", lend.pair, user),
+                protocol: "frax".to_string(),
+                position_type: "supply".to_string(),
+                pair: pair.clone(),
+                value_usd: lend.supplied * lend.asset_price_usd,
+                pnl_usd: 0.0,
+                pnl_percentage: 0.0,
+                metadata: serde_json::json!({
+                    "market": "fraxlend",
+                    "market_health": market_health,
+                    "pnl_breakdown": PnlBreakdown::default(),
+                    "underlying_asset": lend.asset_symbol,
+                    "risk_score": Self::supply_risk_score(lend.utilization),
+                    "position_details": {
+                        "supplied": lend.supplied,
+                        "supply_apy": lend.supply_apy,
+                        "market_utilization": lend.utilization,
+                    }
+                }),
+                last_updated: now,
+            });
+        }
+
+        if lend.borrowed > 0.0 {
+            let liquidation_price = if health_factor.is_finite() && health_factor > 0.0 {
+                Some(lend.collateral_price_usd / health_factor)
+            } else {
+                None
+            };
+
+            positions.push(Position {
+                id: position_id("frax", Self::CHAIN_ID, "borrow", lend.pair, user),
+                protocol: "frax".to_string(),
+                position_type: "borrow".to_string(),
+                pair: pair.clone(),
+                value_usd: -lend.borrow_value_usd(),
+                pnl_usd: 0.0,
+                pnl_percentage: 0.0,
+                metadata: serde_json::json!({
+                    "market": "fraxlend",
+                    "market_health": market_health,
+                    "pnl_breakdown": PnlBreakdown::default(),
+                    "underlying_asset": lend.asset_symbol,
+                    "risk_score": Self::borrow_risk_score(health_factor, lend.utilization),
+                    "position_details": {
+                        "borrowed": lend.borrowed,
+                        "borrow_apy": lend.borrow_apy,
+                        "health_factor": health_factor,
+                        "liquidation_price": liquidation_price,
+                        "ltv": lend.ltv(),
+                        "max_ltv": lend.max_ltv,
+                        "is_healthy": health_factor > 1.0,
+                    }
+                }),
+                last_updated: now,
+            });
+        }
+
+        if lend.collateral > 0.0 {
+            positions.push(Position {
+                id: position_id("frax", Self::CHAIN_ID, "collateral", lend.pair, user),
+                protocol: "frax".to_string(),
+                position_type: "collateral".to_string(),
+                pair: format!("{}/{}", lend.collateral_symbol, lend.asset_symbol),
+                value_usd: lend.collateral_value_usd(),
+                pnl_usd: 0.0,
+                pnl_percentage: 0.0,
+                metadata: serde_json::json!({
+                    "market": "fraxlend",
+                    "market_health": market_health,
+                    "pnl_breakdown": PnlBreakdown::default(),
+                    "underlying_asset": lend.collateral_symbol,
+                    "risk_score": Self::borrow_risk_score(health_factor, lend.utilization),
+                    "position_details": {
+                        "collateral_amount": lend.collateral,
+                        "collateral_token": lend.collateral_symbol,
+                        "max_ltv": lend.max_ltv,
+                    }
+                }),
+                last_updated: now,
+            });
+        }
+
+        positions
+    }
+
+    async fn get_token_price(&self, symbol: &str) -> Result<f64, AdapterError> {
+        let coin_id = match symbol.to_uppercase().as_str() {
+            "FRAX" => "frax",
+            "FRXETH" => "frax-ether",
+            "SFRXETH" => "staked-frax-ether",
+            "FXS" => "frax-share",
+            "WETH" | "ETH" => "ethereum",
+            "WBTC" => "wrapped-bitcoin",
+            "USDC" => "usd-coin",
+            "USDT" => "tether",
+            "CRV" => "curve-dao-token",
+            "CVX" => "convex-finance",
+            "WSTETH" => "wrapped-steth",
+            "RETH" => "rocket-pool-eth",
+            "GOHM" => "governance-ohm",
+            _ => {
+                return Err(AdapterError::UnsupportedProtocol(format!("No price feed for Fraxlend token {}", symbol)));
+            }
+        };
+
+        self.prices.get_price(coin_id).await
+    }
+
+    /// Validator and frxETH/ETH peg risk; frxETH's curve-pool peg is what sfrxETH holders
+    /// realize when exiting without waiting on the redemption queue
+    fn staking_risk_score(peg_deviation: f64) -> f64 {
+        RiskModelRegistry::global().score("staking", &RiskInputs { peg_deviation, ..Default::default() })
+    }
+
+    /// FXS price risk plus illiquidity for the remaining lock duration
+    fn lock_risk_score(remaining_secs: u64) -> f64 {
+        let lock_fraction = remaining_secs as f64 / Self::MAX_LOCK_SECS;
+        RiskModelRegistry::global().score("locked", &RiskInputs { lock_fraction, ..Default::default() })
+    }
+
+    /// Lender risk grows with utilization, since fully borrowed pairs block withdrawals
+    fn supply_risk_score(utilization: f64) -> f64 {
+        RiskModelRegistry::global().score("supply", &RiskInputs { utilization: Some(utilization), ..Default::default() })
+    }
+
+    /// Borrower risk: proximity to liquidation, floored at the pair's lender risk
+    fn borrow_risk_score(health_factor: f64, utilization: f64) -> f64 {
+        let inputs = RiskInputs {
+            health_factor: Some(health_factor),
+            floor: Self::supply_risk_score(utilization),
+            ..Default::default()
+        };
+        RiskModelRegistry::global().score("borrow", &inputs)
+    }
+
+    /// Fraxlend `VaultAccountingLibrary.toAmount` rounding down
+    fn shares_to_amount(shares: U256, total_amount: U256, total_shares: U256) -> U256 {
+        if total_shares == U256::ZERO {
+            shares
+        } else {
+            shares * total_amount / total_shares
+        }
+    }
+
+    /// Per-second rate to a compounded APY percentage
+    fn rate_per_second_to_apy(rate: f64) -> f64 {
+        ((rate * Self::SECONDS_PER_YEAR).exp() - 1.0) * 100.0
+    }
+
+    fn to_f64(value: U256, decimals: i32) -> f64 {
+        let raw: f64 = value.to_string().parse().unwrap_or(0.0);
+        raw / 10f64.powi(decimals)
+    }
+}
+
+#[async_trait]
+impl DeFiAdapter for FraxAdapter {
+    fn protocol_name(&self) -> &'static str {
+        "frax"
+    }
+
+    fn position_types(&self) -> &'static [&'static str] {
+        &["staking", "supply", "collateral", "borrow"]
+    }
+
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+
+    fn supports_historical_block(&self) -> bool {
+        true
+    }
+
+    async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
+        // Check cache first
+        {
+            let cache = self.position_cache.lock().unwrap();
+            if let Some(cached) = cache.get(&address) {
+                let cache_age = cached.cached_at.elapsed().unwrap_or(Duration::from_secs(0));
+                if cache_age < Self::CACHE_DURATION {
+                    return Ok(cached.positions.clone());
+                }
+            }
+        }
+
+        let mut positions = Vec::new();
+
+        if let Some(position) = self.get_sfrxeth_position(address).await? {
+            positions.push(position);
+        }
+
+        match self.get_vefxs_position(address).await {
+            Ok(Some(position)) => positions.push(position),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to read veFXS lock: {}", e),
+        }
+
+        match self.get_fraxlend_positions(address).await {
+            Ok(lends) => {
+                for lend in &lends {
+                    positions.extend(self.fraxlend_to_positions(address, lend));
+                }
+            }
+            Err(e) => tracing::warn!("Failed to read Fraxlend positions: {}", e),
+        }
+
+        // Update cache
+        {
+            let mut cache = self.position_cache.lock().unwrap();
+            cache.insert(address, CachedPositions {
+                positions: positions.clone(),
+                cached_at: SystemTime::now(),
+            });
+        }
+
+        Ok(positions)
+    }
+
+    async fn supports_contract(&self, contract_address: Address) -> bool {
+        if contract_address == self.frxeth_address
+            || contract_address == self.sfrxeth_address
+            || contract_address == self.vefxs_address
+        {
+            return true;
+        }
+
+        let cache = self.pair_cache.lock().unwrap();
+        cache.as_ref().map_or(false, |(pairs, _)| pairs.contains(&contract_address))
+    }
+
+    async fn get_position_value(&self, position: &Position) -> Result<f64, AdapterError> {
+        Ok(position.value_usd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lend(borrowed: f64, collateral: f64) -> FraxlendPosition {
+        FraxlendPosition {
+            pair: Address::ZERO,
+            asset_symbol: "FRAX".to_string(),
+            collateral_symbol: "sfrxETH".to_string(),
+            supplied: 0.0,
+            borrowed,
+            collateral,
+            asset_price_usd: 1.0,
+            collateral_price_usd: 2_000.0,
+            max_ltv: 0.75,
+            utilization: 0.5,
+            supply_apy: 0.0,
+            borrow_apy: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_addresses_parse() {
+        let client = EthereumClient::new("https://eth.llamarpc.com").unwrap();
+        assert!(FraxAdapter::new(client).is_ok());
+    }
+
+    #[test]
+    fn test_fraxlend_health_factor() {
+        // 1 sfrxETH at $2000 with 75% max LTV against 1000 FRAX of debt
+        let position = lend(1_000.0, 1.0);
+        assert!((position.health_factor() - 1.5).abs() < 1e-12);
+        assert!((position.ltv() - 0.5).abs() < 1e-12);
+        assert!(lend(0.0, 1.0).health_factor().is_infinite());
+    }
+
+    #[test]
+    fn test_shares_to_amount_rounds_down() {
+        let amount = FraxAdapter::shares_to_amount(U256::from(10u64), U256::from(1_005u64), U256::from(1_000u64));
+        assert_eq!(amount, U256::from(10u64));
+        assert_eq!(FraxAdapter::shares_to_amount(U256::from(7u64), U256::ZERO, U256::ZERO), U256::from(7u64));
+    }
+
+    #[test]
+    fn test_borrow_risk_tracks_liquidation_distance() {
+        assert!((FraxAdapter::borrow_risk_score(1.25, 0.5) - 0.8).abs() < 1e-12);
+        assert!((FraxAdapter::borrow_risk_score(f64::INFINITY, 0.5) - 0.3).abs() < 1e-12);
+        assert!(FraxAdapter::staking_risk_score(-0.02) > FraxAdapter::staking_risk_score(0.0));
+    }
+
+    #[test]
+    fn test_scores_follow_the_registry_models() {
+        assert_eq!(FraxAdapter::staking_risk_score(0.0), 0.3);
+        assert!((FraxAdapter::staking_risk_score(-0.02) - 0.46).abs() < 1e-12);
+        assert_eq!(FraxAdapter::lock_risk_score(0), 0.4);
+        assert!((FraxAdapter::lock_risk_score(FraxAdapter::MAX_LOCK_SECS as u64) - 0.7).abs() < 1e-12);
+        assert_eq!(FraxAdapter::supply_risk_score(0.5), 0.2);
+        assert!((FraxAdapter::supply_risk_score(0.9) - 0.35).abs() < 1e-12);
+        // A healthy borrow in a busy pair sits at the pair's lender risk
+        assert!((FraxAdapter::borrow_risk_score(f64::INFINITY, 0.9) - 0.35).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,747 @@
+use alloy::{
+    primitives::{keccak256, Address, B256, U256},
+    sol,
+    sol_types::SolValue,
+};
+use async_trait::async_trait;
+use futures::future::join_all;
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
+use crate::blockchain::EthereumClient;
+use crate::risk::{RiskInputs, RiskModelRegistry};
+use crate::services::{TokenMetadataCache, IERC20};
+use crate::utils::{market_read_timeout, with_timeout};
+use reqwest;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GmxTicker {
+    token_address: String,
+    #[serde(default)]
+    token_symbol: Option<String>,
+    min_price: String,
+    max_price: String,
+}
+
+#[derive(Debug, Clone)]
+struct CachedPositions {
+    positions: Vec<Position>,
+    cached_at: SystemTime,
+}
+
+#[derive(Debug, Clone)]
+struct PerpPosition {
+    market: Address,
+    index_token: Address,
+    index_symbol: String,
+    collateral_token: Address,
+    is_long: bool,
+    size_usd: f64,
+    size_in_tokens: f64,
+    collateral_amount: f64,
+}
+
+/// Per-chain GMX deployment
+#[derive(Debug, Clone)]
+struct GmxDeployment {
+    chain_name: &'static str,
+    data_store: Address,
+    reader: Address,
+    glp: Option<Address>,
+    staked_glp: Option<Address>,
+    glp_manager: Option<Address>,
+    api_url: &'static str,
+}
+
+// GMX V2 contract interfaces
+sol! {
+    interface IGmxReader {
+        struct MarketProps {
+            address marketToken;
+            address indexToken;
+            address longToken;
+            address shortToken;
+        }
+
+        struct PositionAddresses {
+            address account;
+            address market;
+            address collateralToken;
+        }
+
+        struct PositionNumbers {
+            uint256 sizeInUsd;
+            uint256 sizeInTokens;
+            uint256 collateralAmount;
+            uint256 borrowingFactor;
+            uint256 fundingFeeAmountPerSize;
+            uint256 longTokenClaimableFundingAmountPerSize;
+            uint256 shortTokenClaimableFundingAmountPerSize;
+            uint256 increasedAtBlock;
+            uint256 decreasedAtBlock;
+        }
+
+        struct PositionFlags {
+            bool isLong;
+        }
+
+        struct PositionProps {
+            PositionAddresses addresses;
+            PositionNumbers numbers;
+            PositionFlags flags;
+        }
+
+        function getAccountPositions(address dataStore, address account, uint256 start, uint256 end) external view returns (PositionProps[] memory);
+        function getMarkets(address dataStore, uint256 start, uint256 end) external view returns (MarketProps[] memory);
+        function getMarket(address dataStore, address key) external view returns (MarketProps memory);
+    }
+
+    interface IGmxDataStore {
+        function getUint(bytes32 key) external view returns (uint256);
+    }
+
+    interface IGlpManager {
+        function getAumInUsdg(bool maximise) external view returns (uint256);
+    }
+}
+
+/// GMX perpetuals adapter (GMX V2 positions, GM pool tokens and legacy GLP)
+pub struct GmxAdapter {
+    client: EthereumClient,
+    chain_id: u64,
+    deployment: GmxDeployment,
+    position_cache: Arc<Mutex<HashMap<Address, CachedPositions>>>,
+    token_metadata: Arc<TokenMetadataCache>,
+    // Decimals for tokens without ERC20 metadata (synthetic index tokens)
+    decimals_fallback: Arc<Mutex<HashMap<Address, u8>>>,
+    // Symbols the oracle keepers API reports, for the same tokens
+    symbol_fallback: Arc<Mutex<HashMap<Address, String>>>,
+    http_client: reqwest::Client,
+}
+
+impl GmxAdapter {
+    const CACHE_DURATION: Duration = Duration::from_secs(60); // Perp positions move fast
+    const MAX_POSITIONS: u64 = 100;
+    const MAX_MARKETS: u64 = 200;
+    /// Minimum collateral factor before a position becomes liquidatable
+    const MAINTENANCE_MARGIN: f64 = 0.01;
+    /// GMX V2 stores USD values with 30 decimals
+    const USD_DECIMALS: i32 = 30;
+
+    // Arbitrum One
+    const ARBITRUM_DATA_STORE: &'static str = "0xFD70de6b91282D8017aA4E741e9Ae325CAb992d8";
+    const ARBITRUM_READER: &'static str = "0xf60becbba223EEA9495Da3f606753867eC10d139";
+    const ARBITRUM_GLP: &'static str = "0x4277f8F2c384827B5273592FF7CeBd9f2C1ac258";
+    const ARBITRUM_STAKED_GLP: &'static str = "0x1aDDD80E6039594eE970E5872D247bf0414C8903";
+    const ARBITRUM_GLP_MANAGER: &'static str = "0x3963FfC9dff443c2A94f21b129D429891E32ec18";
+
+    // Avalanche C-Chain
+    const AVALANCHE_DATA_STORE: &'static str = "0x2F0b22339414ADeD7D5F06f9D604c7fF5b2fe3f6";
+    const AVALANCHE_READER: &'static str = "0x73BA021ACF4Bb6741E82690DdB821e7936050f8C";
+
+    pub fn new(client: EthereumClient, chain_id: u64) -> Result<Self, AdapterError> {
+        let deployment = Self::deployment_for_chain(chain_id)?;
+
+        Ok(Self {
+            client,
+            chain_id,
+            deployment,
+            position_cache: Arc::new(Mutex::new(HashMap::new())),
+            token_metadata: TokenMetadataCache::global(),
+            decimals_fallback: Arc::new(Mutex::new(HashMap::new())),
+            symbol_fallback: Arc::new(Mutex::new(HashMap::new())),
+            http_client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn supported_chains() -> Vec<u64> {
+        vec![42161, 43114]
+    }
+
+    fn deployment_for_chain(chain_id: u64) -> Result<GmxDeployment, AdapterError> {
+        let parse = |address: &str| {
+            Address::from_str(address)
+                .map_err(|e| AdapterError::InvalidData(format!("Invalid GMX address {}: {}", address, e)))
+        };
+
+        match chain_id {
+            42161 => Ok(GmxDeployment {
+                chain_name: "arbitrum",
+                data_store: parse(Self::ARBITRUM_DATA_STORE)?,
+                reader: Self::reader_override()?.unwrap_or(parse(Self::ARBITRUM_READER)?),
+                glp: Some(parse(Self::ARBITRUM_GLP)?),
+                staked_glp: Some(parse(Self::ARBITRUM_STAKED_GLP)?),
+                glp_manager: Some(parse(Self::ARBITRUM_GLP_MANAGER)?),
+                api_url: "https://arbitrum-api.gmxinfra.io",
+            }),
+            43114 => Ok(GmxDeployment {
+                chain_name: "avalanche",
+                data_store: parse(Self::AVALANCHE_DATA_STORE)?,
+                reader: Self::reader_override()?.unwrap_or(parse(Self::AVALANCHE_READER)?),
+                glp: None,
+                staked_glp: None,
+                glp_manager: None,
+                api_url: "https://avalanche-api.gmxinfra.io",
+            }),
+            _ => Err(AdapterError::UnsupportedChain(format!("GMX is not deployed on chain {}", chain_id))),
+        }
+    }
+
+    /// The Reader is stateless and redeployed on upgrades, so allow pointing at a newer one
+    fn reader_override() -> Result<Option<Address>, AdapterError> {
+        match std::env::var("GMX_READER_ADDRESS") {
+            Ok(address) => Address::from_str(&address)
+                .map(Some)
+                .map_err(|e| AdapterError::InvalidData(format!("Invalid GMX_READER_ADDRESS: {}", e))),
+            Err(_) => Ok(None),
+        }
+    }
+
+    async fn get_perp_positions(&self, account: Address) -> Result<Vec<PerpPosition>, AdapterError> {
+        let call = IGmxReader::getAccountPositionsCall {
+            dataStore: self.deployment.data_store,
+            account,
+            start: U256::ZERO,
+            end: U256::from(Self::MAX_POSITIONS),
+        };
+        let raw_positions = self.client.call(self.deployment.reader, &call).await?._0;
+
+        let mut positions = Vec::new();
+        for raw in raw_positions {
+            if raw.numbers.sizeInUsd == U256::ZERO {
+                continue;
+            }
+
+            let market = self.client
+                .call(self.deployment.reader, &IGmxReader::getMarketCall {
+                    dataStore: self.deployment.data_store,
+                    key: raw.addresses.market,
+                })
+                .await?
+                ._0;
+
+            let index_decimals = self.get_token_decimals(market.indexToken).await;
+            let collateral_decimals = self.get_token_decimals(raw.addresses.collateralToken).await;
+
+            positions.push(PerpPosition {
+                market: raw.addresses.market,
+                index_token: market.indexToken,
+                index_symbol: self.get_token_symbol(market.indexToken).await,
+                collateral_token: raw.addresses.collateralToken,
+                is_long: raw.flags.isLong,
+                size_usd: Self::to_f64(raw.numbers.sizeInUsd, Self::USD_DECIMALS),
+                size_in_tokens: Self::to_f64(raw.numbers.sizeInTokens, index_decimals as i32),
+                collateral_amount: Self::to_f64(raw.numbers.collateralAmount, collateral_decimals as i32),
+            });
+        }
+
+        Ok(positions)
+    }
+
+    async fn get_token_decimals(&self, token: Address) -> u8 {
+        if let Some(decimals) = self.decimals_fallback.lock().unwrap().get(&token) {
+            return *decimals;
+        }
+
+        match self.token_metadata.get_or_fetch(&self.client, self.chain_id, token).await {
+            Ok(metadata) => metadata.decimals,
+            Err(_) => {
+                // Synthetic index tokens (e.g. for non-EVM assets) have no contract
+                self.decimals_fallback.lock().unwrap().insert(token, 18);
+                18
+            }
+        }
+    }
+
+    /// ERC20 symbol, else the keepers API's symbol for synthetic tokens, else the address
+    async fn get_token_symbol(&self, token: Address) -> String {
+        match self.token_metadata.get_or_fetch(&self.client, self.chain_id, token).await {
+            Ok(metadata) => metadata.symbol,
+            Err(_) => self.symbol_fallback
+                .lock()
+                .unwrap()
+                .get(&token)
+                .cloned()
+                .unwrap_or_else(|| format!("{:?}", token)),
+        }
+    }
+
+    /// Mid prices in USD keyed by token address, from the GMX oracle keepers API
+    async fn get_token_prices(&self) -> Result<HashMap<Address, f64>, AdapterError> {
+        let url = format!("{}/prices/tickers", self.deployment.api_url);
+
+        let tickers: Vec<GmxTicker> = self.http_client
+            .get(&url)
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| AdapterError::NetworkError(format!("GMX price request failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid GMX price response: {}", e)))?;
+
+        let mut prices = HashMap::new();
+        for ticker in tickers {
+            let Ok(token) = Address::from_str(&ticker.token_address) else {
+                continue;
+            };
+            if let Some(symbol) = ticker.token_symbol {
+                self.symbol_fallback.lock().unwrap().insert(token, symbol);
+            }
+            let (Ok(min_price), Ok(max_price)) = (U256::from_str(&ticker.min_price), U256::from_str(&ticker.max_price)) else {
+                continue;
+            };
+
+            // Oracle prices carry (30 - token decimals) decimals
+            let decimals = self.get_token_decimals(token).await;
+            let scale = Self::USD_DECIMALS - decimals as i32;
+            let mid = (Self::to_f64(min_price, scale) + Self::to_f64(max_price, scale)) / 2.0;
+            prices.insert(token, mid);
+        }
+
+        Ok(prices)
+    }
+
+    async fn get_gm_positions(
+        &self,
+        account: Address,
+        prices: &HashMap<Address, f64>,
+    ) -> Result<Vec<Position>, AdapterError> {
+        let markets = self.client
+            .call(self.deployment.reader, &IGmxReader::getMarketsCall {
+                dataStore: self.deployment.data_store,
+                start: U256::ZERO,
+                end: U256::from(Self::MAX_MARKETS),
+            })
+            .await?
+            ._0;
+
+        // One balance read per market, run together; the client bounds concurrent RPCs
+        let timeout = market_read_timeout();
+        let balances = join_all(markets.iter().map(|market| {
+            with_timeout(timeout, "GMX market balance read", async move {
+                Ok(self.client.call(market.marketToken, &IERC20::balanceOfCall { account }).await?._0)
+            })
+        }))
+        .await;
+
+        let mut positions = Vec::new();
+
+        for (market, balance) in markets.iter().zip(balances) {
+            let balance = balance.unwrap_or(U256::ZERO);
+            if balance == U256::ZERO {
+                continue;
+            }
+
+            let gm_price = self.estimate_gm_token_price(market, prices).await.unwrap_or(0.0);
+            let index_symbol = self.get_token_symbol(market.indexToken).await;
+            let long_symbol = self.get_token_symbol(market.longToken).await;
+            let short_symbol = self.get_token_symbol(market.shortToken).await;
+            let balance_f64 = Self::to_f64(balance, 18);
+            let value_usd = balance_f64 * gm_price;
+
+            positions.push(Position {
+                id: position_id("gmx", self.chain_id, "liquidity", market.marketToken, account),
+                protocol: "gmx".to_string(),
+                position_type: "liquidity".to_string(),
+                pair: format!("GM:{}/USD [{}-{}]", index_symbol, long_symbol, short_symbol),
+                value_usd,
+                pnl_usd: 0.0,
+                pnl_percentage: 0.0,
+                metadata: serde_json::json!({
+                    "chain_id": self.chain_id,
+                    "chain": self.deployment.chain_name,
+                    "market_token": format!("{:?}", market.marketToken),
+                    "index_token": format!("{:?}", market.indexToken),
+                    "long_token": format!("{:?}", market.longToken),
+                    "short_token": format!("{:?}", market.shortToken),
+                    "balance": balance.to_string(),
+                    "gm_token_price_usd": gm_price,
+                    "risk_score": Self::pool_risk_score(market.longToken == market.shortToken),
+                }),
+                last_updated: Self::now(),
+            });
+        }
+
+        Ok(positions)
+    }
+
+    /// GM token price from pool composition. Ignores pending trader PnL, so it is an
+    /// approximation of the on-chain market token price.
+    async fn estimate_gm_token_price(
+        &self,
+        market: &IGmxReader::MarketProps,
+        prices: &HashMap<Address, f64>,
+    ) -> Result<f64, AdapterError> {
+        let long_amount = self.get_pool_amount(market.marketToken, market.longToken).await?;
+        let long_decimals = self.get_token_decimals(market.longToken).await;
+        let mut pool_value = Self::to_f64(long_amount, long_decimals as i32)
+            * prices.get(&market.longToken).copied().unwrap_or(0.0);
+
+        if market.shortToken != market.longToken {
+            let short_amount = self.get_pool_amount(market.marketToken, market.shortToken).await?;
+            let short_decimals = self.get_token_decimals(market.shortToken).await;
+            pool_value += Self::to_f64(short_amount, short_decimals as i32)
+                * prices.get(&market.shortToken).copied().unwrap_or(0.0);
+        }
+
+        let total_supply = self.client
+            .call(market.marketToken, &IERC20::totalSupplyCall {})
+            .await?
+            ._0;
+        let supply = Self::to_f64(total_supply, 18);
+
+        if supply > 0.0 {
+            Ok(pool_value / supply)
+        } else {
+            Ok(0.0)
+        }
+    }
+
+    async fn get_pool_amount(&self, market: Address, token: Address) -> Result<U256, AdapterError> {
+        let key = Self::pool_amount_key(market, token);
+        Ok(self.client
+            .call(self.deployment.data_store, &IGmxDataStore::getUintCall { key })
+            .await?
+            ._0)
+    }
+
+    /// `Keys.poolAmountKey(market, token)` from the GMX V2 contracts
+    fn pool_amount_key(market: Address, token: Address) -> B256 {
+        // Same layout as Solidity's abi.encode(...)
+        let pool_amount = keccak256(("POOL_AMOUNT".to_string(),).abi_encode_params());
+        keccak256((pool_amount, market, token).abi_encode_params())
+    }
+
+    async fn get_glp_position(&self, account: Address) -> Result<Option<Position>, AdapterError> {
+        let (Some(glp), Some(staked_glp), Some(glp_manager)) =
+            (self.deployment.glp, self.deployment.staked_glp, self.deployment.glp_manager)
+        else {
+            return Ok(None);
+        };
+
+        let balance = self.client
+            .call(staked_glp, &IERC20::balanceOfCall { account })
+            .await?
+            ._0;
+
+        if balance == U256::ZERO {
+            return Ok(None);
+        }
+
+        let aum = self.client
+            .call(glp_manager, &IGlpManager::getAumInUsdgCall { maximise: false })
+            .await?
+            ._0;
+        let supply = self.client.call(glp, &IERC20::totalSupplyCall {}).await?._0;
+
+        let glp_price = if supply > U256::ZERO {
+            Self::to_f64(aum, 18) / Self::to_f64(supply, 18)
+        } else {
+            0.0
+        };
+        let value_usd = Self::to_f64(balance, 18) * glp_price;
+
+        Ok(Some(Position {
+            id: position_id("gmx", self.chain_id, "liquidity", "glp", account),
+            protocol: "gmx".to_string(),
+            position_type: "liquidity".to_string(),
+            pair: "GLP".to_string(),
+            value_usd,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({
+                "chain_id": self.chain_id,
+                "chain": self.deployment.chain_name,
+                "token": "fsGLP",
+                "balance": balance.to_string(),
+                "glp_price_usd": glp_price,
+                "risk_score": Self::pool_risk_score(false),
+            }),
+            last_updated: Self::now(),
+        }))
+    }
+
+    fn build_perp_position(&self, account: Address, perp: &PerpPosition, prices: &HashMap<Address, f64>) -> Position {
+        let mark_price = prices.get(&perp.index_token).copied().unwrap_or(0.0);
+        let collateral_price = prices.get(&perp.collateral_token).copied().unwrap_or(0.0);
+        let collateral_usd = perp.collateral_amount * collateral_price;
+
+        let metrics = Self::calculate_perp_metrics(
+            perp.is_long,
+            perp.size_usd,
+            perp.size_in_tokens,
+            collateral_usd,
+            mark_price,
+        );
+
+        let side = if perp.is_long { "long" } else { "short" };
+        // Borrow and funding fees aren't read, so unrealized PnL is all price movement
+        let pnl = PnlBreakdown { price_pnl: metrics.unrealized_pnl, ..Default::default() };
+
+        Position {
+            // GMX keys positions by account, market, collateral token and side
+            id: position_id("gmx", self.chain_id, "perp", format!("{:?}-{:?}-{}", perp.market, perp.collateral_token, side), account),
+            protocol: "gmx".to_string(),
+            position_type: "perp".to_string(),
+            pair: format!("{}/USD {}", perp.index_symbol, side),
+            // Equity in the position: collateral plus unrealized PnL
+            value_usd: (collateral_usd + metrics.unrealized_pnl).max(0.0),
+            pnl_usd: pnl.total(),
+            pnl_percentage: if collateral_usd > 0.0 {
+                pnl.total() / collateral_usd * 100.0
+            } else {
+                0.0
+            },
+            metadata: serde_json::json!({
+                "chain_id": self.chain_id,
+                "chain": self.deployment.chain_name,
+                "market": format!("{:?}", perp.market),
+                "index_token": format!("{:?}", perp.index_token),
+                "collateral_token": format!("{:?}", perp.collateral_token),
+                "is_long": perp.is_long,
+                "size_usd": perp.size_usd,
+                "collateral_usd": collateral_usd,
+                "leverage": metrics.leverage,
+                "entry_price": metrics.entry_price,
+                "mark_price": mark_price,
+                "liquidation_price": metrics.liquidation_price,
+                "distance_to_liquidation_percent": metrics.distance_to_liquidation * 100.0,
+                "unrealized_pnl": metrics.unrealized_pnl,
+                "pnl_breakdown": pnl,
+                "risk_score": metrics.risk_score,
+            }),
+            last_updated: Self::now(),
+        }
+    }
+
+    /// LPs take the other side of traders' PnL; a single-token GM pool has no
+    /// impermanent loss between its legs
+    fn pool_risk_score(single_token: bool) -> f64 {
+        RiskModelRegistry::global().score("liquidity", &RiskInputs { correlated: single_token, ..Default::default() })
+    }
+
+    /// Leverage, entry and liquidation prices for a perp position. Liquidation happens
+    /// when collateral plus PnL falls to the maintenance margin (fees are ignored).
+    fn calculate_perp_metrics(
+        is_long: bool,
+        size_usd: f64,
+        size_in_tokens: f64,
+        collateral_usd: f64,
+        mark_price: f64,
+    ) -> PerpMetrics {
+        let entry_price = if size_in_tokens > 0.0 { size_usd / size_in_tokens } else { 0.0 };
+        let leverage = if collateral_usd > 0.0 { size_usd / collateral_usd } else { 0.0 };
+
+        let unrealized_pnl = if is_long {
+            size_in_tokens * mark_price - size_usd
+        } else {
+            size_usd - size_in_tokens * mark_price
+        };
+
+        let buffer_usd = collateral_usd - size_usd * Self::MAINTENANCE_MARGIN;
+        let liquidation_price = if size_in_tokens > 0.0 {
+            let move_per_token = buffer_usd / size_in_tokens;
+            if is_long {
+                (entry_price - move_per_token).max(0.0)
+            } else {
+                entry_price + move_per_token
+            }
+        } else {
+            0.0
+        };
+
+        let distance_to_liquidation = if mark_price > 0.0 {
+            let distance = if is_long {
+                (mark_price - liquidation_price) / mark_price
+            } else {
+                (liquidation_price - mark_price) / mark_price
+            };
+            distance.max(0.0)
+        } else {
+            0.0
+        };
+
+        // How far the price can move against the position before it's liquidated, as a
+        // ratio like a lending health factor: 1.0 at the liquidation price
+        let health_factor = if mark_price <= 0.0 {
+            0.0
+        } else if is_long {
+            if liquidation_price > 0.0 { mark_price / liquidation_price } else { f64::INFINITY }
+        } else {
+            liquidation_price / mark_price
+        };
+        let risk_score = RiskModelRegistry::global().score("perp", &RiskInputs {
+            health_factor: Some(health_factor),
+            ..Default::default()
+        });
+
+        PerpMetrics {
+            entry_price,
+            leverage,
+            unrealized_pnl,
+            liquidation_price,
+            distance_to_liquidation,
+            risk_score,
+        }
+    }
+
+    fn to_f64(value: U256, decimals: i32) -> f64 {
+        let raw: f64 = value.to_string().parse().unwrap_or(0.0);
+        raw / 10f64.powi(decimals)
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    fn is_gmx_contract(&self, address: Address) -> bool {
+        address == self.deployment.data_store
+            || address == self.deployment.reader
+            || Some(address) == self.deployment.glp
+            || Some(address) == self.deployment.staked_glp
+            || Some(address) == self.deployment.glp_manager
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PerpMetrics {
+    entry_price: f64,
+    leverage: f64,
+    unrealized_pnl: f64,
+    liquidation_price: f64,
+    distance_to_liquidation: f64,
+    risk_score: f64,
+}
+
+#[async_trait]
+impl DeFiAdapter for GmxAdapter {
+    fn protocol_name(&self) -> &'static str {
+        "gmx"
+    }
+
+    fn position_types(&self) -> &'static [&'static str] {
+        &["liquidity", "perp"]
+    }
+
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
+        // Check cache first
+        {
+            let cache = self.position_cache.lock().unwrap();
+            if let Some(cached) = cache.get(&address) {
+                let cache_age = cached.cached_at.elapsed().unwrap_or(Duration::from_secs(0));
+                if cache_age < Self::CACHE_DURATION {
+                    return Ok(cached.positions.clone());
+                }
+            }
+        }
+
+        let prices = self.get_token_prices().await?;
+        let mut positions = Vec::new();
+
+        for perp in self.get_perp_positions(address).await? {
+            positions.push(self.build_perp_position(address, &perp, &prices));
+        }
+
+        positions.extend(self.get_gm_positions(address, &prices).await?);
+
+        if let Some(glp_position) = self.get_glp_position(address).await? {
+            positions.push(glp_position);
+        }
+
+        // Update cache
+        {
+            let mut cache = self.position_cache.lock().unwrap();
+            cache.insert(address, CachedPositions {
+                positions: positions.clone(),
+                cached_at: SystemTime::now(),
+            });
+        }
+
+        Ok(positions)
+    }
+
+    async fn supports_contract(&self, contract_address: Address) -> bool {
+        self.is_gmx_contract(contract_address)
+    }
+
+    async fn get_position_value(&self, position: &Position) -> Result<f64, AdapterError> {
+        Ok(position.value_usd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contract_addresses() {
+        for address in [
+            GmxAdapter::ARBITRUM_DATA_STORE,
+            GmxAdapter::ARBITRUM_READER,
+            GmxAdapter::ARBITRUM_GLP,
+            GmxAdapter::ARBITRUM_STAKED_GLP,
+            GmxAdapter::ARBITRUM_GLP_MANAGER,
+            GmxAdapter::AVALANCHE_DATA_STORE,
+            GmxAdapter::AVALANCHE_READER,
+        ] {
+            assert!(Address::from_str(address).is_ok(), "{}", address);
+        }
+    }
+
+    #[test]
+    fn test_unsupported_chain() {
+        let client = EthereumClient::new("https://eth.llamarpc.com").unwrap();
+        assert!(GmxAdapter::new(client.clone(), 1).is_err());
+        assert!(GmxAdapter::new(client, 42161).is_ok());
+    }
+
+    #[test]
+    fn test_long_liquidation_price() {
+        // 10x long: $10,000 size on 5 tokens entered at $2,000 with $1,000 collateral
+        let metrics = GmxAdapter::calculate_perp_metrics(true, 10_000.0, 5.0, 1_000.0, 2_000.0);
+
+        assert_eq!(metrics.entry_price, 2_000.0);
+        assert_eq!(metrics.leverage, 10.0);
+        assert_eq!(metrics.unrealized_pnl, 0.0);
+        // Buffer of $900 over 5 tokens = $180 below entry
+        assert!((metrics.liquidation_price - 1_820.0).abs() < 1e-9);
+        assert!((metrics.distance_to_liquidation - 0.09).abs() < 1e-9);
+        // A 9% drop liquidates it: health factor 2000 / 1820
+        assert!((metrics.risk_score - 0.91).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_short_pnl_and_liquidation_price() {
+        let metrics = GmxAdapter::calculate_perp_metrics(false, 10_000.0, 5.0, 5_000.0, 1_800.0);
+
+        assert!((metrics.unrealized_pnl - 1_000.0).abs() < 1e-9);
+        assert!((metrics.liquidation_price - 2_980.0).abs() < 1e-9);
+        // Far from liquidation, so scored at the perp baseline
+        assert_eq!(metrics.risk_score, 0.7);
+    }
+
+    #[test]
+    fn test_pool_scores_come_from_the_liquidity_model() {
+        assert_eq!(GmxAdapter::pool_risk_score(false), 0.55);
+        assert!((GmxAdapter::pool_risk_score(true) - 0.3).abs() < 1e-12);
+    }
+}
@@ -3,7 +3,11 @@ use alloy::{
     sol,
 };
 use async_trait::async_trait;
-use crate::adapters::traits::{AdapterError, Position, DeFiAdapter};
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
+use crate::blockchain::EthereumClient;
+use crate::risk::{RiskInputs, RiskModelRegistry};
+use crate::services::{slashing, BalanceSemantics, PriceAggregator, PriceSnapshotStore, SlashingCheck, SlashingPolicy};
+use crate::utils::{normalize_amount, ETH_DECIMALS};
 use reqwest;
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -11,11 +15,6 @@ use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
 
-#[derive(Debug, Clone)]
-pub struct EthereumClient {
-    pub rpc_url: String,
-}
-
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 struct CoinGeckoToken {
@@ -99,13 +98,17 @@ pub struct LidoAdapter {
     withdrawal_queue_address: Address,
     position_cache: Arc<Mutex<HashMap<Address, CachedPositions>>>,
     http_client: reqwest::Client,
-    coingecko_api_key: Option<String>,
+    prices: Arc<PriceAggregator>,
+    price_snapshots: Arc<PriceSnapshotStore>,
 }
 
 impl LidoAdapter {
     const STETH_ADDRESS: &'static str = "0xae7ab96520DE3A18E5e111B5EaAb095312D7fE84";
     const WSTETH_ADDRESS: &'static str = "0x7f39C581F595B53c5cb19bD0b3f8dA6c935E2Ca0";
     const WITHDRAWAL_QUEUE_ADDRESS: &'static str = "0x889edC2eDab5f40e902b864aD4d7AdE8E412F9B1";
+    const CHAIN_ID: u64 = 1;
+    /// Queue wait at which a withdrawal request counts as fully locked
+    const MAX_WITHDRAWAL_WAIT_SECS: u64 = 7 * 24 * 60 * 60;
     
     pub fn new(client: EthereumClient) -> Result<Self, AdapterError> {
         let steth_address = Address::from_str(Self::STETH_ADDRESS)
@@ -124,7 +127,8 @@ impl LidoAdapter {
             withdrawal_queue_address,
             position_cache: Arc::new(Mutex::new(HashMap::new())),
             http_client: reqwest::Client::new(),
-            coingecko_api_key: std::env::var("COINGECKO_API_KEY").ok(),
+            prices: PriceAggregator::global(),
+            price_snapshots: PriceSnapshotStore::global(),
         })
     }
     
@@ -224,7 +228,7 @@ impl LidoAdapter {
     async fn get_validator_metrics(&self) -> Result<ValidatorMetrics, String> {
         // Placeholder calculation based on TVL
         let total_pooled_eth = U256::from(1000000u64);
-        let total_eth_f64 = total_pooled_eth.try_into().unwrap_or(0.0) / 10f64.powi(18);
+        let total_eth_f64 = normalize_amount(total_pooled_eth, ETH_DECIMALS);
         
         let estimated_validators = (total_eth_f64 / 32.0) as u64;
         let active_validators = (estimated_validators as f64 * 0.98) as u64;
@@ -246,33 +250,52 @@ impl LidoAdapter {
     async fn get_protocol_tvl(&self) -> Result<f64, String> {
         // Placeholder for contract call
         let total_pooled_eth = U256::from(1000000u64);
-        let total_eth_f64 = total_pooled_eth.try_into().unwrap_or(0.0) / 10f64.powi(18);
+        let total_eth_f64 = normalize_amount(total_pooled_eth, ETH_DECIMALS);
         let eth_price = self.get_eth_price_usd().await.unwrap_or(4000.0);
         let tvl_usd = total_eth_f64 * eth_price;
         
         Ok(tvl_usd)
     }
 
-    async fn calculate_position_value(&self, position: &LidoStakingPosition) -> (f64, f64, f64) {
+    /// Value, P&L since the position was first seen, and peg-adjusted APY. stETH rebases
+    /// and wstETH redeems for more stETH over time, so the growth in stETH held is
+    /// staking reward and only the stETH held at entry carries price P&L.
+    async fn calculate_position_value(&self, position: &LidoStakingPosition, id: &str) -> (f64, PnlBreakdown, f64) {
         let peg_price = self.get_steth_peg_price().await.unwrap_or(1.0);
         let eth_price = self.get_eth_price_usd().await.unwrap_or(4000.0);
         
         let eth_amount = if position.token_symbol == "wstETH" {
             self.convert_wsteth_to_steth_amount(position.balance).await
-                .unwrap_or(position.balance.try_into().unwrap_or(0.0) / 10f64.powi(18))
+                .unwrap_or(normalize_amount(position.balance, ETH_DECIMALS))
         } else {
-            position.balance.try_into().unwrap_or(0.0) / 10f64.powi(position.decimals as i32)
+            normalize_amount(position.balance, position.decimals)
         };
         
         let base_value_usd = eth_amount * eth_price;
         let peg_adjusted_value = base_value_usd * peg_price;
-        let rewards_eth = position.rewards_earned.try_into().unwrap_or(0.0) / 10f64.powi(position.decimals as i32);
-        let rewards_value_usd = rewards_eth * eth_price;
+        let steth_price = eth_price * peg_price;
+        let entry = self.price_snapshots
+            .get_or_record(id, steth_price, eth_amount, chrono::Utc::now().timestamp() as u64)
+            .attribute(BalanceSemantics::for_symbol(&position.token_symbol), steth_price, eth_amount);
+        let pnl = PnlBreakdown { price_pnl: entry.price_pnl, rewards_earned: entry.yield_usd, ..Default::default() };
         
         let peg_deviation = ((peg_price - 1.0).abs() * 100.0).min(10.0);
         let adjusted_pnl = position.apy - peg_deviation;
         
-        (peg_adjusted_value, rewards_value_usd, adjusted_pnl)
+        (peg_adjusted_value, pnl, adjusted_pnl)
+    }
+    
+    /// Staked stETH is scored on its discount to ETH, a queued withdrawal on how long
+    /// the queue takes to finalize
+    fn risk_score(position_type: &str, peg_price: f64, queue_time_secs: u64) -> f64 {
+        let inputs = match position_type {
+            "withdrawal" => RiskInputs {
+                lock_fraction: queue_time_secs as f64 / Self::MAX_WITHDRAWAL_WAIT_SECS as f64,
+                ..Default::default()
+            },
+            _ => RiskInputs { peg_deviation: peg_price - 1.0, ..Default::default() },
+        };
+        RiskModelRegistry::global().score(position_type, &inputs)
     }
     
     async fn get_lido_apy(&self, _token_type: &str) -> Result<f64, String> {
@@ -318,44 +341,11 @@ impl LidoAdapter {
     async fn convert_wsteth_to_steth_amount(&self, wsteth_amount: U256) -> Result<f64, String> {
         // Placeholder for contract call
         let steth_amount = wsteth_amount; // 1:1 placeholder
-        Ok(steth_amount.try_into().unwrap_or(0.0) / 10f64.powi(18))
+        Ok(normalize_amount(steth_amount, ETH_DECIMALS))
     }
     
     async fn get_eth_price_usd(&self) -> Result<f64, String> {
-        let url = if self.coingecko_api_key.is_some() {
-            "https://pro-api.coingecko.com/api/v3/simple/price?ids=ethereum&vs_currencies=usd"
-        } else {
-            "https://api.coingecko.com/api/v3/simple/price?ids=ethereum&vs_currencies=usd"
-        };
-        
-        let mut request = self.http_client.get(url);
-        
-        if let Some(api_key) = &self.coingecko_api_key {
-            request = request.header("X-Cg-Pro-Api-Key", api_key);
-        }
-        
-        let response = request.send().await
-            .map_err(|e| format!("HTTP request failed: {}", e))?;
-            
-        if !response.status().is_success() {
-            return Err(format!("HTTP error {}", response.status()));
-        }
-        
-        let response_text = response.text().await
-            .map_err(|e| format!("Failed to read response: {}", e))?;
-            
-        let json: serde_json::Value = serde_json::from_str(&response_text)
-            .map_err(|e| format!("JSON parse error: {}", e))?;
-            
-        if let Some(ethereum) = json.get("ethereum") {
-            if let Some(usd_price) = ethereum.get("usd") {
-                if let Some(price) = usd_price.as_f64() {
-                    return Ok(price);
-                }
-            }
-        }
-        
-        Err("ETH price not found in response".to_string())
+        self.prices.get_price("ethereum").await.map_err(|e| e.to_string())
     }
     
     async fn call_lido_api(&self, url: &str) -> Result<f64, String> {
@@ -421,6 +411,14 @@ impl DeFiAdapter for LidoAdapter {
         "lido"
     }
     
+    fn position_types(&self) -> &'static [&'static str] {
+        &["staking", "withdrawal"]
+    }
+    
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+    
     async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
         // Check cache first (5 minute TTL)
         {
@@ -451,23 +449,31 @@ impl DeFiAdapter for LidoAdapter {
             slashed_validators: 0,
         });
         let queue_time = self.estimate_withdrawal_queue_time().await.unwrap_or(259200);
+        let slashing_policy = SlashingPolicy::from_env();
+        let slashing_check = match slashing::lido_oracle_reports(&self.client, slashing_policy.lookback_blocks).await {
+            Ok(reports) => Some(SlashingCheck::from_reports(&reports, &slashing_policy)),
+            Err(e) => {
+                tracing::debug!("Failed to read Lido oracle reports: {}", e);
+                None
+            }
+        };
         
         for stake_pos in staking_positions {
-            let (value_usd, rewards_usd, apy) = self.calculate_position_value(&stake_pos).await;
-            
             let position_type = if stake_pos.token_symbol.contains("withdrawal") {
                 "withdrawal"
             } else {
                 "staking"
             };
+            let id = position_id("lido", Self::CHAIN_ID, position_type, stake_pos.token_address, address);
+            let (value_usd, pnl, apy) = self.calculate_position_value(&stake_pos, &id).await;
             
-            let position = Position {
-                id: format!("lido_{}_{}", stake_pos.token_symbol.to_lowercase(), stake_pos.token_address),
+            let mut position = Position {
+                id,
                 protocol: "lido".to_string(),
                 position_type: position_type.to_string(),
                 pair: format!("{}/ETH", stake_pos.token_symbol),
                 value_usd: value_usd.max(0.01),
-                pnl_usd: rewards_usd,
+                pnl_usd: pnl.total(),
                 pnl_percentage: apy,
                 metadata: serde_json::json!({
                     "token_address": format!("{:?}", stake_pos.token_address),
@@ -477,6 +483,8 @@ impl DeFiAdapter for LidoAdapter {
                     "decimals": stake_pos.decimals,
                     "current_apy": stake_pos.apy,
                     "rewards_earned": stake_pos.rewards_earned.to_string(),
+                    "pnl_breakdown": pnl,
+                    "balance_semantics": BalanceSemantics::for_symbol(&stake_pos.token_symbol),
                     "staking_provider": "lido",
                     "is_liquid": position_type == "staking",
                     "peg_price": peg_price,
@@ -488,12 +496,16 @@ impl DeFiAdapter for LidoAdapter {
                     "validator_count_slashed": validator_metrics.slashed_validators,
                     "withdrawal_queue_time_seconds": queue_time,
                     "withdrawal_queue_time_days": queue_time / 86400,
+                    "risk_score": Self::risk_score(position_type, peg_price, queue_time),
                 }),
                 last_updated: std::time::SystemTime::now()
                     .duration_since(std::time::UNIX_EPOCH)
                     .unwrap()
                     .as_secs(),
             };
+            if let Some(check) = &slashing_check {
+                check.apply(&mut position, &slashing_policy);
+            }
             
             positions.push(position);
         }
@@ -547,7 +559,7 @@ mod tests {
     
     #[test]
     fn test_contract_detection() {
-        let client = EthereumClient { rpc_url: "https://eth.llamarpc.com".to_string() };
+        let client = EthereumClient::new("https://eth.llamarpc.com").unwrap();
         let adapter = LidoAdapter::new(client).unwrap();
         
         let steth_addr = Address::from_str(LidoAdapter::STETH_ADDRESS).unwrap();
@@ -561,7 +573,7 @@ mod tests {
     
     #[test]
     fn test_token_symbols() {
-        let client = EthereumClient { rpc_url: "https://eth.llamarpc.com".to_string() };
+        let client = EthereumClient::new("https://eth.llamarpc.com").unwrap();
         let adapter = LidoAdapter::new(client).unwrap();
         
         let steth_addr = Address::from_str(LidoAdapter::STETH_ADDRESS).unwrap();
@@ -570,4 +582,12 @@ mod tests {
         assert_eq!(adapter.get_lido_token_symbol(steth_addr), "stETH");
         assert_eq!(adapter.get_lido_token_symbol(wsteth_addr), "wstETH");
     }
+    
+    #[test]
+    fn test_risk_scores_come_from_the_registry() {
+        assert_eq!(LidoAdapter::risk_score("staking", 1.0, 0), 0.3);
+        // 2% below peg prices in 40% of the maximum depeg risk
+        assert!((LidoAdapter::risk_score("staking", 0.98, 0) - 0.46).abs() < 1e-12);
+        assert!((LidoAdapter::risk_score("withdrawal", 1.0, 3 * 86_400) - (0.2 + 0.4 * 3.0 / 7.0)).abs() < 1e-12);
+    }
 }
\ No newline at end of file
@@ -3,7 +3,7 @@ use alloy::sol;
 use alloy::providers::Provider;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use crate::adapters::traits::{DeFiAdapter, Position, AdapterError};
+use crate::adapters::traits::{DeFiAdapter, position_id, Position, AdapterError};
 use crate::blockchain::EthereumClient;
 use std::str::FromStr;
 
@@ -242,7 +242,7 @@ impl DeFiAdapter for MakerDaoAdapter {
                 let (pnl_usd, pnl_percentage) = self.calculate_pnl(&cdp_position);
                 
                 let collateral_position = Position {
-                    id: format!("makerdao_collateral_{}_{}", address, cdp_id),
+                    id: position_id("makerdao", self.chain_id(), "collateral", cdp_id, address),
                     protocol: "makerdao".to_string(),
                     position_type: "collateral".to_string(),
                     pair: format!("{}/DAI", cdp_position.collateral_type),
@@ -255,7 +255,7 @@ impl DeFiAdapter for MakerDaoAdapter {
                 };
                 
                 let debt_position = Position {
-                    id: format!("makerdao_debt_{}_{}", address, cdp_id),
+                    id: position_id("makerdao", self.chain_id(), "debt", cdp_id, address),
                     protocol: "makerdao".to_string(),
                     position_type: "debt".to_string(),
                     pair: "DAI".to_string(),
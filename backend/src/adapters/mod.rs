@@ -1,5 +1,11 @@
 // Start with minimal working adapters only
 pub mod traits;
+pub mod catalog;
+pub mod filter;
+pub mod aave_v3;
+pub mod compound_v2;
+pub mod curve;
+pub mod erc4626;
 pub mod uniswap_v3;
 pub mod uniswap_v2;
 pub mod lido;
@@ -7,9 +13,19 @@ pub mod rocketpool;
 pub mod etherfi;
 pub mod yearnfinance;
 pub mod morphoblue;
+pub mod gmx;
+pub mod balancer_v2;
+pub mod ethena;
+pub mod frax;
+pub mod velodrome;
+pub mod spark;
+pub mod convexfinance;
+pub mod beefy;
 
 // Export traits and working adapters
 pub use traits::*;
+pub use catalog::{protocol_catalog, ProtocolInfo};
+pub use filter::{ProtocolFilter, KNOWN_PROTOCOLS};
 pub use uniswap_v3::UniswapV3Adapter;
 pub use uniswap_v2::UniswapV2Adapter;
 pub use lido::LidoAdapter;
@@ -17,10 +33,17 @@ pub use rocketpool::RocketPoolAdapter;
 pub use etherfi::EtherFiAdapter;
 pub use yearnfinance::YearnAdapter;
 pub use morphoblue::MorphoBlueAdapter;
+pub use gmx::GmxAdapter;
+pub use balancer_v2::BalancerV2Adapter;
+pub use ethena::EthenaAdapter;
+pub use frax::FraxAdapter;
+pub use velodrome::VelodromeAdapter;
+pub use spark::SparkAdapter;
+pub use convexfinance::ConvexAdapter;
+pub use compound_v2::CompoundV2Adapter;
+pub use erc4626::Erc4626Adapter;
+pub use beefy::BeefyAdapter;
 
 // TODO: Fix and re-enable these adapters once Position struct fields are aligned:
 // pub mod makerdao;
-// pub mod balancer_v2;
-// pub mod beefy;
-// pub mod convexfinance;
 // pub mod eigenlayer;
@@ -8,13 +8,11 @@ use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
-use tokio::time::timeout;
-use crate::adapters::traits::{DeFiAdapter, Position, AdapterError};
-
-#[derive(Debug, Clone)]
-pub struct EthereumClient {
-    pub rpc_url: String,
-}
+use crate::adapters::traits::{DeFiAdapter, PnlBreakdown, position_id, Position, AdapterError};
+use crate::blockchain::EthereumClient;
+use crate::risk::{RiskInputs, RiskModelRegistry};
+use crate::services::{HealthFactorThresholds, PriceService, PriceSnapshotStore, TokenMetadataCache};
+use crate::utils::RetryPolicy;
 
 sol! {
     #[sol(rpc)]
@@ -44,6 +42,7 @@ sol! {
 
         function market(bytes32 id) external view returns (Market memory);
         function marketParams(bytes32 id) external view returns (MarketParams memory);
+        function idToMarketParams(bytes32 id) external view returns (MarketParams memory);
         function position(bytes32 id, address user) external view returns (Position memory);
         function borrowRate(bytes32 id) external view returns (uint256);
         function supplyRate(bytes32 id) external view returns (uint256);
@@ -53,6 +52,33 @@ sol! {
         function maxBorrow(bytes32 id, address user) external view returns (uint256);
     }
 
+    #[sol(rpc)]
+    interface IIrm {
+        struct MarketParams {
+            address loanToken;
+            address collateralToken;
+            address oracle;
+            address irm;
+            uint256 lltv;
+        }
+
+        struct Market {
+            uint128 totalSupplyAssets;
+            uint128 totalSupplyShares;
+            uint128 totalBorrowAssets;
+            uint128 totalBorrowShares;
+            uint128 lastUpdate;
+            uint128 fee;
+        }
+
+        function borrowRateView(MarketParams memory marketParams, Market memory market) external view returns (uint256);
+    }
+
+    #[sol(rpc)]
+    interface IOracle {
+        function price() external view returns (uint256);
+    }
+
     #[sol(rpc)]
     interface IERC20Extended {
         function symbol() external view returns (string memory);
@@ -74,11 +100,20 @@ pub struct MorphoMarket {
     pub lltv: u64,
     pub total_supply_assets: U256,
     pub total_borrow_assets: U256,
+    pub total_supply_shares: U256,
+    pub total_borrow_shares: U256,
+    /// Oracle price of one collateral token in loan tokens, scaled by 1e36 and token decimals
+    pub oracle_price: U256,
+    /// Supply assets per 1e6 supply shares; starts at 1 and only falls when bad debt is realized
+    pub supply_share_price: f64,
+    pub has_bad_debt: bool,
     pub supply_rate: f64,
     pub borrow_rate: f64,
     pub utilization_rate: f64,
     pub loan_token_price_usd: f64,
     pub collateral_token_price_usd: f64,
+    /// The loan token has no price, so USD values of this market are zero
+    pub price_unknown: bool,
     pub is_active: bool,
 }
 
@@ -99,6 +134,8 @@ pub struct MorphoUserPosition {
     pub is_healthy: bool,
     pub ltv: f64,
     pub liquidation_ltv: f64,
+    /// Health factor below the warning threshold at the current oracle price
+    pub at_risk: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -126,17 +163,27 @@ struct CachedUserPositions {
 }
 
 pub struct MorphoBlueAdapter {
-    #[allow(dead_code)]
     client: EthereumClient,
     chain_id: u64,
     morpho_address: Address,
     market_cache: Arc<Mutex<Option<CachedMarketData>>>,
     position_cache: Arc<Mutex<HashMap<Address, CachedUserPositions>>>,
-    price_oracle: reqwest::Client,
+    price_service: Arc<PriceService>,
     known_markets: Arc<Mutex<Vec<B256>>>,
+    price_snapshots: Arc<PriceSnapshotStore>,
+    token_metadata: Arc<TokenMetadataCache>,
+    health_thresholds: HealthFactorThresholds,
+    retry_policy: RetryPolicy,
 }
 
 impl MorphoBlueAdapter {
+    /// Scale of `IOracle.price()`
+    const ORACLE_PRICE_SCALE: f64 = 1e36;
+    /// Share/asset offsets from Morpho's SharesMathLib
+    const VIRTUAL_SHARES: u64 = 1_000_000;
+    const VIRTUAL_ASSETS: u64 = 1;
+    const SECONDS_PER_YEAR: f64 = 31_536_000.0;
+
     pub fn get_morpho_address(chain_id: u64) -> Option<Address> {
         match chain_id {
             1 => Address::from_str("0xBBBBBbbBBb9cC5e90e3b3Af64bdAF62C37EEFFCb").ok(),
@@ -145,6 +192,10 @@ impl MorphoBlueAdapter {
         }
     }
 
+    pub fn supported_chains() -> Vec<u64> {
+        vec![1, 8453]
+    }
+
     pub fn new(client: EthereumClient, chain_id: u64) -> Result<Self, AdapterError> {
         let morpho_address = Self::get_morpho_address(chain_id)
             .ok_or_else(|| AdapterError::UnsupportedProtocol(format!("Morpho Blue not supported on chain {}", chain_id)))?;
@@ -155,11 +206,12 @@ impl MorphoBlueAdapter {
             morpho_address,
             market_cache: Arc::new(Mutex::new(None)),
             position_cache: Arc::new(Mutex::new(HashMap::new())),
-            price_oracle: reqwest::Client::builder()
-                .timeout(Duration::from_secs(30))
-                .build()
-                .map_err(|e| AdapterError::RpcError(format!("Failed to create HTTP client: {}", e)))?,
+            price_service: PriceService::global(),
             known_markets: Arc::new(Mutex::new(Vec::new())),
+            price_snapshots: PriceSnapshotStore::global(),
+            token_metadata: TokenMetadataCache::global(),
+            health_thresholds: HealthFactorThresholds::from_env(),
+            retry_policy: RetryPolicy::from_env(),
         })
     }
 
@@ -212,85 +264,106 @@ impl MorphoBlueAdapter {
     }
 
     async fn fetch_single_market(&self, market_id: B256) -> Result<MorphoMarket, AdapterError> {
-        // TODO: Implement actual contract calls when blockchain client is ready
-        let (loan_symbol, loan_decimals) = self.fetch_token_metadata(Address::ZERO).await?;
-        let (collateral_symbol, collateral_decimals) = self.fetch_token_metadata(Address::ZERO).await?;
-        
-        let loan_price = self.get_token_price(&loan_symbol).await;
-        let collateral_price = self.get_token_price(&collateral_symbol).await;
+        let params = self.client
+            .call_with_retry(self.morpho_address, &IMorpho::idToMarketParamsCall { id: market_id }, &self.retry_policy)
+            .await?
+            ._0;
+        let state = self.client
+            .call_with_retry(self.morpho_address, &IMorpho::marketCall { id: market_id }, &self.retry_policy)
+            .await?
+            ._0;
+
+        if params.loanToken == Address::ZERO {
+            return Err(AdapterError::InvalidData(format!("Morpho market {} is not created", market_id)));
+        }
+
+        let (loan_symbol, loan_decimals) = self.fetch_token_metadata(params.loanToken).await?;
+        let (collateral_symbol, collateral_decimals) = self.fetch_token_metadata(params.collateralToken).await?;
+
+        let oracle_price = self.client.call_with_retry(params.oracle, &IOracle::priceCall {}, &self.retry_policy).await?._0;
+        let loan_price = self.get_token_price(params.loanToken, &loan_symbol).await;
+        let price_unknown = loan_price.is_none();
+        let loan_price = loan_price.unwrap_or(0.0);
+        // Price collateral the way Morpho does for liquidations: via the market oracle
+        let collateral_price = Self::token_amount(oracle_price, 36 + loan_decimals - collateral_decimals) * loan_price;
+
+        let total_supply_assets = U256::from(state.totalSupplyAssets);
+        let total_borrow_assets = U256::from(state.totalBorrowAssets);
+        let total_supply_shares = U256::from(state.totalSupplyShares);
+        let total_borrow_shares = U256::from(state.totalBorrowShares);
+
+        let utilization_rate = Self::utilization(total_supply_assets, total_borrow_assets);
+        let supply_share_price = Self::supply_share_price(total_supply_assets, total_supply_shares);
+
+        let irm_params = IIrm::MarketParams {
+            loanToken: params.loanToken,
+            collateralToken: params.collateralToken,
+            oracle: params.oracle,
+            irm: params.irm,
+            lltv: params.lltv,
+        };
+        let irm_market = IIrm::Market {
+            totalSupplyAssets: state.totalSupplyAssets,
+            totalSupplyShares: state.totalSupplyShares,
+            totalBorrowAssets: state.totalBorrowAssets,
+            totalBorrowShares: state.totalBorrowShares,
+            lastUpdate: state.lastUpdate,
+            fee: state.fee,
+        };
+        let borrow_rate = match self.client.call(params.irm, &IIrm::borrowRateViewCall { marketParams: irm_params, market: irm_market }).await {
+            Ok(rate) => Self::rate_per_second_to_apy(Self::token_amount(rate._0, 18)),
+            Err(e) => {
+                tracing::warn!(market = %market_id, error = %e, "Failed to read Morpho borrow rate");
+                0.0
+            }
+        };
+        let fee = Self::token_amount(U256::from(state.fee), 18);
+        let supply_rate = borrow_rate * utilization_rate * (1.0 - fee);
 
         Ok(MorphoMarket {
             market_id,
-            loan_token: Address::ZERO,
+            loan_token: params.loanToken,
             loan_token_symbol: loan_symbol,
             loan_token_decimals: loan_decimals,
-            collateral_token: Address::ZERO,
+            collateral_token: params.collateralToken,
             collateral_token_symbol: collateral_symbol,
             collateral_token_decimals: collateral_decimals,
-            oracle: Address::ZERO,
-            irm: Address::ZERO,
-            lltv: 0,
-            total_supply_assets: U256::ZERO,
-            total_borrow_assets: U256::ZERO,
-            supply_rate: 5.0,
-            borrow_rate: 8.0,
-            utilization_rate: 0.0,
+            oracle: params.oracle,
+            irm: params.irm,
+            // WAD -> basis points
+            lltv: (params.lltv / U256::from(100_000_000_000_000u64)).to::<u64>(),
+            total_supply_assets,
+            total_borrow_assets,
+            total_supply_shares,
+            total_borrow_shares,
+            oracle_price,
+            supply_share_price,
+            has_bad_debt: supply_share_price < 1.0,
+            supply_rate,
+            borrow_rate,
+            utilization_rate,
             loan_token_price_usd: loan_price,
             collateral_token_price_usd: collateral_price,
+            price_unknown,
             is_active: true,
         })
     }
 
-    async fn fetch_token_metadata(&self, _token_address: Address) -> Result<(String, u8), AdapterError> {
-        // TODO: Implement actual token metadata fetching
-        Ok(("UNKNOWN".to_string(), 18))
+    async fn fetch_token_metadata(&self, token_address: Address) -> Result<(String, u8), AdapterError> {
+        let metadata = self.token_metadata.get_or_fetch(&self.client, self.chain_id, token_address).await?;
+        Ok((metadata.symbol, metadata.decimals))
     }
 
-    async fn get_token_price(&self, symbol: &str) -> f64 {
-        let coingecko_id = match symbol.to_uppercase().as_str() {
-            "WETH" | "ETH" => "ethereum",
-            "WBTC" | "BTC" => "bitcoin",
-            "USDC" => "usd-coin",
-            "USDT" => "tether",
-            "DAI" => "dai",
-            "WSTETH" => "wrapped-steth",
-            "RETH" => "rocket-pool-eth",
-            "CBETH" => "coinbase-wrapped-staked-eth",
-            _ => return 1.0,
-        };
-
-        self.fetch_coingecko_price(coingecko_id).await.unwrap_or_else(|_| {
-            match symbol.to_uppercase().as_str() {
-                "WETH" | "ETH" => 3000.0,
-                "WBTC" | "BTC" => 50000.0,
-                "USDC" | "USDT" | "DAI" => 1.0,
-                "WSTETH" | "RETH" | "CBETH" => 3200.0,
-                _ => 1.0,
+    /// USD price of the token at `token`, resolved by contract address; `None` when
+    /// CoinGecko has no confident match
+    async fn get_token_price(&self, token: Address, symbol: &str) -> Option<f64> {
+        match self.price_service.get_token_price(self.chain_id, token, Some(symbol)).await {
+            Ok(price) => Some(price),
+            Err(e) => {
+                tracing::warn!(token = %token, symbol = %symbol, error = %e, "No price for Morpho loan token");
+                None
             }
-        })
-    }
-
-    async fn fetch_coingecko_price(&self, coin_id: &str) -> Result<f64, AdapterError> {
-        let url = format!("https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies=usd", coin_id);
-        
-        let response = timeout(Duration::from_secs(10), self.price_oracle.get(&url).send())
-            .await
-            .map_err(|_| AdapterError::RpcError("CoinGecko timeout".to_string()))?
-            .map_err(|e| AdapterError::RpcError(format!("CoinGecko error: {}", e)))?;
-
-        if !response.status().is_success() {
-            return Err(AdapterError::RpcError(format!("CoinGecko HTTP {}", response.status())));
         }
-
-        let data: serde_json::Value = response.json().await
-            .map_err(|e| AdapterError::InvalidData(format!("CoinGecko JSON error: {}", e)))?;
-
-        let price = data.get(coin_id)
-            .and_then(|coin| coin.get("usd"))
-            .and_then(|price| price.as_f64())
-            .ok_or_else(|| AdapterError::InvalidData("Price not found".to_string()))?;
-        
-        Ok(price)
     }
 
     async fn fetch_user_positions(&self, user: Address) -> Result<MorphoAccountSummary, AdapterError> {
@@ -363,20 +436,30 @@ impl MorphoBlueAdapter {
 
     async fn fetch_user_position_in_market(
         &self,
-        _user: Address,
-        _market_id: B256,
+        user: Address,
+        market_id: B256,
         market: &MorphoMarket,
     ) -> Result<Option<MorphoUserPosition>, AdapterError> {
-        // TODO: Implement actual position fetching when blockchain client is ready
-        // For now, return None for no position
-        if market.market_id == B256::ZERO {
+        let position = self.client
+            .call_with_retry(self.morpho_address, &IMorpho::positionCall { id: market_id, user }, &self.retry_policy)
+            .await?
+            ._0;
+
+        let supply_shares = position.supplyShares;
+        let borrow_shares = U256::from(position.borrowShares);
+        let collateral_amount = U256::from(position.collateral);
+
+        if supply_shares == U256::ZERO && borrow_shares == U256::ZERO && collateral_amount == U256::ZERO {
             return Ok(None);
         }
 
-        let supply_value_usd = 1000.0; // Mock data
-        let borrow_value_usd = 500.0;
-        let collateral_value_usd = 2000.0;
-        
+        let supply_assets = Self::to_assets(supply_shares, market.total_supply_assets, market.total_supply_shares, false);
+        let borrow_assets = Self::to_assets(borrow_shares, market.total_borrow_assets, market.total_borrow_shares, true);
+
+        let supply_value_usd = self.calculate_usd_value(supply_assets, market.loan_token_decimals, market.loan_token_price_usd);
+        let borrow_value_usd = self.calculate_usd_value(borrow_assets, market.loan_token_decimals, market.loan_token_price_usd);
+        let collateral_value_usd = self.calculate_usd_value(collateral_amount, market.collateral_token_decimals, market.collateral_token_price_usd);
+
         let health_factor = if borrow_value_usd > 0.0 {
             (collateral_value_usd * (market.lltv as f64 / 10000.0)) / borrow_value_usd
         } else {
@@ -388,27 +471,34 @@ impl MorphoBlueAdapter {
         } else {
             0.0
         };
-        
+
+        // Remaining borrow capacity in loan token units, at the oracle price
+        let collateral_in_loan: f64 = collateral_amount.to_string().parse::<f64>().unwrap_or(0.0)
+            * Self::token_amount(market.oracle_price, 0) / Self::ORACLE_PRICE_SCALE;
+        let capacity = collateral_in_loan * market.lltv as f64 / 10000.0;
+        let borrowed: f64 = borrow_assets.to_string().parse().unwrap_or(0.0);
+        let max_borrowable = U256::from((capacity - borrowed).max(0.0) as u128);
+
         Ok(Some(MorphoUserPosition {
             market: market.clone(),
-            supply_shares: U256::from(1000),
-            borrow_shares: U256::from(500),
-            collateral_amount: U256::from(2000),
-            supply_assets: U256::from(1000),
-            borrow_assets: U256::from(500),
+            supply_shares,
+            borrow_shares,
+            collateral_amount,
+            supply_assets,
+            borrow_assets,
             supply_value_usd,
             borrow_value_usd,
             collateral_value_usd,
             net_value_usd: supply_value_usd + collateral_value_usd - borrow_value_usd,
             health_factor,
-            max_borrowable: U256::from(800),
+            max_borrowable,
             is_healthy: health_factor > 1.0,
             ltv: current_ltv,
             liquidation_ltv: market.lltv as f64 / 100.0,
+            at_risk: health_factor < self.health_thresholds.warning,
         }))
     }
 
-    #[allow(dead_code)]
     fn calculate_usd_value(&self, amount: U256, decimals: u8, price_usd: f64) -> f64 {
         let normalized_amount: f64 = amount.try_into().unwrap_or(0.0) / 10_f64.powi(decimals as i32);
         normalized_amount * price_usd
@@ -417,13 +507,15 @@ impl MorphoBlueAdapter {
     fn convert_to_positions(&self, user: Address, account: &MorphoAccountSummary) -> Vec<Position> {
         let mut positions = Vec::new();
         
-        for (index, morpho_position) in account.positions.iter().enumerate() {
+        for morpho_position in &account.positions {
+            let market_id = morpho_position.market.market_id;
             // Supply position
             if morpho_position.supply_assets > U256::ZERO {
-                let supply_pnl = self.calculate_supply_pnl(morpho_position);
+                let supply_pnl = self.calculate_supply_pnl(user, morpho_position);
+                let supply_pnl_usd = supply_pnl.total();
                 
                 positions.push(Position {
-                    id: format!("morpho_blue_supply_{}_{}_{}", self.chain_id, user, index),
+                    id: position_id("morpho_blue", self.chain_id, "supply", market_id, user),
                     protocol: "morpho_blue".to_string(),
                     position_type: "supply".to_string(),
                     pair: format!("{}/{}", 
@@ -431,12 +523,16 @@ impl MorphoBlueAdapter {
                         morpho_position.market.collateral_token_symbol
                     ),
                     value_usd: morpho_position.supply_value_usd,
-                    pnl_usd: supply_pnl,
+                    pnl_usd: supply_pnl_usd,
                     pnl_percentage: if morpho_position.supply_value_usd > 0.0 {
-                        (supply_pnl / morpho_position.supply_value_usd) * 100.0
+                        (supply_pnl_usd / morpho_position.supply_value_usd) * 100.0
                     } else { 0.0 },
                     metadata: serde_json::json!({
                         "market": morpho_position.market,
+                        "market_health": Self::market_health(morpho_position),
+                        "price_unknown": morpho_position.market.price_unknown,
+                        "risk_score": Self::market_risk_score(&morpho_position.market),
+                        "pnl_breakdown": supply_pnl,
                         "position_details": {
                             "supply_shares": morpho_position.supply_shares.to_string(),
                             "supply_assets": morpho_position.supply_assets.to_string(),
@@ -450,10 +546,11 @@ impl MorphoBlueAdapter {
             
             // Borrow position
             if morpho_position.borrow_assets > U256::ZERO {
-                let borrow_pnl = self.calculate_borrow_pnl(morpho_position);
+                let borrow_pnl = self.calculate_borrow_pnl(user, morpho_position);
+                let borrow_pnl_usd = borrow_pnl.total();
                 
                 positions.push(Position {
-                    id: format!("morpho_blue_borrow_{}_{}_{}", self.chain_id, user, index),
+                    id: position_id("morpho_blue", self.chain_id, "borrow", market_id, user),
                     protocol: "morpho_blue".to_string(),
                     position_type: "borrow".to_string(),
                     pair: format!("{}/{}", 
@@ -461,17 +558,22 @@ impl MorphoBlueAdapter {
                         morpho_position.market.collateral_token_symbol
                     ),
                     value_usd: -morpho_position.borrow_value_usd,
-                    pnl_usd: borrow_pnl,
+                    pnl_usd: borrow_pnl_usd,
                     pnl_percentage: if morpho_position.borrow_value_usd > 0.0 {
-                        (borrow_pnl / morpho_position.borrow_value_usd) * 100.0
+                        (borrow_pnl_usd / morpho_position.borrow_value_usd) * 100.0
                     } else { 0.0 },
                     metadata: serde_json::json!({
                         "market": morpho_position.market,
+                        "market_health": Self::market_health(morpho_position),
+                        "price_unknown": morpho_position.market.price_unknown,
+                        "risk_score": Self::borrow_risk_score(morpho_position),
+                        "pnl_breakdown": borrow_pnl,
                         "position_details": {
                             "borrow_shares": morpho_position.borrow_shares.to_string(),
                             "borrow_assets": morpho_position.borrow_assets.to_string(),
                             "borrow_apy": morpho_position.market.borrow_rate,
                             "health_factor": morpho_position.health_factor,
+                            "liquidation_price": Self::liquidation_price(morpho_position),
                             "ltv": morpho_position.ltv,
                             "liquidation_ltv": morpho_position.liquidation_ltv,
                             "is_healthy": morpho_position.is_healthy
@@ -483,8 +585,11 @@ impl MorphoBlueAdapter {
 
             // Collateral position
             if morpho_position.collateral_amount > U256::ZERO {
+                let collateral_pnl = self.calculate_collateral_pnl(user, morpho_position);
+                let collateral_pnl_usd = collateral_pnl.total();
+                
                 positions.push(Position {
-                    id: format!("morpho_blue_collateral_{}_{}_{}", self.chain_id, user, index),
+                    id: position_id("morpho_blue", self.chain_id, "collateral", market_id, user),
                     protocol: "morpho_blue".to_string(),
                     position_type: "collateral".to_string(),
                     pair: format!("{}/{}", 
@@ -492,10 +597,16 @@ impl MorphoBlueAdapter {
                         morpho_position.market.loan_token_symbol
                     ),
                     value_usd: morpho_position.collateral_value_usd,
-                    pnl_usd: 0.0,
-                    pnl_percentage: 0.0,
+                    pnl_usd: collateral_pnl_usd,
+                    pnl_percentage: if morpho_position.collateral_value_usd > 0.0 {
+                        (collateral_pnl_usd / morpho_position.collateral_value_usd) * 100.0
+                    } else { 0.0 },
                     metadata: serde_json::json!({
                         "market": morpho_position.market,
+                        "market_health": Self::market_health(morpho_position),
+                        "price_unknown": morpho_position.market.price_unknown,
+                        "risk_score": Self::borrow_risk_score(morpho_position),
+                        "pnl_breakdown": collateral_pnl,
                         "position_details": {
                             "collateral_amount": morpho_position.collateral_amount.to_string(),
                             "collateral_token": morpho_position.market.collateral_token_symbol,
@@ -511,27 +622,146 @@ impl MorphoBlueAdapter {
         positions
     }
 
-    fn calculate_supply_pnl(&self, position: &MorphoUserPosition) -> f64 {
-        let days_held = 45.0;
-        let annual_yield = position.supply_value_usd * (position.market.supply_rate / 100.0);
-        let base_pnl = annual_yield * (days_held / 365.0);
-        base_pnl * 1.02 // Morpho efficiency bonus
+    /// Interest accrued since the supply was first observed, plus loan token price movement
+    fn calculate_supply_pnl(&self, user: Address, position: &MorphoUserPosition) -> PnlBreakdown {
+        let market = &position.market;
+        let quantity = Self::token_amount(position.supply_assets, market.loan_token_decimals);
+        let snapshot = self.price_snapshots.get_or_record(
+            &position_id("morpho_blue", self.chain_id, "supply", market.market_id, user),
+            market.loan_token_price_usd,
+            quantity,
+            Self::now(),
+        );
+        let interest = Self::accrued_interest(position.supply_value_usd, market.supply_rate, snapshot.days_held(Self::now()));
+
+        PnlBreakdown {
+            price_pnl: snapshot.price_pnl(market.loan_token_price_usd, quantity),
+            interest,
+            ..Default::default()
+        }
     }
 
-    fn calculate_borrow_pnl(&self, position: &MorphoUserPosition) -> f64 {
-        let days_held = 45.0;
-        let annual_cost = position.borrow_value_usd * (position.market.borrow_rate / 100.0);
-        let base_cost = -annual_cost * (days_held / 365.0);
-        
-        let volatility_factor = if position.market.utilization_rate > 90.0 {
-            1.15
-        } else if position.market.utilization_rate < 50.0 {
-            0.95
+    /// Interest paid since the borrow was first observed; a rising loan token price increases the debt
+    fn calculate_borrow_pnl(&self, user: Address, position: &MorphoUserPosition) -> PnlBreakdown {
+        let market = &position.market;
+        let quantity = Self::token_amount(position.borrow_assets, market.loan_token_decimals);
+        let snapshot = self.price_snapshots.get_or_record(
+            &position_id("morpho_blue", self.chain_id, "borrow", market.market_id, user),
+            market.loan_token_price_usd,
+            quantity,
+            Self::now(),
+        );
+        let interest = Self::accrued_interest(position.borrow_value_usd, market.borrow_rate, snapshot.days_held(Self::now()));
+
+        PnlBreakdown {
+            price_pnl: -snapshot.price_pnl(market.loan_token_price_usd, quantity),
+            interest: -interest,
+            ..Default::default()
+        }
+    }
+
+    /// Collateral price movement since first observation: `(current - entry) * quantity`
+    fn calculate_collateral_pnl(&self, user: Address, position: &MorphoUserPosition) -> PnlBreakdown {
+        let market = &position.market;
+        let quantity = Self::token_amount(position.collateral_amount, market.collateral_token_decimals);
+        let snapshot = self.price_snapshots.get_or_record(
+            &position_id("morpho_blue", self.chain_id, "collateral", market.market_id, user),
+            market.collateral_token_price_usd,
+            quantity,
+            Self::now(),
+        );
+
+        PnlBreakdown {
+            price_pnl: snapshot.price_pnl(market.collateral_token_price_usd, quantity),
+            ..Default::default()
+        }
+    }
+
+    /// Collateral price at which the health factor reaches 1, holding debt constant
+    fn liquidation_price(position: &MorphoUserPosition) -> Option<f64> {
+        if position.health_factor.is_finite() && position.health_factor > 0.0 {
+            Some(position.market.collateral_token_price_usd / position.health_factor)
         } else {
-            1.0
+            None
+        }
+    }
+
+    /// LLTV, utilization, bad debt and oracle-price liquidation risk for the user's market
+    fn market_health(position: &MorphoUserPosition) -> serde_json::Value {
+        let market = &position.market;
+        serde_json::json!({
+            "lltv": market.lltv as f64 / 10000.0,
+            "utilization": market.utilization_rate,
+            "has_bad_debt": market.has_bad_debt,
+            "supply_share_price": market.supply_share_price,
+            "at_risk": position.at_risk,
+            "health_factor": position.health_factor,
+        })
+    }
+
+    /// Risk of an isolated market for lenders: high LLTV leaves little buffer before
+    /// liquidations turn into bad debt, high utilization blocks withdrawals, and
+    /// realized bad debt means the market has already failed once
+    fn market_risk_score(market: &MorphoMarket) -> f64 {
+        let lltv = market.lltv as f64 / 10000.0;
+        let lltv_risk = ((lltv - 0.77) / (0.965 - 0.77)).clamp(0.0, 1.0) * 0.3;
+        let utilization_risk = ((market.utilization_rate - 0.9) / 0.1).clamp(0.0, 1.0) * 0.25;
+        let bad_debt_risk = if market.has_bad_debt { 0.25 } else { 0.0 };
+
+        (0.2 + lltv_risk + utilization_risk + bad_debt_risk).min(1.0)
+    }
+
+    /// Borrower risk: the market's risk or proximity to liquidation, whichever is worse
+    fn borrow_risk_score(position: &MorphoUserPosition) -> f64 {
+        let inputs = RiskInputs {
+            health_factor: Some(position.health_factor),
+            floor: Self::market_risk_score(&position.market),
+            ..Default::default()
         };
-        
-        base_cost * volatility_factor
+        RiskModelRegistry::global().score("borrow", &inputs)
+    }
+
+    fn utilization(total_supply_assets: U256, total_borrow_assets: U256) -> f64 {
+        if total_supply_assets == U256::ZERO {
+            return 0.0;
+        }
+        Self::token_amount(total_borrow_assets, 0) / Self::token_amount(total_supply_assets, 0)
+    }
+
+    fn supply_share_price(total_supply_assets: U256, total_supply_shares: U256) -> f64 {
+        let assets = Self::token_amount(total_supply_assets + U256::from(Self::VIRTUAL_ASSETS), 0);
+        let shares = Self::token_amount(total_supply_shares + U256::from(Self::VIRTUAL_SHARES), 0);
+        assets * Self::VIRTUAL_SHARES as f64 / shares
+    }
+
+    /// SharesMathLib `toAssetsDown` / `toAssetsUp`
+    fn to_assets(shares: U256, total_assets: U256, total_shares: U256, round_up: bool) -> U256 {
+        let numerator = shares * (total_assets + U256::from(Self::VIRTUAL_ASSETS));
+        let denominator = total_shares + U256::from(Self::VIRTUAL_SHARES);
+        if round_up {
+            (numerator + denominator - U256::from(1u8)) / denominator
+        } else {
+            numerator / denominator
+        }
+    }
+
+    /// Per-second WAD rate to a compounded APY percentage
+    fn rate_per_second_to_apy(rate: f64) -> f64 {
+        ((rate * Self::SECONDS_PER_YEAR).exp() - 1.0) * 100.0
+    }
+
+    /// Simple interest on `value_usd` at `apy_percent` over `days_held`
+    fn accrued_interest(value_usd: f64, apy_percent: f64, days_held: f64) -> f64 {
+        value_usd * (apy_percent / 100.0) * (days_held / 365.0)
+    }
+
+    fn token_amount(amount: U256, decimals: u8) -> f64 {
+        let raw: f64 = amount.to_string().parse().unwrap_or(0.0);
+        raw / 10_f64.powi(decimals as i32)
+    }
+
+    fn now() -> u64 {
+        chrono::Utc::now().timestamp() as u64
     }
 }
 
@@ -540,6 +770,22 @@ impl DeFiAdapter for MorphoBlueAdapter {
     fn protocol_name(&self) -> &'static str {
         "morpho_blue"
     }
+    
+    fn position_types(&self) -> &'static [&'static str] {
+        &["supply", "collateral", "borrow"]
+    }
+    
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+    
+    fn supports_historical_block(&self) -> bool {
+        true
+    }
+    
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
 
     async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
         let account_summary = self.fetch_user_positions(address).await?;
@@ -564,5 +810,98 @@ mod tests {
         assert!(MorphoBlueAdapter::get_morpho_address(1).is_some());
         assert!(MorphoBlueAdapter::get_morpho_address(8453).is_some());
         assert!(MorphoBlueAdapter::get_morpho_address(137).is_none());
+        // Every advertised chain has a deployment
+        for chain_id in MorphoBlueAdapter::supported_chains() {
+            assert!(MorphoBlueAdapter::get_morpho_address(chain_id).is_some());
+        }
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_interest_accrues_with_time_held() {
+        assert_eq!(MorphoBlueAdapter::accrued_interest(1000.0, 5.0, 0.0), 0.0);
+        assert!((MorphoBlueAdapter::accrued_interest(1000.0, 5.0, 73.0) - 10.0).abs() < 1e-9);
+    }
+
+    fn market(lltv: u64, utilization_rate: f64, has_bad_debt: bool) -> MorphoMarket {
+        MorphoMarket {
+            market_id: B256::ZERO,
+            loan_token: Address::ZERO,
+            loan_token_symbol: "USDC".to_string(),
+            loan_token_decimals: 6,
+            collateral_token: Address::ZERO,
+            collateral_token_symbol: "WETH".to_string(),
+            collateral_token_decimals: 18,
+            oracle: Address::ZERO,
+            irm: Address::ZERO,
+            lltv,
+            total_supply_assets: U256::ZERO,
+            total_borrow_assets: U256::ZERO,
+            total_supply_shares: U256::ZERO,
+            total_borrow_shares: U256::ZERO,
+            oracle_price: U256::ZERO,
+            supply_share_price: 1.0,
+            has_bad_debt,
+            supply_rate: 0.0,
+            borrow_rate: 0.0,
+            utilization_rate,
+            loan_token_price_usd: 1.0,
+            collateral_token_price_usd: 3000.0,
+            price_unknown: false,
+            is_active: true,
+        }
+    }
+
+    #[test]
+    fn test_market_risk_rises_with_lltv_utilization_and_bad_debt() {
+        let conservative = MorphoBlueAdapter::market_risk_score(&market(7700, 0.5, false));
+        let high_lltv = MorphoBlueAdapter::market_risk_score(&market(9450, 0.5, false));
+        let high_utilization = MorphoBlueAdapter::market_risk_score(&market(7700, 0.99, false));
+        let bad_debt = MorphoBlueAdapter::market_risk_score(&market(7700, 0.5, true));
+
+        assert!((conservative - 0.2).abs() < 1e-12);
+        assert!(high_lltv > conservative);
+        assert!(high_utilization > conservative);
+        assert!(bad_debt > conservative);
+    }
+
+    #[test]
+    fn test_borrow_risk_is_floored_at_the_market_risk() {
+        let borrow = |market: MorphoMarket, health_factor: f64| MorphoUserPosition {
+            market,
+            supply_shares: U256::ZERO,
+            borrow_shares: U256::ZERO,
+            collateral_amount: U256::ZERO,
+            supply_assets: U256::ZERO,
+            borrow_assets: U256::ZERO,
+            supply_value_usd: 0.0,
+            borrow_value_usd: 0.0,
+            collateral_value_usd: 0.0,
+            net_value_usd: 0.0,
+            health_factor,
+            max_borrowable: U256::ZERO,
+            is_healthy: health_factor >= 1.0,
+            ltv: 0.0,
+            liquidation_ltv: 0.0,
+            at_risk: false,
+        };
+
+        assert_eq!(MorphoBlueAdapter::borrow_risk_score(&borrow(market(7700, 0.5, false), f64::INFINITY)), 0.3);
+        assert!((MorphoBlueAdapter::borrow_risk_score(&borrow(market(7700, 0.5, false), 1.25)) - 0.8).abs() < 1e-12);
+        assert!((MorphoBlueAdapter::borrow_risk_score(&borrow(market(7700, 0.5, true), f64::INFINITY)) - 0.45).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_share_math_matches_shares_math_lib() {
+        // Fresh market: 1 asset per 1e6 shares
+        assert!((MorphoBlueAdapter::supply_share_price(U256::ZERO, U256::ZERO) - 1.0).abs() < 1e-12);
+
+        let total_assets = U256::from(999u64);
+        let total_shares = U256::from(999_000_000u64);
+        let shares = U256::from(1_000_000u64);
+        assert_eq!(MorphoBlueAdapter::to_assets(shares, total_assets, total_shares, false), U256::from(1u8));
+        assert_eq!(MorphoBlueAdapter::to_assets(shares, total_assets, total_shares, true), U256::from(1u8));
+
+        // Assets written off below the virtual 1:1e6 ratio indicate realized bad debt
+        assert!(MorphoBlueAdapter::supply_share_price(U256::from(900u64), U256::from(999_000_000u64)) < 1.0);
+    }
+}
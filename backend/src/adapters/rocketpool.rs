@@ -3,7 +3,11 @@ use alloy::{
     sol,
 };
 use async_trait::async_trait;
-use crate::adapters::traits::{AdapterError, Position, DeFiAdapter};
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
+use crate::blockchain::EthereumClient;
+use crate::risk::{RiskInputs, RiskModelRegistry};
+use crate::services::{slashing, PriceAggregator, SlashingCheck, SlashingPolicy};
+use crate::utils::{normalize_amount, ETH_DECIMALS};
 use reqwest;
 use serde::Deserialize;
 use serde_json;
@@ -12,11 +16,6 @@ use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
 
-#[derive(Debug, Clone)]
-pub struct EthereumClient {
-    pub rpc_url: String,
-}
-
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 struct RocketPoolApiResponse {
@@ -59,6 +58,47 @@ struct RocketPoolStakingPosition {
     apy: f64,
     rewards_earned: U256,
     position_subtype: String,
+    /// Set on a node operator's minipool and RPL collateral positions
+    node: Option<NodeOperatorState>,
+}
+
+/// A node operator's minipools and the RPL staked against them, in whole ETH / RPL
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeOperatorState {
+    pub minipools: u64,
+    pub active_minipools: u64,
+    /// ETH the operator bonded into its minipools
+    pub eth_bonded: f64,
+    /// ETH borrowed from the deposit pool (rETH holders) to fill the minipools
+    pub eth_borrowed: f64,
+    pub rpl_staked: f64,
+    /// Portion of the RPL stake that counts towards rewards
+    pub rpl_effective: f64,
+}
+
+impl NodeOperatorState {
+    /// Below this share of borrowed ETH, staked RPL stops earning rewards
+    pub const MIN_COLLATERAL_RATIO: f64 = 0.10;
+
+    /// Value of staked RPL as a fraction of borrowed ETH, the figure Rocket Pool
+    /// checks against its collateral bounds. `None` without borrowed ETH.
+    pub fn collateralization_ratio(&self, rpl_price_eth: f64) -> Option<f64> {
+        (self.eth_borrowed > 0.0).then(|| self.rpl_staked * rpl_price_eth / self.eth_borrowed)
+    }
+
+    /// RPL that can be auctioned off if the node's validators lose more than its bond:
+    /// at most enough to cover the borrowed ETH, and never more than is staked
+    pub fn rpl_slashing_exposure(&self, rpl_price_eth: f64) -> f64 {
+        if rpl_price_eth <= 0.0 {
+            return self.rpl_staked;
+        }
+        self.rpl_staked.min(self.eth_borrowed / rpl_price_eth)
+    }
+
+    pub fn is_under_collateralized(&self, rpl_price_eth: f64) -> bool {
+        self.collateralization_ratio(rpl_price_eth)
+            .is_some_and(|ratio| ratio < Self::MIN_COLLATERAL_RATIO)
+    }
 }
 
 sol! {
@@ -101,11 +141,12 @@ sol! {
     interface IRocketNodeStaking {
         function getNodeRPLStake(address nodeAddress) external view returns (uint256);
         function getNodeEffectiveRPLStake(address nodeAddress) external view returns (uint256);
+        function getNodeETHMatched(address nodeAddress) external view returns (uint256);
+        function getNodeETHProvided(address nodeAddress) external view returns (uint256);
     }
 }
 
 pub struct RocketPoolAdapter {
-    #[allow(dead_code)]
     client: EthereumClient,
     reth_address: Address,
     deposit_pool_address: Address,
@@ -116,10 +157,18 @@ pub struct RocketPoolAdapter {
     rpl_token_address: Address,
     position_cache: Arc<Mutex<HashMap<Address, CachedPositions>>>,
     http_client: reqwest::Client,
-    coingecko_api_key: Option<String>,
+    prices: Arc<PriceAggregator>,
 }
 
 impl RocketPoolAdapter {
+    /// Minipools carry validator and smoothing-pool risk on top of liquid staking
+    const MINIPOOL_RISK_PREMIUM: f64 = 0.1;
+    const CHAIN_ID: u64 = 1;
+    /// RPL collateral adds RPL price risk and is first in line when a node is penalized
+    const RPL_COLLATERAL_RISK_PREMIUM: f64 = 0.3;
+    /// Added when the collateral has fallen below the minimum ratio
+    const UNDER_COLLATERALIZED_RISK_PREMIUM: f64 = 0.2;
+
     const RETH_ADDRESS: &'static str = "0xae78736Cd615f374D3085123A210448E74Fc6393";
     const DEPOSIT_POOL_ADDRESS: &'static str = "0x2cac916b2A963Bf162f076C0a8a4a8200BCFBfb4";
     const NODE_MANAGER_ADDRESS: &'static str = "0x89F478E6Cc24f052103628f36598D4C14Da3D287";
@@ -155,7 +204,7 @@ impl RocketPoolAdapter {
             rpl_token_address,
             position_cache: Arc::new(Mutex::new(HashMap::new())),
             http_client: reqwest::Client::new(),
-            coingecko_api_key: std::env::var("COINGECKO_API_KEY").ok(),
+            prices: PriceAggregator::global(),
         })
     }
     
@@ -166,12 +215,11 @@ impl RocketPoolAdapter {
             positions.push(reth_position);
         }
         
-        if let Some(node_positions) = self.get_node_operator_positions(address).await? {
-            positions.extend(node_positions);
-        }
-        
-        if let Some(rpl_position) = self.get_rpl_staking_position(address).await? {
-            positions.push(rpl_position);
+        // Stale manager addresses shouldn't hide a plain rETH holding
+        match self.get_node_operator_state(address).await {
+            Ok(Some(node)) => positions.extend(self.get_node_operator_positions(address, node).await),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to read Rocket Pool node state for {:?}: {}", address, e),
         }
         
         Ok(positions)
@@ -198,73 +246,78 @@ impl RocketPoolAdapter {
             apy,
             rewards_earned,
             position_subtype: "liquid_staking".to_string(),
+            node: None,
         }))
     }
     
-    async fn get_node_operator_positions(&self, _user_address: Address) -> Result<Option<Vec<RocketPoolStakingPosition>>, AdapterError> {
-        let is_node = false; // Placeholder for contract call
-        
+    /// Minipool and RPL stake of a registered node, `None` for any other address
+    async fn get_node_operator_state(&self, user_address: Address) -> Result<Option<NodeOperatorState>, AdapterError> {
+        let is_node = self.client
+            .call(self.node_manager_address, &IRocketNodeManager::getNodeExistsCall { nodeAddress: user_address })
+            .await?
+            ._0;
         if !is_node {
             return Ok(None);
         }
         
+        let (minipools, active_minipools, eth_matched, eth_provided, rpl_stake, rpl_effective) = tokio::try_join!(
+            self.client.call(self.minipool_manager_address, &IRocketMinipoolManager::getNodeMinipoolCountCall { nodeAddress: user_address }),
+            self.client.call(self.minipool_manager_address, &IRocketMinipoolManager::getNodeActiveMinipoolCountCall { nodeAddress: user_address }),
+            self.client.call(self.node_staking_address, &IRocketNodeStaking::getNodeETHMatchedCall { nodeAddress: user_address }),
+            self.client.call(self.node_staking_address, &IRocketNodeStaking::getNodeETHProvidedCall { nodeAddress: user_address }),
+            self.client.call(self.node_staking_address, &IRocketNodeStaking::getNodeRPLStakeCall { nodeAddress: user_address }),
+            self.client.call(self.node_staking_address, &IRocketNodeStaking::getNodeEffectiveRPLStakeCall { nodeAddress: user_address }),
+        )?;
+        
+        Ok(Some(NodeOperatorState {
+            minipools: minipools._0.saturating_to(),
+            active_minipools: active_minipools._0.saturating_to(),
+            eth_bonded: normalize_amount(eth_provided._0, ETH_DECIMALS),
+            eth_borrowed: normalize_amount(eth_matched._0, ETH_DECIMALS),
+            rpl_staked: normalize_amount(rpl_stake._0, ETH_DECIMALS),
+            rpl_effective: normalize_amount(rpl_effective._0, ETH_DECIMALS),
+        }))
+    }
+    
+    /// The node's ETH bond and its RPL collateral, reported as separate positions
+    async fn get_node_operator_positions(&self, user_address: Address, node: NodeOperatorState) -> Vec<RocketPoolStakingPosition> {
         let mut positions = Vec::new();
-        let minipool_count = 0u64; // Placeholder
-        let _active_minipool_count = 0u64; // Placeholder
         
-        if minipool_count > 0 {
-            let node_eth_deposited = (minipool_count as f64) * 16.0;
-            let node_apy = self.get_node_operator_apy().await.unwrap_or(5.5);
-            let rewards_earned = U256::from((node_eth_deposited * 0.055 * 10f64.powi(18)) as u64);
-            
-            let node_position = RocketPoolStakingPosition {
+        if node.minipools > 0 && node.eth_bonded > 0.0 {
+            positions.push(RocketPoolStakingPosition {
                 token_address: self.minipool_manager_address,
-                token_symbol: format!("RP-NODE-{}", minipool_count),
-                balance: U256::from((node_eth_deposited * 10f64.powi(18)) as u64),
-                decimals: 18,
+                token_symbol: format!("RP-NODE-{}", node.minipools),
+                balance: to_wei(node.eth_bonded),
+                decimals: ETH_DECIMALS,
                 underlying_asset: "ETH".to_string(),
-                apy: node_apy,
-                rewards_earned,
-                position_subtype: "node_operator".to_string(),
-            };
-            
-            positions.push(node_position);
-        }
-        
-        Ok(if positions.is_empty() { None } else { Some(positions) })
-    }
-    
-    async fn get_rpl_staking_position(&self, user_address: Address) -> Result<Option<RocketPoolStakingPosition>, AdapterError> {
-        let is_node = false; // Placeholder
-        
-        if !is_node {
-            return Ok(None);
+                apy: self.get_node_operator_apy().await.unwrap_or(5.5),
+                rewards_earned: U256::ZERO,
+                position_subtype: "minipool".to_string(),
+                node: Some(node),
+            });
         }
         
-        let rpl_stake = U256::ZERO; // Placeholder
-        
-        if rpl_stake == U256::ZERO {
-            return Ok(None);
+        if node.rpl_staked > 0.0 {
+            let rpl_stake = to_wei(node.rpl_staked);
+            positions.push(RocketPoolStakingPosition {
+                token_address: self.rpl_token_address,
+                token_symbol: "RPL-STAKED".to_string(),
+                balance: rpl_stake,
+                decimals: ETH_DECIMALS,
+                underlying_asset: "RPL".to_string(),
+                apy: self.get_rpl_staking_apy().await.unwrap_or(8.0),
+                rewards_earned: self.estimate_rpl_rewards(user_address, rpl_stake).await,
+                position_subtype: "rpl_collateral".to_string(),
+                node: Some(node),
+            });
         }
         
-        let rpl_apy = self.get_rpl_staking_apy().await.unwrap_or(8.0);
-        let rewards_earned = self.estimate_rpl_rewards(user_address, rpl_stake).await;
-        
-        Ok(Some(RocketPoolStakingPosition {
-            token_address: self.rpl_token_address,
-            token_symbol: "RPL-STAKED".to_string(),
-            balance: rpl_stake,
-            decimals: 18,
-            underlying_asset: "RPL".to_string(),
-            apy: rpl_apy,
-            rewards_earned,
-            position_subtype: "rpl_staking".to_string(),
-        }))
+        positions
     }
     
     async fn get_reth_exchange_rate(&self) -> Result<f64, String> {
         let exchange_rate = U256::from(1000000000000000000u64); // Placeholder
-        let rate = exchange_rate.try_into().unwrap_or(0.0) / 10f64.powi(18);
+        let rate = normalize_amount(exchange_rate, ETH_DECIMALS);
         Ok(rate)
     }
     
@@ -280,11 +333,11 @@ impl RocketPoolAdapter {
     async fn get_protocol_metrics(&self) -> Result<ProtocolMetrics, String> {
         let reth_supply = U256::from(1_000_000u64) * U256::from(10u64).pow(U256::from(18u64));
         let exchange_rate = self.get_reth_exchange_rate().await.unwrap_or(1.1);
-        let total_eth_staked = (reth_supply.try_into().unwrap_or(0.0) / 10f64.powi(18)) * exchange_rate;
+        let total_eth_staked = normalize_amount(reth_supply, ETH_DECIMALS) * exchange_rate;
         
         Ok(ProtocolMetrics {
             total_eth_staked,
-            reth_supply: reth_supply.try_into().unwrap_or(0.0) / 10f64.powi(18),
+            reth_supply: normalize_amount(reth_supply, ETH_DECIMALS),
             reth_exchange_rate: exchange_rate,
             node_demand: 0.0,
             deposit_pool_balance: 10000.0,
@@ -309,14 +362,14 @@ impl RocketPoolAdapter {
         };
         
         let underlying_amount = if position.token_symbol == "rETH" {
-            let reth_amount = position.balance.try_into().unwrap_or(0.0) / 10f64.powi(18);
+            let reth_amount = normalize_amount(position.balance, ETH_DECIMALS);
             reth_amount * exchange_rate
         } else {
-            position.balance.try_into().unwrap_or(0.0) / 10f64.powi(position.decimals as i32)
+            normalize_amount(position.balance, position.decimals)
         };
         
         let base_value_usd = underlying_amount * token_price;
-        let rewards_amount = position.rewards_earned.try_into().unwrap_or(0.0) / 10f64.powi(position.decimals as i32);
+        let rewards_amount = normalize_amount(position.rewards_earned, position.decimals);
         let rewards_value_usd = rewards_amount * token_price;
         
         (base_value_usd, rewards_value_usd, position.apy)
@@ -352,8 +405,8 @@ impl RocketPoolAdapter {
     }
     
     async fn estimate_reth_rewards(&self, _user_address: Address, reth_balance: U256, eth_value: U256) -> U256 {
-        let reth_amount_f64 = reth_balance.to::<u128>() as f64 / 10f64.powi(18);
-        let eth_equivalent_f64 = eth_value.to::<u128>() as f64 / 10f64.powi(18);
+        let reth_amount_f64 = normalize_amount(reth_balance, ETH_DECIMALS);
+        let eth_equivalent_f64 = normalize_amount(eth_value, ETH_DECIMALS);
         
         let assumed_entry_rate = 1.05;
         let current_rate = if reth_amount_f64 > 0.0 {
@@ -385,58 +438,11 @@ impl RocketPoolAdapter {
     }
     
     async fn get_eth_price_usd(&self) -> Result<f64, String> {
-        let url = if self.coingecko_api_key.is_some() {
-            "https://pro-api.coingecko.com/api/v3/simple/price?ids=ethereum&vs_currencies=usd"
-        } else {
-            "https://api.coingecko.com/api/v3/simple/price?ids=ethereum&vs_currencies=usd"
-        };
-        
-        self.get_token_price_from_coingecko(url, "ethereum").await
+        self.prices.get_price("ethereum").await.map_err(|e| e.to_string())
     }
     
     async fn get_rpl_price_usd(&self) -> Result<f64, String> {
-        let url = if self.coingecko_api_key.is_some() {
-            "https://pro-api.coingecko.com/api/v3/simple/price?ids=rocket-pool&vs_currencies=usd"
-        } else {
-            "https://api.coingecko.com/api/v3/simple/price?ids=rocket-pool&vs_currencies=usd"
-        };
-        
-        self.get_token_price_from_coingecko(url, "rocket-pool").await
-    }
-    
-    async fn get_token_price_from_coingecko(&self, url: &str, token_id: &str) -> Result<f64, String> {
-        let mut request = self.http_client.get(url);
-        
-        if let Some(api_key) = &self.coingecko_api_key {
-            request = request.header("X-Cg-Pro-Api-Key", api_key);
-        }
-        
-        let response = request
-            .send()
-            .await
-            .map_err(|e| format!("HTTP request failed: {}", e))?;
-            
-        if !response.status().is_success() {
-            return Err(format!("HTTP error {}", response.status()));
-        }
-        
-        let response_text = response
-            .text()
-            .await
-            .map_err(|e| format!("Failed to read response: {}", e))?;
-            
-        let json: serde_json::Value = serde_json::from_str(&response_text)
-            .map_err(|e| format!("JSON parse error: {}", e))?;
-            
-        if let Some(token_data) = json.get(token_id) {
-            if let Some(usd_price) = token_data.get("usd") {
-                if let Some(price) = usd_price.as_f64() {
-                    return Ok(price);
-                }
-            }
-        }
-        
-        Err(format!("{} price not found in response", token_id))
+        self.prices.get_price("rocket-pool").await.map_err(|e| e.to_string())
     }
     
     async fn call_rocket_pool_api(&self, url: &str) -> Result<f64, String> {
@@ -483,6 +489,46 @@ impl RocketPoolAdapter {
         Err("APY not found in Rocket Pool API response".to_string())
     }
     
+    /// Collateral health of a node, recorded on both of its positions so either one
+    /// reads on its own
+    async fn apply_node_metadata(&self, position: &mut Position, node: &NodeOperatorState, subtype: &str) {
+        let rpl_price_eth = match (self.get_rpl_price_usd().await, self.get_eth_price_usd().await) {
+            (Ok(rpl), Ok(eth)) if eth > 0.0 => Some(rpl / eth),
+            _ => None,
+        };
+        let under_collateralized = rpl_price_eth.is_some_and(|price| node.is_under_collateralized(price));
+        
+        if let Some(metadata) = position.metadata.as_object_mut() {
+            metadata.insert("risk_score".to_string(), serde_json::json!(Self::node_risk_score(subtype, under_collateralized)));
+            metadata.insert("node_operator".to_string(), serde_json::json!({
+                "minipools": node.minipools,
+                "active_minipools": node.active_minipools,
+                "eth_bonded": node.eth_bonded,
+                "eth_borrowed": node.eth_borrowed,
+                "rpl_staked": node.rpl_staked,
+                "rpl_effective_stake": node.rpl_effective,
+                "rpl_price_eth": rpl_price_eth,
+                "collateralization_ratio": rpl_price_eth.and_then(|price| node.collateralization_ratio(price)),
+                "min_collateralization_ratio": NodeOperatorState::MIN_COLLATERAL_RATIO,
+                "under_collateralized": under_collateralized,
+                "rpl_slashing_exposure": rpl_price_eth.map(|price| node.rpl_slashing_exposure(price)),
+            }));
+        }
+    }
+    
+    /// Staking risk of a node operator's minipool or RPL collateral position
+    fn node_risk_score(subtype: &str, under_collateralized: bool) -> f64 {
+        let mut premium = if subtype == "rpl_collateral" {
+            Self::RPL_COLLATERAL_RISK_PREMIUM
+        } else {
+            Self::MINIPOOL_RISK_PREMIUM
+        };
+        if under_collateralized {
+            premium += Self::UNDER_COLLATERALIZED_RISK_PREMIUM;
+        }
+        RiskModelRegistry::global().score("staking", &RiskInputs { premium, ..Default::default() })
+    }
+    
     fn is_rocket_pool_contract(&self, address: Address) -> bool {
         address == self.reth_address || 
         address == self.deposit_pool_address || 
@@ -494,12 +540,25 @@ impl RocketPoolAdapter {
     }
 }
 
+/// Whole-token amount back to 18-decimal base units
+fn to_wei(amount: f64) -> U256 {
+    U256::try_from(amount * 1e18).unwrap_or(U256::ZERO)
+}
+
 #[async_trait]
 impl DeFiAdapter for RocketPoolAdapter {
     fn protocol_name(&self) -> &'static str {
         "rocket_pool"
     }
     
+    fn position_types(&self) -> &'static [&'static str] {
+        &["staking", "minipool", "rpl_collateral"]
+    }
+    
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+    
     async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
         // Check cache first
         {
@@ -537,14 +596,23 @@ impl DeFiAdapter for RocketPoolAdapter {
             deposit_pool_balance: 0.0,
             network_node_fee: 0.05,
         });
+        let slashing_policy = SlashingPolicy::from_env();
+        let slashing_check = match slashing::rocket_pool_oracle_reports(&self.client, slashing_policy.lookback_blocks).await {
+            Ok(reports) => Some(SlashingCheck::from_reports(&reports, &slashing_policy)),
+            Err(e) => {
+                tracing::debug!("Failed to read Rocket Pool balance reports: {}", e);
+                None
+            }
+        };
         
         for stake_pos in staking_positions {
             let (base_value_usd, rewards_usd, calculated_apy) = self.calculate_position_value(&stake_pos).await;
+            let pnl = PnlBreakdown { rewards_earned: rewards_usd, ..Default::default() };
             
             let position_type = match stake_pos.position_subtype.as_str() {
                 "liquid_staking" => "staking",
-                "node_operator" => "node_operation",
-                "rpl_staking" => "governance_staking",
+                "minipool" => "minipool",
+                "rpl_collateral" => "rpl_collateral",
                 _ => "staking",
             };
             
@@ -554,13 +622,13 @@ impl DeFiAdapter for RocketPoolAdapter {
                 _ => format!("{}/USD", stake_pos.token_symbol),
             };
             
-            let position = Position {
-                id: format!("rocket_pool_{}_{}", stake_pos.token_symbol.to_lowercase(), stake_pos.token_address),
+            let mut position = Position {
+                id: position_id("rocket_pool", Self::CHAIN_ID, position_type, stake_pos.token_address, address),
                 protocol: "rocket_pool".to_string(),
                 position_type: position_type.to_string(),
                 pair,
                 value_usd: base_value_usd.max(0.01),
-                pnl_usd: rewards_usd,
+                pnl_usd: pnl.total(),
                 pnl_percentage: calculated_apy,
                 metadata: serde_json::json!({
                     "token_address": format!("{:?}", stake_pos.token_address),
@@ -570,6 +638,7 @@ impl DeFiAdapter for RocketPoolAdapter {
                     "decimals": stake_pos.decimals,
                     "current_apy": stake_pos.apy,
                     "rewards_earned": stake_pos.rewards_earned.to_string(),
+                    "pnl_breakdown": pnl,
                     "position_subtype": stake_pos.position_subtype,
                     "is_liquid": stake_pos.position_subtype == "liquid_staking",
                     "reth_exchange_rate": exchange_rate,
@@ -594,6 +663,13 @@ impl DeFiAdapter for RocketPoolAdapter {
                     .unwrap()
                     .as_secs(),
             };
+            if let Some(node) = stake_pos.node {
+                self.apply_node_metadata(&mut position, &node, &stake_pos.position_subtype).await;
+            }
+            // RPL stake isn't backed by validators, so only ETH-denominated positions are flagged
+            if let Some(check) = slashing_check.as_ref().filter(|_| stake_pos.underlying_asset == "ETH") {
+                check.apply(&mut position, &slashing_policy);
+            }
             
             positions.push(position);
         }
@@ -632,11 +708,13 @@ impl DeFiAdapter for RocketPoolAdapter {
                         _ => 4000.0,
                     };
                     
+                    let decimals = position.metadata.get("decimals")
+                        .and_then(|v| v.as_u64())
+                        .map_or(ETH_DECIMALS, |d| d as u8);
                     let underlying_amount = if position.pair.contains("rETH") {
-                        let reth_amount = balance.to::<u128>() as f64 / 10f64.powi(18);
-                        reth_amount * exchange_rate
+                        normalize_amount(balance, decimals) * exchange_rate
                     } else {
-                        balance.to::<u128>() as f64 / 10f64.powi(18)
+                        normalize_amount(balance, decimals)
                     };
                     
                     let calculated_value = underlying_amount * token_price;
@@ -669,7 +747,7 @@ mod tests {
     
     #[test]
     fn test_rocket_pool_contract_detection() {
-        let client = EthereumClient { rpc_url: "https://eth.llamarpc.com".to_string() };
+        let client = EthereumClient::new("https://eth.llamarpc.com").unwrap();
         let adapter = RocketPoolAdapter::new(client).unwrap();
         
         let reth_addr = Address::from_str(RocketPoolAdapter::RETH_ADDRESS).unwrap();
@@ -693,6 +771,36 @@ mod tests {
         assert_eq!(premium_percent, 15.0);
     }
     
+    #[test]
+    fn test_node_collateralization_and_slashing_exposure() {
+        let node = NodeOperatorState {
+            minipools: 2,
+            active_minipools: 2,
+            eth_bonded: 16.0,
+            eth_borrowed: 48.0,
+            rpl_staked: 1_000.0,
+            rpl_effective: 1_000.0,
+        };
+        
+        // 1,000 RPL at 0.006 ETH covers 6 of 48 borrowed ETH
+        assert!((node.collateralization_ratio(0.006).unwrap() - 0.125).abs() < 1e-9);
+        assert!(!node.is_under_collateralized(0.006));
+        assert!(node.is_under_collateralized(0.004));
+        assert_eq!(node.rpl_slashing_exposure(0.006), 1_000.0);
+        assert!((node.rpl_slashing_exposure(0.096) - 500.0).abs() < 1e-9);
+        
+        let no_minipools = NodeOperatorState { eth_borrowed: 0.0, ..node };
+        assert_eq!(no_minipools.collateralization_ratio(0.006), None);
+        assert!(!no_minipools.is_under_collateralized(0.006));
+    }
+    
+    #[test]
+    fn test_node_risk_scores_add_to_the_staking_model() {
+        assert!((RocketPoolAdapter::node_risk_score("minipool", false) - 0.4).abs() < 1e-12);
+        assert!((RocketPoolAdapter::node_risk_score("rpl_collateral", false) - 0.6).abs() < 1e-12);
+        assert!((RocketPoolAdapter::node_risk_score("rpl_collateral", true) - 0.8).abs() < 1e-12);
+    }
+    
     #[test]
     fn test_apy_calculations() {
         let base_eth_apy = 4.0;
@@ -0,0 +1,280 @@
+use alloy::{
+    primitives::{Address, U256},
+    sol,
+};
+use async_trait::async_trait;
+use crate::adapters::aave_v3::{self, AaveV3Deployment, AaveV3Reader};
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
+use crate::blockchain::EthereumClient;
+use crate::risk::{RiskInputs, RiskModelRegistry};
+use crate::services::{subgraph_id_from_env, BalanceSemantics, HealthFactorThresholds, PriceAggregator, PriceSnapshotStore, IERC20};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+// Sky savings vaults
+sol! {
+    interface ISavingsVault {
+        function convertToAssets(uint256 shares) external view returns (uint256);
+    }
+
+    interface IPot {
+        function dsr() external view returns (uint256);
+    }
+
+    interface ISUsds {
+        function ssr() external view returns (uint256);
+    }
+}
+
+/// An ERC4626 savings vault paying the Sky savings rate
+#[derive(Debug, Clone, Copy)]
+struct SavingsVault {
+    symbol: &'static str,
+    underlying: &'static str,
+    underlying_coin_id: &'static str,
+    address: Address,
+}
+
+#[derive(Debug, Clone)]
+struct CachedPositions {
+    positions: Vec<Position>,
+    cached_at: SystemTime,
+}
+
+/// Spark adapter: SparkLend (an Aave V3 fork) plus sDAI / sUSDS savings deposits
+pub struct SparkAdapter {
+    client: EthereumClient,
+    lend: AaveV3Reader,
+    sdai: SavingsVault,
+    susds: SavingsVault,
+    pot_address: Address,
+    position_cache: Arc<Mutex<HashMap<Address, CachedPositions>>>,
+    prices: Arc<PriceAggregator>,
+    price_snapshots: Arc<PriceSnapshotStore>,
+    health_thresholds: HealthFactorThresholds,
+}
+
+impl SparkAdapter {
+    const CHAIN_ID: u64 = 1;
+    const POOL_ADDRESS: &'static str = "0xC13e21B648A5Ee794902342038FF3aDAB66BE987";
+    const DATA_PROVIDER_ADDRESS: &'static str = "0xFc21d6d146E6086B8359705C8b28512a983db0cb";
+    const ORACLE_ADDRESS: &'static str = "0x8105f69D9C41644c6A0803fDA7D03Aa70996cFD9";
+    const SDAI_ADDRESS: &'static str = "0x83F20F44975D03b1b09e64809B757c47f942BEeA";
+    const SUSDS_ADDRESS: &'static str = "0xa3931d71877C0E7a3148CB7Eb4463524FEc27fbD";
+    /// Maker Pot, which sets the DAI savings rate sDAI accrues
+    const POT_ADDRESS: &'static str = "0x197E90f9FAD81970bA7976f33CbD77088E5D7cf7";
+    const CACHE_DURATION: Duration = Duration::from_secs(300); // 5 minutes
+
+    /// Savings rates are per-second multipliers in ray units
+    const RAY: f64 = 1e27;
+    const SECONDS_PER_YEAR: f64 = 31_536_000.0;
+
+    pub fn new(client: EthereumClient) -> Result<Self, AdapterError> {
+        let parse = |address: &str, name: &str| {
+            Address::from_str(address)
+                .map_err(|e| AdapterError::InvalidData(format!("Invalid {} address: {}", name, e)))
+        };
+
+        let deployment = AaveV3Deployment {
+            pool: parse(Self::POOL_ADDRESS, "Spark pool")?,
+            data_provider: parse(Self::DATA_PROVIDER_ADDRESS, "Spark data provider")?,
+            oracle: parse(Self::ORACLE_ADDRESS, "Spark oracle")?,
+        };
+
+        Ok(Self {
+            lend: AaveV3Reader::new(client.clone(), Self::CHAIN_ID, deployment),
+            client,
+            sdai: SavingsVault {
+                symbol: "sDAI",
+                underlying: "DAI",
+                underlying_coin_id: "dai",
+                address: parse(Self::SDAI_ADDRESS, "sDAI")?,
+            },
+            susds: SavingsVault {
+                symbol: "sUSDS",
+                underlying: "USDS",
+                underlying_coin_id: "usds",
+                address: parse(Self::SUSDS_ADDRESS, "sUSDS")?,
+            },
+            pot_address: parse(Self::POT_ADDRESS, "Pot")?,
+            position_cache: Arc::new(Mutex::new(HashMap::new())),
+            prices: PriceAggregator::global(),
+            price_snapshots: PriceSnapshotStore::global(),
+            health_thresholds: HealthFactorThresholds::from_env(),
+        })
+    }
+
+    /// Savings vault shares held by `user`, valued in the underlying stablecoin
+    async fn get_savings_position(&self, vault: SavingsVault, user: Address) -> Result<Option<Position>, AdapterError> {
+        let shares = self.client.call(vault.address, &IERC20::balanceOfCall { account: user }).await?._0;
+        if shares == U256::ZERO {
+            return Ok(None);
+        }
+
+        let assets = self.client.call(vault.address, &ISavingsVault::convertToAssetsCall { shares }).await?._0;
+        let savings_rate = self.savings_rate(vault).await?;
+        let price = match self.prices.get_price(vault.underlying_coin_id).await {
+            Ok(price) => price,
+            Err(e) => {
+                tracing::warn!("{} price unavailable, assuming $1 peg: {}", vault.underlying, e);
+                1.0
+            }
+        };
+
+        let amount = Self::to_f64(assets, 18);
+        let share_amount = Self::to_f64(shares, 18);
+        let id = position_id("spark", Self::CHAIN_ID, "supply", vault.address, user);
+        // Savings accrue through the share price, so growth in redeemable underlying
+        // since entry is interest and only a depeg moves the price
+        let entry = self.price_snapshots
+            .get_or_record(&id, price, amount, chrono::Utc::now().timestamp() as u64)
+            .attribute(BalanceSemantics::ShareBased, price, amount);
+        let pnl = PnlBreakdown { price_pnl: entry.price_pnl, interest: entry.yield_usd, ..Default::default() };
+
+        Ok(Some(Position {
+            id,
+            protocol: "spark".to_string(),
+            position_type: "supply".to_string(),
+            pair: format!("{}/{}", vault.symbol, vault.underlying),
+            value_usd: amount * price,
+            pnl_usd: pnl.total(),
+            pnl_percentage: if amount > 0.0 { pnl.total() / (amount * price) * 100.0 } else { 0.0 },
+            metadata: serde_json::json!({
+                "market": "savings",
+                "token_address": format!("{:?}", vault.address),
+                "token_symbol": vault.symbol,
+                "underlying_asset": vault.underlying,
+                "shares": share_amount,
+                "underlying_amount": amount,
+                "share_price": if share_amount > 0.0 { amount / share_amount } else { 0.0 },
+                "savings_rate_apy": savings_rate,
+                "pnl_breakdown": pnl,
+                "balance_semantics": BalanceSemantics::ShareBased,
+                // DAI and USDS savings: a stablecoin supply with no utilization to block exits
+                "risk_score": RiskModelRegistry::global().score("supply", &RiskInputs { stablecoin: true, ..Default::default() }),
+            }),
+            last_updated: chrono::Utc::now().timestamp() as u64,
+        }))
+    }
+
+    /// Current savings rate APY percentage: the DSR for sDAI, the SSR for sUSDS
+    async fn savings_rate(&self, vault: SavingsVault) -> Result<f64, AdapterError> {
+        let rate = if vault.address == self.sdai.address {
+            self.client.call(self.pot_address, &IPot::dsrCall {}).await?._0
+        } else {
+            self.client.call(vault.address, &ISUsds::ssrCall {}).await?._0
+        };
+        Ok(Self::per_second_rate_to_apy(rate))
+    }
+
+    fn per_second_rate_to_apy(rate: U256) -> f64 {
+        let per_second = Self::to_f64(rate, 0) / Self::RAY;
+        (per_second.powf(Self::SECONDS_PER_YEAR) - 1.0) * 100.0
+    }
+
+    fn to_f64(value: U256, decimals: i32) -> f64 {
+        let raw: f64 = value.to_string().parse().unwrap_or(0.0);
+        raw / 10f64.powi(decimals)
+    }
+}
+
+#[async_trait]
+impl DeFiAdapter for SparkAdapter {
+    fn protocol_name(&self) -> &'static str {
+        "spark"
+    }
+
+    fn position_types(&self) -> &'static [&'static str] {
+        &["supply", "collateral", "borrow"]
+    }
+
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+
+    fn supports_historical_block(&self) -> bool {
+        true
+    }
+
+    fn subgraph_id(&self) -> Option<String> {
+        subgraph_id_from_env("spark", Self::CHAIN_ID)
+    }
+
+    async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
+        // Check cache first
+        {
+            let cache = self.position_cache.lock().unwrap();
+            if let Some(cached) = cache.get(&address) {
+                let cache_age = cached.cached_at.elapsed().unwrap_or(Duration::from_secs(0));
+                if cache_age < Self::CACHE_DURATION {
+                    return Ok(cached.positions.clone());
+                }
+            }
+        }
+
+        let mut positions = Vec::new();
+
+        if let Some(account) = self.lend.account(address).await? {
+            positions.extend(aave_v3::account_positions(
+                "spark",
+                Self::CHAIN_ID,
+                address,
+                &account,
+                &self.health_thresholds,
+                &self.price_snapshots,
+            ));
+        }
+
+        for vault in [self.sdai, self.susds] {
+            match self.get_savings_position(vault, address).await {
+                Ok(Some(position)) => positions.push(position),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to read {} balance: {}", vault.symbol, e),
+            }
+        }
+
+        // Update cache
+        {
+            let mut cache = self.position_cache.lock().unwrap();
+            cache.insert(address, CachedPositions {
+                positions: positions.clone(),
+                cached_at: SystemTime::now(),
+            });
+        }
+
+        Ok(positions)
+    }
+
+    async fn supports_contract(&self, contract_address: Address) -> bool {
+        let deployment = self.lend.deployment();
+        contract_address == deployment.pool
+            || contract_address == deployment.data_provider
+            || contract_address == self.sdai.address
+            || contract_address == self.susds.address
+            || self.lend.is_cached_reserve(contract_address)
+    }
+
+    async fn get_position_value(&self, position: &Position) -> Result<f64, AdapterError> {
+        Ok(position.value_usd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_addresses_parse() {
+        let client = EthereumClient::new("https://eth.llamarpc.com").unwrap();
+        assert!(SparkAdapter::new(client).is_ok());
+    }
+
+    #[test]
+    fn test_savings_rate_compounds_per_second() {
+        // DSR of 1.000000001547125957863212448 per second is 5% APY
+        let rate = U256::from_str("1000000001547125957863212448").unwrap();
+        assert!((SparkAdapter::per_second_rate_to_apy(rate) - 5.0).abs() < 1e-3);
+    }
+}
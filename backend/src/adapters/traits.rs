@@ -2,6 +2,10 @@ use alloy::primitives::{Address, U256};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
+use crate::blockchain::EthereumClient;
+use crate::risk::RiskAggregation;
+use crate::services::{SubgraphClient, TransactionEvent};
+
 /// Common error type for all DeFi protocol adapters
 #[derive(Debug, thiserror::Error)]
 pub enum AdapterError {
@@ -28,6 +32,65 @@ pub enum AdapterError {
     
     #[error("Network error: {0}")]
     NetworkError(String),
+    
+    #[error("Rate limited: {0}")]
+    RateLimited(String),
+}
+
+impl AdapterError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            AdapterError::Timeout(_) => ErrorKind::RpcTimeout,
+            AdapterError::RateLimited(_) => ErrorKind::RateLimit,
+            AdapterError::RpcError(_) | AdapterError::NetworkError(_) => ErrorKind::Rpc,
+            AdapterError::ContractError(_) => ErrorKind::ContractRevert,
+            AdapterError::InvalidData(_) => ErrorKind::ParseError,
+            AdapterError::UnsupportedProtocol(_) | AdapterError::UnsupportedChain(_) => ErrorKind::Unsupported,
+            AdapterError::CalculationError(_) => ErrorKind::Calculation,
+        }
+    }
+}
+
+/// Category of an adapter failure, so clients can tell transient failures from permanent ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    RpcTimeout,
+    RateLimit,
+    /// Other RPC or transport failure
+    Rpc,
+    ContractRevert,
+    ParseError,
+    Unsupported,
+    Calculation,
+}
+
+impl ErrorKind {
+    /// Whether retrying the same request later may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ErrorKind::RpcTimeout | ErrorKind::RateLimit | ErrorKind::Rpc)
+    }
+}
+
+/// A failure from one protocol's adapter, as reported to API clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolError {
+    pub protocol: String,
+    pub kind: ErrorKind,
+    pub message: String,
+    pub retryable: bool,
+}
+
+impl ProtocolError {
+    pub fn new(protocol: impl Into<String>, error: &AdapterError) -> Self {
+        let kind = error.kind();
+        Self {
+            protocol: protocol.into(),
+            kind,
+            message: error.to_string(),
+            retryable: kind.is_retryable(),
+        }
+    }
 }
 
 /// Represents a DeFi position for any protocol
@@ -61,6 +124,86 @@ pub struct Position {
     pub last_updated: u64,
 }
 
+impl Position {
+    /// Whether the adapter couldn't price a token in this position; such positions are
+    /// reported but left out of USD totals
+    pub fn is_price_unknown(&self) -> bool {
+        self.metadata.get("price_unknown").and_then(|v| v.as_bool()).unwrap_or(false)
+    }
+
+    /// Whether the position was valued while its price sources disagreed
+    pub fn is_price_uncertain(&self) -> bool {
+        self.metadata.get("price_uncertain").and_then(|v| v.as_bool()).unwrap_or(false)
+    }
+
+    /// Where `pnl_usd` came from, as reported under `pnl_breakdown`
+    pub fn pnl_breakdown(&self) -> Option<PnlBreakdown> {
+        serde_json::from_value(self.metadata.get("pnl_breakdown")?.clone()).ok()
+    }
+}
+
+/// Canonical position ID: `{protocol}:{chain_id}:{position_type}:{asset_or_pool}:{owner}`.
+///
+/// Built only from what identifies the position on-chain, never list indices or fetch
+/// order, so a position keeps its ID across fetches. Lowercased so checksummed and plain
+/// hex addresses give the same ID.
+pub fn position_id(protocol: &str, chain_id: u64, position_type: &str, asset_or_pool: impl std::fmt::Display, owner: Address) -> String {
+    format!("{}:{}:{}:{}:{:?}", protocol, chain_id, position_type, asset_or_pool, owner).to_lowercase()
+}
+
+/// Sources of a position's P&L in USD. Costs are negative (interest paid on debt,
+/// impermanent loss), so the components add up to `pnl_usd`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PnlBreakdown {
+    /// Change in value of the tokens held
+    pub price_pnl: f64,
+    /// Trading fees earned as a liquidity provider
+    pub fees_earned: f64,
+    /// Staking and incentive rewards, claimed or pending
+    pub rewards_earned: f64,
+    /// Interest or vault yield earned, negative when paid on debt
+    pub interest: f64,
+    /// Loss against holding the deposited tokens, zero or negative
+    pub impermanent_loss: f64,
+}
+
+impl PnlBreakdown {
+    pub fn total(&self) -> f64 {
+        self.price_pnl + self.fees_earned + self.rewards_earned + self.interest + self.impermanent_loss
+    }
+
+    /// Every component multiplied by `factor`, e.g. to convert currency
+    pub fn scaled(&self, factor: f64) -> Self {
+        Self {
+            price_pnl: self.price_pnl * factor,
+            fees_earned: self.fees_earned * factor,
+            rewards_earned: self.rewards_earned * factor,
+            interest: self.interest * factor,
+            impermanent_loss: self.impermanent_loss * factor,
+        }
+    }
+}
+
+impl std::ops::Add for PnlBreakdown {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            price_pnl: self.price_pnl + other.price_pnl,
+            fees_earned: self.fees_earned + other.fees_earned,
+            rewards_earned: self.rewards_earned + other.rewards_earned,
+            interest: self.interest + other.interest,
+            impermanent_loss: self.impermanent_loss + other.impermanent_loss,
+        }
+    }
+}
+
+impl std::iter::Sum for PnlBreakdown {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |total, breakdown| total + breakdown)
+    }
+}
+
 /// Portfolio summary across all protocols
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PortfolioSummary {
@@ -102,6 +245,49 @@ pub trait DeFiAdapter: Send + Sync {
     
     /// Get real-time price data for position valuation
     async fn get_position_value(&self, position: &Position) -> Result<f64, AdapterError>;
+    
+    /// RPC client the adapter reads chain state through, for connectivity checks
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        None
+    }
+    
+    /// Chain the adapter instance reads from; mainnet unless the adapter is chain-aware
+    fn chain_id(&self) -> u64 {
+        1
+    }
+    
+    /// `position_type` values `fetch_positions` can emit, for protocol discovery
+    fn position_types(&self) -> &'static [&'static str] {
+        &[]
+    }
+    
+    /// Whether every read behind `fetch_positions` goes through `rpc_client`, so a
+    /// client pinned with `EthereumClient::at_block` yields positions as of that block.
+    /// Adapters backed by off-chain APIs only ever see current state.
+    fn supports_historical_block(&self) -> bool {
+        false
+    }
+    
+    /// The Graph subgraph indexing the protocol's account events in the Messari lending
+    /// schema; adapters without one have no transaction history
+    fn subgraph_id(&self) -> Option<String> {
+        None
+    }
+    
+    /// The account's `limit` most recent protocol events, oldest first
+    async fn get_transaction_history(&self, address: Address, limit: usize) -> Result<Vec<TransactionEvent>, AdapterError> {
+        match self.subgraph_id() {
+            Some(subgraph_id) => SubgraphClient::from_subgraph_id(&subgraph_id, None)?
+                .fetch_account_events(address, limit)
+                .await,
+            None => Ok(Vec::new()),
+        }
+    }
+    
+    /// Combine position risk scores into a single score using the given strategy
+    fn calculate_risk_score(&self, positions: &[Position], aggregation: RiskAggregation) -> f64 {
+        aggregation.aggregate(positions)
+    }
 }
 
 /// Price information for tokens
@@ -123,3 +309,56 @@ pub struct PoolInfo {
     pub liquidity: U256,
     pub sqrt_price_x96: U256,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_error_classifies_adapter_errors() {
+        let timeout = ProtocolError::new("uniswap_v3", &AdapterError::Timeout("rpc".to_string()));
+        assert_eq!(timeout.kind, ErrorKind::RpcTimeout);
+        assert!(timeout.retryable);
+
+        let revert = ProtocolError::new("lido", &AdapterError::ContractError("execution reverted".to_string()));
+        assert_eq!(revert.kind, ErrorKind::ContractRevert);
+        assert!(!revert.retryable);
+
+        let json = serde_json::to_value(ProtocolError::new("gmx", &AdapterError::RateLimited("429".to_string()))).unwrap();
+        assert_eq!(json["kind"], "rate_limit");
+    }
+
+    #[test]
+    fn test_position_id_ignores_address_checksum() {
+        let usdc: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".parse().unwrap();
+        let id = position_id("aave_v3", 1, "supply", usdc, Address::ZERO);
+
+        assert_eq!(
+            id,
+            "aave_v3:1:supply:0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48:0x0000000000000000000000000000000000000000"
+        );
+        assert_eq!(id, position_id("aave_v3", 1, "supply", "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48", Address::ZERO));
+    }
+
+    #[test]
+    fn test_pnl_breakdown_reads_back_from_metadata_and_sums() {
+        let fees = PnlBreakdown { fees_earned: 120.0, impermanent_loss: -45.0, ..Default::default() };
+        let position = Position {
+            id: "lp".to_string(),
+            protocol: "uniswap_v3".to_string(),
+            position_type: "liquidity".to_string(),
+            pair: "WETH/USDC".to_string(),
+            value_usd: 10_000.0,
+            pnl_usd: fees.total(),
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({ "pnl_breakdown": fees }),
+            last_updated: 0,
+        };
+
+        assert_eq!(position.pnl_breakdown(), Some(fees));
+        let rewards = PnlBreakdown { rewards_earned: 30.0, ..Default::default() };
+        let total: PnlBreakdown = [fees, rewards].into_iter().sum();
+        assert_eq!(total.total(), 105.0);
+        assert_eq!(total.scaled(2.0).rewards_earned, 60.0);
+    }
+}
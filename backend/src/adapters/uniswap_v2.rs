@@ -3,22 +3,9 @@ use alloy::{
     sol,
 };
 use async_trait::async_trait;
-use crate::adapters::traits::{AdapterError, Position, DeFiAdapter};
-// Commented out broken blockchain import:
-// use crate::blockchain::EthereumClient;
-
-// Placeholder EthereumClient type:
-#[derive(Debug, Clone)]
-pub struct EthereumClient {
-    pub rpc_url: String,
-}
-
-impl EthereumClient {
-    pub fn provider(&self) -> &str {
-        &self.rpc_url
-    }
-}
-
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
+use crate::blockchain::EthereumClient;
+use crate::services::{PriceService, TokenMetadataCache};
 use reqwest;
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -98,6 +85,9 @@ pub struct UniswapV2Adapter {
     http_client: reqwest::Client,
     // Optional CoinGecko API key for price fetching
     coingecko_api_key: Option<String>,
+    // Process-wide ERC20 symbol/name/decimals cache
+    token_metadata: Arc<TokenMetadataCache>,
+    price_service: Arc<PriceService>,
 }
 
 #[allow(dead_code)]
@@ -105,6 +95,7 @@ impl UniswapV2Adapter {
     /// Uniswap V2 Factory and Router addresses on Ethereum mainnet
     const FACTORY_ADDRESS: &'static str = "0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f";
     const ROUTER_ADDRESS: &'static str = "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D";
+    const CHAIN_ID: u64 = 1;
     
     pub fn new(client: EthereumClient) -> Result<Self, AdapterError> {
         let factory_address = Address::from_str(Self::FACTORY_ADDRESS)
@@ -121,6 +112,8 @@ impl UniswapV2Adapter {
             token_cache: Arc::new(Mutex::new(HashMap::new())),
             http_client: reqwest::Client::new(),
             coingecko_api_key: std::env::var("COINGECKO_API_KEY").ok(),
+            token_metadata: TokenMetadataCache::global(),
+            price_service: PriceService::global(),
         })
     }
     
@@ -388,8 +381,9 @@ impl UniswapV2Adapter {
         // }))
     }
     
-    /// Calculate real USD value of a V2 liquidity position
-    async fn calculate_position_value(&self, position: &LiquidityPosition) -> (f64, f64, f64) {
+    /// Calculate real USD value of a V2 liquidity position, and whether a token had no
+    /// price (its leg is then left out of the value)
+    async fn calculate_position_value(&self, position: &LiquidityPosition) -> (f64, f64, f64, bool) {
         tracing::info!(
             pair = %position.pair_address,
             "🚀 Calculating REAL USD value for Uniswap V2 position"
@@ -412,17 +406,11 @@ impl UniswapV2Adapter {
         // };
         
         // Step 2: Get token prices from CoinGecko
-        let token0_price = self.get_token_price_usd(position.token0).await
-            .unwrap_or_else(|e| {
-                tracing::warn!("Failed to get token0 price: {}, using fallback", e);
-                self.get_fallback_price(position.token0)
-            });
-            
-        let token1_price = self.get_token_price_usd(position.token1).await
-            .unwrap_or_else(|e| {
-                tracing::warn!("Failed to get token1 price: {}, using fallback", e);
-                self.get_fallback_price(position.token1)
-            });
+        let token0_price = self.get_token_price_usd(position.token0).await.ok();
+        let token1_price = self.get_token_price_usd(position.token1).await.ok();
+        let price_unknown = token0_price.is_none() || token1_price.is_none();
+        let token0_price = token0_price.unwrap_or(0.0);
+        let token1_price = token1_price.unwrap_or(0.0);
             
         // Step 3: Get token decimals
         let token0_decimals = self.get_token_decimals(position.token0).await.unwrap_or(18);
@@ -460,7 +448,7 @@ impl UniswapV2Adapter {
             "✅ Calculated REAL V2 position value"
         );
         
-        (total_value_usd, pnl_usd, pnl_percentage)
+        (total_value_usd, pnl_usd, pnl_percentage, price_unknown)
     }
     
     /// Enhanced token symbol resolution (same as V3 adapter)
@@ -587,39 +575,16 @@ impl UniswapV2Adapter {
         format!("{}/{}", token0_symbol, token1_symbol)
     }
     
-    /// Get token price from CoinGecko API (same as V3 adapter)
+    /// USD price by contract address; unknown tokens are an error, never a default price
     async fn get_token_price_usd(&self, token_address: Address) -> Result<f64, String> {
-        let addr_str = format!("{:?}", token_address).to_lowercase();
-        
-        // Check if it's a known stablecoin first
-        if self.is_stablecoin(token_address) {
-            return Ok(1.0); // Stablecoins are ~$1
-        }
-        
-        // Build CoinGecko price API URL
-        let base_url = if self.coingecko_api_key.is_some() {
-            "https://pro-api.coingecko.com/api/v3"
-        } else {
-            "https://api.coingecko.com/api/v3"
-        };
-        
-        let url = format!(
-            "{}/simple/token_price/ethereum?contract_addresses={}&vs_currencies=usd",
-            base_url, addr_str
-        );
-        
-        tracing::debug!("Fetching price for {} from: {}", addr_str, url);
-        
-        match self.call_coingecko_price_api(&url).await {
-            Ok(price) => {
-                tracing::info!("Got price for {}: ${}", addr_str, price);
-                Ok(price)
-            }
-            Err(e) => {
-                tracing::warn!("Failed to get price for {}: {}", addr_str, e);
-                Err(e)
-            }
-        }
+        let symbol = self.try_blockchain_symbol_safe(token_address).await.ok();
+        self.price_service
+            .get_token_price(Self::CHAIN_ID, token_address, symbol.as_deref())
+            .await
+            .map_err(|e| {
+                tracing::warn!("No price for {:?}: {}", token_address, e);
+                e.to_string()
+            })
     }
     
     /// Get token decimals (same as V3 adapter)
@@ -630,50 +595,17 @@ impl UniswapV2Adapter {
             return Ok(known_decimals.unwrap());
         }
         
-        tracing::debug!(
-            token_address = %token_address,
-            "Fetching token decimals from blockchain"
-        );
-        
-        // Commented out due to provider trait issues
-        // let contract = IERC20::new(token_address, self.client.provider());
-        
-        // Use timeout for blockchain call
-        // let result = tokio::time::timeout(Duration::from_secs(5), async {
-        //     contract.decimals().call().await
-        // }).await;
-        
-        // Use fallback decimals value since contract calls are commented out
-        let decimals = 18u8; // Default to 18 decimals
-        
-        // match result {
-        //     Ok(Ok(decimals_result)) => {
-        //         let decimals = decimals_result._0;
-        tracing::info!(
-            token_address = %token_address,
-            decimals = %decimals,
-            "Using fallback token decimals"
-        );
-        Ok(decimals)
-        
-        // Commented out match arms due to provider trait issues
-        //     }
-        //     Ok(Err(e)) => {
-        //         tracing::error!(
-        //             token_address = %token_address,
-        //             error = %e,
-        //             "Failed to get decimals from blockchain"
-        //         );
-        //         Err(format!("Contract call failed: {}", e))
-        //     }
-        //     Err(_) => {
-        //         tracing::error!(
-        //             token_address = %token_address,
-        //             "Blockchain call for decimals timed out"
-        //         );
-        //         Err("Timeout".to_string())
-        //     }
-        // }
+        match self.token_metadata.get_or_fetch(&self.client, Self::CHAIN_ID, token_address).await {
+            Ok(metadata) => Ok(metadata.decimals),
+            Err(e) => {
+                tracing::debug!(
+                    token_address = %token_address,
+                    error = %e,
+                    "Using fallback token decimals"
+                );
+                Ok(18)
+            }
+        }
     }
     
     /// Estimate P&L for V2 positions (simplified)
@@ -736,41 +668,6 @@ impl UniswapV2Adapter {
         }
     }
     
-    fn is_stablecoin(&self, _token_address: Address) -> bool {
-        let _addr_str = format!("{:?}", _token_address).to_lowercase();
-        
-        // Known stablecoin addresses on Ethereum
-        matches!(_addr_str.as_str(),
-            "0x6b175474e89094c44da98b954eedeac495271d0f" | // DAI
-            "0xa0b86a33e6c3c8c95f2d8c4e9f8e8e8e8e8e8e" | // USDC  
-            "0xa0b86a33e6c3c8c95f2d8c4e9f8e8e8e8e8e8e8e" | // USDC  
-            "0xdac17f958d2ee523a2206206994597c13d831ec7" | // USDT
-            "0x4fabb145d64652a948d72533023f6e7a623c7c53" | // BUSD
-            "0x8e870d67f660d95d5be530380d0ec0bd388289e1" | // USDP
-            "0x57ab1ec28d129707052df4df418d58a2d46d5f51" | // sUSD
-            "0x956f47f50a910163d8bf957cf5846d573e7f87ca" | // FEI
-            "0x853d955acef822db058eb8505911ed77f175b99e" | // FRAX
-            "0x5f98805a4e8be255a32880fdec7f6728c6568ba0" | // LUSD
-            "0x99d8a9c45b2eca8864373a26d1459e3dff1e17f3"   // MIM
-        )
-    }
-    
-    fn get_fallback_price(&self, token_address: Address) -> f64 {
-        if self.is_stablecoin(token_address) {
-            return 1.0;
-        }
-        
-        let addr_str = format!("{:?}", token_address).to_lowercase();
-        match addr_str.as_str() {
-            "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2" => 4000.0, // WETH
-            "0x1f9840a85d5af5bf1d1762f925bdaddc4201f984" => 15.0,   // UNI
-            "0x2260fac5e5542a773aa44fbcfedf7c193bc2c599" => 100000.0, // WBTC
-            "0x7d1afa7b718fb893db30a3abc0cfc608aacfebb0" => 0.5,    // MATIC
-            "0x514910771af9ca656af840dff83e8264ecf986ca" => 25.0,   // LINK
-            _ => 1.0, // Default fallback
-        }
-    }
-    
     async fn call_coingecko_price_api(&self, url: &str) -> Result<f64, String> {
         let mut request = self.http_client.get(url);
         
@@ -813,35 +710,17 @@ impl UniswapV2Adapter {
     }
     
     async fn try_blockchain_symbol_safe(&self, token_address: Address) -> Result<String, String> {
-        // Commented out due to provider trait issues
-        // let contract = IERC20::new(token_address, self.client.provider());
-        
-        // Use async block to properly handle the future
-        // let result = timeout(Duration::from_secs(5), async {
-        //     contract.symbol().call().await
-        // }).await;
-        
-        // Use fallback symbol since contract calls are commented out
-        let symbol = format!("TOKEN_{}", &token_address.to_string()[2..6].to_uppercase());
+        let metadata = self.token_metadata
+            .get_or_fetch(&self.client, Self::CHAIN_ID, token_address)
+            .await
+            .map_err(|e| format!("Contract call failed: {}", e))?;
         
+        let symbol = metadata.symbol.trim().to_uppercase();
         if Self::is_valid_symbol(&symbol) {
             Ok(symbol)
         } else {
-            Err(format!("Fallback symbol generation failed: {}", symbol))
+            Err(format!("Invalid symbol from blockchain: {}", symbol))
         }
-        
-        // match result {
-        //     Ok(Ok(symbol_result)) => {
-        //         let symbol = symbol_result._0.trim().to_uppercase();
-        //         if Self::is_valid_symbol(&symbol) {
-        //             Ok(symbol)
-        //         } else {
-        //             Err(format!("Invalid symbol from blockchain: {}", symbol))
-        //         }
-        //     }
-        //     Ok(Err(e)) => Err(format!("Contract call failed: {}", e)),
-        //     Err(_) => Err("Blockchain call timed out".to_string()),
-        // }
     }
     
     fn is_valid_symbol(symbol: &str) -> bool {
@@ -883,6 +762,14 @@ impl DeFiAdapter for UniswapV2Adapter {
         "uniswap_v2"
     }
     
+    fn position_types(&self) -> &'static [&'static str] {
+        &["liquidity"]
+    }
+    
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+    
     async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
         tracing::info!(
             user_address = %address,
@@ -927,15 +814,18 @@ impl DeFiAdapter for UniswapV2Adapter {
         
         // Convert liquidity positions to Position structs with real valuation
         for liq_pos in liquidity_positions {
-            let (value_usd, pnl_usd, pnl_percentage) = self.calculate_position_value(&liq_pos).await;
+            let (value_usd, pnl_usd, pnl_percentage, price_unknown) = self.calculate_position_value(&liq_pos).await;
+            // The estimate models fee income; without an entry snapshot there's no price
+            // change or impermanent loss to measure
+            let pnl = PnlBreakdown { fees_earned: pnl_usd, ..Default::default() };
             
             let position = Position {
-                id: format!("uniswap_v2_{}", liq_pos.pair_address),
+                id: position_id("uniswap_v2", Self::CHAIN_ID, "liquidity", liq_pos.pair_address, address),
                 protocol: "uniswap_v2".to_string(),
                 position_type: "liquidity".to_string(),
                 pair: self.resolve_token_pair(liq_pos.token0, liq_pos.token1).await,
-                value_usd: value_usd.max(1.0), // Real calculated value
-                pnl_usd,   // Real P&L calculation
+                value_usd,
+                pnl_usd: pnl.total(),
                 pnl_percentage, // Real P&L percentage
                 metadata: serde_json::json!({
                     "pair_address": format!("{:?}", liq_pos.pair_address),
@@ -944,7 +834,9 @@ impl DeFiAdapter for UniswapV2Adapter {
                     "lp_balance": liq_pos.balance.to_string(),
                     "total_supply": liq_pos.total_supply.to_string(),
                     "pool_share": (liq_pos.balance.to::<u128>() as f64 / liq_pos.total_supply.to::<u128>() as f64 * 100.0),
-                    "protocol_version": "v2"
+                    "protocol_version": "v2",
+                    "price_unknown": price_unknown,
+                    "pnl_breakdown": pnl,
                 }),
                 last_updated: std::time::SystemTime::now()
                     .duration_since(std::time::UNIX_EPOCH)
@@ -1,9 +1,11 @@
 use alloy::{
-    primitives::{Address, U256},
+    primitives::{address, Address, U256},
     sol,
 };
 use async_trait::async_trait;
-use crate::adapters::traits::{AdapterError, Position, DeFiAdapter};
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
+use crate::blockchain::EthereumClient;
+use crate::services::{PriceService, PriceSnapshotStore, TokenMetadataCache};
 use reqwest;
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -12,18 +14,6 @@ use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
 // Removed unused import: use tokio::time::timeout;
 
-// Placeholder EthereumClient
-#[derive(Debug, Clone)]
-pub struct EthereumClient {
-    pub rpc_url: String,
-}
-
-impl EthereumClient {
-    pub fn provider(&self) -> &str {
-        &self.rpc_url
-    }
-}
-
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 struct CoinGeckoToken {
@@ -38,6 +28,46 @@ struct CachedPositions {
     cached_at: SystemTime,
 }
 
+/// Valuation of a single position, including impermanent loss against its entry snapshot
+#[derive(Debug, Clone, Default)]
+struct PositionValuation {
+    value_usd: f64,
+    pnl: PnlBreakdown,
+    pnl_percentage: f64,
+    impermanent_loss_usd: f64,
+    impermanent_loss_percentage: f64,
+    impermanent_loss_realized: bool,
+    entry_observed_at: u64,
+    /// A token had no price or decimals, so `value_usd` leaves it out
+    price_unknown: bool,
+}
+
+/// Where a pool's current tick sits relative to a position's tick range
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RangeStatus {
+    current_tick: i32,
+    /// The position's liquidity is active and earning fees
+    in_range: bool,
+    /// Where the current price sits between the range's lower (0) and upper (100)
+    /// price, clamped when out of range
+    range_position_pct: f64,
+}
+
+impl RangeStatus {
+    fn new(tick_lower: i32, tick_upper: i32, current_tick: i32) -> Self {
+        // Liquidity is active on [tick_lower, tick_upper)
+        let in_range = tick_lower <= current_tick && current_tick < tick_upper;
+        let price = |tick: i32| 1.0001_f64.powi(tick);
+        let (lower, upper) = (price(tick_lower), price(tick_upper));
+        let range_position_pct = if upper > lower {
+            ((price(current_tick) - lower) / (upper - lower) * 100.0).clamp(0.0, 100.0)
+        } else {
+            0.0
+        };
+        Self { current_tick, in_range, range_position_pct }
+    }
+}
+
 // Uniswap V3 contract interfaces
 sol! {
     #[sol(rpc)]
@@ -79,23 +109,32 @@ sol! {
         function token1() external view returns (address);
         function fee() external view returns (uint24);
     }
+
+    interface IUniswapV3Factory {
+        function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address pool);
+    }
 }
 
 pub struct UniswapV3Adapter {
-    #[allow(dead_code)]
     client: EthereumClient,
     position_manager_address: Address,
     position_cache: Arc<Mutex<HashMap<Address, CachedPositions>>>,
+    // Each leg's entry price and amount, keyed by position ID and token
+    price_snapshots: Arc<PriceSnapshotStore>,
     #[allow(dead_code)]
     http_client: reqwest::Client,
     #[allow(dead_code)]
     coingecko_api_key: Option<String>,
+    token_metadata: Arc<TokenMetadataCache>,
+    price_service: Arc<PriceService>,
 }
 
 #[allow(dead_code)]
 impl UniswapV3Adapter {
     const POSITION_MANAGER_ADDRESS: &'static str = "0xC36442b4a4522E871399CD717aBDD847Ab11FE88";
     const CACHE_DURATION: Duration = Duration::from_secs(300); // 5 minutes
+    const CHAIN_ID: u64 = 1;
+    const FACTORY_ADDRESS: Address = address!("1F98431c8aD98523631AE4a59f8E4Da1C7a48A68");
     
     pub fn new(client: EthereumClient) -> Result<Self, AdapterError> {
         let position_manager_address = Address::from_str(Self::POSITION_MANAGER_ADDRESS)
@@ -105,19 +144,65 @@ impl UniswapV3Adapter {
             client,
             position_manager_address,
             position_cache: Arc::new(Mutex::new(HashMap::new())),
+            price_snapshots: PriceSnapshotStore::global(),
             http_client: reqwest::Client::new(),
             coingecko_api_key: std::env::var("COINGECKO_API_KEY").ok(),
+            token_metadata: TokenMetadataCache::global(),
+            price_service: PriceService::global(),
         })
     }
     
-    async fn get_user_token_ids(&self, _address: Address) -> Result<Vec<U256>, AdapterError> {
-        // TODO: Implement NFT balance and token ID fetching
-        Ok(vec![])
+    async fn get_user_token_ids(&self, address: Address) -> Result<Vec<U256>, AdapterError> {
+        let manager = self.position_manager_address;
+        let count = self.client
+            .call(manager, &INonfungiblePositionManager::balanceOfCall { owner: address })
+            .await?
+            ._0;
+
+        let mut token_ids = Vec::new();
+        for index in 0..count.to::<u64>() {
+            let call = INonfungiblePositionManager::tokenOfOwnerByIndexCall { owner: address, index: U256::from(index) };
+            token_ids.push(self.client.call(manager, &call).await?._0);
+        }
+        Ok(token_ids)
     }
     
-    async fn get_position_details(&self, _token_id: U256) -> Result<Option<Position>, AdapterError> {
-        // TODO: Implement position details fetching from contract
-        Ok(None)
+    /// The position held in NFT `token_id`, or `None` once it's fully withdrawn and collected
+    async fn get_position_details(&self, owner: Address, token_id: U256) -> Result<Option<Position>, AdapterError> {
+        let position_data = self.client
+            .call(self.position_manager_address, &INonfungiblePositionManager::positionsCall { tokenId: token_id })
+            .await?
+            ._0;
+        if position_data.liquidity == 0 && position_data.tokensOwed0 == 0 && position_data.tokensOwed1 == 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.build_position(owner, &position_data, token_id).await))
+    }
+    
+    /// The position's pool and where its current tick sits in the position's range
+    async fn read_range_status(&self, position_data: &INonfungiblePositionManager::Position) -> Option<(Address, RangeStatus)> {
+        let read = async {
+            let pool = self.client
+                .call(Self::FACTORY_ADDRESS, &IUniswapV3Factory::getPoolCall {
+                    tokenA: position_data.token0,
+                    tokenB: position_data.token1,
+                    fee: position_data.fee,
+                })
+                .await?
+                .pool;
+            let slot0 = self.client.call(pool, &IUniswapV3Pool::slot0Call {}).await?;
+            Ok::<_, AdapterError>((pool, slot0.tick.as_i32()))
+        };
+        match read.await {
+            Ok((pool, current_tick)) => Some((
+                pool,
+                RangeStatus::new(position_data.tickLower.as_i32(), position_data.tickUpper.as_i32(), current_tick),
+            )),
+            Err(e) => {
+                tracing::warn!("Failed to read pool tick for {}/{}: {}", position_data.token0, position_data.token1, e);
+                None
+            }
+        }
     }
     
     async fn resolve_token_pair(&self, token0: Address, token1: Address) -> String {
@@ -197,9 +282,12 @@ impl UniswapV3Adapter {
         Ok((symbol, token_data.name))
     }
     
-    async fn try_blockchain_symbol(&self, _token_address: Address) -> Result<String, String> {
-        // TODO: Implement blockchain call for symbol
-        let symbol = format!("TOKEN_{}", &_token_address.to_string()[2..6].to_uppercase());
+    async fn try_blockchain_symbol(&self, token_address: Address) -> Result<String, String> {
+        let metadata = self.token_metadata
+            .get_or_fetch(&self.client, Self::CHAIN_ID, token_address)
+            .await
+            .map_err(|e| e.to_string())?;
+        let symbol = metadata.symbol.trim().to_uppercase();
         if Self::is_valid_symbol(&symbol) {
             Ok(symbol)
         } else {
@@ -209,171 +297,246 @@ impl UniswapV3Adapter {
     
     async fn calculate_real_position_value(
         &self,
+        id: &str,
         position_data: &INonfungiblePositionManager::Position,
-        _token_id: U256,
-    ) -> (f64, f64, f64) {
-        let token0_price = self.get_token_price_usd(position_data.token0).await
-            .unwrap_or_else(|_| self.get_fallback_price(position_data.token0));
-            
-        let token1_price = self.get_token_price_usd(position_data.token1).await
-            .unwrap_or_else(|_| self.get_fallback_price(position_data.token1));
+        current_tick: Option<i32>,
+    ) -> PositionValuation {
+        // Amounts can't be scaled without both tokens' decimals, so the position is left
+        // unvalued rather than guessed at and no entry is recorded for it
+        let (token0_decimals, token1_decimals) = match (
+            self.get_token_decimals(position_data.token0).await,
+            self.get_token_decimals(position_data.token1).await,
+        ) {
+            (Ok(token0_decimals), Ok(token1_decimals)) => (token0_decimals, token1_decimals),
+            (Err(e), _) | (_, Err(e)) => {
+                tracing::warn!("Not valuing Uniswap V3 position {}: {}", id, e);
+                return PositionValuation { price_unknown: true, ..Default::default() };
+            }
+        };
+
+        // Unpriced legs count for nothing and flag the position instead of guessing a price
+        let token0_price = self.get_token_price_usd(position_data.token0).await.ok();
+        let token1_price = self.get_token_price_usd(position_data.token1).await.ok();
+        let price_unknown = token0_price.is_none() || token1_price.is_none();
+        let token0_price = token0_price.unwrap_or(0.0);
+        let token1_price = token1_price.unwrap_or(0.0);
+
+        let tick_lower = position_data.tickLower.as_i32();
+        let tick_upper = position_data.tickUpper.as_i32();
 
-        let (amount0, amount1) = self.calculate_token_amounts_from_liquidity(
+        // Token0 in token1 as USD prices have it; the pool's own price splits the
+        // liquidity when it could be read
+        let current_price = if token1_price > 0.0 {
+            token0_price / token1_price
+        } else {
+            1.0
+        };
+        let pool_price = current_tick
+            .map(|tick| Self::tick_to_price(tick, token0_decimals, token1_decimals))
+            .unwrap_or(current_price);
+        let (amount0, amount1) = Self::token_amounts(
             position_data.liquidity,
-            position_data.tickLower.as_i32(),
-            position_data.tickUpper.as_i32(),
-            position_data.token0,
-            position_data.token1,
-        ).await;
+            tick_lower,
+            tick_upper,
+            pool_price,
+            token0_decimals,
+            token1_decimals,
+        );
 
         let token0_value_usd = amount0 * token0_price;
         let token1_value_usd = amount1 * token1_price;
         let total_value_usd = token0_value_usd + token1_value_usd;
 
-        let pnl_percentage = self.estimate_position_pnl(total_value_usd, position_data.fee.to());
-        let pnl_usd = total_value_usd * (pnl_percentage / 100.0);
+        // Each leg's entry is recorded the first time it's seen with a price
+        let now = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let entry0 = self.price_snapshots.get_or_record(&format!("{}:token0", id), token0_price, amount0, now);
+        let entry1 = self.price_snapshots.get_or_record(&format!("{}:token1", id), token1_price, amount1, now);
 
-        (total_value_usd, pnl_usd, pnl_percentage)
-    }
-
-    async fn calculate_token_amounts_from_liquidity(
-        &self,
-        liquidity: u128,
-        tick_lower: i32,
-        tick_upper: i32,
-        token0: Address,
-        token1: Address,
-    ) -> (f64, f64) {
-        let token0_decimals = self.get_token_decimals(token0).await.unwrap_or(18);
-        let token1_decimals = self.get_token_decimals(token1).await.unwrap_or(18);
-        
-        let current_price_ratio = self.estimate_current_price_ratio(token0, token1).await;
-        
-        let price_lower = self.tick_to_price(tick_lower);
-        let price_upper = self.tick_to_price(tick_upper);
-        let current_price = current_price_ratio;
-        
-        let liquidity_f64 = liquidity as f64;
-        
-        let (amount0_wei, amount1_wei) = if current_price < price_lower {
-            let amount0 = liquidity_f64 * (1.0 / price_lower.sqrt() - 1.0 / price_upper.sqrt());
-            (amount0, 0.0)
-        } else if current_price > price_upper {
-            let amount1 = liquidity_f64 * (price_upper.sqrt() - price_lower.sqrt());
-            (0.0, amount1)
+        let entry_price = if entry1.entry_price_usd > 0.0 {
+            entry0.entry_price_usd / entry1.entry_price_usd
         } else {
-            let amount0 = liquidity_f64 * (1.0 / current_price.sqrt() - 1.0 / price_upper.sqrt());
-            let amount1 = liquidity_f64 * (current_price.sqrt() - price_lower.sqrt());
-            (amount0, amount1)
+            1.0
         };
-        
-        let amount0_readable = amount0_wei / 10f64.powi(token0_decimals as i32);
-        let amount1_readable = amount1_wei / 10f64.powi(token1_decimals as i32);
-        
-        (amount0_readable, amount1_readable)
-    }
 
-    fn tick_to_price(&self, tick: i32) -> f64 {
-        1.0001_f64.powi(tick)
+        let il_fraction = Self::calculate_concentrated_impermanent_loss(
+            entry_price,
+            current_price,
+            Self::tick_to_price(tick_lower, token0_decimals, token1_decimals),
+            Self::tick_to_price(tick_upper, token0_decimals, token1_decimals),
+        );
+
+        // IL is measured against simply holding the entry amounts at today's prices
+        let hold_value_usd = entry0.entry_quantity * token0_price + entry1.entry_quantity * token1_price;
+        let impermanent_loss_usd = (-il_fraction * hold_value_usd).max(0.0);
+        let entry_value_usd = entry0.entry_quantity * entry0.entry_price_usd + entry1.entry_quantity * entry1.entry_price_usd;
+
+        // Fees collected into the position but not yet withdrawn
+        let fees_earned = position_data.tokensOwed0 as f64 / 10f64.powi(token0_decimals as i32) * token0_price
+            + position_data.tokensOwed1 as f64 / 10f64.powi(token1_decimals as i32) * token1_price;
+
+        let pnl = PnlBreakdown {
+            price_pnl: hold_value_usd - entry_value_usd,
+            fees_earned,
+            impermanent_loss: -impermanent_loss_usd,
+            ..Default::default()
+        };
+
+        PositionValuation {
+            value_usd: total_value_usd,
+            pnl,
+            pnl_percentage: if entry_value_usd > 0.0 { pnl.total() / entry_value_usd * 100.0 } else { 0.0 },
+            impermanent_loss_usd,
+            impermanent_loss_percentage: -il_fraction * 100.0,
+            // Once all liquidity is withdrawn the loss is locked in
+            impermanent_loss_realized: position_data.liquidity == 0,
+            entry_observed_at: entry0.first_seen.min(entry1.first_seen),
+            price_unknown,
+        }
     }
 
-    async fn estimate_current_price_ratio(&self, token0: Address, token1: Address) -> f64 {
-        let price0 = self.get_token_price_usd(token0).await.unwrap_or(1.0);
-        let price1 = self.get_token_price_usd(token1).await.unwrap_or(1.0);
-        
-        if price1 > 0.0 {
-            price0 / price1
-        } else {
-            1.0
+    async fn build_position(
+        &self,
+        owner: Address,
+        position_data: &INonfungiblePositionManager::Position,
+        token_id: U256,
+    ) -> Position {
+        // The NFT is the position
+        let id = position_id("uniswap_v3", Self::CHAIN_ID, "liquidity", token_id, owner);
+        let pair = self.resolve_token_pair(position_data.token0, position_data.token1).await;
+        let (pool, range) = self.read_range_status(position_data).await.unzip();
+        let valuation = self.calculate_real_position_value(&id, position_data, range.map(|range| range.current_tick)).await;
+        
+        Position {
+            id,
+            protocol: "uniswap_v3".to_string(),
+            position_type: "liquidity".to_string(),
+            pair,
+            value_usd: valuation.value_usd,
+            pnl_usd: valuation.pnl.total(),
+            pnl_percentage: valuation.pnl_percentage,
+            metadata: serde_json::json!({
+                "token_id": token_id.to_string(),
+                "token0_address": format!("{:?}", position_data.token0),
+                "token1_address": format!("{:?}", position_data.token1),
+                "fee_tier": position_data.fee.to::<u32>(),
+                "tick_lower": position_data.tickLower.as_i32(),
+                "tick_upper": position_data.tickUpper.as_i32(),
+                "pool_address": pool.map(|pool| format!("{:?}", pool)),
+                "current_tick": range.map(|range| range.current_tick),
+                "in_range": range.map(|range| range.in_range),
+                "range_position_pct": range.map(|range| range.range_position_pct),
+                "liquidity": position_data.liquidity.to_string(),
+                "impermanent_loss_usd": valuation.impermanent_loss_usd,
+                "impermanent_loss_percentage": valuation.impermanent_loss_percentage,
+                "impermanent_loss_realized": valuation.impermanent_loss_realized,
+                "entry_observed_at": valuation.entry_observed_at,
+                "price_unknown": valuation.price_unknown,
+                "pnl_breakdown": valuation.pnl,
+            }),
+            last_updated: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
         }
     }
 
-    fn estimate_position_pnl(&self, position_value_usd: f64, fee_tier: u32) -> f64 {
-        let base_return = match fee_tier {
-            500 => 2.0,
-            3000 => 5.0,
-            10000 => 8.0,
-            _ => 3.0,
-        };
-        
-        let size_adjustment = if position_value_usd > 100_000.0 {
-            0.7
-        } else if position_value_usd > 10_000.0 {
-            0.9
+    /// Token amounts held per unit of liquidity at `price` for a position bounded by
+    /// `price_lower` and `price_upper` (prices are token1 per token0)
+    fn amounts_per_liquidity(price: f64, price_lower: f64, price_upper: f64) -> (f64, f64) {
+        let sqrt_lower = price_lower.sqrt();
+        let sqrt_upper = price_upper.sqrt();
+        
+        if price <= price_lower {
+            (1.0 / sqrt_lower - 1.0 / sqrt_upper, 0.0)
+        } else if price >= price_upper {
+            (0.0, sqrt_upper - sqrt_lower)
         } else {
-            1.2
-        };
-        
-        base_return * size_adjustment
+            let sqrt_price = price.sqrt();
+            (1.0 / sqrt_price - 1.0 / sqrt_upper, sqrt_price - sqrt_lower)
+        }
     }
 
-    async fn get_token_decimals(&self, token_address: Address) -> Result<u8, String> {
-        if let Some(decimals) = Self::get_known_token_decimals(token_address) {
-            return Ok(decimals);
+    /// Impermanent loss of a concentrated liquidity position as a fraction of the
+    /// hold value (always <= 0). Reduces to the classic `2*sqrt(r)/(1+r) - 1`
+    /// formula when the range is unbounded.
+    fn calculate_concentrated_impermanent_loss(
+        entry_price: f64,
+        current_price: f64,
+        price_lower: f64,
+        price_upper: f64,
+    ) -> f64 {
+        if entry_price <= 0.0 || current_price <= 0.0 || price_lower >= price_upper {
+            return 0.0;
         }
         
-        // TODO: Implement blockchain call for decimals
-        Ok(18) // Default fallback
-    }
-    
-    async fn get_token_price_usd(&self, token_address: Address) -> Result<f64, String> {
-        let addr_str = format!("{:?}", token_address).to_lowercase();
+        let (entry_amount0, entry_amount1) = Self::amounts_per_liquidity(entry_price, price_lower, price_upper);
+        let (current_amount0, current_amount1) = Self::amounts_per_liquidity(current_price, price_lower, price_upper);
         
-        if self.is_stablecoin(token_address) {
-            return Ok(1.0);
-        }
+        let hold_value = entry_amount0 * current_price + entry_amount1;
+        let lp_value = current_amount0 * current_price + current_amount1;
         
-        let base_url = if self.coingecko_api_key.is_some() {
-            "https://pro-api.coingecko.com/api/v3"
-        } else {
-            "https://api.coingecko.com/api/v3"
-        };
+        if hold_value <= 0.0 {
+            return 0.0;
+        }
         
-        let url = format!(
-            "{}/simple/token_price/ethereum?contract_addresses={}&vs_currencies=usd",
-            base_url, addr_str
+        (lp_value / hold_value - 1.0).min(0.0)
+    }
+
+    /// Whole tokens `liquidity` holds over [tick_lower, tick_upper) when one token0 is
+    /// worth `price` token1. Liquidity is defined on raw units, so the math runs on the
+    /// pool's undecimalled price and amounts are scaled down afterwards.
+    fn token_amounts(
+        liquidity: u128,
+        tick_lower: i32,
+        tick_upper: i32,
+        price: f64,
+        token0_decimals: u8,
+        token1_decimals: u8,
+    ) -> (f64, f64) {
+        let decimal_shift = 10f64.powi(token0_decimals as i32 - token1_decimals as i32);
+        let (amount0, amount1) = Self::amounts_per_liquidity(
+            price / decimal_shift,
+            1.0001_f64.powi(tick_lower),
+            1.0001_f64.powi(tick_upper),
         );
-        
-        self.call_coingecko_price_api(&url).await
+
+        (
+            liquidity as f64 * amount0 / 10f64.powi(token0_decimals as i32),
+            liquidity as f64 * amount1 / 10f64.powi(token1_decimals as i32),
+        )
     }
 
-    async fn call_coingecko_price_api(&self, url: &str) -> Result<f64, String> {
-        let mut request = self.http_client.get(url);
-        
-        if let Some(api_key) = &self.coingecko_api_key {
-            request = request.header("X-Cg-Pro-Api-Key", api_key);
+    /// Price of one token0 in token1 at `tick`: `1.0001^tick` is per raw unit, so it's
+    /// shifted by the difference in decimals
+    fn tick_to_price(tick: i32, token0_decimals: u8, token1_decimals: u8) -> f64 {
+        1.0001_f64.powi(tick) * 10f64.powi(token0_decimals as i32 - token1_decimals as i32)
+    }
+
+    async fn get_token_decimals(&self, token_address: Address) -> Result<u8, String> {
+        if let Some(decimals) = Self::get_known_token_decimals(token_address) {
+            return Ok(decimals);
         }
         
-        let response = request
-            .send()
+        self.token_metadata
+            .get_or_fetch(&self.client, Self::CHAIN_ID, token_address)
             .await
-            .map_err(|e| format!("HTTP request failed: {}", e))?;
-            
-        if !response.status().is_success() {
-            return Err(format!("HTTP error {}", response.status()));
-        }
-        
-        let response_text = response
-            .text()
+            .map(|metadata| metadata.decimals)
+            .map_err(|e| format!("decimals of {:?} unavailable: {}", token_address, e))
+    }
+    
+    /// USD price by contract address; unknown tokens are an error, never a default price
+    async fn get_token_price_usd(&self, token_address: Address) -> Result<f64, String> {
+        let symbol = self.try_blockchain_symbol(token_address).await.ok();
+        self.price_service
+            .get_token_price(Self::CHAIN_ID, token_address, symbol.as_deref())
             .await
-            .map_err(|e| format!("Failed to read response: {}", e))?;
-            
-        let json: serde_json::Value = serde_json::from_str(&response_text)
-            .map_err(|e| format!("JSON parse error: {}", e))?;
-            
-        if let Some(prices) = json.as_object() {
-            for (_, price_data) in prices {
-                if let Some(usd_price) = price_data.get("usd") {
-                    if let Some(price) = usd_price.as_f64() {
-                        return Ok(price);
-                    }
-                }
-            }
-        }
-        
-        Err("Price not found in response".to_string())
+            .map_err(|e| e.to_string())
     }
 
+
     // Helper functions
     fn get_known_token_symbol(address: Address) -> String {
         let addr_str = format!("{:?}", address).to_lowercase();
@@ -405,33 +568,6 @@ impl UniswapV3Adapter {
         }
     }
     
-    fn is_stablecoin(&self, token_address: Address) -> bool {
-        let addr_str = format!("{:?}", token_address).to_lowercase();
-        matches!(addr_str.as_str(),
-            "0x6b175474e89094c44da98b954eedeac495271d0f" | // DAI
-            "0xa0b86a33e6c1a8c95f686066c9c9e8c8c8c8c2" | // USDC  
-            "0xdac17f958d2ee523a2206206994597c13d831ec7" | // USDT
-            "0x4fabb145d64652a948d72533023f6e7a623c7c53" | // BUSD
-            "0x853d955acef822db058eb8505911ed77f175b99e" | // FRAX
-            "0x5f98805a4e8be255a32880fdec7f6728c6568ba0"   // LUSD
-        )
-    }
-    
-    fn get_fallback_price(&self, token_address: Address) -> f64 {
-        if self.is_stablecoin(token_address) {
-            return 1.0;
-        }
-        
-        let addr_str = format!("{:?}", token_address).to_lowercase();
-        match addr_str.as_str() {
-            "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2" => 4000.0, // WETH
-            "0x1f9840a85d5af5bf1d1762f925bdaddc4201f984" => 15.0,   // UNI
-            "0x2260fac5e5542a773aa44fbcfedf7c193bc2c599" => 100000.0, // WBTC
-            "0x514910771af9ca656af840dff83e8264ecf986ca" => 25.0,   // LINK
-            _ => 1.0,
-        }
-    }
-    
     fn is_valid_symbol(symbol: &str) -> bool {
         !symbol.is_empty() 
             && symbol.len() <= 12 
@@ -457,6 +593,14 @@ impl DeFiAdapter for UniswapV3Adapter {
         "uniswap_v3"
     }
     
+    fn position_types(&self) -> &'static [&'static str] {
+        &["liquidity"]
+    }
+    
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+    
     async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
         // Check cache first
         {
@@ -478,7 +622,7 @@ impl DeFiAdapter for UniswapV3Adapter {
         let mut positions = Vec::new();
         
         for token_id in token_ids {
-            match self.get_position_details(token_id).await? {
+            match self.get_position_details(address, token_id).await? {
                 Some(position) => positions.push(position),
                 None => continue,
             }
@@ -503,4 +647,70 @@ impl DeFiAdapter for UniswapV3Adapter {
     async fn get_position_value(&self, position: &Position) -> Result<f64, AdapterError> {
         Ok(position.value_usd)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_position_manager_address() {
+        let addr = Address::from_str(UniswapV3Adapter::POSITION_MANAGER_ADDRESS);
+        assert!(addr.is_ok());
+    }
+    
+    #[test]
+    fn test_no_impermanent_loss_without_price_move() {
+        let il = UniswapV3Adapter::calculate_concentrated_impermanent_loss(2000.0, 2000.0, 1500.0, 2500.0);
+        assert!(il.abs() < 1e-12);
+    }
+    
+    #[test]
+    fn test_wide_range_matches_full_range_formula() {
+        // A 2x price move costs ~5.72% in a full-range position
+        let il = UniswapV3Adapter::calculate_concentrated_impermanent_loss(1.0, 2.0, 1e-12, 1e12);
+        let expected = 2.0 * 2.0_f64.sqrt() / 3.0 - 1.0;
+        assert!((il - expected).abs() < 1e-4);
+    }
+    
+    #[test]
+    fn test_concentrated_range_amplifies_loss() {
+        let full_range = UniswapV3Adapter::calculate_concentrated_impermanent_loss(1.0, 1.2, 1e-12, 1e12);
+        let narrow_range = UniswapV3Adapter::calculate_concentrated_impermanent_loss(1.0, 1.2, 0.8, 1.25);
+        assert!(narrow_range < full_range);
+        assert!(narrow_range < 0.0);
+    }
+    
+    #[test]
+    fn test_range_status() {
+        let inside = RangeStatus::new(-600, 600, 0);
+        assert!(inside.in_range);
+        assert!((inside.range_position_pct - 48.5).abs() < 0.1);
+
+        // The upper tick itself is outside the active range
+        let at_upper = RangeStatus::new(-600, 600, 600);
+        assert!(!at_upper.in_range);
+        assert_eq!(at_upper.range_position_pct, 100.0);
+        assert_eq!(RangeStatus::new(-600, 600, -900).range_position_pct, 0.0);
+    }
+    
+    #[test]
+    fn test_tick_prices_are_decimal_adjusted() {
+        // USDC (6 decimals) / WETH (18) with ETH at $2,000: a USDC is 0.0005 WETH
+        let tick = (5e8_f64.ln() / 1.0001_f64.ln()).round() as i32;
+        let price = UniswapV3Adapter::tick_to_price(tick, 6, 18);
+        assert!((price - 0.0005).abs() / 0.0005 < 1e-4);
+
+        // A full-range position holds equal value of each side at the pool price
+        let (usdc, weth) = UniswapV3Adapter::token_amounts(10u128.pow(15), -887_220, 887_220, price, 6, 18);
+        assert!((usdc * price - weth).abs() / weth < 1e-6);
+        assert!((weth * 2_000.0 - usdc).abs() / usdc < 1e-3);
+    }
+
+    #[test]
+    fn test_out_of_range_at_entry_has_no_loss() {
+        // Entirely token0 before and after: behaves like holding
+        let il = UniswapV3Adapter::calculate_concentrated_impermanent_loss(0.5, 0.7, 1.0, 2.0);
+        assert!(il.abs() < 1e-12);
+    }
+}
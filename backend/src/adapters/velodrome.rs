@@ -0,0 +1,668 @@
+use alloy::{
+    primitives::{Address, U256},
+    sol,
+};
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
+use crate::blockchain::EthereumClient;
+use crate::risk::{RiskInputs, RiskModelRegistry};
+use serde::Serialize;
+use crate::services::{PriceAggregator, StakedLpScanner, StakingContract, StakingKind, TokenMetadataCache, IERC20};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+// Velodrome V2 / Aerodrome contract interfaces (same codebase on both chains)
+sol! {
+    interface IVelodromePoolFactory {
+        function isPool(address pool) external view returns (bool);
+    }
+
+    interface IVelodromeVoter {
+        function length() external view returns (uint256);
+        function pools(uint256 index) external view returns (address);
+        function gauges(address pool) external view returns (address);
+        function usedWeights(uint256 tokenId) external view returns (uint256);
+        function poolVote(uint256 tokenId, uint256 index) external view returns (address);
+        function gaugeToFees(address gauge) external view returns (address);
+        function gaugeToBribe(address gauge) external view returns (address);
+    }
+
+    // FeesVotingReward and BribeVotingReward
+    interface IVotingReward {
+        function rewardsListLength() external view returns (uint256);
+        function rewards(uint256 index) external view returns (address);
+        function earned(address token, uint256 tokenId) external view returns (uint256);
+    }
+
+    interface IVelodromePool {
+        function token0() external view returns (address);
+        function token1() external view returns (address);
+        function stable() external view returns (bool);
+        function getReserves() external view returns (uint256 _reserve0, uint256 _reserve1, uint256 _blockTimestampLast);
+    }
+
+    interface IVotingEscrow {
+        function balanceOf(address owner) external view returns (uint256);
+        function ownerToNFTokenIdList(address owner, uint256 index) external view returns (uint256);
+        function locked(uint256 tokenId) external view returns (int128 amount, uint256 end, bool isPermanent);
+        function balanceOfNFT(uint256 tokenId) external view returns (uint256);
+    }
+}
+
+/// Contracts for one ve(3,3) deployment
+#[derive(Debug, Clone)]
+struct VelodromeDeployment {
+    protocol: &'static str,
+    factory: Address,
+    voter: Address,
+    voting_escrow: Address,
+    /// Emissions token locked in the voting escrow and paid out by gauges
+    reward_token: Address,
+    reward_symbol: &'static str,
+    reward_coin_id: &'static str,
+}
+
+/// A gauge-enabled pool, as listed by the voter
+#[derive(Debug, Clone, Copy)]
+struct GaugedPool {
+    pool: Address,
+    gauge: Option<Address>,
+}
+
+/// Which of a pool's voting reward contracts a reward is claimable from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum VotingRewardKind {
+    /// Swap fees of the voted pool
+    Fees,
+    /// Incentives deposited to attract votes
+    Bribe,
+}
+
+/// A token claimable by a veNFT for its votes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct VotingReward {
+    kind: VotingRewardKind,
+    pool: Address,
+    token: Address,
+    symbol: String,
+    amount: f64,
+    /// `None` when the token has no price feed
+    amount_usd: Option<f64>,
+}
+
+/// Priced fees and bribes, in USD
+fn voting_reward_totals(rewards: &[VotingReward]) -> (f64, f64) {
+    let total = |kind: VotingRewardKind| {
+        rewards.iter().filter(|reward| reward.kind == kind).filter_map(|reward| reward.amount_usd).sum::<f64>()
+    };
+    (total(VotingRewardKind::Fees), total(VotingRewardKind::Bribe))
+}
+
+#[derive(Debug, Clone)]
+struct CachedPositions {
+    positions: Vec<Position>,
+    cached_at: SystemTime,
+}
+
+/// Velodrome (Optimism) and Aerodrome (Base) adapter: stable and volatile pool LP tokens,
+/// gauge stakes and veNFT locks
+pub struct VelodromeAdapter {
+    client: EthereumClient,
+    chain_id: u64,
+    deployment: VelodromeDeployment,
+    position_cache: Arc<Mutex<HashMap<Address, CachedPositions>>>,
+    pool_cache: Arc<Mutex<Option<(Vec<GaugedPool>, SystemTime)>>>,
+    staked_lp: StakedLpScanner,
+    prices: Arc<PriceAggregator>,
+    token_metadata: Arc<TokenMetadataCache>,
+}
+
+impl VelodromeAdapter {
+    const CACHE_DURATION: Duration = Duration::from_secs(300); // 5 minutes
+    const POOL_CACHE_DURATION: Duration = Duration::from_secs(3600); // 1 hour
+    /// Concurrent reads while listing pools and scanning a user's balances
+    const POOL_SCAN_CONCURRENCY: usize = 16;
+    /// veNFTs read per wallet
+    const MAX_LOCKS: u64 = 50;
+    /// Pools a veNFT's votes are read for
+    const MAX_VOTED_POOLS: u64 = 30;
+    /// Reward tokens read per voting reward contract
+    const MAX_REWARD_TOKENS: u64 = 20;
+    /// Longest non-permanent lock
+    const MAX_LOCK_SECS: f64 = 4.0 * 365.0 * 86_400.0;
+    /// VELO's price risk over the locked-token baseline, as an emissions token
+    const VELO_PRICE_RISK: f64 = 0.05;
+
+    // Velodrome V2 on Optimism
+    const OPTIMISM_FACTORY: &'static str = "0xF1046053aa5682b4F9a81b5481394DA16BE5FF5a";
+    const OPTIMISM_VOTER: &'static str = "0x41C914ee0c7E1A5edCD0295623e6dC557B5aBf3C";
+    const OPTIMISM_VOTING_ESCROW: &'static str = "0xFAf8FD17D9840595845582fCB047DF13f006787d";
+    const OPTIMISM_VELO: &'static str = "0x9560e827aF36c94D2Ac33a39bCE1Fe78631088Db";
+
+    // Aerodrome on Base
+    const BASE_FACTORY: &'static str = "0x420DD381b31aEf6683db6B902084cB0FFECe40Da";
+    const BASE_VOTER: &'static str = "0x16613524e02ad97eDfeF371bC883F2F5d6C480A5";
+    const BASE_VOTING_ESCROW: &'static str = "0xeBf418Fe2512e7E6bd9b87a8F0f294aCDC67e6B4";
+    const BASE_AERO: &'static str = "0x940181a94A35A4569E4529A3CDfB74e38FD98631";
+
+    pub fn new(client: EthereumClient, chain_id: u64) -> Result<Self, AdapterError> {
+        let deployment = Self::deployment_for_chain(chain_id)?;
+
+        Ok(Self {
+            staked_lp: StakedLpScanner::new(client.clone()),
+            client,
+            chain_id,
+            deployment,
+            position_cache: Arc::new(Mutex::new(HashMap::new())),
+            pool_cache: Arc::new(Mutex::new(None)),
+            prices: PriceAggregator::global(),
+            token_metadata: TokenMetadataCache::global(),
+        })
+    }
+
+    pub fn supported_chains() -> Vec<u64> {
+        vec![10, 8453]
+    }
+
+    /// Protocol name the adapter reports on `chain_id`: velodrome or aerodrome
+    pub fn protocol_for_chain(chain_id: u64) -> Option<&'static str> {
+        Self::deployment_for_chain(chain_id).ok().map(|deployment| deployment.protocol)
+    }
+
+    fn deployment_for_chain(chain_id: u64) -> Result<VelodromeDeployment, AdapterError> {
+        let parse = |address: &str| {
+            Address::from_str(address)
+                .map_err(|e| AdapterError::InvalidData(format!("Invalid Velodrome address {}: {}", address, e)))
+        };
+
+        match chain_id {
+            10 => Ok(VelodromeDeployment {
+                protocol: "velodrome",
+                factory: parse(Self::OPTIMISM_FACTORY)?,
+                voter: parse(Self::OPTIMISM_VOTER)?,
+                voting_escrow: parse(Self::OPTIMISM_VOTING_ESCROW)?,
+                reward_token: parse(Self::OPTIMISM_VELO)?,
+                reward_symbol: "VELO",
+                reward_coin_id: "velodrome-finance",
+            }),
+            8453 => Ok(VelodromeDeployment {
+                protocol: "aerodrome",
+                factory: parse(Self::BASE_FACTORY)?,
+                voter: parse(Self::BASE_VOTER)?,
+                voting_escrow: parse(Self::BASE_VOTING_ESCROW)?,
+                reward_token: parse(Self::BASE_AERO)?,
+                reward_symbol: "AERO",
+                reward_coin_id: "aerodrome-finance",
+            }),
+            _ => Err(AdapterError::UnsupportedChain(format!("Velodrome/Aerodrome is not deployed on chain {}", chain_id))),
+        }
+    }
+
+    /// Every pool registered with the voter and its gauge, cached for an hour
+    async fn get_gauged_pools(&self) -> Result<Vec<GaugedPool>, AdapterError> {
+        {
+            let cache = self.pool_cache.lock().unwrap();
+            if let Some((pools, cached_at)) = cache.as_ref() {
+                if cached_at.elapsed().unwrap_or(Duration::MAX) < Self::POOL_CACHE_DURATION {
+                    return Ok(pools.clone());
+                }
+            }
+        }
+
+        let voter = self.deployment.voter;
+        let count = self.client.call(voter, &IVelodromeVoter::lengthCall {}).await?._0.to::<u64>();
+
+        let lookups = (0..count).map(|index| async move {
+            let pool = self.client.call(voter, &IVelodromeVoter::poolsCall { index: U256::from(index) }).await?._0;
+            let gauge = self.client.call(voter, &IVelodromeVoter::gaugesCall { pool }).await?._0;
+            Ok::<_, AdapterError>(GaugedPool {
+                pool,
+                gauge: (gauge != Address::ZERO).then_some(gauge),
+            })
+        });
+        let pools: Vec<GaugedPool> = stream::iter(lookups)
+            .buffer_unordered(Self::POOL_SCAN_CONCURRENCY)
+            .filter_map(|result| async move { result.ok() })
+            .collect()
+            .await;
+
+        *self.pool_cache.lock().unwrap() = Some((pools.clone(), SystemTime::now()));
+        Ok(pools)
+    }
+
+    async fn get_lp_positions(&self, user: Address) -> Result<Vec<Position>, AdapterError> {
+        let pools = self.get_gauged_pools().await?;
+
+        let scans = pools.iter().map(|pool| async move { self.get_lp_position(user, *pool).await });
+        let results: Vec<Result<Option<Position>, AdapterError>> = stream::iter(scans)
+            .buffer_unordered(Self::POOL_SCAN_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut positions = Vec::new();
+        for result in results {
+            match result {
+                Ok(Some(position)) => positions.push(position),
+                Ok(None) => {}
+                Err(e) => tracing::debug!("Failed to read {} pool: {}", self.deployment.protocol, e),
+            }
+        }
+
+        Ok(positions)
+    }
+
+    /// LP tokens held in the wallet plus those staked in the pool's gauge
+    async fn get_lp_position(&self, user: Address, gauged: GaugedPool) -> Result<Option<Position>, AdapterError> {
+        let pool = gauged.pool;
+        let wallet_balance = self.client.call(pool, &IERC20::balanceOfCall { account: user }).await?._0;
+        let stake = match gauged.gauge {
+            Some(gauge) => Some(self.staked_lp.read_stake(user, &StakingContract {
+                address: gauge,
+                lp_token: pool,
+                kind: StakingKind::VeGauge { reward_token: self.deployment.reward_token },
+            }).await?),
+            None => None,
+        };
+        let staked_balance = stake.as_ref().map_or(U256::ZERO, |stake| stake.staked);
+
+        let lp_balance = wallet_balance + staked_balance;
+        if lp_balance == U256::ZERO {
+            return Ok(None);
+        }
+
+        let (token0, token1, stable, reserves, total_supply) = tokio::try_join!(
+            self.client.call(pool, &IVelodromePool::token0Call {}),
+            self.client.call(pool, &IVelodromePool::token1Call {}),
+            self.client.call(pool, &IVelodromePool::stableCall {}),
+            self.client.call(pool, &IVelodromePool::getReservesCall {}),
+            self.client.call(pool, &IERC20::totalSupplyCall {}),
+        )?;
+        let (token0, token1, stable, total_supply) = (token0._0, token1._0, stable._0, total_supply._0);
+        if total_supply == U256::ZERO {
+            return Ok(None);
+        }
+
+        let metadata0 = self.token_metadata.get_or_fetch(&self.client, self.chain_id, token0).await?;
+        let metadata1 = self.token_metadata.get_or_fetch(&self.client, self.chain_id, token1).await?;
+        let reserve0 = Self::to_f64(reserves._reserve0, metadata0.decimals as i32);
+        let reserve1 = Self::to_f64(reserves._reserve1, metadata1.decimals as i32);
+
+        let price0 = self.get_token_price(&metadata0.symbol).await.ok();
+        let price1 = self.get_token_price(&metadata1.symbol).await.ok();
+        let Some(pool_tvl_usd) = Self::pool_value_usd(reserve0, reserve1, price0, price1, stable) else {
+            return Err(AdapterError::UnsupportedProtocol(format!(
+                "No price feed for {}/{}", metadata0.symbol, metadata1.symbol
+            )));
+        };
+
+        let share = Self::to_f64(lp_balance, 18) / Self::to_f64(total_supply, 18);
+        let value_usd = pool_tvl_usd * share;
+
+        let earned: U256 = stake.iter().flat_map(|stake| &stake.rewards).map(|reward| reward.amount).sum();
+        let (pending_rewards, pending_rewards_usd) = if earned > U256::ZERO {
+            let earned = Self::to_f64(earned, 18);
+            let reward_price = self.prices.get_price(self.deployment.reward_coin_id).await.unwrap_or(0.0);
+            (earned, earned * reward_price)
+        } else {
+            (0.0, 0.0)
+        };
+
+        let pool_type = if stable { "stable" } else { "volatile" };
+        // Staked LPs earn emissions instead of swap fees, which go to voters
+        let pnl = PnlBreakdown { rewards_earned: pending_rewards_usd, ..Default::default() };
+
+        Ok(Some(Position {
+            id: position_id(self.deployment.protocol, self.chain_id, "liquidity", pool, user),
+            protocol: self.deployment.protocol.to_string(),
+            position_type: "liquidity".to_string(),
+            pair: format!("{}/{}", metadata0.symbol, metadata1.symbol),
+            value_usd,
+            pnl_usd: pnl.total(),
+            pnl_percentage: if value_usd > 0.0 { pnl.total() / value_usd * 100.0 } else { 0.0 },
+            metadata: serde_json::json!({
+                "chain_id": self.chain_id,
+                "pool_address": format!("{:?}", pool),
+                "pool_type": pool_type,
+                "token0_address": format!("{:?}", token0),
+                "token1_address": format!("{:?}", token1),
+                "lp_balance": lp_balance.to_string(),
+                "wallet_balance": wallet_balance.to_string(),
+                "staked_balance": staked_balance.to_string(),
+                "gauge_address": gauged.gauge.map(|gauge| format!("{:?}", gauge)),
+                "pool_share": share,
+                "pool_tvl_usd": pool_tvl_usd,
+                "token0_amount": reserve0 * share,
+                "token1_amount": reserve1 * share,
+                "pending_rewards": pending_rewards,
+                "pending_rewards_usd": pending_rewards_usd,
+                "pnl_breakdown": pnl,
+                "reward_token": self.deployment.reward_symbol,
+                "risk_score": Self::lp_risk_score(stable),
+            }),
+            last_updated: chrono::Utc::now().timestamp() as u64,
+        }))
+    }
+
+    /// veNFTs owned by `user`, valued at the locked emissions token
+    async fn get_lock_positions(&self, user: Address) -> Result<Vec<Position>, AdapterError> {
+        let escrow = self.deployment.voting_escrow;
+        let count = self.client.call(escrow, &IVotingEscrow::balanceOfCall { owner: user }).await?._0.to::<u64>();
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let reward_price = self.prices.get_price(self.deployment.reward_coin_id).await?;
+        let now = chrono::Utc::now().timestamp() as u64;
+        let mut positions = Vec::new();
+
+        for index in 0..count.min(Self::MAX_LOCKS) {
+            let token_id = self.client
+                .call(escrow, &IVotingEscrow::ownerToNFTokenIdListCall { owner: user, index: U256::from(index) })
+                .await?
+                ._0;
+            let (locked, voting_power) = tokio::try_join!(
+                self.client.call(escrow, &IVotingEscrow::lockedCall { tokenId: token_id }),
+                self.client.call(escrow, &IVotingEscrow::balanceOfNFTCall { tokenId: token_id }),
+            )?;
+
+            let amount: u128 = locked.amount.try_into().unwrap_or(0);
+            if amount == 0 {
+                continue;
+            }
+            let locked_amount = amount as f64 / 1e18;
+            let lock_end = locked.end.to::<u64>();
+            let remaining_secs = if locked.isPermanent { None } else { Some(lock_end.saturating_sub(now)) };
+
+            // Most of a lock's return is the fees and bribes its votes earn
+            let voting_rewards = match self.read_voting_rewards(token_id).await {
+                Ok(rewards) => Some(rewards),
+                Err(e) => {
+                    tracing::warn!("Failed to read voting rewards for {} veNFT {}: {}", self.deployment.protocol, token_id, e);
+                    None
+                }
+            };
+            let (fees_usd, bribes_usd) = voting_rewards.as_deref().map_or((0.0, 0.0), voting_reward_totals);
+            let pnl = PnlBreakdown { rewards_earned: fees_usd + bribes_usd, ..Default::default() };
+            let value_usd = locked_amount * reward_price;
+
+            positions.push(Position {
+                id: position_id(self.deployment.protocol, self.chain_id, "locked", token_id, user),
+                protocol: self.deployment.protocol.to_string(),
+                position_type: "locked".to_string(),
+                pair: format!("ve{}/{}", self.deployment.reward_symbol, self.deployment.reward_symbol),
+                value_usd,
+                pnl_usd: pnl.total(),
+                pnl_percentage: if value_usd > 0.0 { pnl.total() / value_usd * 100.0 } else { 0.0 },
+                metadata: serde_json::json!({
+                    "chain_id": self.chain_id,
+                    "token_id": token_id.to_string(),
+                    "voting_escrow": format!("{:?}", escrow),
+                    "underlying_asset": self.deployment.reward_symbol,
+                    "locked_amount": locked_amount,
+                    "lock_end": if locked.isPermanent { None } else { Some(lock_end) },
+                    "is_permanent": locked.isPermanent,
+                    "voting_power": Self::to_f64(voting_power._0, 18),
+                    "claimable_bribes_usd": voting_rewards.as_ref().map(|_| bribes_usd),
+                    "claimable_fees_usd": voting_rewards.as_ref().map(|_| fees_usd),
+                    "voting_rewards": voting_rewards,
+                    "pnl_breakdown": pnl,
+                    "risk_score": Self::lock_risk_score(remaining_secs),
+                }),
+                last_updated: now,
+            });
+        }
+
+        Ok(positions)
+    }
+
+    /// Fees and bribes claimable by veNFT `token_id` from every pool it votes for.
+    /// Unclaimed rewards from pools it no longer votes for aren't found.
+    async fn read_voting_rewards(&self, token_id: U256) -> Result<Vec<VotingReward>, AdapterError> {
+        let voter = self.deployment.voter;
+        if self.client.call(voter, &IVelodromeVoter::usedWeightsCall { tokenId: token_id }).await?._0 == U256::ZERO {
+            return Ok(Vec::new());
+        }
+
+        // The voted pool list has no length getter; reading past its end reverts
+        let mut pools = Vec::new();
+        for index in 0..Self::MAX_VOTED_POOLS {
+            match self.client.call(voter, &IVelodromeVoter::poolVoteCall { tokenId: token_id, index: U256::from(index) }).await {
+                Ok(pool) => pools.push(pool._0),
+                Err(AdapterError::ContractError(_)) => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut rewards = Vec::new();
+        for pool in pools {
+            let gauge = self.client.call(voter, &IVelodromeVoter::gaugesCall { pool }).await?._0;
+            if gauge == Address::ZERO {
+                continue;
+            }
+            let (fees, bribe) = tokio::try_join!(
+                self.client.call(voter, &IVelodromeVoter::gaugeToFeesCall { gauge }),
+                self.client.call(voter, &IVelodromeVoter::gaugeToBribeCall { gauge }),
+            )?;
+            for (kind, contract) in [(VotingRewardKind::Fees, fees._0), (VotingRewardKind::Bribe, bribe._0)] {
+                if contract != Address::ZERO {
+                    rewards.extend(self.read_earned(kind, pool, contract, token_id).await?);
+                }
+            }
+        }
+        Ok(rewards)
+    }
+
+    /// Nonzero rewards veNFT `token_id` can claim from one voting reward contract
+    async fn read_earned(&self, kind: VotingRewardKind, pool: Address, contract: Address, token_id: U256) -> Result<Vec<VotingReward>, AdapterError> {
+        let count = self.client.call(contract, &IVotingReward::rewardsListLengthCall {}).await?._0.to::<u64>();
+        let mut rewards = Vec::new();
+
+        for index in 0..count.min(Self::MAX_REWARD_TOKENS) {
+            let token = self.client.call(contract, &IVotingReward::rewardsCall { index: U256::from(index) }).await?._0;
+            let earned = self.client.call(contract, &IVotingReward::earnedCall { token, tokenId: token_id }).await?._0;
+            if earned == U256::ZERO {
+                continue;
+            }
+            let metadata = self.token_metadata.get_or_fetch(&self.client, self.chain_id, token).await?;
+            let amount = Self::to_f64(earned, metadata.decimals as i32);
+            let amount_usd = self.get_token_price(&metadata.symbol).await.ok().map(|price| amount * price);
+            rewards.push(VotingReward { kind, pool, token, symbol: metadata.symbol.clone(), amount, amount_usd });
+        }
+        Ok(rewards)
+    }
+
+    /// Pool value from its reserves. When only one side has a price, the other is
+    /// inferred: constant-product pools hold equal value on both sides, and stable
+    /// pools pair assets trading at par.
+    fn pool_value_usd(reserve0: f64, reserve1: f64, price0: Option<f64>, price1: Option<f64>, stable: bool) -> Option<f64> {
+        match (price0, price1) {
+            (Some(price0), Some(price1)) => Some(reserve0 * price0 + reserve1 * price1),
+            (Some(price), None) if stable => Some((reserve0 + reserve1) * price),
+            (None, Some(price)) if stable => Some((reserve0 + reserve1) * price),
+            (Some(price0), None) => Some(2.0 * reserve0 * price0),
+            (None, Some(price1)) => Some(2.0 * reserve1 * price1),
+            (None, None) => None,
+        }
+    }
+
+    async fn get_token_price(&self, symbol: &str) -> Result<f64, AdapterError> {
+        let coin_id = match symbol.to_uppercase().as_str() {
+            "WETH" | "ETH" => "ethereum",
+            "USDC" | "USDC.E" | "USDBC" => "usd-coin",
+            "USDT" => "tether",
+            "DAI" => "dai",
+            "OP" => "optimism",
+            "VELO" => "velodrome-finance",
+            "AERO" => "aerodrome-finance",
+            "WSTETH" => "wrapped-steth",
+            "CBETH" => "coinbase-wrapped-staked-eth",
+            "RETH" => "rocket-pool-eth",
+            "WBTC" => "wrapped-bitcoin",
+            "CBBTC" => "coinbase-wrapped-btc",
+            "FRAX" => "frax",
+            "LUSD" => "liquity-usd",
+            "SNX" => "havven",
+            _ => {
+                return Err(AdapterError::UnsupportedProtocol(format!("No price feed for token {}", symbol)));
+            }
+        };
+
+        self.prices.get_price(coin_id).await
+    }
+
+    /// Volatile pools carry impermanent loss; stable pools mostly depeg risk
+    fn lp_risk_score(stable: bool) -> f64 {
+        RiskModelRegistry::global().score("liquidity", &RiskInputs { correlated: stable, ..Default::default() })
+    }
+
+    /// Emissions token price risk plus illiquidity for the remaining lock; permanent
+    /// locks can never be withdrawn
+    fn lock_risk_score(remaining_secs: Option<u64>) -> f64 {
+        let inputs = RiskInputs {
+            lock_fraction: remaining_secs.map_or(1.0, |secs| secs as f64 / Self::MAX_LOCK_SECS),
+            premium: Self::VELO_PRICE_RISK,
+            ..Default::default()
+        };
+        RiskModelRegistry::global().score("locked", &inputs)
+    }
+
+    fn to_f64(value: U256, decimals: i32) -> f64 {
+        let raw: f64 = value.to_string().parse().unwrap_or(0.0);
+        raw / 10f64.powi(decimals)
+    }
+}
+
+#[async_trait]
+impl DeFiAdapter for VelodromeAdapter {
+    fn protocol_name(&self) -> &'static str {
+        self.deployment.protocol
+    }
+
+    fn position_types(&self) -> &'static [&'static str] {
+        &["liquidity", "locked"]
+    }
+
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+
+    fn supports_historical_block(&self) -> bool {
+        true
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
+        // Check cache first
+        {
+            let cache = self.position_cache.lock().unwrap();
+            if let Some(cached) = cache.get(&address) {
+                let cache_age = cached.cached_at.elapsed().unwrap_or(Duration::from_secs(0));
+                if cache_age < Self::CACHE_DURATION {
+                    return Ok(cached.positions.clone());
+                }
+            }
+        }
+
+        let mut positions = self.get_lp_positions(address).await?;
+
+        match self.get_lock_positions(address).await {
+            Ok(locks) => positions.extend(locks),
+            Err(e) => tracing::warn!("Failed to read {} veNFT locks: {}", self.deployment.protocol, e),
+        }
+
+        // Update cache
+        {
+            let mut cache = self.position_cache.lock().unwrap();
+            cache.insert(address, CachedPositions {
+                positions: positions.clone(),
+                cached_at: SystemTime::now(),
+            });
+        }
+
+        Ok(positions)
+    }
+
+    async fn supports_contract(&self, contract_address: Address) -> bool {
+        if contract_address == self.deployment.voting_escrow || contract_address == self.deployment.voter {
+            return true;
+        }
+
+        self.client
+            .call(self.deployment.factory, &IVelodromePoolFactory::isPoolCall { pool: contract_address })
+            .await
+            .map(|result| result._0)
+            .unwrap_or(false)
+    }
+
+    async fn get_position_value(&self, position: &Position) -> Result<f64, AdapterError> {
+        Ok(position.value_usd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deployments_per_chain() {
+        for chain_id in VelodromeAdapter::supported_chains() {
+            assert!(VelodromeAdapter::deployment_for_chain(chain_id).is_ok());
+        }
+        assert_eq!(VelodromeAdapter::deployment_for_chain(10).unwrap().protocol, "velodrome");
+        assert_eq!(VelodromeAdapter::deployment_for_chain(8453).unwrap().protocol, "aerodrome");
+        assert!(matches!(
+            VelodromeAdapter::deployment_for_chain(1),
+            Err(AdapterError::UnsupportedChain(_))
+        ));
+    }
+
+    #[test]
+    fn test_pool_value_infers_unpriced_side() {
+        // Volatile pool: 10 WETH at $2000 balances 20000 of an unpriced token
+        let volatile = VelodromeAdapter::pool_value_usd(10.0, 20_000.0, Some(2_000.0), None, false).unwrap();
+        assert!((volatile - 40_000.0).abs() < 1e-9);
+
+        // Stable pool: the unpriced stablecoin is assumed at par
+        let stable = VelodromeAdapter::pool_value_usd(1_000.0, 3_000.0, None, Some(1.0), true).unwrap();
+        assert!((stable - 4_000.0).abs() < 1e-9);
+
+        assert!(VelodromeAdapter::pool_value_usd(1.0, 1.0, None, None, false).is_none());
+    }
+
+    #[test]
+    fn test_voting_rewards_split_into_fees_and_bribes() {
+        let reward = |kind, amount_usd| VotingReward {
+            kind,
+            pool: Address::ZERO,
+            token: Address::ZERO,
+            symbol: "USDC".to_string(),
+            amount: 1.0,
+            amount_usd,
+        };
+        let rewards = [
+            reward(VotingRewardKind::Fees, Some(12.0)),
+            reward(VotingRewardKind::Bribe, Some(30.0)),
+            reward(VotingRewardKind::Bribe, Some(5.0)),
+            // Unpriced bribes are listed but not counted
+            reward(VotingRewardKind::Bribe, None),
+        ];
+
+        assert_eq!(voting_reward_totals(&rewards), (12.0, 35.0));
+        assert_eq!(voting_reward_totals(&[]), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_permanent_locks_carry_max_lock_risk() {
+        let permanent = VelodromeAdapter::lock_risk_score(None);
+        let expiring = VelodromeAdapter::lock_risk_score(Some(0));
+        assert!((permanent - 0.75).abs() < 1e-12);
+        assert!((expiring - 0.45).abs() < 1e-12);
+    }
+}
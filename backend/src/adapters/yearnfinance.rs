@@ -3,20 +3,18 @@ use alloy::{
     sol,
 };
 use async_trait::async_trait;
-use crate::adapters::traits::{AdapterError, Position, DeFiAdapter};
+use crate::adapters::traits::{AdapterError, PnlBreakdown, position_id, Position, DeFiAdapter};
+use crate::blockchain::EthereumClient;
+use crate::services::{PriceAggregator, TokenMetadataCache};
+use futures::stream::{self, StreamExt};
 use reqwest;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
 use tokio::time::timeout;
 
-#[derive(Debug, Clone)]
-pub struct EthereumClient {
-    pub rpc_url: String,
-}
-
 #[derive(Debug, Deserialize, Clone)]
 #[allow(dead_code)]
 struct YearnVault {
@@ -142,6 +140,26 @@ struct YearnEarningsData {
     earnings_usd: f64,
 }
 
+/// Vault generation, which determines how shares convert to the underlying asset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum VaultGeneration {
+    /// Vyper V2 vaults: `pricePerShare()` scaled by the vault's decimals
+    V2,
+    /// ERC4626 V3 vaults: `convertToAssets(shares)` in raw asset units
+    V3,
+}
+
+impl VaultGeneration {
+    fn from_api_version(version: &str) -> Self {
+        if version.trim().trim_start_matches('v').starts_with('3') {
+            VaultGeneration::V3
+        } else {
+            VaultGeneration::V2
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 struct YearnPosition {
@@ -149,6 +167,7 @@ struct YearnPosition {
     vault_name: String,
     vault_symbol: String,
     vault_version: String,
+    generation: VaultGeneration,
     vault_type: String,
     category: String,
     token: YearnToken,
@@ -171,6 +190,8 @@ struct CachedYearnData {
     vaults: Vec<YearnVault>,
     vault_map: HashMap<String, YearnVault>,
     earnings: HashMap<String, YearnEarningsData>,
+    /// Endorsed V3 vaults from the on-chain registry that the API doesn't list
+    v3_vaults: Vec<Address>,
     cached_at: SystemTime,
 }
 
@@ -189,6 +210,11 @@ sol! {
         function symbol() external view returns (string memory);
         function name() external view returns (string memory);
         function token() external view returns (address);
+        function apiVersion() external view returns (string memory);
+        // ERC4626 (V3)
+        function asset() external view returns (address);
+        function totalAssets() external view returns (uint256);
+        function convertToAssets(uint256 shares) external view returns (uint256);
     }
     
     #[sol(rpc)]
@@ -196,6 +222,11 @@ sol! {
         function latestVault(address token) external view returns (address);
         function isRegistered(address vault) external view returns (bool);
     }
+
+    #[sol(rpc)]
+    interface IYearnV3Registry {
+        function getAllEndorsedVaults() external view returns (address[][] memory);
+    }
 }
 
 pub struct YearnAdapter {
@@ -205,12 +236,17 @@ pub struct YearnAdapter {
     vault_cache: Arc<Mutex<Option<CachedYearnData>>>,
     position_cache: Arc<Mutex<HashMap<Address, CachedPositions>>>,
     http_client: reqwest::Client,
+    prices: Arc<PriceAggregator>,
+    token_metadata: Arc<TokenMetadataCache>,
     #[allow(dead_code)]
     registry_address: Option<Address>,
+    v3_registry_address: Option<Address>,
 }
 
 impl YearnAdapter {
     const YEARN_API_BASE: &'static str = "https://api.yearn.finance";
+    /// Concurrent `balanceOf` reads while scanning vaults for a user
+    const VAULT_SCAN_CONCURRENCY: usize = 16;
     
     fn get_registry_address(chain_id: u64) -> Option<Address> {
         match chain_id {
@@ -221,6 +257,14 @@ impl YearnAdapter {
         }
     }
     
+    /// V3 registry, deployed at the same address on every supported chain
+    fn get_v3_registry_address(chain_id: u64) -> Option<Address> {
+        match chain_id {
+            1 | 137 | 42161 | 8453 => Address::from_str("0xd40ecF29e001c76Dcc4cC0D9cd50520CE845B038").ok(),
+            _ => None,
+        }
+    }
+    
     fn get_chain_name(chain_id: u64) -> &'static str {
         match chain_id {
             1 => "ethereum",
@@ -228,13 +272,20 @@ impl YearnAdapter {
             42161 => "arbitrum",
             10 => "optimism",
             137 => "polygon",
+            8453 => "base",
             _ => "ethereum",
         }
     }
     
+    /// Chains with a V2 or V3 registry, or vaults listed by the yearn API
+    pub fn supported_chains() -> Vec<u64> {
+        vec![1, 10, 137, 250, 8453, 42161]
+    }
+    
     pub fn new(client: EthereumClient, chain_id: Option<u64>) -> Result<Self, AdapterError> {
         let chain_id = chain_id.unwrap_or(1);
         let registry_address = Self::get_registry_address(chain_id);
+        let v3_registry_address = Self::get_v3_registry_address(chain_id);
         
         Ok(Self {
             client,
@@ -246,7 +297,10 @@ impl YearnAdapter {
                 .user_agent("DeFi-Adapter/1.0")
                 .build()
                 .map_err(|e| AdapterError::RpcError(format!("Failed to create HTTP client: {}", e)))?,
+            prices: PriceAggregator::global(),
+            token_metadata: TokenMetadataCache::global(),
             registry_address,
+            v3_registry_address,
         })
     }
     
@@ -262,10 +316,11 @@ impl YearnAdapter {
             }
         }
         
-        // Fetch vaults and earnings concurrently
-        let (vaults_result, earnings_result) = tokio::join!(
+        // Fetch vaults, earnings and the V3 registry concurrently
+        let (vaults_result, earnings_result, v3_result) = tokio::join!(
             self.fetch_vaults(),
-            self.fetch_earnings()
+            self.fetch_earnings(),
+            self.fetch_v3_registry_vaults()
         );
         
         let vaults = vaults_result?;
@@ -273,13 +328,25 @@ impl YearnAdapter {
         
         let mut vault_map = HashMap::new();
         for vault in &vaults {
-            vault_map.insert(vault.address.clone(), vault.clone());
+            vault_map.insert(vault.address.to_lowercase(), vault.clone());
         }
         
+        let v3_vaults = match v3_result {
+            Ok(addresses) => addresses
+                .into_iter()
+                .filter(|address| !vault_map.contains_key(&address.to_string().to_lowercase()))
+                .collect(),
+            Err(e) => {
+                tracing::warn!("Failed to read Yearn V3 registry: {}", e);
+                Vec::new()
+            }
+        };
+        
         let cached_data = CachedYearnData {
             vaults,
             vault_map,
             earnings,
+            v3_vaults,
             cached_at: SystemTime::now(),
         };
         
@@ -345,58 +412,77 @@ impl YearnAdapter {
         Ok(earnings.vault_earnings)
     }
     
+    /// Endorsed vaults from the V3 registry, flattened across vault types
+    async fn fetch_v3_registry_vaults(&self) -> Result<Vec<Address>, AdapterError> {
+        let Some(registry) = self.v3_registry_address else {
+            return Ok(Vec::new());
+        };
+        
+        let endorsed = self.client
+            .call(registry, &IYearnV3Registry::getAllEndorsedVaultsCall {})
+            .await?
+            ._0;
+        
+        Ok(endorsed.into_iter().flatten().collect())
+    }
+    
     async fn get_user_yearn_positions(&self, address: Address) -> Result<Vec<YearnPosition>, AdapterError> {
         let cached_data = self.fetch_all_vaults_data().await?;
-        let mut positions = Vec::new();
         
-        for vault in &cached_data.vaults {
-            if let Ok(vault_address) = Address::from_str(&vault.address) {
-                match self.get_vault_position(address, vault, vault_address).await {
-                    Ok(Some(position)) => positions.push(position),
-                    Ok(None) => {},
-                    Err(_) => {},
-                }
-            }
-        }
+        let api_vaults = cached_data.vaults.iter().filter_map(|vault| {
+            let vault_address = Address::from_str(&vault.address).ok()?;
+            Some(async move { self.get_vault_position(address, vault, vault_address).await })
+        });
+        let mut positions: Vec<YearnPosition> = stream::iter(api_vaults)
+            .buffer_unordered(Self::VAULT_SCAN_CONCURRENCY)
+            .filter_map(|result| async move { result.ok().flatten() })
+            .collect()
+            .await;
+        
+        let registry_vaults = cached_data.v3_vaults.iter().map(|vault_address| async move {
+            self.get_v3_registry_position(address, *vault_address).await
+        });
+        let v3_positions: Vec<YearnPosition> = stream::iter(registry_vaults)
+            .buffer_unordered(Self::VAULT_SCAN_CONCURRENCY)
+            .filter_map(|result| async move { result.ok().flatten() })
+            .collect()
+            .await;
+        positions.extend(v3_positions);
         
         Ok(positions)
     }
     
     async fn get_vault_position(
         &self,
-        _user_address: Address,
+        user_address: Address,
         vault: &YearnVault,
         vault_address: Address,
     ) -> Result<Option<YearnPosition>, AdapterError> {
-        // Placeholder values - replace with actual contract calls
-        let shares = U256::from(1000u64);
+        let shares = self.client
+            .call(vault_address, &IYearnVault::balanceOfCall { account: user_address })
+            .await?
+            ._0;
         
         if shares == U256::ZERO {
             return Ok(None);
         }
         
-        let price_per_share_raw = U256::from(1050000000000000000u64); // 1.05 in 18 decimals
-        let price_per_share = price_per_share_raw.try_into().unwrap_or(0u64) as f64 / 10f64.powi(vault.decimals as i32);
-        
-        let shares_f64 = shares.try_into().unwrap_or(0u64) as f64 / 10f64.powi(vault.decimals as i32);
-        let underlying_balance = shares_f64 * price_per_share;
-        let underlying_balance_raw = U256::from((underlying_balance * 10f64.powi(vault.token.decimals as i32)) as u64);
-        
-        let balance_f64 = shares_f64 * price_per_share;
-        let balance_raw = U256::from((balance_f64 * 10f64.powi(vault.token.decimals as i32)) as u64);
+        let generation = VaultGeneration::from_api_version(&vault.version);
+        let underlying_balance = self.shares_to_assets(generation, vault_address, shares, vault.decimals).await?;
         
         Ok(Some(YearnPosition {
             vault_address,
             vault_name: vault.name.clone(),
             vault_symbol: vault.symbol.clone(),
             vault_version: vault.version.clone(),
+            generation,
             vault_type: vault.vault_type.clone(),
             category: vault.category.clone(),
             token: vault.token.clone(),
-            balance: balance_raw,
+            balance: underlying_balance,
             shares,
-            underlying_balance: underlying_balance_raw,
-            price_per_share,
+            underlying_balance,
+            price_per_share: Self::price_per_share(shares, underlying_balance, vault.decimals, vault.token.decimals),
             net_apy: vault.apy.net_apy,
             gross_apr: vault.apy.gross_apr,
             strategies: vault.strategies.clone(),
@@ -408,8 +494,114 @@ impl YearnAdapter {
         }))
     }
     
+    /// Position in a V3 vault known only from the registry, described entirely on-chain
+    async fn get_v3_registry_position(
+        &self,
+        user_address: Address,
+        vault_address: Address,
+    ) -> Result<Option<YearnPosition>, AdapterError> {
+        let shares = self.client
+            .call(vault_address, &IYearnVault::balanceOfCall { account: user_address })
+            .await?
+            ._0;
+        
+        if shares == U256::ZERO {
+            return Ok(None);
+        }
+        
+        let (asset, name, symbol, decimals, api_version, total_assets) = tokio::try_join!(
+            self.client.call(vault_address, &IYearnVault::assetCall {}),
+            self.client.call(vault_address, &IYearnVault::nameCall {}),
+            self.client.call(vault_address, &IYearnVault::symbolCall {}),
+            self.client.call(vault_address, &IYearnVault::decimalsCall {}),
+            self.client.call(vault_address, &IYearnVault::apiVersionCall {}),
+            self.client.call(vault_address, &IYearnVault::totalAssetsCall {}),
+        )?;
+        let asset_metadata = self.token_metadata.get_or_fetch(&self.client, self.chain_id, asset._0).await?;
+        let underlying_balance = self.shares_to_assets(VaultGeneration::V3, vault_address, shares, decimals._0).await?;
+        
+        Ok(Some(YearnPosition {
+            vault_address,
+            vault_name: name._0,
+            vault_symbol: symbol._0,
+            vault_version: api_version._0,
+            generation: VaultGeneration::V3,
+            vault_type: "Standard".to_string(),
+            category: String::new(),
+            token: YearnToken {
+                address: format!("{:?}", asset._0),
+                name: asset_metadata.name,
+                symbol: asset_metadata.symbol,
+                decimals: asset_metadata.decimals,
+            },
+            balance: underlying_balance,
+            shares,
+            underlying_balance,
+            price_per_share: Self::price_per_share(shares, underlying_balance, decimals._0, asset_metadata.decimals),
+            net_apy: 0.0,
+            gross_apr: 0.0,
+            strategies: Vec::new(),
+            fees: YearnFees {
+                performance: 0.0,
+                withdrawal: 0.0,
+                management: 0.0,
+            },
+            tvl: YearnTVL {
+                total_assets: total_assets._0.to_string(),
+                total_assets_usd: 0.0,
+                tvl: 0.0,
+            },
+            chain_id: self.chain_id,
+            is_migrable: false,
+            migration_target: None,
+        }))
+    }
+    
+    /// Underlying asset amount (raw units) redeemable for `shares`
+    async fn shares_to_assets(
+        &self,
+        generation: VaultGeneration,
+        vault_address: Address,
+        shares: U256,
+        vault_decimals: u8,
+    ) -> Result<U256, AdapterError> {
+        match generation {
+            VaultGeneration::V3 => Ok(self.client
+                .call(vault_address, &IYearnVault::convertToAssetsCall { shares })
+                .await?
+                ._0),
+            VaultGeneration::V2 => {
+                let price_per_share = self.client
+                    .call(vault_address, &IYearnVault::pricePerShareCall {})
+                    .await?
+                    ._0;
+                Ok(Self::v2_shares_to_assets(shares, price_per_share, vault_decimals))
+            }
+        }
+    }
+    
+    /// V2 `pricePerShare` is the underlying amount for one whole share (10^decimals units)
+    fn v2_shares_to_assets(shares: U256, price_per_share: U256, vault_decimals: u8) -> U256 {
+        shares * price_per_share / U256::from(10u64).pow(U256::from(vault_decimals))
+    }
+    
+    /// Underlying tokens per whole share
+    fn price_per_share(shares: U256, assets: U256, vault_decimals: u8, token_decimals: u8) -> f64 {
+        let shares = Self::to_f64(shares, vault_decimals);
+        if shares > 0.0 {
+            Self::to_f64(assets, token_decimals) / shares
+        } else {
+            0.0
+        }
+    }
+    
+    fn to_f64(value: U256, decimals: u8) -> f64 {
+        let raw: f64 = value.to_string().parse().unwrap_or(0.0);
+        raw / 10f64.powi(decimals as i32)
+    }
+    
     async fn calculate_position_value(&self, position: &YearnPosition, cached_data: &CachedYearnData) -> (f64, f64, f64) {
-        let balance_f64 = position.balance.try_into().unwrap_or(0u64) as f64 / 10f64.powi(position.token.decimals as i32);
+        let balance_f64 = Self::to_f64(position.balance, position.token.decimals);
         
         let token_price = match position.token.symbol.to_uppercase().as_str() {
             "WETH" | "ETH" => self.get_token_price("ethereum").await.unwrap_or(4000.0),
@@ -422,7 +614,7 @@ impl YearnAdapter {
                 // Fallback: estimate from vault TVL
                 if position.tvl.total_assets_usd > 0.0 {
                     let total_assets_f64 = if let Ok(total_assets_raw) = U256::from_str(&position.tvl.total_assets) {
-                        total_assets_raw.try_into().unwrap_or(0u64) as f64 / 10f64.powi(position.token.decimals as i32)
+                        Self::to_f64(total_assets_raw, position.token.decimals)
                     } else {
                         position.tvl.tvl
                     };
@@ -451,26 +643,13 @@ impl YearnAdapter {
     }
     
     async fn get_token_price(&self, coin_id: &str) -> Result<f64, AdapterError> {
-        let url = format!("https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies=usd", coin_id);
-        
-        if let Ok(response) = timeout(Duration::from_secs(10), self.http_client.get(&url).send()).await {
-            if let Ok(response) = response {
-                if let Ok(data) = response.json::<serde_json::Value>().await {
-                    if let Some(coin_data) = data.get(coin_id) {
-                        if let Some(price) = coin_data.get("usd").and_then(|p| p.as_f64()) {
-                            return Ok(price);
-                        }
-                    }
-                }
-            }
-        }
-        
-        Err(AdapterError::RpcError("Failed to get token price from CoinGecko".to_string()))
+        self.prices.get_price(coin_id).await
     }
     
     async fn is_yearn_vault(&self, vault_address: Address) -> Result<bool, AdapterError> {
         let cached_data = self.fetch_all_vaults_data().await?;
-        Ok(cached_data.vault_map.contains_key(&vault_address.to_string().to_lowercase()))
+        Ok(cached_data.vault_map.contains_key(&vault_address.to_string().to_lowercase())
+            || cached_data.v3_vaults.contains(&vault_address))
     }
 }
 
@@ -479,6 +658,19 @@ impl DeFiAdapter for YearnAdapter {
     fn protocol_name(&self) -> &'static str {
         "Yearn Finance"
     }
+    
+    /// Vault types come from Yearn's API; these are the ones it currently reports
+    fn position_types(&self) -> &'static [&'static str] {
+        &["Yearn Standard Vault", "Yearn Automated Vault", "Yearn Experimental Vault"]
+    }
+    
+    fn rpc_client(&self) -> Option<&EthereumClient> {
+        Some(&self.client)
+    }
+    
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
 
     async fn fetch_positions(&self, address: Address) -> Result<Vec<Position>, AdapterError> {
         // Check position cache (5-minute cache)
@@ -500,6 +692,7 @@ impl DeFiAdapter for YearnAdapter {
         for yearn_pos in yearn_positions {
             let (base_value_usd, earnings_usd, apy) = self.calculate_position_value(&yearn_pos, &cached_data).await;
             let total_value_usd = base_value_usd + earnings_usd;
+            let pnl = PnlBreakdown { interest: earnings_usd, ..Default::default() };
             
             let strategies_desc = if yearn_pos.strategies.is_empty() {
                 "Strategy information unavailable".to_string()
@@ -512,18 +705,23 @@ impl DeFiAdapter for YearnAdapter {
             };
             
             let position = Position {
-                id: format!("yearn_{}_{}", yearn_pos.vault_address, address),
+                id: position_id("yearn_finance", self.chain_id, "vault", yearn_pos.vault_address, address),
                 protocol: self.protocol_name().to_string(),
                 position_type: format!("Yearn {} Vault", yearn_pos.vault_type),
                 pair: format!("{}/{}", yearn_pos.token.symbol, "USD"),
                 value_usd: total_value_usd,
-                pnl_usd: 0.0,
-                pnl_percentage: 0.0,
+                pnl_usd: pnl.total(),
+                pnl_percentage: if base_value_usd > 0.0 { pnl.total() / base_value_usd * 100.0 } else { 0.0 },
                 metadata: serde_json::json!({
                     "vault_name": yearn_pos.vault_name,
                     "vault_symbol": yearn_pos.vault_symbol,
                     "vault_type": yearn_pos.vault_type,
                     "vault_version": yearn_pos.vault_version,
+                    "vault_generation": yearn_pos.generation,
+                    "pnl_breakdown": pnl,
+                    "erc4626": yearn_pos.generation == VaultGeneration::V3,
+                    "shares": yearn_pos.shares.to_string(),
+                    "price_per_share": yearn_pos.price_per_share,
                     "vault_address": yearn_pos.vault_address.to_string(),
                     "asset_symbol": yearn_pos.token.symbol,
                     "asset_address": yearn_pos.token.address,
@@ -570,9 +768,7 @@ mod tests {
 
     #[test]
     fn test_chain_configurations() {
-        let supported_chains = vec![1, 250, 42161, 10, 137];
-        
-        for chain_id in supported_chains {
+        for chain_id in YearnAdapter::supported_chains() {
             let chain_name = YearnAdapter::get_chain_name(chain_id);
             assert!(!chain_name.is_empty());
             
@@ -580,5 +776,26 @@ mod tests {
                 assert!(YearnAdapter::get_registry_address(chain_id).is_some());
             }
         }
+        
+        assert!(YearnAdapter::get_v3_registry_address(1).is_some());
+        assert!(YearnAdapter::get_v3_registry_address(250).is_none());
+    }
+
+    #[test]
+    fn test_vault_generation_from_api_version() {
+        assert_eq!(VaultGeneration::from_api_version("0.4.6"), VaultGeneration::V2);
+        assert_eq!(VaultGeneration::from_api_version("3.0.2"), VaultGeneration::V3);
+        assert_eq!(VaultGeneration::from_api_version("v3"), VaultGeneration::V3);
+    }
+
+    #[test]
+    fn test_v2_shares_scale_by_vault_decimals() {
+        // 2 shares of a 6-decimal USDC vault at 1.05 USDC per share
+        let shares = U256::from(2_000_000u64);
+        let price_per_share = U256::from(1_050_000u64);
+        let assets = YearnAdapter::v2_shares_to_assets(shares, price_per_share, 6);
+
+        assert_eq!(assets, U256::from(2_100_000u64));
+        assert!((YearnAdapter::price_per_share(shares, assets, 6, 6) - 1.05).abs() < 1e-12);
     }
 }
\ No newline at end of file
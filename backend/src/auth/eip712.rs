@@ -0,0 +1,178 @@
+use alloy::{
+    primitives::{Address, Signature, B256},
+    sol,
+    sol_types::{eip712_domain, Eip712Domain, SolStruct},
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+
+use super::AuthError;
+use crate::blockchain::EthereumClient;
+
+sol! {
+    /// Typed data signed to prove control of `wallet` before its portfolio is served
+    #[derive(Debug)]
+    struct PortfolioAccess {
+        address wallet;
+        address signer;
+        uint64 issuedAt;
+        /// Random value chosen by the client; each is accepted once per wallet
+        bytes32 nonce;
+    }
+
+    interface IDelegateRegistry {
+        function checkDelegateForAll(address to, address from, bytes32 rights) external view returns (bool);
+    }
+}
+
+/// delegate.xyz v2 registry, used to accept signatures from a wallet's delegates
+const DELEGATE_REGISTRY: &str = "0x00000000000000447e69651d841bD8D104Bed493";
+
+/// How old a signed access request may be
+pub const MAX_SIGNATURE_AGE_SECS: u64 = 300;
+
+/// Tolerated clock skew for requests issued slightly in the future
+const CLOCK_SKEW_SECS: u64 = 60;
+
+pub fn domain() -> Eip712Domain {
+    eip712_domain! {
+        name: "DeFi Risk Monitor",
+        version: "1",
+        chain_id: 1,
+    }
+}
+
+/// Address that produced `signature` over `access`
+pub fn recover_signer(access: &PortfolioAccess, signature: &str) -> Result<Address, AuthError> {
+    let signature = Signature::from_str(signature.trim())
+        .map_err(|e| AuthError::InvalidSignature(e.to_string()))?;
+    let hash: B256 = access.eip712_signing_hash(&domain());
+
+    signature
+        .recover_address_from_prehash(&hash)
+        .map_err(|e| AuthError::InvalidSignature(e.to_string()))
+}
+
+fn check_freshness(issued_at: u64, now: u64) -> Result<(), AuthError> {
+    if issued_at > now + CLOCK_SKEW_SECS || now.saturating_sub(issued_at) > MAX_SIGNATURE_AGE_SECS {
+        return Err(AuthError::SignatureExpired(MAX_SIGNATURE_AGE_SECS));
+    }
+    Ok(())
+}
+
+/// Nonces of accepted access requests, kept until their signatures expire so a
+/// captured signature can't be exchanged for a second token
+#[derive(Debug, Default)]
+pub struct UsedNonces {
+    seen: Mutex<HashMap<(Address, B256), u64>>,
+}
+
+impl UsedNonces {
+    pub fn global() -> &'static UsedNonces {
+        static GLOBAL: OnceLock<UsedNonces> = OnceLock::new();
+        GLOBAL.get_or_init(UsedNonces::default)
+    }
+
+    /// Mark `access`'s nonce used, failing if it already was
+    pub fn consume(&self, access: &PortfolioAccess, now: u64) -> Result<(), AuthError> {
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, issued_at| now.saturating_sub(*issued_at) <= MAX_SIGNATURE_AGE_SECS + CLOCK_SKEW_SECS);
+        if seen.insert((access.wallet, access.nonce), access.issuedAt).is_some() {
+            return Err(AuthError::NonceReused);
+        }
+        Ok(())
+    }
+}
+
+/// Verify a recent signature over `access` from its signer, and that the signer is the
+/// wallet or one of its delegate.xyz delegates. The nonce is only spent once all of
+/// that holds, so a forged request can't burn it.
+pub async fn verify_portfolio_access(
+    client: &EthereumClient,
+    access: &PortfolioAccess,
+    signature: &str,
+) -> Result<(), AuthError> {
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+    check_freshness(access.issuedAt, now)?;
+
+    if recover_signer(access, signature)? != access.signer {
+        return Err(AuthError::InvalidSignature("signature does not match signer".to_string()));
+    }
+    if access.signer == access.wallet {
+        return UsedNonces::global().consume(access, now);
+    }
+
+    let registry = Address::from_str(DELEGATE_REGISTRY)
+        .map_err(|e| AuthError::DelegationCheckFailed(e.to_string()))?;
+    let delegated = client
+        .call(registry, &IDelegateRegistry::checkDelegateForAllCall {
+            to: access.signer,
+            from: access.wallet,
+            rights: B256::ZERO,
+        })
+        .await
+        .map_err(|e| AuthError::DelegationCheckFailed(e.to_string()))?;
+
+    if delegated._0 {
+        UsedNonces::global().consume(access, now)
+    } else {
+        Err(AuthError::NotDelegated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::{local::PrivateKeySigner, SignerSync};
+
+    #[test]
+    fn test_recovers_the_signing_wallet() {
+        let key = PrivateKeySigner::random();
+        let access = PortfolioAccess {
+            wallet: key.address(),
+            signer: key.address(),
+            issuedAt: 1_700_000_000,
+            nonce: B256::repeat_byte(0x01),
+        };
+        let signature = key.sign_hash_sync(&access.eip712_signing_hash(&domain())).unwrap();
+        let encoded = format!("0x{}", alloy::hex::encode(signature.as_bytes()));
+
+        assert_eq!(recover_signer(&access, &encoded).unwrap(), key.address());
+
+        let other = PortfolioAccess { wallet: Address::repeat_byte(0x11), ..access };
+        assert_ne!(recover_signer(&other, &encoded).unwrap(), key.address());
+        let renonced = PortfolioAccess { nonce: B256::repeat_byte(0x02), ..access };
+        assert_ne!(recover_signer(&renonced, &encoded).unwrap(), key.address());
+        assert!(recover_signer(&access, "0x1234").is_err());
+    }
+
+    #[test]
+    fn test_stale_and_future_signatures_are_rejected() {
+        let now = 1_700_000_000;
+        assert!(check_freshness(now - 10, now).is_ok());
+        assert!(check_freshness(now + 30, now).is_ok());
+        assert_eq!(check_freshness(now - 301, now), Err(AuthError::SignatureExpired(MAX_SIGNATURE_AGE_SECS)));
+        assert!(check_freshness(now + 120, now).is_err());
+    }
+
+    #[test]
+    fn test_nonces_are_single_use_until_they_expire() {
+        let nonces = UsedNonces::default();
+        let now = 1_700_000_000;
+        let access = PortfolioAccess {
+            wallet: Address::repeat_byte(0x11),
+            signer: Address::repeat_byte(0x11),
+            issuedAt: now,
+            nonce: B256::repeat_byte(0x01),
+        };
+
+        assert!(nonces.consume(&access, now).is_ok());
+        assert_eq!(nonces.consume(&access, now + 10), Err(AuthError::NonceReused));
+        assert!(nonces.consume(&PortfolioAccess { nonce: B256::repeat_byte(0x02), ..access }, now + 10).is_ok());
+        assert!(nonces.consume(&PortfolioAccess { wallet: Address::repeat_byte(0x22), ..access }, now + 10).is_ok());
+
+        // By then the signature would fail the freshness check anyway
+        assert!(nonces.consume(&access, now + MAX_SIGNATURE_AGE_SECS + CLOCK_SKEW_SECS + 1).is_ok());
+    }
+}
@@ -0,0 +1,150 @@
+use alloy::primitives::Address;
+use axum::http::{header, HeaderMap};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use super::AuthError;
+
+/// Claims of a wallet access token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    /// Wallet the token grants read access to, lowercase hex
+    pub sub: String,
+    /// Address that signed the access request: the wallet itself or a delegate
+    pub signer: String,
+    pub iat: u64,
+    pub exp: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedToken {
+    pub token: String,
+    pub token_type: &'static str,
+    pub expires_at: u64,
+}
+
+/// Issues and checks short-lived HS256 tokens scoped to a single wallet
+pub struct JwtService {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    ttl_secs: u64,
+}
+
+impl JwtService {
+    pub fn new(secret: &[u8], ttl_secs: u64) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(secret),
+            decoding: DecodingKey::from_secret(secret),
+            ttl_secs: ttl_secs.max(1),
+        }
+    }
+
+    /// `AUTH_JWT_SECRET` and `AUTH_JWT_TTL_SECS` (default 900). Without a secret a
+    /// random one is generated, so tokens don't survive a restart.
+    pub fn from_env() -> Self {
+        let ttl_secs = std::env::var("AUTH_JWT_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(900);
+        let secret = match std::env::var("AUTH_JWT_SECRET") {
+            Ok(secret) if !secret.is_empty() => secret,
+            _ => {
+                tracing::warn!("⚠️ AUTH_JWT_SECRET not set; using a random per-process secret");
+                format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+            }
+        };
+
+        Self::new(secret.as_bytes(), ttl_secs)
+    }
+
+    pub fn ttl_secs(&self) -> u64 {
+        self.ttl_secs
+    }
+
+    pub fn issue(&self, wallet: Address, signer: Address) -> Result<IssuedToken, AuthError> {
+        let iat = chrono::Utc::now().timestamp().max(0) as u64;
+        let claims = Claims {
+            sub: format!("{:#x}", wallet),
+            signer: format!("{:#x}", signer),
+            iat,
+            exp: iat + self.ttl_secs,
+        };
+        let token = encode(&Header::new(Algorithm::HS256), &claims, &self.encoding)
+            .map_err(|e| AuthError::TokenIssuance(e.to_string()))?;
+
+        Ok(IssuedToken { token, token_type: "Bearer", expires_at: claims.exp })
+    }
+
+    pub fn verify(&self, token: &str) -> Result<Claims, AuthError> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 0;
+        decode::<Claims>(token, &self.decoding, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| AuthError::InvalidToken(e.to_string()))
+    }
+
+    /// Check one of the request's bearer tokens grants access to `wallet`. Requests
+    /// covering several wallets send an `Authorization` header for each.
+    pub fn authorize(&self, headers: &HeaderMap, wallet: Address) -> Result<Claims, AuthError> {
+        let tokens = headers
+            .get_all(header::AUTHORIZATION)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .filter(|token| !token.is_empty());
+
+        let mut result = Err(AuthError::MissingToken);
+        for token in tokens {
+            result = self.verify(token).and_then(|claims| {
+                if claims.sub == format!("{:#x}", wallet) { Ok(claims) } else { Err(AuthError::WalletMismatch) }
+            });
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_token_only_authorizes_its_wallet() {
+        let service = JwtService::new(b"test-secret", 60);
+        let wallet = Address::repeat_byte(0x11);
+        let issued = service.issue(wallet, wallet).unwrap();
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(service.authorize(&headers, wallet), Err(AuthError::MissingToken));
+
+        let bearer = format!("Bearer {}", issued.token);
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_str(&bearer).unwrap());
+        let claims = service.authorize(&headers, wallet).unwrap();
+        assert_eq!(claims.exp, issued.expires_at);
+        assert_eq!(
+            service.authorize(&headers, Address::repeat_byte(0x22)),
+            Err(AuthError::WalletMismatch)
+        );
+
+        // A token per wallet covers both
+        let other = service.issue(Address::repeat_byte(0x22), Address::repeat_byte(0x22)).unwrap();
+        headers.append(header::AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", other.token)).unwrap());
+        assert!(service.authorize(&headers, wallet).is_ok());
+        assert!(service.authorize(&headers, Address::repeat_byte(0x22)).is_ok());
+    }
+
+    #[test]
+    fn test_tokens_from_another_secret_are_rejected() {
+        let wallet = Address::repeat_byte(0x11);
+        let issued = JwtService::new(b"other-secret", 60).issue(wallet, wallet).unwrap();
+
+        assert!(matches!(
+            JwtService::new(b"test-secret", 60).verify(&issued.token),
+            Err(AuthError::InvalidToken(_))
+        ));
+    }
+}
@@ -0,0 +1,77 @@
+pub mod eip712;
+pub mod jwt;
+
+pub use eip712::{verify_portfolio_access, PortfolioAccess, UsedNonces};
+pub use jwt::{Claims, IssuedToken, JwtService};
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+
+/// Failed wallet authentication, rendered with a structured body like `ValidationError`
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AuthError {
+    #[error("Missing bearer token")]
+    MissingToken,
+
+    #[error("Invalid token: {0}")]
+    InvalidToken(String),
+
+    #[error("Token was not issued for this wallet")]
+    WalletMismatch,
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Signature is older than {0} seconds or issued in the future")]
+    SignatureExpired(u64),
+
+    #[error("Access request nonce was already used")]
+    NonceReused,
+
+    #[error("Signer is not a delegate of the wallet")]
+    NotDelegated,
+
+    #[error("Delegation check failed: {0}")]
+    DelegationCheckFailed(String),
+
+    #[error("Failed to issue token: {0}")]
+    TokenIssuance(String),
+}
+
+impl AuthError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::MissingToken => "missing_token",
+            AuthError::InvalidToken(_) => "invalid_token",
+            AuthError::WalletMismatch => "wallet_mismatch",
+            AuthError::InvalidSignature(_) => "invalid_signature",
+            AuthError::SignatureExpired(_) => "signature_expired",
+            AuthError::NonceReused => "nonce_reused",
+            AuthError::NotDelegated => "not_delegated",
+            AuthError::DelegationCheckFailed(_) => "delegation_check_failed",
+            AuthError::TokenIssuance(_) => "token_issuance_failed",
+        }
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            AuthError::WalletMismatch | AuthError::NotDelegated => StatusCode::FORBIDDEN,
+            AuthError::DelegationCheckFailed(_) => StatusCode::BAD_GATEWAY,
+            AuthError::TokenIssuance(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({
+            "success": false,
+            "error": self.code(),
+            "message": self.to_string(),
+        });
+        (self.status(), Json(body)).into_response()
+    }
+}
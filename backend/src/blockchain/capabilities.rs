@@ -0,0 +1,102 @@
+use alloy::primitives::Address;
+use serde::Serialize;
+
+use crate::adapters::traits::AdapterError;
+use crate::blockchain::EthereumClient;
+
+/// Mainnet block old enough that only archive nodes still hold its state
+const ARCHIVE_PROBE_BLOCK: u64 = 1_000_000;
+
+/// How `archive_node` was decided
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapabilitySource {
+    /// Set by `RPC_ARCHIVE_NODE`
+    Configured,
+    /// Answered by a state read at an old block
+    Probed,
+    /// The probe couldn't reach the node; assumed latest-only until restart
+    ProbeFailed,
+}
+
+/// What the configured mainnet RPC can serve. Reading positions at a past block needs
+/// the state of that block, which pruned nodes have discarded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DataSourceCapabilities {
+    pub archive_node: bool,
+    pub source: CapabilitySource,
+}
+
+impl DataSourceCapabilities {
+    /// Archive access as declared by `RPC_ARCHIVE_NODE` (`true`/`false`); `None` when
+    /// unset or `auto`, meaning it should be probed
+    pub fn from_env() -> Option<Self> {
+        let value = std::env::var("RPC_ARCHIVE_NODE").ok()?;
+        let archive_node = parse_flag(&value)?;
+        Some(Self { archive_node, source: CapabilitySource::Configured })
+    }
+
+    /// Configured capabilities, or else the result of reading state at an old block
+    pub async fn detect(client: &EthereumClient) -> Self {
+        if let Some(configured) = Self::from_env() {
+            return configured;
+        }
+
+        let probe: Result<String, AdapterError> = client
+            .request(
+                "eth_getBalance",
+                serde_json::json!([format!("{:?}", Address::ZERO), format!("{:#x}", ARCHIVE_PROBE_BLOCK)]),
+            )
+            .await;
+        Self::from_probe(probe.map(|_| ()))
+    }
+
+    /// Node errors (missing trie node, state unavailable) mean a pruned node; transport
+    /// failures say nothing about the node, so archive access isn't assumed
+    fn from_probe(probe: Result<(), AdapterError>) -> Self {
+        match probe {
+            Ok(()) => Self { archive_node: true, source: CapabilitySource::Probed },
+            Err(AdapterError::RpcError(e)) | Err(AdapterError::ContractError(e)) => {
+                tracing::info!("RPC node has no state at block {}: {}", ARCHIVE_PROBE_BLOCK, e);
+                Self { archive_node: false, source: CapabilitySource::Probed }
+            }
+            Err(e) => {
+                tracing::warn!("Archive node probe failed: {}", e);
+                Self { archive_node: false, source: CapabilitySource::ProbeFailed }
+            }
+        }
+    }
+}
+
+fn parse_flag(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_result_decides_archive_access() {
+        let archive = DataSourceCapabilities::from_probe(Ok(()));
+        assert!(archive.archive_node);
+
+        let pruned = DataSourceCapabilities::from_probe(Err(AdapterError::RpcError("missing trie node".to_string())));
+        assert_eq!(pruned, DataSourceCapabilities { archive_node: false, source: CapabilitySource::Probed });
+
+        let unreachable = DataSourceCapabilities::from_probe(Err(AdapterError::NetworkError("connection refused".to_string())));
+        assert_eq!(unreachable.source, CapabilitySource::ProbeFailed);
+        assert!(!unreachable.archive_node);
+    }
+
+    #[test]
+    fn test_flag_parsing() {
+        assert_eq!(parse_flag(" TRUE "), Some(true));
+        assert_eq!(parse_flag("0"), Some(false));
+        assert_eq!(parse_flag("auto"), None);
+    }
+}
@@ -0,0 +1,539 @@
+use alloy::{
+    primitives::{Address, B256, U256},
+    providers::{ProviderBuilder, RootProvider},
+    sol_types::{SolCall, SolEvent},
+    transports::http::{Client, Http},
+};
+use serde::de::DeserializeOwned;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+use crate::adapters::traits::AdapterError;
+use crate::utils::{retry_call, RetryPolicy};
+
+/// In-flight RPC requests allowed when `MAX_CONCURRENT_RPC_CALLS` is unset
+const DEFAULT_MAX_CONCURRENT_RPC_CALLS: usize = 32;
+
+fn parse_concurrency_limit(value: Option<&str>) -> usize {
+    value
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|limit| *limit > 0)
+        .unwrap_or(DEFAULT_MAX_CONCURRENT_RPC_CALLS)
+}
+
+/// Cap on in-flight RPC requests across every client in the process, from
+/// `MAX_CONCURRENT_RPC_CALLS`
+pub fn rpc_concurrency_limit() -> usize {
+    static LIMIT: OnceLock<usize> = OnceLock::new();
+    *LIMIT.get_or_init(|| parse_concurrency_limit(std::env::var("MAX_CONCURRENT_RPC_CALLS").ok().as_deref()))
+}
+
+/// Permits every request takes before it is sent, so adapters fanning out in parallel
+/// queue up instead of together tripping a rate-limited endpoint's 429s
+fn rpc_permits() -> &'static Semaphore {
+    static PERMITS: OnceLock<Semaphore> = OnceLock::new();
+    PERMITS.get_or_init(|| Semaphore::new(rpc_concurrency_limit()))
+}
+
+/// Shared Ethereum JSON-RPC client with multi-endpoint failover.
+///
+/// Requests go to the first healthy endpoint in configuration order. An endpoint
+/// that returns a transport error or HTTP 429 is put into exponential backoff and
+/// the request is retried on the next one. Clones share endpoint health.
+#[derive(Debug, Clone)]
+pub struct EthereumClient {
+    endpoints: Arc<Vec<RpcEndpoint>>,
+    http_client: reqwest::Client,
+    /// Block contract calls are executed at; the latest block when unset
+    block: Option<u64>,
+    /// Blocks behind the head unpinned contract calls read at, so state a reorg
+    /// could undo isn't reported; 0 reads the latest block
+    confirmations: u64,
+    /// Last head read for confirmed calls, shared by clones
+    head: Arc<Mutex<Option<(u64, Instant)>>>,
+    /// Answers requests instead of the endpoints in tests
+    #[cfg(test)]
+    mock: Option<Arc<super::MockRpc>>,
+}
+
+#[derive(Debug)]
+struct RpcEndpoint {
+    url: String,
+    health: Mutex<EndpointHealth>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct EndpointHealth {
+    consecutive_failures: u32,
+    retry_after: Option<Instant>,
+}
+
+impl EndpointHealth {
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.retry_after.map_or(true, |at| now >= at)
+    }
+}
+
+/// Log entry as returned by `eth_getLogs`
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawLog {
+    topics: Vec<B256>,
+    data: String,
+    block_number: String,
+}
+
+/// Point-in-time health of a single configured RPC endpoint
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EndpointStatus {
+    pub url: String,
+    pub healthy: bool,
+    pub consecutive_failures: u32,
+}
+
+impl EthereumClient {
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+    const BASE_BACKOFF: Duration = Duration::from_secs(1);
+    const MAX_BACKOFF: Duration = Duration::from_secs(60);
+    /// How long a head read is reused for confirmed calls
+    const HEAD_CACHE_TTL: Duration = Duration::from_secs(2);
+
+    /// Create a client for a single RPC endpoint
+    pub fn new(rpc_url: &str) -> Result<Self, AdapterError> {
+        Self::with_fallbacks(vec![rpc_url.to_string()])
+    }
+
+    /// Create a client that fails over across `urls` in order of preference
+    pub fn with_fallbacks(urls: Vec<String>) -> Result<Self, AdapterError> {
+        let mut endpoints = Vec::new();
+
+        for url in urls {
+            let url = url.trim().to_string();
+            if url.is_empty() {
+                continue;
+            }
+
+            url::Url::parse(&url)
+                .map_err(|e| AdapterError::InvalidData(format!("Invalid RPC URL '{}': {}", url, e)))?;
+
+            endpoints.push(RpcEndpoint {
+                url,
+                health: Mutex::new(EndpointHealth::default()),
+            });
+        }
+
+        if endpoints.is_empty() {
+            return Err(AdapterError::InvalidData("At least one RPC URL is required".to_string()));
+        }
+
+        let http_client = reqwest::Client::builder()
+            .timeout(Self::REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| AdapterError::NetworkError(format!("Failed to build HTTP client: {}", e)))?;
+
+        Ok(Self {
+            endpoints: Arc::new(endpoints),
+            http_client,
+            block: None,
+            confirmations: 0,
+            head: Arc::new(Mutex::new(None)),
+            #[cfg(test)]
+            mock: None,
+        })
+    }
+
+    /// Client answering every request from `rpc` instead of the network
+    #[cfg(test)]
+    pub fn mock(rpc: super::MockRpc) -> Self {
+        Self {
+            mock: Some(Arc::new(rpc)),
+            ..Self::new("http://mock.invalid").expect("valid mock URL")
+        }
+    }
+
+    /// Client sharing this one's endpoints whose contract calls read state at `block`
+    pub fn at_block(&self, block: u64) -> Self {
+        Self {
+            block: Some(block),
+            ..self.clone()
+        }
+    }
+
+    /// Client sharing this one's endpoints whose unpinned contract calls read state
+    /// `confirmations` blocks behind the head
+    pub fn with_confirmations(&self, confirmations: u64) -> Self {
+        Self {
+            confirmations,
+            ..self.clone()
+        }
+    }
+
+    /// Blocks behind the head unpinned contract calls read at
+    pub fn confirmations(&self) -> u64 {
+        self.confirmations
+    }
+
+    /// Block contract calls are pinned to, if any
+    pub fn pinned_block(&self) -> Option<u64> {
+        self.block
+    }
+
+    /// URL of the endpoint that the next request will be sent to
+    pub fn rpc_url(&self) -> &str {
+        &self.endpoints[self.endpoint_order()[0]].url
+    }
+
+    /// Alloy provider bound to the currently preferred endpoint
+    pub fn provider(&self) -> RootProvider<Http<Client>> {
+        // URLs are validated in the constructor
+        let url = self.rpc_url().parse().expect("validated RPC URL");
+        ProviderBuilder::new().on_http(url)
+    }
+
+    /// Health of every configured endpoint, in configuration order
+    pub fn endpoint_statuses(&self) -> Vec<EndpointStatus> {
+        let now = Instant::now();
+        self.endpoints
+            .iter()
+            .map(|endpoint| {
+                let health = endpoint.health.lock().unwrap();
+                EndpointStatus {
+                    url: endpoint.url.clone(),
+                    healthy: health.is_healthy(now),
+                    consecutive_failures: health.consecutive_failures,
+                }
+            })
+            .collect()
+    }
+
+    /// Current block number
+    pub async fn block_number(&self) -> Result<u64, AdapterError> {
+        let hex: String = self.request("eth_blockNumber", serde_json::json!([])).await?;
+        u64::from_str_radix(hex.trim_start_matches("0x"), 16)
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid block number '{}': {}", hex, e)))
+    }
+
+    /// Unix timestamp of `block`
+    pub async fn block_timestamp(&self, block: u64) -> Result<u64, AdapterError> {
+        let header: serde_json::Value = self
+            .request("eth_getBlockByNumber", serde_json::json!([format!("{:#x}", block), false]))
+            .await?;
+        let hex = header
+            .get("timestamp")
+            .and_then(|t| t.as_str())
+            .ok_or_else(|| AdapterError::InvalidData(format!("Block {} not found", block)))?;
+        u64::from_str_radix(hex.trim_start_matches("0x"), 16)
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid block timestamp '{}': {}", hex, e)))
+    }
+
+    /// Head minus the confirmation depth, reading the head at most once per
+    /// `HEAD_CACHE_TTL` across clones
+    async fn confirmed_block(&self) -> Result<u64, AdapterError> {
+        let cached = *self.head.lock().unwrap();
+        let head = match cached {
+            Some((head, read_at)) if read_at.elapsed() < Self::HEAD_CACHE_TTL => head,
+            _ => {
+                let head = self.block_number().await?;
+                *self.head.lock().unwrap() = Some((head, Instant::now()));
+                head
+            }
+        };
+        Ok(head.saturating_sub(self.confirmations))
+    }
+
+    /// Execute a read-only contract call via `eth_call` at the pinned block, or
+    /// `confirmations` blocks behind the head when none is pinned
+    pub async fn call<C: SolCall>(&self, to: Address, call: &C) -> Result<C::Return, AdapterError> {
+        let tx = serde_json::json!({
+            "to": format!("{:?}", to),
+            "data": format!("0x{}", alloy::hex::encode(call.abi_encode())),
+        });
+        let block = match (self.block, self.confirmations) {
+            (Some(block), _) => format!("{:#x}", block),
+            (None, 0) => "latest".to_string(),
+            (None, _) => format!("{:#x}", self.confirmed_block().await?),
+        };
+
+        let raw: String = self.request("eth_call", serde_json::json!([tx, block])).await?;
+        let bytes = alloy::hex::decode(&raw)
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid eth_call result: {}", e)))?;
+
+        C::abi_decode_returns(&bytes, true)
+            .map_err(|e| AdapterError::ContractError(format!("Failed to decode {} result: {}", C::SIGNATURE, e)))
+    }
+
+    /// `E` events emitted by `address` between `from_block` and `to_block` inclusive, with
+    /// the block each was emitted in, oldest first
+    pub async fn get_logs<E: SolEvent>(
+        &self,
+        address: Address,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<(u64, E)>, AdapterError> {
+        let filter = serde_json::json!({
+            "address": format!("{:?}", address),
+            "fromBlock": format!("{:#x}", from_block),
+            "toBlock": format!("{:#x}", to_block),
+            "topics": [format!("{:?}", E::SIGNATURE_HASH)],
+        });
+        let logs: Vec<RawLog> = self.request("eth_getLogs", serde_json::json!([filter])).await?;
+
+        logs.into_iter()
+            .map(|log| {
+                let block = u64::from_str_radix(log.block_number.trim_start_matches("0x"), 16)
+                    .map_err(|e| AdapterError::InvalidData(format!("Invalid log block '{}': {}", log.block_number, e)))?;
+                let data = alloy::hex::decode(&log.data)
+                    .map_err(|e| AdapterError::InvalidData(format!("Invalid log data: {}", e)))?;
+                let event = E::decode_raw_log(log.topics, &data, true)
+                    .map_err(|e| AdapterError::InvalidData(format!("Failed to decode {} log: {}", E::SIGNATURE, e)))?;
+                Ok((block, event))
+            })
+            .collect()
+    }
+
+    /// [`call`](Self::call), retried on transient failures according to `policy`
+    pub async fn call_with_retry<C: SolCall>(
+        &self,
+        to: Address,
+        call: &C,
+        policy: &RetryPolicy,
+    ) -> Result<C::Return, AdapterError> {
+        retry_call(policy, || self.call(to, call)).await
+    }
+
+    /// Gas estimate for calling `to` with `calldata` from `from` via `eth_estimateGas`
+    pub async fn estimate_gas(
+        &self,
+        from: Address,
+        to: Address,
+        calldata: &[u8],
+        value: U256,
+    ) -> Result<u64, AdapterError> {
+        let tx = serde_json::json!({
+            "from": format!("{:?}", from),
+            "to": format!("{:?}", to),
+            "data": format!("0x{}", alloy::hex::encode(calldata)),
+            "value": format!("{:#x}", value),
+        });
+
+        let hex: String = self.request("eth_estimateGas", serde_json::json!([tx])).await?;
+        u64::from_str_radix(hex.trim_start_matches("0x"), 16)
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid gas estimate '{}': {}", hex, e)))
+    }
+
+    /// Current gas price in wei via `eth_gasPrice`
+    pub async fn gas_price(&self) -> Result<u128, AdapterError> {
+        let hex: String = self.request("eth_gasPrice", serde_json::json!([])).await?;
+        u128::from_str_radix(hex.trim_start_matches("0x"), 16)
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid gas price '{}': {}", hex, e)))
+    }
+
+    /// Send a JSON-RPC request, failing over to the next endpoint on transport
+    /// errors or rate limiting. JSON-RPC error responses are returned as-is.
+    pub async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T, AdapterError> {
+        #[cfg(test)]
+        if let Some(mock) = &self.mock {
+            return mock.respond(method, &params);
+        }
+
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let mut last_error = None;
+
+        for index in self.endpoint_order() {
+            let endpoint = &self.endpoints[index];
+
+            match self.send(&endpoint.url, &body).await {
+                Ok(response) => {
+                    self.mark_success(index);
+
+                    if let Some(error) = response.get("error") {
+                        let message = format!("{} failed: {}", method, error);
+                        let reverted = error.get("message")
+                            .and_then(|m| m.as_str())
+                            .is_some_and(|m| m.contains("revert"));
+                        return Err(if reverted {
+                            AdapterError::ContractError(message)
+                        } else {
+                            AdapterError::RpcError(message)
+                        });
+                    }
+
+                    let result = response.get("result").cloned().unwrap_or(serde_json::Value::Null);
+                    return serde_json::from_value(result)
+                        .map_err(|e| AdapterError::InvalidData(format!("Invalid {} response: {}", method, e)));
+                }
+                Err(e) => {
+                    tracing::warn!(endpoint = %endpoint.url, error = %e, "RPC endpoint failed, rotating");
+                    self.mark_failure(index);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| AdapterError::RpcError("No RPC endpoints configured".to_string())))
+    }
+
+    async fn send(&self, url: &str, body: &serde_json::Value) -> Result<serde_json::Value, AdapterError> {
+        // Held until the response body is read
+        let _permit = rpc_permits()
+            .acquire()
+            .await
+            .map_err(|e| AdapterError::RpcError(format!("RPC concurrency limiter closed: {}", e)))?;
+
+        let response = self.http_client
+            .post(url)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() {
+                    AdapterError::Timeout(format!("RPC request timed out: {}", e))
+                } else {
+                    AdapterError::NetworkError(format!("RPC transport error: {}", e))
+                }
+            })?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(AdapterError::RateLimited("RPC endpoint rate limited (429)".to_string()));
+        }
+        if !status.is_success() {
+            return Err(AdapterError::RpcError(format!("RPC endpoint returned status {}", status)));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid JSON-RPC response: {}", e)))
+    }
+
+    /// Healthy endpoints in configuration order, then backed-off endpoints by
+    /// soonest retry time so a request is still attempted when all are down
+    fn endpoint_order(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let mut healthy = Vec::new();
+        let mut backing_off = Vec::new();
+
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            let health = endpoint.health.lock().unwrap();
+            match health.retry_after {
+                Some(at) if now < at => backing_off.push((at, index)),
+                _ => healthy.push(index),
+            }
+        }
+
+        backing_off.sort();
+        healthy.extend(backing_off.into_iter().map(|(_, index)| index));
+        healthy
+    }
+
+    fn mark_success(&self, index: usize) {
+        let mut health = self.endpoints[index].health.lock().unwrap();
+        *health = EndpointHealth::default();
+    }
+
+    fn mark_failure(&self, index: usize) {
+        let mut health = self.endpoints[index].health.lock().unwrap();
+        health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+        health.retry_after = Some(Instant::now() + Self::backoff_for(health.consecutive_failures));
+    }
+
+    fn backoff_for(consecutive_failures: u32) -> Duration {
+        let exponent = consecutive_failures.saturating_sub(1).min(16);
+        Self::BASE_BACKOFF
+            .saturating_mul(1u32 << exponent)
+            .min(Self::MAX_BACKOFF)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_fallbacks_requires_an_endpoint() {
+        assert!(EthereumClient::with_fallbacks(vec![]).is_err());
+        assert!(EthereumClient::with_fallbacks(vec!["  ".to_string()]).is_err());
+        assert!(EthereumClient::with_fallbacks(vec!["not a url".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_concurrency_limit_falls_back_to_default() {
+        assert_eq!(parse_concurrency_limit(Some("8")), 8);
+        assert_eq!(parse_concurrency_limit(Some("0")), DEFAULT_MAX_CONCURRENT_RPC_CALLS);
+        assert_eq!(parse_concurrency_limit(Some("many")), DEFAULT_MAX_CONCURRENT_RPC_CALLS);
+        assert_eq!(parse_concurrency_limit(None), DEFAULT_MAX_CONCURRENT_RPC_CALLS);
+    }
+
+    #[test]
+    fn test_failed_endpoint_is_rotated_out() {
+        let client = EthereumClient::with_fallbacks(vec![
+            "https://rpc-a.example.com".to_string(),
+            "https://rpc-b.example.com".to_string(),
+        ]).unwrap();
+
+        assert_eq!(client.rpc_url(), "https://rpc-a.example.com");
+
+        client.mark_failure(0);
+        assert_eq!(client.rpc_url(), "https://rpc-b.example.com");
+        assert_eq!(client.endpoint_order(), vec![1, 0]);
+
+        client.mark_success(0);
+        assert_eq!(client.rpc_url(), "https://rpc-a.example.com");
+    }
+
+    #[test]
+    fn test_pinned_client_shares_endpoints() {
+        let client = EthereumClient::new("https://rpc-a.example.com").unwrap();
+        let pinned = client.at_block(19_000_000);
+
+        assert_eq!(client.pinned_block(), None);
+        assert_eq!(pinned.pinned_block(), Some(19_000_000));
+        // Endpoint health is shared with the unpinned client
+        assert!(Arc::ptr_eq(&client.endpoints, &pinned.endpoints));
+    }
+
+    #[test]
+    fn test_backoff_grows_exponentially_and_is_capped() {
+        assert_eq!(EthereumClient::backoff_for(1), Duration::from_secs(1));
+        assert_eq!(EthereumClient::backoff_for(2), Duration::from_secs(2));
+        assert_eq!(EthereumClient::backoff_for(4), Duration::from_secs(8));
+        assert_eq!(EthereumClient::backoff_for(30), Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_confirmed_calls_read_behind_the_head() {
+        let token = Address::repeat_byte(1);
+        let call = crate::services::IERC20::totalSupplyCall {};
+        let rpc = crate::blockchain::MockRpc::new()
+            .on_call(token, &call, crate::services::IERC20::totalSupplyCall::abi_encode_returns(&(U256::from(1u64),)))
+            .on_method("eth_blockNumber", serde_json::json!("0x64"));
+        let client = EthereumClient::mock(rpc).with_confirmations(12);
+
+        client.call(token, &call).await.unwrap();
+        client.call(token, &call).await.unwrap();
+        client.at_block(50).call(token, &call).await.unwrap();
+
+        let requests = client.mock.as_ref().unwrap().requests();
+        let selector = alloy::hex::encode(crate::services::IERC20::totalSupplyCall::SELECTOR);
+        let read_at = |block: &str| format!("eth_call {:?} 0x{} at {}", token, selector, block);
+        // The head is read once and reused; a pinned block ignores the confirmation depth
+        assert_eq!(requests, vec![
+            "eth_blockNumber".to_string(),
+            read_at("0x58"),
+            read_at("0x58"),
+            read_at("0x32"),
+        ]);
+    }
+}
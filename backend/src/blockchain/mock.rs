@@ -0,0 +1,109 @@
+use alloy::primitives::Address;
+use alloy::sol_types::SolCall;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::adapters::traits::AdapterError;
+
+/// Scripted JSON-RPC responses for adapter tests, served through
+/// [`EthereumClient::mock`](super::EthereumClient::mock).
+///
+/// `eth_call`s are answered by target and exact calldata, other methods by name.
+/// Anything unscripted fails with an `RpcError` naming the request, so a test shows
+/// which read it's missing.
+#[derive(Debug, Default)]
+pub struct MockRpc {
+    calls: HashMap<(Address, Vec<u8>), Result<Vec<u8>, String>>,
+    methods: HashMap<String, serde_json::Value>,
+    requests: Mutex<Vec<String>>,
+}
+
+impl MockRpc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer `call` to `to` with ABI-encoded `returns`, e.g. from `C::abi_encode_returns`
+    pub fn on_call<C: SolCall>(mut self, to: Address, call: &C, returns: Vec<u8>) -> Self {
+        self.calls.insert((to, call.abi_encode()), Ok(returns));
+        self
+    }
+
+    /// Make `call` to `to` revert with `reason`
+    pub fn reverts<C: SolCall>(mut self, to: Address, call: &C, reason: &str) -> Self {
+        self.calls.insert((to, call.abi_encode()), Err(reason.to_string()));
+        self
+    }
+
+    /// Answer every request for `method` with `result`
+    pub fn on_method(mut self, method: &str, result: serde_json::Value) -> Self {
+        self.methods.insert(method.to_string(), result);
+        self
+    }
+
+    /// Requests served so far, as `method` or `eth_call <to> <selector> at <block>`
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub(crate) fn respond<T: DeserializeOwned>(&self, method: &str, params: &serde_json::Value) -> Result<T, AdapterError> {
+        let result = if method == "eth_call" {
+            let tx = &params[0];
+            let to = tx["to"].as_str().and_then(|to| Address::from_str(to).ok()).unwrap_or_default();
+            let data = tx["data"].as_str().and_then(|data| alloy::hex::decode(data).ok()).unwrap_or_default();
+            let selector = alloy::hex::encode(data.get(..4).unwrap_or_default());
+            let block = params[1].as_str().unwrap_or("latest");
+            self.requests.lock().unwrap().push(format!("eth_call {:?} 0x{} at {}", to, selector, block));
+
+            match self.calls.get(&(to, data)) {
+                Some(Ok(returns)) => serde_json::json!(format!("0x{}", alloy::hex::encode(returns))),
+                Some(Err(reason)) => {
+                    return Err(AdapterError::ContractError(format!("eth_call failed: execution reverted: {}", reason)));
+                }
+                None => {
+                    return Err(AdapterError::RpcError(format!("No mock response for eth_call to {:?} with selector 0x{}", to, selector)));
+                }
+            }
+        } else {
+            self.requests.lock().unwrap().push(method.to_string());
+            self.methods
+                .get(method)
+                .cloned()
+                .ok_or_else(|| AdapterError::RpcError(format!("No mock response for {}", method)))?
+        };
+
+        serde_json::from_value(result)
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid {} response: {}", method, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::EthereumClient;
+    use crate::services::IERC20;
+    use alloy::primitives::U256;
+
+    #[tokio::test]
+    async fn test_scripted_calls_and_methods() {
+        let token = Address::repeat_byte(1);
+        let holder = Address::repeat_byte(2);
+        let client = EthereumClient::mock(
+            MockRpc::new()
+                .on_call(token, &IERC20::balanceOfCall { account: holder }, IERC20::balanceOfCall::abi_encode_returns(&(U256::from(42u64),)))
+                .reverts(token, &IERC20::totalSupplyCall {}, "paused")
+                .on_method("eth_blockNumber", serde_json::json!("0x10")),
+        );
+
+        let balance = client.call(token, &IERC20::balanceOfCall { account: holder }).await.unwrap();
+        assert_eq!(balance._0, U256::from(42u64));
+        assert!(matches!(client.call(token, &IERC20::totalSupplyCall {}).await, Err(AdapterError::ContractError(_))));
+        assert_eq!(client.block_number().await.unwrap(), 16);
+
+        // A different argument isn't the scripted call
+        let other = client.call(token, &IERC20::balanceOfCall { account: token }).await;
+        assert!(matches!(other, Err(AdapterError::RpcError(_))));
+    }
+}
@@ -0,0 +1,9 @@
+pub mod capabilities;
+pub mod ethereum_client;
+#[cfg(test)]
+pub mod mock;
+
+pub use ethereum_client::{rpc_concurrency_limit, EthereumClient};
+#[cfg(test)]
+pub use mock::MockRpc;
+pub use capabilities::{CapabilitySource, DataSourceCapabilities};
@@ -1,5 +1,9 @@
 use axum::{response::Json, http::StatusCode};
+use futures::future::join_all;
 use serde_json;
+use std::time::Instant;
+
+use crate::adapters::DeFiAdapter;
 
 /// Simple health check endpoint
 pub async fn health_check() -> Result<Json<serde_json::Value>, StatusCode> {
@@ -10,3 +14,75 @@ pub async fn health_check() -> Result<Json<serde_json::Value>, StatusCode> {
         "version": "1.0.0"
     })))
 }
+
+/// Probe each adapter's RPC endpoint with `eth_blockNumber`.
+///
+/// Overall status is `degraded` when some adapters are unreachable and
+/// `unhealthy` (HTTP 503) when none are reachable.
+pub async fn adapter_health(adapters: &[Box<dyn DeFiAdapter>]) -> (StatusCode, Json<serde_json::Value>) {
+    let probes = adapters.iter().filter_map(|adapter| {
+        let client = adapter.rpc_client()?;
+        Some(async move {
+            let started_at = Instant::now();
+            let result = client.block_number().await;
+            let latency_ms = started_at.elapsed().as_millis() as u64;
+
+            let mut report = serde_json::json!({
+                "healthy": result.is_ok(),
+                "latency_ms": latency_ms,
+                "block_number": result.as_ref().ok(),
+                "rpc_host": rpc_host(client.rpc_url()),
+            });
+            if let Err(e) = &result {
+                report["error"] = serde_json::json!(e.to_string());
+            }
+
+            (adapter.protocol_name(), result.is_ok(), report)
+        })
+    });
+
+    let results = join_all(probes).await;
+    let healthy_count = results.iter().filter(|(_, healthy, _)| *healthy).count();
+
+    let (status, code) = if !results.is_empty() && healthy_count == results.len() {
+        ("healthy", StatusCode::OK)
+    } else if healthy_count > 0 {
+        ("degraded", StatusCode::OK)
+    } else {
+        ("unhealthy", StatusCode::SERVICE_UNAVAILABLE)
+    };
+
+    let adapters: serde_json::Map<String, serde_json::Value> = results
+        .into_iter()
+        .map(|(protocol, _, report)| (protocol.to_string(), report))
+        .collect();
+
+    (code, Json(serde_json::json!({
+        "status": status,
+        "healthy_adapters": healthy_count,
+        "total_adapters": adapters.len(),
+        "adapters": adapters,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    })))
+}
+
+/// Host part of an RPC URL, so API keys in the path or query are never exposed
+fn rpc_host(rpc_url: &str) -> Option<String> {
+    url::Url::parse(rpc_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rpc_host_strips_key() {
+        assert_eq!(
+            rpc_host("https://eth-mainnet.g.alchemy.com/v2/secret-key").as_deref(),
+            Some("eth-mainnet.g.alchemy.com")
+        );
+        assert_eq!(rpc_host("not a url"), None);
+    }
+}
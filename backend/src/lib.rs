@@ -1,17 +1,21 @@
 // Only include modules that actually exist
 pub mod adapters;
+pub mod auth;
+pub mod blockchain;
 pub mod health;
+pub mod metrics;
+pub mod request_id;
+pub mod risk;
+pub mod schema;
+pub mod security;
+pub mod services;
+pub mod utils;
 
 // Removed missing modules (cleaned up):
 // pub mod handlers; - removed, starting fresh
-// pub mod services; - removed, starting fresh
 // pub mod config;
 // pub mod models;
-// pub mod blockchain;
 // pub mod error;
-// pub mod security;
-// pub mod auth;
-// pub mod utils;
 // pub mod database;
 // pub mod comprehensive_test_demo;
 
@@ -25,7 +29,18 @@ pub struct AppState {
     // No complex service layer needed
     pub rpc_url: String,
     pub coingecko_api_key: Option<String>,
+    pub metrics: std::sync::Arc<metrics::AdapterMetrics>,
+    pub jwt_service: std::sync::Arc<auth::jwt::JwtService>,
+    /// Adapters built once at startup and shared by every request, so their clients
+    /// and caches persist; historical reads still build their own pinned to a block
+    pub adapters: std::sync::Arc<Vec<Box<dyn adapters::DeFiAdapter>>>,
+    /// Client per configured chain, for reading chain heads
+    pub chain_clients: std::sync::Arc<std::collections::HashMap<u64, blockchain::EthereumClient>>,
+    /// Whether the mainnet RPC can serve state at past blocks, detected at startup
+    pub data_source: blockchain::DataSourceCapabilities,
+    /// Flips to `true` once the server starts shutting down, so long-lived handlers
+    /// such as WebSocket streams can close cleanly
+    pub shutdown: tokio::sync::watch::Receiver<bool>,
     // pub real_time_service: Option<std::sync::Arc<services::real_time_risk_service::RealTimeRiskService>>,
-    // pub jwt_service: std::sync::Arc<auth::jwt::JwtService>,
     // pub health_checker: std::sync::Arc<utils::monitoring::HealthChecker>,
 }
@@ -1,16 +1,27 @@
 use axum::{
-    routing::get,
+    middleware,
+    routing::{get, post},
     Router,
 };
 use std::net::SocketAddr;
-use tower_http::cors::CorsLayer;
-use tracing::info;
+use tracing::{info, Instrument};
 
 // Import ALL available working DeFi protocol adapters
 use defi_risk_monitor::{
+    AppState,
+    auth::{self, AuthError, JwtService, PortfolioAccess},
     health,
+    metrics::{self, AdapterMetrics, FetchOutcome},
+    request_id::{self, RequestId},
+    schema::{self, SchemaVersion, SCHEMA_VERSION_HEADER},
     adapters::{
+        self,
+        AdapterError,
         DeFiAdapter,
+        PnlBreakdown,
+        Position,
+        ProtocolError,
+        ProtocolFilter,
         UniswapV3Adapter,
         UniswapV2Adapter,
         LidoAdapter,
@@ -18,171 +29,2735 @@ use defi_risk_monitor::{
         EtherFiAdapter,
         YearnAdapter,
         MorphoBlueAdapter,
-        uniswap_v3::EthereumClient as V3EthereumClient,
-        uniswap_v2::EthereumClient as V2EthereumClient,
-        lido::EthereumClient as LidoEthereumClient,
-        rocketpool::EthereumClient as RocketPoolEthereumClient,
-        etherfi::EthereumClient as EtherFiEthereumClient,
-        yearnfinance::EthereumClient as YearnEthereumClient,
-        morphoblue::EthereumClient as MorphoBlueEthereumClient,
+        GmxAdapter,
+        VelodromeAdapter,
+        BalancerV2Adapter,
+        EthenaAdapter,
+        FraxAdapter,
+        SparkAdapter,
+        ConvexAdapter,
+        CompoundV2Adapter,
+        Erc4626Adapter,
+        BeefyAdapter,
     },
+    blockchain::{rpc_concurrency_limit, CapabilitySource, DataSourceCapabilities, EthereumClient},
+    security::{allowed_origins_from_env, check_outbound_url, cors_layer, rate_limit, InputValidator, RateLimiter, ValidatedAddress, ValidationError},
+    utils::{PaginatedResponse, Precision},
+    services::{export, exit_cost, report::{self, ReportSigner}, ExitCostPolicy, approvals::{ApprovalScanner, ApprovalSeverity, KNOWN_SPENDERS}, freshness, monitoring::{self, AlertLevel, LiquidationAlert}, AlertFeed, AlertService, ChannelConfig, LiveAlert, ValueDropTracker, WalletReading, oracle_deviation, OracleDeviationMonitor, portfolio_history, position_aggregator, price_history, EnsResolver, LiquidationMonitor, PortfolioHistoryStore, PortfolioSnapshot, DiffThresholds, PortfolioDiff, PortfolioWebhooks, WebhookSubscription, PriceAggregator, PriceService, GroupBy, asset_class, ProtocolTvlService, ProtocolRiskService, MarketShareLimit, AlertThresholds, RiskProfileStore, UserRiskSettings, ConfirmationPolicy, StalenessPolicy, with_price_timestamp},
+    risk::{self, var, Benchmark, BenchmarkComparison, ConcentrationLimits, PerformanceMetrics, RiskAggregation, RiskDecomposition, RiskMetrics, RiskProfile},
 };
-use axum::{response::Json, extract::Path, http::StatusCode};
-use alloy::primitives::Address;
+use axum::{
+    response::{IntoResponse, Json, Response},
+    extract::{Extension, Path, Query, Request, State, ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade}},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+};
+use serde::Deserialize;
+use alloy::primitives::{Address, B256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::IntoFuture;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use chrono;
 // For now, we'll implement a basic ENS resolution fallback
 // In production, you'd want to use a proper ENS resolver
 
-// Initialize ALL working DeFi protocol adapters
-async fn initialize_adapters(rpc_url: &str, coingecko_api_key: Option<String>) -> Vec<Box<dyn DeFiAdapter>> {
-    let mut adapters: Vec<Box<dyn DeFiAdapter>> = Vec::new();
+// RPC endpoints from ETHEREUM_RPC_URLS (comma-separated), falling back to the single URL
+fn configured_rpc_urls(rpc_url: &str) -> Vec<String> {
+    let urls: Vec<String> = std::env::var("ETHEREUM_RPC_URLS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+        .collect();
     
-    tracing::info!("🚀 Initializing ALL DeFi protocol adapters with RPC: {}", rpc_url);
+    if urls.is_empty() {
+        vec![rpc_url.to_string()]
+    } else {
+        urls
+    }
+}
+
+// RPC URL environment variable for each chain besides mainnet
+const CHAIN_RPC_ENV_VARS: &[(u64, &str)] = &[
+    (10, "OPTIMISM_RPC_URL"),
+    (137, "POLYGON_RPC_URL"),
+    (250, "FANTOM_RPC_URL"),
+    (8453, "BASE_RPC_URL"),
+    (42161, "ARBITRUM_RPC_URL"),
+    (43114, "AVALANCHE_RPC_URL"),
+];
+
+// Chains to query with their RPC endpoints: mainnet plus every chain with an RPC URL
+// configured, narrowed to ENABLED_CHAINS (comma-separated chain IDs) when set
+fn configured_chains(rpc_url: &str) -> Vec<(u64, Vec<String>)> {
+    let other_chains = CHAIN_RPC_ENV_VARS.iter().filter_map(|(chain_id, env_var)| {
+        let url = std::env::var(env_var).ok()?.trim().to_string();
+        (!url.is_empty()).then(|| (*chain_id, vec![url]))
+    });
+    let chains = std::iter::once((1, configured_rpc_urls(rpc_url))).chain(other_chains);
     
-    // Uniswap V3 Adapter
-    let v3_client = V3EthereumClient { rpc_url: rpc_url.to_string() };
-    match UniswapV3Adapter::new(v3_client) {
-        Ok(adapter) => {
-            adapters.push(Box::new(adapter));
-            tracing::info!("✅ Initialized Uniswap V3 adapter");
+    match std::env::var("ENABLED_CHAINS") {
+        Ok(enabled) => {
+            let enabled = parse_chain_list(&enabled);
+            chains.filter(|(chain_id, _)| enabled.contains(chain_id)).collect()
         }
+        Err(_) => chains.collect(),
+    }
+}
+
+fn parse_chain_list(list: &str) -> Vec<u64> {
+    list.split(',').filter_map(|id| id.trim().parse().ok()).collect()
+}
+
+// Initialize the working DeFi protocol adapters `filter` allows; with `block` set, mainnet
+// contract calls read state at that block, otherwise each chain's confirmation depth behind
+// its head
+async fn initialize_adapters(
+    rpc_url: &str,
+    coingecko_api_key: Option<String>,
+    block: Option<u64>,
+    filter: &ProtocolFilter,
+) -> Vec<Box<dyn DeFiAdapter>> {
+    let mut adapters: Vec<Box<dyn DeFiAdapter>> = Vec::new();
+    
+    let rpc_urls = configured_rpc_urls(rpc_url);
+    tracing::info!("🚀 Initializing ALL DeFi protocol adapters with {} RPC endpoint(s)", rpc_urls.len());
+    
+    // One client shared by all adapters so endpoint health is tracked globally
+    let client = match EthereumClient::with_fallbacks(rpc_urls) {
+        Ok(client) => client,
         Err(e) => {
-            tracing::warn!("❌ Failed to initialize Uniswap V3 adapter: {}", e);
+            tracing::error!("❌ Failed to create Ethereum client: {}", e);
+            return adapters;
+        }
+    };
+    let confirmations = ConfirmationPolicy::from_env();
+    let client = match block {
+        Some(block) => client.at_block(block),
+        None => client.with_confirmations(confirmations.confirmation_blocks(1)),
+    };
+    
+    // Uniswap V3 Adapter
+    if filter.allows("uniswap_v3") {
+        let v3_client = client.clone();
+        match UniswapV3Adapter::new(v3_client) {
+            Ok(adapter) => {
+                adapters.push(Box::new(adapter));
+                tracing::info!("✅ Initialized Uniswap V3 adapter");
+            }
+            Err(e) => {
+                tracing::warn!("❌ Failed to initialize Uniswap V3 adapter: {}", e);
+            }
         }
     }
     
     // Uniswap V2 Adapter
-    let v2_client = V2EthereumClient { rpc_url: rpc_url.to_string() };
-    match UniswapV2Adapter::new(v2_client) {
-        Ok(adapter) => {
-            adapters.push(Box::new(adapter));
-            tracing::info!("✅ Initialized Uniswap V2 adapter");
-        }
-        Err(e) => {
-            tracing::warn!("❌ Failed to initialize Uniswap V2 adapter: {}", e);
+    if filter.allows("uniswap_v2") {
+        let v2_client = client.clone();
+        match UniswapV2Adapter::new(v2_client) {
+            Ok(adapter) => {
+                adapters.push(Box::new(adapter));
+                tracing::info!("✅ Initialized Uniswap V2 adapter");
+            }
+            Err(e) => {
+                tracing::warn!("❌ Failed to initialize Uniswap V2 adapter: {}", e);
+            }
         }
     }
     
     // Lido Adapter (Liquid Staking)
-    let lido_client = LidoEthereumClient { rpc_url: rpc_url.to_string() };
-    match LidoAdapter::new(lido_client) {
-        Ok(adapter) => {
-            adapters.push(Box::new(adapter));
-            tracing::info!("✅ Initialized Lido adapter");
-        }
-        Err(e) => {
-            tracing::warn!("❌ Failed to initialize Lido adapter: {}", e);
+    if filter.allows("lido") {
+        let lido_client = client.clone();
+        match LidoAdapter::new(lido_client) {
+            Ok(adapter) => {
+                adapters.push(Box::new(adapter));
+                tracing::info!("✅ Initialized Lido adapter");
+            }
+            Err(e) => {
+                tracing::warn!("❌ Failed to initialize Lido adapter: {}", e);
+            }
         }
     }
     
     // Rocket Pool Adapter (Decentralized Liquid Staking)
-    let rocketpool_client = RocketPoolEthereumClient { rpc_url: rpc_url.to_string() };
-    match RocketPoolAdapter::new(rocketpool_client) {
-        Ok(adapter) => {
-            adapters.push(Box::new(adapter));
-            tracing::info!("✅ Initialized Rocket Pool adapter");
-        }
-        Err(e) => {
-            tracing::warn!("❌ Failed to initialize Rocket Pool adapter: {}", e);
+    if filter.allows("rocket_pool") {
+        let rocketpool_client = client.clone();
+        match RocketPoolAdapter::new(rocketpool_client) {
+            Ok(adapter) => {
+                adapters.push(Box::new(adapter));
+                tracing::info!("✅ Initialized Rocket Pool adapter");
+            }
+            Err(e) => {
+                tracing::warn!("❌ Failed to initialize Rocket Pool adapter: {}", e);
+            }
         }
     }
     
     // EtherFi Adapter (Liquid Staking + EigenLayer Restaking)
-    let etherfi_client = EtherFiEthereumClient { rpc_url: rpc_url.to_string() };
-    match EtherFiAdapter::new(etherfi_client) {
-        Ok(adapter) => {
-            adapters.push(Box::new(adapter));
-            tracing::info!("✅ Initialized EtherFi adapter");
+    if filter.allows("ether_fi") {
+        let etherfi_client = client.clone();
+        match EtherFiAdapter::new(etherfi_client) {
+            Ok(adapter) => {
+                adapters.push(Box::new(adapter));
+                tracing::info!("✅ Initialized EtherFi adapter");
+            }
+            Err(e) => {
+                tracing::warn!("❌ Failed to initialize EtherFi adapter: {}", e);
+            }
         }
-        Err(e) => {
-            tracing::warn!("❌ Failed to initialize EtherFi adapter: {}", e);
+    }
+    
+    // Balancer V2 Adapter (Weighted & Composable Stable Pools)
+    if filter.allows("balancer_v2") {
+        let balancer_client = client.clone();
+        match BalancerV2Adapter::new(balancer_client, coingecko_api_key.clone()) {
+            Ok(adapter) => {
+                adapters.push(Box::new(adapter));
+                tracing::info!("✅ Initialized Balancer V2 adapter");
+            }
+            Err(e) => {
+                tracing::warn!("❌ Failed to initialize Balancer V2 adapter: {}", e);
+            }
+        }
+    }
+    
+    // Ethena Adapter (USDe / sUSDe synthetic dollar)
+    if filter.allows("ethena") {
+        let ethena_client = client.clone();
+        match EthenaAdapter::new(ethena_client) {
+            Ok(adapter) => {
+                adapters.push(Box::new(adapter));
+                tracing::info!("✅ Initialized Ethena adapter");
+            }
+            Err(e) => {
+                tracing::warn!("❌ Failed to initialize Ethena adapter: {}", e);
+            }
+        }
+    }
+    
+    // Frax Adapter (sfrxETH staking, veFXS, Fraxlend)
+    if filter.allows("frax") {
+        let frax_client = client.clone();
+        match FraxAdapter::new(frax_client) {
+            Ok(adapter) => {
+                adapters.push(Box::new(adapter));
+                tracing::info!("✅ Initialized Frax adapter");
+            }
+            Err(e) => {
+                tracing::warn!("❌ Failed to initialize Frax adapter: {}", e);
+            }
+        }
+    }
+    
+    // Spark Adapter (SparkLend + sDAI / sUSDS savings)
+    if filter.allows("spark") {
+        let spark_client = client.clone();
+        match SparkAdapter::new(spark_client) {
+            Ok(adapter) => {
+                adapters.push(Box::new(adapter));
+                tracing::info!("✅ Initialized Spark adapter");
+            }
+            Err(e) => {
+                tracing::warn!("❌ Failed to initialize Spark adapter: {}", e);
+            }
+        }
+    }
+    
+    // Convex Finance Adapter (boosted Curve LP + cvxCRV)
+    if filter.allows("convex") {
+        let convex_client = client.clone();
+        match ConvexAdapter::new(convex_client) {
+            Ok(adapter) => {
+                adapters.push(Box::new(adapter));
+                tracing::info!("✅ Initialized Convex Finance adapter");
+            }
+            Err(e) => {
+                tracing::warn!("❌ Failed to initialize Convex Finance adapter: {}", e);
+            }
+        }
+    
+    }
+    
+    // Compound V2 Adapter (legacy cToken lending)
+    if filter.allows("compound_v2") {
+        let compound_client = client.clone();
+        match CompoundV2Adapter::new(compound_client) {
+            Ok(adapter) => {
+                adapters.push(Box::new(adapter));
+                tracing::info!("✅ Initialized Compound V2 adapter");
+            }
+            Err(e) => {
+                tracing::warn!("❌ Failed to initialize Compound V2 adapter: {}", e);
+            }
+        }
+    }
+    
+    // Generic ERC-4626 Adapter (allowlisted long-tail vaults)
+    if filter.allows("erc4626") {
+        match Erc4626Adapter::vaults_from_env() {
+            Ok(vaults) if vaults.is_empty() => {}
+            Ok(vaults) => {
+                tracing::info!("✅ Initialized ERC-4626 adapter for {} vault(s)", vaults.len());
+                adapters.push(Box::new(Erc4626Adapter::new(client.clone(), vaults)));
+            }
+            Err(e) => {
+                tracing::warn!("❌ Failed to initialize ERC-4626 adapter: {}", e);
+            }
+        }
+    }
+    
+    // Chain-aware adapters, one instance per configured chain they are deployed on
+    for (chain_id, chain_rpc_urls) in configured_chains(rpc_url) {
+        let chain_client = if chain_id == 1 {
+            client.clone()
+        } else {
+            match EthereumClient::with_fallbacks(chain_rpc_urls) {
+                Ok(chain_client) => chain_client.with_confirmations(confirmations.confirmation_blocks(chain_id)),
+                Err(e) => {
+                    tracing::warn!("❌ Failed to create client for chain {}: {}", chain_id, e);
+                    continue;
+                }
+            }
+        };
+        
+        // Yearn Finance Adapter (Yield Farming)
+        if YearnAdapter::supported_chains().contains(&chain_id) && filter.allows("yearn_finance") {
+            match YearnAdapter::new(chain_client.clone(), Some(chain_id)) {
+                Ok(adapter) => {
+                    adapters.push(Box::new(adapter));
+                    tracing::info!("✅ Initialized Yearn Finance adapter for chain {}", chain_id);
+                }
+                Err(e) => {
+                    tracing::warn!("❌ Failed to initialize Yearn Finance adapter for chain {}: {}", chain_id, e);
+                }
+            }
+        }
+        
+        // MorphoBlue Adapter (Lending Protocol)
+        if MorphoBlueAdapter::supported_chains().contains(&chain_id) && filter.allows("morpho_blue") {
+            match MorphoBlueAdapter::new(chain_client.clone(), chain_id) {
+                Ok(adapter) => {
+                    adapters.push(Box::new(adapter));
+                    tracing::info!("✅ Initialized MorphoBlue adapter for chain {}", chain_id);
+                }
+                Err(e) => {
+                    tracing::warn!("❌ Failed to initialize MorphoBlue adapter for chain {}: {}", chain_id, e);
+                }
+            }
+        }
+        
+        // GMX Adapter (Perpetuals on Arbitrum / Avalanche)
+        if GmxAdapter::supported_chains().contains(&chain_id) && filter.allows("gmx") {
+            match GmxAdapter::new(chain_client.clone(), chain_id) {
+                Ok(adapter) => {
+                    adapters.push(Box::new(adapter));
+                    tracing::info!("✅ Initialized GMX adapter for chain {}", chain_id);
+                }
+                Err(e) => {
+                    tracing::warn!("❌ Failed to initialize GMX adapter for chain {}: {}", chain_id, e);
+                }
+            }
+        }
+        
+        // Beefy Adapter (auto-compounding vaults)
+        if BeefyAdapter::supported_chains().contains(&chain_id) && filter.allows("beefy") {
+            match BeefyAdapter::new(chain_client.clone(), chain_id) {
+                Ok(adapter) => {
+                    adapters.push(Box::new(adapter));
+                    tracing::info!("✅ Initialized Beefy adapter for chain {}", chain_id);
+                }
+                Err(e) => {
+                    tracing::warn!("❌ Failed to initialize Beefy adapter for chain {}: {}", chain_id, e);
+                }
+            }
+        }
+        
+        // Velodrome (Optimism) / Aerodrome (Base) Adapter (ve(3,3) DEX)
+        if VelodromeAdapter::protocol_for_chain(chain_id).is_some_and(|protocol| filter.allows(protocol)) {
+            match VelodromeAdapter::new(chain_client.clone(), chain_id) {
+                Ok(adapter) => {
+                    adapters.push(Box::new(adapter));
+                    tracing::info!("✅ Initialized Velodrome/Aerodrome adapter for chain {}", chain_id);
+                }
+                Err(e) => {
+                    tracing::warn!("❌ Failed to initialize Velodrome/Aerodrome adapter for chain {}: {}", chain_id, e);
+                }
+            }
         }
     }
     
-    // Yearn Finance Adapter (Yield Farming)
-    let yearn_client = YearnEthereumClient { rpc_url: rpc_url.to_string() };
-    match YearnAdapter::new(yearn_client, None) { // Expects Option<u64> for chain_id
-        Ok(adapter) => {
-            adapters.push(Box::new(adapter));
-            tracing::info!("✅ Initialized Yearn Finance adapter");
+    tracing::info!("🚀 Successfully initialized {} DeFi protocol adapters", adapters.len());
+    tracing::info!("📊 Supported protocols: {}", 
+        adapters.iter().map(|a| a.protocol_name()).collect::<Vec<_>>().join(", "));
+    
+    adapters
+}
+
+async fn get_adapter_health(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    let (status, Json(mut report)) = health::adapter_health(&state.adapters).await;
+    
+    report["rpc_concurrency_limit"] = serde_json::json!(rpc_concurrency_limit());
+    report["data_source"] = serde_json::json!(state.data_source);
+    
+    let prices = PriceService::global();
+    report["price_feed"] = serde_json::json!({
+        "circuit": prices.circuit_state(),
+        "prices": prices.price_ages(),
+    });
+    
+    (status, Json(report))
+}
+
+// Protocols, chains and position types the initialized adapters cover
+async fn get_supported_protocols(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let confirmations = ConfirmationPolicy::from_env();
+    let confirmation_blocks: BTreeMap<u64, u64> = configured_chains(&state.rpc_url)
+        .into_iter()
+        .map(|(chain_id, _)| (chain_id, confirmations.confirmation_blocks(chain_id)))
+        .collect();
+
+    Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "version": env!("CARGO_PKG_VERSION"),
+            "archive_node": state.data_source.archive_node,
+            // Blocks behind the head each configured chain is read at
+            "require_confirmations": confirmations.require_confirmations,
+            "confirmation_blocks": confirmation_blocks,
+            "protocols": adapters::protocol_catalog(&state.adapters)
+        }
+    }))
+}
+
+// Parse a hex address, or resolve an ENS name through the shared mainnet client
+async fn resolve_address(input: &str, clients: &HashMap<u64, EthereumClient>) -> Result<Address, String> {
+    if let Ok(addr) = Address::from_str(input) {
+        return Ok(addr);
+    }
+    if !input.contains('.') {
+        return Err(format!("Invalid address format: '{}'. Please provide a valid Ethereum address or ENS name", input));
+    }
+
+    let client = clients
+        .get(&1)
+        .ok_or_else(|| format!("Can't resolve '{}': no mainnet RPC is configured", input))?;
+    match EnsResolver::global().resolve(client, input).await {
+        Ok(Some(address)) => {
+            tracing::info!("✅ Resolved {} to {}", input, address);
+            Ok(address)
+        }
+        Ok(None) => Err(format!("ENS name '{}' does not resolve to an address", input)),
+        Err(e) => Err(format!("Failed to resolve ENS name '{}': {}", input, e)),
+    }
+}
+
+// Positions gathered from every adapter for one address
+struct PortfolioFetch {
+    positions: Vec<Position>,
+    protocol_stats: HashMap<String, usize>,
+    errors: Vec<ProtocolError>,
+    protocols_queried: usize,
+    dust_positions_hidden: usize,
+    dust_value_usd: f64,
+    /// Positions dropped because an adapter queried earlier already reported the same ID
+    duplicates_removed: usize,
+    /// Coin ids each protocol had valued at today's price for lack of a historical one
+    spot_priced: BTreeMap<String, Vec<String>>,
+}
+
+impl PortfolioFetch {
+    // Fraction of queried adapters that returned positions without error
+    fn completeness(&self) -> f64 {
+        if self.protocols_queried == 0 {
+            return 0.0;
+        }
+        self.protocols_queried.saturating_sub(self.errors.len()) as f64 / self.protocols_queried as f64
+    }
+
+    // 200 when every adapter answered, 206 when some failed, 502 when none did
+    fn status_code(&self) -> StatusCode {
+        if self.protocols_queried > 0 && self.errors.is_empty() {
+            StatusCode::OK
+        } else if self.errors.len() < self.protocols_queried {
+            StatusCode::PARTIAL_CONTENT
+        } else {
+            StatusCode::BAD_GATEWAY
         }
+    }
+}
+
+// Query every adapter for an address, recording per-adapter metrics
+async fn fetch_portfolio(state: &AppState, address: Address) -> PortfolioFetch {
+    fetch_portfolio_filtered(state, address, None, None, &ProtocolFilter::all()).await
+}
+
+// Shared mainnet client; `None` only when no mainnet RPC is configured
+fn mainnet_client(state: &AppState) -> Option<&EthereumClient> {
+    state.chain_clients.get(&1)
+}
+
+// Verified primary ENS name for the address; lookup failures just omit the name
+async fn lookup_ens_name(state: &AppState, address: Address) -> Option<String> {
+    EnsResolver::global().reverse_resolve(mainnet_client(state)?, address).await
+}
+
+// Record the chain a position was read from, unless the adapter already did
+fn tag_chain_id(position: &mut Position, chain_id: u64) {
+    if let Some(metadata) = position.metadata.as_object_mut() {
+        metadata.entry("chain_id").or_insert(serde_json::json!(chain_id));
+    }
+}
+
+// Client per configured chain, for reading chain heads
+fn chain_clients(rpc_url: &str) -> HashMap<u64, EthereumClient> {
+    configured_chains(rpc_url)
+        .into_iter()
+        .filter_map(|(chain_id, urls)| Some((chain_id, EthereumClient::with_fallbacks(urls).ok()?)))
+        .collect()
+}
+
+async fn chain_head(clients: &HashMap<u64, EthereumClient>, chain_id: u64) -> Option<u64> {
+    match clients.get(&chain_id)?.block_number().await {
+        Ok(head) => Some(head),
         Err(e) => {
-            tracing::warn!("❌ Failed to initialize Yearn Finance adapter: {}", e);
+            tracing::debug!("Failed to read head of chain {}: {}", chain_id, e);
+            None
+        }
+    }
+}
+
+// Mainnet block a portfolio is read at, with its timestamp for historical prices
+#[derive(Debug, Clone, Copy)]
+struct HistoricalBlock {
+    number: u64,
+    timestamp: u64,
+}
+
+// Look up a requested block; fails for blocks the RPC node doesn't have yet
+async fn resolve_block(state: &AppState, block: u64) -> Result<HistoricalBlock, AdapterError> {
+    let client = mainnet_client(state)
+        .ok_or_else(|| AdapterError::UnsupportedChain("no mainnet RPC is configured".to_string()))?;
+    let timestamp = client.block_timestamp(block).await?;
+    Ok(HistoricalBlock { number: block, timestamp })
+}
+
+// As fetch_portfolio, dropping positions worth less than `min_value_usd` before they are
+// counted towards protocol_stats. With `block` set, positions are read at that mainnet
+// block and priced at its date by adapters built for that block; adapters that can't read
+// historical state report an error instead of returning current positions. Otherwise the
+// adapters shared in `state` are used. Only adapters `protocols` allows are queried.
+async fn fetch_portfolio_filtered(
+    state: &AppState,
+    address: Address,
+    min_value_usd: Option<f64>,
+    block: Option<HistoricalBlock>,
+    protocols: &ProtocolFilter,
+) -> PortfolioFetch {
+    let historical_adapters = match block {
+        Some(block) => initialize_adapters(
+            &state.rpc_url,
+            state.coingecko_api_key.clone(),
+            Some(block.number),
+            protocols,
+        ).await,
+        None => Vec::new(),
+    };
+    let adapters: Vec<&dyn DeFiAdapter> = match block {
+        Some(_) => historical_adapters.iter().map(|adapter| adapter.as_ref()).collect(),
+        None => state.adapters
+            .iter()
+            .filter(|adapter| protocols.allows(adapter.protocol_name()))
+            .map(|adapter| adapter.as_ref())
+            .collect(),
+    };
+    let mut all_positions = Vec::new();
+    let mut errors = Vec::new();
+    let mut protocol_stats = HashMap::new();
+    let mut dust_positions_hidden = 0;
+    let mut dust_value_usd = 0.0;
+    let mut spot_priced = BTreeMap::new();
+    let mut seen_ids = HashSet::new();
+    let mut duplicates_removed = 0;
+
+    tracing::info!("📡 Querying {} protocol adapters for positions", adapters.len());
+
+    let total_adapters = adapters.len();
+    let clients = &state.chain_clients;
+    
+    // Fetch positions from all adapters
+    for adapter in adapters {
+        let protocol_name = adapter.protocol_name();
+        tracing::debug!("🔄 Querying {} for positions...", protocol_name);
+        
+        if let Some(block) = block {
+            if adapter.chain_id() != 1 || !adapter.supports_historical_block() {
+                errors.push(ProtocolError::new(protocol_name, &AdapterError::UnsupportedProtocol(format!(
+                    "{} on chain {} cannot be queried at historical block {}",
+                    protocol_name, adapter.chain_id(), block.number
+                ))));
+                continue;
+            }
+        }
+        
+        // Block the adapter reads at: the pinned one, or the head right before fetching less
+        // the confirmation depth of adapters reading contract state through their client
+        let confirmations = match block {
+            Some(_) => 0,
+            None => adapter.rpc_client()
+                .filter(|_| adapter.supports_historical_block())
+                .map_or(0, |client| client.confirmations()),
+        };
+        let block_number = match block {
+            Some(block) => Some(block.number),
+            None => chain_head(clients, adapter.chain_id()).await.map(|head| head.saturating_sub(confirmations)),
+        };
+        
+        // Child of the request span, so each adapter's latency shows under the request id
+        let span = tracing::info_span!(
+            "adapter_fetch",
+            protocol = protocol_name,
+            address = %address,
+            chain_id = adapter.chain_id(),
+            latency_ms = tracing::field::Empty,
+        );
+        let started_at = Instant::now();
+        let fetch = adapter.fetch_positions(address);
+        let (result, spot_fallbacks) = with_price_timestamp(block.map(|b| b.timestamp), fetch).instrument(span.clone()).await;
+        span.record("latency_ms", started_at.elapsed().as_millis() as u64);
+        if !spot_fallbacks.is_empty() {
+            tracing::warn!("⚠️ {} valued {:?} at current prices for want of prices at the block", protocol_name, spot_fallbacks);
+            spot_priced.insert(protocol_name.to_string(), spot_fallbacks);
+        }
+        match result {
+            Ok(mut positions) => {
+                state.metrics.record_fetch(protocol_name, FetchOutcome::Success, started_at.elapsed(), positions.len());
+                if let Some(min_value_usd) = min_value_usd {
+                    positions.retain(|p| {
+                        let keep = p.value_usd.abs() >= min_value_usd;
+                        if !keep {
+                            dust_positions_hidden += 1;
+                            dust_value_usd += p.value_usd;
+                        }
+                        keep
+                    });
+                }
+                // Each position is counted once, however many adapters report it
+                let reported = positions.len();
+                positions.retain(|p| seen_ids.insert(p.id.clone()));
+                duplicates_removed += reported - positions.len();
+                let count = positions.len();
+                if count > 0 {
+                    tracing::info!("✅ Found {} positions in {} on chain {}", count, protocol_name, adapter.chain_id());
+                    for position in &mut positions {
+                        tag_chain_id(position, adapter.chain_id());
+                        if let Some(block_number) = block_number {
+                            freshness::tag_block_number(position, block_number);
+                        }
+                        if confirmations > 0 {
+                            freshness::tag_confirmations(position, confirmations);
+                        }
+                    }
+                    *protocol_stats.entry(protocol_name.to_string()).or_insert(0) += count;
+                    all_positions.append(&mut positions);
+                } else {
+                    tracing::debug!("ℹ️ No positions found in {}", protocol_name);
+                }
+            }
+            Err(e) => {
+                state.metrics.record_fetch(protocol_name, FetchOutcome::Error, started_at.elapsed(), 0);
+                tracing::warn!("⚠️ Failed to fetch positions from {}: {}", protocol_name, e);
+                errors.push(ProtocolError::new(protocol_name, &e));
+            }
+        }
+    }
+
+    // Age of each position's data relative to its chain's head now
+    let staleness = StalenessPolicy::from_env();
+    let chain_ids: std::collections::BTreeSet<u64> = all_positions
+        .iter()
+        .filter_map(|p| p.metadata.get("chain_id").and_then(|v| v.as_u64()))
+        .collect();
+    let heads: HashMap<u64, Option<u64>> = futures::future::join_all(
+        chain_ids.into_iter().map(|chain_id| async move { (chain_id, chain_head(clients, chain_id).await) }),
+    ).await.into_iter().collect();
+    for position in &mut all_positions {
+        let chain_id = position.metadata.get("chain_id").and_then(|v| v.as_u64()).unwrap_or(1);
+        freshness::annotate_freshness(position, heads.get(&chain_id).copied().flatten(), &staleness);
+    }
+    // Flag positions priced while their price sources disagreed; historical reads are
+    // priced from CoinGecko alone
+    if block.is_none() {
+        PriceAggregator::global().tag_price_uncertainty(&mut all_positions);
+    }
+
+    PortfolioFetch {
+        positions: all_positions,
+        protocol_stats,
+        errors,
+        protocols_queried: total_adapters,
+        dust_positions_hidden,
+        dust_value_usd,
+        duplicates_removed,
+        spot_priced,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PositionSortKey {
+    ValueUsd,
+    RiskScore,
+    PnlUsd,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PositionsQuery {
+    min_value_usd: Option<f64>,
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+    sort_by: Option<PositionSortKey>,
+    #[serde(default)]
+    order: SortOrder,
+    /// Fiat currency for monetary fields, USD by default
+    currency: Option<String>,
+    /// Mainnet block number to read positions at instead of the latest block
+    block: Option<u64>,
+    /// Comma-separated protocols to query, e.g. `lido,uniswap_v3`; all when unset
+    protocols: Option<String>,
+    /// Comma-separated protocols to skip
+    exclude: Option<String>,
+    /// Nest the returned positions under `asset_class`, `protocol` or `chain` groups
+    group_by: Option<GroupBy>,
+    /// Response schema version; overrides the `Accept-Version` header
+    v: Option<String>,
+}
+
+// Scale monetary position fields by a USD exchange rate
+fn convert_currency(positions: &mut [Position], fx_rate: f64) {
+    if fx_rate == 1.0 {
+        return;
+    }
+    for position in positions {
+        position.value_usd *= fx_rate;
+        position.pnl_usd *= fx_rate;
+    }
+}
+
+fn sort_positions(positions: &mut [Position], key: PositionSortKey, order: SortOrder) {
+    let value = |p: &Position| match key {
+        PositionSortKey::ValueUsd => p.value_usd,
+        PositionSortKey::RiskScore => risk::position_risk_score(p),
+        PositionSortKey::PnlUsd => p.pnl_usd,
+    };
+    positions.sort_by(|a, b| {
+        let ordering = value(a).partial_cmp(&value(b)).unwrap_or(std::cmp::Ordering::Equal);
+        match order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    });
+}
+
+#[derive(Debug, Deserialize)]
+struct WalletTokenRequest {
+    wallet: String,
+    /// Delegate that signed on the wallet's behalf; the wallet itself when unset
+    signer: Option<String>,
+    /// Unix timestamp included in the signed PortfolioAccess message
+    issued_at: u64,
+    /// 32-byte hex nonce included in the signed message; each is accepted once
+    nonce: String,
+    signature: String,
+}
+
+// Exchange an EIP-712 PortfolioAccess signature for a short-lived token scoped to the wallet
+async fn issue_wallet_token(
+    State(state): State<AppState>,
+    Json(request): Json<WalletTokenRequest>,
+) -> Response {
+    let hex_address = |input: &str| match InputValidator::validate_address(input) {
+        Ok(ValidatedAddress::Hex(address)) => Ok(address),
+        Ok(ValidatedAddress::Ens(_)) => Err(ValidationError::MalformedAddress),
+        Err(e) => Err(e),
+    };
+    let wallet = match hex_address(&request.wallet) {
+        Ok(wallet) => wallet,
+        Err(e) => return e.into_response(),
+    };
+    let signer = match request.signer.as_deref().map(hex_address).transpose() {
+        Ok(signer) => signer.unwrap_or(wallet),
+        Err(e) => return e.into_response(),
+    };
+
+    let Some(client) = mainnet_client(&state) else {
+        return AuthError::DelegationCheckFailed("no mainnet RPC is configured".to_string()).into_response();
+    };
+    let nonce = match B256::from_str(request.nonce.trim()) {
+        Ok(nonce) => nonce,
+        Err(e) => return AuthError::InvalidSignature(format!("invalid nonce: {}", e)).into_response(),
+    };
+    let access = PortfolioAccess { wallet, signer, issuedAt: request.issued_at, nonce };
+    if let Err(e) = auth::verify_portfolio_access(client, &access, &request.signature).await {
+        tracing::warn!("🔒 Rejected access request for {:?} signed by {:?}: {}", wallet, signer, e);
+        return e.into_response();
+    }
+
+    match state.jwt_service.issue(wallet, signer) {
+        Ok(issued) => Json(serde_json::json!({
+            "success": true,
+            "wallet": format!("{:#x}", wallet),
+            "signer": format!("{:#x}", signer),
+            "token": issued.token,
+            "token_type": issued.token_type,
+            "expires_at": issued.expires_at,
+        })).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// Only let requests through whose bearer token was issued for the `:address` path wallet
+async fn require_wallet_token(
+    State(state): State<AppState>,
+    Path(address_input): Path<String>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let address_str = match InputValidator::validate_address(&address_input) {
+        Ok(validated) => validated.normalized(),
+        Err(e) => return e.into_response(),
+    };
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(address) => address,
+        Err(error_msg) => {
+            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            }))).into_response();
+        }
+    };
+
+    match state.jwt_service.authorize(request.headers(), address) {
+        Ok(_) => next.run(request).await,
+        Err(e) => e.into_response(),
+    }
+}
+
+// REQUIRE_PORTFOLIO_AUTH: wallet data is only served with a token for that wallet
+fn portfolio_auth_required() -> bool {
+    std::env::var("REQUIRE_PORTFOLIO_AUTH")
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+// `require_wallet_token` for handlers whose wallet comes from the query or body
+fn authorize_wallet(state: &AppState, headers: &HeaderMap, wallet: Address) -> Result<(), AuthError> {
+    if portfolio_auth_required() {
+        state.jwt_service.authorize(headers, wallet)?;
+    }
+    Ok(())
+}
+
+// API endpoint handlers - Real position fetching from all adapters
+async fn get_portfolio_positions(
+    State(state): State<AppState>,
+    request_id: Extension<RequestId>,
+    Path(address_input): Path<String>,
+    Query(query): Query<PositionsQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ValidationError> {
+    let schema = SchemaVersion::negotiate(query.v.as_deref(), &headers)
+        .map_err(ValidationError::UnsupportedSchemaVersion)?;
+    let mut response = portfolio_positions(state, request_id, address_input, query, schema).await?.into_response();
+    response.headers_mut().insert(SCHEMA_VERSION_HEADER, HeaderValue::from_static(schema.as_str()));
+    Ok(response)
+}
+
+async fn portfolio_positions(
+    state: AppState,
+    Extension(RequestId(request_id)): Extension<RequestId>,
+    address_input: String,
+    query: PositionsQuery,
+    schema: SchemaVersion,
+) -> Result<(StatusCode, Json<serde_json::Value>), ValidationError> {
+    // Reject garbage before any adapter is invoked; lowercase form keys caches
+    let address_str = InputValidator::validate_address(&address_input)?.normalized();
+    let protocols = ProtocolFilter::parse(query.protocols.as_deref(), query.exclude.as_deref())?;
+    tracing::info!("🔍 Fetching portfolio positions for address: {}", address_str);
+    
+    // Resolve the address (handles both direct addresses and ENS names)
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            tracing::warn!("❌ Address resolution failed: {}", error_msg);
+            return Ok((StatusCode::OK, Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            }))));
+        }
+    };
+
+    let currency = query.currency.as_deref().unwrap_or("usd").trim().to_uppercase();
+    let fx_rate = match PriceService::global().usd_fx_rate(&currency).await {
+        Ok(rate) => rate,
+        Err(e) => {
+            tracing::warn!("❌ No exchange rate for {}: {}", currency, e);
+            return Ok((StatusCode::OK, Json(serde_json::json!({
+                "success": false,
+                "error": "Unsupported currency",
+                "message": e.to_string()
+            }))));
+        }
+    };
+
+    if query.block.is_some() && !state.data_source.archive_node {
+        return Ok((StatusCode::NOT_IMPLEMENTED, Json(serde_json::json!({
+            "success": false,
+            "error": "Archive node required",
+            "message": "The configured RPC node does not serve historical state, so positions can only be read at the latest block",
+            "data_source": state.data_source
+        }))));
+    }
+
+    let block = match query.block {
+        Some(block) => match resolve_block(&state, block).await {
+            Ok(block) => Some(block),
+            Err(e) => {
+                tracing::warn!("❌ Block {} lookup failed: {}", block, e);
+                return Ok((StatusCode::OK, Json(serde_json::json!({
+                    "success": false,
+                    "error": "Invalid block",
+                    "message": e.to_string()
+                }))));
+            }
+        },
+        None => None,
+    };
+
+    let (fetch, ens_name) = tokio::join!(
+        fetch_portfolio_filtered(&state, address, query.min_value_usd, block, &protocols),
+        lookup_ens_name(&state, address),
+    );
+    let status = fetch.status_code();
+    let completeness = fetch.completeness();
+    let PortfolioFetch {
+        positions: mut all_positions,
+        protocol_stats,
+        errors,
+        protocols_queried: total_adapters,
+        dust_positions_hidden,
+        dust_value_usd,
+        duplicates_removed,
+        spot_priced,
+    } = fetch;
+
+    // Gas to close each position at the current gas price, which says nothing about a past block
+    let exit_costs = match block {
+        None => Some(exit_cost::estimate_exit_costs(&mut all_positions, address, &state.chain_clients, &ExitCostPolicy::from_env()).await),
+        Some(_) => None,
+    };
+
+    // Everything below is in the requested currency; the `_usd` field names are kept for
+    // compatibility and labeled by `currency`. Position metadata stays in USD.
+    convert_currency(&mut all_positions, fx_rate);
+    let dust_value_usd = dust_value_usd * fx_rate;
+
+    // Calculate portfolio summary before converting positions; positions are already
+    // deduplicated, and those with an unpriced token are listed but not counted towards
+    // the USD totals
+    let priced = || all_positions.iter().filter(|p| !p.is_price_unknown());
+    let total_value_usd: f64 = priced().map(|p| p.value_usd).sum();
+    let total_pnl_usd: f64 = priced().map(|p| p.pnl_usd).sum();
+    // Breakdowns are kept in USD in metadata
+    let pnl_breakdown = priced().filter_map(Position::pnl_breakdown).sum::<PnlBreakdown>().scaled(fx_rate);
+    let price_unknown_positions = all_positions.len() - priced().count();
+    let stale_positions = all_positions
+        .iter()
+        .filter(|p| p.metadata.get("stale").and_then(|v| v.as_bool()) == Some(true))
+        .count();
+    let total_positions = all_positions.len();
+    
+    // Net exposure across protocols (supply netted against borrow per token)
+    let aggregated = position_aggregator::aggregate(all_positions.clone());
+
+    // Totals above cover the whole portfolio; only the returned positions are paged
+    if let Some(sort_by) = query.sort_by {
+        sort_positions(&mut all_positions, sort_by, query.order);
+    }
+    // Group subtotals cover the whole portfolio too
+    let groups = query.group_by.map(|group_by| asset_class::group_totals(&all_positions, group_by));
+    let page = PaginatedResponse::paginate(all_positions, query.limit, query.offset);
+    let group_keys: Vec<Option<String>> = page.items
+        .iter()
+        .map(|pos| query.group_by.map(|group_by| group_by.key(pos)))
+        .collect();
+
+    // Convert positions to frontend format, rounding away float noise in money fields
+    let precision = Precision::global();
+    let frontend_positions: Vec<serde_json::Value> = page.items
+        .into_iter()
+        .map(|pos| frontend_position(&pos, &address_str, &currency, fx_rate, &precision, schema))
+        .collect();
+    
+    tracing::info!("📊 Portfolio Summary: {} positions, ${:.2} total value, ${:.2} PnL", 
+        total_positions, total_value_usd, total_pnl_usd);
+
+    // With group_by, positions are nested under their group instead of listed flat
+    let (positions_key, positions_json) = match groups {
+        Some(groups) => {
+            let mut nested: Vec<serde_json::Value> = groups
+                .into_iter()
+                .map(|group| {
+                    let mut group = serde_json::json!(group);
+                    group["positions"] = serde_json::json!([]);
+                    group
+                })
+                .collect();
+            for (position, key) in frontend_positions.into_iter().zip(group_keys) {
+                let group = nested.iter_mut().find(|group| key.as_deref() == group["key"].as_str());
+                if let Some(group) = group.and_then(|group| group["positions"].as_array_mut()) {
+                    group.push(position);
+                }
+            }
+            ("groups", serde_json::json!(nested))
+        }
+        None => ("positions", serde_json::json!(frontend_positions)),
+    };
+
+    let mut response = serde_json::json!({
+        "success": status != StatusCode::BAD_GATEWAY,
+        "data": {
+            "pagination": page.pagination,
+            "summary": {
+                "total_positions": total_positions,
+                "total_value_usd": precision.usd(total_value_usd),
+                "total_pnl_usd": precision.usd(total_pnl_usd),
+                "pnl_breakdown": {
+                    "price_pnl": precision.usd(pnl_breakdown.price_pnl),
+                    "fees_earned": precision.usd(pnl_breakdown.fees_earned),
+                    "rewards_earned": precision.usd(pnl_breakdown.rewards_earned),
+                    "interest": precision.usd(pnl_breakdown.interest),
+                    "impermanent_loss": precision.usd(pnl_breakdown.impermanent_loss),
+                },
+                "price_unknown_positions": price_unknown_positions,
+                "stale_positions": stale_positions,
+                "protocol_breakdown": protocol_stats,
+                "net_exposure_by_token": aggregated.net_exposure_by_token
+                    .iter()
+                    .map(|(token, exposure)| (token.clone(), precision.usd(*exposure)))
+                    .collect::<BTreeMap<_, _>>(),
+                "total_supplied_usd": precision.usd(aggregated.total_supplied_usd),
+                "total_borrowed_usd": precision.usd(aggregated.total_borrowed_usd),
+                "leverage_ratio": aggregated.leverage_ratio,
+                "portfolio_net_apy": aggregated.portfolio_net_apy,
+                "projected_annual_yield_usd": precision.usd(aggregated.projected_annual_yield_usd),
+                "dust_positions_hidden": dust_positions_hidden,
+                "dust_value_usd": precision.usd(dust_value_usd),
+                "duplicates_removed": duplicates_removed,
+                "total_estimated_exit_gas_usd": exit_costs.map(|costs| precision.usd(costs.total_exit_cost_usd * fx_rate)),
+                "net_of_exit_cost_positions": exit_costs.map(|costs| costs.net_of_exit_cost_positions),
+                "uneconomical_exit_positions": exit_costs.map(|costs| costs.uneconomical_positions),
+                "currency": currency,
+                "last_updated": chrono::Utc::now().to_rfc3339()
+            }
+        },
+        "errors": if errors.is_empty() { None } else { Some(errors) },
+        "meta": {
+            "address": address_str,
+            "request_id": request_id,
+            "currency": currency,
+            "fx_rate_from_usd": fx_rate,
+            "schema_version": schema,
+            "protocols_queried": total_adapters,
+            "protocols_with_positions": protocol_stats.len(),
+            "completeness": completeness
+        }
+    });
+    response["data"][positions_key] = positions_json;
+    if let Some(ens_name) = ens_name {
+        response["meta"]["ens_name"] = serde_json::json!(ens_name);
+    }
+    // Coins a historical read had to value at today's price, by protocol
+    if block.is_some() {
+        response["meta"]["spot_priced"] = serde_json::json!(spot_priced);
+    }
+    if let Some(block) = block {
+        response["meta"]["block"] = serde_json::json!(block.number);
+        response["meta"]["block_timestamp"] = serde_json::json!(block.timestamp);
+    }
+
+    Ok((status, Json(response)))
+}
+
+#[derive(Debug, Deserialize)]
+struct PositionValidationRequest {
+    /// Wallet whose current positions the candidate is assessed alongside
+    address: Option<String>,
+    protocol: String,
+    position_type: String,
+    pair: String,
+    value_usd: f64,
+    /// Optional details such as `health_factor`, `risk_score` or `transferable`
+    #[serde(default)]
+    metadata: serde_json::Map<String, serde_json::Value>,
+    /// Overrides the wallet's saved risk profile
+    profile: Option<RiskProfile>,
+}
+
+// Validate and risk-score a prospective position without adding it to monitoring
+async fn validate_position(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<PositionValidationRequest>,
+) -> Result<Response, ValidationError> {
+    InputValidator::validate_position(&request.protocol, &request.position_type, &request.pair, request.value_usd)?;
+
+    let address = match &request.address {
+        Some(input) => {
+            let address_str = InputValidator::validate_address(input)?.normalized();
+            match resolve_address(&address_str, &state.chain_clients).await {
+                Ok(addr) => Some(addr),
+                Err(error_msg) => {
+                    return Ok(Json(serde_json::json!({
+                        "success": false,
+                        "error": "Address resolution failed",
+                        "message": error_msg
+                    })).into_response());
+                }
+            }
+        }
+        None => None,
+    };
+
+    // The wallet's positions and saved profile are its data, so it needs a token too
+    if let Some(address) = address {
+        if let Err(e) = authorize_wallet(&state, &headers, address) {
+            return Ok(e.into_response());
+        }
+    }
+
+    let (existing, errors) = match address {
+        Some(address) => {
+            let portfolio = fetch_portfolio(&state, address).await;
+            (portfolio.positions, portfolio.errors)
+        }
+        None => (Vec::new(), Vec::new()),
+    };
+    let profile = request.profile
+        .or_else(|| address.and_then(|address| RiskProfileStore::global().get(address)))
+        .unwrap_or_default();
+
+    let candidate = Position {
+        id: "preview".to_string(),
+        protocol: request.protocol.trim().to_lowercase(),
+        position_type: request.position_type,
+        pair: request.pair.trim().to_string(),
+        value_usd: request.value_usd,
+        pnl_usd: 0.0,
+        pnl_percentage: 0.0,
+        metadata: serde_json::Value::Object(request.metadata),
+        last_updated: chrono::Utc::now().timestamp() as u64,
+    };
+    let preview = risk::preview_position(
+        &candidate,
+        &existing,
+        &profile.health_thresholds(),
+        &ConcentrationLimits::from_env(),
+    );
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "valid": true,
+            "persisted": false,
+            "position": candidate,
+            "risk_profile": profile.as_str(),
+            "existing_positions": existing.len(),
+            "assessment": preview
+        },
+        "errors": if errors.is_empty() { None } else { Some(errors) }
+    })).into_response())
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PositionDetailQuery {
+    /// Response schema version; overrides the `Accept-Version` header
+    v: Option<String>,
+}
+
+// Split a canonical position ID into its protocol key, chain and owner; the asset
+// segment may itself contain colons
+fn parse_position_id(id: &str) -> Result<(String, u64, Address), ValidationError> {
+    let invalid = || ValidationError::InvalidPosition(format!(
+        "'{}' is not a position ID of the form protocol:chain_id:type:asset:owner", id
+    ));
+    let mut parts = id.trim().splitn(4, ':');
+    let (Some(protocol), Some(chain_id), Some(_position_type), Some(rest)) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+    let (asset, owner) = rest.rsplit_once(':').ok_or_else(invalid)?;
+    let chain_id = chain_id.parse::<u64>().map_err(|_| invalid())?;
+    let owner = Address::from_str(owner).map_err(|_| invalid())?;
+    if protocol.is_empty() || asset.is_empty() {
+        return Err(invalid());
+    }
+    Ok((protocol.to_string(), chain_id, owner))
+}
+
+// One position re-read from its adapter, with everything behind its risk score
+async fn get_position_detail(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<PositionDetailQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ValidationError> {
+    let schema = SchemaVersion::negotiate(query.v.as_deref(), &headers)
+        .map_err(ValidationError::UnsupportedSchemaVersion)?;
+    let id = id.trim().to_lowercase();
+    let (protocol, chain_id, owner) = parse_position_id(&id)?;
+    let protocols = ProtocolFilter::parse(Some(&protocol), None)?;
+
+    if let Err(e) = authorize_wallet(&state, &headers, owner) {
+        return Ok(e.into_response());
+    }
+
+    // Only the owning protocol's adapters are queried. A failed adapter means the
+    // position can't be ruled out, so that's a 502 rather than a 404.
+    let fetch = fetch_portfolio_filtered(&state, owner, None, None, &protocols).await;
+    let Some(position) = fetch.positions.iter().find(|p| p.id == id) else {
+        let (status, error) = if fetch.errors.is_empty() {
+            (StatusCode::NOT_FOUND, "Position not found")
+        } else {
+            (StatusCode::BAD_GATEWAY, "Protocol unavailable")
+        };
+        return Ok((status, Json(serde_json::json!({
+            "success": false,
+            "error": error,
+            "message": format!("No open {} position with ID {} on chain {}", protocol, id, chain_id),
+            "errors": if fetch.errors.is_empty() { None } else { Some(&fetch.errors) }
+        }))).into_response());
+    };
+
+    let thresholds = RiskProfileStore::global().get(owner).unwrap_or_default().health_thresholds();
+    let precision = Precision::global();
+    let explain = risk::explain_position(position, &thresholds);
+    let mut response = Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "position": frontend_position(position, &format!("{:#x}", owner), "USD", 1.0, &precision, schema),
+            "risk": explain,
+        },
+        "meta": {
+            "schema_version": schema,
+            "fetched_at": chrono::Utc::now().to_rfc3339(),
+        }
+    })).into_response();
+    response.headers_mut().insert(SCHEMA_VERSION_HEADER, HeaderValue::from_static(schema.as_str()));
+    Ok(response)
+}
+
+// A position in the frontend format, rounding away float noise in money fields. Fields
+// the adapter didn't report are stand-ins in schema v1 and `null` from v2 on.
+fn frontend_position(
+    pos: &Position,
+    address_str: &str,
+    currency: &str,
+    fx_rate: f64,
+    precision: &Precision,
+    schema: SchemaVersion,
+) -> serde_json::Value {
+    let risk_score = risk::position_risk_score(pos);
+    let impermanent_loss_usd = pos.metadata
+        .get("impermanent_loss_usd")
+        .and_then(|v| v.as_f64())
+        .unwrap_or(0.0) * fx_rate;
+    let fees_earned = pos.pnl_breakdown().map_or(0.0, |pnl| pnl.fees_earned * fx_rate);
+    let exit_gas = pos.metadata.get("estimated_exit_gas_usd").and_then(|v| v.as_f64());
+    
+    let mut json = serde_json::json!({
+        "id": pos.id,
+        "user_id": address_str,
+        "protocol": pos.protocol,
+        "pool_address": "", // Will be in metadata
+        "chain_id": pos.metadata.get("chain_id").and_then(|v| v.as_u64()).unwrap_or(1),
+        "token0_address": "", // Will be in metadata
+        "token1_address": "", // Will be in metadata
+        "position_type": pos.position_type,
+        "value_usd": precision.format_usd(pos.value_usd),
+        "liquidity": "0", // Will be calculated
+        "tick_lower": pos.metadata.get("tick_lower").and_then(|v| v.as_i64()).unwrap_or(0),
+        "tick_upper": pos.metadata.get("tick_upper").and_then(|v| v.as_i64()).unwrap_or(0),
+        // Concentrated liquidity earns fees only while the pool's tick is in range
+        "in_range": pos.metadata.get("in_range").and_then(|v| v.as_bool()),
+        "pnl_usd": precision.format_usd(pos.pnl_usd),
+        "fees_earned_usd": precision.format_usd(fees_earned),
+        "impermanent_loss_usd": precision.format_usd(impermanent_loss_usd),
+        "risk_score": risk_score,
+        // Adapters score the positions they understand; the rest are estimated
+        "risk_score_source": if risk::reported_risk_score(pos).is_some() { "adapter" } else { "estimated" },
+        "asset_class": asset_class::classify(pos),
+        "price_unknown": pos.is_price_unknown(),
+        "price_uncertain": pos.is_price_uncertain(),
+        "block_number": freshness::position_block(pos),
+        "data_age_blocks": pos.metadata.get("data_age_blocks").and_then(|v| v.as_u64()),
+        "stale": pos.metadata.get("stale").and_then(|v| v.as_bool()).unwrap_or(false),
+        "confirmations": pos.metadata.get("confirmations").and_then(|v| v.as_u64()).unwrap_or(0),
+        "estimated_exit_gas_usd": exit_gas.map(|gas| precision.format_usd(gas * fx_rate)),
+        // Exit gas is a material share of the position, so its yield should be read net of it
+        "net_of_exit_cost": pos.metadata.get("net_of_exit_cost").and_then(|v| v.as_bool()).unwrap_or(false),
+        "is_active": true,
+        "created_at": chrono::DateTime::from_timestamp(pos.last_updated as i64, 0)
+            .unwrap_or_default()
+            .to_rfc3339(),
+        "updated_at": chrono::DateTime::from_timestamp(pos.last_updated as i64, 0)
+            .unwrap_or_default()
+            .to_rfc3339(),
+        "pair": pos.pair,
+        "currency": currency,
+        "metadata": pos.metadata
+    });
+
+    if schema.nulls_placeholders() {
+        let reported = |keys: &[&str]| {
+            keys.iter().find_map(|key| pos.metadata.get(*key)).cloned().unwrap_or(serde_json::Value::Null)
+        };
+        json["chain_id"] = reported(&["chain_id"]);
+        json["pool_address"] = reported(&["pool_address", "pair_address"]);
+        json["token0_address"] = reported(&["token0_address", "token0"]);
+        json["token1_address"] = reported(&["token1_address", "token1"]);
+        json["liquidity"] = reported(&["liquidity"]);
+        json["tick_lower"] = reported(&["tick_lower"]);
+        json["tick_upper"] = reported(&["tick_upper"]);
+        json["fees_earned_usd"] = serde_json::json!(pos.pnl_breakdown().map(|pnl| precision.format_usd(pnl.fees_earned * fx_rate)));
+        json["impermanent_loss_usd"] = serde_json::json!(pos.metadata
+            .get("impermanent_loss_usd")
+            .and_then(|v| v.as_f64())
+            .map(|il| precision.format_usd(il * fx_rate)));
+        json["created_at"] = serde_json::Value::Null;
+    }
+
+    json
+}
+
+// Fields of each position per schema version, and which carry real values
+async fn get_response_schema() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "default_version": SchemaVersion::DEFAULT,
+            "latest_version": SchemaVersion::LATEST,
+            "supported_versions": SchemaVersion::SUPPORTED,
+            "position_fields": schema::POSITION_FIELDS,
+        }
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportQuery {
+    format: Option<String>,
+    min_value_usd: Option<f64>,
+}
+
+// Positions export: CSV by default, or the regular JSON payload with ?format=json
+async fn export_portfolio_positions(
+    State(state): State<AppState>,
+    request_id: Extension<RequestId>,
+    Path(address_input): Path<String>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> Response {
+    match query.format.as_deref().unwrap_or("csv") {
+        "csv" => {}
+        "json" => {
+            let positions_query = PositionsQuery { min_value_usd: query.min_value_usd, ..PositionsQuery::default() };
+            return get_portfolio_positions(State(state), request_id, Path(address_input), Query(positions_query), headers).await.into_response();
+        }
+        other => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({
+                "success": false,
+                "error": "unsupported_format",
+                "message": format!("Unsupported export format '{}', expected csv or json", other)
+            }))).into_response();
+        }
+    }
+    
+    let address_str = match InputValidator::validate_address(&address_input) {
+        Ok(validated) => validated.normalized(),
+        Err(e) => return e.into_response(),
+    };
+    
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            }))).into_response();
+        }
+    };
+    
+    let portfolio = fetch_portfolio_filtered(&state, address, query.min_value_usd, None, &ProtocolFilter::all()).await;
+    let csv = export::positions_to_csv(&portfolio.positions);
+    
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"positions_{}.csv\"", address_str)),
+        ],
+        csv,
+    ).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct ReportQuery {
+    block: Option<u64>,
+}
+
+// Tamper-evident snapshot of a portfolio at one mainnet block: its positions, the SHA-256
+// of their canonical JSON and, with REPORT_SIGNING_KEY set, a server signature over the
+// hash. Defaults to the latest confirmed block; older blocks need an archive node.
+async fn get_portfolio_report(
+    State(state): State<AppState>,
+    Path(address_input): Path<String>,
+    Query(query): Query<ReportQuery>,
+) -> Response {
+    let address_str = match InputValidator::validate_address(&address_input) {
+        Ok(validated) => validated.normalized(),
+        Err(e) => return e.into_response(),
+    };
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return (StatusCode::NOT_FOUND, Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            }))).into_response();
+        }
+    };
+
+    if query.block.is_some() && !state.data_source.archive_node {
+        return (StatusCode::NOT_IMPLEMENTED, Json(serde_json::json!({
+            "success": false,
+            "error": "Archive node required",
+            "message": "The configured RPC node does not serve historical state, so reports can only be taken at the latest block",
+            "data_source": state.data_source
+        }))).into_response();
+    }
+
+    let block_number = match query.block {
+        Some(block) => block,
+        None => match chain_head(&state.chain_clients, 1).await {
+            Some(head) => head.saturating_sub(ConfirmationPolicy::from_env().confirmation_blocks(1)),
+            None => {
+                return (StatusCode::BAD_GATEWAY, Json(serde_json::json!({
+                    "success": false,
+                    "error": "Chain head unavailable",
+                    "message": "Could not read the latest mainnet block"
+                }))).into_response();
+            }
+        },
+    };
+    let block = match resolve_block(&state, block_number).await {
+        Ok(block) => block,
+        Err(e) => {
+            tracing::warn!("❌ Block {} lookup failed: {}", block_number, e);
+            return (StatusCode::OK, Json(serde_json::json!({
+                "success": false,
+                "error": "Invalid block",
+                "message": e.to_string()
+            }))).into_response();
+        }
+    };
+
+    let fetch = fetch_portfolio_filtered(&state, address, None, Some(block), &ProtocolFilter::all()).await;
+    if fetch.status_code() == StatusCode::BAD_GATEWAY {
+        return (StatusCode::BAD_GATEWAY, Json(serde_json::json!({
+            "success": false,
+            "error": "Protocols unavailable",
+            "message": format!("No protocol could be read at block {}", block.number),
+            "errors": fetch.errors
+        }))).into_response();
+    }
+
+    let unavailable = fetch.errors.iter().map(|error| error.protocol.clone()).collect();
+    match report::build_report(address, block.number, block.timestamp, &fetch.positions, unavailable, &fetch.spot_priced, ReportSigner::global()) {
+        Ok(report) => Json(serde_json::json!({
+            "success": true,
+            "data": report
+        })).into_response(),
+        Err(e) => {
+            tracing::error!("❌ Failed to build report for {}: {}", address_str, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({
+                "success": false,
+                "error": "Report failed",
+                "message": e.to_string()
+            }))).into_response()
+        }
+    }
+}
+
+// WebSocket endpoint pushing a wallet's alerts as the monitor detects them
+async fn live_alerts_stream(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Path(address_str): Path<String>,
+) -> Response {
+    let address_str = match InputValidator::validate_address(&address_str) {
+        Ok(validated) => validated.normalized(),
+        Err(e) => return e.into_response(),
+    };
+
+    ws.on_upgrade(move |socket| stream_live_alerts(socket, state, address_str))
+}
+
+async fn stream_live_alerts(mut socket: WebSocket, state: AppState, address_str: String) {
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            let error = serde_json::json!({
+                "type": "error",
+                "error": "Address resolution failed",
+                "message": error_msg
+            });
+            let _ = socket.send(Message::Text(error.to_string())).await;
+            let _ = socket.send(Message::Close(None)).await;
+            return;
+        }
+    };
+
+    // Subscribers of the same wallet share one monitor: the background one for wallets
+    // it polls, otherwise one started by the first subscriber
+    let mut subscription = AlertFeed::global().subscribe(address);
+    let mut shutdown = state.shutdown.clone();
+    if subscription.start_monitor && !background_wallets().contains(&address) {
+        spawn_wallet_alert_monitor(state, address);
+    }
+
+    let snapshot = serde_json::json!({
+        "type": "snapshot",
+        "address": address_str,
+        "alerts": subscription.active,
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    });
+    if socket.send(Message::Text(snapshot.to_string())).await.is_err() {
+        return;
+    }
+
+    tracing::info!("📡 Streaming live alerts for {}", address_str);
+
+    loop {
+        tokio::select! {
+            alert = subscription.receiver.recv() => {
+                let alert = match alert {
+                    Ok(alert) => alert,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("⚠️ Live alert subscriber for {} skipped {} alert(s)", address_str, skipped);
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                let message = serde_json::json!({ "type": "alert", "alert": alert });
+                if socket.send(Message::Text(message.to_string())).await.is_err() {
+                    break;
+                }
+            }
+            _ = shutdown_requested(&mut shutdown) => {
+                let _ = socket.send(shutdown_close_frame()).await;
+                break;
+            }
+            incoming = socket.recv() => {
+                match incoming {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    tracing::info!("🔌 Live alert stream closed for {}", address_str);
+}
+
+#[derive(Debug, Deserialize)]
+struct ValueStreamQuery {
+    interval_secs: Option<u64>,
+}
+
+const DEFAULT_STREAM_INTERVAL_SECS: u64 = 30;
+const MIN_STREAM_INTERVAL_SECS: u64 = 5;
+
+// WebSocket endpoint pushing live portfolio value updates
+async fn portfolio_value_stream(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Path(address_str): Path<String>,
+    Query(query): Query<ValueStreamQuery>,
+) -> Response {
+    let address_str = match InputValidator::validate_address(&address_str) {
+        Ok(validated) => validated.normalized(),
+        Err(e) => return e.into_response(),
+    };
+    
+    let interval_secs = query.interval_secs
+        .unwrap_or(DEFAULT_STREAM_INTERVAL_SECS)
+        .max(MIN_STREAM_INTERVAL_SECS);
+    
+    ws.on_upgrade(move |socket| stream_portfolio_value(socket, state, address_str, Duration::from_secs(interval_secs)))
+}
+
+async fn stream_portfolio_value(mut socket: WebSocket, state: AppState, address_str: String, interval: Duration) {
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            let error = serde_json::json!({
+                "type": "error",
+                "error": "Address resolution failed",
+                "message": error_msg
+            });
+            let _ = socket.send(Message::Text(error.to_string())).await;
+            let _ = socket.send(Message::Close(None)).await;
+            return;
+        }
+    };
+    
+    tracing::info!("📡 Streaming portfolio value for {} every {:?}", address_str, interval);
+    
+    // First tick fires immediately, giving the client an initial snapshot
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut shutdown = state.shutdown.clone();
+    
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                let update = portfolio_value_update(&state, address, &address_str).await;
+                if socket.send(Message::Text(update.to_string())).await.is_err() {
+                    break;
+                }
+            }
+            _ = shutdown_requested(&mut shutdown) => {
+                let _ = socket.send(shutdown_close_frame()).await;
+                break;
+            }
+            incoming = socket.recv() => {
+                match incoming {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    _ => {}
+                }
+            }
+        }
+    }
+    
+    tracing::info!("🔌 Portfolio value stream closed for {}", address_str);
+}
+
+async fn portfolio_value_update(state: &AppState, address: Address, address_str: &str) -> serde_json::Value {
+    let portfolio = fetch_portfolio(state, address).await;
+    
+    let precision = Precision::global();
+    let mut protocol_values: HashMap<String, f64> = HashMap::new();
+    for position in &portfolio.positions {
+        *protocol_values.entry(position.protocol.clone()).or_insert(0.0) += position.value_usd;
+    }
+    for value in protocol_values.values_mut() {
+        *value = precision.usd(*value);
+    }
+    
+    serde_json::json!({
+        "type": "portfolio_value",
+        "address": address_str,
+        "total_value_usd": precision.usd(portfolio.positions.iter().filter(|p| !p.is_price_unknown()).map(|p| p.value_usd).sum::<f64>()),
+        "total_pnl_usd": precision.usd(portfolio.positions.iter().filter(|p| !p.is_price_unknown()).map(|p| p.pnl_usd).sum::<f64>()),
+        "total_positions": portfolio.positions.len(),
+        "protocol_breakdown": protocol_values,
+        "errors": if portfolio.errors.is_empty() { None } else { Some(portfolio.errors) },
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    })
+}
+
+async fn get_portfolio_summary() -> Result<Json<serde_json::Value>, StatusCode> {
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "total_value_usd": 10000.0,
+            "total_pnl_usd": 500.0,
+            "pnl_percentage": 5.0,
+            "risk_score": 0.65,
+            "positions": []
+        }
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+struct RiskMetricsQuery {
+    address: Option<String>,
+    #[serde(default)]
+    aggregation: RiskAggregation,
+    /// Overrides the wallet's saved risk profile
+    profile: Option<RiskProfile>,
+}
+
+async fn get_portfolio_risk_metrics(
+    State(state): State<AppState>,
+    Query(query): Query<RiskMetricsQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    // Without an address there are no positions to score
+    let Some(address_str) = query.address else {
+        return Ok(Json(serde_json::json!({
+            "success": true,
+            "data": {
+                "status": "insufficient_data",
+                "overall_risk": null,
+                "liquidity_risk": null,
+                "volatility_risk": null,
+                "mev_risk": null,
+                "protocol_risk": null,
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            }
+        })).into_response());
+    };
+    
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            })).into_response());
+        }
+    };
+
+    if let Err(e) = authorize_wallet(&state, &headers, address) {
+        return Ok(e.into_response());
+    }
+    
+    let portfolio = fetch_portfolio(&state, address).await;
+    let profile = query.profile
+        .or_else(|| RiskProfileStore::global().get(address))
+        .unwrap_or_default();
+    let overall_risk = profile.scale_score(query.aggregation.aggregate(&portfolio.positions));
+    let (protocol_risk, protocol_risks) = ProtocolRiskService::global().portfolio_risk(&portfolio.positions);
+    let thresholds = profile.health_thresholds();
+    let oracle_deviations = match mainnet_client(&state) {
+        Some(client) => OracleDeviationMonitor::from_env(client.clone()).check_positions(&portfolio.positions).await,
+        None => Vec::new(),
+    };
+    
+    let sandwich_risks = risk::portfolio_sandwich_risk(&portfolio.positions);
+    let sandwich_attack_risk: Vec<serde_json::Value> = sandwich_risks
+        .iter()
+        .map(|(position_id, sandwich)| {
+            let mut entry = serde_json::json!(sandwich);
+            entry["position_id"] = serde_json::json!(position_id);
+            entry
+        })
+        .collect();
+    
+    let health_alerts: Vec<serde_json::Value> = portfolio.positions
+        .iter()
+        .filter_map(|position| {
+            let health_factor = monitoring::health_factor(position)?;
+            let level = thresholds.level(health_factor);
+            (level != AlertLevel::Healthy).then(|| serde_json::json!({
+                "position_id": position.id,
+                "protocol": position.protocol,
+                "asset": position.pair,
+                "health_factor": health_factor,
+                "level": level,
+            }))
+        })
+        .collect();
+    let illiquid_value_ratio = risk::illiquid_value_ratio(&portfolio.positions);
+    
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "status": "ok",
+            "overall_risk": overall_risk,
+            "aggregation": query.aggregation.as_str(),
+            "risk_profile": profile.as_str(),
+            "health_factor_thresholds": {
+                "warning": thresholds.warning,
+                "critical": thresholds.critical,
+            },
+            "health_alerts": health_alerts,
+            // Audit coverage, exploit history and governance of the protocols held, by value
+            "protocol_risk": protocol_risk,
+            "protocol_risks": protocol_risks,
+            "concentration_warnings": risk::concentration_warnings(&portfolio.positions, &ConcentrationLimits::from_env()),
+            "oracle_deviation_risk": oracle_deviation::portfolio_oracle_risk(&oracle_deviations),
+            "oracle_deviations": oracle_deviations,
+            "mev_risk": sandwich_risks.first().map(|(_, sandwich)| sandwich.score),
+            "sandwich_attack_risk": sandwich_attack_risk,
+            "max_estimated_slippage": risk::max_estimated_slippage(&portfolio.positions),
+            "illiquid_value_ratio": illiquid_value_ratio,
+            // Share of value that can't be exited right now
+            "liquidity_risk": illiquid_value_ratio,
+            // Needs price history; `/analytics/portfolio-performance` reports `daily_volatility`
+            "volatility_risk": null,
+            "liquidation_simulations": risk::liquidation::simulate_portfolio(&portfolio.positions),
+            // Loans in different markets that one collateral price drop liquidates together
+            "cascade_risk": risk::cascade_risk(&portfolio.positions),
+            "cross_chain_risk": risk::CrossChainRisk::from_positions(&portfolio.positions),
+            "positions_scored": portfolio.positions.len(),
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        },
+        "errors": if portfolio.errors.is_empty() { None } else { Some(portfolio.errors) }
+    })).into_response())
+}
+
+// Unlimited and excessive token approvals from the wallet to known protocol spenders
+async fn get_token_approvals(
+    State(state): State<AppState>,
+    Path(address_input): Path<String>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    let address_str = InputValidator::validate_address(&address_input)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            })));
+        }
+    };
+
+    let Some(client) = mainnet_client(&state) else {
+        return Ok(Json(serde_json::json!({
+            "success": false,
+            "error": "RPC unavailable",
+            "message": "no mainnet RPC is configured"
+        })));
+    };
+
+    let portfolio = fetch_portfolio(&state, address).await;
+    let tokens = ApprovalScanner::tokens_for(&portfolio.positions);
+    let approvals = ApprovalScanner::new(client.clone()).scan(address, &tokens).await;
+    let unlimited = approvals.iter().filter(|a| a.severity == ApprovalSeverity::Unlimited).count();
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "address": address_str,
+            "tokens_checked": tokens.len(),
+            "spenders_checked": KNOWN_SPENDERS.len(),
+            "unlimited_approvals": unlimited,
+            "approvals": approvals
+        },
+        "errors": if portfolio.errors.is_empty() { None } else { Some(portfolio.errors) }
+    })))
+}
+
+// Either field may be left out to keep what is saved
+#[derive(Debug, Deserialize)]
+struct RiskProfileUpdate {
+    risk_profile: Option<RiskProfile>,
+    alert_thresholds: Option<AlertThresholds>,
+}
+
+fn risk_profile_json(address: Address, settings: UserRiskSettings, saved: bool) -> serde_json::Value {
+    let profile = settings.risk_profile;
+    let thresholds = settings.health_thresholds();
+    let alert_config = settings.alert_config(AlertFeed::global().config());
+    serde_json::json!({
+        "address": format!("{:?}", address),
+        "risk_profile": profile.as_str(),
+        "saved": saved,
+        "risk_weight": profile.risk_weight(),
+        "health_factor_thresholds": {
+            "warning": thresholds.warning,
+            "critical": thresholds.critical,
+        },
+        "value_drop_percent": alert_config.value_drop_percent,
+        "risk_score_threshold": alert_config.risk_score_threshold,
+        // Only what the wallet set itself; everything else follows the profile or service defaults
+        "alert_thresholds": settings.alert_thresholds,
+    })
+}
+
+async fn get_user_risk_profile(
+    State(state): State<AppState>,
+    Path(address_input): Path<String>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    let address_str = InputValidator::validate_address(&address_input)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            })));
+        }
+    };
+
+    let saved = RiskProfileStore::global().settings(address);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": risk_profile_json(address, saved.unwrap_or_default(), saved.is_some()),
+    })))
+}
+
+async fn update_user_risk_profile(
+    State(state): State<AppState>,
+    Path(address_input): Path<String>,
+    Json(update): Json<RiskProfileUpdate>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    let address_str = InputValidator::validate_address(&address_input)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            })));
+        }
+    };
+
+    let store = RiskProfileStore::global();
+    if let Some(alert_thresholds) = &update.alert_thresholds {
+        alert_thresholds.validate().map_err(ValidationError::InvalidAlertThresholds)?;
+        // A single override can still cross the other band coming from the profile
+        let mut merged = store.settings(address).unwrap_or_default();
+        merged.risk_profile = update.risk_profile.unwrap_or(merged.risk_profile);
+        merged.alert_thresholds = *alert_thresholds;
+        let bands = merged.health_thresholds();
+        if bands.critical >= bands.warning {
+            return Err(ValidationError::InvalidAlertThresholds(format!(
+                "critical health factor {} must be below warning {}",
+                bands.critical, bands.warning
+            )));
+        }
+    }
+    if let Some(profile) = update.risk_profile {
+        store.set(address, profile);
+        tracing::info!("🎚️ Risk profile for {:?} set to {}", address, profile.as_str());
+    }
+    if let Some(alert_thresholds) = update.alert_thresholds {
+        store.set_alert_thresholds(address, alert_thresholds);
+        tracing::info!("🎚️ Alert thresholds for {:?} set to {:?}", address, alert_thresholds);
+    }
+
+    let saved = store.settings(address);
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": risk_profile_json(address, saved.unwrap_or_default(), saved.is_some()),
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+struct NotificationSettingsUpdate {
+    channels: Vec<ChannelConfig>,
+}
+
+fn notification_settings_json(address: Address, channels: &[ChannelConfig]) -> serde_json::Value {
+    serde_json::json!({
+        "address": format!("{:?}", address),
+        "channels": channels.iter().map(ChannelConfig::redacted).collect::<Vec<_>>(),
+    })
+}
+
+// Behind the wallet token: channel configs hold bot tokens and webhook URLs
+async fn get_notification_settings(
+    State(state): State<AppState>,
+    Path(address_input): Path<String>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    let address_str = InputValidator::validate_address(&address_input)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            })));
+        }
+    };
+
+    let channels = AlertService::global().settings().get(address);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": notification_settings_json(address, &channels),
+    })))
+}
+
+async fn update_notification_settings(
+    State(state): State<AppState>,
+    Path(address_input): Path<String>,
+    Json(update): Json<NotificationSettingsUpdate>,
+) -> Result<(StatusCode, Json<serde_json::Value>), ValidationError> {
+    let address_str = InputValidator::validate_address(&address_input)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok((StatusCode::OK, Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            }))));
+        }
+    };
+
+    for channel in &update.channels {
+        let checked = match channel {
+            // Also catch names that resolve into our own network before accepting them
+            ChannelConfig::Webhook { url } => check_outbound_url(url).await.map(|_| ()),
+            _ => channel.validate(),
+        };
+        if let Err(message) = checked {
+            return Ok((StatusCode::BAD_REQUEST, Json(serde_json::json!({
+                "success": false,
+                "error": "invalid_channel",
+                "message": format!("{} channel: {}", channel.kind(), message)
+            }))));
+        }
+    }
+
+    AlertService::global().settings().set(address, update.channels.clone());
+    tracing::info!("🔔 {:?} configured {} notification channel(s)", address, update.channels.len());
+
+    Ok((StatusCode::OK, Json(serde_json::json!({
+        "success": true,
+        "data": notification_settings_json(address, &update.channels),
+    }))))
+}
+
+async fn get_notification_deliveries(
+    State(state): State<AppState>,
+    Path(address_input): Path<String>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    let address_str = InputValidator::validate_address(&address_input)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            })));
+        }
+    };
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": AlertService::global().deliveries(address),
+    })))
+}
+
+fn webhook_subscription_json(address: Address, subscription: Option<&WebhookSubscription>) -> serde_json::Value {
+    serde_json::json!({
+        "address": format!("{:?}", address),
+        "webhook": subscription.map(WebhookSubscription::redacted),
+    })
+}
+
+// Behind the wallet token: webhook URLs are secrets, and the wallet owner decides where
+// its portfolio changes are pushed
+async fn get_portfolio_webhook(
+    State(state): State<AppState>,
+    Path(address_input): Path<String>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    let address_str = InputValidator::validate_address(&address_input)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            })));
+        }
+    };
+
+    let subscription = PortfolioWebhooks::global().get(address);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": webhook_subscription_json(address, subscription.as_ref()),
+    })))
+}
+
+async fn update_portfolio_webhook(
+    State(state): State<AppState>,
+    Path(address_input): Path<String>,
+    Json(subscription): Json<WebhookSubscription>,
+) -> Result<(StatusCode, Json<serde_json::Value>), ValidationError> {
+    let address_str = InputValidator::validate_address(&address_input)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok((StatusCode::OK, Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            }))));
+        }
+    };
+
+    let checked = match subscription.validate() {
+        // Also catch names that resolve into our own network before accepting them
+        Ok(()) => check_outbound_url(&subscription.url).await.map(|_| ()),
+        Err(message) => Err(message),
+    };
+    if let Err(message) = checked {
+        return Ok((StatusCode::BAD_REQUEST, Json(serde_json::json!({
+            "success": false,
+            "error": "invalid_webhook",
+            "message": message
+        }))));
+    }
+
+    PortfolioWebhooks::global().set(address, Some(subscription.clone()));
+    tracing::info!("📬 {:?} registered a portfolio change webhook", address);
+
+    Ok((StatusCode::OK, Json(serde_json::json!({
+        "success": true,
+        "data": webhook_subscription_json(address, Some(&subscription)),
+    }))))
+}
+
+async fn delete_portfolio_webhook(
+    State(state): State<AppState>,
+    Path(address_input): Path<String>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    let address_str = InputValidator::validate_address(&address_input)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            })));
+        }
+    };
+
+    PortfolioWebhooks::global().set(address, None);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": webhook_subscription_json(address, None),
+    })))
+}
+
+async fn get_live_risk_alerts() -> Result<Json<serde_json::Value>, StatusCode> {
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": []
+    })))
+}
+
+async fn get_position_risk_heatmap() -> Result<Json<serde_json::Value>, StatusCode> {
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": []
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+struct AnalyticsQuery {
+    address: Option<String>,
+    horizon_days: Option<u32>,
+    /// Lookback for performance metrics, e.g. 30d
+    window: Option<String>,
+    /// Annualized, as a fraction; overrides `RISK_FREE_RATE`
+    risk_free_rate: Option<f64>,
+    /// eth, btc or none
+    benchmark: Option<Benchmark>,
+}
+
+const DEFAULT_PERFORMANCE_WINDOW: &str = "30d";
+const DEFAULT_RISK_FREE_RATE: f64 = 0.04;
+
+// Query parameter first, then RISK_FREE_RATE, then the T-bill/staking baseline
+fn risk_free_rate(requested: Option<f64>) -> f64 {
+    requested
+        .or_else(|| std::env::var("RISK_FREE_RATE").ok().and_then(|v| v.parse().ok()))
+        .filter(|rate: &f64| rate.is_finite())
+        .unwrap_or(DEFAULT_RISK_FREE_RATE)
+}
+
+// The wallet's recorded (timestamp, total value) points within the window
+fn equity_curve(address: Address, window: Duration) -> Vec<(u64, f64)> {
+    let since = (chrono::Utc::now().timestamp() as u64).saturating_sub(window.as_secs());
+    PortfolioHistoryStore::global()
+        .history(address, since)
+        .iter()
+        .map(|snapshot| (snapshot.timestamp, snapshot.total_value_usd))
+        .collect()
+}
+
+// Performance over the wallet's recorded equity curve, or insufficient_data
+fn performance_from_history(curve: &[(u64, f64)], risk_free_rate: f64) -> serde_json::Value {
+    match PerformanceMetrics::from_equity_curve(curve, risk_free_rate) {
+        Some(metrics) => serde_json::json!({
+            "status": "ok",
+            "total_return_usd": Precision::global().format_usd(metrics.total_return_usd),
+            "total_return_percentage": metrics.total_return_percentage.to_string(),
+            "volatility": metrics.annualized_volatility.to_string(),
+            "sharpe_ratio": metrics.sharpe_ratio.to_string(),
+            "max_drawdown": metrics.max_drawdown.to_string(),
+            "risk_free_rate": metrics.risk_free_rate,
+            "observations": metrics.daily_observations,
+        }),
+        None => serde_json::json!({
+            "status": "insufficient_data",
+            "total_return_usd": null,
+            "total_return_percentage": null,
+            "volatility": null,
+            "sharpe_ratio": null,
+            "max_drawdown": null,
+            "observations": curve.len(),
+            "min_daily_observations": risk::performance::MIN_DAILY_OBSERVATIONS,
+        }),
+    }
+}
+
+// CoinGecko coin whose returns stand in for holdings without a price history of their own
+const VAR_PROXY_COIN: &str = "ethereum";
+const VAR_HISTORY_DAYS: u32 = 365;
+
+// Daily returns VaR is simulated on, and where they came from
+struct VarReturns {
+    returns: Vec<f64>,
+    /// Value held in each coin whose own history was used
+    coins: BTreeMap<String, f64>,
+    /// Tokens without a usable history, simulated with VAR_PROXY_COIN's returns
+    proxied: BTreeMap<String, f64>,
+}
+
+// The portfolio's own daily returns: each token's price history weighted by its share
+// of the value of priced positions, with debt counting against the token owed. `None`
+// when there's no value to weigh or no history could be fetched.
+async fn portfolio_var_returns(positions: &[Position], portfolio_value_usd: f64, api_key: Option<&str>) -> Option<VarReturns> {
+    if portfolio_value_usd <= 0.0 {
+        return None;
+    }
+
+    let mut exposures: BTreeMap<String, f64> = BTreeMap::new();
+    for position in positions.iter().filter(|p| !p.is_price_unknown()) {
+        let tokens = position_aggregator::underlying_tokens(position);
+        let share = position.value_usd / tokens.len() as f64;
+        for token in tokens {
+            *exposures.entry(token).or_default() += share;
+        }
+    }
+
+    let mut coin_values: BTreeMap<&'static str, f64> = BTreeMap::new();
+    let mut proxied = BTreeMap::new();
+    for (token, value) in exposures {
+        match price_history::coin_id_for_symbol(&token) {
+            Some(coin_id) => *coin_values.entry(coin_id).or_default() += value,
+            None => {
+                proxied.insert(token, value);
+            }
+        }
+    }
+
+    let histories = futures::future::join_all(
+        coin_values.keys().map(|coin_id| price_history::fetch_daily_prices(coin_id, VAR_HISTORY_DAYS, api_key))
+    ).await;
+    let mut returns_by_coin: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+    let mut coins = BTreeMap::new();
+    for ((coin_id, value), history) in coin_values.into_iter().zip(histories) {
+        match history.map(|prices| var::returns_from_prices(&prices)) {
+            Ok(returns) if !returns.is_empty() => {
+                returns_by_coin.insert(coin_id, returns);
+                coins.insert(coin_id.to_string(), value);
+            }
+            Ok(_) => {
+                proxied.insert(coin_id.to_string(), value);
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ No {} price history for VaR, using {} instead: {}", coin_id, VAR_PROXY_COIN, e);
+                proxied.insert(coin_id.to_string(), value);
+            }
+        }
+    }
+
+    let mut series: Vec<(f64, Vec<f64>)> = coins
+        .iter()
+        .map(|(coin_id, value)| (value / portfolio_value_usd, returns_by_coin[coin_id.as_str()].clone()))
+        .collect();
+    let proxied_value_usd: f64 = proxied.values().sum();
+    if proxied_value_usd != 0.0 {
+        let proxy_returns = match returns_by_coin.get(VAR_PROXY_COIN) {
+            Some(returns) => returns.clone(),
+            None => match price_history::fetch_daily_prices(VAR_PROXY_COIN, VAR_HISTORY_DAYS, api_key).await {
+                Ok(prices) => var::returns_from_prices(&prices),
+                Err(e) => {
+                    tracing::warn!("⚠️ Failed to fetch {} price history for VaR: {}", VAR_PROXY_COIN, e);
+                    return None;
+                }
+            },
+        };
+        series.push((proxied_value_usd / portfolio_value_usd, proxy_returns));
+    }
+
+    let returns = var::weighted_returns(&series);
+    (!returns.is_empty()).then_some(VarReturns { returns, coins, proxied })
+}
+
+async fn get_portfolio_analytics(
+    State(state): State<AppState>,
+    Query(query): Query<AnalyticsQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let window = query.window.as_deref().unwrap_or(DEFAULT_PERFORMANCE_WINDOW);
+    let Some(window_duration) = portfolio_history::parse_window(window) else {
+        return Ok(Json(serde_json::json!({
+            "success": false,
+            "error": "Invalid window",
+            "message": format!("Window '{}' must be a number followed by h, d or w, e.g. 30d", window)
+        })).into_response());
+    };
+
+    // Without an address there is no history to measure
+    let mut data = serde_json::json!({
+        "status": "insufficient_data",
+        "total_return_usd": null,
+        "total_return_percentage": null,
+        "volatility": null,
+        "sharpe_ratio": null,
+        "max_drawdown": null
+    });
+    
+    if let Some(address_str) = query.address {
+        let address = match resolve_address(&address_str, &state.chain_clients).await {
+            Ok(addr) => addr,
+            Err(error_msg) => {
+                return Ok(Json(serde_json::json!({
+                    "success": false,
+                    "error": "Address resolution failed",
+                    "message": error_msg
+                })).into_response());
+            }
+        };
+
+        if let Err(e) = authorize_wallet(&state, &headers, address) {
+            return Ok(e.into_response());
+        }
+        
+        let risk_free_rate = risk_free_rate(query.risk_free_rate);
+        let curve = equity_curve(address, window_duration);
+        data = performance_from_history(&curve, risk_free_rate);
+        data["window"] = serde_json::json!(window);
+
+        let benchmark = query.benchmark.unwrap_or_default();
+        data["benchmark"] = serde_json::json!(benchmark);
+        data["benchmark_beta"] = serde_json::Value::Null;
+        data["alpha"] = serde_json::Value::Null;
+        if let Some(coin_id) = benchmark.coin_id() {
+            let days = (window_duration.as_secs() / 86_400 + 1) as u32;
+            match price_history::fetch_daily_price_points(coin_id, days, state.coingecko_api_key.as_deref()).await {
+                Ok(prices) => {
+                    if let Some(comparison) = BenchmarkComparison::from_curves(&curve, &prices, benchmark, risk_free_rate) {
+                        data["benchmark_beta"] = serde_json::json!(comparison.benchmark_beta.to_string());
+                        data["alpha"] = serde_json::json!(comparison.alpha.to_string());
+                        data["benchmark_return_percentage"] = serde_json::json!(comparison.benchmark_return_percentage.to_string());
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to fetch {} prices for benchmark comparison: {}", coin_id, e);
+                }
+            }
+        }
+        
+        let portfolio = fetch_portfolio(&state, address).await;
+        let portfolio_value_usd: f64 = portfolio.positions.iter().filter(|p| !p.is_price_unknown()).map(|p| p.value_usd).sum();
+        
+        data["value_at_risk"] = match portfolio_var_returns(
+            &portfolio.positions,
+            portfolio_value_usd,
+            state.coingecko_api_key.as_deref(),
+        ).await {
+            Some(var_returns) => {
+                let metrics = RiskMetrics::from_returns(portfolio_value_usd, &var_returns.returns, query.horizon_days.unwrap_or(1));
+                let method = match (var_returns.coins.is_empty(), var_returns.proxied.is_empty()) {
+                    (false, true) => "portfolio",
+                    (true, false) => "proxy",
+                    _ => "portfolio_with_proxy",
+                };
+
+                serde_json::json!({
+                    // Whether the returns are the portfolio's own or partly a stand-in
+                    "method": method,
+                    "coins": var_returns.coins,
+                    "proxy": (!var_returns.proxied.is_empty()).then(|| serde_json::json!({
+                        "coin": VAR_PROXY_COIN,
+                        "assets": var_returns.proxied,
+                    })),
+                    "horizon_days": metrics.horizon_days,
+                    "observations": metrics.observations,
+                    "portfolio_value_usd": metrics.portfolio_value_usd,
+                    "daily_volatility": metrics.daily_volatility,
+                    "parametric": {
+                        "var_95_usd": metrics.var_95_parametric_usd,
+                        "var_99_usd": metrics.var_99_parametric_usd
+                    },
+                    "historical": {
+                        "var_95_usd": metrics.var_95_historical_usd,
+                        "var_99_usd": metrics.var_99_historical_usd,
+                        "cvar_95_usd": metrics.cvar_95_usd,
+                        "cvar_99_usd": metrics.cvar_99_usd
+                    }
+                })
+            }
+            None => serde_json::Value::Null,
+        };
+    }
+    
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": data
+    })).into_response())
+}
+
+async fn get_correlation_matrix() -> Result<Json<serde_json::Value>, StatusCode> {
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {}
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchPortfolioRequest {
+    addresses: Vec<String>,
+}
+
+// Value, P&L and per-protocol totals of one wallet's priced positions
+struct WalletTotals {
+    value_usd: f64,
+    pnl_usd: f64,
+    pnl_breakdown: PnlBreakdown,
+    protocol_values: BTreeMap<String, f64>,
+}
+
+impl WalletTotals {
+    fn from_positions(positions: &[Position]) -> Self {
+        let priced = || positions.iter().filter(|p| !p.is_price_unknown());
+        let mut protocol_values = BTreeMap::new();
+        for position in priced() {
+            *protocol_values.entry(position.protocol.clone()).or_insert(0.0) += position.value_usd;
+        }
+        Self {
+            value_usd: priced().map(|p| p.value_usd).sum(),
+            pnl_usd: priced().map(|p| p.pnl_usd).sum(),
+            pnl_breakdown: priced().filter_map(Position::pnl_breakdown).sum(),
+            protocol_values,
+        }
+    }
+
+    fn add(&mut self, other: &WalletTotals) {
+        self.value_usd += other.value_usd;
+        self.pnl_usd += other.pnl_usd;
+        self.pnl_breakdown = self.pnl_breakdown + other.pnl_breakdown;
+        for (protocol, value) in &other.protocol_values {
+            *self.protocol_values.entry(protocol.clone()).or_insert(0.0) += value;
+        }
+    }
+
+    fn to_json(&self, precision: Precision) -> serde_json::Value {
+        serde_json::json!({
+            "total_value_usd": precision.usd(self.value_usd),
+            "total_pnl_usd": precision.usd(self.pnl_usd),
+            "pnl_breakdown": {
+                "price_pnl": precision.usd(self.pnl_breakdown.price_pnl),
+                "fees_earned": precision.usd(self.pnl_breakdown.fees_earned),
+                "rewards_earned": precision.usd(self.pnl_breakdown.rewards_earned),
+                "interest": precision.usd(self.pnl_breakdown.interest),
+                "impermanent_loss": precision.usd(self.pnl_breakdown.impermanent_loss),
+            },
+            "protocol_breakdown": self.protocol_values
+                .iter()
+                .map(|(protocol, value)| (protocol.clone(), precision.usd(*value)))
+                .collect::<BTreeMap<_, _>>(),
+        })
+    }
+}
+
+// Portfolios of several wallets, each broken down and combined into one summary.
+// Addresses that fail to resolve are reported without failing the rest.
+async fn get_batch_portfolio(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<BatchPortfolioRequest>,
+) -> Result<Response, ValidationError> {
+    let inputs: Vec<String> = InputValidator::validate_addresses(&request.addresses)?
+        .iter()
+        .map(ValidatedAddress::normalized)
+        .collect();
+
+    let resolutions = futures::future::join_all(
+        inputs.iter().map(|input| resolve_address(input, &state.chain_clients))
+    ).await;
+
+    // An ENS name and the address it resolves to are one wallet
+    let mut wallets: Vec<(String, Address)> = Vec::new();
+    let mut unresolved = Vec::new();
+    for (input, resolution) in inputs.into_iter().zip(resolutions) {
+        match resolution {
+            Ok(address) if wallets.iter().any(|(_, seen)| *seen == address) => {}
+            Ok(address) => wallets.push((input, address)),
+            Err(message) => unresolved.push(serde_json::json!({ "input": input, "message": message })),
+        }
+    }
+
+    // Every wallet needs its own token; one the caller can't read fails the whole batch
+    for (_, address) in &wallets {
+        if let Err(e) = authorize_wallet(&state, &headers, *address) {
+            return Ok(e.into_response());
+        }
+    }
+
+    let portfolios = futures::future::join_all(
+        wallets.iter().map(|(_, address)| fetch_portfolio(&state, *address))
+    ).await;
+
+    let precision = Precision::global();
+    let mut combined = WalletTotals::from_positions(&[]);
+    let mut total_positions = 0;
+    let mut aggregates = Vec::with_capacity(portfolios.len());
+    let mut breakdowns = Vec::with_capacity(portfolios.len());
+
+    for ((input, address), portfolio) in wallets.iter().zip(portfolios) {
+        let totals = WalletTotals::from_positions(&portfolio.positions);
+        combined.add(&totals);
+        total_positions += portfolio.positions.len();
+        aggregates.push(position_aggregator::aggregate(portfolio.positions.clone()));
+
+        let mut breakdown = totals.to_json(precision);
+        breakdown["input"] = serde_json::json!(input);
+        breakdown["address"] = serde_json::json!(format!("{:?}", address));
+        breakdown["total_positions"] = serde_json::json!(portfolio.positions.len());
+        breakdown["positions"] = serde_json::json!(portfolio.positions);
+        breakdown["errors"] = serde_json::json!(if portfolio.errors.is_empty() { None } else { Some(portfolio.errors) });
+        breakdowns.push(breakdown);
+    }
+
+    let exposure = position_aggregator::combine(&aggregates);
+    let mut aggregate = combined.to_json(precision);
+    aggregate["addresses"] = serde_json::json!(wallets.len());
+    aggregate["total_positions"] = serde_json::json!(total_positions);
+    aggregate["net_exposure_by_token"] = serde_json::json!(exposure.net_exposure_by_token
+        .iter()
+        .map(|(token, value)| (token.clone(), precision.usd(*value)))
+        .collect::<BTreeMap<_, _>>());
+    aggregate["total_supplied_usd"] = serde_json::json!(precision.usd(exposure.total_supplied_usd));
+    aggregate["total_borrowed_usd"] = serde_json::json!(precision.usd(exposure.total_borrowed_usd));
+    aggregate["leverage_ratio"] = serde_json::json!(exposure.leverage_ratio);
+    aggregate["portfolio_net_apy"] = serde_json::json!(exposure.portfolio_net_apy);
+
+    Ok(Json(serde_json::json!({
+        "success": !wallets.is_empty(),
+        "data": {
+            "aggregate": aggregate,
+            "wallets": breakdowns,
+            "unresolved": unresolved
+        }
+    })).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+struct RiskCompareRequest {
+    address: String,
+}
+
+// Wallet's yielding positions ranked by APY per unit of risk, worst ones flagged
+async fn compare_position_risk(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<RiskCompareRequest>,
+) -> Result<Response, ValidationError> {
+    let address_str = InputValidator::validate_address(&request.address)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            })).into_response());
+        }
+    };
+
+    if let Err(e) = authorize_wallet(&state, &headers, address) {
+        return Ok(e.into_response());
+    }
+
+    let portfolio = fetch_portfolio(&state, address).await;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": risk::compare_risk_adjusted(&portfolio.positions),
+        "errors": if portfolio.errors.is_empty() { None } else { Some(portfolio.errors) }
+    })).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+struct WhatIfRequest {
+    address: String,
+    position: risk::HypotheticalPosition,
+    /// Overrides the wallet's saved risk profile
+    profile: Option<RiskProfile>,
+}
+
+// Wallet's portfolio risk before and after a hypothetical supply or borrow; nothing is persisted
+async fn simulate_what_if(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<WhatIfRequest>,
+) -> Result<Response, ValidationError> {
+    let hypothetical = request.position;
+    InputValidator::validate_position(
+        &hypothetical.protocol,
+        hypothetical.side.position_type(),
+        &hypothetical.asset,
+        hypothetical.size_usd,
+    )?;
+    if let Some(threshold) = hypothetical.liquidation_threshold {
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            return Err(ValidationError::InvalidPosition(
+                "liquidation_threshold must be greater than 0 and at most 1".to_string(),
+            ));
         }
     }
-    
-    // MorphoBlue Adapter (Lending Protocol)
-    let morphoblue_client = MorphoBlueEthereumClient { rpc_url: rpc_url.to_string() };
-    match MorphoBlueAdapter::new(morphoblue_client, 1) { // Expects u64 for chain_id
-        Ok(adapter) => {
-            adapters.push(Box::new(adapter));
-            tracing::info!("✅ Initialized MorphoBlue adapter");
-        }
-        Err(e) => {
-            tracing::warn!("❌ Failed to initialize MorphoBlue adapter: {}", e);
+
+    let address_str = InputValidator::validate_address(&request.address)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            })).into_response());
         }
+    };
+
+    if let Err(e) = authorize_wallet(&state, &headers, address) {
+        return Ok(e.into_response());
     }
-    
-    tracing::info!("🚀 Successfully initialized {} DeFi protocol adapters", adapters.len());
-    tracing::info!("📊 Supported protocols: {}", 
-        adapters.iter().map(|a| a.protocol_name()).collect::<Vec<_>>().join(", "));
-    
-    adapters
-}
 
-// Helper function to resolve ENS names to addresses
-async fn resolve_address(input: &str, _rpc_url: &str) -> Result<Address, String> {
-    // First try to parse as a direct address
-    if let Ok(addr) = Address::from_str(input) {
-        return Ok(addr);
+    let portfolio = fetch_portfolio(&state, address).await;
+    let mut settings = RiskProfileStore::global().settings(address).unwrap_or_default();
+    if let Some(profile) = request.profile {
+        settings.risk_profile = profile;
     }
-    
-    // If it looks like an ENS name, provide common known addresses for testing
-    if input.ends_with(".eth") || input.ends_with(".ens") {
-        tracing::info!("🔍 Resolving ENS name: {}", input);
-        
-        // For now, provide some known ENS mappings for testing
-        // In production, you'd want to implement proper ENS resolution
-        let known_ens = match input.to_lowercase().as_str() {
-            "vitalik.eth" => "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045",
-            "hayden.eth" => "0x50EC05ADe8280758E2077fcBC08D878D4aef79C3", 
-            "uniswap.eth" => "0x1a9C8182C09F50C8318d769245beA52c32BE35BC",
-            "aave.eth" => "0x25F2226B597E8F9514B3F68F00f494cF4f286491",
-            "compound.eth" => "0x3d9819210A31b4961b30EF54bE2aeD79B9c9Cd3B",
-            _ => {
-                tracing::warn!("❌ ENS name {} not in known mappings", input);
-                return Err(format!("ENS name '{}' not found in known mappings. For testing, try: vitalik.eth, hayden.eth, uniswap.eth", input));
-            }
-        };
-        
-        match Address::from_str(known_ens) {
-            Ok(addr) => {
-                tracing::info!("✅ Resolved {} to {}", input, addr);
-                return Ok(addr);
-            }
-            Err(e) => {
-                return Err(format!("Invalid resolved address: {}", e));
-            }
+
+    let analysis = risk::what_if(
+        address,
+        &portfolio.positions,
+        &hypothetical,
+        &settings.health_thresholds(),
+        &ConcentrationLimits::from_env(),
+    );
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "address": format!("{:?}", address),
+            "persisted": false,
+            "risk_profile": settings.risk_profile.as_str(),
+            "existing_positions": portfolio.positions.len(),
+            "analysis": analysis
+        },
+        "errors": if portfolio.errors.is_empty() { None } else { Some(portfolio.errors) }
+    })).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+struct RiskDecompositionQuery {
+    address: String,
+}
+
+async fn get_risk_decomposition(
+    State(state): State<AppState>,
+    Query(query): Query<RiskDecompositionQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ValidationError> {
+    let address_str = InputValidator::validate_address(&query.address)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            })).into_response());
         }
+    };
+
+    if let Err(e) = authorize_wallet(&state, &headers, address) {
+        return Ok(e.into_response());
     }
-    
-    Err(format!("Invalid address format: '{}'. Please provide a valid Ethereum address or ENS name (vitalik.eth, hayden.eth, etc.)", input))
+
+    let portfolio = fetch_portfolio(&state, address).await;
+    let decomposition = RiskDecomposition::from_positions(&portfolio.positions);
+    let market_context = ProtocolTvlService::global()
+        .market_context(&portfolio.positions, MarketShareLimit::from_env())
+        .await;
+
+    let mut data = serde_json::to_value(&decomposition).unwrap_or_default();
+    data["market_context"] = serde_json::json!(market_context);
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": data,
+        "errors": if portfolio.errors.is_empty() { None } else { Some(portfolio.errors) }
+    })).into_response())
 }
 
-// API endpoint handlers - Real position fetching from all adapters
-async fn get_portfolio_positions(Path(address_str): Path<String>) -> Result<Json<serde_json::Value>, StatusCode> {
-    tracing::info!("🔍 Fetching portfolio positions for address: {}", address_str);
-    
-    // Get configuration from environment first (needed for ENS resolution)
-    let rpc_url = std::env::var("ETHEREUM_RPC_URL")
-        .unwrap_or_else(|_| "https://eth-mainnet.alchemyapi.io/v2/demo".to_string());
-    
-    // Resolve the address (handles both direct addresses and ENS names)
-    let address = match resolve_address(&address_str, &rpc_url).await {
+#[derive(Debug, Deserialize)]
+struct HistoryQuery {
+    window: Option<String>,
+}
+
+// Recorded value time series for a monitored wallet
+async fn get_portfolio_history(
+    State(state): State<AppState>,
+    Path(address_input): Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    let address_str = InputValidator::validate_address(&address_input)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
         Ok(addr) => addr,
         Err(error_msg) => {
-            tracing::warn!("❌ Address resolution failed: {}", error_msg);
             return Ok(Json(serde_json::json!({
                 "success": false,
                 "error": "Address resolution failed",
@@ -191,184 +2766,314 @@ async fn get_portfolio_positions(Path(address_str): Path<String>) -> Result<Json
         }
     };
 
-    // Get coingecko API key (RPC URL already obtained for ENS resolution)
-    let coingecko_api_key = std::env::var("COINGECKO_API_KEY").ok();
+    let window = query.window.as_deref().unwrap_or("7d");
+    let Some(window_duration) = portfolio_history::parse_window(window) else {
+        return Ok(Json(serde_json::json!({
+            "success": false,
+            "error": "Invalid window",
+            "message": format!("Window '{}' must be a number followed by h, d or w, e.g. 7d", window)
+        })));
+    };
 
-    // Initialize all adapters
-    let adapters = initialize_adapters(&rpc_url, coingecko_api_key).await;
-    let mut all_positions = Vec::new();
-    let mut errors = Vec::new();
-    let mut protocol_stats = std::collections::HashMap::new();
+    let since = (chrono::Utc::now().timestamp() as u64).saturating_sub(window_duration.as_secs());
+    let snapshots = PortfolioHistoryStore::global().history(address, since);
 
-    tracing::info!("📡 Querying {} protocol adapters for positions", adapters.len());
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "address": address_str,
+            "window": window,
+            "tracked": monitored_wallets().contains(&address),
+            "snapshots": snapshots
+        }
+    })))
+}
 
-    // Store adapter count before consuming the vector
-    let total_adapters = adapters.len();
-    
-    // Fetch positions from all adapters
-    for adapter in adapters {
-        let protocol_name = adapter.protocol_name();
-        tracing::debug!("🔄 Querying {} for positions...", protocol_name);
-        
-        match adapter.fetch_positions(address).await {
-            Ok(mut positions) => {
-                let count = positions.len();
-                if count > 0 {
-                    tracing::info!("✅ Found {} positions in {}", count, protocol_name);
-                    protocol_stats.insert(protocol_name.to_string(), count);
-                    all_positions.append(&mut positions);
-                } else {
-                    tracing::debug!("ℹ️ No positions found in {}", protocol_name);
-                }
-            }
-            Err(e) => {
-                tracing::warn!("⚠️ Failed to fetch positions from {}: {}", protocol_name, e);
-                errors.push(format!("{}: {}", protocol_name, e));
-            }
+#[derive(Debug, Deserialize)]
+struct TransactionsQuery {
+    /// Most recent events to return across protocols, 50 by default
+    limit: Option<usize>,
+}
+
+const DEFAULT_TRANSACTION_LIMIT: usize = 50;
+const MAX_TRANSACTION_LIMIT: usize = 1000;
+
+// Lending events (supply, withdraw, borrow, repay, liquidation) from the subgraph of
+// each protocol that has one configured, oldest first
+async fn get_transaction_history(
+    State(state): State<AppState>,
+    Path(address_input): Path<String>,
+    Query(query): Query<TransactionsQuery>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    let address_str = InputValidator::validate_address(&address_input)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            })));
         }
-    }
+    };
 
-    // Calculate portfolio summary before converting positions
-    let total_value_usd: f64 = all_positions.iter().map(|p| p.value_usd).sum();
-    let total_pnl_usd: f64 = all_positions.iter().map(|p| p.pnl_usd).sum();
-    let total_positions = all_positions.len();
+    let limit = query.limit.unwrap_or(DEFAULT_TRANSACTION_LIMIT).min(MAX_TRANSACTION_LIMIT);
+    let adapters: Vec<&dyn DeFiAdapter> = state.adapters
+        .iter()
+        .filter(|adapter| adapter.subgraph_id().is_some())
+        .map(|adapter| adapter.as_ref())
+        .collect();
+    let results = futures::future::join_all(
+        adapters.iter().map(|adapter| adapter.get_transaction_history(address, limit))
+    ).await;
 
-    // Convert positions to frontend format
-    let frontend_positions: Vec<serde_json::Value> = all_positions
+    let mut transactions = Vec::new();
+    let mut errors = Vec::new();
+    for (adapter, result) in adapters.iter().zip(results) {
+        match result {
+            Ok(events) => transactions.extend(events.into_iter().map(|event| (adapter.protocol_name(), event))),
+            Err(e) => errors.push(ProtocolError::new(adapter.protocol_name(), &e)),
+        }
+    }
+    // Each protocol's events are its most recent; keep the most recent overall
+    transactions.sort_by_key(|(_, event)| event.timestamp);
+    let transactions: Vec<serde_json::Value> = transactions
+        .split_off(transactions.len().saturating_sub(limit))
         .into_iter()
-        .map(|pos| {
-            serde_json::json!({
-                "id": pos.id,
-                "user_id": address_str,
-                "protocol": pos.protocol,
-                "pool_address": "", // Will be in metadata
-                "chain_id": 1, // Default to mainnet
-                "token0_address": "", // Will be in metadata
-                "token1_address": "", // Will be in metadata
-                "position_type": pos.position_type,
-                "value_usd": pos.value_usd.to_string(),
-                "liquidity": "0", // Will be calculated
-                "tick_lower": 0, // Will be in metadata
-                "tick_upper": 0, // Will be in metadata
-                "pnl_usd": pos.pnl_usd.to_string(),
-                "fees_earned_usd": "0.0", // Not tracked in current model
-                "impermanent_loss_usd": "0.0", // Not tracked in current model
-                "risk_score": 0.5, // Default risk score - will be calculated later
-                "is_active": true,
-                "created_at": chrono::DateTime::from_timestamp(pos.last_updated as i64, 0)
-                    .unwrap_or_default()
-                    .to_rfc3339(),
-                "updated_at": chrono::DateTime::from_timestamp(pos.last_updated as i64, 0)
-                    .unwrap_or_default()
-                    .to_rfc3339(),
-                "pair": pos.pair,
-                "metadata": pos.metadata
-            })
+        .map(|(protocol, event)| {
+            let mut entry = serde_json::json!(event);
+            entry["protocol"] = serde_json::json!(protocol);
+            entry
         })
         .collect();
-    
-    tracing::info!("📊 Portfolio Summary: {} positions, ${:.2} total value, ${:.2} PnL", 
-        total_positions, total_value_usd, total_pnl_usd);
 
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
-            "positions": frontend_positions,
-            "summary": {
-                "total_positions": total_positions,
-                "total_value_usd": total_value_usd,
-                "total_pnl_usd": total_pnl_usd,
-                "protocol_breakdown": protocol_stats,
-                "last_updated": chrono::Utc::now().to_rfc3339()
-            }
-        },
-        "errors": if errors.is_empty() { None } else { Some(errors) },
-        "meta": {
             "address": address_str,
-            "protocols_queried": total_adapters,
-            "protocols_with_positions": protocol_stats.len()
-        }
+            "protocols": adapters.iter().map(|adapter| adapter.protocol_name()).collect::<Vec<_>>(),
+            "transactions": transactions
+        },
+        "errors": if errors.is_empty() { None } else { Some(errors) }
     })))
 }
 
-async fn get_portfolio_summary() -> Result<Json<serde_json::Value>, StatusCode> {
-    Ok(Json(serde_json::json!({
-        "success": true,
-        "data": {
-            "total_value_usd": 10000.0,
-            "total_pnl_usd": 500.0,
-            "pnl_percentage": 5.0,
-            "risk_score": 0.65,
-            "positions": []
-        }
-    })))
+#[derive(Debug, Deserialize)]
+struct DiffQuery {
+    /// Unix seconds, or "last" for the most recent snapshot (the default)
+    since: Option<String>,
+    /// Percent change in a position's size reported as a change
+    threshold_pct: Option<f64>,
 }
 
-async fn get_portfolio_risk_metrics() -> Result<Json<serde_json::Value>, StatusCode> {
+// Current portfolio compared with the snapshot taken at or before `since`
+async fn get_portfolio_diff(
+    State(state): State<AppState>,
+    Path(address_input): Path<String>,
+    Query(query): Query<DiffQuery>,
+) -> Result<Json<serde_json::Value>, ValidationError> {
+    let address_str = InputValidator::validate_address(&address_input)?.normalized();
+
+    let address = match resolve_address(&address_str, &state.chain_clients).await {
+        Ok(addr) => addr,
+        Err(error_msg) => {
+            return Ok(Json(serde_json::json!({
+                "success": false,
+                "error": "Address resolution failed",
+                "message": error_msg
+            })));
+        }
+    };
+
+    let now = chrono::Utc::now().timestamp() as u64;
+    let since = match query.since.as_deref().unwrap_or("last") {
+        "last" => now,
+        timestamp => match timestamp.parse::<u64>() {
+            Ok(timestamp) => timestamp,
+            Err(_) => {
+                return Ok(Json(serde_json::json!({
+                    "success": false,
+                    "error": "Invalid since",
+                    "message": format!("since '{}' must be a unix timestamp or \"last\"", timestamp)
+                })));
+            }
+        },
+    };
+
+    let Some(previous) = PortfolioHistoryStore::global().at(address, since) else {
+        return Ok(Json(serde_json::json!({
+            "success": false,
+            "error": "No snapshot",
+            "message": format!("No snapshot of {} was recorded at or before {}", address_str, since)
+        })));
+    };
+
+    let portfolio = fetch_portfolio(&state, address).await;
+    let current = PortfolioSnapshot::from_positions(&portfolio.positions, now);
+    let mut thresholds = DiffThresholds::default();
+    if let Some(threshold_pct) = query.threshold_pct.filter(|pct| pct.is_finite() && *pct >= 0.0) {
+        thresholds.value_change_pct = threshold_pct;
+    }
+    let diff = PortfolioDiff::between(&previous, &current, &thresholds);
+
     Ok(Json(serde_json::json!({
         "success": true,
         "data": {
-            "overall_risk": 0.65,
-            "liquidity_risk": 0.4,
-            "volatility_risk": 0.7,
-            "mev_risk": 0.3,
-            "protocol_risk": 0.2,
-            "timestamp": "2024-01-01T12:00:00Z"
-        }
+            "address": address_str,
+            // Snapshots recorded before positions were kept can only be compared by total
+            "positions_recorded": !previous.positions.is_empty() || previous.position_count == 0,
+            "diff": diff,
+        },
+        "errors": if portfolio.errors.is_empty() { None } else { Some(portfolio.errors) }
     })))
 }
 
-async fn get_live_risk_alerts() -> Result<Json<serde_json::Value>, StatusCode> {
+async fn get_stress_test_results() -> Result<Json<serde_json::Value>, StatusCode> {
     Ok(Json(serde_json::json!({
         "success": true,
         "data": []
     })))
 }
 
-async fn get_position_risk_heatmap() -> Result<Json<serde_json::Value>, StatusCode> {
-    Ok(Json(serde_json::json!({
-        "success": true,
-        "data": []
-    })))
+const LIQUIDATION_POLL_INTERVAL: Duration = Duration::from_secs(60);
+const DEFAULT_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(3600);
+
+// Wallets tracked in the background, from MONITORED_WALLETS (comma-separated)
+fn monitored_wallets() -> Vec<Address> {
+    std::env::var("MONITORED_WALLETS")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|wallet| Address::from_str(wallet.trim()).ok())
+        .collect()
 }
 
-async fn get_portfolio_analytics() -> Result<Json<serde_json::Value>, StatusCode> {
-    Ok(Json(serde_json::json!({
-        "success": true,
-        "data": {
-            "total_return_usd": "500.0",
-            "total_return_percentage": "5.0",
-            "volatility": "0.25",
-            "sharpe_ratio": "1.2",
-            "max_drawdown": "0.15"
+// Wallets the background monitor polls: MONITORED_WALLETS plus every wallet with a
+// notification channel or portfolio webhook configured
+fn background_wallets() -> Vec<Address> {
+    let mut wallets = monitored_wallets();
+    let configured = AlertService::global().settings().wallets();
+    for wallet in configured.into_iter().chain(PortfolioWebhooks::global().wallets()) {
+        if !wallets.contains(&wallet) {
+            wallets.push(wallet);
         }
-    })))
+    }
+    wallets
 }
 
-async fn get_correlation_matrix() -> Result<Json<serde_json::Value>, StatusCode> {
-    Ok(Json(serde_json::json!({
-        "success": true,
-        "data": {}
-    })))
+/// Poll wallets listed in `MONITORED_WALLETS` (comma-separated), and those with alerts
+/// or webhooks configured, and alert when a lending position's health factor crosses a threshold
+fn spawn_liquidation_monitor(state: AppState) {
+    info!("🛡️ Monitoring health factors for {} wallet(s)", background_wallets().len());
+    let monitor = LiquidationMonitor::from_env();
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(LIQUIDATION_POLL_INTERVAL);
+        loop {
+            ticker.tick().await;
+            // Re-read each poll so wallets that configure notifications are picked up
+            for wallet in &background_wallets() {
+                let portfolio = fetch_portfolio(&state, *wallet).await;
+                let (alerts, value_drops) = evaluate_wallet_alerts(&monitor, *wallet, &portfolio);
+                monitor.dispatch(&alerts).await;
+                let notifications: Vec<LiveAlert> = alerts.iter().map(LiveAlert::from).chain(value_drops).collect();
+                AlertService::global().notify(*wallet, &notifications).await;
+                // A partial fetch would look like closed positions and a value drop
+                if portfolio.errors.is_empty() {
+                    PortfolioWebhooks::global().observe(*wallet, &portfolio.positions).await;
+                }
+                // Live subscribers of a monitored wallet are fed from here; drop their feed once they leave
+                AlertFeed::global().release_if_unsubscribed(*wallet);
+            }
+        }
+    });
 }
 
-async fn get_risk_decomposition() -> Result<Json<serde_json::Value>, StatusCode> {
-    Ok(Json(serde_json::json!({
-        "success": true,
-        "data": {
-            "systematic_risk": 0.4,
-            "idiosyncratic_risk": 0.3,
-            "concentration_risk": 0.2,
-            "liquidity_risk": 0.1
+// Health factor alerts for a fresh read of `wallet`, banded with the thresholds it set
+// or its risk profile's, and value drops over the last day that newly crossed its
+// threshold; the read is also pushed to the wallet's live alert feed
+fn evaluate_wallet_alerts(monitor: &LiquidationMonitor, wallet: Address, portfolio: &PortfolioFetch) -> (Vec<LiquidationAlert>, Vec<LiveAlert>) {
+    let settings = RiskProfileStore::global().settings(wallet);
+    let profile = settings.map(|settings| settings.risk_profile);
+    let thresholds = settings.map_or(monitor.thresholds(), |settings| settings.health_thresholds());
+    let feed_config = AlertFeed::global().config();
+    let config = settings.map_or(feed_config, |settings| settings.alert_config(feed_config));
+    let alerts = monitor.evaluate_with_thresholds(wallet, &portfolio.positions, thresholds);
+
+    let risk_score = RiskAggregation::ValueWeighted.aggregate(&portfolio.positions);
+    // A partial fetch would show up as a fake portfolio drop
+    let value_drops = ValueDropTracker::global().observe(
+        wallet,
+        &portfolio.positions,
+        !portfolio.errors.is_empty(),
+        config.value_drop_percent,
+        chrono::Utc::now().timestamp() as u64,
+    );
+    AlertFeed::global().observe(wallet, WalletReading {
+        positions: &portfolio.positions,
+        liquidation_alerts: &alerts,
+        thresholds,
+        config,
+        value_drops: &value_drops,
+        risk_score: profile.map_or(risk_score, |profile| profile.scale_score(risk_score)),
+    });
+
+    let new_drops = value_drops.iter().filter(|drop| drop.new).map(|drop| LiveAlert::value_drop(wallet, drop)).collect();
+    (alerts, new_drops)
+}
+
+// Poll a wallet with live alert subscribers that the background monitor doesn't cover,
+// until its last subscriber disconnects
+fn spawn_wallet_alert_monitor(state: AppState, wallet: Address) {
+    info!("🛡️ Watching {:?} for live alert subscribers", wallet);
+
+    tokio::spawn(async move {
+        let monitor = LiquidationMonitor::from_env();
+        let mut ticker = tokio::time::interval(LIQUIDATION_POLL_INTERVAL);
+        loop {
+            ticker.tick().await;
+            if !AlertFeed::global().release_if_unsubscribed(wallet) {
+                break;
+            }
+            let portfolio = fetch_portfolio(&state, wallet).await;
+            evaluate_wallet_alerts(&monitor, wallet, &portfolio);
         }
-    })))
+        info!("🛡️ Stopped watching {:?}: no live alert subscribers left", wallet);
+    });
 }
 
-async fn get_stress_test_results() -> Result<Json<serde_json::Value>, StatusCode> {
-    Ok(Json(serde_json::json!({
-        "success": true,
-        "data": []
-    })))
+// Periodically record each monitored wallet's value for the history endpoint
+fn spawn_portfolio_snapshots(state: AppState) {
+    let wallets = monitored_wallets();
+
+    if wallets.is_empty() {
+        return;
+    }
+
+    let interval = std::env::var("PORTFOLIO_SNAPSHOT_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_SNAPSHOT_INTERVAL);
+    info!("📈 Snapshotting {} wallet(s) every {}s", wallets.len(), interval.as_secs());
+    let history = PortfolioHistoryStore::global();
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            for wallet in &wallets {
+                let portfolio = fetch_portfolio(&state, *wallet).await;
+                // A partial fetch would show up as a fake drawdown in the equity curve
+                if !portfolio.errors.is_empty() {
+                    tracing::warn!("⚠️ Skipping snapshot for {:?}: {} adapter(s) failed", wallet, portfolio.errors.len());
+                    continue;
+                }
+                let now = chrono::Utc::now().timestamp() as u64;
+                history.record(*wallet, PortfolioSnapshot::from_positions(&portfolio.positions, now));
+            }
+        }
+    });
 }
 
 #[tokio::main]
@@ -387,36 +3092,113 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let coingecko_api_key = std::env::var("COINGECKO_API_KEY").ok();
     
     info!("🔗 Using RPC URL: {}", rpc_url);
+    info!("🚦 Max concurrent RPC calls: {}", rpc_concurrency_limit());
     info!("🪙 CoinGecko API: {}", if coingecko_api_key.is_some() { "Configured" } else { "Using free tier" });
     
-    // Test adapter initialization
-    let test_adapters = initialize_adapters(&rpc_url, coingecko_api_key.clone()).await;
-    info!("✅ Successfully initialized {} DeFi protocol adapters", test_adapters.len());
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+
+    // Adapters are built once and shared by all requests
+    let adapters = initialize_adapters(&rpc_url, coingecko_api_key.clone(), None, &ProtocolFilter::all()).await;
+    info!("✅ Successfully initialized {} DeFi protocol adapters", adapters.len());
+    
+    // Historical-block reads are refused up front on nodes without archive state
+    let data_source = match EthereumClient::with_fallbacks(configured_rpc_urls(&rpc_url)) {
+        Ok(client) => DataSourceCapabilities::detect(&client).await,
+        Err(e) => {
+            tracing::warn!("❌ Failed to create Ethereum client for archive probe: {}", e);
+            DataSourceCapabilities { archive_node: false, source: CapabilitySource::ProbeFailed }
+        }
+    };
+    info!("🗄️ RPC archive access: {} ({:?})", if data_source.archive_node { "available" } else { "latest block only" }, data_source.source);
     
-    // Create AppState for future use
-    let _app_state = defi_risk_monitor::AppState {
+    // Shared state for handlers
+    let app_state = AppState {
         rpc_url: rpc_url.clone(),
         coingecko_api_key: coingecko_api_key.clone(),
+        metrics: Arc::new(AdapterMetrics::new()),
+        jwt_service: Arc::new(JwtService::from_env()),
+        adapters: Arc::new(adapters),
+        chain_clients: Arc::new(chain_clients(&rpc_url)),
+        data_source,
+        shutdown: shutdown_rx.clone(),
     };
 
-    // Create lean web server with only working routes (no state)
-    let app = Router::new()
+    spawn_liquidation_monitor(app_state.clone());
+    spawn_portfolio_snapshots(app_state.clone());
+    ProtocolRiskService::global().spawn_refresh();
+
+    // Wallet-scoped routes; REQUIRE_PORTFOLIO_AUTH puts them behind a wallet token too.
+    // Handlers taking the wallet from the query or body check it with `authorize_wallet`.
+    let wallet_auth = middleware::from_fn_with_state(app_state.clone(), require_wallet_token);
+    let mut wallet_routes = Router::new()
+        .route("/api/v1/positions/wallet/:address", get(get_portfolio_positions))
+        .route("/api/v1/positions/wallet/:address/export", get(export_portfolio_positions))
+        .route("/api/v1/positions/wallet/:address/diff", get(get_portfolio_diff))
+        .route("/api/v1/portfolio/:address/history", get(get_portfolio_history))
+        .route("/api/v1/portfolio/:address/transactions", get(get_transaction_history))
+        .route("/api/v1/portfolio/:address/report", get(get_portfolio_report))
+        .route("/api/v1/security/:address/approvals", get(get_token_approvals))
+        .route("/ws/portfolio/:address/value-stream", get(portfolio_value_stream))
+        .route("/ws/alerts/:address/live", get(live_alerts_stream));
+    if portfolio_auth_required() {
+        info!("🔒 Portfolio endpoints require a wallet token");
+        wallet_routes = wallet_routes.route_layer(wallet_auth.clone());
+    }
+    let private_routes = Router::new()
+        .route("/api/v1/positions/wallet/:address/private", get(get_portfolio_positions))
+        .route("/api/v1/users/:address/risk-profile", get(get_user_risk_profile).put(update_user_risk_profile))
+        .route("/api/v1/users/:address/notifications", get(get_notification_settings).put(update_notification_settings))
+        .route("/api/v1/users/:address/notifications/deliveries", get(get_notification_deliveries))
+        .route("/api/v1/users/:address/webhooks", get(get_portfolio_webhook).put(update_portfolio_webhook).delete(delete_portfolio_webhook))
+        .route_layer(wallet_auth);
+
+    // Create lean web server with only working routes
+    let mut app = Router::new()
+        .merge(wallet_routes)
+        .merge(private_routes)
         // Health check
         .route("/health", get(health::health_check))
+        .route("/health/adapters", get(get_adapter_health))
+        .route("/api/v1/protocols", get(get_supported_protocols))
+        .route("/api/v1/schema", get(get_response_schema))
+        // Prometheus metrics
+        .route("/metrics", get(metrics::metrics_handler))
         // Portfolio API endpoints (matching frontend expectations)
-        .route("/api/v1/positions/wallet/:address", get(get_portfolio_positions))
+        .route("/api/v1/positions/validate", post(validate_position))
+        .route("/api/v1/positions/:id", get(get_position_detail))
+        // Wallet authentication: EIP-712 signature in, short-lived bearer token out
+        .route("/api/v1/auth/token", post(issue_wallet_token))
         .route("/api/v1/portfolio/summary", get(get_portfolio_summary))
+        .route("/api/v1/portfolio/batch", post(get_batch_portfolio))
         // Risk Monitor API endpoints
         .route("/api/v1/portfolio-risk-metrics", get(get_portfolio_risk_metrics))
+        .route("/api/v1/risk/compare", post(compare_position_risk))
+        .route("/api/v1/risk/whatif", post(simulate_what_if))
         .route("/api/v1/live-alerts", get(get_live_risk_alerts))
         .route("/api/v1/position-risk-heatmap", get(get_position_risk_heatmap))
         // Advanced Analytics API endpoints
         .route("/api/v1/analytics/portfolio-performance", get(get_portfolio_analytics))
         .route("/api/v1/analytics/correlation-matrix", get(get_correlation_matrix))
         .route("/api/v1/analytics/risk-decomposition", get(get_risk_decomposition))
-        .route("/api/v1/analytics/stress-test", get(get_stress_test_results))
-        // CORS for frontend
-        .layer(CorsLayer::permissive());
+        .route("/api/v1/analytics/stress-test", get(get_stress_test_results));
+
+    // Per-IP rate limit; inside CORS so 429s still carry CORS headers
+    if let Some(limiter) = RateLimiter::from_env() {
+        info!("🚦 Rate limiting to {} requests/min per IP", limiter.requests_per_minute());
+        app = app.layer(middleware::from_fn_with_state(limiter, rate_limit));
+    }
+
+    // CORS for frontend, locked to ALLOWED_ORIGINS when set
+    let allowed_origins = allowed_origins_from_env();
+    match &allowed_origins {
+        Some(origins) => info!("🔒 CORS restricted to {} origin(s)", origins.len()),
+        None => tracing::warn!("⚠️ ALLOWED_ORIGINS not set; CORS is permissive (dev mode only)"),
+    }
+    let app = app
+        .layer(cors_layer(allowed_origins))
+        // Outermost, so the request span covers every other layer and rejections carry an id
+        .layer(middleware::from_fn(request_id::request_id))
+        .with_state(app_state);
 
     // Start server
     let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
@@ -424,9 +3206,146 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("📊 Ready to track positions across all DeFi protocols!");
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    // Use axum 0.7 compatible serving - simple approach
-    axum::serve(listener, app.into_make_service()).await?;
+    // Connect info gives the rate limiter each client's IP
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            info!("🛑 Shutting down: no new connections, draining in-flight requests");
+            let _ = shutdown_tx.send(true);
+        })
+        .into_future();
 
+    // In-flight requests get a grace period after the signal; past it, exit anyway
+    let grace = shutdown_grace_period();
+    let mut drain_rx = shutdown_rx;
+    tokio::select! {
+        result = server => result?,
+        _ = async {
+            shutdown_requested(&mut drain_rx).await;
+            tokio::time::sleep(grace).await;
+        } => {
+            tracing::warn!("⚠️ Requests still in flight after {:?}; exiting", grace);
+        }
+    }
+
+    info!("👋 DeFi Risk Monitor stopped");
     Ok(())
 }
 
+const DEFAULT_SHUTDOWN_GRACE_SECS: u64 = 30;
+
+/// How long shutdown waits for in-flight requests, from `SHUTDOWN_GRACE_SECS`
+fn shutdown_grace_period() -> Duration {
+    let secs = std::env::var("SHUTDOWN_GRACE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_SHUTDOWN_GRACE_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Resolves on Ctrl-C, or on SIGTERM from the deploy environment
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("❌ Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("❌ Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Resolves once `AppState::shutdown` flips, or its sender is gone
+async fn shutdown_requested(shutdown: &mut tokio::sync::watch::Receiver<bool>) {
+    let _ = shutdown.wait_for(|shutting_down| *shutting_down).await;
+}
+
+/// Close frame telling a WebSocket client the server is going away and it should reconnect
+fn shutdown_close_frame() -> Message {
+    Message::Close(Some(CloseFrame {
+        code: close_code::AWAY,
+        reason: "Server shutting down".into(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_state() -> AppState {
+        AppState {
+            rpc_url: String::new(),
+            coingecko_api_key: None,
+            metrics: Arc::new(AdapterMetrics::new()),
+            jwt_service: Arc::new(JwtService::new(b"test-secret", 60)),
+            adapters: Arc::new(Vec::new()),
+            chain_clients: Arc::new(HashMap::new()),
+            data_source: DataSourceCapabilities { archive_node: false, source: CapabilitySource::ProbeFailed },
+            shutdown: tokio::sync::watch::channel(false).1,
+        }
+    }
+
+    #[test]
+    fn test_fees_and_impermanent_loss_are_both_converted() {
+        let position = Position {
+            id: "uniswap_v3:1:liquidity:1:0x11".to_string(),
+            protocol: "uniswap_v3".to_string(),
+            position_type: "liquidity".to_string(),
+            pair: "WETH/USDC".to_string(),
+            value_usd: 10_000.0,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({
+                "impermanent_loss_usd": -200.0,
+                "pnl_breakdown": PnlBreakdown { fees_earned: 100.0, impermanent_loss: -200.0, ..Default::default() },
+            }),
+            last_updated: 0,
+        };
+
+        for schema in [SchemaVersion::V1, SchemaVersion::V2] {
+            let json = frontend_position(&position, "0x11", "EUR", 0.9, &Precision::default(), schema);
+            assert_eq!(json["fees_earned_usd"], "90.00");
+            assert_eq!(json["impermanent_loss_usd"], "-180.00");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validating_against_a_wallet_requires_its_token() {
+        std::env::set_var("REQUIRE_PORTFOLIO_AUTH", "true");
+        let request = || PositionValidationRequest {
+            address: Some(format!("{:#x}", Address::repeat_byte(0x11))),
+            protocol: "aave".to_string(),
+            position_type: "supply".to_string(),
+            pair: "USDC".to_string(),
+            value_usd: 1_000.0,
+            metadata: serde_json::Map::new(),
+            profile: None,
+        };
+
+        let response = validate_position(State(test_state()), HeaderMap::new(), Json(request())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // Without a wallet nothing private is read
+        let anonymous = PositionValidationRequest { address: None, ..request() };
+        let response = validate_position(State(test_state()), HeaderMap::new(), Json(anonymous)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,173 @@
+use axum::{extract::State, http::header, response::IntoResponse};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::AppState;
+
+/// Upper bounds (seconds) of the adapter fetch latency histogram buckets
+const LATENCY_BUCKETS: [f64; 9] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Result of a single adapter fetch, used as the `outcome` label
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FetchOutcome {
+    Success,
+    Error,
+}
+
+impl FetchOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FetchOutcome::Success => "success",
+            FetchOutcome::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct LatencyHistogram {
+    bucket_counts: [u64; LATENCY_BUCKETS.len()],
+    sum_seconds: f64,
+    count: u64,
+}
+
+impl LatencyHistogram {
+    fn observe(&mut self, seconds: f64) {
+        for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+            if seconds <= *bound {
+                self.bucket_counts[i] += 1;
+            }
+        }
+        self.sum_seconds += seconds;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetricsInner {
+    fetches: BTreeMap<(String, FetchOutcome), LatencyHistogram>,
+    positions_returned: BTreeMap<String, u64>,
+}
+
+/// Per-adapter fetch metrics exported in Prometheus text format
+#[derive(Debug, Default)]
+pub struct AdapterMetrics {
+    inner: Mutex<MetricsInner>,
+}
+
+impl AdapterMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one adapter fetch with its latency and the number of positions it returned
+    pub fn record_fetch(&self, protocol: &str, outcome: FetchOutcome, latency: Duration, positions: usize) {
+        let mut inner = self.inner.lock().unwrap();
+
+        inner.fetches
+            .entry((protocol.to_string(), outcome))
+            .or_default()
+            .observe(latency.as_secs_f64());
+
+        *inner.positions_returned.entry(protocol.to_string()).or_insert(0) += positions as u64;
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let inner = self.inner.lock().unwrap();
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# HELP defi_adapter_fetch_total Adapter position fetches by outcome");
+        let _ = writeln!(out, "# TYPE defi_adapter_fetch_total counter");
+        for ((protocol, outcome), histogram) in &inner.fetches {
+            let _ = writeln!(
+                out,
+                "defi_adapter_fetch_total{{protocol=\"{}\",outcome=\"{}\"}} {}",
+                protocol, outcome.as_str(), histogram.count
+            );
+        }
+
+        let _ = writeln!(out, "# HELP defi_adapter_fetch_errors_total Adapter position fetches that returned an error");
+        let _ = writeln!(out, "# TYPE defi_adapter_fetch_errors_total counter");
+        for ((protocol, outcome), histogram) in &inner.fetches {
+            if *outcome == FetchOutcome::Error {
+                let _ = writeln!(
+                    out,
+                    "defi_adapter_fetch_errors_total{{protocol=\"{}\",outcome=\"{}\"}} {}",
+                    protocol, outcome.as_str(), histogram.count
+                );
+            }
+        }
+
+        let _ = writeln!(out, "# HELP defi_adapter_fetch_duration_seconds Adapter position fetch latency");
+        let _ = writeln!(out, "# TYPE defi_adapter_fetch_duration_seconds histogram");
+        for ((protocol, outcome), histogram) in &inner.fetches {
+            let labels = format!("protocol=\"{}\",outcome=\"{}\"", protocol, outcome.as_str());
+            for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "defi_adapter_fetch_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, histogram.bucket_counts[i]
+                );
+            }
+            let _ = writeln!(
+                out,
+                "defi_adapter_fetch_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, histogram.count
+            );
+            let _ = writeln!(out, "defi_adapter_fetch_duration_seconds_sum{{{}}} {}", labels, histogram.sum_seconds);
+            let _ = writeln!(out, "defi_adapter_fetch_duration_seconds_count{{{}}} {}", labels, histogram.count);
+        }
+
+        let _ = writeln!(out, "# HELP defi_adapter_positions_returned_total Positions returned by each adapter");
+        let _ = writeln!(out, "# TYPE defi_adapter_positions_returned_total counter");
+        for (protocol, count) in &inner.positions_returned {
+            let _ = writeln!(
+                out,
+                "defi_adapter_positions_returned_total{{protocol=\"{}\",outcome=\"success\"}} {}",
+                protocol, count
+            );
+        }
+
+        out
+    }
+}
+
+/// Prometheus scrape endpoint
+pub async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let mut histogram = LatencyHistogram::default();
+        histogram.observe(0.2);
+        histogram.observe(3.0);
+
+        assert_eq!(histogram.bucket_counts[0], 0); // 0.05
+        assert_eq!(histogram.bucket_counts[2], 1); // 0.25
+        assert_eq!(histogram.bucket_counts[6], 2); // 5.0
+        assert_eq!(histogram.count, 2);
+    }
+
+    #[test]
+    fn test_render_includes_protocol_and_outcome_labels() {
+        let metrics = AdapterMetrics::new();
+        metrics.record_fetch("lido", FetchOutcome::Success, Duration::from_millis(120), 2);
+        metrics.record_fetch("lido", FetchOutcome::Error, Duration::from_millis(40), 0);
+
+        let output = metrics.render();
+        assert!(output.contains("defi_adapter_fetch_total{protocol=\"lido\",outcome=\"success\"} 1"));
+        assert!(output.contains("defi_adapter_fetch_errors_total{protocol=\"lido\",outcome=\"error\"} 1"));
+        assert!(output.contains("defi_adapter_positions_returned_total{protocol=\"lido\",outcome=\"success\"} 2"));
+        assert!(output.contains("defi_adapter_fetch_duration_seconds_count{protocol=\"lido\",outcome=\"error\"} 1"));
+    }
+}
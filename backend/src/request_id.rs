@@ -0,0 +1,76 @@
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+use tracing::Instrument;
+
+/// Header a request id is read from and echoed back in
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest caller-supplied id that is kept; anything longer gets a fresh one
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Correlation id of the current request, available to handlers as an extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// Id from the caller's `x-request-id` when it is short printable ASCII, so ids can be
+/// traced through a proxy; a new UUID otherwise
+pub fn request_id_from(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic()))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Middleware running each request inside a `request` span carrying its id, so every
+/// log line it produces (adapter fetches included) can be filtered by that id
+pub async fn request_id(mut request: Request, next: Next) -> Response {
+    let id = request_id_from(request.headers());
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    request.extensions_mut().insert(RequestId(id.clone()));
+
+    let started_at = Instant::now();
+    let mut response = next.run(request).instrument(span.clone()).await;
+    span.in_scope(|| {
+        tracing::info!(
+            status = response.status().as_u16(),
+            latency_ms = started_at.elapsed().as_millis() as u64,
+            "request finished"
+        );
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_caller_id_is_kept_only_when_well_formed() {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("lb-7f3a"));
+        assert_eq!(request_id_from(&headers), "lb-7f3a");
+
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("has space"));
+        let generated = request_id_from(&headers);
+        assert!(uuid::Uuid::parse_str(&generated).is_ok());
+
+        assert_ne!(request_id_from(&HeaderMap::new()), request_id_from(&HeaderMap::new()));
+    }
+}
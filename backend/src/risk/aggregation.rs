@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+use crate::adapters::traits::Position;
+use crate::risk::models::{RiskInputs, RiskModelRegistry};
+use crate::services::asset_class::{self, AssetClass};
+use crate::services::monitoring;
+
+/// How individual position risk scores are combined into a portfolio score
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskAggregation {
+    /// Simple average across positions
+    Mean,
+    /// The single riskiest position
+    Max,
+    /// Average weighted by each position's absolute USD value
+    #[default]
+    ValueWeighted,
+    /// Average of the riskiest 10% of positions (at least one)
+    #[serde(rename = "worst_10_percent")]
+    Worst10Percent,
+}
+
+impl RiskAggregation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskAggregation::Mean => "mean",
+            RiskAggregation::Max => "max",
+            RiskAggregation::ValueWeighted => "value_weighted",
+            RiskAggregation::Worst10Percent => "worst_10_percent",
+        }
+    }
+
+    /// Portfolio risk score in [0, 1]; zero for an empty portfolio
+    pub fn aggregate(&self, positions: &[Position]) -> f64 {
+        let scored: Vec<(f64, f64)> = positions
+            .iter()
+            .map(|p| (position_risk_score(p), p.value_usd.abs()))
+            .collect();
+
+        self.aggregate_scores(&scored)
+    }
+
+    /// Aggregate `(risk_score, value_usd)` pairs
+    pub fn aggregate_scores(&self, scored: &[(f64, f64)]) -> f64 {
+        if scored.is_empty() {
+            return 0.0;
+        }
+
+        let mean = scored.iter().map(|(risk, _)| risk).sum::<f64>() / scored.len() as f64;
+
+        match self {
+            RiskAggregation::Mean => mean,
+            RiskAggregation::Max => scored.iter().map(|(risk, _)| *risk).fold(0.0, f64::max),
+            RiskAggregation::ValueWeighted => {
+                let total_value: f64 = scored.iter().map(|(_, value)| value).sum();
+                if total_value > 0.0 {
+                    scored.iter().map(|(risk, value)| risk * value).sum::<f64>() / total_value
+                } else {
+                    mean
+                }
+            }
+            RiskAggregation::Worst10Percent => {
+                let mut risks: Vec<f64> = scored.iter().map(|(risk, _)| *risk).collect();
+                risks.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+                let count = ((risks.len() as f64) * 0.1).ceil().max(1.0) as usize;
+                risks[..count].iter().sum::<f64>() / count as f64
+            }
+        }
+    }
+}
+
+/// Risk score of a single position in [0, 1], read from its `risk_score` metadata.
+/// Positions the adapter didn't score are estimated from their type, asset class and
+/// health factor.
+pub fn position_risk_score(position: &Position) -> f64 {
+    reported_risk_score(position).unwrap_or_else(|| estimated_risk_score(position))
+}
+
+/// The adapter's risk score, or `None` when it didn't score the position. Adapters
+/// report on the registry's [0, 1] scale; anything outside it is clamped, not rescaled.
+pub fn reported_risk_score(position: &Position) -> Option<f64> {
+    let score = position.metadata.get("risk_score").and_then(|v| v.as_f64())?;
+    score.is_finite().then(|| score.clamp(0.0, 1.0))
+}
+
+/// Risk of a position without a `risk_score`, from the model for its type: lowered
+/// for stablecoin deposits and raised to its proximity to liquidation
+pub fn estimated_risk_score(position: &Position) -> f64 {
+    let inputs = RiskInputs {
+        health_factor: monitoring::health_factor(position),
+        stablecoin: asset_class::classify(position) == AssetClass::Stablecoin,
+        ..Default::default()
+    };
+    RiskModelRegistry::global().score(&position.position_type, &inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One tiny high-risk position next to a large safe one
+    const PORTFOLIO: [(f64, f64); 2] = [(0.9, 100.0), (0.1, 9_900.0)];
+
+    #[test]
+    fn test_value_weighted_is_default_and_discounts_small_positions() {
+        let score = RiskAggregation::default().aggregate_scores(&PORTFOLIO);
+        assert!((score - 0.108).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_max_and_worst_percentile_let_small_positions_dominate() {
+        assert_eq!(RiskAggregation::Max.aggregate_scores(&PORTFOLIO), 0.9);
+        assert_eq!(RiskAggregation::Worst10Percent.aggregate_scores(&PORTFOLIO), 0.9);
+        assert!((RiskAggregation::Mean.aggregate_scores(&PORTFOLIO) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_empty_portfolio_has_no_risk() {
+        assert_eq!(RiskAggregation::ValueWeighted.aggregate_scores(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_aggregation_parses_from_snake_case() {
+        let parsed: RiskAggregation = serde_json::from_str("\"worst_10_percent\"").unwrap();
+        assert_eq!(parsed, RiskAggregation::Worst10Percent);
+    }
+
+    #[test]
+    fn test_unscored_positions_are_estimated() {
+        let position = |position_type: &str, pair: &str, metadata: serde_json::Value| Position {
+            id: "id".to_string(),
+            protocol: "test".to_string(),
+            position_type: position_type.to_string(),
+            pair: pair.to_string(),
+            value_usd: 1_000.0,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata,
+            last_updated: 0,
+        };
+
+        let stable_supply = position("supply", "USDC", serde_json::json!({}));
+        assert_eq!(position_risk_score(&stable_supply), 0.15);
+
+        let near_liquidation = position("borrow", "WETH/USDC", serde_json::json!({ "health_factor": 1.05 }));
+        assert!((position_risk_score(&near_liquidation) - 1.0 / 1.05).abs() < 1e-9);
+
+        let scored = position("liquidity", "WETH/USDC", serde_json::json!({ "risk_score": 0.35 }));
+        assert_eq!(position_risk_score(&scored), 0.35);
+        // A score off the [0, 1] scale is clamped rather than guessed to be a percentage
+        let off_scale = position("liquidity", "WETH/USDC", serde_json::json!({ "risk_score": 35.0 }));
+        assert_eq!(reported_risk_score(&off_scale), Some(1.0));
+        assert_eq!(reported_risk_score(&stable_supply), None);
+    }
+}
@@ -0,0 +1,225 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+use crate::adapters::traits::Position;
+use crate::risk::liquidation::{borrow_collateral, pooled_collateral, CollateralExposure};
+use crate::services::asset_class::AssetClass;
+
+/// One loan a drop in the shared collateral would liquidate
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CascadeExposure {
+    pub protocol: String,
+    /// Borrow positions of the loan; a pooled account lists each of its borrows
+    pub position_ids: Vec<String>,
+    pub debt_usd: f64,
+    /// Liquidation-weighted value of the shared collateral backing the loan
+    pub exposed_collateral_usd: f64,
+    /// Decline in the shared collateral alone that liquidates the loan
+    pub liquidation_drop_pct: f64,
+}
+
+/// Loans in different markets that the same collateral price drop liquidates together,
+/// which per-position liquidation prices don't show
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CascadeRisk {
+    /// Price the collateral follows, e.g. ETH for both WETH and wstETH
+    pub collateral: String,
+    /// Smallest decline that liquidates two loans at once
+    pub cascade_drop_pct: f64,
+    /// Decline that liquidates every affected loan
+    pub full_cascade_drop_pct: f64,
+    pub total_debt_usd: f64,
+    /// Every loan in the portfolio is backed by this collateral alone
+    pub sole_collateral: bool,
+    /// Nearest to liquidation first
+    pub affected: Vec<CascadeExposure>,
+    pub warning: String,
+}
+
+/// Borrows against the same collateral, liquidated as one
+struct Loan {
+    protocol: String,
+    position_ids: Vec<String>,
+    debt_usd: f64,
+    /// Prices the borrowed assets follow
+    debt_drivers: Vec<String>,
+    collateral: Vec<CollateralExposure>,
+}
+
+/// Price a token moves with: ETH for liquid staking tokens, BTC for wrapped bitcoin and
+/// the symbol itself otherwise. Stablecoins aren't volatile and have none.
+fn price_driver(symbol: &str) -> Option<String> {
+    match AssetClass::of_token(symbol) {
+        AssetClass::Stablecoin => None,
+        AssetClass::EthCorrelated => Some("ETH".to_string()),
+        AssetClass::BtcCorrelated => Some("BTC".to_string()),
+        _ => Some(symbol.trim().to_uppercase()),
+    }
+}
+
+/// Borrow positions as loans. Borrows of a pooled account (Aave V3 and forks) share its
+/// collateral, so they're one loan per protocol and chain.
+fn loans(positions: &[Position]) -> Vec<Loan> {
+    let mut loans: Vec<(String, Loan)> = Vec::new();
+
+    for position in positions.iter().filter(|p| p.position_type == "borrow") {
+        let Some((collateral, debt_usd)) = borrow_collateral(position) else {
+            continue;
+        };
+        let key = if pooled_collateral(position).is_some() {
+            let chain_id = position.metadata.get("chain_id").and_then(|v| v.as_u64()).unwrap_or(1);
+            format!("{}:{}", position.protocol, chain_id)
+        } else {
+            position.id.clone()
+        };
+        let debt_driver = price_driver(position.pair.split('/').next().unwrap_or_default());
+
+        match loans.iter_mut().find(|(existing, _)| *existing == key) {
+            Some((_, loan)) => {
+                loan.position_ids.push(position.id.clone());
+                loan.debt_drivers.extend(debt_driver);
+            }
+            None => loans.push((key, Loan {
+                protocol: position.protocol.clone(),
+                position_ids: vec![position.id.clone()],
+                debt_usd,
+                debt_drivers: debt_driver.into_iter().collect(),
+                collateral,
+            })),
+        }
+    }
+
+    loans.into_iter().map(|(_, loan)| loan).collect()
+}
+
+/// Collateral shared by two or more loans that a price drop would liquidate together,
+/// lowest cascade threshold first
+pub fn cascade_risk(positions: &[Position]) -> Vec<CascadeRisk> {
+    let loans = loans(positions);
+    let drivers: BTreeSet<String> = loans
+        .iter()
+        .flat_map(|loan| loan.collateral.iter().filter_map(|asset| price_driver(&asset.symbol)))
+        .collect();
+
+    let mut risks: Vec<CascadeRisk> = drivers.iter().filter_map(|driver| cascade_for(driver, &loans)).collect();
+    risks.sort_by(|a, b| a.cascade_drop_pct.partial_cmp(&b.cascade_drop_pct).unwrap_or(std::cmp::Ordering::Equal));
+    risks
+}
+
+fn cascade_for(driver: &str, loans: &[Loan]) -> Option<CascadeRisk> {
+    let follows = |asset: &CollateralExposure| price_driver(&asset.symbol).as_deref() == Some(driver);
+
+    let mut affected: Vec<CascadeExposure> = loans
+        .iter()
+        // Debt in the same asset falls with the collateral, so the price alone doesn't liquidate it
+        .filter(|loan| !loan.debt_drivers.iter().any(|debt| debt == driver))
+        .filter_map(|loan| {
+            let weighted: f64 = loan.collateral.iter().map(CollateralExposure::weighted_value).sum();
+            let exposed: f64 = loan.collateral.iter().filter(|asset| follows(asset)).map(CollateralExposure::weighted_value).sum();
+            if exposed <= 0.0 {
+                return None;
+            }
+            let drop = (weighted - loan.debt_usd).max(0.0) / exposed;
+            (drop <= 1.0).then(|| CascadeExposure {
+                protocol: loan.protocol.clone(),
+                position_ids: loan.position_ids.clone(),
+                debt_usd: loan.debt_usd,
+                exposed_collateral_usd: exposed,
+                liquidation_drop_pct: drop * 100.0,
+            })
+        })
+        .collect();
+    if affected.len() < 2 {
+        return None;
+    }
+    affected.sort_by(|a, b| a.liquidation_drop_pct.partial_cmp(&b.liquidation_drop_pct).unwrap_or(std::cmp::Ordering::Equal));
+
+    let cascade_drop_pct = affected[1].liquidation_drop_pct;
+    let full_cascade_drop_pct = affected[affected.len() - 1].liquidation_drop_pct;
+    let sole_collateral = loans.iter().all(|loan| loan.collateral.iter().all(follows));
+    let warning = if sole_collateral {
+        format!(
+            "All your borrowing is backed by {}: a {:.0}% drop would liquidate all {} loans at once",
+            driver, full_cascade_drop_pct, affected.len()
+        )
+    } else {
+        format!(
+            "{} loans share {} collateral: a {:.0}% drop liquidates two of them at once and {:.0}% liquidates all",
+            affected.len(), driver, cascade_drop_pct, full_cascade_drop_pct
+        )
+    };
+
+    Some(CascadeRisk {
+        collateral: driver.to_string(),
+        cascade_drop_pct,
+        full_cascade_drop_pct,
+        total_debt_usd: affected.iter().map(|loan| loan.debt_usd).sum(),
+        sole_collateral,
+        affected,
+        warning,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn borrow(id: &str, protocol: &str, pair: &str, debt_usd: f64, details: serde_json::Value) -> Position {
+        Position {
+            id: id.to_string(),
+            protocol: protocol.to_string(),
+            position_type: "borrow".to_string(),
+            pair: pair.to_string(),
+            value_usd: -debt_usd,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({ "position_details": details }),
+            last_updated: 0,
+        }
+    }
+
+    #[test]
+    fn test_loans_backed_by_the_same_token_cascade() {
+        // Aave: 12k weighted WETH + wstETH against 8k of debt across two borrows falls 33.3%
+        let aave_details = serde_json::json!({
+            "health_factor": 1.5,
+            "account_debt_usd": 8_000.0,
+            "collateral": [
+                { "symbol": "WETH", "value_usd": 10_000.0, "liquidation_threshold": 0.8 },
+                { "symbol": "wstETH", "value_usd": 5_000.0, "liquidation_threshold": 0.8 },
+            ],
+        });
+        let mut positions = vec![
+            borrow("aave-usdc", "aave_v3", "USDC/WETH+wstETH", 5_000.0, aave_details.clone()),
+            borrow("aave-dai", "aave_v3", "DAI/WETH+wstETH", 3_000.0, aave_details),
+            // Morpho at HF 1.25 is liquidated by a 20% drop
+            borrow("morpho", "morpho_blue", "USDC/wstETH", 1_000.0, serde_json::json!({ "health_factor": 1.25 })),
+            // A leveraged staking loop borrows ETH, so an ETH drop doesn't liquidate it
+            borrow("loop", "spark", "WETH/wstETH", 1_000.0, serde_json::json!({ "health_factor": 1.05 })),
+        ];
+
+        let risks = cascade_risk(&positions);
+        assert_eq!(risks.len(), 1);
+        let eth = &risks[0];
+        assert_eq!(eth.collateral, "ETH");
+        assert!(eth.sole_collateral);
+        assert_eq!(eth.affected.len(), 2);
+        assert_eq!(eth.affected[0].protocol, "morpho_blue");
+        assert_eq!(eth.affected[1].position_ids, vec!["aave-usdc", "aave-dai"]);
+        assert!((eth.cascade_drop_pct - 100.0 / 3.0).abs() < 1e-9);
+        assert!((eth.total_debt_usd - 9_000.0).abs() < 1e-9);
+        assert!(eth.warning.starts_with("All your borrowing is backed by ETH"));
+
+        positions.push(borrow("fraxlend", "frax", "FRAX/sfrxETH", 100.0, serde_json::json!({ "health_factor": 3.0 })));
+        positions.push(borrow("stable", "morpho_blue", "WETH/USDC", 500.0, serde_json::json!({ "health_factor": 1.1 })));
+        let risks = cascade_risk(&positions);
+        assert_eq!(risks[0].affected.len(), 3);
+        assert!(!risks[0].sole_collateral);
+    }
+
+    #[test]
+    fn test_single_loan_is_not_a_cascade() {
+        let positions = vec![borrow("morpho", "morpho_blue", "USDC/WETH", 1_000.0, serde_json::json!({ "health_factor": 1.2 }))];
+        assert!(cascade_risk(&positions).is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+use serde::Serialize;
+
+use crate::adapters::traits::Position;
+use crate::risk::aggregation::position_risk_score;
+use crate::services::position_aggregator::position_apy;
+
+/// Floor on the risk score divided by, so a position scored 0 doesn't rank as infinitely good
+const MIN_RISK_SCORE: f64 = 0.05;
+
+/// A position's yield per unit of risk
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RiskAdjustedReturn {
+    pub position_id: String,
+    pub protocol: String,
+    pub pair: String,
+    pub value_usd: f64,
+    /// APY in percent
+    pub apy: f64,
+    /// Risk score in [0, 1]
+    pub risk_score: f64,
+    /// `apy / risk_score`, a crude Sharpe-like ratio
+    pub return_per_risk: f64,
+    pub flagged_for_review: bool,
+}
+
+/// A wallet's yielding positions ranked by return per unit of risk, best first
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RiskComparison {
+    pub ranked: Vec<RiskAdjustedReturn>,
+    /// Why each flagged position was flagged, worst first
+    pub review: Vec<String>,
+    /// Positions left out for reporting no APY
+    pub positions_without_apy: usize,
+    /// Debt positions left out, since their APY is a cost rather than a return
+    pub debt_positions_skipped: usize,
+}
+
+/// Rank `positions` by APY over risk score and flag the bottom quarter (at least one
+/// position, once there are two to compare) for review
+pub fn compare_risk_adjusted(positions: &[Position]) -> RiskComparison {
+    let mut comparison = RiskComparison::default();
+
+    for position in positions {
+        if position.value_usd < 0.0 {
+            comparison.debt_positions_skipped += 1;
+            continue;
+        }
+        let Some(apy) = position_apy(position) else {
+            comparison.positions_without_apy += 1;
+            continue;
+        };
+        let risk_score = position_risk_score(position);
+
+        comparison.ranked.push(RiskAdjustedReturn {
+            position_id: position.id.clone(),
+            protocol: position.protocol.clone(),
+            pair: position.pair.clone(),
+            value_usd: position.value_usd,
+            apy,
+            risk_score,
+            return_per_risk: apy / risk_score.max(MIN_RISK_SCORE),
+            flagged_for_review: false,
+        });
+    }
+
+    comparison.ranked.sort_by(|a, b| {
+        b.return_per_risk.partial_cmp(&a.return_per_risk).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    if comparison.ranked.len() >= 2 {
+        let flagged = (comparison.ranked.len() / 4).max(1);
+        for entry in comparison.ranked.iter_mut().rev().take(flagged) {
+            entry.flagged_for_review = true;
+            comparison.review.push(format!(
+                "{} {} pays {:.2}% for a {:.2} risk score ({:.1} per unit of risk), among the worst in your portfolio",
+                entry.protocol, entry.pair, entry.apy, entry.risk_score, entry.return_per_risk,
+            ));
+        }
+    }
+
+    comparison
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(id: &str, protocol: &str, value_usd: f64, metadata: serde_json::Value) -> Position {
+        Position {
+            id: id.to_string(),
+            protocol: protocol.to_string(),
+            position_type: "supply".to_string(),
+            pair: "USDC".to_string(),
+            value_usd,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata,
+            last_updated: 0,
+        }
+    }
+
+    #[test]
+    fn test_positions_rank_by_return_per_risk() {
+        let positions = vec![
+            position("aave", "aave_v3", 5_000.0, serde_json::json!({ "supply_apy": 4.0, "risk_score": 0.2 })),
+            position("morpho", "morpho_blue", 3_000.0, serde_json::json!({ "supply_apy": 9.0, "risk_score": 0.9 })),
+            position("lido", "lido", 4_000.0, serde_json::json!({ "staking_apy": 3.0, "risk_score": 0.1 })),
+            position("debt", "aave_v3", -1_000.0, serde_json::json!({ "borrow_apy": 5.0 })),
+            position("lp", "uniswap_v3", 2_000.0, serde_json::json!({})),
+        ];
+
+        let comparison = compare_risk_adjusted(&positions);
+        let order: Vec<&str> = comparison.ranked.iter().map(|r| r.position_id.as_str()).collect();
+
+        assert_eq!(order, ["lido", "aave", "morpho"]);
+        assert!(comparison.ranked[2].flagged_for_review);
+        assert!(!comparison.ranked[1].flagged_for_review);
+        assert!(comparison.review[0].starts_with("morpho_blue USDC pays 9.00%"));
+        assert_eq!(comparison.positions_without_apy, 1);
+        assert_eq!(comparison.debt_positions_skipped, 1);
+    }
+
+    #[test]
+    fn test_single_position_is_not_flagged() {
+        let positions = [position("aave", "aave_v3", 5_000.0, serde_json::json!({ "supply_apy": 4.0, "risk_score": 0.0 }))];
+        let comparison = compare_risk_adjusted(&positions);
+
+        assert_eq!(comparison.ranked[0].return_per_risk, 80.0);
+        assert!(comparison.review.is_empty());
+    }
+}
@@ -0,0 +1,349 @@
+use serde::Serialize;
+
+use crate::adapters::traits::Position;
+use crate::services::position_aggregator::underlying_tokens;
+
+const MAINNET: u64 = 1;
+/// Swap fee paid to turn a bridged token variant (e.g. USDC.e) back into the native one
+const VARIANT_SWAP_FEE: f64 = 0.0005;
+/// Consolidation costs above this share of the fragmented value count as fully stuck
+const FULL_COST_RATIO: f64 = 0.02;
+
+/// How a bridge keeps funds on the other side honest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BridgeSecurity {
+    /// Rollup bridge secured by L1 with fraud or validity proofs
+    CanonicalRollup,
+    /// Token minted on the chain by its issuer rather than bridged (e.g. USDC via CCTP)
+    NativeIssuance,
+    /// Checkpointed by the chain's own validator set
+    ValidatorSet,
+}
+
+impl BridgeSecurity {
+    /// Base risk in [0, 1] before accounting for how much the bridge secures
+    fn base_risk(&self) -> f64 {
+        match self {
+            Self::NativeIssuance => 0.05,
+            Self::CanonicalRollup => 0.15,
+            Self::ValidatorSet => 0.35,
+        }
+    }
+}
+
+/// Bridge funds on an L2 sit behind, and what it costs to bring them back to mainnet
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BridgeProfile {
+    pub chain_id: u64,
+    pub chain: &'static str,
+    pub bridge: &'static str,
+    pub security: BridgeSecurity,
+    /// Value locked in the bridge, roughly; thinner bridges are less battle-tested
+    pub tvl_usd: f64,
+    pub withdrawal_delay_days: f64,
+    /// L1 gas to prove and finalize a withdrawal
+    pub exit_cost_usd: f64,
+}
+
+impl BridgeProfile {
+    /// Risk in [0, 1] of funds behind this bridge: the security model's base risk,
+    /// raised by up to half again for bridges securing well under $10B
+    pub fn risk(&self) -> f64 {
+        let thinness = ((1e10 / self.tvl_usd.max(1.0)).log10() / 4.0).clamp(0.0, 1.0);
+        (self.security.base_risk() * (1.0 + 0.5 * thinness)).clamp(0.0, 1.0)
+    }
+}
+
+/// Canonical bridges of the L2s positions are read from
+pub const BRIDGES: &[BridgeProfile] = &[
+    BridgeProfile {
+        chain_id: 10,
+        chain: "optimism",
+        bridge: "Optimism Standard Bridge",
+        security: BridgeSecurity::CanonicalRollup,
+        tvl_usd: 3.0e9,
+        withdrawal_delay_days: 7.0,
+        exit_cost_usd: 20.0,
+    },
+    BridgeProfile {
+        chain_id: 137,
+        chain: "polygon",
+        bridge: "Polygon PoS Bridge",
+        security: BridgeSecurity::ValidatorSet,
+        tvl_usd: 2.0e9,
+        withdrawal_delay_days: 0.125,
+        exit_cost_usd: 15.0,
+    },
+    BridgeProfile {
+        chain_id: 8453,
+        chain: "base",
+        bridge: "Base Standard Bridge",
+        security: BridgeSecurity::CanonicalRollup,
+        tvl_usd: 4.0e9,
+        withdrawal_delay_days: 7.0,
+        exit_cost_usd: 20.0,
+    },
+    BridgeProfile {
+        chain_id: 42161,
+        chain: "arbitrum",
+        bridge: "Arbitrum One Bridge",
+        security: BridgeSecurity::CanonicalRollup,
+        tvl_usd: 1.0e10,
+        withdrawal_delay_days: 7.0,
+        exit_cost_usd: 10.0,
+    },
+];
+
+/// Chains where Circle mints USDC natively, so it doesn't depend on the rollup bridge
+const NATIVE_USDC_CHAINS: &[u64] = &[10, 137, 8453, 42161];
+
+pub fn bridge_profile(chain_id: u64) -> Option<&'static BridgeProfile> {
+    BRIDGES.iter().find(|bridge| bridge.chain_id == chain_id)
+}
+
+/// The asset a token symbol represents on any chain, and whether the symbol is a
+/// bridged variant that has to be swapped for the native token
+fn canonical_asset(symbol: &str) -> (String, bool) {
+    let symbol = symbol.trim().to_uppercase();
+    match symbol.as_str() {
+        "WETH" => ("ETH".to_string(), false),
+        "USDBC" => ("USDC".to_string(), true),
+        _ => match symbol.strip_suffix(".E") {
+            Some(native) => (native.to_string(), true),
+            None => (symbol, false),
+        },
+    }
+}
+
+/// Risk of holdings on `chain_id` coming from the bridge they sit behind. Mainnet
+/// holdings and natively issued USDC on an L2 carry none beyond the issuer's.
+fn bridge_risk(chain_id: u64, asset: &str, bridged_variant: bool) -> f64 {
+    let Some(bridge) = bridge_profile(chain_id) else {
+        return 0.0;
+    };
+    if asset == "USDC" && !bridged_variant && NATIVE_USDC_CHAINS.contains(&chain_id) {
+        return BridgeSecurity::NativeIssuance.base_risk();
+    }
+    bridge.risk()
+}
+
+// Value of one token on one chain, as read from positions
+struct Holding {
+    asset: String,
+    chain_id: u64,
+    /// Held as a bridged variant of `asset`, e.g. USDC.e
+    bridged_variant: bool,
+    value_usd: f64,
+}
+
+impl Holding {
+    fn bridge_risk(&self) -> f64 {
+        bridge_risk(self.chain_id, &self.asset, self.bridged_variant)
+    }
+}
+
+/// Holdings of one asset on one chain
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChainHolding {
+    pub chain_id: u64,
+    pub value_usd: f64,
+    /// Share of the asset's cross-chain value in [0, 1]
+    pub share: f64,
+    pub bridge_risk: f64,
+}
+
+/// An asset held on more than one chain
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrossChainAsset {
+    pub asset: String,
+    pub total_value_usd: f64,
+    /// Largest holding first
+    pub chains: Vec<ChainHolding>,
+    /// In [0, 1]: 0 when nearly all of it is on one chain, 1 when evenly split and
+    /// expensive to bring back together
+    pub fragmentation_risk: f64,
+    /// Bridge exits and variant swaps needed to move everything onto the largest chain
+    pub consolidation_cost_usd: f64,
+}
+
+/// Risk a wallet takes on by holding funds across L1 and L2s
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CrossChainRisk {
+    /// Bridge and fragmentation risk combined, in [0, 1]; 0 for single-chain wallets
+    pub score: f64,
+    /// Value-weighted bridge risk of everything the wallet holds
+    pub bridge_risk: f64,
+    /// Value-weighted fragmentation risk of assets held on several chains
+    pub fragmentation_risk: f64,
+    /// Chains the wallet holds value on, ascending
+    pub chain_ids: Vec<u64>,
+    /// Share of portfolio value held outside mainnet
+    pub l2_value_ratio: f64,
+    /// Multi-chain assets, largest first
+    pub assets: Vec<CrossChainAsset>,
+}
+
+impl CrossChainRisk {
+    /// Detect assets held on several chains from each position's `chain_id` and
+    /// underlying tokens. Liquidity positions count half their value toward each leg;
+    /// positions whose price is unknown are skipped.
+    pub fn from_positions(positions: &[Position]) -> Self {
+        let mut holdings: Vec<Holding> = Vec::new();
+        for position in positions.iter().filter(|p| !p.is_price_unknown() && p.value_usd != 0.0) {
+            let chain_id = position.metadata.get("chain_id").and_then(|v| v.as_u64()).unwrap_or(MAINNET);
+            let tokens = underlying_tokens(position);
+            let value = position.value_usd.abs() / tokens.len() as f64;
+            for token in &tokens {
+                let (asset, bridged_variant) = canonical_asset(token);
+                match holdings.iter().position(|h| h.asset == asset && h.chain_id == chain_id && h.bridged_variant == bridged_variant) {
+                    Some(index) => holdings[index].value_usd += value,
+                    None => holdings.push(Holding { asset, chain_id, bridged_variant, value_usd: value }),
+                }
+            }
+        }
+
+        let total_value: f64 = holdings.iter().map(|h| h.value_usd).sum();
+        let mut chain_ids: Vec<u64> = holdings.iter().map(|h| h.chain_id).collect();
+        chain_ids.sort_unstable();
+        chain_ids.dedup();
+        if total_value <= 0.0 || chain_ids.len() < 2 {
+            return Self { chain_ids, ..Self::default() };
+        }
+
+        let bridge_risk = holdings.iter().map(|h| h.value_usd * h.bridge_risk()).sum::<f64>() / total_value;
+        let l2_value_ratio = holdings.iter().filter(|h| h.chain_id != MAINNET).map(|h| h.value_usd).sum::<f64>() / total_value;
+
+        let mut asset_names: Vec<&str> = holdings.iter().map(|h| h.asset.as_str()).collect();
+        asset_names.sort_unstable();
+        asset_names.dedup();
+        let mut assets: Vec<CrossChainAsset> = asset_names
+            .into_iter()
+            .filter_map(|asset| cross_chain_asset(asset, &holdings))
+            .collect();
+        assets.sort_by(|a, b| b.total_value_usd.partial_cmp(&a.total_value_usd).unwrap_or(std::cmp::Ordering::Equal));
+
+        let fragmentation_risk = assets.iter().map(|a| a.total_value_usd * a.fragmentation_risk).sum::<f64>() / total_value;
+
+        Self {
+            // Losing funds to a bridge and being stuck across chains are independent
+            score: (1.0 - (1.0 - bridge_risk) * (1.0 - fragmentation_risk)).clamp(0.0, 1.0),
+            bridge_risk,
+            fragmentation_risk,
+            chain_ids,
+            l2_value_ratio,
+            assets,
+        }
+    }
+}
+
+fn cross_chain_asset(asset: &str, holdings: &[Holding]) -> Option<CrossChainAsset> {
+    let held: Vec<&Holding> = holdings.iter().filter(|h| h.asset == asset).collect();
+    let total_value_usd: f64 = held.iter().map(|h| h.value_usd).sum();
+
+    let mut chains: Vec<ChainHolding> = Vec::new();
+    for holding in &held {
+        let risk = holding.bridge_risk();
+        match chains.iter().position(|c| c.chain_id == holding.chain_id) {
+            Some(index) => {
+                // Native and bridged variants on the same chain
+                let chain = &mut chains[index];
+                chain.bridge_risk = (chain.bridge_risk * chain.value_usd + risk * holding.value_usd) / (chain.value_usd + holding.value_usd);
+                chain.value_usd += holding.value_usd;
+            }
+            None => chains.push(ChainHolding { chain_id: holding.chain_id, value_usd: holding.value_usd, share: 0.0, bridge_risk: risk }),
+        }
+    }
+    if chains.len() < 2 || total_value_usd <= 0.0 {
+        return None;
+    }
+    for chain in &mut chains {
+        chain.share = chain.value_usd / total_value_usd;
+    }
+    chains.sort_by(|a, b| b.value_usd.partial_cmp(&a.value_usd).unwrap_or(std::cmp::Ordering::Equal));
+
+    // Everything off the largest chain is bridged over, bridged variants swapped first
+    let home = chains[0].chain_id;
+    let consolidation_cost_usd: f64 = held
+        .iter()
+        .filter(|h| h.chain_id != home)
+        .map(|h| {
+            let exit = bridge_profile(h.chain_id).map_or(0.0, |bridge| bridge.exit_cost_usd);
+            let swap = if h.bridged_variant { h.value_usd * VARIANT_SWAP_FEE } else { 0.0 };
+            exit + swap
+        })
+        .sum();
+
+    // Herfindahl index of chain shares, rescaled so an even split is 1
+    let n = chains.len() as f64;
+    let hhi: f64 = chains.iter().map(|c| c.share * c.share).sum();
+    let spread = ((1.0 - hhi) / (1.0 - 1.0 / n)).clamp(0.0, 1.0);
+    let fragmented_value = total_value_usd * (1.0 - chains[0].share);
+    let cost_pressure = (consolidation_cost_usd / fragmented_value.max(f64::EPSILON) / FULL_COST_RATIO).min(1.0);
+
+    Some(CrossChainAsset {
+        asset: asset.to_string(),
+        total_value_usd,
+        chains,
+        fragmentation_risk: (0.7 * spread + 0.3 * cost_pressure * spread).clamp(0.0, 1.0),
+        consolidation_cost_usd,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(id: &str, pair: &str, chain_id: u64, value_usd: f64) -> Position {
+        Position {
+            id: id.to_string(),
+            protocol: "aave_v3".to_string(),
+            position_type: "supply".to_string(),
+            pair: pair.to_string(),
+            value_usd,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({ "chain_id": chain_id }),
+            last_updated: 0,
+        }
+    }
+
+    #[test]
+    fn test_single_chain_wallet_has_no_cross_chain_risk() {
+        let risk = CrossChainRisk::from_positions(&[position("a", "USDC", 1, 10_000.0), position("b", "WETH", 1, 5_000.0)]);
+
+        assert_eq!(risk.score, 0.0);
+        assert_eq!(risk.chain_ids, vec![1]);
+        assert!(risk.assets.is_empty());
+    }
+
+    #[test]
+    fn test_asset_split_across_l1_and_l2s_is_detected() {
+        let positions = [
+            position("mainnet_usdc", "USDC", 1, 5_000.0),
+            position("arbitrum_usdc", "USDC.e", 42161, 5_000.0),
+            position("base_eth", "WETH", 8453, 1_000.0),
+            position("mainnet_eth", "ETH", 1, 9_000.0),
+        ];
+
+        let risk = CrossChainRisk::from_positions(&positions);
+
+        assert_eq!(risk.chain_ids, vec![1, 8453, 42161]);
+        assert_eq!(risk.assets.len(), 2);
+        let usdc = risk.assets.iter().find(|a| a.asset == "USDC").unwrap();
+        let eth = risk.assets.iter().find(|a| a.asset == "ETH").unwrap();
+        // An even split fragments more than a 90/10 one
+        assert!(usdc.fragmentation_risk > eth.fragmentation_risk);
+        // Arbitrum exit plus swapping USDC.e for USDC
+        assert!((usdc.consolidation_cost_usd - (10.0 + 5_000.0 * VARIANT_SWAP_FEE)).abs() < 1e-9);
+        assert!((risk.l2_value_ratio - 0.3).abs() < 1e-9);
+        assert!(risk.bridge_risk > 0.0 && risk.score > risk.bridge_risk);
+    }
+
+    #[test]
+    fn test_native_usdc_depends_less_on_the_bridge() {
+        assert!(bridge_risk(42161, "USDC", false) < bridge_risk(42161, "USDC", true));
+        assert!(bridge_profile(137).unwrap().risk() > bridge_profile(42161).unwrap().risk());
+        assert_eq!(bridge_risk(1, "USDC", false), 0.0);
+    }
+}
@@ -0,0 +1,325 @@
+use serde::{Deserialize, Serialize};
+
+use crate::adapters::traits::Position;
+use crate::risk::aggregation::{position_risk_score, RiskAggregation};
+use crate::risk::slippage::illiquid_value_ratio;
+use crate::services::asset_class::AssetClass;
+
+/// Share of a position's risk that moves with the broad crypto market, by asset class
+const MAJOR_BETA: f64 = 0.9;
+const STABLE_BETA: f64 = 0.2;
+const OTHER_BETA: f64 = 0.6;
+
+/// Raw score of one risk factor and its share of the overall portfolio risk
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RiskComponent {
+    /// Factor score in [0, 1]
+    pub score: f64,
+    /// Portion of `overall_risk` attributed to this factor
+    pub contribution: f64,
+}
+
+/// Portfolio weights above which a single position or protocol is flagged
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConcentrationLimits {
+    pub max_position_weight: f64,
+    pub max_protocol_weight: f64,
+}
+
+impl Default for ConcentrationLimits {
+    fn default() -> Self {
+        Self {
+            max_position_weight: 0.4,
+            max_protocol_weight: 0.6,
+        }
+    }
+}
+
+impl ConcentrationLimits {
+    /// Defaults overridden by `CONCENTRATION_MAX_POSITION_WEIGHT` /
+    /// `CONCENTRATION_MAX_PROTOCOL_WEIGHT`, as fractions of portfolio value
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |key: &str, default: f64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            max_position_weight: read("CONCENTRATION_MAX_POSITION_WEIGHT", defaults.max_position_weight),
+            max_protocol_weight: read("CONCENTRATION_MAX_PROTOCOL_WEIGHT", defaults.max_protocol_weight),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConcentrationScope {
+    Position,
+    Protocol,
+}
+
+/// A single position or protocol holding more of the portfolio than its limit allows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConcentrationWarning {
+    pub scope: ConcentrationScope,
+    /// Token symbol (or pair) of the position, or the protocol name
+    pub entity: String,
+    /// Position id, for position warnings
+    pub position_id: Option<String>,
+    /// Share of portfolio value in [0, 1]
+    pub weight: f64,
+    pub limit: f64,
+    pub message: String,
+}
+
+/// Portfolio risk split into the factors driving it. Component contributions
+/// sum to `overall_risk`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RiskDecomposition {
+    /// Value-weighted position risk
+    pub overall_risk: f64,
+    /// Risk that moves with the market (ETH/BTC beta)
+    pub systematic: RiskComponent,
+    /// Token- and protocol-specific risk not explained by the market
+    pub idiosyncratic: RiskComponent,
+    /// Weight of the largest position
+    pub concentration: RiskComponent,
+    /// Share of value in positions that can't currently be exited
+    pub liquidity: RiskComponent,
+    pub positions_analyzed: usize,
+    /// Positions and protocols over their concentration limits, largest first
+    pub concentration_warnings: Vec<ConcentrationWarning>,
+}
+
+impl RiskDecomposition {
+    /// Decomposition with concentration limits from the environment
+    pub fn from_positions(positions: &[Position]) -> Self {
+        Self::from_positions_with_limits(positions, &ConcentrationLimits::from_env())
+    }
+
+    pub fn from_positions_with_limits(positions: &[Position], limits: &ConcentrationLimits) -> Self {
+        let total_value: f64 = positions.iter().map(|p| p.value_usd.abs()).sum();
+        if positions.is_empty() || total_value <= 0.0 {
+            return Self {
+                positions_analyzed: positions.len(),
+                ..Self::default()
+            };
+        }
+
+        let overall_risk = RiskAggregation::ValueWeighted.aggregate(positions);
+
+        let mut systematic = 0.0;
+        let mut idiosyncratic = 0.0;
+        let mut largest_weight: f64 = 0.0;
+
+        for position in positions {
+            let weight = position.value_usd.abs() / total_value;
+            let risk = position_risk_score(position);
+            let beta = market_beta(position);
+
+            systematic += weight * risk * beta;
+            idiosyncratic += weight * risk * (1.0 - beta);
+            largest_weight = largest_weight.max(weight);
+        }
+
+        // A single position is fully concentrated by definition; only the excess over
+        // an evenly split portfolio counts as concentration risk
+        let even_weight = 1.0 / positions.len() as f64;
+        let concentration = if positions.len() > 1 {
+            ((largest_weight - even_weight) / (1.0 - even_weight)).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let liquidity = illiquid_value_ratio(positions);
+
+        let scores = [systematic, idiosyncratic, concentration, liquidity];
+        let total_score: f64 = scores.iter().sum();
+        let contribution = |score: f64| {
+            if total_score > 0.0 {
+                overall_risk * score / total_score
+            } else {
+                0.0
+            }
+        };
+        let component = |score: f64| RiskComponent {
+            score,
+            contribution: contribution(score),
+        };
+
+        Self {
+            overall_risk,
+            systematic: component(systematic),
+            idiosyncratic: component(idiosyncratic),
+            concentration: component(concentration),
+            liquidity: component(liquidity),
+            positions_analyzed: positions.len(),
+            concentration_warnings: concentration_warnings(positions, limits),
+        }
+    }
+}
+
+/// Flag every position and protocol whose share of portfolio value exceeds `limits`
+pub fn concentration_warnings(positions: &[Position], limits: &ConcentrationLimits) -> Vec<ConcentrationWarning> {
+    let total_value: f64 = positions.iter().map(|p| p.value_usd.abs()).sum();
+    if total_value <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut warnings = Vec::new();
+
+    for position in positions {
+        let weight = position.value_usd.abs() / total_value;
+        if weight > limits.max_position_weight {
+            let entity = position.metadata
+                .get("token_symbol")
+                .and_then(|v| v.as_str())
+                .unwrap_or(&position.pair)
+                .to_string();
+            warnings.push(ConcentrationWarning {
+                scope: ConcentrationScope::Position,
+                message: format!("{:.0}% of your portfolio is in {}", weight * 100.0, entity),
+                entity,
+                position_id: Some(position.id.clone()),
+                weight,
+                limit: limits.max_position_weight,
+            });
+        }
+    }
+
+    let mut protocol_values: Vec<(&str, f64)> = Vec::new();
+    for position in positions {
+        match protocol_values.iter_mut().find(|(protocol, _)| *protocol == position.protocol) {
+            Some((_, value)) => *value += position.value_usd.abs(),
+            None => protocol_values.push((&position.protocol, position.value_usd.abs())),
+        }
+    }
+    for (protocol, value) in protocol_values {
+        let weight = value / total_value;
+        if weight > limits.max_protocol_weight {
+            warnings.push(ConcentrationWarning {
+                scope: ConcentrationScope::Protocol,
+                entity: protocol.to_string(),
+                position_id: None,
+                weight,
+                limit: limits.max_protocol_weight,
+                message: format!("{:.0}% of your portfolio is on {}", weight * 100.0, protocol),
+            });
+        }
+    }
+
+    warnings.sort_by(|a, b| b.weight.partial_cmp(&a.weight).unwrap_or(std::cmp::Ordering::Equal));
+    warnings
+}
+
+/// Average market beta across the tokens a position is exposed to
+pub(crate) fn market_beta(position: &Position) -> f64 {
+    let tokens: Vec<String> = match position.metadata.get("underlying_asset").and_then(|v| v.as_str()) {
+        Some(asset) if !asset.is_empty() => vec![asset.to_uppercase()],
+        _ => position.pair
+            .split('/')
+            .map(|leg| leg.trim().to_uppercase())
+            .filter(|leg| !leg.is_empty())
+            .collect(),
+    };
+
+    if tokens.is_empty() {
+        return OTHER_BETA;
+    }
+
+    let beta_of = |token: &str| match AssetClass::of_token(token) {
+        AssetClass::EthCorrelated | AssetClass::BtcCorrelated => MAJOR_BETA,
+        AssetClass::Stablecoin => STABLE_BETA,
+        _ => OTHER_BETA,
+    };
+
+    tokens.iter().map(|t| beta_of(t)).sum::<f64>() / tokens.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(pair: &str, value_usd: f64, metadata: serde_json::Value) -> Position {
+        Position {
+            id: pair.to_string(),
+            protocol: "test".to_string(),
+            position_type: "supply".to_string(),
+            pair: pair.to_string(),
+            value_usd,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata,
+            last_updated: 0,
+        }
+    }
+
+    #[test]
+    fn test_contributions_sum_to_overall_risk() {
+        let positions = vec![
+            position("WETH/USDC", 6_000.0, serde_json::json!({ "risk_score": 0.6 })),
+            position("eETH/ETH", 3_000.0, serde_json::json!({ "risk_score": 0.4, "transferable": false })),
+            position("PEPE/WETH", 1_000.0, serde_json::json!({ "risk_score": 0.9 })),
+        ];
+
+        let decomposition = RiskDecomposition::from_positions(&positions);
+        let sum = decomposition.systematic.contribution
+            + decomposition.idiosyncratic.contribution
+            + decomposition.concentration.contribution
+            + decomposition.liquidity.contribution;
+
+        assert!((decomposition.overall_risk - 0.57).abs() < 1e-9);
+        assert!((sum - decomposition.overall_risk).abs() < 1e-9);
+        assert!((decomposition.liquidity.score - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stablecoins_carry_little_systematic_risk() {
+        let stable = RiskDecomposition::from_positions(&[position("USDC/DAI", 1_000.0, serde_json::json!({}))]);
+        let major = RiskDecomposition::from_positions(&[position("WETH/WBTC", 1_000.0, serde_json::json!({}))]);
+
+        assert!(stable.systematic.score < stable.idiosyncratic.score);
+        assert!(major.systematic.score > major.idiosyncratic.score);
+    }
+
+    #[test]
+    fn test_over_weighted_position_and_protocol_are_flagged() {
+        let mut steth = position("stETH/ETH", 6_500.0, serde_json::json!({ "token_symbol": "stETH" }));
+        steth.protocol = "lido".to_string();
+        let positions = vec![
+            steth,
+            position("WETH/USDC", 2_000.0, serde_json::json!({})),
+            position("USDC/DAI", 1_500.0, serde_json::json!({})),
+        ];
+
+        let decomposition = RiskDecomposition::from_positions_with_limits(&positions, &ConcentrationLimits::default());
+        let warnings = &decomposition.concentration_warnings;
+
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].scope, ConcentrationScope::Position);
+        assert_eq!(warnings[0].message, "65% of your portfolio is in stETH");
+        assert_eq!(warnings[1].scope, ConcentrationScope::Protocol);
+        assert_eq!(warnings[1].entity, "lido");
+    }
+
+    #[test]
+    fn test_balanced_portfolio_has_no_warnings() {
+        let positions = vec![
+            position("WETH/USDC", 3_500.0, serde_json::json!({})),
+            position("USDC/DAI", 3_500.0, serde_json::json!({})),
+            position("WBTC/ETH", 3_000.0, serde_json::json!({})),
+        ];
+        let limits = ConcentrationLimits { max_position_weight: 0.4, max_protocol_weight: 1.0 };
+
+        assert!(concentration_warnings(&positions, &limits).is_empty());
+    }
+
+    #[test]
+    fn test_empty_portfolio_has_no_risk() {
+        let decomposition = RiskDecomposition::from_positions(&[]);
+        assert_eq!(decomposition.overall_risk, 0.0);
+        assert_eq!(decomposition.concentration.contribution, 0.0);
+    }
+}
@@ -0,0 +1,116 @@
+use serde::Serialize;
+
+use crate::adapters::traits::{PnlBreakdown, Position};
+use crate::risk::aggregation::{position_risk_score, reported_risk_score};
+use crate::risk::decomposition::market_beta;
+use crate::risk::liquidation::{simulate_portfolio, LiquidationSimulation};
+use crate::risk::slippage::max_estimated_slippage;
+use crate::services::asset_class::{self, AssetClass};
+use crate::services::monitoring::{self, AlertLevel};
+use crate::services::{HealthFactorThresholds, ProtocolRisk, ProtocolRiskService};
+
+/// Everything behind one position's risk score, for a drill-down view
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionRiskExplain {
+    /// Risk score in [0, 1]
+    pub risk_score: f64,
+    /// Whether the adapter scored the position or it was estimated
+    pub risk_score_source: &'static str,
+    pub asset_class: AssetClass,
+    /// Share of the risk that moves with the broad market
+    pub market_beta: f64,
+    /// `risk_score` split into market-driven and position-specific parts
+    pub systematic_risk: f64,
+    pub idiosyncratic_risk: f64,
+    /// The position can't currently be transferred or exited
+    pub illiquid: bool,
+    /// Estimated price impact of exiting, as a fraction
+    pub exit_slippage: Option<f64>,
+    pub health_factor: Option<f64>,
+    pub health_alert: AlertLevel,
+    /// Price declines that would liquidate the position, for borrows
+    pub liquidation: Option<LiquidationSimulation>,
+    pub pnl_breakdown: Option<PnlBreakdown>,
+    /// Audit coverage, exploit history and governance of the protocol
+    pub protocol_risk: ProtocolRisk,
+    /// Plain-language reasons the position is risky, most severe first
+    pub factors: Vec<String>,
+}
+
+/// Explain `position`'s risk score and what would liquidate it
+pub fn explain_position(position: &Position, thresholds: &HealthFactorThresholds) -> PositionRiskExplain {
+    let risk_score = position_risk_score(position);
+    let beta = market_beta(position);
+    let health_factor = monitoring::health_factor(position);
+    let health_alert = health_factor.map_or(AlertLevel::Healthy, |hf| thresholds.level(hf));
+    let liquidation = simulate_portfolio(std::slice::from_ref(position)).into_iter().next();
+    let illiquid = position.metadata.get("transferable").and_then(|v| v.as_bool()) == Some(false);
+    let flag = |key: &str| position.metadata.get(key).and_then(|v| v.as_bool()) == Some(true);
+
+    let mut factors = Vec::new();
+    if let Some(simulation) = &liquidation {
+        factors.push(simulation.summary.clone());
+    } else if let Some(hf) = health_factor.filter(|_| health_alert != AlertLevel::Healthy) {
+        factors.push(format!("Health factor is {:.2}", hf));
+    }
+    if flag("recent_slashing_detected") {
+        factors.push("Recent slashing was detected for this staking provider".to_string());
+    }
+    if illiquid {
+        factors.push("Position can't be exited until it unlocks".to_string());
+    }
+    if position.is_price_unknown() {
+        factors.push("A token in this position has no price, so its value is understated".to_string());
+    } else if position.is_price_uncertain() {
+        factors.push("Price sources disagree on a token in this position".to_string());
+    }
+    if flag("stale") {
+        factors.push("Position data is older than the freshness limit for its chain".to_string());
+    }
+
+    PositionRiskExplain {
+        risk_score,
+        risk_score_source: if reported_risk_score(position).is_some() { "adapter" } else { "estimated" },
+        asset_class: asset_class::classify(position),
+        market_beta: beta,
+        systematic_risk: risk_score * beta,
+        idiosyncratic_risk: risk_score * (1.0 - beta),
+        illiquid,
+        exit_slippage: max_estimated_slippage(std::slice::from_ref(position)),
+        health_factor,
+        health_alert,
+        liquidation,
+        pnl_breakdown: position.pnl_breakdown(),
+        protocol_risk: ProtocolRiskService::global().get_protocol_risk(&position.protocol),
+        factors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_borrow_explains_liquidation_and_health() {
+        let borrow = Position {
+            id: "morpho_blue:1:borrow:0xmarket:0x0".to_string(),
+            protocol: "morpho_blue".to_string(),
+            position_type: "borrow".to_string(),
+            pair: "USDC/WETH".to_string(),
+            value_usd: -1_000.0,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({ "health_factor": 1.25, "risk_score": 0.8 }),
+            last_updated: 0,
+        };
+
+        let explain = explain_position(&borrow, &HealthFactorThresholds::default());
+
+        assert_eq!(explain.risk_score_source, "adapter");
+        assert_eq!(explain.health_alert, AlertLevel::Warning);
+        assert!((explain.systematic_risk + explain.idiosyncratic_risk - 0.8).abs() < 1e-12);
+        let liquidation = explain.liquidation.unwrap();
+        assert!((liquidation.all_collateral_drop_pct - 20.0).abs() < 1e-9);
+        assert_eq!(explain.factors[0], liquidation.summary);
+    }
+}
@@ -0,0 +1,248 @@
+use serde::Serialize;
+
+use crate::adapters::traits::Position;
+
+/// One collateral asset backing a debt, weighted by its liquidation threshold
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollateralExposure {
+    pub symbol: String,
+    pub value_usd: f64,
+    /// Fraction of `value_usd` that counts toward the health factor (LLTV / max LTV)
+    pub liquidation_threshold: f64,
+}
+
+impl CollateralExposure {
+    pub(crate) fn weighted_value(&self) -> f64 {
+        self.value_usd * self.liquidation_threshold
+    }
+}
+
+/// Price decline in one collateral asset that brings the health factor to 1.0
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CollateralPriceDrop {
+    pub symbol: String,
+    /// Percentage decline with every other price held constant; `None` when the debt
+    /// stays covered even if this asset goes to zero
+    pub price_drop_pct: Option<f64>,
+}
+
+/// How far collateral prices can fall before a borrow position is liquidatable
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LiquidationSimulation {
+    pub position_id: String,
+    pub protocol: String,
+    pub health_factor: f64,
+    pub debt_usd: f64,
+    pub per_asset: Vec<CollateralPriceDrop>,
+    /// Percentage decline in all collateral at once that triggers liquidation
+    pub all_collateral_drop_pct: f64,
+    /// Human-readable headline, e.g. "ETH can drop 22% before you're liquidated"
+    pub summary: String,
+}
+
+/// Price declines that bring a debt's health factor to 1.0.
+///
+/// With health factor `HF = sum(c_i * lt_i) / debt`, asset `i` alone can fall by
+/// `(sum(c * lt) - debt) / (c_i * lt_i)` and everything together by `1 - 1 / HF`.
+pub fn simulate_liquidation(
+    position_id: &str,
+    protocol: &str,
+    collateral: &[CollateralExposure],
+    debt_usd: f64,
+) -> LiquidationSimulation {
+    let weighted_collateral: f64 = collateral.iter().map(CollateralExposure::weighted_value).sum();
+    let health_factor = if debt_usd > 0.0 {
+        weighted_collateral / debt_usd
+    } else {
+        f64::INFINITY
+    };
+    let buffer = (weighted_collateral - debt_usd).max(0.0);
+
+    let per_asset: Vec<CollateralPriceDrop> = collateral
+        .iter()
+        .map(|asset| {
+            let weighted = asset.weighted_value();
+            let drop = if weighted > 0.0 { buffer / weighted } else { f64::INFINITY };
+            CollateralPriceDrop {
+                symbol: asset.symbol.clone(),
+                price_drop_pct: (drop <= 1.0).then_some(drop * 100.0),
+            }
+        })
+        .collect();
+
+    let all_collateral_drop_pct = if health_factor.is_finite() && health_factor > 0.0 {
+        (1.0 - 1.0 / health_factor).max(0.0) * 100.0
+    } else {
+        100.0
+    };
+
+    let summary = summarize(&per_asset, all_collateral_drop_pct, health_factor);
+
+    LiquidationSimulation {
+        position_id: position_id.to_string(),
+        protocol: protocol.to_string(),
+        health_factor,
+        debt_usd,
+        per_asset,
+        all_collateral_drop_pct,
+        summary,
+    }
+}
+
+/// Simulations for every borrow position reporting a health factor.
+///
+/// Pooled markets (Aave V3 and forks) list each collateral at its effective, e.g.
+/// e-mode, liquidation threshold under `position_details.collateral`, simulated against
+/// the account's whole debt. Isolated markets (Morpho Blue, Fraxlend) back each borrow
+/// with the single collateral token named second in its pair.
+pub fn simulate_portfolio(positions: &[Position]) -> Vec<LiquidationSimulation> {
+    positions
+        .iter()
+        .filter(|p| p.position_type == "borrow")
+        .filter_map(|position| {
+            let (collateral, debt_usd) = borrow_collateral(position)?;
+            Some(simulate_liquidation(&position.id, &position.protocol, &collateral, debt_usd))
+        })
+        .collect()
+}
+
+/// Collateral backing a borrow position and the debt it's measured against, as
+/// [`simulate_portfolio`] reads them
+pub(crate) fn borrow_collateral(position: &Position) -> Option<(Vec<CollateralExposure>, f64)> {
+    if let Some(pooled) = pooled_collateral(position) {
+        return Some(pooled);
+    }
+
+    let health_factor = metadata_f64(position, "health_factor")
+        .filter(|hf| hf.is_finite() && *hf > 0.0)?;
+    let debt_usd = position.value_usd.abs();
+    let symbol = position.pair.split('/').nth(1).unwrap_or("Collateral").trim().to_string();
+
+    // Only the liquidation-weighted value matters, which the health factor already gives
+    let collateral = vec![CollateralExposure {
+        symbol,
+        value_usd: health_factor * debt_usd,
+        liquidation_threshold: 1.0,
+    }];
+    Some((collateral, debt_usd))
+}
+
+/// Collateral exposures and account debt of a borrow in a pooled market
+pub(crate) fn pooled_collateral(position: &Position) -> Option<(Vec<CollateralExposure>, f64)> {
+    let details = position.metadata.get("position_details")?;
+    let debt_usd = details.get("account_debt_usd")?.as_f64().filter(|debt| *debt > 0.0)?;
+    let collateral: Vec<CollateralExposure> = details
+        .get("collateral")?
+        .as_array()?
+        .iter()
+        .filter_map(|entry| {
+            Some(CollateralExposure {
+                symbol: entry.get("symbol")?.as_str()?.to_string(),
+                value_usd: entry.get("value_usd")?.as_f64()?,
+                liquidation_threshold: entry.get("liquidation_threshold")?.as_f64()?,
+            })
+        })
+        .collect();
+    (!collateral.is_empty()).then_some((collateral, debt_usd))
+}
+
+fn summarize(per_asset: &[CollateralPriceDrop], all_collateral_drop_pct: f64, health_factor: f64) -> String {
+    if health_factor <= 1.0 {
+        return "Position is already liquidatable".to_string();
+    }
+
+    match per_asset {
+        [single] => match single.price_drop_pct {
+            Some(drop) => format!("{} can drop {:.0}% before you're liquidated", single.symbol, drop),
+            None => format!("{} can go to zero without triggering liquidation", single.symbol),
+        },
+        _ => format!("All collateral can drop {:.0}% before you're liquidated", all_collateral_drop_pct),
+    }
+}
+
+fn metadata_f64(position: &Position, key: &str) -> Option<f64> {
+    position.metadata
+        .get(key)
+        .or_else(|| position.metadata.get("position_details").and_then(|d| d.get(key)))
+        .and_then(|v| v.as_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exposure(symbol: &str, value_usd: f64, liquidation_threshold: f64) -> CollateralExposure {
+        CollateralExposure {
+            symbol: symbol.to_string(),
+            value_usd,
+            liquidation_threshold,
+        }
+    }
+
+    #[test]
+    fn test_single_collateral_drop_matches_health_factor() {
+        // $10k ETH at 80% threshold against $6k debt: HF 1.333, ETH can fall 25%
+        let simulation = simulate_liquidation("p", "test", &[exposure("ETH", 10_000.0, 0.8)], 6_000.0);
+
+        assert!((simulation.health_factor - 4.0 / 3.0).abs() < 1e-12);
+        assert!((simulation.per_asset[0].price_drop_pct.unwrap() - 25.0).abs() < 1e-9);
+        assert!((simulation.all_collateral_drop_pct - 25.0).abs() < 1e-9);
+        assert_eq!(simulation.summary, "ETH can drop 25% before you're liquidated");
+    }
+
+    #[test]
+    fn test_multi_collateral_drops_hold_other_prices_constant() {
+        // Weighted collateral 8000 + 1500 = 9500 against 7000 debt leaves a 2500 buffer
+        let collateral = [exposure("ETH", 10_000.0, 0.8), exposure("WBTC", 2_000.0, 0.75)];
+        let simulation = simulate_liquidation("p", "test", &collateral, 7_000.0);
+
+        assert!((simulation.per_asset[0].price_drop_pct.unwrap() - 31.25).abs() < 1e-9);
+        // WBTC going to zero removes only 1500 of the 2500 buffer
+        assert_eq!(simulation.per_asset[1].price_drop_pct, None);
+        assert!(simulation.all_collateral_drop_pct < 31.25);
+    }
+
+    #[test]
+    fn test_portfolio_simulation_uses_borrow_health_factor() {
+        let borrow = Position {
+            id: "borrow".to_string(),
+            protocol: "morpho_blue".to_string(),
+            position_type: "borrow".to_string(),
+            pair: "USDC/WETH".to_string(),
+            value_usd: -1_000.0,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({ "position_details": { "health_factor": 2.0 } }),
+            last_updated: 0,
+        };
+
+        let simulations = simulate_portfolio(&[borrow]);
+        assert_eq!(simulations.len(), 1);
+        assert_eq!(simulations[0].per_asset[0].symbol, "WETH");
+        assert!((simulations[0].all_collateral_drop_pct - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pooled_collateral_uses_reported_thresholds() {
+        // 10 WETH-worth at the 95% e-mode threshold against 8 of debt: HF 1.1875
+        let borrow = Position {
+            id: "borrow".to_string(),
+            protocol: "aave_v3".to_string(),
+            position_type: "borrow".to_string(),
+            pair: "wstETH/WETH".to_string(),
+            value_usd: -8.0,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({ "position_details": {
+                "health_factor": 1.1875,
+                "account_debt_usd": 8.0,
+                "collateral": [{ "symbol": "WETH", "value_usd": 10.0, "liquidation_threshold": 0.95 }],
+            } }),
+            last_updated: 0,
+        };
+
+        let simulation = &simulate_portfolio(&[borrow])[0];
+        assert!((simulation.health_factor - 1.1875).abs() < 1e-12);
+        assert!((simulation.per_asset[0].price_drop_pct.unwrap() - 100.0 * 1.5 / 9.5).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,150 @@
+use serde::Serialize;
+
+use crate::adapters::traits::Position;
+use crate::risk::slippage::{estimate_slippage, PoolSnapshot};
+
+/// Mainnet blocks per day at 12s slots
+const BLOCKS_PER_DAY: f64 = 7_200.0;
+/// Gas for a front-run and back-run pair; below this a sandwich doesn't pay
+const SANDWICH_GAS_COST_USD: f64 = 15.0;
+/// Daily volume as a share of TVL assumed when the adapter doesn't report volume
+const DEFAULT_DAILY_TURNOVER: f64 = 0.1;
+/// Daily price volatility assumed when the adapter doesn't report one
+const DEFAULT_DAILY_VOLATILITY: f64 = 0.03;
+/// Price impact at which a trade is as attractive a target as it gets
+const FULL_IMPACT: f64 = 0.01;
+/// Daily volatility at which price noise fully masks an attack
+const FULL_VOLATILITY: f64 = 0.05;
+
+/// Recent trading in a pool
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PoolActivity {
+    pub swap_volume_24h_usd: f64,
+    /// Standard deviation of the pool price's daily returns
+    pub daily_volatility: f64,
+}
+
+impl PoolActivity {
+    /// Activity reported in position metadata (`pool_volume_24h_usd`,
+    /// `pool_daily_volatility`), with defaults for whatever is missing
+    pub fn from_position(position: &Position, pool: &PoolSnapshot) -> Self {
+        let metadata = &position.metadata;
+        Self {
+            swap_volume_24h_usd: metadata
+                .get("pool_volume_24h_usd")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(pool.tvl_usd * DEFAULT_DAILY_TURNOVER),
+            daily_volatility: metadata
+                .get("pool_daily_volatility")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(DEFAULT_DAILY_VOLATILITY),
+        }
+    }
+
+    pub fn volume_per_block_usd(&self) -> f64 {
+        self.swap_volume_24h_usd.max(0.0) / BLOCKS_PER_DAY
+    }
+}
+
+/// How exposed a trade of a given size through a pool is to being sandwiched
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SandwichRisk {
+    /// In [0, 1]; higher for larger trades in thinner, more volatile pools
+    pub score: f64,
+    /// Roughly the price impact the trade creates, which a sandwich can capture in full
+    pub worst_case_extractable_usd: f64,
+    pub trade_size_usd: f64,
+    pub price_impact: f64,
+    /// Trade size over trade size plus a typical block's volume in the pool
+    pub block_share: f64,
+    pub daily_volatility: f64,
+}
+
+/// Sandwich risk of trading `trade_size_usd` through `pool`, or `None` when the pool
+/// snapshot can't give a price impact.
+///
+/// The score blends price impact (what an attacker can take), the trade's share of a
+/// block's flow (how much it stands out) and volatility (how well the attack hides in
+/// price noise and how wide users set slippage), then scales it down when the
+/// extractable value wouldn't cover the attacker's gas.
+pub fn estimate_sandwich_risk(trade_size_usd: f64, pool: &PoolSnapshot, activity: &PoolActivity) -> Option<SandwichRisk> {
+    let trade_size_usd = trade_size_usd.abs();
+    let price_impact = estimate_slippage(trade_size_usd, pool)?;
+    let worst_case_extractable_usd = trade_size_usd * price_impact;
+
+    let per_block = activity.volume_per_block_usd();
+    let block_share = if trade_size_usd + per_block > 0.0 {
+        trade_size_usd / (trade_size_usd + per_block)
+    } else {
+        0.0
+    };
+
+    let impact_component = (price_impact / FULL_IMPACT).min(1.0);
+    let volatility_component = (activity.daily_volatility.max(0.0) / FULL_VOLATILITY).min(1.0);
+    let attractiveness = 0.5 * impact_component + 0.3 * block_share + 0.2 * volatility_component;
+    let profitability = (worst_case_extractable_usd / SANDWICH_GAS_COST_USD).min(1.0);
+
+    Some(SandwichRisk {
+        score: (attractiveness * profitability).clamp(0.0, 1.0),
+        worst_case_extractable_usd,
+        trade_size_usd,
+        price_impact,
+        block_share,
+        daily_volatility: activity.daily_volatility,
+    })
+}
+
+/// Sandwich risk of exiting each position that reports pool state, worst first
+pub fn portfolio_sandwich_risk(positions: &[Position]) -> Vec<(String, SandwichRisk)> {
+    let mut risks: Vec<(String, SandwichRisk)> = positions
+        .iter()
+        .filter_map(|position| {
+            let pool = PoolSnapshot::from_position(position)?;
+            let activity = PoolActivity::from_position(position, &pool);
+            let risk = estimate_sandwich_risk(position.value_usd, &pool, &activity)?;
+            Some((position.id.clone(), risk))
+        })
+        .collect();
+
+    risks.sort_by(|a, b| b.1.score.partial_cmp(&a.1.score).unwrap_or(std::cmp::Ordering::Equal));
+    risks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(tvl_usd: f64) -> PoolSnapshot {
+        PoolSnapshot {
+            protocol: "uniswap_v2".to_string(),
+            tvl_usd,
+            active_liquidity: None,
+        }
+    }
+
+    #[test]
+    fn test_large_trade_in_thin_pool_scores_higher() {
+        let activity = PoolActivity { swap_volume_24h_usd: 7_200_000.0, daily_volatility: 0.03 };
+
+        let thin = estimate_sandwich_risk(50_000.0, &pool(2_000_000.0), &activity).unwrap();
+        let deep = estimate_sandwich_risk(50_000.0, &pool(200_000_000.0), &activity).unwrap();
+        let small = estimate_sandwich_risk(500.0, &pool(2_000_000.0), &activity).unwrap();
+
+        assert!(thin.score > deep.score);
+        assert!(thin.score > small.score);
+        // 50k into a 1M reserve moves the price ~4.8%
+        assert!((thin.worst_case_extractable_usd - 50_000.0 * 50_000.0 / 1_050_000.0).abs() < 1e-6);
+        assert!((thin.block_share - 50_000.0 / 51_000.0).abs() < 1e-9);
+        assert!(thin.score > 0.8);
+    }
+
+    #[test]
+    fn test_unprofitable_sandwich_scores_low() {
+        let activity = PoolActivity { swap_volume_24h_usd: 0.0, daily_volatility: 0.1 };
+        let risk = estimate_sandwich_risk(100.0, &pool(10_000_000.0), &activity).unwrap();
+
+        assert!(risk.worst_case_extractable_usd < 0.01);
+        assert!(risk.score < 0.01);
+        assert!(estimate_sandwich_risk(100.0, &pool(0.0), &activity).is_none());
+    }
+}
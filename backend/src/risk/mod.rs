@@ -0,0 +1,31 @@
+pub mod aggregation;
+pub mod cascade;
+pub mod compare;
+pub mod cross_chain;
+pub mod decomposition;
+pub mod explain;
+pub mod liquidation;
+pub mod mev;
+pub mod models;
+pub mod performance;
+pub mod preview;
+pub mod profile;
+pub mod slippage;
+pub mod var;
+pub mod whatif;
+
+pub use aggregation::{position_risk_score, reported_risk_score, RiskAggregation};
+pub use cascade::{cascade_risk, CascadeExposure, CascadeRisk};
+pub use compare::{compare_risk_adjusted, RiskAdjustedReturn, RiskComparison};
+pub use cross_chain::{bridge_profile, BridgeProfile, BridgeSecurity, CrossChainAsset, CrossChainRisk};
+pub use decomposition::{concentration_warnings, ConcentrationLimits, ConcentrationScope, ConcentrationWarning, RiskDecomposition};
+pub use explain::{explain_position, PositionRiskExplain};
+pub use liquidation::{simulate_liquidation, CollateralExposure, LiquidationSimulation};
+pub use mev::{estimate_sandwich_risk, portfolio_sandwich_risk, PoolActivity, SandwichRisk};
+pub use models::{RiskInputs, RiskModel, RiskModelRegistry};
+pub use performance::{Benchmark, BenchmarkComparison, PerformanceMetrics};
+pub use preview::{preview_position, PositionPreview};
+pub use profile::RiskProfile;
+pub use slippage::{illiquid_value_ratio, max_estimated_slippage, SlippageModel};
+pub use var::RiskMetrics;
+pub use whatif::{what_if, HypotheticalPosition, PositionSide, WhatIfAnalysis};
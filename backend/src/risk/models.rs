@@ -0,0 +1,187 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Shortfall below peg at which depeg risk is fully priced in
+const MAX_DEPEG: f64 = 0.05;
+
+/// Utilization where withdrawals start to get hard, and where they're blocked
+const UTILIZATION_KINK: f64 = 0.8;
+const FULL_UTILIZATION: f64 = 1.0;
+
+/// How a position type is scored: a baseline plus weights for what makes that kind of
+/// position riskier or safer
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RiskModel {
+    pub base: f64,
+    /// Added as utilization goes from 80% to 100%, since borrowed-out markets block withdrawals
+    pub utilization_weight: f64,
+    /// Added in proportion to how much of the maximum lock remains
+    pub lock_weight: f64,
+    /// Added as the asset falls up to 5% below its peg
+    pub depeg_weight: f64,
+    /// Taken off when the position's assets track each other, e.g. a stable pool
+    pub correlated_discount: f64,
+    /// Ceiling for positions holding only stablecoins
+    pub stablecoin_cap: Option<f64>,
+}
+
+impl RiskModel {
+    pub const fn flat(base: f64) -> Self {
+        Self {
+            base,
+            utilization_weight: 0.0,
+            lock_weight: 0.0,
+            depeg_weight: 0.0,
+            correlated_discount: 0.0,
+            stablecoin_cap: None,
+        }
+    }
+
+    /// Risk in [0, 1]. A health factor raises the score to its proximity to liquidation.
+    pub fn score(&self, inputs: &RiskInputs) -> f64 {
+        let utilization_risk = inputs.utilization.map_or(0.0, |utilization| {
+            ((utilization - UTILIZATION_KINK) / (FULL_UTILIZATION - UTILIZATION_KINK)).clamp(0.0, 1.0)
+        });
+        let lock_risk = inputs.lock_fraction.clamp(0.0, 1.0);
+        let depeg_risk = (inputs.peg_deviation.min(0.0).abs() / MAX_DEPEG).min(1.0);
+
+        let mut score = self.base
+            + inputs.premium
+            + utilization_risk * self.utilization_weight
+            + lock_risk * self.lock_weight
+            + depeg_risk * self.depeg_weight;
+        if inputs.correlated {
+            score -= self.correlated_discount;
+        }
+        if let Some(cap) = self.stablecoin_cap.filter(|_| inputs.stablecoin) {
+            score = score.min(cap);
+        }
+        score = score.max(inputs.floor);
+        if let Some(hf) = inputs.health_factor.filter(|hf| hf.is_finite()) {
+            score = score.max(health_factor_risk(hf));
+        }
+        score.clamp(0.0, 1.0)
+    }
+}
+
+/// What's known about a position when it's scored; anything left at its default
+/// doesn't move the score
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RiskInputs {
+    /// Liquidation health factor; infinite for an account without debt
+    pub health_factor: Option<f64>,
+    /// Borrowed share of the market the position lends into
+    pub utilization: Option<f64>,
+    /// Remaining lock as a share of the longest possible lock
+    pub lock_fraction: f64,
+    /// Price relative to peg minus one; only a discount adds risk
+    pub peg_deviation: f64,
+    /// The position's assets track each other
+    pub correlated: bool,
+    /// The position holds only stablecoins
+    pub stablecoin: bool,
+    /// Protocol-specific risk the model doesn't cover, added to the baseline
+    pub premium: f64,
+    /// Lowest the score can be, e.g. the risk of the market a borrow sits in
+    pub floor: f64,
+}
+
+/// Risk implied by a health factor: 1.0 at liquidation, halving as it doubles
+fn health_factor_risk(health_factor: f64) -> f64 {
+    if health_factor <= 0.0 {
+        return 1.0;
+    }
+    (1.0 / health_factor).clamp(0.0, 1.0)
+}
+
+/// Risk models by `position_type`, so adapters declare what a position is and get the
+/// same scoring as every other protocol offering that kind of position
+#[derive(Debug, Clone)]
+pub struct RiskModelRegistry {
+    models: HashMap<String, RiskModel>,
+    /// Model for position types nothing is registered for
+    fallback: RiskModel,
+}
+
+impl Default for RiskModelRegistry {
+    fn default() -> Self {
+        let lending = RiskModel { stablecoin_cap: Some(0.15), ..RiskModel::flat(0.3) };
+        let mut registry = Self { models: HashMap::new(), fallback: RiskModel::flat(0.5) };
+
+        registry.register("supply", RiskModel { utilization_weight: 0.3, stablecoin_cap: Some(0.15), ..RiskModel::flat(0.2) });
+        registry.register("collateral", lending);
+        registry.register("stablecoin", RiskModel { stablecoin_cap: Some(0.15), ..RiskModel::flat(0.25) });
+        registry.register("borrow", RiskModel::flat(0.3));
+        registry.register("debt", RiskModel::flat(0.3));
+        registry.register("staking", RiskModel { depeg_weight: 0.4, ..lending });
+        // Restaked ETH is slashable by every service it secures, on top of validator risk
+        registry.register("restaking", RiskModel { base: 0.45, depeg_weight: 0.4, ..lending });
+        // Queued withdrawals are riskier the longer they remain locked
+        let withdrawal = RiskModel { lock_weight: 0.4, ..RiskModel::flat(0.2) };
+        registry.register("withdrawing", withdrawal);
+        registry.register("withdrawal", withdrawal);
+        registry.register("liquidity", RiskModel { correlated_discount: 0.25, ..RiskModel::flat(0.55) });
+        registry.register("yield_farming", RiskModel::flat(0.45));
+        registry.register("locked", RiskModel { lock_weight: 0.3, ..RiskModel::flat(0.4) });
+        registry.register("perp", RiskModel::flat(0.7));
+        registry
+    }
+}
+
+impl RiskModelRegistry {
+    pub fn global() -> &'static RiskModelRegistry {
+        static GLOBAL: OnceLock<RiskModelRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(Self::default)
+    }
+
+    /// Score `position_type` with `model`, replacing any existing one
+    pub fn register(&mut self, position_type: &str, model: RiskModel) {
+        self.models.insert(position_type.to_string(), model);
+    }
+
+    pub fn model(&self, position_type: &str) -> &RiskModel {
+        self.models.get(position_type).unwrap_or(&self.fallback)
+    }
+
+    pub fn score(&self, position_type: &str, inputs: &RiskInputs) -> f64 {
+        self.model(position_type).score(inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_models_score_what_each_type_is_exposed_to() {
+        let registry = RiskModelRegistry::default();
+        let score = |position_type: &str, inputs: RiskInputs| registry.score(position_type, &inputs);
+
+        assert_eq!(score("supply", RiskInputs::default()), 0.2);
+        assert!((score("supply", RiskInputs { utilization: Some(0.9), ..Default::default() }) - 0.35).abs() < 1e-12);
+        assert_eq!(score("supply", RiskInputs { stablecoin: true, ..Default::default() }), 0.15);
+
+        // Healthy borrows sit at the lending baseline, then track the health factor
+        assert_eq!(score("borrow", RiskInputs { health_factor: Some(f64::INFINITY), ..Default::default() }), 0.3);
+        assert!((score("borrow", RiskInputs { health_factor: Some(1.25), ..Default::default() }) - 0.8).abs() < 1e-12);
+        assert_eq!(score("perp", RiskInputs { health_factor: Some(0.0), ..Default::default() }), 1.0);
+
+        assert!((score("liquidity", RiskInputs { correlated: true, ..Default::default() }) - 0.3).abs() < 1e-12);
+        assert!((score("locked", RiskInputs { lock_fraction: 1.0, ..Default::default() }) - 0.7).abs() < 1e-12);
+        assert!((score("staking", RiskInputs { peg_deviation: -0.1, ..Default::default() }) - 0.7).abs() < 1e-12);
+        assert_eq!(score("staking", RiskInputs { peg_deviation: 0.1, ..Default::default() }), 0.3);
+        assert_eq!(score("restaking", RiskInputs::default()), 0.45);
+        assert_eq!(score("withdrawing", RiskInputs::default()), 0.2);
+        assert!((score("withdrawing", RiskInputs { lock_fraction: 0.5, ..Default::default() }) - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_unregistered_types_use_the_fallback_until_registered() {
+        let mut registry = RiskModelRegistry::default();
+        assert_eq!(registry.score("options", &RiskInputs::default()), 0.5);
+
+        registry.register("options", RiskModel::flat(0.8));
+        assert!((registry.score("options", &RiskInputs { premium: 0.1, ..Default::default() }) - 0.9).abs() < 1e-12);
+    }
+}
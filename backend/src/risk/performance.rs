@@ -0,0 +1,256 @@
+use serde::{Deserialize, Serialize};
+
+use crate::risk::var::{returns_from_prices, standard_deviation};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+/// Crypto markets trade every day of the year
+const TRADING_DAYS_PER_YEAR: f64 = 365.0;
+/// Daily closes required before volatility and Sharpe mean anything
+pub const MIN_DAILY_OBSERVATIONS: usize = 7;
+
+/// Realized performance over a recorded equity curve.
+///
+/// Returns are computed from portfolio value alone, so deposits and withdrawals
+/// show up as gains and losses.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PerformanceMetrics {
+    pub total_return_usd: f64,
+    pub total_return_percentage: f64,
+    /// Standard deviation of daily returns, annualized by √365
+    pub annualized_volatility: f64,
+    /// Annualized mean daily return over the risk-free rate, per unit of volatility
+    pub sharpe_ratio: f64,
+    /// Largest peak-to-trough decline as a fraction of the peak
+    pub max_drawdown: f64,
+    pub risk_free_rate: f64,
+    pub daily_observations: usize,
+    pub start_timestamp: u64,
+    pub end_timestamp: u64,
+}
+
+impl PerformanceMetrics {
+    /// Metrics for `(timestamp, value)` points sorted by time, or `None` when the curve
+    /// covers fewer than [`MIN_DAILY_OBSERVATIONS`] days
+    pub fn from_equity_curve(curve: &[(u64, f64)], risk_free_rate: f64) -> Option<Self> {
+        let daily = daily_closes(curve);
+        if daily.len() < MIN_DAILY_OBSERVATIONS {
+            return None;
+        }
+
+        let (start_timestamp, start_value) = *curve.first()?;
+        let (end_timestamp, end_value) = *curve.last()?;
+        let total_return_usd = end_value - start_value;
+        let total_return_percentage = if start_value > 0.0 {
+            total_return_usd / start_value * 100.0
+        } else {
+            0.0
+        };
+
+        let returns = returns_from_prices(&daily);
+        let mean_daily = returns.iter().sum::<f64>() / returns.len().max(1) as f64;
+        let annualized_volatility = standard_deviation(&returns) * TRADING_DAYS_PER_YEAR.sqrt();
+        let sharpe_ratio = if annualized_volatility > 0.0 {
+            (mean_daily * TRADING_DAYS_PER_YEAR - risk_free_rate) / annualized_volatility
+        } else {
+            0.0
+        };
+
+        Some(Self {
+            total_return_usd,
+            total_return_percentage,
+            annualized_volatility,
+            sharpe_ratio,
+            max_drawdown: max_drawdown(curve.iter().map(|(_, value)| *value)),
+            risk_free_rate,
+            daily_observations: daily.len(),
+            start_timestamp,
+            end_timestamp,
+        })
+    }
+}
+
+/// Asset a portfolio's returns are measured against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Benchmark {
+    Eth,
+    Btc,
+    #[default]
+    None,
+}
+
+impl Benchmark {
+    /// CoinGecko coin whose price history is the benchmark
+    pub fn coin_id(&self) -> Option<&'static str> {
+        match self {
+            Self::Eth => Some("ethereum"),
+            Self::Btc => Some("bitcoin"),
+            Self::None => None,
+        }
+    }
+}
+
+/// Portfolio returns regressed on a benchmark's over the days both have closes for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkComparison {
+    pub benchmark: Benchmark,
+    /// Sensitivity of daily portfolio returns to the benchmark's
+    pub benchmark_beta: f64,
+    /// Annualized excess return not explained by beta (Jensen's alpha)
+    pub alpha: f64,
+    pub benchmark_return_percentage: f64,
+    pub daily_observations: usize,
+}
+
+impl BenchmarkComparison {
+    /// Compare an equity curve with `(timestamp, price)` benchmark points, both sorted by
+    /// time. `None` with fewer than [`MIN_DAILY_OBSERVATIONS`] shared days or a flat benchmark.
+    pub fn from_curves(
+        curve: &[(u64, f64)],
+        benchmark_prices: &[(u64, f64)],
+        benchmark: Benchmark,
+        risk_free_rate: f64,
+    ) -> Option<Self> {
+        let benchmark_closes = daily_closes_by_day(benchmark_prices);
+        let (portfolio, benchmark_series): (Vec<f64>, Vec<f64>) = daily_closes_by_day(curve)
+            .into_iter()
+            .filter_map(|(day, value)| {
+                let index = benchmark_closes.binary_search_by_key(&day, |(d, _)| *d).ok()?;
+                Some((value, benchmark_closes[index].1))
+            })
+            .unzip();
+        if portfolio.len() < MIN_DAILY_OBSERVATIONS {
+            return None;
+        }
+
+        let portfolio_returns = returns_from_prices(&portfolio);
+        let benchmark_returns = returns_from_prices(&benchmark_series);
+        if portfolio_returns.len() != benchmark_returns.len() {
+            // A zero close breaks the pairing between the two return series
+            return None;
+        }
+
+        let mean = |returns: &[f64]| returns.iter().sum::<f64>() / returns.len().max(1) as f64;
+        let (mean_portfolio, mean_benchmark) = (mean(&portfolio_returns), mean(&benchmark_returns));
+        let covariance = portfolio_returns
+            .iter()
+            .zip(&benchmark_returns)
+            .map(|(p, b)| (p - mean_portfolio) * (b - mean_benchmark))
+            .sum::<f64>()
+            / benchmark_returns.len() as f64;
+        let benchmark_variance = benchmark_returns.iter().map(|b| (b - mean_benchmark).powi(2)).sum::<f64>()
+            / benchmark_returns.len() as f64;
+        if benchmark_variance <= 0.0 {
+            return None;
+        }
+
+        let benchmark_beta = covariance / benchmark_variance;
+        let daily_risk_free = risk_free_rate / TRADING_DAYS_PER_YEAR;
+        let alpha = ((mean_portfolio - daily_risk_free) - benchmark_beta * (mean_benchmark - daily_risk_free))
+            * TRADING_DAYS_PER_YEAR;
+        let (first, last) = (benchmark_series[0], benchmark_series[benchmark_series.len() - 1]);
+
+        Some(Self {
+            benchmark,
+            benchmark_beta,
+            alpha,
+            benchmark_return_percentage: if first > 0.0 { (last - first) / first * 100.0 } else { 0.0 },
+            daily_observations: portfolio.len(),
+        })
+    }
+}
+
+/// Last value recorded on each UTC day
+fn daily_closes(curve: &[(u64, f64)]) -> Vec<f64> {
+    daily_closes_by_day(curve).into_iter().map(|(_, close)| close).collect()
+}
+
+/// Last value recorded on each UTC day, keyed by days since the epoch
+fn daily_closes_by_day(curve: &[(u64, f64)]) -> Vec<(u64, f64)> {
+    let mut closes: Vec<(u64, f64)> = Vec::new();
+    for &(timestamp, value) in curve {
+        let day = timestamp / SECONDS_PER_DAY;
+        match closes.last_mut() {
+            Some((last_day, close)) if *last_day == day => *close = value,
+            _ => closes.push((day, value)),
+        }
+    }
+    closes
+}
+
+fn max_drawdown(values: impl Iterator<Item = f64>) -> f64 {
+    let mut peak = f64::MIN;
+    let mut drawdown: f64 = 0.0;
+    for value in values {
+        peak = peak.max(value);
+        if peak > 0.0 {
+            drawdown = drawdown.max((peak - value) / peak);
+        }
+    }
+    drawdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve(values: &[f64]) -> Vec<(u64, f64)> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(day, value)| (day as u64 * SECONDS_PER_DAY, *value))
+            .collect()
+    }
+
+    #[test]
+    fn test_short_history_is_insufficient() {
+        assert!(PerformanceMetrics::from_equity_curve(&curve(&[100.0; 6]), 0.0).is_none());
+    }
+
+    #[test]
+    fn test_intraday_points_collapse_to_daily_closes() {
+        let points = [(0, 100.0), (3_600, 90.0), (SECONDS_PER_DAY, 95.0)];
+        assert_eq!(daily_closes(&points), vec![90.0, 95.0]);
+    }
+
+    #[test]
+    fn test_return_and_drawdown() {
+        let metrics = PerformanceMetrics::from_equity_curve(
+            &curve(&[100.0, 110.0, 120.0, 90.0, 100.0, 105.0, 110.0]),
+            0.0,
+        )
+        .unwrap();
+
+        assert!((metrics.total_return_usd - 10.0).abs() < 1e-12);
+        assert!((metrics.total_return_percentage - 10.0).abs() < 1e-12);
+        assert!((metrics.max_drawdown - 0.25).abs() < 1e-12);
+        assert!(metrics.annualized_volatility > 0.0);
+    }
+
+    #[test]
+    fn test_portfolio_tracking_twice_the_benchmark_has_beta_two() {
+        let benchmark = curve(&[100.0, 102.0, 99.0, 101.0, 104.0, 103.0, 106.0, 108.0]);
+        let mut portfolio = vec![(0, 1_000.0)];
+        for window in benchmark.windows(2) {
+            let benchmark_return = window[1].1 / window[0].1 - 1.0;
+            let (_, last) = *portfolio.last().unwrap();
+            portfolio.push((window[1].0, last * (1.0 + 2.0 * benchmark_return)));
+        }
+
+        let comparison = BenchmarkComparison::from_curves(&portfolio, &benchmark, Benchmark::Eth, 0.0).unwrap();
+        assert!((comparison.benchmark_beta - 2.0).abs() < 1e-9);
+        assert!(comparison.alpha.abs() < 1e-9);
+        assert!((comparison.benchmark_return_percentage - 8.0).abs() < 1e-9);
+        assert_eq!(comparison.daily_observations, 8);
+
+        assert!(BenchmarkComparison::from_curves(&portfolio, &curve(&[100.0; 8]), Benchmark::Eth, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_flat_curve_has_no_volatility_or_sharpe() {
+        let metrics = PerformanceMetrics::from_equity_curve(&curve(&[100.0; 7]), 0.04).unwrap();
+        assert_eq!(metrics.annualized_volatility, 0.0);
+        assert_eq!(metrics.sharpe_ratio, 0.0);
+        assert_eq!(metrics.max_drawdown, 0.0);
+    }
+}
@@ -0,0 +1,128 @@
+use serde::Serialize;
+
+use crate::adapters::traits::Position;
+use crate::risk::aggregation::{position_risk_score, RiskAggregation};
+use crate::risk::decomposition::{concentration_warnings, ConcentrationLimits, ConcentrationWarning};
+use crate::services::monitoring::{self, AlertLevel};
+use crate::services::HealthFactorThresholds;
+
+/// Assessment of a position that has not been added to monitoring
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionPreview {
+    /// Risk score in [0, 1] the position would be monitored with
+    pub risk_score: f64,
+    pub health_factor: Option<f64>,
+    pub health_alert: AlertLevel,
+    /// Value-weighted risk of the wallet's current positions, without and with this one
+    pub portfolio_risk_before: f64,
+    pub portfolio_risk_after: f64,
+    /// Concentration limits the position would break or add to
+    pub concentration_warnings: Vec<ConcentrationWarning>,
+    pub warnings: Vec<String>,
+}
+
+/// Score `candidate` as if it were added next to `existing` positions. A
+/// `risk_score` in the candidate's metadata is kept; otherwise one is derived from
+/// its type and health factor.
+pub fn preview_position(
+    candidate: &Position,
+    existing: &[Position],
+    thresholds: &HealthFactorThresholds,
+    limits: &ConcentrationLimits,
+) -> PositionPreview {
+    let health_factor = monitoring::health_factor(candidate);
+    let risk_score = position_risk_score(candidate);
+
+    let mut scored = candidate.clone();
+    if let Some(metadata) = scored.metadata.as_object_mut() {
+        metadata.insert("risk_score".to_string(), serde_json::json!(risk_score));
+    }
+
+    let mut combined = existing.to_vec();
+    combined.push(scored);
+
+    let health_alert = health_factor.map_or(AlertLevel::Healthy, |hf| thresholds.level(hf));
+    let concentration_warnings: Vec<ConcentrationWarning> = concentration_warnings(&combined, limits)
+        .into_iter()
+        .filter(|warning| {
+            warning.position_id.as_deref() == Some(candidate.id.as_str())
+                || (warning.position_id.is_none() && warning.entity == candidate.protocol)
+        })
+        .collect();
+
+    let mut warnings = Vec::new();
+    if let Some(health_factor) = health_factor {
+        match health_alert {
+            AlertLevel::Critical => warnings.push(format!(
+                "Health factor {:.2} is below the critical threshold of {:.2}; this position is close to liquidation",
+                health_factor, thresholds.critical
+            )),
+            AlertLevel::Warning => warnings.push(format!(
+                "Health factor {:.2} is below the warning threshold of {:.2}",
+                health_factor, thresholds.warning
+            )),
+            AlertLevel::Healthy => {}
+        }
+    } else if matches!(candidate.position_type.as_str(), "borrow" | "debt") {
+        warnings.push("No health_factor given; liquidation risk can't be assessed".to_string());
+    }
+    if candidate.metadata.get("transferable").and_then(|v| v.as_bool()) == Some(false) {
+        warnings.push("Position can't be exited until it unlocks".to_string());
+    }
+    warnings.extend(concentration_warnings.iter().map(|warning| warning.message.clone()));
+
+    PositionPreview {
+        risk_score,
+        health_factor,
+        health_alert,
+        portfolio_risk_before: RiskAggregation::ValueWeighted.aggregate(existing),
+        portfolio_risk_after: RiskAggregation::ValueWeighted.aggregate(&combined),
+        concentration_warnings,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(id: &str, position_type: &str, value_usd: f64, metadata: serde_json::Value) -> Position {
+        Position {
+            id: id.to_string(),
+            protocol: "test".to_string(),
+            position_type: position_type.to_string(),
+            pair: "WETH/USDC".to_string(),
+            value_usd,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata,
+            last_updated: 0,
+        }
+    }
+
+    #[test]
+    fn test_low_health_factor_raises_risk_and_warns() {
+        let thresholds = HealthFactorThresholds { warning: 1.5, critical: 1.2 };
+        let candidate = position("new", "borrow", 1_000.0, serde_json::json!({ "health_factor": 1.1 }));
+
+        let preview = preview_position(&candidate, &[], &thresholds, &ConcentrationLimits { max_position_weight: 1.0, max_protocol_weight: 1.0 });
+
+        assert!((preview.risk_score - 1.0 / 1.1).abs() < 1e-9);
+        assert_eq!(preview.health_alert, AlertLevel::Critical);
+        assert_eq!(preview.warnings.len(), 1);
+        assert_eq!(preview.portfolio_risk_before, 0.0);
+    }
+
+    #[test]
+    fn test_preview_flags_only_the_candidates_concentration() {
+        let existing = vec![position("old", "supply", 1_000.0, serde_json::json!({ "risk_score": 0.2 }))];
+        let candidate = position("new", "staking", 9_000.0, serde_json::json!({}));
+
+        let preview = preview_position(&candidate, &existing, &HealthFactorThresholds::default(), &ConcentrationLimits::default());
+
+        assert_eq!(preview.risk_score, 0.3);
+        assert_eq!(preview.concentration_warnings.len(), 2);
+        assert!(preview.concentration_warnings.iter().all(|w| w.position_id.as_deref() != Some("old")));
+        assert!((preview.portfolio_risk_after - 0.29).abs() < 1e-9);
+    }
+}
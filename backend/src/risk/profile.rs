@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+use crate::services::HealthFactorThresholds;
+
+/// A user's risk tolerance, which rescales alert thresholds and reported risk scores
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskProfile {
+    /// Warned early, while there is still a wide buffer to liquidation
+    Conservative,
+    /// The service-wide `HEALTH_FACTOR_*` thresholds
+    #[default]
+    Moderate,
+    /// Only warned once liquidation is close
+    Aggressive,
+}
+
+impl RiskProfile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskProfile::Conservative => "conservative",
+            RiskProfile::Moderate => "moderate",
+            RiskProfile::Aggressive => "aggressive",
+        }
+    }
+
+    /// Health factor thresholds for alerts under this profile
+    pub fn health_thresholds(&self) -> HealthFactorThresholds {
+        match self {
+            RiskProfile::Conservative => HealthFactorThresholds { warning: 2.0, critical: 1.5 },
+            RiskProfile::Moderate => HealthFactorThresholds::from_env(),
+            RiskProfile::Aggressive => HealthFactorThresholds { warning: 1.2, critical: 1.05 },
+        }
+    }
+
+    /// Weight applied to risk scores: the same portfolio reads as riskier to a
+    /// conservative user than to an aggressive one
+    pub fn risk_weight(&self) -> f64 {
+        match self {
+            RiskProfile::Conservative => 1.25,
+            RiskProfile::Moderate => 1.0,
+            RiskProfile::Aggressive => 0.8,
+        }
+    }
+
+    /// Risk score in [0, 1] rescaled for this profile
+    pub fn scale_score(&self, score: f64) -> f64 {
+        (score * self.risk_weight()).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::monitoring::AlertLevel;
+
+    #[test]
+    fn test_profiles_warn_at_different_health_factors() {
+        assert_eq!(RiskProfile::Conservative.health_thresholds().level(1.9), AlertLevel::Warning);
+        assert_eq!(RiskProfile::Aggressive.health_thresholds().level(1.9), AlertLevel::Healthy);
+        assert_eq!(RiskProfile::Aggressive.health_thresholds().level(1.15), AlertLevel::Warning);
+    }
+
+    #[test]
+    fn test_scaled_scores_stay_in_range() {
+        assert!((RiskProfile::Conservative.scale_score(0.4) - 0.5).abs() < 1e-12);
+        assert_eq!(RiskProfile::Conservative.scale_score(0.9), 1.0);
+        assert!((RiskProfile::Aggressive.scale_score(0.5) - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_profile_parses_from_snake_case() {
+        let parsed: RiskProfile = serde_json::from_str("\"aggressive\"").unwrap();
+        assert_eq!(parsed, RiskProfile::Aggressive);
+        assert_eq!(RiskProfile::default(), RiskProfile::Moderate);
+    }
+}
@@ -0,0 +1,294 @@
+use alloy::primitives::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::adapters::traits::Position;
+
+/// Tick bounds supported by Uniswap V3
+pub const MIN_TICK: i32 = -887272;
+pub const MAX_TICK: i32 = 887272;
+
+/// In-range liquidity of a concentrated liquidity pool at its current tick
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveLiquidity {
+    /// Pool `liquidity()`: liquidity active at the current tick
+    pub liquidity: u128,
+    /// Pool `slot0().tick`
+    pub tick: i32,
+    pub token1_price_usd: f64,
+    pub token1_decimals: u8,
+}
+
+/// Pool state needed to estimate the price impact of exiting a position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolSnapshot {
+    pub protocol: String,
+    pub tvl_usd: f64,
+    pub active_liquidity: Option<ActiveLiquidity>,
+}
+
+impl PoolSnapshot {
+    /// Pool state reported by an adapter in position metadata (`pool_tvl_usd`, and for
+    /// concentrated pools `pool_liquidity`, `pool_tick`, `token1_price_usd`, `token1_decimals`)
+    pub fn from_position(position: &Position) -> Option<Self> {
+        let metadata = &position.metadata;
+        let tvl_usd = metadata.get("pool_tvl_usd").and_then(|v| v.as_f64())?;
+
+        let active_liquidity = (|| {
+            let liquidity = metadata.get("pool_liquidity")?;
+            let liquidity = liquidity
+                .as_str()
+                .and_then(|s| s.parse().ok())
+                .or_else(|| liquidity.as_u64().map(u128::from))?;
+
+            Some(ActiveLiquidity {
+                liquidity,
+                tick: metadata.get("pool_tick")?.as_i64()? as i32,
+                token1_price_usd: metadata.get("token1_price_usd")?.as_f64()?,
+                token1_decimals: metadata.get("token1_decimals")?.as_u64()? as u8,
+            })
+        })();
+
+        Some(Self {
+            protocol: position.protocol.clone(),
+            tvl_usd,
+            active_liquidity,
+        })
+    }
+}
+
+/// Estimates the fraction of output lost to price impact when selling into a pool
+pub trait SlippageModel: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Slippage in [0, 1] for selling `trade_size_usd`, or `None` if the pool
+    /// snapshot lacks the data this model needs
+    fn estimate_slippage(&self, trade_size_usd: f64, pool: &PoolSnapshot) -> Option<f64>;
+}
+
+/// x·y = k pools (Uniswap V2 and forks): half of TVL sits on the input side
+pub struct ConstantProductModel;
+
+impl SlippageModel for ConstantProductModel {
+    fn name(&self) -> &'static str {
+        "constant_product"
+    }
+
+    fn estimate_slippage(&self, trade_size_usd: f64, pool: &PoolSnapshot) -> Option<f64> {
+        let reserve_in_usd = pool.tvl_usd / 2.0;
+        if reserve_in_usd <= 0.0 {
+            return None;
+        }
+
+        let trade = trade_size_usd.max(0.0);
+        Some(trade / (reserve_in_usd + trade))
+    }
+}
+
+/// Uniswap V3 pools: within the current tick range the pool behaves like a constant
+/// product pool on virtual reserves derived from the active liquidity, which can be far
+/// deeper (or shallower) than TVL suggests. Assumes the trade stays in the current range.
+pub struct ConcentratedLiquidityModel;
+
+impl SlippageModel for ConcentratedLiquidityModel {
+    fn name(&self) -> &'static str {
+        "concentrated_liquidity"
+    }
+
+    fn estimate_slippage(&self, trade_size_usd: f64, pool: &PoolSnapshot) -> Option<f64> {
+        let active = pool.active_liquidity.as_ref()?;
+        if active.liquidity == 0 || active.token1_price_usd <= 0.0 {
+            return None;
+        }
+
+        // Selling token1: √P' = √P + Δy / L, and output falls short of spot by 1 - √P/√P'
+        let sqrt_price = sqrt_ratio_to_f64(get_sqrt_ratio_at_tick(active.tick)?);
+        let liquidity = active.liquidity as f64;
+        let amount_in = trade_size_usd.max(0.0) / active.token1_price_usd
+            * 10f64.powi(active.token1_decimals as i32);
+
+        let next_sqrt_price = sqrt_price + amount_in / liquidity;
+        Some(1.0 - sqrt_price / next_sqrt_price)
+    }
+}
+
+/// Slippage model matching a pool's protocol
+pub fn model_for_protocol(protocol: &str) -> &'static dyn SlippageModel {
+    match protocol {
+        "uniswap_v3" => &ConcentratedLiquidityModel,
+        _ => &ConstantProductModel,
+    }
+}
+
+/// Estimate slippage with the protocol's model, falling back to constant product
+/// when the pool snapshot lacks concentrated liquidity data
+pub fn estimate_slippage(trade_size_usd: f64, pool: &PoolSnapshot) -> Option<f64> {
+    model_for_protocol(&pool.protocol)
+        .estimate_slippage(trade_size_usd, pool)
+        .or_else(|| ConstantProductModel.estimate_slippage(trade_size_usd, pool))
+}
+
+/// Worst slippage across positions if each were exited in full; `None` when no
+/// position reports pool state
+pub fn max_estimated_slippage(positions: &[Position]) -> Option<f64> {
+    positions
+        .iter()
+        .filter_map(|position| {
+            let pool = PoolSnapshot::from_position(position)?;
+            estimate_slippage(position.value_usd.abs(), &pool)
+        })
+        .reduce(f64::max)
+}
+
+/// Share of portfolio value locked in positions that can't currently be exited, such
+/// as withdrawal-queue entries (`metadata.transferable == false`)
+pub fn illiquid_value_ratio(positions: &[Position]) -> f64 {
+    let total: f64 = positions.iter().map(|p| p.value_usd.abs()).sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+
+    let locked: f64 = positions
+        .iter()
+        .filter(|p| p.metadata.get("transferable").and_then(|v| v.as_bool()) == Some(false))
+        .map(|p| p.value_usd.abs())
+        .sum();
+
+    locked / total
+}
+
+/// `TickMath.getSqrtRatioAtTick`: √(1.0001^tick) as a Q64.96, or `None` outside the tick bounds
+pub fn get_sqrt_ratio_at_tick(tick: i32) -> Option<U256> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return None;
+    }
+
+    const MAGIC: [(u32, u128); 19] = [
+        (0x2, 0xfff97272373d413259a46990580e213a),
+        (0x4, 0xfff2e50f5f656932ef12357cf3c7fdcc),
+        (0x8, 0xffe5caca7e10e4e61c3624eaa0941cd0),
+        (0x10, 0xffcb9843d60f6159c9db58835c926644),
+        (0x20, 0xff973b41fa98c081472e6896dfb254c0),
+        (0x40, 0xff2ea16466c96a3843ec78b326b52861),
+        (0x80, 0xfe5dee046a99a2a811c461f1969c3053),
+        (0x100, 0xfcbe86c7900a88aedcffc83b479aa3a4),
+        (0x200, 0xf987a7253ac413176f2b074cf7815e54),
+        (0x400, 0xf3392b0822b70005940c7a398e4b70f3),
+        (0x800, 0xe7159475a2c29b7443b29c7fa6e889d9),
+        (0x1000, 0xd097f3bdfd2022b8845ad8f792aa5825),
+        (0x2000, 0xa9f746462d870fdf8a65dc1f90e061e5),
+        (0x4000, 0x70d869a156d2a1b890bb3df62baf32f7),
+        (0x8000, 0x31be135f97d08fd981231505542fcfa6),
+        (0x10000, 0x9aa508b5b7a84e1c677de54f3e99bc9),
+        (0x20000, 0x5d6af8dedb81196699c329225ee604),
+        (0x40000, 0x2216e584f5fa1ea926041bedfe98),
+        (0x80000, 0x48a170391f7dc42444e8fa2),
+    ];
+
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = if abs_tick & 0x1 != 0 {
+        U256::from(0xfffcb933bd6fad37aa2d162d1a594001u128)
+    } else {
+        U256::from(1u8) << 128
+    };
+
+    for (bit, factor) in MAGIC {
+        if abs_tick & bit != 0 {
+            ratio = (ratio * U256::from(factor)) >> 128;
+        }
+    }
+
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    // Q128.128 -> Q64.96, rounding up
+    let remainder = ratio % (U256::from(1u8) << 32);
+    Some((ratio >> 32) + if remainder.is_zero() { U256::ZERO } else { U256::from(1u8) })
+}
+
+fn sqrt_ratio_to_f64(sqrt_ratio_x96: U256) -> f64 {
+    let raw: f64 = sqrt_ratio_x96.to_string().parse().unwrap_or(0.0);
+    raw / 2f64.powi(96)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v3_pool(liquidity: u128) -> PoolSnapshot {
+        PoolSnapshot {
+            protocol: "uniswap_v3".to_string(),
+            tvl_usd: 1_000_000.0,
+            active_liquidity: Some(ActiveLiquidity {
+                liquidity,
+                tick: 0,
+                token1_price_usd: 1.0,
+                token1_decimals: 6,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_sqrt_ratio_at_tick_matches_tick_math() {
+        assert_eq!(get_sqrt_ratio_at_tick(0), Some(U256::from(1u8) << 96));
+        assert_eq!(get_sqrt_ratio_at_tick(MIN_TICK), Some(U256::from(4295128739u64)));
+        assert_eq!(
+            get_sqrt_ratio_at_tick(MAX_TICK).unwrap().to_string(),
+            "1461446703485210103287273052203988822378723970342"
+        );
+        assert_eq!(get_sqrt_ratio_at_tick(MAX_TICK + 1), None);
+    }
+
+    #[test]
+    fn test_constant_product_slippage() {
+        let pool = PoolSnapshot {
+            protocol: "uniswap_v2".to_string(),
+            tvl_usd: 2_000_000.0,
+            active_liquidity: None,
+        };
+        let slippage = estimate_slippage(1_000_000.0, &pool).unwrap();
+        assert!((slippage - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_concentrated_liquidity_uses_active_liquidity() {
+        // At tick 0 with 6-decimal token1, virtual token1 reserve is L / 1e6 USD
+        let deep = estimate_slippage(10_000.0, &v3_pool(10u128.pow(13))).unwrap();
+        let shallow = estimate_slippage(10_000.0, &v3_pool(10u128.pow(10))).unwrap();
+
+        assert!((deep - 10_000.0 / 10_010_000.0).abs() < 1e-9);
+        assert!(shallow > deep);
+        // TVL-based estimate would be the same for both pools
+        assert!(shallow > ConstantProductModel.estimate_slippage(10_000.0, &v3_pool(0)).unwrap());
+    }
+
+    #[test]
+    fn test_concentrated_falls_back_without_active_liquidity() {
+        let mut pool = v3_pool(0);
+        pool.active_liquidity = None;
+        assert_eq!(model_for_protocol("uniswap_v3").name(), "concentrated_liquidity");
+        assert!(estimate_slippage(1_000.0, &pool).is_some());
+    }
+
+    #[test]
+    fn test_illiquid_value_ratio_counts_non_transferable_positions() {
+        let position = |value_usd: f64, metadata: serde_json::Value| Position {
+            id: String::new(),
+            protocol: "ether_fi".to_string(),
+            position_type: "staking".to_string(),
+            pair: "eETH/ETH".to_string(),
+            value_usd,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata,
+            last_updated: 0,
+        };
+        let positions = vec![
+            position(3000.0, serde_json::json!({})),
+            position(1000.0, serde_json::json!({ "transferable": false })),
+        ];
+
+        assert!((illiquid_value_ratio(&positions) - 0.25).abs() < 1e-12);
+        assert_eq!(illiquid_value_ratio(&[]), 0.0);
+    }
+}
@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+
+/// One-sided z-scores for the supported confidence levels
+const Z_95: f64 = 1.645;
+const Z_99: f64 = 2.326;
+
+/// Value-at-Risk and expected shortfall in USD, computed both parametrically
+/// (normal assumption) and from the empirical return distribution
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RiskMetrics {
+    pub portfolio_value_usd: f64,
+    pub horizon_days: u32,
+    pub daily_volatility: f64,
+    pub var_95_parametric_usd: f64,
+    pub var_99_parametric_usd: f64,
+    pub var_95_historical_usd: f64,
+    pub var_99_historical_usd: f64,
+    pub cvar_95_usd: f64,
+    pub cvar_99_usd: f64,
+    pub observations: usize,
+}
+
+impl RiskMetrics {
+    /// Build metrics for a portfolio from a series of daily simple returns.
+    /// One-day historical figures are scaled to the horizon by √t, like the parametric ones.
+    pub fn from_returns(portfolio_value_usd: f64, returns: &[f64], horizon_days: u32) -> Self {
+        let daily_volatility = standard_deviation(returns);
+        let historical_value = portfolio_value_usd * (horizon_days.max(1) as f64).sqrt();
+
+        Self {
+            portfolio_value_usd,
+            horizon_days,
+            daily_volatility,
+            var_95_parametric_usd: portfolio_value_usd * calculate_var_parametric(daily_volatility, 0.95, horizon_days),
+            var_99_parametric_usd: portfolio_value_usd * calculate_var_parametric(daily_volatility, 0.99, horizon_days),
+            var_95_historical_usd: historical_value * calculate_var_historical(returns, 0.95),
+            var_99_historical_usd: historical_value * calculate_var_historical(returns, 0.99),
+            cvar_95_usd: historical_value * calculate_cvar(returns, 0.95),
+            cvar_99_usd: historical_value * calculate_cvar(returns, 0.99),
+            observations: returns.len(),
+        }
+    }
+}
+
+/// Parametric VaR as a fraction of value: volatility × z-score × √t
+pub fn calculate_var_parametric(daily_volatility: f64, confidence: f64, horizon_days: u32) -> f64 {
+    let z = if confidence >= 0.99 { Z_99 } else { Z_95 };
+    daily_volatility * z * (horizon_days.max(1) as f64).sqrt()
+}
+
+/// Historical-simulation VaR as a fraction of value: the empirical loss quantile
+/// of `returns` at `confidence` (e.g. 0.95). Zero when there is no history.
+pub fn calculate_var_historical(returns: &[f64], confidence: f64) -> f64 {
+    let sorted = sorted_returns(returns);
+    if sorted.is_empty() {
+        return 0.0;
+    }
+
+    let index = tail_index(sorted.len(), confidence);
+    (-sorted[index]).max(0.0)
+}
+
+/// Expected shortfall (CVaR) as a fraction of value: the average loss in the
+/// tail at or beyond the historical VaR quantile
+pub fn calculate_cvar(returns: &[f64], confidence: f64) -> f64 {
+    let sorted = sorted_returns(returns);
+    if sorted.is_empty() {
+        return 0.0;
+    }
+
+    let tail = &sorted[..=tail_index(sorted.len(), confidence)];
+    let mean_tail_return = tail.iter().sum::<f64>() / tail.len() as f64;
+    (-mean_tail_return).max(0.0)
+}
+
+/// Simple returns between consecutive prices
+pub fn returns_from_prices(prices: &[f64]) -> Vec<f64> {
+    prices
+        .windows(2)
+        .filter(|w| w[0] > 0.0)
+        .map(|w| w[1] / w[0] - 1.0)
+        .collect()
+}
+
+/// Daily returns of a portfolio holding each return series at its weight, e.g. its
+/// share of portfolio value. Series are aligned on their latest day and the result is
+/// as long as the shortest one.
+pub fn weighted_returns(series: &[(f64, Vec<f64>)]) -> Vec<f64> {
+    let len = series.iter().map(|(_, returns)| returns.len()).min().unwrap_or(0);
+    (0..len)
+        .map(|day| {
+            series
+                .iter()
+                .map(|(weight, returns)| weight * returns[returns.len() - len + day])
+                .sum()
+        })
+        .collect()
+}
+
+fn sorted_returns(returns: &[f64]) -> Vec<f64> {
+    let mut sorted: Vec<f64> = returns.iter().copied().filter(|r| r.is_finite()).collect();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    sorted
+}
+
+fn tail_index(len: usize, confidence: f64) -> usize {
+    let index = ((1.0 - confidence) * len as f64).floor() as usize;
+    index.min(len - 1)
+}
+
+/// Sample standard deviation
+pub(crate) fn standard_deviation(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    variance.sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_returns() -> Vec<f64> {
+        // 100 observations: -0.10, -0.09, ..., -0.01, then 90 small gains
+        let mut returns: Vec<f64> = (1..=10).map(|i| -(i as f64) / 100.0).collect();
+        returns.extend([0.01; 90]);
+        returns
+    }
+
+    #[test]
+    fn test_historical_var_picks_empirical_quantile() {
+        let returns = sample_returns();
+        assert!((calculate_var_historical(&returns, 0.95) - 0.05).abs() < 1e-12);
+        assert!((calculate_var_historical(&returns, 0.99) - 0.09).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_cvar_exceeds_var() {
+        let returns = sample_returns();
+        let var = calculate_var_historical(&returns, 0.95);
+        let cvar = calculate_cvar(&returns, 0.95);
+        // Average of -0.10..=-0.05
+        assert!((cvar - 0.075).abs() < 1e-12);
+        assert!(cvar >= var);
+    }
+
+    #[test]
+    fn test_parametric_var_scales_with_sqrt_time() {
+        let one_day = calculate_var_parametric(0.02, 0.95, 1);
+        let four_day = calculate_var_parametric(0.02, 0.95, 4);
+        assert!((four_day - 2.0 * one_day).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_empty_history_has_no_var() {
+        assert_eq!(calculate_var_historical(&[], 0.95), 0.0);
+        assert_eq!(calculate_cvar(&[], 0.99), 0.0);
+    }
+
+    #[test]
+    fn test_weighted_returns_align_on_latest_day() {
+        let eth = vec![0.5, 0.10, -0.20];
+        let usdc = vec![0.0, 0.0];
+        let returns = weighted_returns(&[(0.5, eth), (0.5, usdc)]);
+        assert_eq!(returns, vec![0.05, -0.10]);
+        assert!(weighted_returns(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_returns_from_prices() {
+        let returns = returns_from_prices(&[100.0, 110.0, 99.0]);
+        assert!((returns[0] - 0.1).abs() < 1e-12);
+        assert!((returns[1] + 0.1).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,396 @@
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::adapters::filter::protocol_key;
+use crate::adapters::traits::{position_id, Position};
+use crate::risk::aggregation::position_risk_score;
+use crate::risk::decomposition::{ConcentrationLimits, ConcentrationWarning, RiskDecomposition};
+use crate::risk::liquidation::simulate_portfolio;
+use crate::services::monitoring::AlertLevel;
+use crate::services::position_aggregator::{self, position_apy, underlying_tokens};
+use crate::services::HealthFactorThresholds;
+
+/// Share of a new supply counted toward the health factor when the request doesn't say
+const DEFAULT_LIQUIDATION_THRESHOLD: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionSide {
+    Supply,
+    Borrow,
+}
+
+impl PositionSide {
+    pub fn position_type(&self) -> &'static str {
+        match self {
+            Self::Supply => "supply",
+            Self::Borrow => "borrow",
+        }
+    }
+}
+
+/// A supply or borrow the wallet is considering
+#[derive(Debug, Clone, Deserialize)]
+pub struct HypotheticalPosition {
+    pub protocol: String,
+    /// Token symbol supplied or borrowed
+    pub asset: String,
+    pub size_usd: f64,
+    pub side: PositionSide,
+    /// APY in percent earned or paid; defaults to that of the wallet's existing position
+    /// in the same asset on the same protocol
+    pub apy: Option<f64>,
+    /// Share of a supply that counts as collateral, in (0, 1]
+    pub liquidation_threshold: Option<f64>,
+}
+
+/// Portfolio-level risk measures a what-if compares
+#[derive(Debug, Clone, Serialize)]
+pub struct PortfolioRiskState {
+    /// Supplied minus borrowed value
+    pub equity_usd: f64,
+    pub decomposition: RiskDecomposition,
+    pub net_apy: Option<f64>,
+    pub leverage_ratio: Option<f64>,
+    /// Lowest health factor across the wallet's borrows
+    pub min_health_factor: Option<f64>,
+    /// Smallest drop in all collateral, in percent, that liquidates one of the borrows
+    pub liquidation_buffer_pct: Option<f64>,
+}
+
+impl PortfolioRiskState {
+    pub fn of(positions: &[Position], limits: &ConcentrationLimits) -> Self {
+        let aggregated = position_aggregator::aggregate(positions.to_vec());
+        let simulations = simulate_portfolio(positions);
+        let min = |values: Vec<f64>| values.into_iter().filter(|v| v.is_finite()).min_by(f64::total_cmp);
+
+        Self {
+            equity_usd: aggregated.total_supplied_usd - aggregated.total_borrowed_usd,
+            decomposition: RiskDecomposition::from_positions_with_limits(positions, limits),
+            net_apy: aggregated.portfolio_net_apy,
+            leverage_ratio: aggregated.leverage_ratio,
+            min_health_factor: min(simulations.iter().map(|s| s.health_factor).collect()),
+            liquidation_buffer_pct: min(simulations.iter().map(|s| s.all_collateral_drop_pct).collect()),
+        }
+    }
+}
+
+/// `after - before` of each measure; `None` when either side has no value
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskDelta {
+    pub overall_risk: f64,
+    pub concentration: f64,
+    pub liquidity: f64,
+    pub net_apy: Option<f64>,
+    pub min_health_factor: Option<f64>,
+    pub liquidation_buffer_pct: Option<f64>,
+}
+
+impl RiskDelta {
+    fn between(before: &PortfolioRiskState, after: &PortfolioRiskState) -> Self {
+        let diff = |before: Option<f64>, after: Option<f64>| Some(after? - before?);
+        Self {
+            overall_risk: after.decomposition.overall_risk - before.decomposition.overall_risk,
+            concentration: after.decomposition.concentration.score - before.decomposition.concentration.score,
+            liquidity: after.decomposition.liquidity.score - before.decomposition.liquidity.score,
+            net_apy: diff(before.net_apy, after.net_apy),
+            min_health_factor: diff(before.min_health_factor, after.min_health_factor),
+            liquidation_buffer_pct: diff(before.liquidation_buffer_pct, after.liquidation_buffer_pct),
+        }
+    }
+}
+
+/// Portfolio risk before and after adding a hypothetical position
+#[derive(Debug, Clone, Serialize)]
+pub struct WhatIfAnalysis {
+    /// The hypothetical position as it would be reported
+    pub position: Position,
+    /// Health factor of the protocol account the position would join
+    pub health_factor_before: Option<f64>,
+    pub health_factor_after: Option<f64>,
+    pub before: PortfolioRiskState,
+    pub after: PortfolioRiskState,
+    pub delta: RiskDelta,
+    /// Concentration limits broken only once the position is added
+    pub new_concentration_warnings: Vec<ConcentrationWarning>,
+    pub warnings: Vec<String>,
+}
+
+/// Recompute `owner`'s portfolio risk with `hypothetical` added to `existing`.
+///
+/// On protocols that pool collateral (Aave V3 and forks, Compound), the position joins
+/// the account the wallet already holds there: a supply adds collateral at its
+/// liquidation threshold and a borrow adds debt, and the account's health factor is
+/// updated on every position of that protocol. A position matching an existing one's
+/// ID grows it rather than being added alongside.
+pub fn what_if(
+    owner: Address,
+    existing: &[Position],
+    hypothetical: &HypotheticalPosition,
+    thresholds: &HealthFactorThresholds,
+    limits: &ConcentrationLimits,
+) -> WhatIfAnalysis {
+    let protocol = protocol_key(&hypothetical.protocol);
+    let asset = hypothetical.asset.trim().to_uppercase();
+    let size_usd = hypothetical.size_usd.abs();
+    let same_protocol = |position: &&Position| protocol_key(&position.protocol) == protocol;
+
+    let (weighted_collateral, debt_usd) = account_balance(existing.iter().filter(same_protocol));
+    let liquidation_threshold = hypothetical
+        .liquidation_threshold
+        .unwrap_or(DEFAULT_LIQUIDATION_THRESHOLD)
+        .clamp(0.0, 1.0);
+    let (weighted_after, debt_after) = match hypothetical.side {
+        PositionSide::Supply => (weighted_collateral + size_usd * liquidation_threshold, debt_usd),
+        PositionSide::Borrow => (weighted_collateral, debt_usd + size_usd),
+    };
+    let health_factor_before = (debt_usd > 0.0).then(|| weighted_collateral / debt_usd);
+    let health_factor_after = (debt_after > 0.0).then(|| weighted_after / debt_after);
+
+    let chain_id = existing
+        .iter()
+        .filter(same_protocol)
+        .find_map(|p| p.metadata.get("chain_id").and_then(|v| v.as_u64()))
+        .unwrap_or(1);
+    let position_type = hypothetical.side.position_type();
+    let apy = hypothetical.apy.or_else(|| {
+        existing
+            .iter()
+            .filter(same_protocol)
+            .filter(|p| p.position_type == position_type || (position_type == "supply" && p.position_type == "collateral"))
+            .filter(|p| underlying_tokens(p).first() == Some(&asset))
+            .find_map(position_apy)
+    });
+    let apy_key = match hypothetical.side {
+        PositionSide::Supply => "supply_apy",
+        PositionSide::Borrow => "borrow_apy",
+    };
+
+    let mut position = Position {
+        id: position_id(&protocol, chain_id, position_type, &asset, owner),
+        protocol: protocol.clone(),
+        position_type: position_type.to_string(),
+        pair: asset.clone(),
+        value_usd: match hypothetical.side {
+            PositionSide::Supply => size_usd,
+            PositionSide::Borrow => -size_usd,
+        },
+        pnl_usd: 0.0,
+        pnl_percentage: 0.0,
+        metadata: serde_json::json!({
+            "underlying_asset": asset,
+            "chain_id": chain_id,
+            "hypothetical": true,
+            "position_details": {
+                apy_key: apy,
+                "health_factor": health_factor_after.filter(|_| hypothetical.side == PositionSide::Borrow),
+                "liquidation_threshold": liquidation_threshold,
+            },
+        }),
+        last_updated: chrono::Utc::now().timestamp() as u64,
+    };
+    let risk_score = position_risk_score(&position);
+    position.metadata["risk_score"] = serde_json::json!(risk_score);
+
+    let mut combined = existing.to_vec();
+    for held in combined.iter_mut().filter(|p| protocol_key(&p.protocol) == protocol) {
+        apply_account_change(held, hypothetical, &asset, liquidation_threshold, health_factor_after, debt_after);
+    }
+    // Canonical IDs match when the wallet already holds this asset on the protocol
+    let matching = combined.iter().position(|held| held.id.to_lowercase() == position.id);
+    match matching {
+        Some(index) => combined[index].value_usd += position.value_usd,
+        None => combined.push(position.clone()),
+    }
+
+    let before = PortfolioRiskState::of(existing, limits);
+    let after = PortfolioRiskState::of(&combined, limits);
+    let new_concentration_warnings: Vec<ConcentrationWarning> = after
+        .decomposition
+        .concentration_warnings
+        .iter()
+        .filter(|warning| {
+            !before.decomposition.concentration_warnings
+                .iter()
+                .any(|old| old.scope == warning.scope && old.entity == warning.entity)
+        })
+        .cloned()
+        .collect();
+
+    let mut warnings = Vec::new();
+    if hypothetical.side == PositionSide::Borrow && weighted_collateral <= 0.0 {
+        warnings.push(format!("No collateral on {}; this borrow can't be opened without supplying some first", protocol));
+    } else if let Some(health_factor) = health_factor_after {
+        match thresholds.level(health_factor) {
+            _ if health_factor < 1.0 => warnings.push(format!(
+                "Health factor would be {:.2}; the account would be liquidatable",
+                health_factor
+            )),
+            AlertLevel::Critical => warnings.push(format!(
+                "Health factor would fall to {:.2}, below the critical threshold of {:.2}",
+                health_factor, thresholds.critical
+            )),
+            AlertLevel::Warning => warnings.push(format!(
+                "Health factor would fall to {:.2}, below the warning threshold of {:.2}",
+                health_factor, thresholds.warning
+            )),
+            AlertLevel::Healthy => {}
+        }
+    }
+    warnings.extend(new_concentration_warnings.iter().map(|warning| warning.message.clone()));
+
+    WhatIfAnalysis {
+        delta: RiskDelta::between(&before, &after),
+        position,
+        health_factor_before,
+        health_factor_after,
+        before,
+        after,
+        new_concentration_warnings,
+        warnings,
+    }
+}
+
+/// Liquidation-weighted collateral and debt of a pooled account, from the
+/// `account_health` its positions report
+fn account_balance<'a>(positions: impl Iterator<Item = &'a Position>) -> (f64, f64) {
+    for position in positions {
+        let Some(health) = position.metadata.get("account_health") else {
+            continue;
+        };
+        let read = |key: &str| health.get(key).and_then(|v| v.as_f64()).filter(|v| v.is_finite());
+        let debt_usd = read("total_debt_usd").unwrap_or(0.0);
+        let collateral_usd = read("total_collateral_usd").unwrap_or(0.0);
+
+        let weighted = match (read("health_factor"), read("liquidation_threshold")) {
+            (Some(health_factor), _) if debt_usd > 0.0 => health_factor * debt_usd,
+            (_, Some(threshold)) => collateral_usd * threshold,
+            _ => collateral_usd * DEFAULT_LIQUIDATION_THRESHOLD,
+        };
+        return (weighted, debt_usd);
+    }
+    (0.0, 0.0)
+}
+
+/// Update a position of the account the hypothetical joins with the account's new
+/// health factor, debt and collateral
+fn apply_account_change(
+    position: &mut Position,
+    hypothetical: &HypotheticalPosition,
+    asset: &str,
+    liquidation_threshold: f64,
+    health_factor: Option<f64>,
+    debt_usd: f64,
+) {
+    if let Some(health) = position.metadata.get_mut("account_health").and_then(|v| v.as_object_mut()) {
+        health.insert("health_factor".to_string(), serde_json::json!(health_factor));
+        health.insert("total_debt_usd".to_string(), serde_json::json!(debt_usd));
+    }
+
+    let Some(details) = position.metadata.get_mut("position_details").and_then(|v| v.as_object_mut()) else {
+        return;
+    };
+    if details.contains_key("health_factor") {
+        details.insert("health_factor".to_string(), serde_json::json!(health_factor));
+    }
+    if details.contains_key("account_debt_usd") {
+        details.insert("account_debt_usd".to_string(), serde_json::json!(debt_usd));
+    }
+    if hypothetical.side == PositionSide::Supply {
+        if let Some(collateral) = details.get_mut("collateral").and_then(|v| v.as_array_mut()) {
+            collateral.push(serde_json::json!({
+                "symbol": asset,
+                "value_usd": hypothetical.size_usd.abs(),
+                "liquidation_threshold": liquidation_threshold,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aave(id: &str, position_type: &str, pair: &str, value_usd: f64, details: serde_json::Value) -> Position {
+        Position {
+            id: id.to_string(),
+            protocol: "aave_v3".to_string(),
+            position_type: position_type.to_string(),
+            pair: pair.to_string(),
+            value_usd,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({
+                "underlying_asset": pair.split('/').next().unwrap(),
+                "account_health": {
+                    "health_factor": 2.0,
+                    "total_collateral_usd": 10_000.0,
+                    "total_debt_usd": 4_000.0,
+                    "liquidation_threshold": 0.8,
+                },
+                "position_details": details,
+            }),
+            last_updated: 0,
+        }
+    }
+
+    fn wallet() -> Vec<Position> {
+        vec![
+            aave("weth_supply", "collateral", "WETH", 10_000.0, serde_json::json!({ "supply_apy": 2.0 })),
+            aave("usdc_borrow", "borrow", "USDC/WETH", -4_000.0, serde_json::json!({
+                "borrow_apy": 5.0,
+                "health_factor": 2.0,
+                "account_debt_usd": 4_000.0,
+                "collateral": [{ "symbol": "WETH", "value_usd": 10_000.0, "liquidation_threshold": 0.8 }],
+            })),
+        ]
+    }
+
+    #[test]
+    fn test_extra_borrow_lowers_health_factor_and_apy() {
+        let borrow = HypotheticalPosition {
+            protocol: "aave_v3".to_string(),
+            asset: "usdc".to_string(),
+            size_usd: 2_400.0,
+            side: PositionSide::Borrow,
+            apy: None,
+            liquidation_threshold: None,
+        };
+
+        let analysis = what_if(Address::ZERO, &wallet(), &borrow, &HealthFactorThresholds { warning: 1.5, critical: 1.2 }, &ConcentrationLimits::default());
+
+        assert_eq!(analysis.health_factor_before, Some(2.0));
+        assert_eq!(analysis.health_factor_after, Some(8_000.0 / 6_400.0));
+        assert_eq!(analysis.before.min_health_factor, Some(2.0));
+        assert_eq!(analysis.after.min_health_factor, Some(1.25));
+        // Borrow APY is taken from the existing USDC borrow
+        assert_eq!(analysis.position.metadata["position_details"]["borrow_apy"], 5.0);
+        assert!(analysis.delta.net_apy.unwrap() < 0.0);
+        assert!(analysis.delta.liquidation_buffer_pct.unwrap() < 0.0);
+        assert_eq!(analysis.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_large_supply_on_a_new_protocol_is_flagged_as_concentrated() {
+        let supply = HypotheticalPosition {
+            protocol: "Lido".to_string(),
+            asset: "stETH".to_string(),
+            size_usd: 50_000.0,
+            side: PositionSide::Supply,
+            apy: Some(5.0),
+            liquidation_threshold: None,
+        };
+        let existing = vec![
+            aave("weth_supply", "supply", "WETH", 10_000.0, serde_json::json!({ "supply_apy": 2.0 })),
+            aave("usdc_supply", "supply", "USDC", 10_000.0, serde_json::json!({ "supply_apy": 4.0 })),
+        ];
+
+        let analysis = what_if(Address::ZERO, &existing, &supply, &HealthFactorThresholds::default(), &ConcentrationLimits::default());
+
+        assert_eq!(analysis.health_factor_after, None);
+        assert_eq!(analysis.position.id, position_id("lido", 1, "supply", "STETH", Address::ZERO));
+        assert!(analysis.delta.concentration > 0.0);
+        assert!(analysis.new_concentration_warnings.iter().any(|w| w.entity == "lido"));
+        assert!(analysis.after.net_apy.unwrap() > analysis.before.net_apy.unwrap());
+    }
+}
@@ -0,0 +1,132 @@
+use axum::http::HeaderMap;
+use serde::Serialize;
+
+/// Request header naming the schema version wanted
+pub const ACCEPT_VERSION_HEADER: &str = "accept-version";
+
+/// Response header naming the schema version served
+pub const SCHEMA_VERSION_HEADER: &str = "schema-version";
+
+/// Shape of the portfolio positions response. Version 1 fills fields the service
+/// can't compute with stand-ins such as `tick_lower: 0` or `fees_earned_usd: 0`, so a
+/// real zero can't be told from "not computed"; version 2 keeps the same fields but
+/// sets them to `null` unless the adapter reported a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum SchemaVersion {
+    /// Original shape with placeholder defaults
+    #[serde(rename = "1")]
+    V1,
+    /// Placeholders replaced by `null`
+    #[serde(rename = "2")]
+    V2,
+}
+
+impl SchemaVersion {
+    /// Served when the client doesn't ask, so existing clients keep their shape
+    pub const DEFAULT: Self = SchemaVersion::V1;
+    pub const LATEST: Self = SchemaVersion::V2;
+    pub const SUPPORTED: &'static [Self] = &[SchemaVersion::V1, SchemaVersion::V2];
+
+    /// `1`, `v1` and `1.0` all name version 1
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        let number = value.strip_prefix('v').unwrap_or(&value);
+        match number.strip_suffix(".0").unwrap_or(number) {
+            "1" => Some(SchemaVersion::V1),
+            "2" => Some(SchemaVersion::V2),
+            "latest" => Some(Self::LATEST),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SchemaVersion::V1 => "1",
+            SchemaVersion::V2 => "2",
+        }
+    }
+
+    /// Version asked for by `?v=`, else `Accept-Version`, else the default. An
+    /// unsupported version is an error rather than silently served as another.
+    pub fn negotiate(query: Option<&str>, headers: &HeaderMap) -> Result<Self, String> {
+        let requested = query.or_else(|| headers.get(ACCEPT_VERSION_HEADER).and_then(|value| value.to_str().ok()));
+        match requested {
+            None => Ok(Self::DEFAULT),
+            Some(value) => Self::parse(value).ok_or_else(|| {
+                let supported: Vec<&str> = Self::SUPPORTED.iter().map(SchemaVersion::as_str).collect();
+                format!("'{}' is not a supported schema version (supported: {})", value.trim(), supported.join(", "))
+            }),
+        }
+    }
+
+    /// Whether fields that weren't computed are `null` instead of a stand-in value
+    pub fn nulls_placeholders(&self) -> bool {
+        *self >= SchemaVersion::V2
+    }
+}
+
+/// Where a position field's value comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldSource {
+    /// Always computed from on-chain or price data
+    Computed,
+    /// Reported by the adapters that know it; a stand-in in v1, `null` in v2 otherwise
+    WhenReported,
+    /// Never computed; a constant in v1, `null` in v2
+    Placeholder,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FieldDoc {
+    pub field: &'static str,
+    pub source: FieldSource,
+    /// What version 1 returns when the value isn't known
+    pub v1_default: Option<&'static str>,
+    pub note: &'static str,
+}
+
+/// Fields of each returned position and whether their values are real, served from
+/// `GET /api/v1/schema`
+pub const POSITION_FIELDS: &[FieldDoc] = &[
+    FieldDoc { field: "id", source: FieldSource::Computed, v1_default: None, note: "Stable across reads" },
+    FieldDoc { field: "protocol", source: FieldSource::Computed, v1_default: None, note: "" },
+    FieldDoc { field: "position_type", source: FieldSource::Computed, v1_default: None, note: "" },
+    FieldDoc { field: "pair", source: FieldSource::Computed, v1_default: None, note: "" },
+    FieldDoc { field: "value_usd", source: FieldSource::Computed, v1_default: None, note: "In the requested currency; debt is negative" },
+    FieldDoc { field: "pnl_usd", source: FieldSource::Computed, v1_default: None, note: "Since the position was first seen by this service" },
+    FieldDoc { field: "chain_id", source: FieldSource::WhenReported, v1_default: Some("1"), note: "Set for positions read from a configured chain" },
+    FieldDoc { field: "pool_address", source: FieldSource::WhenReported, v1_default: Some("\"\""), note: "Liquidity positions only" },
+    FieldDoc { field: "token0_address", source: FieldSource::WhenReported, v1_default: Some("\"\""), note: "Liquidity positions only" },
+    FieldDoc { field: "token1_address", source: FieldSource::WhenReported, v1_default: Some("\"\""), note: "Liquidity positions only" },
+    FieldDoc { field: "liquidity", source: FieldSource::WhenReported, v1_default: Some("\"0\""), note: "Uniswap V3 positions only" },
+    FieldDoc { field: "tick_lower", source: FieldSource::WhenReported, v1_default: Some("0"), note: "Uniswap V3 positions only" },
+    FieldDoc { field: "tick_upper", source: FieldSource::WhenReported, v1_default: Some("0"), note: "Uniswap V3 positions only" },
+    FieldDoc { field: "in_range", source: FieldSource::WhenReported, v1_default: None, note: "Uniswap V3 positions only; whether the pool's current tick is inside the range" },
+    FieldDoc { field: "fees_earned_usd", source: FieldSource::WhenReported, v1_default: Some("0"), note: "From the position's P&L breakdown" },
+    FieldDoc { field: "impermanent_loss_usd", source: FieldSource::WhenReported, v1_default: Some("0"), note: "Liquidity positions with an entry snapshot" },
+    FieldDoc { field: "risk_score", source: FieldSource::Computed, v1_default: None, note: "Risk in [0, 1]; see risk_score_source" },
+    FieldDoc { field: "risk_score_source", source: FieldSource::Computed, v1_default: None, note: "adapter, or estimated from type, asset class and health factor" },
+    FieldDoc { field: "confirmations", source: FieldSource::Computed, v1_default: None, note: "Blocks behind the head the position was read at when REQUIRE_CONFIRMATIONS is set; 0 otherwise" },
+    FieldDoc { field: "estimated_exit_gas_usd", source: FieldSource::WhenReported, v1_default: None, note: "Gas to close the position at the current gas price; null for historical reads" },
+    FieldDoc { field: "net_of_exit_cost", source: FieldSource::Computed, v1_default: None, note: "Exit gas exceeds EXIT_COST_WARNING_RATIO of the position's value" },
+    FieldDoc { field: "created_at", source: FieldSource::Placeholder, v1_default: Some("updated_at"), note: "Opening time isn't tracked" },
+    FieldDoc { field: "updated_at", source: FieldSource::Computed, v1_default: None, note: "When the position was read" },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_query_overrides_header_and_defaults_to_v1() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(SchemaVersion::negotiate(None, &headers), Ok(SchemaVersion::V1));
+
+        headers.insert(ACCEPT_VERSION_HEADER, HeaderValue::from_static("v2"));
+        assert_eq!(SchemaVersion::negotiate(None, &headers), Ok(SchemaVersion::V2));
+        assert_eq!(SchemaVersion::negotiate(Some("1.0"), &headers), Ok(SchemaVersion::V1));
+        assert!(SchemaVersion::negotiate(Some("3"), &headers).unwrap_err().contains("supported: 1, 2"));
+    }
+}
@@ -0,0 +1,55 @@
+use axum::http::{header, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// Origins allowed to call the API from a browser, from `ALLOWED_ORIGINS`
+/// (comma-separated, e.g. `https://app.example.com,https://staging.example.com`).
+/// `None` when unset or empty, meaning any origin is allowed (dev mode).
+pub fn allowed_origins_from_env() -> Option<Vec<HeaderValue>> {
+    std::env::var("ALLOWED_ORIGINS").ok().and_then(|list| parse_origins(&list))
+}
+
+fn parse_origins(list: &str) -> Option<Vec<HeaderValue>> {
+    let origins: Vec<HeaderValue> = list
+        .split(',')
+        .map(|origin| origin.trim().trim_end_matches('/'))
+        .filter(|origin| !origin.is_empty())
+        .filter_map(|origin| match HeaderValue::from_str(origin) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                tracing::warn!("Ignoring invalid CORS origin '{}'", origin);
+                None
+            }
+        })
+        .collect();
+
+    (!origins.is_empty()).then_some(origins)
+}
+
+/// CORS restricted to `origins`, or permissive when `None`. PUT is allowed alongside
+/// GET and POST for the risk profile update endpoint.
+pub fn cors_layer(origins: Option<Vec<HeaderValue>>) -> CorsLayer {
+    match origins {
+        Some(origins) => CorsLayer::new()
+            .allow_origin(AllowOrigin::list(origins))
+            .allow_methods([Method::GET, Method::POST, Method::PUT])
+            .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION, header::ACCEPT]),
+        None => CorsLayer::permissive(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origins_are_trimmed_and_blank_list_is_permissive() {
+        let origins = parse_origins(" https://app.example.com/, https://staging.example.com ,").unwrap();
+        assert_eq!(origins, vec![
+            HeaderValue::from_static("https://app.example.com"),
+            HeaderValue::from_static("https://staging.example.com"),
+        ]);
+
+        assert!(parse_origins("").is_none());
+        assert!(parse_origins(" , ").is_none());
+    }
+}
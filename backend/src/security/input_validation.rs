@@ -0,0 +1,273 @@
+use alloy::primitives::Address;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use std::str::FromStr;
+
+/// Rejected user input, rendered as a 400 with a structured body
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationError {
+    #[error("Address must be 0x followed by 40 hex characters or an ENS name")]
+    MalformedAddress,
+
+    #[error("Address has an invalid EIP-55 checksum")]
+    InvalidChecksum,
+
+    #[error("Invalid ENS name")]
+    InvalidEnsName,
+
+    #[error("Invalid position: {0}")]
+    InvalidPosition(String),
+
+    #[error("Unknown protocol '{0}'")]
+    UnknownProtocol(String),
+
+    #[error("Invalid address list: {0}")]
+    InvalidAddressList(String),
+
+    #[error("Invalid alert thresholds: {0}")]
+    InvalidAlertThresholds(String),
+
+    #[error("Unsupported schema version: {0}")]
+    UnsupportedSchemaVersion(String),
+}
+
+impl ValidationError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ValidationError::MalformedAddress => "malformed_address",
+            ValidationError::InvalidChecksum => "invalid_checksum",
+            ValidationError::InvalidEnsName => "invalid_ens_name",
+            ValidationError::InvalidPosition(_) => "invalid_position",
+            ValidationError::UnknownProtocol(_) => "unknown_protocol",
+            ValidationError::InvalidAddressList(_) => "invalid_address_list",
+            ValidationError::InvalidAlertThresholds(_) => "invalid_alert_thresholds",
+            ValidationError::UnsupportedSchemaVersion(_) => "unsupported_schema_version",
+        }
+    }
+}
+
+impl IntoResponse for ValidationError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({
+            "success": false,
+            "error": self.code(),
+            "message": self.to_string(),
+        });
+        (StatusCode::BAD_REQUEST, Json(body)).into_response()
+    }
+}
+
+/// A wallet identifier that passed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidatedAddress {
+    Hex(Address),
+    Ens(String),
+}
+
+impl ValidatedAddress {
+    /// Lowercase form, stable across input casing for use as a cache key
+    pub fn normalized(&self) -> String {
+        match self {
+            ValidatedAddress::Hex(address) => format!("{:?}", address).to_lowercase(),
+            ValidatedAddress::Ens(name) => name.clone(),
+        }
+    }
+}
+
+pub struct InputValidator;
+
+impl InputValidator {
+    const MAX_ENS_LENGTH: usize = 255;
+    const MAX_LABEL_LENGTH: usize = 64;
+    /// Wallets accepted by one batch request
+    pub const MAX_BATCH_ADDRESSES: usize = 20;
+    /// Sanity cap on a single position's value
+    const MAX_POSITION_VALUE_USD: f64 = 1e12;
+    /// Position types emitted by the adapters
+    pub const POSITION_TYPES: &'static [&'static str] = &[
+        "supply", "collateral", "borrow", "debt", "liquidity", "staking", "locked",
+        "withdrawing", "perp", "yield_farming", "stablecoin",
+    ];
+
+    /// Validate a wallet path parameter: a hex address (EIP-55 checksum enforced
+    /// when mixed-case) or an ENS name
+    pub fn validate_address(input: &str) -> Result<ValidatedAddress, ValidationError> {
+        let input = input.trim();
+
+        if input.ends_with(".eth") || input.ends_with(".ens") {
+            return Self::validate_ens_name(input).map(ValidatedAddress::Ens);
+        }
+
+        let hex = input
+            .strip_prefix("0x")
+            .or_else(|| input.strip_prefix("0X"))
+            .ok_or(ValidationError::MalformedAddress)?;
+
+        if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ValidationError::MalformedAddress);
+        }
+
+        let has_upper = hex.chars().any(|c| c.is_ascii_uppercase());
+        let has_lower = hex.chars().any(|c| c.is_ascii_lowercase());
+
+        let address = if has_upper && has_lower {
+            Address::parse_checksummed(format!("0x{}", hex), None)
+                .map_err(|_| ValidationError::InvalidChecksum)?
+        } else {
+            Address::from_str(hex).map_err(|_| ValidationError::MalformedAddress)?
+        };
+
+        Ok(ValidatedAddress::Hex(address))
+    }
+
+    /// Validate a batch of wallets, dropping repeats. Fails on the first invalid entry,
+    /// or when the list is empty or longer than [`Self::MAX_BATCH_ADDRESSES`].
+    pub fn validate_addresses(inputs: &[String]) -> Result<Vec<ValidatedAddress>, ValidationError> {
+        if inputs.is_empty() {
+            return Err(ValidationError::InvalidAddressList("at least one address is required".to_string()));
+        }
+        if inputs.len() > Self::MAX_BATCH_ADDRESSES {
+            return Err(ValidationError::InvalidAddressList(format!(
+                "at most {} addresses per request",
+                Self::MAX_BATCH_ADDRESSES
+            )));
+        }
+
+        let mut validated: Vec<ValidatedAddress> = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            let address = Self::validate_address(input)?;
+            if !validated.iter().any(|seen| seen.normalized() == address.normalized()) {
+                validated.push(address);
+            }
+        }
+        Ok(validated)
+    }
+
+    /// Validate the fields of a user-described position: a known position type, a
+    /// protocol and `TOKEN/TOKEN` style pair of sane length, and a finite positive value
+    pub fn validate_position(
+        protocol: &str,
+        position_type: &str,
+        pair: &str,
+        value_usd: f64,
+    ) -> Result<(), ValidationError> {
+        let invalid = |message: String| Err(ValidationError::InvalidPosition(message));
+
+        let protocol = protocol.trim();
+        if protocol.is_empty() || protocol.len() > Self::MAX_LABEL_LENGTH {
+            return invalid(format!("protocol must be 1-{} characters", Self::MAX_LABEL_LENGTH));
+        }
+        if !Self::POSITION_TYPES.contains(&position_type) {
+            return invalid(format!("position_type must be one of {}", Self::POSITION_TYPES.join(", ")));
+        }
+
+        let legs: Vec<&str> = pair.split('/').map(str::trim).collect();
+        let valid_pair = pair.len() <= Self::MAX_LABEL_LENGTH
+            && legs.iter().all(|leg| !leg.is_empty() && leg.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+'));
+        if !valid_pair {
+            return invalid("pair must be token symbols separated by '/', e.g. WETH/USDC".to_string());
+        }
+
+        if !value_usd.is_finite() || value_usd <= 0.0 || value_usd > Self::MAX_POSITION_VALUE_USD {
+            return invalid("value_usd must be a positive amount".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn validate_ens_name(input: &str) -> Result<String, ValidationError> {
+        let name = input.to_lowercase();
+        let valid = name.len() <= Self::MAX_ENS_LENGTH
+            && name.split('.').all(|label| {
+                !label.is_empty()
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+            });
+
+        if valid {
+            Ok(name)
+        } else {
+            Err(ValidationError::InvalidEnsName)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VITALIK: &str = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045";
+
+    #[test]
+    fn test_checksummed_and_single_case_addresses_are_accepted() {
+        let expected = ValidatedAddress::Hex(Address::from_str(VITALIK).unwrap());
+
+        assert_eq!(InputValidator::validate_address(VITALIK), Ok(expected.clone()));
+        assert_eq!(InputValidator::validate_address(&VITALIK.to_lowercase()), Ok(expected.clone()));
+        assert_eq!(
+            expected.normalized(),
+            "0xd8da6bf26964af9d7eed9e03e53415d37aa96045"
+        );
+    }
+
+    #[test]
+    fn test_bad_checksum_is_rejected() {
+        let bad = VITALIK.replace("d8dA", "d8Da");
+        assert_eq!(InputValidator::validate_address(&bad), Err(ValidationError::InvalidChecksum));
+    }
+
+    #[test]
+    fn test_malformed_input_is_rejected() {
+        for input in ["", "0x123", "d8da6bf26964af9d7eed9e03e53415d37aa96045", "0xzz8da6bf26964af9d7eed9e03e53415d37aa9604"] {
+            assert_eq!(InputValidator::validate_address(input), Err(ValidationError::MalformedAddress), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_ens_names() {
+        assert_eq!(
+            InputValidator::validate_address("Vitalik.eth"),
+            Ok(ValidatedAddress::Ens("vitalik.eth".to_string()))
+        );
+        assert_eq!(InputValidator::validate_address("bad..eth"), Err(ValidationError::InvalidEnsName));
+    }
+
+    #[test]
+    fn test_position_fields() {
+        assert!(InputValidator::validate_position("aave_v3", "borrow", "USDC/WETH+WBTC", 5_000.0).is_ok());
+
+        for (protocol, position_type, pair, value_usd) in [
+            ("", "supply", "USDC", 1.0),
+            ("spark", "lending", "USDC", 1.0),
+            ("spark", "supply", "USDC//DAI", 1.0),
+            ("spark", "supply", "USDC", -1.0),
+            ("spark", "supply", "USDC", f64::NAN),
+        ] {
+            assert!(
+                matches!(
+                    InputValidator::validate_position(protocol, position_type, pair, value_usd),
+                    Err(ValidationError::InvalidPosition(_))
+                ),
+                "{} {} {} {}", protocol, position_type, pair, value_usd
+            );
+        }
+    }
+
+    #[test]
+    fn test_address_batches() {
+        let batch = vec![VITALIK.to_string(), VITALIK.to_lowercase(), "vitalik.eth".to_string()];
+        let validated = InputValidator::validate_addresses(&batch).unwrap();
+        assert_eq!(validated.len(), 2);
+
+        assert!(matches!(InputValidator::validate_addresses(&[]), Err(ValidationError::InvalidAddressList(_))));
+        let too_many = vec![VITALIK.to_string(); InputValidator::MAX_BATCH_ADDRESSES + 1];
+        assert!(matches!(InputValidator::validate_addresses(&too_many), Err(ValidationError::InvalidAddressList(_))));
+        assert_eq!(
+            InputValidator::validate_addresses(&[VITALIK.to_string(), "0x123".to_string()]),
+            Err(ValidationError::MalformedAddress)
+        );
+    }
+}
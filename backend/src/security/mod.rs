@@ -0,0 +1,9 @@
+pub mod cors;
+pub mod input_validation;
+pub mod outbound_url;
+pub mod rate_limit;
+
+pub use cors::{allowed_origins_from_env, cors_layer};
+pub use input_validation::{InputValidator, ValidatedAddress, ValidationError};
+pub use outbound_url::{check_outbound_url, outbound_client, resolve_public, validate_outbound_url};
+pub use rate_limit::{rate_limit, RateLimiter};
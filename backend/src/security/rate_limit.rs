@@ -0,0 +1,201 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Paths that are never rate limited, so load balancers can always probe liveness
+const EXEMPT_PATHS: &[&str] = &["/health"];
+
+/// Clients tracked before idle, fully refilled buckets are evicted
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Per-IP token bucket: each client may burst up to `requests_per_minute` requests,
+/// refilled continuously at the same rate.
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    /// Proxies in front of the service, each appending its peer to `X-Forwarded-For`.
+    /// Clients are keyed by the hop the outermost one saw, since anything left of it is
+    /// whatever the client sent; 0 keys by the socket address.
+    trusted_proxies: usize,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: u32, trusted_proxies: usize) -> Self {
+        Self {
+            capacity: requests_per_minute as f64,
+            refill_per_sec: requests_per_minute as f64 / 60.0,
+            trusted_proxies,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `RATE_LIMIT_PER_MINUTE` (default 30, 0 disables) and `RATE_LIMIT_TRUSTED_PROXIES`
+    /// (default 0). `RATE_LIMIT_TRUST_FORWARDED_FOR=true` still means a single proxy.
+    pub fn from_env() -> Option<Arc<Self>> {
+        let requests_per_minute = std::env::var("RATE_LIMIT_PER_MINUTE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+        let trusted_proxies = std::env::var("RATE_LIMIT_TRUSTED_PROXIES")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or_else(|| {
+                std::env::var("RATE_LIMIT_TRUST_FORWARDED_FOR")
+                    .map(|v| usize::from(v == "true" || v == "1"))
+                    .unwrap_or(0)
+            });
+
+        (requests_per_minute > 0).then(|| Arc::new(Self::new(requests_per_minute, trusted_proxies)))
+    }
+
+    pub fn requests_per_minute(&self) -> u32 {
+        self.capacity as u32
+    }
+
+    /// Take one token for `client`, or return how long until one is available
+    pub fn check(&self, client: IpAddr, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(&client) {
+            buckets.retain(|_, bucket| self.refilled(*bucket, now) < self.capacity);
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: self.capacity,
+            last_refill: now,
+        });
+        bucket.tokens = self.refilled(*bucket, now);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill_per_sec))
+        }
+    }
+
+    fn refilled(&self, bucket: Bucket, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.last_refill).as_secs_f64();
+        (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity)
+    }
+
+    fn client_ip(&self, request: &Request) -> Option<IpAddr> {
+        if self.trusted_proxies > 0 {
+            let hops: Vec<&str> = request
+                .headers()
+                .get_all("x-forwarded-for")
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .flat_map(|v| v.split(','))
+                .map(str::trim)
+                .collect();
+            // The nth hop from the right; a shorter chain came through fewer proxies
+            let forwarded = hops
+                .get(hops.len().saturating_sub(self.trusted_proxies))
+                .and_then(|ip| ip.parse().ok());
+            if forwarded.is_some() {
+                return forwarded;
+            }
+        }
+
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+    }
+}
+
+/// Middleware rejecting requests over the client's budget with 429 and `Retry-After`
+pub async fn rate_limit(State(limiter): State<Arc<RateLimiter>>, request: Request, next: Next) -> Response {
+    if EXEMPT_PATHS.contains(&request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let Some(client) = limiter.client_ip(&request) else {
+        return next.run(request).await;
+    };
+
+    match limiter.check(client, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            tracing::warn!("🚫 Rate limit exceeded for {}", client);
+
+            let body = serde_json::json!({
+                "success": false,
+                "error": "rate_limited",
+                "message": format!("Too many requests, retry after {} seconds", retry_after_secs),
+            });
+            let mut response = (StatusCode::TOO_MANY_REQUESTS, Json(body)).into_response();
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_allows_burst_then_limits() {
+        let limiter = RateLimiter::new(3, 0);
+        let client: IpAddr = "10.0.0.1".parse().unwrap();
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check(client, now).is_ok());
+        }
+        let retry_after = limiter.check(client, now).unwrap_err();
+        assert_eq!(retry_after.as_secs_f64().round(), 20.0);
+
+        // One token refills every 20s at 3 req/min
+        assert!(limiter.check(client, now + Duration::from_secs(21)).is_ok());
+    }
+
+    #[test]
+    fn test_clients_have_separate_buckets() {
+        let limiter = RateLimiter::new(1, 0);
+        let now = Instant::now();
+
+        assert!(limiter.check("10.0.0.1".parse().unwrap(), now).is_ok());
+        assert!(limiter.check("10.0.0.2".parse().unwrap(), now).is_ok());
+        assert!(limiter.check("10.0.0.1".parse().unwrap(), now).is_err());
+    }
+
+    #[test]
+    fn test_forwarded_client_is_the_hop_the_outermost_proxy_saw() {
+        let request = |forwarded_for: &str| {
+            let mut request = Request::builder()
+                .header("x-forwarded-for", forwarded_for)
+                .body(axum::body::Body::empty())
+                .unwrap();
+            request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 9], 443))));
+            request
+        };
+        // The client prepended a spoofed hop; the proxy appended the address it saw
+        let spoofed = request("1.2.3.4, 203.0.113.7");
+
+        assert_eq!(RateLimiter::new(1, 0).client_ip(&spoofed), Some("10.0.0.9".parse().unwrap()));
+        assert_eq!(RateLimiter::new(1, 1).client_ip(&spoofed), Some("203.0.113.7".parse().unwrap()));
+        assert_eq!(RateLimiter::new(1, 2).client_ip(&spoofed), Some("1.2.3.4".parse().unwrap()));
+        assert_eq!(RateLimiter::new(1, 3).client_ip(&request("203.0.113.7")), Some("203.0.113.7".parse().unwrap()));
+        assert_eq!(RateLimiter::new(1, 1).client_ip(&request("garbage")), Some("10.0.0.9".parse().unwrap()));
+    }
+}
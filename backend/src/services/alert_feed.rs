@@ -0,0 +1,376 @@
+use alloy::primitives::Address;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::broadcast;
+
+use super::monitoring::{self, AlertLevel, HealthFactorThresholds, LiquidationAlert};
+use super::value_drop::ValueDrop;
+use crate::adapters::traits::Position;
+
+/// Alerts buffered per wallet for subscribers that fall behind
+const CHANNEL_CAPACITY: usize = 64;
+
+/// What a live alert is about
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "alert_type", rename_all = "snake_case")]
+pub enum LiveAlertKind {
+    /// A lending position's health factor crossed into a worse band
+    Liquidation {
+        position_id: String,
+        protocol: String,
+        asset: String,
+        health_factor: f64,
+        liquidation_price: Option<f64>,
+    },
+    /// A position, or the whole portfolio when `position_id` is absent, fell by at least
+    /// the configured share from its highest value over the last day
+    ValueDrop {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        position_id: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        protocol: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        asset: Option<String>,
+        peak_value_usd: f64,
+        current_value_usd: f64,
+        drop_percentage: f64,
+    },
+    /// Portfolio risk score rose above the configured threshold
+    RiskThreshold {
+        risk_score: f64,
+        threshold: f64,
+    },
+}
+
+/// Alert pushed to live subscribers of a wallet
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LiveAlert {
+    pub wallet: String,
+    pub level: AlertLevel,
+    #[serde(flatten)]
+    pub kind: LiveAlertKind,
+    pub timestamp: String,
+}
+
+impl LiveAlert {
+    fn new(wallet: Address, level: AlertLevel, kind: LiveAlertKind) -> Self {
+        Self {
+            wallet: format!("{:?}", wallet),
+            level,
+            kind,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn value_drop(wallet: Address, drop: &ValueDrop) -> Self {
+        Self::new(wallet, drop.level, LiveAlertKind::ValueDrop {
+            position_id: drop.position_id.clone(),
+            protocol: drop.protocol.clone(),
+            asset: drop.asset.clone(),
+            peak_value_usd: drop.peak_value_usd,
+            current_value_usd: drop.current_value_usd,
+            drop_percentage: drop.drop_percentage,
+        })
+    }
+
+    /// Position the alert is about; `None` for portfolio-wide alerts
+    pub fn position_id(&self) -> Option<&str> {
+        match &self.kind {
+            LiveAlertKind::Liquidation { position_id, .. } => Some(position_id),
+            LiveAlertKind::ValueDrop { position_id, .. } => position_id.as_deref(),
+            LiveAlertKind::RiskThreshold { .. } => None,
+        }
+    }
+
+    // Identity in the active set: a newer alert of the same key replaces the older one
+    fn key(&self) -> String {
+        match &self.kind {
+            LiveAlertKind::Liquidation { position_id, .. } => format!("liquidation:{}", position_id),
+            LiveAlertKind::ValueDrop { position_id, .. } => value_drop_key(position_id.as_deref()),
+            LiveAlertKind::RiskThreshold { .. } => "risk_threshold".to_string(),
+        }
+    }
+}
+
+fn value_drop_key(position_id: Option<&str>) -> String {
+    match position_id {
+        Some(position_id) => format!("value_drop:{}", position_id),
+        None => "value_drop".to_string(),
+    }
+}
+
+impl From<&LiquidationAlert> for LiveAlert {
+    fn from(alert: &LiquidationAlert) -> Self {
+        Self {
+            wallet: alert.wallet.clone(),
+            level: alert.level,
+            kind: LiveAlertKind::Liquidation {
+                position_id: alert.position_id.clone(),
+                protocol: alert.protocol.clone(),
+                asset: alert.asset.clone(),
+                health_factor: alert.health_factor,
+                liquidation_price: alert.liquidation_price,
+            },
+            timestamp: alert.timestamp.clone(),
+        }
+    }
+}
+
+/// Portfolio-level alert thresholds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlertFeedConfig {
+    /// Drop from a position's or the portfolio's highest value over the last day, in
+    /// percent, that raises an alert
+    pub value_drop_percent: f64,
+    /// Portfolio risk score in [0, 1] above which an alert is raised
+    pub risk_score_threshold: f64,
+}
+
+impl Default for AlertFeedConfig {
+    fn default() -> Self {
+        Self {
+            value_drop_percent: 10.0,
+            risk_score_threshold: 0.7,
+        }
+    }
+}
+
+impl AlertFeedConfig {
+    /// Defaults overridden by `ALERT_VALUE_DROP_PERCENT` / `ALERT_RISK_SCORE_THRESHOLD`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |key: &str, default: f64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            value_drop_percent: read("ALERT_VALUE_DROP_PERCENT", defaults.value_drop_percent),
+            risk_score_threshold: read("ALERT_RISK_SCORE_THRESHOLD", defaults.risk_score_threshold),
+        }
+    }
+}
+
+/// One read of a wallet's portfolio by whichever monitor watches it
+pub struct WalletReading<'a> {
+    pub positions: &'a [Position],
+    /// Alerts the health-factor monitor raised for `positions`
+    pub liquidation_alerts: &'a [LiquidationAlert],
+    /// Thresholds the liquidation alerts were banded with
+    pub thresholds: HealthFactorThresholds,
+    /// Portfolio-level thresholds for this wallet, usually [`AlertFeed::config`] with
+    /// the wallet's own overrides applied
+    pub config: AlertFeedConfig,
+    /// Drops still in effect, from [`ValueDropTracker::observe`](super::ValueDropTracker::observe)
+    pub value_drops: &'a [ValueDrop],
+    pub risk_score: f64,
+}
+
+/// A new subscription to a wallet's alerts
+pub struct AlertSubscription {
+    /// Alerts still in effect, for the initial snapshot
+    pub active: Vec<LiveAlert>,
+    pub receiver: broadcast::Receiver<LiveAlert>,
+    /// Whether this is the wallet's first subscriber, so no monitor is feeding it yet
+    pub start_monitor: bool,
+}
+
+struct WalletFeed {
+    sender: broadcast::Sender<LiveAlert>,
+    active: BTreeMap<String, LiveAlert>,
+    last_risk_score: Option<f64>,
+}
+
+/// Live alert channels, one per subscribed wallet, shared by all of its subscribers.
+///
+/// Monitors push each portfolio read through [`observe`](Self::observe); the feed works
+/// out which alerts are new, broadcasts them, and keeps the set still in effect for
+/// subscribers that connect later.
+pub struct AlertFeed {
+    config: AlertFeedConfig,
+    feeds: Mutex<HashMap<Address, WalletFeed>>,
+}
+
+impl AlertFeed {
+    pub fn new(config: AlertFeedConfig) -> Self {
+        Self {
+            config,
+            feeds: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Process-wide feed shared by the monitors and WebSocket handlers
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<AlertFeed>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::new(AlertFeedConfig::from_env()))).clone()
+    }
+
+    /// Service-wide portfolio thresholds, for wallets that haven't set their own
+    pub fn config(&self) -> AlertFeedConfig {
+        self.config
+    }
+
+    pub fn subscribe(&self, wallet: Address) -> AlertSubscription {
+        let mut feeds = self.feeds.lock().unwrap();
+        let start_monitor = !feeds.contains_key(&wallet);
+        let feed = feeds.entry(wallet).or_insert_with(|| WalletFeed {
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+            active: BTreeMap::new(),
+            last_risk_score: None,
+        });
+
+        AlertSubscription {
+            active: feed.active.values().cloned().collect(),
+            receiver: feed.sender.subscribe(),
+            start_monitor,
+        }
+    }
+
+    /// Drop the wallet's feed once its last subscriber has gone. Called by the monitor
+    /// feeding it before each read; returns whether anyone is still listening.
+    pub fn release_if_unsubscribed(&self, wallet: Address) -> bool {
+        let mut feeds = self.feeds.lock().unwrap();
+        match feeds.get(&wallet) {
+            Some(feed) if feed.sender.receiver_count() > 0 => true,
+            Some(_) => {
+                feeds.remove(&wallet);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Update the wallet's active alerts from a fresh read and broadcast the new ones.
+    /// Wallets nobody subscribed to are ignored.
+    pub fn observe(&self, wallet: Address, reading: WalletReading<'_>) -> Vec<LiveAlert> {
+        let mut feeds = self.feeds.lock().unwrap();
+        let Some(feed) = feeds.get_mut(&wallet) else {
+            return Vec::new();
+        };
+        let mut raised: Vec<LiveAlert> = reading.liquidation_alerts.iter().map(LiveAlert::from).collect();
+
+        // Liquidation alerts stay active until the position is healthy again or gone, value
+        // drops while they're still past the threshold
+        feed.active.retain(|key, alert| match &alert.kind {
+            LiveAlertKind::Liquidation { position_id, .. } => reading.positions
+                .iter()
+                .find(|p| &p.id == position_id)
+                .and_then(monitoring::health_factor)
+                .is_some_and(|hf| reading.thresholds.level(hf) != AlertLevel::Healthy),
+            LiveAlertKind::ValueDrop { .. } => reading.value_drops
+                .iter()
+                .any(|drop| value_drop_key(drop.position_id.as_deref()) == *key),
+            _ => true,
+        });
+        raised.extend(reading.value_drops.iter().filter(|drop| drop.new).map(|drop| LiveAlert::value_drop(wallet, drop)));
+
+        let threshold = reading.config.risk_score_threshold;
+        let was_above = feed.last_risk_score.is_some_and(|score| score >= threshold);
+        if reading.risk_score >= threshold {
+            if !was_above {
+                raised.push(LiveAlert::new(wallet, AlertLevel::Warning, LiveAlertKind::RiskThreshold {
+                    risk_score: reading.risk_score,
+                    threshold,
+                }));
+            }
+        } else {
+            feed.active.remove("risk_threshold");
+        }
+        feed.last_risk_score = Some(reading.risk_score);
+
+        for alert in &raised {
+            feed.active.insert(alert.key(), alert.clone());
+            // No receivers just means everyone disconnected since the last read
+            let _ = feed.sender.send(alert.clone());
+        }
+
+        raised
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::LiquidationMonitor;
+
+    fn borrow(health_factor: f64) -> Position {
+        Position {
+            id: "borrow".to_string(),
+            protocol: "aave_v3".to_string(),
+            position_type: "borrow".to_string(),
+            pair: "USDC/WETH".to_string(),
+            value_usd: -500.0,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({ "position_details": { "health_factor": health_factor } }),
+            last_updated: 0,
+        }
+    }
+
+    fn portfolio_drop(current_value_usd: f64, new: bool) -> ValueDrop {
+        ValueDrop {
+            position_id: None,
+            protocol: None,
+            asset: None,
+            peak_value_usd: 10_000.0,
+            current_value_usd,
+            drop_percentage: (10_000.0 - current_value_usd) / 100.0,
+            level: AlertLevel::Warning,
+            new,
+        }
+    }
+
+    fn observe(feed: &AlertFeed, monitor: &LiquidationMonitor, health_factor: f64, value_drops: &[ValueDrop], risk_score: f64) -> Vec<LiveAlert> {
+        let positions = [borrow(health_factor)];
+        let liquidation_alerts = monitor.evaluate(Address::ZERO, &positions);
+        feed.observe(Address::ZERO, WalletReading {
+            positions: &positions,
+            liquidation_alerts: &liquidation_alerts,
+            thresholds: HealthFactorThresholds::default(),
+            config: feed.config(),
+            value_drops,
+            risk_score,
+        })
+    }
+
+    #[test]
+    fn test_subscribers_share_a_feed_and_late_joiners_get_active_alerts() {
+        let feed = AlertFeed::new(AlertFeedConfig::default());
+        let monitor = LiquidationMonitor::new(HealthFactorThresholds::default(), None);
+
+        let mut first = feed.subscribe(Address::ZERO);
+        assert!(first.start_monitor && first.active.is_empty());
+
+        assert!(observe(&feed, &monitor, 2.0, &[], 0.3).is_empty());
+        let raised = observe(&feed, &monitor, 1.1, &[portfolio_drop(8_500.0, true)], 0.8);
+        let kinds: Vec<String> = raised.iter().map(LiveAlert::key).collect();
+        assert_eq!(kinds, ["liquidation:borrow", "value_drop", "risk_threshold"]);
+        assert_eq!(first.receiver.try_recv().unwrap().level, AlertLevel::Critical);
+
+        let second = feed.subscribe(Address::ZERO);
+        assert!(!second.start_monitor);
+        assert_eq!(second.active.len(), 3);
+
+        // A drop already alerted stays active without being raised again
+        assert!(observe(&feed, &monitor, 1.1, &[portfolio_drop(8_400.0, false)], 0.8).is_empty());
+        assert_eq!(feed.subscribe(Address::ZERO).active.len(), 3);
+
+        // Recovery clears the active set without raising anything new
+        assert!(observe(&feed, &monitor, 2.0, &[], 0.4).is_empty());
+        assert!(feed.subscribe(Address::ZERO).active.is_empty());
+    }
+
+    #[test]
+    fn test_feed_is_released_after_last_subscriber_leaves() {
+        let feed = AlertFeed::new(AlertFeedConfig::default());
+        let subscription = feed.subscribe(Address::ZERO);
+        assert!(feed.release_if_unsubscribed(Address::ZERO));
+
+        drop(subscription);
+        assert!(!feed.release_if_unsubscribed(Address::ZERO));
+        assert!(feed.subscribe(Address::ZERO).start_monitor);
+    }
+}
@@ -0,0 +1,560 @@
+use alloy::primitives::Address;
+use async_trait::async_trait;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::Duration;
+
+use super::alert_feed::{LiveAlert, LiveAlertKind};
+use super::monitoring::AlertLevel;
+use crate::security::{outbound_client, validate_outbound_url};
+
+/// Delivery records kept per wallet
+const MAX_DELIVERY_RECORDS: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum NotificationError {
+    #[error("Request failed: {0}")]
+    Request(String),
+
+    #[error("Rejected with status {0}: {1}")]
+    Rejected(u16, String),
+
+    #[error("Destination not allowed: {0}")]
+    Blocked(String),
+
+    #[error("Channel not configured: {0}")]
+    NotConfigured(String),
+}
+
+/// Where a user wants alerts delivered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChannelConfig {
+    /// JSON alert POSTed to any public HTTPS endpoint
+    Webhook { url: String },
+    /// Message from the user's own bot to a chat it has been added to
+    Telegram { bot_token: String, chat_id: String },
+    Discord { webhook_url: String },
+    /// Sent through the service's email provider (`EMAIL_API_KEY`)
+    Email { to: String },
+}
+
+impl ChannelConfig {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ChannelConfig::Webhook { .. } => "webhook",
+            ChannelConfig::Telegram { .. } => "telegram",
+            ChannelConfig::Discord { .. } => "discord",
+            ChannelConfig::Email { .. } => "email",
+        }
+    }
+
+    /// Reject configs that could never deliver, would send alerts over plain HTTP, or
+    /// point the service at its own network
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ChannelConfig::Webhook { url } => validate_outbound_url(url).map(|_| ()),
+            ChannelConfig::Telegram { bot_token, chat_id } if bot_token.trim().is_empty() || chat_id.trim().is_empty() => {
+                Err("telegram needs a bot_token and chat_id".to_string())
+            }
+            ChannelConfig::Discord { webhook_url }
+                if !webhook_url.starts_with("https://discord.com/api/webhooks/")
+                    && !webhook_url.starts_with("https://discordapp.com/api/webhooks/") =>
+            {
+                Err("discord webhook_url must be a Discord webhook".to_string())
+            }
+            ChannelConfig::Email { to } if !to.contains('@') => Err("email address is invalid".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Copy safe to return from the API: bot tokens and webhook paths are secrets
+    pub fn redacted(&self) -> Self {
+        let mask = |secret: &str| format!("{}…", secret.chars().take(8).collect::<String>());
+        match self {
+            ChannelConfig::Webhook { url } => ChannelConfig::Webhook { url: mask(url) },
+            ChannelConfig::Telegram { bot_token, chat_id } => ChannelConfig::Telegram {
+                bot_token: mask(bot_token),
+                chat_id: chat_id.clone(),
+            },
+            ChannelConfig::Discord { .. } => ChannelConfig::Discord {
+                webhook_url: "https://discord.com/api/webhooks/…".to_string(),
+            },
+            ChannelConfig::Email { to } => ChannelConfig::Email { to: to.clone() },
+        }
+    }
+}
+
+/// Transactional email API used by email channels, SendGrid-compatible
+#[derive(Debug, Clone)]
+pub struct EmailProvider {
+    pub api_url: String,
+    pub api_key: String,
+    pub from: String,
+}
+
+impl EmailProvider {
+    /// `EMAIL_API_KEY` and `EMAIL_FROM`, with `EMAIL_API_URL` defaulting to SendGrid;
+    /// `None` without a key
+    pub fn from_env() -> Option<Self> {
+        let api_key = std::env::var("EMAIL_API_KEY").ok().filter(|key| !key.is_empty())?;
+        Some(Self {
+            api_url: std::env::var("EMAIL_API_URL")
+                .unwrap_or_else(|_| "https://api.sendgrid.com/v3/mail/send".to_string()),
+            api_key,
+            from: std::env::var("EMAIL_FROM").unwrap_or_else(|_| "alerts@defi-risk-monitor.local".to_string()),
+        })
+    }
+}
+
+/// A destination alerts can be delivered to
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn send(&self, alert: &LiveAlert) -> Result<(), NotificationError>;
+}
+
+/// Gets a client of its own per send, pinned to where the url resolves right then
+pub struct WebhookChannel {
+    url: String,
+}
+
+pub struct TelegramChannel {
+    client: reqwest::Client,
+    bot_token: String,
+    chat_id: String,
+}
+
+pub struct DiscordChannel {
+    client: reqwest::Client,
+    webhook_url: String,
+}
+
+pub struct EmailChannel {
+    client: reqwest::Client,
+    provider: Option<EmailProvider>,
+    to: String,
+}
+
+// Map a non-2xx response to an error carrying the start of its body
+async fn check_response(response: reqwest::Response) -> Result<(), NotificationError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(NotificationError::Rejected(status.as_u16(), body.chars().take(200).collect()))
+}
+
+pub(crate) async fn post_json(request: reqwest::RequestBuilder, body: &impl Serialize) -> Result<(), NotificationError> {
+    let response = request
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| NotificationError::Request(e.to_string()))?;
+    check_response(response).await
+}
+
+#[async_trait]
+impl NotificationChannel for WebhookChannel {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn send(&self, alert: &LiveAlert) -> Result<(), NotificationError> {
+        // Checked again here: settings saved before a rule existed are still delivered to
+        let url = validate_outbound_url(&self.url).map_err(NotificationError::Blocked)?;
+        let client = outbound_client(&url).await.map_err(NotificationError::Blocked)?;
+        post_json(client.post(url), alert).await
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for TelegramChannel {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    async fn send(&self, alert: &LiveAlert) -> Result<(), NotificationError> {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token);
+        let body = serde_json::json!({
+            "chat_id": self.chat_id,
+            "text": format_alert(alert),
+            "disable_web_page_preview": true,
+        });
+        post_json(self.client.post(url), &body).await
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for DiscordChannel {
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    async fn send(&self, alert: &LiveAlert) -> Result<(), NotificationError> {
+        let body = serde_json::json!({ "content": format_alert(alert) });
+        post_json(self.client.post(&self.webhook_url), &body).await
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for EmailChannel {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    async fn send(&self, alert: &LiveAlert) -> Result<(), NotificationError> {
+        let provider = self.provider
+            .as_ref()
+            .ok_or_else(|| NotificationError::NotConfigured("EMAIL_API_KEY is not set".to_string()))?;
+        let body = serde_json::json!({
+            "personalizations": [{ "to": [{ "email": self.to }] }],
+            "from": { "email": provider.from },
+            "subject": alert_subject(alert),
+            "content": [{ "type": "text/plain", "value": format_alert(alert) }],
+        });
+        post_json(self.client.post(&provider.api_url).bearer_auth(&provider.api_key), &body).await
+    }
+}
+
+fn level_label(level: AlertLevel) -> &'static str {
+    match level {
+        AlertLevel::Critical => "CRITICAL",
+        AlertLevel::Warning => "WARNING",
+        AlertLevel::Healthy => "RECOVERED",
+    }
+}
+
+pub fn alert_subject(alert: &LiveAlert) -> String {
+    let level = level_label(alert.level);
+    match &alert.kind {
+        LiveAlertKind::Liquidation { protocol, .. } => format!("[DeFi Risk Monitor] {} liquidation risk on {}", level, protocol),
+        LiveAlertKind::ValueDrop { protocol: Some(protocol), .. } => format!("[DeFi Risk Monitor] {} value drop on {}", level, protocol),
+        LiveAlertKind::ValueDrop { protocol: None, .. } => format!("[DeFi Risk Monitor] {} portfolio value drop", level),
+        LiveAlertKind::RiskThreshold { .. } => format!("[DeFi Risk Monitor] {} portfolio risk above threshold", level),
+    }
+}
+
+/// Plain-text alert message shared by the chat and email channels
+pub fn format_alert(alert: &LiveAlert) -> String {
+    let icon = if alert.level == AlertLevel::Critical { "🚨" } else { "⚠️" };
+    let level = level_label(alert.level);
+    let mut message = match &alert.kind {
+        LiveAlertKind::Liquidation { protocol, asset, health_factor, liquidation_price, .. } => {
+            let mut message = format!("{} {}: {} {} health factor is {:.3}", icon, level, protocol, asset, health_factor);
+            if let Some(price) = liquidation_price {
+                message.push_str(&format!("\nLiquidation price: ${:.2}", price));
+            }
+            message
+        }
+        LiveAlertKind::ValueDrop { protocol, asset, peak_value_usd, current_value_usd, drop_percentage, .. } => {
+            let subject = match (protocol, asset) {
+                (Some(protocol), Some(asset)) => format!("{} {}", protocol, asset),
+                _ => "Portfolio".to_string(),
+            };
+            format!(
+                "{} {}: {} fell {:.1}% in the last day, from ${:.2} to ${:.2}",
+                icon, level, subject, drop_percentage, peak_value_usd, current_value_usd
+            )
+        }
+        LiveAlertKind::RiskThreshold { risk_score, threshold } => {
+            format!("{} {}: portfolio risk score {:.2} is above {:.2}", icon, level, risk_score, threshold)
+        }
+    };
+    message.push_str(&format!("\nWallet: {}", alert.wallet));
+    if let Some(position_id) = alert.position_id() {
+        message.push_str(&format!("\nPosition: {}", position_id));
+    }
+    message.push_str(&format!("\nAt: {}", alert.timestamp));
+    message
+}
+
+/// Outcome of sending one alert to one channel
+#[derive(Debug, Clone, Serialize)]
+pub struct DeliveryRecord {
+    pub channel: &'static str,
+    pub position_id: String,
+    pub level: AlertLevel,
+    pub delivered: bool,
+    pub error: Option<String>,
+    /// Sends made, counting retries
+    pub attempts: u32,
+    pub timestamp: String,
+}
+
+/// Notification channels configured by each wallet.
+///
+/// When `NOTIFICATION_SETTINGS_PATH` is set the whole map is rewritten there as JSON on
+/// every change and reloaded on startup.
+#[derive(Debug)]
+pub struct NotificationSettingsStore {
+    settings: RwLock<HashMap<Address, Vec<ChannelConfig>>>,
+    path: Option<PathBuf>,
+    file_lock: Mutex<()>,
+}
+
+impl NotificationSettingsStore {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            settings: RwLock::new(HashMap::new()),
+            path,
+            file_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let store = Self::new(std::env::var("NOTIFICATION_SETTINGS_PATH").ok().map(PathBuf::from));
+        if let Err(e) = store.load() {
+            tracing::warn!("Failed to load notification settings: {}", e);
+        }
+        store
+    }
+
+    /// Process-wide store shared by all handlers
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<NotificationSettingsStore>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::from_env())).clone()
+    }
+
+    pub fn get(&self, address: Address) -> Vec<ChannelConfig> {
+        self.settings.read().unwrap().get(&address).cloned().unwrap_or_default()
+    }
+
+    /// Replace the wallet's channels; an empty list removes the wallet
+    pub fn set(&self, address: Address, channels: Vec<ChannelConfig>) {
+        let snapshot = {
+            let mut settings = self.settings.write().unwrap();
+            if channels.is_empty() {
+                settings.remove(&address);
+            } else {
+                settings.insert(address, channels);
+            }
+            settings.clone()
+        };
+
+        if let Err(e) = self.save(&snapshot) {
+            tracing::warn!("Failed to persist notification settings: {}", e);
+        }
+    }
+
+    /// Wallets with at least one channel configured
+    pub fn wallets(&self) -> Vec<Address> {
+        self.settings.read().unwrap().keys().copied().collect()
+    }
+
+    fn load(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let settings: HashMap<Address, Vec<ChannelConfig>> = serde_json::from_str(&contents)?;
+        *self.settings.write().unwrap() = settings;
+        Ok(())
+    }
+
+    fn save(&self, settings: &HashMap<Address, Vec<ChannelConfig>>) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let _guard = self.file_lock.lock().unwrap();
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, serde_json::to_vec_pretty(settings)?)?;
+        std::fs::rename(tmp_path, path)
+    }
+}
+
+/// Fans alerts out to every channel a wallet configured, recording each delivery
+pub struct AlertService {
+    http_client: reqwest::Client,
+    settings: Arc<NotificationSettingsStore>,
+    email: Option<EmailProvider>,
+    deliveries: Mutex<HashMap<Address, VecDeque<DeliveryRecord>>>,
+}
+
+impl AlertService {
+    pub fn new(settings: Arc<NotificationSettingsStore>, email: Option<EmailProvider>) -> Self {
+        Self {
+            http_client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .unwrap_or_default(),
+            settings,
+            email,
+            deliveries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Process-wide service shared by the monitor and handlers
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<AlertService>> = OnceLock::new();
+        GLOBAL
+            .get_or_init(|| Arc::new(Self::new(NotificationSettingsStore::global(), EmailProvider::from_env())))
+            .clone()
+    }
+
+    pub fn settings(&self) -> &NotificationSettingsStore {
+        &self.settings
+    }
+
+    fn channel(&self, config: &ChannelConfig) -> Box<dyn NotificationChannel> {
+        let client = self.http_client.clone();
+        match config.clone() {
+            ChannelConfig::Webhook { url } => Box::new(WebhookChannel { url }),
+            ChannelConfig::Telegram { bot_token, chat_id } => Box::new(TelegramChannel { client, bot_token, chat_id }),
+            ChannelConfig::Discord { webhook_url } => Box::new(DiscordChannel { client, webhook_url }),
+            ChannelConfig::Email { to } => Box::new(EmailChannel { client, provider: self.email.clone(), to }),
+        }
+    }
+
+    /// Send each alert to all of the wallet's channels
+    pub async fn notify(&self, wallet: Address, alerts: &[LiveAlert]) -> Vec<DeliveryRecord> {
+        let channels: Vec<Box<dyn NotificationChannel>> = self.settings
+            .get(wallet)
+            .iter()
+            .map(|config| self.channel(config))
+            .collect();
+        self.deliver(wallet, alerts, &channels).await
+    }
+
+    /// Deliveries run concurrently and fail independently; every attempt is recorded
+    async fn deliver(
+        &self,
+        wallet: Address,
+        alerts: &[LiveAlert],
+        channels: &[Box<dyn NotificationChannel>],
+    ) -> Vec<DeliveryRecord> {
+        if alerts.is_empty() || channels.is_empty() {
+            return Vec::new();
+        }
+
+        let attempts = alerts.iter().flat_map(|alert| channels.iter().map(move |channel| async move {
+            let result = channel.send(alert).await;
+            if let Err(e) = &result {
+                tracing::warn!("❌ Failed to deliver alert for {} via {}: {}", alert.wallet, channel.name(), e);
+            }
+            DeliveryRecord {
+                channel: channel.name(),
+                position_id: alert.position_id().unwrap_or("portfolio").to_string(),
+                level: alert.level,
+                delivered: result.is_ok(),
+                error: result.err().map(|e| e.to_string()),
+                attempts: 1,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            }
+        }));
+        let records = join_all(attempts).await;
+        self.record_deliveries(wallet, &records);
+
+        records
+    }
+
+    /// Add deliveries made outside the alert channels to the wallet's history
+    pub fn record_deliveries(&self, wallet: Address, records: &[DeliveryRecord]) {
+        let mut deliveries = self.deliveries.lock().unwrap();
+        let history = deliveries.entry(wallet).or_default();
+        history.extend(records.iter().cloned());
+        while history.len() > MAX_DELIVERY_RECORDS {
+            history.pop_front();
+        }
+    }
+
+    /// Recent deliveries for the wallet, oldest first
+    pub fn deliveries(&self, wallet: Address) -> Vec<DeliveryRecord> {
+        self.deliveries.lock().unwrap().get(&wallet).map(|history| history.iter().cloned().collect()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::monitoring::LiquidationAlert;
+    use crate::services::ValueDrop;
+
+    struct FakeChannel {
+        name: &'static str,
+        fails: bool,
+    }
+
+    #[async_trait]
+    impl NotificationChannel for FakeChannel {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn send(&self, _alert: &LiveAlert) -> Result<(), NotificationError> {
+            if self.fails {
+                Err(NotificationError::Rejected(401, "Unauthorized".to_string()))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    fn alert() -> LiveAlert {
+        LiveAlert::from(&LiquidationAlert {
+            wallet: format!("{:?}", Address::ZERO),
+            position_id: "aave_borrow".to_string(),
+            protocol: "aave_v3".to_string(),
+            asset: "USDC/WETH".to_string(),
+            level: AlertLevel::Critical,
+            health_factor: 1.08,
+            liquidation_price: Some(1_800.0),
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_one_failing_channel_does_not_block_the_others() {
+        let service = AlertService::new(Arc::new(NotificationSettingsStore::new(None)), None);
+        let channels: Vec<Box<dyn NotificationChannel>> = vec![
+            Box::new(FakeChannel { name: "telegram", fails: true }),
+            Box::new(FakeChannel { name: "discord", fails: false }),
+        ];
+
+        let records = service.deliver(Address::ZERO, &[alert()], &channels).await;
+        assert_eq!(records.len(), 2);
+        assert!(!records[0].delivered);
+        assert_eq!(records[0].error.as_deref(), Some("Rejected with status 401: Unauthorized"));
+        assert!(records[1].delivered);
+        assert_eq!(service.deliveries(Address::ZERO).len(), 2);
+    }
+
+    #[test]
+    fn test_message_and_config_handling() {
+        let message = format_alert(&alert());
+        assert!(message.starts_with("🚨 CRITICAL: aave_v3 USDC/WETH health factor is 1.080"));
+        assert!(message.contains("Liquidation price: $1800.00"));
+        let drop = LiveAlert::value_drop(Address::ZERO, &ValueDrop {
+            position_id: Some("lp".to_string()),
+            protocol: Some("uniswap_v3".to_string()),
+            asset: Some("PEPE/WETH".to_string()),
+            peak_value_usd: 10_000.0,
+            current_value_usd: 8_400.0,
+            drop_percentage: 16.0,
+            level: AlertLevel::Warning,
+            new: true,
+        });
+        assert!(format_alert(&drop).starts_with("⚠️ WARNING: uniswap_v3 PEPE/WETH fell 16.0% in the last day, from $10000.00 to $8400.00"));
+        assert_eq!(alert_subject(&drop), "[DeFi Risk Monitor] WARNING value drop on uniswap_v3");
+
+        let telegram = ChannelConfig::Telegram { bot_token: "123456789:secret".to_string(), chat_id: "42".to_string() };
+        assert!(telegram.validate().is_ok());
+        assert_eq!(telegram.redacted(), ChannelConfig::Telegram { bot_token: "12345678…".to_string(), chat_id: "42".to_string() });
+        assert!(ChannelConfig::Webhook { url: "http://example.com".to_string() }.validate().is_err());
+        assert!(ChannelConfig::Webhook { url: "https://169.254.169.254/latest/meta-data/".to_string() }.validate().is_err());
+        assert!(ChannelConfig::Webhook { url: "https://example.com/hook".to_string() }.validate().is_ok());
+        assert!(ChannelConfig::Discord { webhook_url: "https://example.com/hook".to_string() }.validate().is_err());
+    }
+}
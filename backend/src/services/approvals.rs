@@ -0,0 +1,217 @@
+use alloy::primitives::{address, Address, U256};
+use futures::future::join_all;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::adapters::traits::{AdapterError, Position};
+use crate::blockchain::EthereumClient;
+use crate::services::{TokenMetadataCache, IERC20};
+use crate::utils::normalize_amount;
+
+const CHAIN_ID: u64 = 1;
+
+/// Contracts wallets commonly approve to pull their tokens, by name
+pub const KNOWN_SPENDERS: &[(&str, Address)] = &[
+    ("Uniswap V2 Router", address!("7a250d5630B4cF539739dF2C5dAcb4c659F2488D")),
+    ("Uniswap V3 SwapRouter", address!("E592427A0AEce92De3Edee1F18E0157C05861564")),
+    ("Uniswap SwapRouter02", address!("68b3465833fb72A70ecDF485E0e4C7bD8665Fc45")),
+    ("Uniswap Universal Router", address!("3fC91A3afd70395Cd496C647d5a6CC9D4B2b7FAD")),
+    ("Permit2", address!("000000000022D473030F116dDEE9F6B43aC78BA3")),
+    ("1inch Router v5", address!("1111111254EEB25477B68fb85Ed929f73A960582")),
+    ("1inch Router v6", address!("111111125421cA6dc452d289314280a0f8842A65")),
+    ("0x Exchange Proxy", address!("Def1C0ded9bec7F1a1670819833240f027b25EfF")),
+    ("ParaSwap TokenTransferProxy", address!("216B4B4Ba9F3e719726886d34a177484278Bfcae")),
+    ("CoW Protocol Vault Relayer", address!("C92E8bdf79f0507f65a392b0ab4667716BFE0110")),
+    ("Aave V3 Pool", address!("87870Bca3F3fD6335C3F4ce8392D69350B4fA4E2")),
+    ("Spark Pool", address!("C13e21B648A5Ee794902342038FF3aDAB66BE987")),
+    ("Morpho Blue", address!("BBBBBbbBBb9cC5e90e3b3Af64bdAF62C37EEFFCb")),
+    ("Balancer V2 Vault", address!("BA12222222228d8Ba445958a75a0704d566BF2C8")),
+];
+
+/// Widely held tokens checked for every wallet, on top of those in its positions
+pub const COMMON_TOKENS: &[Address] = &[
+    address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"), // WETH
+    address!("A0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"), // USDC
+    address!("dAC17F958D2ee523a2206206994597C13D831ec7"), // USDT
+    address!("6B175474E89094C44Da98b954EedeAC495271d0F"), // DAI
+    address!("2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599"), // WBTC
+    address!("ae7ab96520DE3A18E5e111B5EaAb095312D7fE84"), // stETH
+    address!("7f39C581F595B53c5cb19bD0b3f8dA6c935E2Ca0"), // wstETH
+    address!("ae78736Cd615f374D3085123A210448E74Fc6393"), // rETH
+];
+
+/// How dangerous a lingering approval is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalSeverity {
+    /// Far more than the wallet holds, e.g. left over from a past trade
+    Excessive,
+    /// Unlimited: the spender can take any balance the wallet ever holds
+    Unlimited,
+}
+
+/// A flagged allowance from the wallet to a known spender
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TokenApproval {
+    pub token: Address,
+    pub token_symbol: String,
+    pub spender: Address,
+    pub spender_name: &'static str,
+    /// Raw allowance in base units
+    pub allowance: String,
+    /// Allowance in whole tokens; `None` when unlimited
+    pub allowance_amount: Option<f64>,
+    /// Wallet balance in whole tokens
+    pub balance: f64,
+    pub severity: ApprovalSeverity,
+}
+
+/// Allowances at or above 2^128 are treated as unlimited; some tokens and UIs approve
+/// `type(uint160).max` or decrement from `U256::MAX` rather than using it exactly
+fn is_unlimited(allowance: U256) -> bool {
+    allowance >= U256::from(1u8) << 128
+}
+
+/// Severity of an allowance against the wallet's balance (both in base units), or
+/// `None` when it isn't worth flagging
+pub fn classify_allowance(allowance: U256, balance: U256, excessive_multiple: u64) -> Option<ApprovalSeverity> {
+    if allowance.is_zero() {
+        None
+    } else if is_unlimited(allowance) {
+        Some(ApprovalSeverity::Unlimited)
+    } else if allowance > balance.saturating_mul(U256::from(excessive_multiple)) {
+        Some(ApprovalSeverity::Excessive)
+    } else {
+        None
+    }
+}
+
+/// Reads a wallet's allowances to well-known protocol spenders and flags the risky ones
+pub struct ApprovalScanner {
+    client: EthereumClient,
+    spenders: Vec<(&'static str, Address)>,
+    token_metadata: Arc<TokenMetadataCache>,
+    /// Allowances above this multiple of the balance are flagged as excessive
+    excessive_multiple: u64,
+}
+
+impl ApprovalScanner {
+    const DEFAULT_EXCESSIVE_MULTIPLE: u64 = 10;
+
+    pub fn new(client: EthereumClient) -> Self {
+        Self {
+            client,
+            spenders: KNOWN_SPENDERS.to_vec(),
+            token_metadata: TokenMetadataCache::global(),
+            excessive_multiple: Self::DEFAULT_EXCESSIVE_MULTIPLE,
+        }
+    }
+
+    /// Tokens worth checking for a wallet: [`COMMON_TOKENS`] plus every `token_address`
+    /// its positions report
+    pub fn tokens_for(positions: &[Position]) -> Vec<Address> {
+        let mut tokens: BTreeSet<Address> = COMMON_TOKENS.iter().copied().collect();
+        tokens.extend(positions.iter().filter_map(|position| {
+            let token = position.metadata.get("token_address")?.as_str()?;
+            Address::from_str(token).ok()
+        }));
+        tokens.into_iter().collect()
+    }
+
+    /// Flagged approvals from `owner` across `tokens`, unlimited ones first. Tokens that
+    /// can't be read (not ERC-20s, reverted calls) are skipped.
+    pub async fn scan(&self, owner: Address, tokens: &[Address]) -> Vec<TokenApproval> {
+        let results = join_all(tokens.iter().map(|token| self.scan_token(owner, *token))).await;
+
+        let mut approvals = Vec::new();
+        for (token, result) in tokens.iter().zip(results) {
+            match result {
+                Ok(found) => approvals.extend(found),
+                Err(e) => tracing::debug!("Skipping approvals of token {:?}: {}", token, e),
+            }
+        }
+
+        approvals.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.token_symbol.cmp(&b.token_symbol)));
+        approvals
+    }
+
+    async fn scan_token(&self, owner: Address, token: Address) -> Result<Vec<TokenApproval>, AdapterError> {
+        let allowances = join_all(self.spenders.iter().map(|(_, spender)| async move {
+            self.client.call(token, &IERC20::allowanceCall { owner, spender: *spender }).await
+        }))
+        .await;
+
+        let flagged: Vec<(usize, U256)> = allowances
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, result)| Some((index, result.ok()?._0)))
+            .filter(|(_, allowance)| !allowance.is_zero())
+            .collect();
+        if flagged.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (balance, metadata) = tokio::try_join!(
+            self.client.call(token, &IERC20::balanceOfCall { account: owner }),
+            self.token_metadata.get_or_fetch(&self.client, CHAIN_ID, token),
+        )?;
+        let balance = balance._0;
+
+        Ok(flagged
+            .into_iter()
+            .filter_map(|(index, allowance)| {
+                let severity = classify_allowance(allowance, balance, self.excessive_multiple)?;
+                let (spender_name, spender) = self.spenders[index];
+                Some(TokenApproval {
+                    token,
+                    token_symbol: metadata.symbol.clone(),
+                    spender,
+                    spender_name,
+                    allowance: allowance.to_string(),
+                    allowance_amount: (severity != ApprovalSeverity::Unlimited)
+                        .then(|| normalize_amount(allowance, metadata.decimals)),
+                    balance: normalize_amount(balance, metadata.decimals),
+                    severity,
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowances_are_classified_against_balance() {
+        let balance = U256::from(1_000u64);
+
+        assert_eq!(classify_allowance(U256::MAX, balance, 10), Some(ApprovalSeverity::Unlimited));
+        assert_eq!(classify_allowance(U256::MAX - U256::from(5u8), balance, 10), Some(ApprovalSeverity::Unlimited));
+        assert_eq!(classify_allowance(U256::from(50_000u64), balance, 10), Some(ApprovalSeverity::Excessive));
+        assert_eq!(classify_allowance(U256::from(5_000u64), balance, 10), None);
+        assert_eq!(classify_allowance(U256::from(1u8), U256::ZERO, 10), Some(ApprovalSeverity::Excessive));
+        assert_eq!(classify_allowance(U256::ZERO, balance, 10), None);
+    }
+
+    #[test]
+    fn test_position_tokens_are_added_to_common_tokens() {
+        let position = Position {
+            id: "supply".to_string(),
+            protocol: "aave_v3".to_string(),
+            position_type: "supply".to_string(),
+            pair: "LINK".to_string(),
+            value_usd: 1_000.0,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({ "token_address": "0x514910771AF9Ca656af840dff83E8264EcF986CA" }),
+            last_updated: 0,
+        };
+
+        let tokens = ApprovalScanner::tokens_for(&[position]);
+        assert_eq!(tokens.len(), COMMON_TOKENS.len() + 1);
+        assert!(tokens.contains(&address!("514910771AF9Ca656af840dff83E8264EcF986CA")));
+    }
+}
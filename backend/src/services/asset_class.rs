@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+
+use crate::adapters::traits::Position;
+use crate::services::position_aggregator::underlying_tokens;
+
+pub const ETH_CORRELATED: &[&str] = &[
+    "ETH", "WETH", "STETH", "WSTETH", "RETH", "CBETH", "EETH", "WEETH", "FRXETH", "SFRXETH",
+];
+pub const BTC_CORRELATED: &[&str] = &["BTC", "WBTC", "CBBTC", "TBTC"];
+pub const STABLECOINS: &[&str] = &[
+    "USDC", "USDT", "DAI", "FRAX", "LUSD", "GHO", "USDE", "PYUSD", "CRVUSD", "USDS", "SDAI", "SUSDE",
+];
+
+/// What kind of market exposure a position carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetClass {
+    Stablecoin,
+    EthCorrelated,
+    BtcCorrelated,
+    Alt,
+    /// Liquidity provision, exposed to both legs and impermanent loss
+    Lp,
+    /// Perps and other leveraged derivatives
+    Derivative,
+}
+
+impl AssetClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stablecoin => "stablecoin",
+            Self::EthCorrelated => "eth_correlated",
+            Self::BtcCorrelated => "btc_correlated",
+            Self::Alt => "alt",
+            Self::Lp => "lp",
+            Self::Derivative => "derivative",
+        }
+    }
+
+    /// Class of a single token symbol, compared case-insensitively
+    pub fn of_token(symbol: &str) -> Self {
+        let symbol = symbol.trim().to_uppercase();
+        if STABLECOINS.contains(&symbol.as_str()) {
+            Self::Stablecoin
+        } else if ETH_CORRELATED.contains(&symbol.as_str()) {
+            Self::EthCorrelated
+        } else if BTC_CORRELATED.contains(&symbol.as_str()) {
+            Self::BtcCorrelated
+        } else {
+            Self::Alt
+        }
+    }
+}
+
+/// Asset class of a position, from its type first and its underlying token otherwise
+pub fn classify(position: &Position) -> AssetClass {
+    match position.position_type.as_str() {
+        "perp" => return AssetClass::Derivative,
+        "liquidity" | "yield_farming" => return AssetClass::Lp,
+        _ => {}
+    }
+
+    underlying_tokens(position)
+        .first()
+        .map_or(AssetClass::Alt, |token| AssetClass::of_token(token))
+}
+
+/// Key positions can be grouped by in the portfolio response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    AssetClass,
+    Protocol,
+    Chain,
+}
+
+impl GroupBy {
+    /// Group a position falls into
+    pub fn key(&self, position: &Position) -> String {
+        match self {
+            Self::AssetClass => classify(position).as_str().to_string(),
+            Self::Protocol => position.protocol.clone(),
+            Self::Chain => position.metadata
+                .get("chain_id")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(1)
+                .to_string(),
+        }
+    }
+}
+
+/// Subtotals of one group of positions
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PositionGroup {
+    pub key: String,
+    pub position_count: usize,
+    /// Excludes positions whose token price is unknown
+    pub total_value_usd: f64,
+    pub total_pnl_usd: f64,
+}
+
+/// Per-group subtotals, largest group by value first
+pub fn group_totals(positions: &[Position], group_by: GroupBy) -> Vec<PositionGroup> {
+    let mut groups: Vec<PositionGroup> = Vec::new();
+    for position in positions {
+        let key = group_by.key(position);
+        let index = match groups.iter().position(|group| group.key == key) {
+            Some(index) => index,
+            None => {
+                groups.push(PositionGroup { key, position_count: 0, total_value_usd: 0.0, total_pnl_usd: 0.0 });
+                groups.len() - 1
+            }
+        };
+
+        let group = &mut groups[index];
+        group.position_count += 1;
+        if !position.is_price_unknown() {
+            group.total_value_usd += position.value_usd;
+            group.total_pnl_usd += position.pnl_usd;
+        }
+    }
+
+    groups.sort_by(|a, b| b.total_value_usd.partial_cmp(&a.total_value_usd).unwrap_or(std::cmp::Ordering::Equal));
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(protocol: &str, position_type: &str, pair: &str, value_usd: f64) -> Position {
+        Position {
+            id: pair.to_string(),
+            protocol: protocol.to_string(),
+            position_type: position_type.to_string(),
+            pair: pair.to_string(),
+            value_usd,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({ "chain_id": 1 }),
+            last_updated: 0,
+        }
+    }
+
+    #[test]
+    fn test_positions_are_classified_by_type_then_token() {
+        assert_eq!(classify(&position("lido", "staking", "stETH/ETH", 1.0)), AssetClass::EthCorrelated);
+        assert_eq!(classify(&position("aave_v3", "supply", "USDC", 1.0)), AssetClass::Stablecoin);
+        assert_eq!(classify(&position("aave_v3", "supply", "WBTC", 1.0)), AssetClass::BtcCorrelated);
+        assert_eq!(classify(&position("uniswap_v3", "liquidity", "USDC/WETH", 1.0)), AssetClass::Lp);
+        assert_eq!(classify(&position("gmx", "perp", "ETH/USD", 1.0)), AssetClass::Derivative);
+        assert_eq!(classify(&position("aave_v3", "supply", "PEPE", 1.0)), AssetClass::Alt);
+    }
+
+    #[test]
+    fn test_group_totals_sum_each_class() {
+        let positions = vec![
+            position("aave_v3", "supply", "USDC", 3_000.0),
+            position("spark", "supply", "DAI", 2_000.0),
+            position("lido", "staking", "stETH/ETH", 4_000.0),
+        ];
+
+        let groups = group_totals(&positions, GroupBy::AssetClass);
+        assert_eq!(groups[0].key, "stablecoin");
+        assert_eq!(groups[0].total_value_usd, 5_000.0);
+        assert_eq!(groups[0].position_count, 2);
+        assert_eq!(groups[1].key, "eth_correlated");
+
+        assert_eq!(group_totals(&positions, GroupBy::Chain).len(), 1);
+    }
+}
@@ -0,0 +1,155 @@
+use alloy::primitives::{keccak256, Address, B256};
+use alloy::sol;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::adapters::traits::AdapterError;
+use crate::blockchain::EthereumClient;
+
+sol! {
+    interface IEnsRegistry {
+        function resolver(bytes32 node) external view returns (address);
+    }
+
+    interface IEnsResolver {
+        function addr(bytes32 node) external view returns (address);
+        function name(bytes32 node) external view returns (string memory);
+    }
+}
+
+/// ENS registry, same address on mainnet and testnets
+const ENS_REGISTRY_ADDRESS: Address = Address::new([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x2e, 0x07, 0x4e, 0xc6,
+    0x9a, 0x0d, 0xfb, 0x29, 0x97, 0xba, 0x6c, 0x7d, 0x2e, 0x1e,
+]);
+
+/// Names can be transferred or re-pointed, so lookups are refreshed hourly
+const CACHE_TTL: Duration = Duration::from_secs(3600);
+
+/// Forward (`name -> address`) and reverse (`address -> primary name`) ENS lookups.
+///
+/// Both directions are cached, including misses, since most wallets have no primary
+/// name and the lookup costs several RPC calls. RPC failures are not cached.
+#[derive(Debug, Default)]
+pub struct EnsResolver {
+    forward: RwLock<HashMap<String, (Option<Address>, Instant)>>,
+    reverse: RwLock<HashMap<Address, (Option<String>, Instant)>>,
+}
+
+impl EnsResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process-wide resolver shared by all handlers
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<EnsResolver>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::new())).clone()
+    }
+
+    /// Address `name` points to, or `None` when it has no resolver or address record
+    pub async fn resolve(&self, client: &EthereumClient, name: &str) -> Result<Option<Address>, AdapterError> {
+        let name = name.trim().to_lowercase();
+        if let Some((address, cached_at)) = self.forward.read().unwrap().get(&name) {
+            if cached_at.elapsed() < CACHE_TTL {
+                return Ok(*address);
+            }
+        }
+
+        let node = namehash(&name);
+        let address = match Self::resolver(client, node).await? {
+            Some(resolver) => {
+                let address = client.call(resolver, &IEnsResolver::addrCall { node }).await?._0;
+                (address != Address::ZERO).then_some(address)
+            }
+            None => None,
+        };
+
+        self.forward.write().unwrap().insert(name, (address, Instant::now()));
+        Ok(address)
+    }
+
+    /// Primary ENS name for `address`. The reverse record is only trusted when the name
+    /// forward-resolves back to the same address, since anyone can set any reverse name.
+    pub async fn reverse_resolve(&self, client: &EthereumClient, address: Address) -> Option<String> {
+        if let Some((name, cached_at)) = self.reverse.read().unwrap().get(&address) {
+            if cached_at.elapsed() < CACHE_TTL {
+                return name.clone();
+            }
+        }
+
+        match self.lookup_primary_name(client, address).await {
+            Ok(name) => {
+                self.reverse.write().unwrap().insert(address, (name.clone(), Instant::now()));
+                name
+            }
+            Err(e) => {
+                tracing::debug!("Reverse ENS lookup for {:?} failed: {}", address, e);
+                None
+            }
+        }
+    }
+
+    async fn lookup_primary_name(&self, client: &EthereumClient, address: Address) -> Result<Option<String>, AdapterError> {
+        let node = reverse_node(address);
+        let Some(resolver) = Self::resolver(client, node).await? else {
+            return Ok(None);
+        };
+
+        let name = client.call(resolver, &IEnsResolver::nameCall { node }).await?._0;
+        if name.is_empty() {
+            return Ok(None);
+        }
+
+        let verified = self.resolve(client, &name).await? == Some(address);
+        Ok(verified.then_some(name))
+    }
+
+    async fn resolver(client: &EthereumClient, node: B256) -> Result<Option<Address>, AdapterError> {
+        let resolver = client.call(ENS_REGISTRY_ADDRESS, &IEnsRegistry::resolverCall { node }).await?._0;
+        Ok((resolver != Address::ZERO).then_some(resolver))
+    }
+}
+
+/// EIP-137 namehash. Labels are lowercased but not otherwise UTS-46 normalized.
+pub fn namehash(name: &str) -> B256 {
+    name.rsplit('.')
+        .filter(|label| !label.is_empty())
+        .fold(B256::ZERO, |node, label| {
+            let label_hash = keccak256(label.to_lowercase().as_bytes());
+            keccak256([node.as_slice(), label_hash.as_slice()].concat())
+        })
+}
+
+/// Node of `<hex address>.addr.reverse`
+fn reverse_node(address: Address) -> B256 {
+    namehash(&format!("{:x}.addr.reverse", address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_namehash_matches_eip137() {
+        assert_eq!(namehash(""), B256::ZERO);
+        assert_eq!(
+            namehash("eth"),
+            B256::from_str("0x93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae").unwrap()
+        );
+        assert_eq!(
+            namehash("foo.eth"),
+            B256::from_str("0xde9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_registry_address() {
+        assert_eq!(
+            ENS_REGISTRY_ADDRESS,
+            Address::from_str("0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e").unwrap()
+        );
+    }
+}
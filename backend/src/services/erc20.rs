@@ -0,0 +1,14 @@
+use alloy::sol;
+
+// Standard ERC20 interface shared by all adapters
+sol! {
+    #[sol(rpc)]
+    interface IERC20 {
+        function balanceOf(address account) external view returns (uint256);
+        function totalSupply() external view returns (uint256);
+        function decimals() external view returns (uint8);
+        function symbol() external view returns (string memory);
+        function name() external view returns (string memory);
+        function allowance(address owner, address spender) external view returns (uint256);
+    }
+}
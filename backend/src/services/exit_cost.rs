@@ -0,0 +1,312 @@
+use alloy::{
+    primitives::{address, Address, U256},
+    sol,
+    sol_types::SolCall,
+};
+use futures::future::join_all;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+
+use crate::adapters::traits::Position;
+use crate::blockchain::EthereumClient;
+use crate::services::position_aggregator::position_apy;
+use crate::services::PriceAggregator;
+
+/// Uniswap V3 NonfungiblePositionManager, at the same address on every chain it's on
+const UNISWAP_V3_POSITION_MANAGER: Address = address!("C36442b4a4522E871399CD717aBDD847Ab11FE88");
+
+sol! {
+    interface IExitPositionManager {
+        struct DecreaseLiquidityParams {
+            uint256 tokenId;
+            uint128 liquidity;
+            uint256 amount0Min;
+            uint256 amount1Min;
+            uint256 deadline;
+        }
+
+        function decreaseLiquidity(DecreaseLiquidityParams calldata params) external payable returns (uint256 amount0, uint256 amount1);
+    }
+
+    interface IExitVault {
+        function withdraw(uint256 maxShares) external returns (uint256);
+        function redeem(uint256 shares, address receiver, address owner) external returns (uint256);
+        function withdrawAll() external;
+    }
+}
+
+/// How a position's exit gas was arrived at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GasSource {
+    /// `eth_estimateGas` on the owner's exit transaction
+    Estimated,
+    /// Typical gas for the position type, when no exit call can be simulated
+    Typical,
+}
+
+/// Gas price on a chain and the USD price of the token gas is paid in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasQuote {
+    pub gas_price_wei: u128,
+    pub native_price_usd: f64,
+}
+
+impl GasQuote {
+    pub fn cost_usd(&self, gas_units: u64) -> f64 {
+        gas_units as f64 * self.gas_price_wei as f64 / 1e18 * self.native_price_usd
+    }
+}
+
+/// What closing a position would cost in gas. On rollups this is the execution fee
+/// only; the L1 data fee comes on top.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ExitCost {
+    pub gas_units: u64,
+    pub gas_source: GasSource,
+    pub gas_price_gwei: f64,
+    pub exit_cost_usd: f64,
+}
+
+/// When an exit cost is worth warning about
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExitCostPolicy {
+    /// Exit cost, as a fraction of position value, above which the position should be
+    /// judged net of that cost
+    pub warning_ratio: f64,
+}
+
+impl Default for ExitCostPolicy {
+    fn default() -> Self {
+        Self { warning_ratio: 0.05 }
+    }
+}
+
+impl ExitCostPolicy {
+    /// Default overridden by `EXIT_COST_WARNING_RATIO`
+    pub fn from_env() -> Self {
+        let warning_ratio = std::env::var("EXIT_COST_WARNING_RATIO")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|ratio: &f64| *ratio >= 0.0)
+            .unwrap_or(Self::default().warning_ratio);
+        Self { warning_ratio }
+    }
+}
+
+/// Exit costs added up across a portfolio
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ExitCostSummary {
+    pub total_exit_cost_usd: f64,
+    /// Positions whose exit cost is material relative to their value
+    pub net_of_exit_cost_positions: usize,
+    /// Positions that would cost more in gas to close than they're worth
+    pub uneconomical_positions: usize,
+}
+
+/// CoinGecko id of the token gas is paid in on `chain_id`
+pub fn native_coin_id(chain_id: u64) -> &'static str {
+    match chain_id {
+        137 => "matic-network",
+        250 => "fantom",
+        43114 => "avalanche-2",
+        _ => "ethereum",
+    }
+}
+
+/// Gas a typical exit of this kind of position takes, for positions whose exit
+/// transaction can't be simulated
+pub fn typical_exit_gas(position: &Position) -> u64 {
+    let position_type = position.position_type.to_lowercase();
+    match position.protocol.as_str() {
+        // decreaseLiquidity, collect and usually burn
+        "uniswap_v3" => 250_000,
+        _ if position_type.contains("liquidity") => 180_000,
+        _ if position_type.contains("borrow") => 200_000,
+        _ if position_type.contains("stak") => 120_000,
+        _ => 150_000,
+    }
+}
+
+/// The owner's transaction closing `position`, where the adapter reports enough to build it
+fn exit_call(position: &Position, owner: Address) -> Option<(Address, Vec<u8>)> {
+    let metadata = &position.metadata;
+    let text = |key: &str| metadata.get(key).and_then(|v| v.as_str());
+
+    match position.protocol.as_str() {
+        "uniswap_v3" => {
+            let liquidity = text("liquidity")?.parse::<u128>().ok().filter(|l| *l > 0)?;
+            let params = IExitPositionManager::DecreaseLiquidityParams {
+                tokenId: U256::from_str(text("token_id")?).ok()?,
+                liquidity,
+                amount0Min: U256::ZERO,
+                amount1Min: U256::ZERO,
+                deadline: U256::MAX,
+            };
+            Some((UNISWAP_V3_POSITION_MANAGER, IExitPositionManager::decreaseLiquidityCall { params }.abi_encode()))
+        }
+        "yearn_finance" => {
+            let vault = Address::from_str(text("vault_address")?).ok()?;
+            let shares = U256::from_str(text("shares")?).ok()?;
+            let calldata = if metadata.get("erc4626").and_then(|v| v.as_bool()) == Some(true) {
+                IExitVault::redeemCall { shares, receiver: owner, owner }.abi_encode()
+            } else {
+                IExitVault::withdrawCall { maxShares: shares }.abi_encode()
+            };
+            Some((vault, calldata))
+        }
+        "beefy" => Some((Address::from_str(text("vault_address")?).ok()?, IExitVault::withdrawAllCall {}.abi_encode())),
+        _ => None,
+    }
+}
+
+/// Gas to close `position`: the owner's simulated exit when it can be built and
+/// doesn't revert, else the typical amount for its type
+pub async fn exit_gas(client: &EthereumClient, position: &Position, owner: Address) -> (u64, GasSource) {
+    if let Some((to, calldata)) = exit_call(position, owner) {
+        match client.estimate_gas(owner, to, &calldata, U256::ZERO).await {
+            Ok(gas) => return (gas, GasSource::Estimated),
+            Err(e) => tracing::debug!("Exit gas estimate for {} failed: {}", position.id, e),
+        }
+    }
+    (typical_exit_gas(position), GasSource::Typical)
+}
+
+/// Write `estimated_exit_gas_usd` and `net_of_exit_cost` into a position's metadata,
+/// with `net_yield_1y_usd` when the position reports an APY
+pub fn annotate_exit_cost(position: &mut Position, cost: &ExitCost, policy: &ExitCostPolicy) {
+    let value = position.value_usd.abs();
+    let net_yield_1y = position_apy(position)
+        .filter(|_| position.value_usd > 0.0)
+        .map(|apy| position.value_usd * apy / 100.0 - cost.exit_cost_usd);
+
+    if let Some(metadata) = position.metadata.as_object_mut() {
+        metadata.insert("estimated_exit_gas_usd".to_string(), serde_json::json!(cost.exit_cost_usd));
+        metadata.insert("exit_gas_units".to_string(), serde_json::json!(cost.gas_units));
+        metadata.insert("exit_gas_source".to_string(), serde_json::json!(cost.gas_source));
+        metadata.insert("exit_gas_price_gwei".to_string(), serde_json::json!(cost.gas_price_gwei));
+        metadata.insert("net_of_exit_cost".to_string(), serde_json::json!(cost.exit_cost_usd > value * policy.warning_ratio));
+        metadata.insert("uneconomical_exit".to_string(), serde_json::json!(cost.exit_cost_usd >= value));
+        if let Some(net_yield_1y) = net_yield_1y {
+            metadata.insert("net_yield_1y_usd".to_string(), serde_json::json!(net_yield_1y));
+        }
+    }
+}
+
+/// Estimate and record the exit cost of every position `owner` holds, reading gas
+/// prices from each position's chain. Chains without a client or gas price are skipped.
+pub async fn estimate_exit_costs(
+    positions: &mut [Position],
+    owner: Address,
+    clients: &HashMap<u64, EthereumClient>,
+    policy: &ExitCostPolicy,
+) -> ExitCostSummary {
+    let chain_of = |position: &Position| position.metadata.get("chain_id").and_then(|v| v.as_u64()).unwrap_or(1);
+    let chain_ids: BTreeSet<u64> = positions.iter().map(chain_of).collect();
+
+    let quotes: HashMap<u64, GasQuote> = join_all(chain_ids.into_iter().map(|chain_id| async move {
+        let client = clients.get(&chain_id)?;
+        let (gas_price, native_price) = tokio::join!(
+            client.gas_price(),
+            PriceAggregator::global().get_price(native_coin_id(chain_id)),
+        );
+        match (gas_price, native_price) {
+            (Ok(gas_price_wei), Ok(native_price_usd)) => Some((chain_id, GasQuote { gas_price_wei, native_price_usd })),
+            (Err(e), _) | (_, Err(e)) => {
+                tracing::warn!("No gas quote for chain {}: {}", chain_id, e);
+                None
+            }
+        }
+    }))
+    .await
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let gas: Vec<Option<(u64, GasSource)>> = join_all(positions.iter().map(|position| async {
+        let client = clients.get(&chain_of(position)).filter(|_| quotes.contains_key(&chain_of(position)))?;
+        Some(exit_gas(client, position, owner).await)
+    }))
+    .await;
+
+    let mut summary = ExitCostSummary::default();
+    for (position, gas) in positions.iter_mut().zip(gas) {
+        let (Some((gas_units, gas_source)), Some(quote)) = (gas, quotes.get(&chain_of(position))) else {
+            continue;
+        };
+        let cost = ExitCost {
+            gas_units,
+            gas_source,
+            gas_price_gwei: quote.gas_price_wei as f64 / 1e9,
+            exit_cost_usd: quote.cost_usd(gas_units),
+        };
+        annotate_exit_cost(position, &cost, policy);
+
+        summary.total_exit_cost_usd += cost.exit_cost_usd;
+        if position.metadata.get("net_of_exit_cost").and_then(|v| v.as_bool()) == Some(true) {
+            summary.net_of_exit_cost_positions += 1;
+        }
+        if cost.exit_cost_usd >= position.value_usd.abs() {
+            summary.uneconomical_positions += 1;
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault(value_usd: f64, apy: f64) -> Position {
+        Position {
+            id: "beefy:1:vault:0x0:0x0".to_string(),
+            protocol: "beefy".to_string(),
+            position_type: "vault".to_string(),
+            pair: "WETH-USDC".to_string(),
+            value_usd,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({ "apy": apy }),
+            last_updated: 0,
+        }
+    }
+
+    #[test]
+    fn test_dust_position_costs_more_to_exit_than_it_earns() {
+        // 150k gas at 20 gwei with ETH at $3,000 is $9
+        let quote = GasQuote { gas_price_wei: 20_000_000_000, native_price_usd: 3_000.0 };
+        let cost = ExitCost {
+            gas_units: 150_000,
+            gas_source: GasSource::Typical,
+            gas_price_gwei: 20.0,
+            exit_cost_usd: quote.cost_usd(150_000),
+        };
+        assert!((cost.exit_cost_usd - 9.0).abs() < 1e-9);
+
+        let mut dust = vault(50.0, 4.0);
+        annotate_exit_cost(&mut dust, &cost, &ExitCostPolicy::default());
+        assert_eq!(dust.metadata["net_of_exit_cost"], true);
+        assert_eq!(dust.metadata["uneconomical_exit"], false);
+        assert!((dust.metadata["net_yield_1y_usd"].as_f64().unwrap() - (2.0 - 9.0)).abs() < 1e-9);
+
+        let mut large = vault(50_000.0, 4.0);
+        annotate_exit_cost(&mut large, &cost, &ExitCostPolicy::default());
+        assert_eq!(large.metadata["net_of_exit_cost"], false);
+    }
+
+    #[test]
+    fn test_exit_calls_built_from_metadata() {
+        let owner = Address::repeat_byte(1);
+        let mut lp = vault(100.0, 0.0);
+        lp.protocol = "uniswap_v3".to_string();
+        lp.metadata = serde_json::json!({ "token_id": "42", "liquidity": "1000" });
+        let (to, calldata) = exit_call(&lp, owner).unwrap();
+        assert_eq!(to, UNISWAP_V3_POSITION_MANAGER);
+        assert_eq!(calldata[..4], IExitPositionManager::decreaseLiquidityCall::SELECTOR);
+
+        lp.metadata["liquidity"] = serde_json::json!("0");
+        assert!(exit_call(&lp, owner).is_none());
+        assert_eq!(typical_exit_gas(&lp), 250_000);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::adapters::traits::Position;
+use crate::risk::position_risk_score;
+
+const CSV_HEADER: &str = "protocol,position_type,pair,value_usd,pnl_usd,pnl_percentage,risk_score,last_updated";
+
+/// Serialize positions as RFC 4180 CSV. `risk_score` is left empty for positions
+/// whose adapter doesn't report one.
+pub fn positions_to_csv(positions: &[Position]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push_str("\r\n");
+
+    for position in positions {
+        let risk_score = if position.metadata.get("risk_score").is_some() {
+            position_risk_score(position).to_string()
+        } else {
+            String::new()
+        };
+
+        let row = [
+            escape_field(&position.protocol),
+            escape_field(&position.position_type),
+            escape_field(&position.pair),
+            position.value_usd.to_string(),
+            position.pnl_usd.to_string(),
+            position.pnl_percentage.to_string(),
+            risk_score,
+            position.last_updated.to_string(),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+
+    csv
+}
+
+/// Quote a field if it contains a delimiter, quote or line break, doubling inner quotes
+fn escape_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_with_commas_and_quotes_are_escaped() {
+        let position = Position {
+            id: "lp".to_string(),
+            protocol: "balancer_v2".to_string(),
+            position_type: "liquidity".to_string(),
+            pair: "wstETH,\"WETH\"".to_string(),
+            value_usd: 1500.5,
+            pnl_usd: -2.0,
+            pnl_percentage: -0.1,
+            metadata: serde_json::json!({ "risk_score": 0.25 }),
+            last_updated: 1_700_000_000,
+        };
+
+        let csv = positions_to_csv(&[position]);
+        let mut lines = csv.lines();
+
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some("balancer_v2,liquidity,\"wstETH,\"\"WETH\"\"\",1500.5,-2,-0.1,0.25,1700000000")
+        );
+    }
+}
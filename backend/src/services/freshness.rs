@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+
+use crate::adapters::traits::Position;
+
+/// How far behind the chain head a position's data may be before it's marked stale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StalenessPolicy {
+    pub max_age_blocks: u64,
+    /// Per-chain overrides, for chains whose block times differ a lot from mainnet's
+    pub per_chain: HashMap<u64, u64>,
+}
+
+impl Default for StalenessPolicy {
+    fn default() -> Self {
+        Self {
+            max_age_blocks: 25,
+            per_chain: HashMap::new(),
+        }
+    }
+}
+
+impl StalenessPolicy {
+    /// `STALE_POSITION_BLOCKS` (default 25), overridden per chain by
+    /// `STALE_POSITION_BLOCKS_<chain id>`, e.g. `STALE_POSITION_BLOCKS_42161=1200`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let max_age_blocks = std::env::var("STALE_POSITION_BLOCKS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.max_age_blocks);
+        let per_chain = std::env::vars()
+            .filter_map(|(key, value)| {
+                let chain_id = key.strip_prefix("STALE_POSITION_BLOCKS_")?.parse().ok()?;
+                Some((chain_id, value.parse().ok()?))
+            })
+            .collect();
+
+        Self { max_age_blocks, per_chain }
+    }
+
+    pub fn max_age_blocks(&self, chain_id: u64) -> u64 {
+        self.per_chain.get(&chain_id).copied().unwrap_or(self.max_age_blocks)
+    }
+}
+
+/// How many blocks behind the head positions are read at, so state a reorg could still
+/// undo isn't reported. Off unless `REQUIRE_CONFIRMATIONS` is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfirmationPolicy {
+    pub require_confirmations: bool,
+    pub confirmation_blocks: u64,
+    /// Per-chain depths, e.g. deeper for an L2 prone to reorgs or 0 to keep a chain at its head
+    pub per_chain: HashMap<u64, u64>,
+}
+
+impl Default for ConfirmationPolicy {
+    fn default() -> Self {
+        Self {
+            require_confirmations: false,
+            confirmation_blocks: 3,
+            per_chain: HashMap::new(),
+        }
+    }
+}
+
+impl ConfirmationPolicy {
+    /// `REQUIRE_CONFIRMATIONS=true` enables it with `CONFIRMATION_BLOCKS` (default 3),
+    /// overridden per chain by `CONFIRMATION_BLOCKS_<chain id>`, e.g. `CONFIRMATION_BLOCKS_8453=30`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let require_confirmations = std::env::var("REQUIRE_CONFIRMATIONS")
+            .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(defaults.require_confirmations);
+        let confirmation_blocks = std::env::var("CONFIRMATION_BLOCKS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.confirmation_blocks);
+        let per_chain = std::env::vars()
+            .filter_map(|(key, value)| {
+                let chain_id = key.strip_prefix("CONFIRMATION_BLOCKS_")?.parse().ok()?;
+                Some((chain_id, value.parse().ok()?))
+            })
+            .collect();
+
+        Self { require_confirmations, confirmation_blocks, per_chain }
+    }
+
+    /// Blocks behind the head `chain_id` is read at; 0 when confirmations aren't required
+    pub fn confirmation_blocks(&self, chain_id: u64) -> u64 {
+        if !self.require_confirmations {
+            return 0;
+        }
+        self.per_chain.get(&chain_id).copied().unwrap_or(self.confirmation_blocks)
+    }
+}
+
+/// Block a position was read at, from its `block_number` metadata
+pub fn position_block(position: &Position) -> Option<u64> {
+    position.metadata.get("block_number").and_then(|v| v.as_u64())
+}
+
+/// Record the block a position was read at, keeping one an adapter already set
+pub fn tag_block_number(position: &mut Position, block_number: u64) {
+    if let Some(metadata) = position.metadata.as_object_mut() {
+        metadata.entry("block_number").or_insert(serde_json::json!(block_number));
+    }
+}
+
+/// Record that a position was deliberately read `confirmations` blocks behind the head
+pub fn tag_confirmations(position: &mut Position, confirmations: u64) {
+    if let Some(metadata) = position.metadata.as_object_mut() {
+        metadata.insert("confirmations".to_string(), serde_json::json!(confirmations));
+    }
+}
+
+/// Write `data_age_blocks` and `stale` into a position's metadata given the current
+/// head of its chain. Positions without a recorded block or head are left untouched.
+/// Blocks a position was held back for confirmations don't count towards staleness.
+pub fn annotate_freshness(position: &mut Position, head: Option<u64>, policy: &StalenessPolicy) {
+    let (Some(block), Some(head)) = (position_block(position), head) else {
+        return;
+    };
+    let chain_id = position.metadata.get("chain_id").and_then(|v| v.as_u64()).unwrap_or(1);
+    let confirmations = position.metadata.get("confirmations").and_then(|v| v.as_u64()).unwrap_or(0);
+    let data_age_blocks = head.saturating_sub(block);
+    let stale = data_age_blocks.saturating_sub(confirmations) > policy.max_age_blocks(chain_id);
+
+    if let Some(metadata) = position.metadata.as_object_mut() {
+        metadata.insert("data_age_blocks".to_string(), serde_json::json!(data_age_blocks));
+        metadata.insert("stale".to_string(), serde_json::json!(stale));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(chain_id: u64) -> Position {
+        Position {
+            id: "1".to_string(),
+            protocol: "test".to_string(),
+            position_type: "supply".to_string(),
+            pair: "WETH".to_string(),
+            value_usd: 1.0,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({ "chain_id": chain_id }),
+            last_updated: 0,
+        }
+    }
+
+    #[test]
+    fn test_age_is_measured_against_the_chain_head() {
+        let policy = StalenessPolicy { max_age_blocks: 25, per_chain: HashMap::from([(42161, 1_200)]) };
+
+        let mut mainnet = position(1);
+        tag_block_number(&mut mainnet, 100);
+        tag_block_number(&mut mainnet, 200);
+        annotate_freshness(&mut mainnet, Some(130), &policy);
+        assert_eq!(mainnet.metadata["block_number"], 100);
+        assert_eq!(mainnet.metadata["data_age_blocks"], 30);
+        assert_eq!(mainnet.metadata["stale"], true);
+
+        let mut arbitrum = position(42161);
+        tag_block_number(&mut arbitrum, 1_000);
+        annotate_freshness(&mut arbitrum, Some(1_500), &policy);
+        assert_eq!(arbitrum.metadata["stale"], false);
+    }
+
+    #[test]
+    fn test_untagged_positions_are_left_alone() {
+        let mut untagged = position(1);
+        annotate_freshness(&mut untagged, Some(100), &StalenessPolicy::default());
+        assert!(untagged.metadata.get("stale").is_none());
+    }
+
+    #[test]
+    fn test_confirmation_depth_is_per_chain_and_not_stale() {
+        let mut policy = ConfirmationPolicy { per_chain: HashMap::from([(8453, 30)]), ..Default::default() };
+        assert_eq!(policy.confirmation_blocks(8453), 0);
+
+        policy.require_confirmations = true;
+        assert_eq!(policy.confirmation_blocks(1), 3);
+        assert_eq!(policy.confirmation_blocks(8453), 30);
+
+        let mut base = position(8453);
+        tag_block_number(&mut base, 970);
+        tag_confirmations(&mut base, 30);
+        annotate_freshness(&mut base, Some(1_000), &StalenessPolicy::default());
+        assert_eq!(base.metadata["data_age_blocks"], 30);
+        assert_eq!(base.metadata["stale"], false);
+    }
+}
@@ -0,0 +1,55 @@
+pub mod alert_feed;
+pub mod alert_service;
+pub mod approvals;
+pub mod asset_class;
+pub mod ens;
+pub mod erc20;
+pub mod exit_cost;
+pub mod export;
+pub mod freshness;
+pub mod monitoring;
+pub mod oracle_deviation;
+pub mod portfolio_diff;
+pub mod portfolio_history;
+pub mod portfolio_webhooks;
+pub mod position_aggregator;
+pub mod price_aggregator;
+pub mod price_history;
+pub mod price_service;
+pub mod price_snapshots;
+pub mod protocol_risk;
+pub mod protocol_tvl;
+pub mod report;
+pub mod risk_profiles;
+pub mod slashing;
+pub mod staked_lp;
+pub mod subgraph;
+pub mod token_metadata;
+pub mod value_drop;
+
+pub use alert_feed::{AlertFeed, AlertSubscription, LiveAlert, WalletReading};
+pub use asset_class::{AssetClass, GroupBy, PositionGroup};
+pub use approvals::{ApprovalScanner, ApprovalSeverity, TokenApproval};
+pub use alert_service::{AlertService, ChannelConfig, NotificationChannel, NotificationSettingsStore};
+pub use ens::EnsResolver;
+pub use freshness::{ConfirmationPolicy, StalenessPolicy};
+pub use erc20::IERC20;
+pub use exit_cost::{ExitCost, ExitCostPolicy, ExitCostSummary, GasSource};
+pub use monitoring::{HealthFactorThresholds, LiquidationMonitor};
+pub use oracle_deviation::{OracleDeviation, OracleDeviationMonitor};
+pub use portfolio_diff::{DiffThresholds, PortfolioDiff};
+pub use portfolio_history::{PortfolioHistoryStore, PortfolioSnapshot, PositionSnapshot};
+pub use portfolio_webhooks::{ChangeThresholds, PortfolioChangeEvent, PortfolioWebhooks, WebhookSubscription};
+pub use position_aggregator::{aggregate, AggregatedPortfolio};
+pub use price_aggregator::{AggregatedPrice, PriceAggregator, PriceSource, SourcePrice};
+pub use price_service::{with_price_timestamp, PriceService};
+pub use price_snapshots::{BalanceSemantics, EntryPnl, PriceSnapshot, PriceSnapshotStore};
+pub use protocol_risk::{ProtocolRisk, ProtocolRiskFactors, ProtocolRiskService, RiskDataSource};
+pub use protocol_tvl::{MarketContext, MarketShareLimit, ProtocolTvlService};
+pub use report::{PortfolioReport, ReportSignature, ReportSigner};
+pub use risk_profiles::{AlertThresholds, RiskProfileStore, UserRiskSettings};
+pub use slashing::{SlashingCheck, SlashingPolicy};
+pub use staked_lp::{PendingReward, StakedLp, StakedLpScanner, StakingContract, StakingKind};
+pub use subgraph::{subgraph_id_from_env, SubgraphClient, TransactionEvent};
+pub use token_metadata::{TokenMetadata, TokenMetadataCache};
+pub use value_drop::{ValueDrop, ValueDropTracker};
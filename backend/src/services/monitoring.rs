@@ -0,0 +1,229 @@
+use alloy::primitives::Address;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::adapters::traits::Position;
+
+/// Health factor band a lending position is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertLevel {
+    Healthy,
+    Warning,
+    Critical,
+}
+
+/// Health factor thresholds below which a position is flagged
+#[derive(Debug, Clone, Copy)]
+pub struct HealthFactorThresholds {
+    pub warning: f64,
+    pub critical: f64,
+}
+
+impl Default for HealthFactorThresholds {
+    fn default() -> Self {
+        Self {
+            warning: 1.5,
+            critical: 1.15,
+        }
+    }
+}
+
+impl HealthFactorThresholds {
+    /// Defaults overridden by `HEALTH_FACTOR_WARNING` / `HEALTH_FACTOR_CRITICAL`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |key: &str, default: f64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            warning: read("HEALTH_FACTOR_WARNING", defaults.warning),
+            critical: read("HEALTH_FACTOR_CRITICAL", defaults.critical),
+        }
+    }
+
+    pub fn level(&self, health_factor: f64) -> AlertLevel {
+        if health_factor < self.critical {
+            AlertLevel::Critical
+        } else if health_factor < self.warning {
+            AlertLevel::Warning
+        } else {
+            AlertLevel::Healthy
+        }
+    }
+}
+
+/// Payload sent to the alert webhook
+#[derive(Debug, Clone, Serialize)]
+pub struct LiquidationAlert {
+    pub wallet: String,
+    pub position_id: String,
+    pub protocol: String,
+    pub asset: String,
+    pub level: AlertLevel,
+    pub health_factor: f64,
+    pub liquidation_price: Option<f64>,
+    pub timestamp: String,
+}
+
+/// Watches lending position health factors and alerts when a threshold is crossed.
+///
+/// Alerts fire only on transitions into a worse or better non-healthy band, so a
+/// position that stays below a threshold is reported once rather than every poll.
+pub struct LiquidationMonitor {
+    thresholds: HealthFactorThresholds,
+    webhook_url: Option<String>,
+    http_client: reqwest::Client,
+    last_levels: Mutex<HashMap<(Address, String), AlertLevel>>,
+}
+
+impl LiquidationMonitor {
+    pub fn new(thresholds: HealthFactorThresholds, webhook_url: Option<String>) -> Self {
+        Self {
+            thresholds,
+            webhook_url,
+            http_client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            last_levels: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Configured from `HEALTH_FACTOR_*` and `LIQUIDATION_WEBHOOK_URL`
+    pub fn from_env() -> Self {
+        Self::new(
+            HealthFactorThresholds::from_env(),
+            std::env::var("LIQUIDATION_WEBHOOK_URL").ok(),
+        )
+    }
+
+    pub fn thresholds(&self) -> HealthFactorThresholds {
+        self.thresholds
+    }
+
+    /// Alerts for positions whose health factor band changed since the last evaluation
+    pub fn evaluate(&self, wallet: Address, positions: &[Position]) -> Vec<LiquidationAlert> {
+        self.evaluate_with_thresholds(wallet, positions, self.thresholds)
+    }
+
+    /// As [`evaluate`](Self::evaluate), banding health factors with the wallet's own
+    /// thresholds instead of the service-wide ones
+    pub fn evaluate_with_thresholds(
+        &self,
+        wallet: Address,
+        positions: &[Position],
+        thresholds: HealthFactorThresholds,
+    ) -> Vec<LiquidationAlert> {
+        let mut last_levels = self.last_levels.lock().unwrap();
+        let mut alerts = Vec::new();
+
+        for position in positions {
+            let Some(health_factor) = health_factor(position) else {
+                continue;
+            };
+
+            let level = thresholds.level(health_factor);
+            let key = (wallet, position.id.clone());
+            let previous = last_levels.insert(key, level).unwrap_or(AlertLevel::Healthy);
+
+            if level != previous && level != AlertLevel::Healthy {
+                alerts.push(LiquidationAlert {
+                    wallet: format!("{:?}", wallet),
+                    position_id: position.id.clone(),
+                    protocol: position.protocol.clone(),
+                    asset: position.pair.clone(),
+                    level,
+                    health_factor,
+                    liquidation_price: metadata_f64(position, "liquidation_price"),
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                });
+            }
+        }
+
+        alerts
+    }
+
+    /// POST each alert to the webhook, or log it when no webhook is configured
+    pub async fn dispatch(&self, alerts: &[LiquidationAlert]) {
+        for alert in alerts {
+            tracing::warn!(
+                wallet = %alert.wallet,
+                protocol = %alert.protocol,
+                health_factor = alert.health_factor,
+                level = ?alert.level,
+                "🚨 Health factor threshold crossed"
+            );
+
+            let Some(webhook_url) = &self.webhook_url else {
+                continue;
+            };
+
+            if let Err(e) = self.http_client.post(webhook_url).json(alert).send().await {
+                tracing::error!("❌ Failed to deliver liquidation alert: {}", e);
+            }
+        }
+    }
+}
+
+/// Health factor reported in position metadata, top-level or under `position_details`
+pub fn health_factor(position: &Position) -> Option<f64> {
+    metadata_f64(position, "health_factor").filter(|hf| hf.is_finite() && *hf > 0.0)
+}
+
+fn metadata_f64(position: &Position, key: &str) -> Option<f64> {
+    position.metadata
+        .get(key)
+        .or_else(|| position.metadata.get("position_details").and_then(|d| d.get(key)))
+        .and_then(|v| v.as_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn borrow(health_factor: f64) -> Position {
+        Position {
+            id: "borrow".to_string(),
+            protocol: "morpho_blue".to_string(),
+            position_type: "borrow".to_string(),
+            pair: "USDC/WETH".to_string(),
+            value_usd: -500.0,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({
+                "position_details": { "health_factor": health_factor, "liquidation_price": 1800.0 }
+            }),
+            last_updated: 0,
+        }
+    }
+
+    #[test]
+    fn test_alerts_only_on_threshold_transitions() {
+        let monitor = LiquidationMonitor::new(HealthFactorThresholds::default(), None);
+        let wallet = Address::ZERO;
+
+        assert!(monitor.evaluate(wallet, &[borrow(2.0)]).is_empty());
+
+        let alerts = monitor.evaluate(wallet, &[borrow(1.4)]);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].level, AlertLevel::Warning);
+        assert_eq!(alerts[0].liquidation_price, Some(1800.0));
+
+        // Still in the warning band: no repeat
+        assert!(monitor.evaluate(wallet, &[borrow(1.3)]).is_empty());
+
+        let alerts = monitor.evaluate(wallet, &[borrow(1.1)]);
+        assert_eq!(alerts[0].level, AlertLevel::Critical);
+
+        // Recovery resets state so the next breach alerts again
+        assert!(monitor.evaluate(wallet, &[borrow(1.8)]).is_empty());
+        assert_eq!(monitor.evaluate(wallet, &[borrow(1.4)]).len(), 1);
+    }
+}
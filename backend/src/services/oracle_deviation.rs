@@ -0,0 +1,324 @@
+use alloy::{
+    primitives::Address,
+    sol,
+};
+use serde::Serialize;
+use std::str::FromStr;
+
+use crate::adapters::traits::{AdapterError, Position};
+use crate::blockchain::EthereumClient;
+
+sol! {
+    interface IChainlinkAggregator {
+        function decimals() external view returns (uint8);
+        function latestRoundData() external view returns (
+            uint80 roundId,
+            int256 answer,
+            uint256 startedAt,
+            uint256 updatedAt,
+            uint80 answeredInRound
+        );
+    }
+
+    interface IUniswapV3OraclePool {
+        function slot0() external view returns (
+            uint160 sqrtPriceX96,
+            int24 tick,
+            uint16 observationIndex,
+            uint16 observationCardinality,
+            uint16 observationCardinalityNext,
+            uint8 feeProtocol,
+            bool unlocked
+        );
+        function observe(uint32[] secondsAgos) external view returns (
+            int56[] tickCumulatives,
+            uint160[] secondsPerLiquidityCumulativeX128s
+        );
+    }
+}
+
+/// A token with both a Chainlink USD feed and a deep Uniswap V3 pool against USDC
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OracleMarket {
+    pub(crate) symbol: &'static str,
+    /// CoinGecko id of the same token
+    pub(crate) coin_id: &'static str,
+    /// Position pair legs that count as exposure to this token
+    pub(crate) aliases: &'static [&'static str],
+    chainlink_feed: &'static str,
+    pool: &'static str,
+    /// Whether the token is the pool's token0 (USDC being token1)
+    token_is_token0: bool,
+    token_decimals: u8,
+}
+
+/// Mainnet markets checked; USDC is taken as $1
+pub(crate) const MARKETS: &[OracleMarket] = &[
+    OracleMarket {
+        symbol: "ETH",
+        coin_id: "ethereum",
+        aliases: &["ETH", "WETH"],
+        chainlink_feed: "0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419",
+        pool: "0x88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640",
+        token_is_token0: false,
+        token_decimals: 18,
+    },
+    OracleMarket {
+        symbol: "WBTC",
+        coin_id: "wrapped-bitcoin",
+        aliases: &["WBTC", "BTC"],
+        chainlink_feed: "0xF4030086522a5bEEa4988F8cA5B36dbC97BeE88c",
+        pool: "0x99ac8cA7087fA4A2A1FB6357269965A2014ABc35",
+        token_is_token0: true,
+        token_decimals: 8,
+    },
+    OracleMarket {
+        symbol: "DAI",
+        coin_id: "dai",
+        aliases: &["DAI"],
+        chainlink_feed: "0xAed0c38402a5d19df6E4c03F4E2DceD6e29c1ee9",
+        pool: "0x5777d92f208679DB4b9778590Fa3CAB3aC9e2168",
+        token_is_token0: true,
+        token_decimals: 18,
+    },
+];
+
+const USDC_DECIMALS: u8 = 6;
+
+/// Chainlink price against the Uniswap V3 spot and TWAP for one token
+#[derive(Debug, Clone, Serialize)]
+pub struct OracleDeviation {
+    pub symbol: String,
+    pub chainlink_price: f64,
+    pub chainlink_updated_at: u64,
+    pub spot_price: f64,
+    pub twap_price: f64,
+    /// Relative gaps to the Chainlink price, as fractions
+    pub spot_deviation: f64,
+    pub twap_deviation: f64,
+    /// Risk in [0, 1]: 0.5 at the threshold, 1.0 at twice the threshold
+    pub oracle_deviation_risk: f64,
+    pub flagged: bool,
+}
+
+/// Compares Chainlink feeds to Uniswap V3 prices to spot stale or manipulated oracles.
+/// A spot price far from the TWAP as well points at pool manipulation; a TWAP far from
+/// Chainlink points at the oracle.
+pub struct OracleDeviationMonitor {
+    client: EthereumClient,
+    /// Deviation flagged as a potential oracle or manipulation issue
+    threshold: f64,
+    twap_window_secs: u32,
+}
+
+impl OracleDeviationMonitor {
+    pub fn new(client: EthereumClient, threshold: f64, twap_window_secs: u32) -> Self {
+        Self {
+            client,
+            threshold,
+            twap_window_secs: twap_window_secs.max(1),
+        }
+    }
+
+    /// `ORACLE_DEVIATION_THRESHOLD` (default 0.02) and `ORACLE_TWAP_SECONDS` (default 1800)
+    pub fn from_env(client: EthereumClient) -> Self {
+        let threshold = std::env::var("ORACLE_DEVIATION_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.02);
+        let twap_window_secs = std::env::var("ORACLE_TWAP_SECONDS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1800);
+
+        Self::new(client, threshold, twap_window_secs)
+    }
+
+    /// Check every market a position is exposed to; markets that fail to read are
+    /// skipped with a warning
+    pub async fn check_positions(&self, positions: &[Position]) -> Vec<OracleDeviation> {
+        let exposed: Vec<&OracleMarket> = MARKETS
+            .iter()
+            .filter(|market| positions.iter().any(|position| is_exposed(position, market)))
+            .collect();
+
+        let checks = exposed.iter().map(|market| self.check_market(market));
+        futures::future::join_all(checks)
+            .await
+            .into_iter()
+            .zip(&exposed)
+            .filter_map(|(result, market)| match result {
+                Ok(deviation) => Some(deviation),
+                Err(e) => {
+                    tracing::warn!("Oracle deviation check for {} failed: {}", market.symbol, e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    async fn check_market(&self, market: &OracleMarket) -> Result<OracleDeviation, AdapterError> {
+        let prices = read_market_prices(&self.client, market, self.twap_window_secs).await?;
+        let spot_deviation = relative_gap(prices.spot_price, prices.chainlink_price);
+        let twap_deviation = relative_gap(prices.twap_price, prices.chainlink_price);
+        let deviation = spot_deviation.max(twap_deviation);
+
+        Ok(OracleDeviation {
+            symbol: market.symbol.to_string(),
+            chainlink_price: prices.chainlink_price,
+            chainlink_updated_at: prices.chainlink_updated_at,
+            spot_price: prices.spot_price,
+            twap_price: prices.twap_price,
+            spot_deviation,
+            twap_deviation,
+            oracle_deviation_risk: deviation_risk(deviation, self.threshold),
+            flagged: deviation > self.threshold,
+        })
+    }
+}
+
+/// A market's Chainlink answer alongside its Uniswap V3 spot and TWAP prices
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MarketPrices {
+    pub(crate) chainlink_price: f64,
+    pub(crate) chainlink_updated_at: u64,
+    pub(crate) spot_price: f64,
+    pub(crate) twap_price: f64,
+}
+
+pub(crate) async fn read_market_prices(
+    client: &EthereumClient,
+    market: &OracleMarket,
+    twap_window_secs: u32,
+) -> Result<MarketPrices, AdapterError> {
+    let parse = |address: &str| {
+        Address::from_str(address)
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid oracle address {}: {}", address, e)))
+    };
+    let feed = parse(market.chainlink_feed)?;
+    let pool = parse(market.pool)?;
+    let twap_window_secs = twap_window_secs.max(1);
+
+    let (decimals, round, slot0, observations) = tokio::try_join!(
+        client.call(feed, &IChainlinkAggregator::decimalsCall {}),
+        client.call(feed, &IChainlinkAggregator::latestRoundDataCall {}),
+        client.call(pool, &IUniswapV3OraclePool::slot0Call {}),
+        client.call(pool, &IUniswapV3OraclePool::observeCall {
+            secondsAgos: vec![twap_window_secs, 0],
+        }),
+    )?;
+
+    let chainlink_price = round.answer.to_string().parse::<f64>().unwrap_or(0.0)
+        / 10f64.powi(decimals._0 as i32);
+    if chainlink_price <= 0.0 {
+        return Err(AdapterError::InvalidData(format!("Chainlink {} price is not positive", market.symbol)));
+    }
+
+    let [start, end] = observations.tickCumulatives[..] else {
+        return Err(AdapterError::InvalidData("Unexpected observe() result length".to_string()));
+    };
+    let twap_tick = (end.as_i64() - start.as_i64()) as f64 / twap_window_secs as f64;
+
+    Ok(MarketPrices {
+        chainlink_price,
+        chainlink_updated_at: round.updatedAt.try_into().unwrap_or(0),
+        spot_price: token_price_from_tick(slot0.tick.as_i32() as f64, market),
+        twap_price: token_price_from_tick(twap_tick, market),
+    })
+}
+
+/// The market quoting the token CoinGecko lists as `coin_id`, if any
+pub(crate) fn market_for_coin(coin_id: &str) -> Option<&'static OracleMarket> {
+    MARKETS.iter().find(|market| market.coin_id == coin_id)
+}
+
+/// Highest deviation risk among the checked markets
+pub fn portfolio_oracle_risk(deviations: &[OracleDeviation]) -> f64 {
+    deviations.iter().map(|d| d.oracle_deviation_risk).fold(0.0, f64::max)
+}
+
+pub(crate) fn is_exposed(position: &Position, market: &OracleMarket) -> bool {
+    position.pair
+        .split(['/', '+'])
+        .any(|leg| market.aliases.contains(&leg.trim().to_uppercase().as_str()))
+}
+
+/// USD price of the market's token at a pool tick, with USDC at $1
+fn token_price_from_tick(tick: f64, market: &OracleMarket) -> f64 {
+    // Raw token1 per token0, then adjusted for decimals
+    let (decimals0, decimals1) = if market.token_is_token0 {
+        (market.token_decimals, USDC_DECIMALS)
+    } else {
+        (USDC_DECIMALS, market.token_decimals)
+    };
+    let token1_per_token0 = 1.0001f64.powf(tick) * 10f64.powi(decimals0 as i32 - decimals1 as i32);
+
+    if market.token_is_token0 {
+        token1_per_token0
+    } else if token1_per_token0 > 0.0 {
+        1.0 / token1_per_token0
+    } else {
+        0.0
+    }
+}
+
+fn relative_gap(price: f64, reference: f64) -> f64 {
+    if reference <= 0.0 {
+        return 0.0;
+    }
+    (price - reference).abs() / reference
+}
+
+fn deviation_risk(deviation: f64, threshold: f64) -> f64 {
+    if threshold <= 0.0 {
+        return if deviation > 0.0 { 1.0 } else { 0.0 };
+    }
+    (deviation / (2.0 * threshold)).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_prices_match_pool_orientation() {
+        // USDC/WETH pool: tick 195_000 is roughly $3,400 ETH
+        let eth = token_price_from_tick(195_000.0, &MARKETS[0]);
+        assert!((eth - 3_400.0).abs() / 3_400.0 < 0.01, "{}", eth);
+
+        // WBTC/USDC pool: tick 66_000 is roughly $73,500 BTC
+        let btc = token_price_from_tick(66_000.0, &MARKETS[1]);
+        assert!((btc - 73_500.0).abs() / 73_500.0 < 0.01, "{}", btc);
+
+        // DAI/USDC pool at par sits near tick -276_324
+        let dai = token_price_from_tick(-276_324.0, &MARKETS[2]);
+        assert!((dai - 1.0).abs() < 0.001, "{}", dai);
+    }
+
+    #[test]
+    fn test_deviation_risk_scales_with_gap() {
+        assert_eq!(deviation_risk(0.0, 0.02), 0.0);
+        assert!((deviation_risk(0.02, 0.02) - 0.5).abs() < 1e-12);
+        assert_eq!(deviation_risk(0.1, 0.02), 1.0);
+        assert!((relative_gap(102.0, 100.0) - 0.02).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_exposure_matches_pair_legs() {
+        let position = Position {
+            id: "1".to_string(),
+            protocol: "test".to_string(),
+            position_type: "borrow".to_string(),
+            pair: "USDC/WETH+WBTC".to_string(),
+            value_usd: 1.0,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({}),
+            last_updated: 0,
+        };
+
+        assert!(is_exposed(&position, &MARKETS[0]));
+        assert!(is_exposed(&position, &MARKETS[1]));
+        assert!(!is_exposed(&position, &MARKETS[2]));
+    }
+}
@@ -0,0 +1,218 @@
+use serde::Serialize;
+
+use crate::services::portfolio_history::{PortfolioSnapshot, PositionSnapshot};
+
+/// How much a position has to move to be reported as changed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffThresholds {
+    /// Change in position size, in percent
+    pub value_change_pct: f64,
+    /// Absolute change in health factor
+    pub health_factor_change: f64,
+}
+
+impl Default for DiffThresholds {
+    fn default() -> Self {
+        Self {
+            value_change_pct: 1.0,
+            health_factor_change: 0.05,
+        }
+    }
+}
+
+/// A position held in both snapshots that moved beyond the thresholds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PositionChange {
+    pub id: String,
+    pub protocol: String,
+    pub position_type: String,
+    pub pair: String,
+    pub value_before_usd: f64,
+    pub value_after_usd: f64,
+    pub value_change_usd: f64,
+    /// Change in position size in percent; `None` when it was worth nothing before
+    pub value_change_pct: Option<f64>,
+    pub health_factor_before: Option<f64>,
+    pub health_factor_after: Option<f64>,
+}
+
+/// What changed in a wallet between two snapshots
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PortfolioDiff {
+    pub from_timestamp: u64,
+    pub to_timestamp: u64,
+    pub added: Vec<PositionSnapshot>,
+    pub removed: Vec<PositionSnapshot>,
+    pub changed: Vec<PositionChange>,
+    pub value_before_usd: f64,
+    pub value_after_usd: f64,
+    pub value_change_usd: f64,
+    /// One sentence per added, removed or changed position
+    pub summary: Vec<String>,
+}
+
+impl PortfolioDiff {
+    /// Match positions by id and report what was opened, closed or moved. Values of
+    /// positions whose price was unknown on either side are not compared.
+    pub fn between(before: &PortfolioSnapshot, after: &PortfolioSnapshot, thresholds: &DiffThresholds) -> Self {
+        let find = |snapshot: &PortfolioSnapshot, id: &str| snapshot.positions.iter().find(|p| p.id == id).cloned();
+
+        let added: Vec<PositionSnapshot> = after.positions.iter().filter(|p| find(before, &p.id).is_none()).cloned().collect();
+        let removed: Vec<PositionSnapshot> = before.positions.iter().filter(|p| find(after, &p.id).is_none()).cloned().collect();
+        let changed: Vec<PositionChange> = after
+            .positions
+            .iter()
+            .filter_map(|current| position_change(&find(before, &current.id)?, current, thresholds))
+            .collect();
+
+        let mut summary = Vec::new();
+        for position in &added {
+            summary.push(format!("You opened a new {} {} ({})", position.protocol, position.position_type, position.pair));
+        }
+        for position in &removed {
+            summary.push(format!("You closed your {} {} ({})", position.protocol, position.position_type, position.pair));
+        }
+        for change in &changed {
+            summary.extend(describe_change(change, thresholds));
+        }
+
+        Self {
+            from_timestamp: before.timestamp,
+            to_timestamp: after.timestamp,
+            added,
+            removed,
+            changed,
+            value_before_usd: before.total_value_usd,
+            value_after_usd: after.total_value_usd,
+            value_change_usd: after.total_value_usd - before.total_value_usd,
+            summary,
+        }
+    }
+}
+
+fn position_change(before: &PositionSnapshot, after: &PositionSnapshot, thresholds: &DiffThresholds) -> Option<PositionChange> {
+    let priced = !before.price_unknown && !after.price_unknown;
+    // Debt is carried as negative value, so size is compared by magnitude
+    let value_change_pct = (priced && before.value_usd != 0.0)
+        .then(|| (after.value_usd.abs() - before.value_usd.abs()) / before.value_usd.abs() * 100.0);
+
+    let value_moved = priced
+        && match value_change_pct {
+            Some(pct) => pct.abs() >= thresholds.value_change_pct,
+            None => after.value_usd != 0.0,
+        };
+    let health_factor_moved = match (before.health_factor, after.health_factor) {
+        (Some(hf_before), Some(hf_after)) => (hf_after - hf_before).abs() >= thresholds.health_factor_change,
+        (None, None) => false,
+        _ => true,
+    };
+    if !value_moved && !health_factor_moved {
+        return None;
+    }
+
+    Some(PositionChange {
+        id: after.id.clone(),
+        protocol: after.protocol.clone(),
+        position_type: after.position_type.clone(),
+        pair: after.pair.clone(),
+        value_before_usd: before.value_usd,
+        value_after_usd: after.value_usd,
+        value_change_usd: if priced { after.value_usd - before.value_usd } else { 0.0 },
+        value_change_pct,
+        health_factor_before: before.health_factor,
+        health_factor_after: after.health_factor,
+    })
+}
+
+fn describe_change(change: &PositionChange, thresholds: &DiffThresholds) -> Vec<String> {
+    let mut sentences = Vec::new();
+    if let Some(pct) = change.value_change_pct.filter(|pct| pct.abs() >= thresholds.value_change_pct) {
+        sentences.push(format!(
+            "Your {} {} {} {:.1}%",
+            change.protocol,
+            change.pair,
+            if pct > 0.0 { "grew" } else { "shrank" },
+            pct.abs(),
+        ));
+    }
+    if let (Some(before), Some(after)) = (change.health_factor_before, change.health_factor_after) {
+        if (after - before).abs() >= thresholds.health_factor_change {
+            sentences.push(format!(
+                "Health factor on your {} {} {} from {:.2} to {:.2}",
+                change.protocol,
+                change.pair,
+                if after > before { "rose" } else { "fell" },
+                before,
+                after,
+            ));
+        }
+    }
+    sentences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn position(id: &str, protocol: &str, position_type: &str, value_usd: f64, health_factor: Option<f64>) -> PositionSnapshot {
+        PositionSnapshot {
+            id: id.to_string(),
+            protocol: protocol.to_string(),
+            position_type: position_type.to_string(),
+            pair: "stETH/ETH".to_string(),
+            value_usd,
+            price_unknown: false,
+            health_factor,
+        }
+    }
+
+    fn snapshot(timestamp: u64, positions: Vec<PositionSnapshot>) -> PortfolioSnapshot {
+        PortfolioSnapshot {
+            timestamp,
+            total_value_usd: positions.iter().map(|p| p.value_usd).sum(),
+            total_pnl_usd: 0.0,
+            protocol_values_usd: BTreeMap::new(),
+            position_count: positions.len(),
+            positions,
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_opened_closed_and_grown_positions() {
+        let before = snapshot(0, vec![
+            position("lido", "lido", "staking", 10_000.0, None),
+            position("curve", "curve", "liquidity", 2_000.0, None),
+            position("aave_supply", "aave_v3", "supply", 5_000.0, Some(2.0)),
+        ]);
+        let after = snapshot(604_800, vec![
+            position("lido", "lido", "staking", 10_200.0, None),
+            position("aave_supply", "aave_v3", "supply", 5_020.0, Some(2.01)),
+            position("aave_borrow", "aave_v3", "borrow", -1_000.0, Some(1.6)),
+        ]);
+
+        let diff = PortfolioDiff::between(&before, &after, &DiffThresholds::default());
+
+        assert_eq!(diff.added[0].id, "aave_borrow");
+        assert_eq!(diff.removed[0].id, "curve");
+        assert_eq!(diff.changed.len(), 1);
+        assert!((diff.changed[0].value_change_pct.unwrap() - 2.0).abs() < 1e-9);
+        assert_eq!(diff.value_change_usd, -2_780.0);
+        assert_eq!(diff.summary, vec![
+            "You opened a new aave_v3 borrow (stETH/ETH)",
+            "You closed your curve liquidity (stETH/ETH)",
+            "Your lido stETH/ETH grew 2.0%",
+        ]);
+    }
+
+    #[test]
+    fn test_health_factor_drop_is_reported_without_value_change() {
+        let before = snapshot(0, vec![position("borrow", "aave_v3", "borrow", -1_000.0, Some(1.8))]);
+        let after = snapshot(1, vec![position("borrow", "aave_v3", "borrow", -1_000.0, Some(1.3))]);
+
+        let diff = PortfolioDiff::between(&before, &after, &DiffThresholds::default());
+
+        assert_eq!(diff.changed[0].value_change_pct, Some(0.0));
+        assert_eq!(diff.summary, vec!["Health factor on your aave_v3 stETH/ETH fell from 1.80 to 1.30"]);
+    }
+}
@@ -0,0 +1,287 @@
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::Duration;
+
+use crate::adapters::traits::Position;
+use crate::services::monitoring::health_factor;
+
+/// One position as it stood when a snapshot was taken
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionSnapshot {
+    pub id: String,
+    pub protocol: String,
+    pub position_type: String,
+    pub pair: String,
+    pub value_usd: f64,
+    pub price_unknown: bool,
+    pub health_factor: Option<f64>,
+}
+
+impl From<&Position> for PositionSnapshot {
+    fn from(position: &Position) -> Self {
+        Self {
+            id: position.id.clone(),
+            protocol: position.protocol.clone(),
+            position_type: position.position_type.clone(),
+            pair: position.pair.clone(),
+            value_usd: position.value_usd,
+            price_unknown: position.is_price_unknown(),
+            health_factor: health_factor(position),
+        }
+    }
+}
+
+/// Total and per-protocol value of a wallet at one point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortfolioSnapshot {
+    /// Unix seconds
+    pub timestamp: u64,
+    pub total_value_usd: f64,
+    pub total_pnl_usd: f64,
+    pub protocol_values_usd: BTreeMap<String, f64>,
+    pub position_count: usize,
+    /// Empty for snapshots recorded before positions were kept
+    #[serde(default)]
+    pub positions: Vec<PositionSnapshot>,
+}
+
+impl PortfolioSnapshot {
+    pub fn from_positions(positions: &[Position], timestamp: u64) -> Self {
+        let mut protocol_values_usd = BTreeMap::new();
+        for position in positions.iter().filter(|p| !p.is_price_unknown()) {
+            *protocol_values_usd.entry(position.protocol.clone()).or_insert(0.0) += position.value_usd;
+        }
+
+        Self {
+            timestamp,
+            total_value_usd: positions.iter().filter(|p| !p.is_price_unknown()).map(|p| p.value_usd).sum(),
+            total_pnl_usd: positions.iter().filter(|p| !p.is_price_unknown()).map(|p| p.pnl_usd).sum(),
+            protocol_values_usd,
+            position_count: positions.len(),
+            positions: positions.iter().map(PositionSnapshot::from).collect(),
+        }
+    }
+}
+
+/// On-disk form: one JSON object per line
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredSnapshot {
+    address: Address,
+    #[serde(flatten)]
+    snapshot: PortfolioSnapshot,
+}
+
+/// Time series of portfolio snapshots per wallet.
+///
+/// Snapshots older than `retention` are pruned. When a path is configured every
+/// snapshot is also appended to it as JSON lines and reloaded on startup, so history
+/// survives restarts.
+#[derive(Debug)]
+pub struct PortfolioHistoryStore {
+    snapshots: RwLock<HashMap<Address, Vec<PortfolioSnapshot>>>,
+    retention: Duration,
+    path: Option<PathBuf>,
+    file_lock: Mutex<()>,
+}
+
+impl PortfolioHistoryStore {
+    pub fn new(retention: Duration, path: Option<PathBuf>) -> Self {
+        Self {
+            snapshots: RwLock::new(HashMap::new()),
+            retention,
+            path,
+            file_lock: Mutex::new(()),
+        }
+    }
+
+    /// `PORTFOLIO_HISTORY_RETENTION_DAYS` (default 365) and optional `PORTFOLIO_HISTORY_PATH`
+    pub fn from_env() -> Self {
+        let retention_days = std::env::var("PORTFOLIO_HISTORY_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(365);
+        let path = std::env::var("PORTFOLIO_HISTORY_PATH").ok().map(PathBuf::from);
+
+        let store = Self::new(Duration::from_secs(retention_days * 86_400), path);
+        if let Err(e) = store.load() {
+            tracing::warn!("Failed to load portfolio history: {}", e);
+        }
+        store
+    }
+
+    /// Process-wide store shared by the snapshot task and handlers
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<PortfolioHistoryStore>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::from_env())).clone()
+    }
+
+    pub fn record(&self, address: Address, snapshot: PortfolioSnapshot) {
+        if let Err(e) = self.append_to_file(address, &snapshot) {
+            tracing::warn!("Failed to persist portfolio snapshot: {}", e);
+        }
+        self.insert(address, snapshot);
+    }
+
+    /// Snapshots for `address` taken at or after `since`, oldest first
+    pub fn history(&self, address: Address, since: u64) -> Vec<PortfolioSnapshot> {
+        self.snapshots
+            .read()
+            .unwrap()
+            .get(&address)
+            .map(|series| series.iter().filter(|s| s.timestamp >= since).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Most recent snapshot for `address` taken at or before `timestamp`
+    pub fn at(&self, address: Address, timestamp: u64) -> Option<PortfolioSnapshot> {
+        self.snapshots
+            .read()
+            .unwrap()
+            .get(&address)?
+            .iter()
+            .rev()
+            .find(|s| s.timestamp <= timestamp)
+            .cloned()
+    }
+
+    fn insert(&self, address: Address, snapshot: PortfolioSnapshot) {
+        let mut snapshots = self.snapshots.write().unwrap();
+        let series = snapshots.entry(address).or_default();
+
+        series.push(snapshot);
+        series.sort_by_key(|s| s.timestamp);
+        let newest = series.last().map_or(0, |s| s.timestamp);
+        let cutoff = newest.saturating_sub(self.retention.as_secs());
+        series.retain(|s| s.timestamp >= cutoff);
+    }
+
+    fn load(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let mut loaded = 0;
+        for line in BufReader::new(file).lines() {
+            match serde_json::from_str::<StoredSnapshot>(&line?) {
+                Ok(stored) => {
+                    self.insert(stored.address, stored.snapshot);
+                    loaded += 1;
+                }
+                Err(e) => tracing::debug!("Skipping malformed portfolio snapshot: {}", e),
+            }
+        }
+
+        tracing::info!("📈 Loaded {} portfolio snapshots", loaded);
+        Ok(())
+    }
+
+    fn append_to_file(&self, address: Address, snapshot: &PortfolioSnapshot) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let line = serde_json::to_string(&StoredSnapshot { address, snapshot: snapshot.clone() })?;
+        let _guard = self.file_lock.lock().unwrap();
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", line)
+    }
+}
+
+/// Parse a lookback window such as `24h`, `7d` or `4w`
+pub fn parse_window(window: &str) -> Option<Duration> {
+    let window = window.trim();
+    let unit = window.chars().last()?;
+    let amount: u64 = window[..window.len() - unit.len_utf8()].parse().ok().filter(|n| *n > 0)?;
+
+    let unit_secs = match unit {
+        'h' => 3_600,
+        'd' => 86_400,
+        'w' => 7 * 86_400,
+        _ => return None,
+    };
+    Some(Duration::from_secs(amount.checked_mul(unit_secs)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(timestamp: u64, total_value_usd: f64) -> PortfolioSnapshot {
+        PortfolioSnapshot {
+            timestamp,
+            total_value_usd,
+            total_pnl_usd: 0.0,
+            protocol_values_usd: BTreeMap::new(),
+            position_count: 1,
+            positions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_history_is_ordered_and_windowed() {
+        let store = PortfolioHistoryStore::new(Duration::from_secs(1_000), None);
+        let wallet = Address::ZERO;
+
+        store.record(wallet, snapshot(300, 3.0));
+        store.record(wallet, snapshot(100, 1.0));
+        store.record(wallet, snapshot(200, 2.0));
+
+        let values: Vec<f64> = store.history(wallet, 150).iter().map(|s| s.total_value_usd).collect();
+        assert_eq!(values, vec![2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_snapshot_at_picks_latest_not_after_timestamp() {
+        let store = PortfolioHistoryStore::new(Duration::from_secs(1_000), None);
+        let wallet = Address::ZERO;
+
+        store.record(wallet, snapshot(100, 1.0));
+        store.record(wallet, snapshot(200, 2.0));
+
+        assert_eq!(store.at(wallet, 150).map(|s| s.timestamp), Some(100));
+        assert_eq!(store.at(wallet, 200).map(|s| s.timestamp), Some(200));
+        assert_eq!(store.at(wallet, 50), None);
+    }
+
+    #[test]
+    fn test_old_snapshots_are_pruned() {
+        let store = PortfolioHistoryStore::new(Duration::from_secs(100), None);
+        let wallet = Address::ZERO;
+
+        store.record(wallet, snapshot(0, 1.0));
+        store.record(wallet, snapshot(500, 2.0));
+
+        assert_eq!(store.history(wallet, 0).len(), 1);
+    }
+
+    #[test]
+    fn test_snapshots_survive_reload() {
+        let path = std::env::temp_dir().join(format!("portfolio_history_{}.jsonl", uuid::Uuid::new_v4()));
+        let wallet = Address::ZERO;
+
+        PortfolioHistoryStore::new(Duration::from_secs(1_000), Some(path.clone())).record(wallet, snapshot(10, 5.0));
+        let reloaded = PortfolioHistoryStore::new(Duration::from_secs(1_000), Some(path.clone()));
+        reloaded.load().unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(reloaded.history(wallet, 0), vec![snapshot(10, 5.0)]);
+    }
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(parse_window("7d"), Some(Duration::from_secs(7 * 86_400)));
+        assert_eq!(parse_window("24h"), Some(Duration::from_secs(86_400)));
+        assert_eq!(parse_window("0d"), None);
+        assert_eq!(parse_window("7"), None);
+        assert_eq!(parse_window(""), None);
+    }
+}
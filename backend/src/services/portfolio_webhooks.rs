@@ -0,0 +1,441 @@
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::Duration;
+
+use crate::adapters::traits::Position;
+use crate::security::{outbound_client, validate_outbound_url};
+use super::alert_service::{post_json, AlertService, DeliveryRecord, NotificationError};
+use super::monitoring::{AlertLevel, HealthFactorThresholds};
+use super::portfolio_diff::{DiffThresholds, PortfolioDiff};
+use super::portfolio_history::PortfolioSnapshot;
+
+/// Sends per change event, including the first
+const MAX_ATTEMPTS: u32 = 3;
+/// Wait before the first retry, doubled for each one after
+const RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
+/// What counts as a material change for a subscription. Each trigger is measured
+/// against the portfolio as of the last event sent, so slow drifts still fire.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeThresholds {
+    /// Move in total portfolio value, in percent; off when `None`
+    #[serde(default)]
+    pub value_change_pct: Option<f64>,
+    /// Fire when a position is opened
+    #[serde(default)]
+    pub new_position: bool,
+    /// Fire when a health factor crosses one of these levels, in either direction
+    #[serde(default)]
+    pub health_factor_levels: Vec<f64>,
+}
+
+impl Default for ChangeThresholds {
+    fn default() -> Self {
+        let health = HealthFactorThresholds::default();
+        Self {
+            value_change_pct: Some(5.0),
+            new_position: true,
+            health_factor_levels: vec![health.warning, health.critical],
+        }
+    }
+}
+
+impl ChangeThresholds {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.value_change_pct.is_some_and(|pct| !pct.is_finite() || pct <= 0.0) {
+            return Err("value_change_pct must be a positive percentage".to_string());
+        }
+        if self.health_factor_levels.iter().any(|level| !level.is_finite() || *level <= 0.0) {
+            return Err("health_factor_levels must be positive".to_string());
+        }
+        if self.value_change_pct.is_none() && !self.new_position && self.health_factor_levels.is_empty() {
+            return Err("at least one trigger must be enabled".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Webhook a wallet's portfolio changes are POSTed to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookSubscription {
+    pub url: String,
+    #[serde(default)]
+    pub thresholds: ChangeThresholds,
+}
+
+impl WebhookSubscription {
+    pub fn validate(&self) -> Result<(), String> {
+        validate_outbound_url(&self.url)?;
+        self.thresholds.validate()
+    }
+
+    /// Copy safe to return from the API: webhook paths often carry a secret
+    pub fn redacted(&self) -> Self {
+        Self {
+            url: format!("{}…", self.url.chars().take(8).collect::<String>()),
+            thresholds: self.thresholds.clone(),
+        }
+    }
+}
+
+/// Why a change event was sent
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChangeTrigger {
+    ValueChange { change_pct: f64 },
+    NewPosition { position_id: String },
+    HealthFactorCrossing { position_id: String, level: f64, before: f64, after: f64 },
+}
+
+/// Body POSTed to a subscription's webhook
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PortfolioChangeEvent {
+    pub event: &'static str,
+    pub wallet: String,
+    pub triggers: Vec<ChangeTrigger>,
+    pub diff: PortfolioDiff,
+    pub timestamp: String,
+}
+
+impl PortfolioChangeEvent {
+    /// The event for moving from `before` to `after`, or `None` when no trigger fired
+    pub fn detect(
+        wallet: Address,
+        before: &PortfolioSnapshot,
+        after: &PortfolioSnapshot,
+        thresholds: &ChangeThresholds,
+    ) -> Option<Self> {
+        let mut triggers = Vec::new();
+
+        if let Some(threshold) = thresholds.value_change_pct {
+            if before.total_value_usd != 0.0 {
+                let change_pct = (after.total_value_usd - before.total_value_usd) / before.total_value_usd.abs() * 100.0;
+                if change_pct.abs() >= threshold {
+                    triggers.push(ChangeTrigger::ValueChange { change_pct });
+                }
+            }
+        }
+
+        for current in &after.positions {
+            let Some(previous) = before.positions.iter().find(|p| p.id == current.id) else {
+                if thresholds.new_position {
+                    triggers.push(ChangeTrigger::NewPosition { position_id: current.id.clone() });
+                }
+                continue;
+            };
+            let (Some(hf_before), Some(hf_after)) = (previous.health_factor, current.health_factor) else {
+                continue;
+            };
+            for &level in &thresholds.health_factor_levels {
+                if (hf_before >= level) != (hf_after >= level) {
+                    triggers.push(ChangeTrigger::HealthFactorCrossing {
+                        position_id: current.id.clone(),
+                        level,
+                        before: hf_before,
+                        after: hf_after,
+                    });
+                }
+            }
+        }
+
+        if triggers.is_empty() {
+            return None;
+        }
+
+        let diff_thresholds = DiffThresholds {
+            value_change_pct: thresholds.value_change_pct.unwrap_or(DiffThresholds::default().value_change_pct),
+            ..DiffThresholds::default()
+        };
+        Some(Self {
+            event: "portfolio.changed",
+            wallet: format!("{:?}", wallet),
+            triggers,
+            diff: PortfolioDiff::between(before, after, &diff_thresholds),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+
+    /// Critical when a health factor fell through a level, for the delivery history
+    pub fn level(&self) -> AlertLevel {
+        let fell = self.triggers.iter().any(|trigger| {
+            matches!(trigger, ChangeTrigger::HealthFactorCrossing { before, after, .. } if after < before)
+        });
+        if fell { AlertLevel::Critical } else { AlertLevel::Warning }
+    }
+}
+
+/// Timeouts, transport errors, rate limits and server errors may pass; other rejections won't
+fn is_retryable(error: &NotificationError) -> bool {
+    match error {
+        NotificationError::Request(_) => true,
+        NotificationError::Rejected(status, _) => *status == 429 || *status >= 500,
+        NotificationError::Blocked(_) | NotificationError::NotConfigured(_) => false,
+    }
+}
+
+/// Run `send` until it succeeds, fails for good or runs out of attempts, backing off
+/// exponentially between tries. Returns the last result and the attempts made.
+async fn send_with_retries<F, Fut>(mut send: F, backoff: Duration) -> (Result<(), NotificationError>, u32)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), NotificationError>>,
+{
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let result = send().await;
+        match &result {
+            Err(e) if attempts < MAX_ATTEMPTS && is_retryable(e) => {
+                tokio::time::sleep(backoff * 2u32.pow(attempts - 1)).await;
+            }
+            _ => return (result, attempts),
+        }
+    }
+}
+
+/// Portfolio-change webhooks registered by each wallet, and the portfolio each was
+/// last notified about.
+///
+/// When `PORTFOLIO_WEBHOOKS_PATH` is set subscriptions are rewritten there as JSON on
+/// every change and reloaded on startup. Baselines are kept in memory only, so the
+/// first poll after a restart sets a fresh one instead of sending an event.
+pub struct PortfolioWebhooks {
+    alerts: Arc<AlertService>,
+    subscriptions: RwLock<HashMap<Address, WebhookSubscription>>,
+    baselines: Mutex<HashMap<Address, PortfolioSnapshot>>,
+    path: Option<PathBuf>,
+    file_lock: Mutex<()>,
+}
+
+impl PortfolioWebhooks {
+    pub fn new(alerts: Arc<AlertService>, path: Option<PathBuf>) -> Self {
+        Self {
+            alerts,
+            subscriptions: RwLock::new(HashMap::new()),
+            baselines: Mutex::new(HashMap::new()),
+            path,
+            file_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let webhooks = Self::new(AlertService::global(), std::env::var("PORTFOLIO_WEBHOOKS_PATH").ok().map(PathBuf::from));
+        if let Err(e) = webhooks.load() {
+            tracing::warn!("Failed to load portfolio webhooks: {}", e);
+        }
+        webhooks
+    }
+
+    /// Process-wide service shared by the monitor and handlers
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<PortfolioWebhooks>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::from_env())).clone()
+    }
+
+    pub fn get(&self, wallet: Address) -> Option<WebhookSubscription> {
+        self.subscriptions.read().unwrap().get(&wallet).cloned()
+    }
+
+    /// Register or replace the wallet's webhook, or remove it with `None`. Either way
+    /// the next poll starts a new baseline.
+    pub fn set(&self, wallet: Address, subscription: Option<WebhookSubscription>) {
+        let snapshot = {
+            let mut subscriptions = self.subscriptions.write().unwrap();
+            match subscription {
+                Some(subscription) => subscriptions.insert(wallet, subscription),
+                None => subscriptions.remove(&wallet),
+            };
+            subscriptions.clone()
+        };
+        self.baselines.lock().unwrap().remove(&wallet);
+
+        if let Err(e) = self.save(&snapshot) {
+            tracing::warn!("Failed to persist portfolio webhooks: {}", e);
+        }
+    }
+
+    /// Wallets with a webhook registered
+    pub fn wallets(&self) -> Vec<Address> {
+        self.subscriptions.read().unwrap().keys().copied().collect()
+    }
+
+    /// Compare a complete read of `wallet` with its baseline and POST a change event
+    /// if a trigger fired. The baseline then moves to this read, whether or not the
+    /// webhook accepted it, so a dead endpoint isn't retried every poll.
+    pub async fn observe(&self, wallet: Address, positions: &[Position]) -> Option<DeliveryRecord> {
+        let subscription = self.get(wallet)?;
+        let current = PortfolioSnapshot::from_positions(positions, chrono::Utc::now().timestamp() as u64);
+
+        let event = {
+            let mut baselines = self.baselines.lock().unwrap();
+            let Some(baseline) = baselines.get(&wallet) else {
+                baselines.insert(wallet, current);
+                return None;
+            };
+            let event = PortfolioChangeEvent::detect(wallet, baseline, &current, &subscription.thresholds)?;
+            baselines.insert(wallet, current);
+            event
+        };
+
+        // Resolved once per event, so retries can't be re-pointed at an internal address
+        let client = match validate_outbound_url(&subscription.url) {
+            Ok(url) => outbound_client(&url).await.map(|client| (client, url)),
+            Err(e) => Err(e),
+        };
+        let (result, attempts) = match &client {
+            Ok((client, url)) => send_with_retries(|| post_json(client.post(url.clone()), &event), RETRY_BACKOFF).await,
+            Err(e) => (Err(NotificationError::Blocked(e.clone())), 1),
+        };
+        match &result {
+            Ok(()) => tracing::info!("📬 Sent portfolio change for {:?} ({} trigger(s))", wallet, event.triggers.len()),
+            Err(e) => tracing::warn!("❌ Failed to deliver portfolio change for {:?} after {} attempt(s): {}", wallet, attempts, e),
+        }
+
+        let record = DeliveryRecord {
+            channel: "portfolio_webhook",
+            position_id: "portfolio".to_string(),
+            level: event.level(),
+            delivered: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+            attempts,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        self.alerts.record_deliveries(wallet, std::slice::from_ref(&record));
+        Some(record)
+    }
+
+    fn load(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let subscriptions: HashMap<Address, WebhookSubscription> = serde_json::from_str(&contents)?;
+        *self.subscriptions.write().unwrap() = subscriptions;
+        Ok(())
+    }
+
+    fn save(&self, subscriptions: &HashMap<Address, WebhookSubscription>) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let _guard = self.file_lock.lock().unwrap();
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, serde_json::to_vec_pretty(subscriptions)?)?;
+        std::fs::rename(tmp_path, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::portfolio_history::PositionSnapshot;
+    use std::collections::BTreeMap;
+
+    fn position(id: &str, value_usd: f64, health_factor: Option<f64>) -> PositionSnapshot {
+        PositionSnapshot {
+            id: id.to_string(),
+            protocol: "aave_v3".to_string(),
+            position_type: "borrow".to_string(),
+            pair: "USDC/WETH".to_string(),
+            value_usd,
+            price_unknown: false,
+            health_factor,
+        }
+    }
+
+    fn snapshot(positions: Vec<PositionSnapshot>) -> PortfolioSnapshot {
+        PortfolioSnapshot {
+            timestamp: 0,
+            total_value_usd: positions.iter().map(|p| p.value_usd).sum(),
+            total_pnl_usd: 0.0,
+            protocol_values_usd: BTreeMap::new(),
+            position_count: positions.len(),
+            positions,
+        }
+    }
+
+    #[test]
+    fn test_triggers_fire_only_past_thresholds() {
+        let before = snapshot(vec![position("supply", 10_000.0, Some(1.6))]);
+        let thresholds = ChangeThresholds::default();
+
+        let drift = snapshot(vec![position("supply", 10_300.0, Some(1.55))]);
+        assert!(PortfolioChangeEvent::detect(Address::ZERO, &before, &drift, &thresholds).is_none());
+
+        let after = snapshot(vec![position("supply", 10_300.0, Some(1.4)), position("lido", 1_000.0, None)]);
+        let event = PortfolioChangeEvent::detect(Address::ZERO, &before, &after, &thresholds).unwrap();
+        assert_eq!(event.triggers.len(), 3);
+        assert!(matches!(event.triggers[0], ChangeTrigger::ValueChange { change_pct } if (change_pct - 13.0).abs() < 1e-9));
+        assert_eq!(event.triggers[1], ChangeTrigger::HealthFactorCrossing {
+            position_id: "supply".to_string(),
+            level: 1.5,
+            before: 1.6,
+            after: 1.4,
+        });
+        assert_eq!(event.triggers[2], ChangeTrigger::NewPosition { position_id: "lido".to_string() });
+        assert_eq!(event.level(), AlertLevel::Critical);
+        assert_eq!(event.diff.added.len(), 1);
+    }
+
+    #[test]
+    fn test_subscription_validation() {
+        let mut subscription = WebhookSubscription {
+            url: "https://example.com/hooks/secret".to_string(),
+            thresholds: ChangeThresholds::default(),
+        };
+        assert!(subscription.validate().is_ok());
+        assert_eq!(subscription.redacted().url, "https://…");
+
+        subscription.thresholds = ChangeThresholds { value_change_pct: None, new_position: false, health_factor_levels: Vec::new() };
+        assert!(subscription.validate().is_err());
+        subscription.thresholds = ChangeThresholds::default();
+        for url in ["http://example.com", "https://10.0.0.8/hook", "https://[::ffff:169.254.169.254]/", "https://localhost/hook"] {
+            subscription.url = url.to_string();
+            assert!(subscription.validate().is_err(), "{} should be rejected", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_stop_on_success_or_permanent_rejection() {
+        let calls = Mutex::new(0);
+        let (result, attempts) = send_with_retries(
+            || {
+                let mut calls = calls.lock().unwrap();
+                *calls += 1;
+                let failed = *calls < 2;
+                async move {
+                    if failed { Err(NotificationError::Rejected(503, "Unavailable".to_string())) } else { Ok(()) }
+                }
+            },
+            Duration::ZERO,
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(attempts, 2);
+
+        let (result, attempts) = send_with_retries(
+            || async { Err(NotificationError::Rejected(404, "Not Found".to_string())) },
+            Duration::ZERO,
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+
+        let (_, attempts) = send_with_retries(
+            || async { Err(NotificationError::Request("timed out".to_string())) },
+            Duration::ZERO,
+        )
+        .await;
+        assert_eq!(attempts, MAX_ATTEMPTS);
+    }
+}
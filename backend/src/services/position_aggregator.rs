@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::adapters::traits::Position;
+
+/// Portfolio exposure after deduplicating positions and netting debt against supply
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AggregatedPortfolio {
+    /// Net USD exposure per underlying token (supplied minus borrowed)
+    pub net_exposure_by_token: BTreeMap<String, f64>,
+
+    /// Gross USD value of all non-debt positions
+    pub total_supplied_usd: f64,
+
+    /// Gross USD value of all debt positions
+    pub total_borrowed_usd: f64,
+
+    /// Supplied assets divided by net equity; `None` when equity is not positive
+    pub leverage_ratio: Option<f64>,
+
+    /// Positions dropped because another adapter already reported the same ID
+    pub duplicates_removed: usize,
+
+    /// Expected yearly earnings: supply yield minus borrow cost, from each position's APY
+    pub projected_annual_yield_usd: f64,
+
+    /// Projected yield as a percentage of net equity; `None` when equity is not positive
+    pub portfolio_net_apy: Option<f64>,
+}
+
+/// Group positions by underlying token and net borrows against supplies across protocols,
+/// blending position APYs into a portfolio-level yield
+pub fn aggregate(positions: Vec<Position>) -> AggregatedPortfolio {
+    let mut portfolio = AggregatedPortfolio::default();
+    let mut seen_ids = HashSet::new();
+
+    for position in positions {
+        if !seen_ids.insert(position.id.clone()) {
+            portfolio.duplicates_removed += 1;
+            continue;
+        }
+
+        let value = position.value_usd.abs();
+        let sign = if is_debt(&position) {
+            portfolio.total_borrowed_usd += value;
+            -1.0
+        } else {
+            portfolio.total_supplied_usd += value;
+            1.0
+        };
+        if let Some(apy) = position_apy(&position) {
+            portfolio.projected_annual_yield_usd += sign * value * apy / 100.0;
+        }
+
+        let tokens = underlying_tokens(&position);
+        let share = value / tokens.len() as f64;
+        for token in tokens {
+            *portfolio.net_exposure_by_token.entry(token).or_insert(0.0) += sign * share;
+        }
+    }
+
+    portfolio.with_equity_ratios()
+}
+
+/// Sum portfolios aggregated separately, e.g. one per wallet. Positions aren't
+/// deduplicated across them, since the same ID in two wallets is two positions.
+pub fn combine(portfolios: &[AggregatedPortfolio]) -> AggregatedPortfolio {
+    let mut combined = AggregatedPortfolio::default();
+    for portfolio in portfolios {
+        for (token, exposure) in &portfolio.net_exposure_by_token {
+            *combined.net_exposure_by_token.entry(token.clone()).or_insert(0.0) += exposure;
+        }
+        combined.total_supplied_usd += portfolio.total_supplied_usd;
+        combined.total_borrowed_usd += portfolio.total_borrowed_usd;
+        combined.duplicates_removed += portfolio.duplicates_removed;
+        combined.projected_annual_yield_usd += portfolio.projected_annual_yield_usd;
+    }
+    combined.with_equity_ratios()
+}
+
+impl AggregatedPortfolio {
+    /// Fill in the ratios to net equity from the totals
+    fn with_equity_ratios(mut self) -> Self {
+        let equity = self.total_supplied_usd - self.total_borrowed_usd;
+        if equity > 0.0 {
+            self.leverage_ratio = Some(self.total_supplied_usd / equity);
+            self.portfolio_net_apy = Some(self.projected_annual_yield_usd / equity * 100.0);
+        }
+        self
+    }
+}
+
+fn is_debt(position: &Position) -> bool {
+    let position_type = position.position_type.to_lowercase();
+    position_type.contains("borrow") || position_type.contains("debt")
+}
+
+/// APY in percent the position earns, or pays for debt, under whichever key its adapter
+/// reports it. Positions without one are counted as earning nothing.
+pub fn position_apy(position: &Position) -> Option<f64> {
+    const APY_KEYS: [&str; 6] = ["supply_apy", "borrow_apy", "current_apy", "staking_apy", "savings_rate_apy", "apy"];
+
+    let details = position.metadata.get("position_details");
+    APY_KEYS.iter().find_map(|key| {
+        position.metadata.get(*key)
+            .or_else(|| details.and_then(|d| d.get(*key)))
+            .and_then(|v| v.as_f64())
+            .filter(|apy| apy.is_finite())
+    })
+}
+
+/// Underlying tokens a position is exposed to. Liquidity positions are split evenly
+/// across both legs of the pair; everything else is attributed to a single asset.
+pub fn underlying_tokens(position: &Position) -> Vec<String> {
+    for key in ["underlying_asset", "asset_symbol"] {
+        if let Some(symbol) = position.metadata.get(key).and_then(|v| v.as_str()) {
+            if !symbol.is_empty() {
+                return vec![symbol.to_uppercase()];
+            }
+        }
+    }
+
+    let legs: Vec<String> = position.pair
+        .split('/')
+        .map(|leg| leg.trim().to_uppercase())
+        .filter(|leg| !leg.is_empty())
+        .collect();
+
+    if legs.is_empty() {
+        vec!["UNKNOWN".to_string()]
+    } else if position.position_type == "liquidity" {
+        legs
+    } else {
+        vec![legs[0].clone()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(id: &str, position_type: &str, pair: &str, value_usd: f64) -> Position {
+        Position {
+            id: id.to_string(),
+            protocol: "test".to_string(),
+            position_type: position_type.to_string(),
+            pair: pair.to_string(),
+            value_usd,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({}),
+            last_updated: 0,
+        }
+    }
+
+    #[test]
+    fn test_borrow_is_netted_against_supply() {
+        let portfolio = aggregate(vec![
+            position("a", "supply", "USDC/WETH", 1000.0),
+            position("b", "borrow", "USDC/WETH", -400.0),
+            position("c", "collateral", "WETH/USDC", 2000.0),
+        ]);
+
+        assert_eq!(portfolio.total_supplied_usd, 3000.0);
+        assert_eq!(portfolio.total_borrowed_usd, 400.0);
+        assert_eq!(portfolio.net_exposure_by_token["USDC"], 600.0);
+        assert_eq!(portfolio.net_exposure_by_token["WETH"], 2000.0);
+        assert!((portfolio.leverage_ratio.unwrap() - 3000.0 / 2600.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_liquidity_is_split_across_pair_and_duplicates_are_dropped() {
+        let portfolio = aggregate(vec![
+            position("lp", "liquidity", "WETH/USDC", 1000.0),
+            position("lp", "liquidity", "WETH/USDC", 1000.0),
+        ]);
+
+        assert_eq!(portfolio.duplicates_removed, 1);
+        assert_eq!(portfolio.net_exposure_by_token["WETH"], 500.0);
+        assert_eq!(portfolio.net_exposure_by_token["USDC"], 500.0);
+        assert_eq!(portfolio.leverage_ratio, Some(1.0));
+    }
+
+    #[test]
+    fn test_underlying_asset_metadata_takes_precedence() {
+        let mut staked = position("s", "staking", "stETH/ETH", 500.0);
+        staked.metadata = serde_json::json!({ "underlying_asset": "ETH" });
+
+        let portfolio = aggregate(vec![staked]);
+        assert_eq!(portfolio.net_exposure_by_token["ETH"], 500.0);
+    }
+
+    #[test]
+    fn test_borrow_cost_is_subtracted_from_net_apy() {
+        let mut supply = position("a", "supply", "USDC", 20_000.0);
+        supply.metadata = serde_json::json!({ "position_details": { "supply_apy": 5.0 } });
+        let mut borrow = position("b", "borrow", "WETH", -5_000.0);
+        borrow.metadata = serde_json::json!({ "position_details": { "borrow_apy": 3.0 } });
+        let mut staked = position("c", "staking", "stETH/ETH", 5_000.0);
+        staked.metadata = serde_json::json!({ "current_apy": 4.0 });
+
+        let portfolio = aggregate(vec![supply, borrow, staked]);
+        assert!((portfolio.projected_annual_yield_usd - 1_050.0).abs() < 1e-9);
+        assert!((portfolio.portfolio_net_apy.unwrap() - 5.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_wallets_combine_without_cross_wallet_dedup() {
+        let treasury = aggregate(vec![position("lido", "staking", "stETH", 3_000.0)]);
+        let multisig = aggregate(vec![
+            position("lido", "staking", "stETH", 1_000.0),
+            position("b", "borrow", "STETH", 2_000.0),
+        ]);
+
+        let combined = combine(&[treasury, multisig]);
+
+        assert_eq!(combined.duplicates_removed, 0);
+        assert_eq!(combined.total_supplied_usd, 4_000.0);
+        assert_eq!(combined.net_exposure_by_token["STETH"], 2_000.0);
+        assert_eq!(combined.leverage_ratio, Some(2.0));
+    }
+}
@@ -0,0 +1,294 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::adapters::traits::{AdapterError, Position};
+use crate::blockchain::EthereumClient;
+use crate::services::oracle_deviation::{is_exposed, market_for_coin, read_market_prices, MARKETS};
+use crate::services::price_service::{price_timestamp, PriceService};
+
+/// How long an aggregated price is reused before reading the sources again
+const AGGREGATE_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    Chainlink,
+    UniswapV3Twap,
+    CoinGecko,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SourcePrice {
+    pub source: PriceSource,
+    pub price_usd: f64,
+}
+
+/// A token's price as agreed on by several sources
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AggregatedPrice {
+    /// Median of the source prices
+    pub price_usd: f64,
+    pub sources: Vec<SourcePrice>,
+    /// Largest relative gap between a source and the median
+    pub max_deviation: f64,
+    /// In [0, 1]: falls as sources disagree, and is capped when few sources answered
+    pub confidence: f64,
+    /// Sources disagree by more than the configured threshold
+    pub uncertain: bool,
+}
+
+/// Combine source prices into their median, or `None` without a positive price.
+/// A lone source caps confidence at 0.5 since nothing corroborates it.
+pub fn aggregate(quotes: Vec<SourcePrice>, threshold: f64) -> Option<AggregatedPrice> {
+    let sources: Vec<SourcePrice> = quotes
+        .into_iter()
+        .filter(|quote| quote.price_usd.is_finite() && quote.price_usd > 0.0)
+        .collect();
+    if sources.is_empty() {
+        return None;
+    }
+
+    let mut prices: Vec<f64> = sources.iter().map(|quote| quote.price_usd).collect();
+    prices.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mid = prices.len() / 2;
+    let median = if prices.len() % 2 == 0 {
+        (prices[mid - 1] + prices[mid]) / 2.0
+    } else {
+        prices[mid]
+    };
+
+    let max_deviation = prices
+        .iter()
+        .map(|price| (price - median).abs() / median)
+        .fold(0.0, f64::max);
+    let agreement = if threshold > 0.0 {
+        (1.0 - max_deviation / (2.0 * threshold)).clamp(0.0, 1.0)
+    } else if max_deviation > 0.0 {
+        0.0
+    } else {
+        1.0
+    };
+    let coverage = match sources.len() {
+        1 => 0.5,
+        2 => 0.8,
+        _ => 1.0,
+    };
+
+    Some(AggregatedPrice {
+        price_usd: median,
+        sources,
+        max_deviation,
+        confidence: agreement * coverage,
+        uncertain: max_deviation > threshold,
+    })
+}
+
+/// Prices tokens from Chainlink, a Uniswap V3 TWAP and CoinGecko where each is
+/// available, so one bad source can't skew a valuation. Tokens without on-chain
+/// markets fall back to CoinGecko alone.
+pub struct PriceAggregator {
+    /// Mainnet client for the on-chain sources; CoinGecko only when `None`
+    client: Option<EthereumClient>,
+    price_service: Arc<PriceService>,
+    /// Disagreement between sources, as a fraction of the median, that marks a price uncertain
+    threshold: f64,
+    twap_window_secs: u32,
+    latest: RwLock<HashMap<String, (AggregatedPrice, Instant)>>,
+}
+
+impl PriceAggregator {
+    pub fn new(client: Option<EthereumClient>, price_service: Arc<PriceService>, threshold: f64, twap_window_secs: u32) -> Self {
+        Self {
+            client,
+            price_service,
+            threshold,
+            twap_window_secs,
+            latest: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Mainnet RPC from `ETHEREUM_RPC_URLS` or `ETHEREUM_RPC_URL`, with
+    /// `PRICE_DISAGREEMENT_THRESHOLD` (default 0.02) and `ORACLE_TWAP_SECONDS` (1800)
+    pub fn from_env() -> Self {
+        let urls: Vec<String> = std::env::var("ETHEREUM_RPC_URLS")
+            .or_else(|_| std::env::var("ETHEREUM_RPC_URL"))
+            .unwrap_or_default()
+            .split(',')
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .collect();
+        let client = if urls.is_empty() {
+            None
+        } else {
+            EthereumClient::with_fallbacks(urls)
+                .map_err(|e| tracing::warn!("Price aggregator has no on-chain sources: {}", e))
+                .ok()
+        };
+        let read = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<f64>().ok());
+
+        Self::new(
+            client,
+            PriceService::global(),
+            read("PRICE_DISAGREEMENT_THRESHOLD").unwrap_or(0.02),
+            read("ORACLE_TWAP_SECONDS").map_or(1800, |secs| secs as u32),
+        )
+    }
+
+    /// Process-wide aggregator shared by all adapters
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<PriceAggregator>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::from_env())).clone()
+    }
+
+    /// Median USD price for a CoinGecko coin id; a drop-in for [`PriceService::get_price`]
+    pub async fn get_price(&self, coin_id: &str) -> Result<f64, AdapterError> {
+        Ok(self.get_aggregated(coin_id).await?.price_usd)
+    }
+
+    /// Every source's price for `coin_id` and how well they agree. Historical valuations
+    /// use CoinGecko alone, since on-chain reads here are at the latest block.
+    pub async fn get_aggregated(&self, coin_id: &str) -> Result<AggregatedPrice, AdapterError> {
+        if price_timestamp().is_some() {
+            let price_usd = self.price_service.get_price(coin_id).await?;
+            return aggregate(vec![SourcePrice { source: PriceSource::CoinGecko, price_usd }], self.threshold)
+                .ok_or_else(|| AdapterError::InvalidData(format!("No positive price for {}", coin_id)));
+        }
+
+        if let Some((aggregated, at)) = self.latest.read().unwrap().get(coin_id) {
+            if at.elapsed() < AGGREGATE_TTL {
+                return Ok(aggregated.clone());
+            }
+        }
+
+        let mut quotes = Vec::new();
+        let onchain = async {
+            let (client, market) = (self.client.as_ref()?, market_for_coin(coin_id)?);
+            match read_market_prices(client, market, self.twap_window_secs).await {
+                Ok(prices) => Some(prices),
+                Err(e) => {
+                    tracing::warn!("On-chain {} prices unavailable: {}", market.symbol, e);
+                    None
+                }
+            }
+        };
+        let (onchain, coingecko) = tokio::join!(onchain, self.price_service.get_price(coin_id));
+
+        if let Some(prices) = onchain {
+            quotes.push(SourcePrice { source: PriceSource::Chainlink, price_usd: prices.chainlink_price });
+            quotes.push(SourcePrice { source: PriceSource::UniswapV3Twap, price_usd: prices.twap_price });
+        }
+        let coingecko_error = match coingecko {
+            Ok(price_usd) => {
+                quotes.push(SourcePrice { source: PriceSource::CoinGecko, price_usd });
+                None
+            }
+            Err(e) => Some(e),
+        };
+
+        let Some(aggregated) = aggregate(quotes, self.threshold) else {
+            return Err(coingecko_error
+                .unwrap_or_else(|| AdapterError::InvalidData(format!("No positive price for {}", coin_id))));
+        };
+        if aggregated.uncertain {
+            tracing::warn!(
+                "Price sources for {} disagree by {:.2}%: {:?}",
+                coin_id, aggregated.max_deviation * 100.0, aggregated.sources
+            );
+        }
+
+        self.latest.write().unwrap().insert(coin_id.to_string(), (aggregated.clone(), Instant::now()));
+        Ok(aggregated)
+    }
+
+    /// Last aggregated price for `coin_id`, if read within the reuse window
+    pub fn latest(&self, coin_id: &str) -> Option<AggregatedPrice> {
+        let latest = self.latest.read().unwrap();
+        let (aggregated, at) = latest.get(coin_id)?;
+        (at.elapsed() < AGGREGATE_TTL).then(|| aggregated.clone())
+    }
+
+    /// Mark positions exposed to a token whose sources currently disagree with
+    /// `price_uncertain` and that price's `price_confidence`
+    pub fn tag_price_uncertainty(&self, positions: &mut [Position]) {
+        let uncertain: Vec<_> = MARKETS
+            .iter()
+            .filter_map(|market| Some((market, self.latest(market.coin_id).filter(|price| price.uncertain)?)))
+            .collect();
+        if uncertain.is_empty() {
+            return;
+        }
+
+        for position in positions {
+            let Some(confidence) = uncertain
+                .iter()
+                .filter(|(market, _)| is_exposed(position, market))
+                .map(|(_, price)| price.confidence)
+                .reduce(f64::min)
+            else {
+                continue;
+            };
+            if let Some(metadata) = position.metadata.as_object_mut() {
+                metadata.insert("price_uncertain".to_string(), serde_json::json!(true));
+                metadata.insert("price_confidence".to_string(), serde_json::json!(confidence));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(source: PriceSource, price_usd: f64) -> SourcePrice {
+        SourcePrice { source, price_usd }
+    }
+
+    #[test]
+    fn test_agreeing_sources_give_confident_median() {
+        let aggregated = aggregate(
+            vec![
+                quote(PriceSource::Chainlink, 3_000.0),
+                quote(PriceSource::UniswapV3Twap, 3_006.0),
+                quote(PriceSource::CoinGecko, 2_997.0),
+            ],
+            0.02,
+        )
+        .unwrap();
+
+        assert_eq!(aggregated.price_usd, 3_000.0);
+        assert!(!aggregated.uncertain);
+        assert!(aggregated.confidence > 0.9);
+    }
+
+    #[test]
+    fn test_outlier_source_is_outvoted_but_flagged() {
+        let aggregated = aggregate(
+            vec![
+                quote(PriceSource::Chainlink, 3_000.0),
+                quote(PriceSource::UniswapV3Twap, 2_400.0),
+                quote(PriceSource::CoinGecko, 3_010.0),
+            ],
+            0.02,
+        )
+        .unwrap();
+
+        assert_eq!(aggregated.price_usd, 3_000.0);
+        assert!(aggregated.uncertain);
+        assert!((aggregated.max_deviation - 0.2).abs() < 1e-12);
+        assert_eq!(aggregated.confidence, 0.0);
+    }
+
+    #[test]
+    fn test_lone_or_missing_sources() {
+        let lone = aggregate(vec![quote(PriceSource::CoinGecko, 1.0)], 0.02).unwrap();
+        assert_eq!(lone.confidence, 0.5);
+        assert!(!lone.uncertain);
+
+        let pair = aggregate(vec![quote(PriceSource::Chainlink, 1.0), quote(PriceSource::CoinGecko, 1.02)], 0.02).unwrap();
+        assert!((pair.price_usd - 1.01).abs() < 1e-12);
+
+        assert!(aggregate(vec![quote(PriceSource::CoinGecko, 0.0)], 0.02).is_none());
+    }
+}
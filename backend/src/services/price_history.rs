@@ -0,0 +1,112 @@
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::adapters::traits::AdapterError;
+
+#[derive(Debug, Deserialize)]
+struct MarketChart {
+    prices: Vec<(f64, f64)>,
+}
+
+/// CoinGecko coin ID for a token symbol as positions report it, for looking up its
+/// price history; `None` for tokens without a well-known listing
+pub fn coin_id_for_symbol(symbol: &str) -> Option<&'static str> {
+    let coin_id = match symbol.trim().to_uppercase().as_str() {
+        "ETH" | "WETH" => "ethereum",
+        "STETH" => "staked-ether",
+        "WSTETH" => "wrapped-steth",
+        "RETH" => "rocket-pool-eth",
+        "CBETH" => "coinbase-wrapped-staked-eth",
+        "EETH" | "WEETH" => "wrapped-eeth",
+        "FRXETH" => "frax-ether",
+        "SFRXETH" => "staked-frax-ether",
+        "WBTC" => "wrapped-bitcoin",
+        "CBBTC" => "coinbase-wrapped-btc",
+        "TBTC" => "tbtc",
+        "USDC" | "USDC.E" | "USDBC" => "usd-coin",
+        "USDT" => "tether",
+        "DAI" => "dai",
+        "SDAI" => "savings-dai",
+        "FRAX" => "frax",
+        "LUSD" => "liquity-usd",
+        "CRVUSD" => "crvusd",
+        "PYUSD" => "paypal-usd",
+        "USDE" => "ethena-usde",
+        "SUSDE" => "ethena-staked-usde",
+        "GHO" => "gho",
+        "AAVE" => "aave",
+        "COMP" => "compound-governance-token",
+        "UNI" => "uniswap",
+        "LINK" => "chainlink",
+        "MKR" => "maker",
+        "CRV" => "curve-dao-token",
+        "CVX" => "convex-finance",
+        "FXS" => "frax-share",
+        "LDO" => "lido-dao",
+        "RPL" => "rocket-pool",
+        "GMX" => "gmx",
+        "ARB" => "arbitrum",
+        "OP" => "optimism",
+        "MATIC" | "POL" => "matic-network",
+        _ => return None,
+    };
+    Some(coin_id)
+}
+
+/// Daily USD closing prices for a CoinGecko coin ID (e.g. "ethereum"), oldest first
+pub async fn fetch_daily_prices(
+    coin_id: &str,
+    days: u32,
+    coingecko_api_key: Option<&str>,
+) -> Result<Vec<f64>, AdapterError> {
+    let points = fetch_daily_price_points(coin_id, days, coingecko_api_key).await?;
+    Ok(points.into_iter().map(|(_, price)| price).collect())
+}
+
+/// As [`fetch_daily_prices`], with the unix timestamp (seconds) of each price
+pub async fn fetch_daily_price_points(
+    coin_id: &str,
+    days: u32,
+    coingecko_api_key: Option<&str>,
+) -> Result<Vec<(u64, f64)>, AdapterError> {
+    let base_url = if coingecko_api_key.is_some() {
+        "https://pro-api.coingecko.com/api/v3"
+    } else {
+        "https://api.coingecko.com/api/v3"
+    };
+
+    let url = format!(
+        "{}/coins/{}/market_chart?vs_currency=usd&days={}&interval=daily",
+        base_url, coin_id, days
+    );
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| AdapterError::NetworkError(format!("Failed to build HTTP client: {}", e)))?;
+
+    let mut request = client
+        .get(&url)
+        .header("Accept", "application/json")
+        .header("User-Agent", "DeFi-Portfolio-Tracker/1.0");
+
+    if let Some(api_key) = coingecko_api_key {
+        request = request.header("X-Cg-Pro-Api-Key", api_key);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| AdapterError::NetworkError(format!("HTTP request failed: {}", e)))?;
+
+    if !response.status().is_success() {
+        return Err(AdapterError::NetworkError(format!("API returned status: {}", response.status())));
+    }
+
+    let chart: MarketChart = response
+        .json()
+        .await
+        .map_err(|e| AdapterError::InvalidData(format!("Failed to parse price history: {}", e)))?;
+
+    Ok(chart.prices.into_iter().map(|(millis, price)| ((millis / 1000.0) as u64, price)).collect())
+}
@@ -0,0 +1,535 @@
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use alloy::primitives::Address;
+
+use crate::adapters::traits::AdapterError;
+use crate::utils::{ApiClient, ApiClientConfig, CircuitBreaker, CircuitState};
+
+/// Last successfully fetched price for a CoinGecko coin id
+#[derive(Debug, Clone, Copy)]
+struct CachedPrice {
+    price_usd: f64,
+    fetched_at: Instant,
+}
+
+/// How long fiat exchange rates are reused before refetching
+const FX_RATE_TTL: Duration = Duration::from_secs(600);
+
+/// How long a token CoinGecko doesn't list (or lists with low confidence) is remembered
+const UNKNOWN_COIN_ID_TTL: Duration = Duration::from_secs(900);
+
+/// CoinGecko asset platform ids by chain id
+fn coingecko_platform(chain_id: u64) -> Option<&'static str> {
+    match chain_id {
+        1 => Some("ethereum"),
+        10 => Some("optimistic-ethereum"),
+        137 => Some("polygon-pos"),
+        8453 => Some("base"),
+        42161 => Some("arbitrum-one"),
+        43114 => Some("avalanche"),
+        _ => None,
+    }
+}
+
+/// Confidence in [0, 1] that a CoinGecko contract lookup is the token we hold: half
+/// for matching the on-chain symbol, the rest for being ranked and actively priced
+fn coin_match_confidence(coin: &serde_json::Value, onchain_symbol: Option<&str>) -> f64 {
+    let symbol_matches = match (coin.get("symbol").and_then(|s| s.as_str()), onchain_symbol) {
+        (Some(listed), Some(onchain)) => listed.trim().eq_ignore_ascii_case(onchain.trim()),
+        // Nothing to compare against; don't penalize
+        (Some(_), None) => true,
+        (None, _) => false,
+    };
+    let ranked = coin.get("market_cap_rank").is_some_and(|rank| rank.is_u64());
+    let priced = coin
+        .get("market_data")
+        .and_then(|m| m.get("current_price"))
+        .and_then(|p| p.get("usd"))
+        .and_then(|usd| usd.as_f64())
+        .is_some_and(|price| price > 0.0);
+
+    let mut confidence = 0.0;
+    if symbol_matches {
+        confidence += 0.5;
+    }
+    if ranked {
+        confidence += 0.3;
+    }
+    if priced {
+        confidence += 0.2;
+    }
+    confidence
+}
+
+/// Prices quoted at a past time, and the coins that had to fall back to today's price
+#[derive(Debug)]
+struct PriceScope {
+    timestamp: u64,
+    spot_fallbacks: Arc<Mutex<BTreeSet<String>>>,
+}
+
+tokio::task_local! {
+    /// Set while valuing a historical position
+    static PRICE_SCOPE: PriceScope;
+}
+
+/// Run `future` with every [`PriceService::get_price`] inside it quoting the price at
+/// `timestamp` (daily granularity) instead of the current price. Also returns the coin
+/// ids CoinGecko had no price for at `timestamp`, which were quoted at the current price.
+pub async fn with_price_timestamp<F: Future>(timestamp: Option<u64>, future: F) -> (F::Output, Vec<String>) {
+    match timestamp {
+        Some(timestamp) => {
+            let spot_fallbacks = Arc::new(Mutex::new(BTreeSet::new()));
+            let scope = PriceScope { timestamp, spot_fallbacks: spot_fallbacks.clone() };
+            let output = PRICE_SCOPE.scope(scope, future).await;
+            let spot_fallbacks = spot_fallbacks.lock().unwrap().iter().cloned().collect();
+            (output, spot_fallbacks)
+        }
+        None => (future.await, Vec::new()),
+    }
+}
+
+/// Unix time prices are being quoted at, when inside [`with_price_timestamp`]
+pub(crate) fn price_timestamp() -> Option<u64> {
+    PRICE_SCOPE.try_with(|scope| scope.timestamp).ok()
+}
+
+/// Age of a tracked price, for status reporting
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceAge {
+    pub coin_id: String,
+    pub price_usd: f64,
+    pub age_secs: u64,
+    pub stale: bool,
+}
+
+/// Centralized CoinGecko price fetching with a circuit breaker.
+///
+/// After `failure_threshold` consecutive failures the breaker opens and requests are
+/// answered from the last successful price for the cooldown window instead of
+/// hitting the API. Cached prices older than `max_staleness` are never served.
+pub struct PriceService {
+    http_client: ApiClient,
+    api_key: Option<String>,
+    breaker: CircuitBreaker,
+    max_staleness: Duration,
+    last_prices: RwLock<HashMap<String, CachedPrice>>,
+    /// Units of each fiat currency per USD, with when they were fetched
+    fx_rates: RwLock<Option<(HashMap<String, f64>, Instant)>>,
+    /// Past daily prices keyed by coin id and `dd-mm-yyyy` date; these never change
+    historical_prices: RwLock<HashMap<(String, String), f64>>,
+    /// CoinGecko coin id by (chain id, token contract); `None` for tokens without a
+    /// confident match, retried after `UNKNOWN_COIN_ID_TTL`
+    coin_ids: RwLock<HashMap<(u64, Address), (Option<String>, Instant)>>,
+    /// Contract lookups scoring below this are treated as unknown tokens
+    min_confidence: f64,
+}
+
+impl PriceService {
+    pub fn new(
+        api_key: Option<String>,
+        failure_threshold: u32,
+        cooldown: Duration,
+        max_staleness: Duration,
+    ) -> Self {
+        Self {
+            http_client: ApiClientConfig::from_env()
+                .build()
+                .expect("HTTP client with default TLS settings"),
+            api_key,
+            breaker: CircuitBreaker::new(failure_threshold, cooldown),
+            max_staleness,
+            last_prices: RwLock::new(HashMap::new()),
+            fx_rates: RwLock::new(None),
+            historical_prices: RwLock::new(HashMap::new()),
+            coin_ids: RwLock::new(HashMap::new()),
+            min_confidence: 0.7,
+        }
+    }
+
+    /// Contract lookups below `min_confidence` are treated as unpriced
+    pub fn with_min_confidence(mut self, min_confidence: f64) -> Self {
+        self.min_confidence = min_confidence.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Configured from `COINGECKO_API_KEY`, `PRICE_BREAKER_FAILURES` (default 5),
+    /// `PRICE_BREAKER_COOLDOWN_SECS` (60), `PRICE_MAX_STALENESS_SECS` (900) and
+    /// `COINGECKO_MIN_CONFIDENCE` (0.7)
+    pub fn from_env() -> Self {
+        let read = |key: &str, default: u64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self::new(
+            std::env::var("COINGECKO_API_KEY").ok(),
+            read("PRICE_BREAKER_FAILURES", 5) as u32,
+            Duration::from_secs(read("PRICE_BREAKER_COOLDOWN_SECS", 60)),
+            Duration::from_secs(read("PRICE_MAX_STALENESS_SECS", 900)),
+        )
+        .with_min_confidence(
+            std::env::var("COINGECKO_MIN_CONFIDENCE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.7),
+        )
+    }
+
+    /// Process-wide service shared by all adapters
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<PriceService>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::from_env())).clone()
+    }
+
+    /// USD price for a CoinGecko coin id, falling back to the last good price when
+    /// CoinGecko fails or the circuit is open.
+    ///
+    /// Inside [`with_price_timestamp`] this is the historical price at that time when
+    /// CoinGecko has one, and otherwise the current price, recorded as a fallback.
+    pub async fn get_price(&self, coin_id: &str) -> Result<f64, AdapterError> {
+        if let Some(timestamp) = price_timestamp() {
+            match self.get_historical_price(coin_id, timestamp).await {
+                Ok(price) => return Ok(price),
+                Err(e) => {
+                    tracing::warn!("Historical {} price unavailable, using current price: {}", coin_id, e);
+                    let _ = PRICE_SCOPE.try_with(|scope| scope.spot_fallbacks.lock().unwrap().insert(coin_id.to_string()));
+                }
+            }
+        }
+
+        if !self.breaker.allow_request() {
+            return self.cached_price(coin_id).ok_or_else(|| {
+                AdapterError::NetworkError("CoinGecko circuit open and no fresh cached price".to_string())
+            });
+        }
+
+        match self.fetch_price(coin_id).await {
+            Ok(price) => {
+                self.breaker.record_success();
+                self.last_prices.write().unwrap().insert(coin_id.to_string(), CachedPrice {
+                    price_usd: price,
+                    fetched_at: Instant::now(),
+                });
+                Ok(price)
+            }
+            Err(e) => {
+                self.breaker.record_failure();
+                tracing::warn!("CoinGecko price fetch for {} failed: {}", coin_id, e);
+                self.cached_price(coin_id).ok_or(e)
+            }
+        }
+    }
+
+    /// USD price of a token contract, looked up by address rather than symbol.
+    /// `onchain_symbol` guards against CoinGecko listing a different asset at the
+    /// address; tokens without a confident match are an error, never a default price.
+    pub async fn get_token_price(
+        &self,
+        chain_id: u64,
+        token: Address,
+        onchain_symbol: Option<&str>,
+    ) -> Result<f64, AdapterError> {
+        match self.coin_id_for_token(chain_id, token, onchain_symbol).await {
+            Some(coin_id) => self.get_price(&coin_id).await,
+            None => Err(AdapterError::UnsupportedProtocol(format!(
+                "No CoinGecko price for token {:?} on chain {}", token, chain_id
+            ))),
+        }
+    }
+
+    /// CoinGecko coin id for a token contract via `/coins/{platform}/contract/{address}`,
+    /// cached once resolved
+    pub async fn coin_id_for_token(&self, chain_id: u64, token: Address, onchain_symbol: Option<&str>) -> Option<String> {
+        if let Some((coin_id, resolved_at)) = self.coin_ids.read().unwrap().get(&(chain_id, token)) {
+            if coin_id.is_some() || resolved_at.elapsed() < UNKNOWN_COIN_ID_TTL {
+                return coin_id.clone();
+            }
+        }
+
+        let platform = coingecko_platform(chain_id)?;
+        if !self.breaker.allow_request() {
+            return None;
+        }
+
+        let path = format!(
+            "/coins/{}/contract/{:?}?localization=false&tickers=false&community_data=false&developer_data=false",
+            platform, token
+        );
+        let coin_id = match self.get_json(&path).await {
+            Ok(coin) => {
+                let confidence = coin_match_confidence(&coin, onchain_symbol);
+                let coin_id = coin.get("id").and_then(|id| id.as_str()).map(str::to_string);
+                if confidence < self.min_confidence {
+                    tracing::warn!(
+                        "CoinGecko match {:?} for {:?} has confidence {:.2} below {:.2}; treating as unpriced",
+                        coin_id, token, confidence, self.min_confidence
+                    );
+                    None
+                } else {
+                    coin_id
+                }
+            }
+            Err(e) => {
+                tracing::debug!("CoinGecko contract lookup for {:?} failed: {}", token, e);
+                None
+            }
+        };
+
+        self.coin_ids.write().unwrap().insert((chain_id, token), (coin_id.clone(), Instant::now()));
+        coin_id
+    }
+
+    /// USD price of `coin_id` on the UTC day containing `timestamp`
+    pub async fn get_historical_price(&self, coin_id: &str, timestamp: u64) -> Result<f64, AdapterError> {
+        let date = chrono::DateTime::from_timestamp(timestamp as i64, 0)
+            .ok_or_else(|| AdapterError::InvalidData(format!("Invalid timestamp {}", timestamp)))?
+            .format("%d-%m-%Y")
+            .to_string();
+        let key = (coin_id.to_string(), date);
+
+        if let Some(price) = self.historical_prices.read().unwrap().get(&key) {
+            return Ok(*price);
+        }
+        if !self.breaker.allow_request() {
+            return Err(AdapterError::NetworkError("CoinGecko circuit open".to_string()));
+        }
+
+        let data = self.get_json(&format!("/coins/{}/history?date={}&localization=false", coin_id, key.1)).await?;
+        let price = data
+            .get("market_data")
+            .and_then(|m| m.get("current_price"))
+            .and_then(|p| p.get("usd"))
+            .and_then(|usd| usd.as_f64())
+            .ok_or_else(|| AdapterError::InvalidData(format!("No {} price for {}", coin_id, key.1)))?;
+
+        self.historical_prices.write().unwrap().insert(key, price);
+        Ok(price)
+    }
+
+    /// Units of fiat `currency` per US dollar (about 0.92 for "eur"), cached for
+    /// ten minutes and falling back to the last rates while they aren't stale
+    pub async fn usd_fx_rate(&self, currency: &str) -> Result<f64, AdapterError> {
+        let currency = currency.trim().to_lowercase();
+        if currency == "usd" {
+            return Ok(1.0);
+        }
+
+        self.fiat_rates()
+            .await?
+            .get(&currency)
+            .copied()
+            .ok_or_else(|| AdapterError::InvalidData(format!("Unsupported currency: {}", currency.to_uppercase())))
+    }
+
+    async fn fiat_rates(&self) -> Result<HashMap<String, f64>, AdapterError> {
+        let cached = self.fx_rates.read().unwrap().clone();
+        if let Some((rates, fetched_at)) = &cached {
+            if fetched_at.elapsed() < FX_RATE_TTL {
+                return Ok(rates.clone());
+            }
+        }
+
+        match self.fetch_fiat_rates().await {
+            Ok(rates) => {
+                *self.fx_rates.write().unwrap() = Some((rates.clone(), Instant::now()));
+                Ok(rates)
+            }
+            Err(e) => {
+                tracing::warn!("CoinGecko exchange rate fetch failed: {}", e);
+                cached
+                    .filter(|(_, fetched_at)| fetched_at.elapsed() <= self.max_staleness)
+                    .map(|(rates, _)| rates)
+                    .ok_or(e)
+            }
+        }
+    }
+
+    /// Age of the last successful price for `coin_id`
+    pub fn price_age(&self, coin_id: &str) -> Option<Duration> {
+        self.last_prices.read().unwrap().get(coin_id).map(|p| p.fetched_at.elapsed())
+    }
+
+    pub fn circuit_state(&self) -> CircuitState {
+        self.breaker.state()
+    }
+
+    /// Every tracked price with its age, sorted by coin id
+    pub fn price_ages(&self) -> Vec<PriceAge> {
+        let mut ages: Vec<PriceAge> = self.last_prices
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(coin_id, cached)| {
+                let age = cached.fetched_at.elapsed();
+                PriceAge {
+                    coin_id: coin_id.clone(),
+                    price_usd: cached.price_usd,
+                    age_secs: age.as_secs(),
+                    stale: age > self.max_staleness,
+                }
+            })
+            .collect();
+        ages.sort_by(|a, b| a.coin_id.cmp(&b.coin_id));
+        ages
+    }
+
+    fn cached_price(&self, coin_id: &str) -> Option<f64> {
+        self.last_prices
+            .read()
+            .unwrap()
+            .get(coin_id)
+            .filter(|cached| cached.fetched_at.elapsed() <= self.max_staleness)
+            .map(|cached| cached.price_usd)
+    }
+
+    async fn fetch_price(&self, coin_id: &str) -> Result<f64, AdapterError> {
+        let data = self.get_json(&format!("/simple/price?ids={}&vs_currencies=usd", coin_id)).await?;
+
+        data.get(coin_id)
+            .and_then(|coin| coin.get("usd"))
+            .and_then(|price| price.as_f64())
+            .ok_or_else(|| AdapterError::InvalidData(format!("{} price not found", coin_id)))
+    }
+
+    async fn fetch_fiat_rates(&self) -> Result<HashMap<String, f64>, AdapterError> {
+        let data = self.get_json("/exchange_rates").await?;
+        fiat_rates_per_usd(&data)
+    }
+
+    async fn get_json(&self, path: &str) -> Result<serde_json::Value, AdapterError> {
+        let base_url = if self.api_key.is_some() {
+            "https://pro-api.coingecko.com/api/v3"
+        } else {
+            "https://api.coingecko.com/api/v3"
+        };
+
+        let mut request = self.http_client.get(&format!("{}{}", base_url, path));
+        if let Some(api_key) = &self.api_key {
+            request = request.header("X-Cg-Pro-Api-Key", api_key);
+        }
+
+        let response = self.http_client.send(request).await?;
+
+        if !response.status().is_success() {
+            return Err(AdapterError::NetworkError(format!("CoinGecko HTTP {}", response.status())));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| AdapterError::InvalidData(format!("CoinGecko JSON error: {}", e)))
+    }
+}
+
+/// CoinGecko's `/exchange_rates` are quoted per BTC; rebase the fiat ones onto USD
+fn fiat_rates_per_usd(data: &serde_json::Value) -> Result<HashMap<String, f64>, AdapterError> {
+    let rates = data
+        .get("rates")
+        .and_then(|r| r.as_object())
+        .ok_or_else(|| AdapterError::InvalidData("Exchange rates missing".to_string()))?;
+    let btc_in_usd = rates
+        .get("usd")
+        .and_then(|usd| usd.get("value"))
+        .and_then(|v| v.as_f64())
+        .filter(|v| *v > 0.0)
+        .ok_or_else(|| AdapterError::InvalidData("USD exchange rate missing".to_string()))?;
+
+    Ok(rates
+        .iter()
+        .filter(|(_, rate)| rate.get("type").and_then(|t| t.as_str()) == Some("fiat"))
+        .filter_map(|(code, rate)| {
+            let btc_in_fiat = rate.get("value")?.as_f64()?;
+            Some((code.clone(), btc_in_fiat / btc_in_usd))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contract_match_confidence() {
+        let listed = serde_json::json!({
+            "id": "weth",
+            "symbol": "weth",
+            "market_cap_rank": 30,
+            "market_data": { "current_price": { "usd": 3000.0 } },
+        });
+        assert_eq!(coin_match_confidence(&listed, Some("WETH")), 1.0);
+        assert_eq!(coin_match_confidence(&listed, Some("SCAM")), 0.5);
+
+        let obscure = serde_json::json!({ "id": "tiny", "symbol": "tiny", "market_cap_rank": null });
+        assert_eq!(coin_match_confidence(&obscure, Some("TINY")), 0.5);
+    }
+
+    #[test]
+    fn test_stale_cached_prices_are_not_served() {
+        let service = PriceService::new(None, 1, Duration::from_secs(60), Duration::from_secs(60));
+        service.last_prices.write().unwrap().insert("ethereum".to_string(), CachedPrice {
+            price_usd: 3000.0,
+            fetched_at: Instant::now() - Duration::from_secs(120),
+        });
+        service.last_prices.write().unwrap().insert("bitcoin".to_string(), CachedPrice {
+            price_usd: 60000.0,
+            fetched_at: Instant::now(),
+        });
+
+        assert_eq!(service.cached_price("ethereum"), None);
+        assert_eq!(service.cached_price("bitcoin"), Some(60000.0));
+        assert!(service.price_ages()[1].stale);
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_serves_cache_without_network() {
+        let service = PriceService::new(None, 1, Duration::from_secs(60), Duration::from_secs(60));
+        service.last_prices.write().unwrap().insert("ethereum".to_string(), CachedPrice {
+            price_usd: 3000.0,
+            fetched_at: Instant::now(),
+        });
+        service.breaker.record_failure();
+
+        assert_eq!(service.circuit_state(), CircuitState::Open);
+        assert_eq!(service.get_price("ethereum").await.unwrap(), 3000.0);
+        assert!(service.get_price("bitcoin").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_historical_reads_record_spot_fallbacks() {
+        let service = PriceService::new(None, 1, Duration::from_secs(60), Duration::from_secs(60));
+        service.last_prices.write().unwrap().insert("ethereum".to_string(), CachedPrice {
+            price_usd: 3000.0,
+            fetched_at: Instant::now(),
+        });
+        service.historical_prices.write().unwrap().insert(("bitcoin".to_string(), "14-11-2023".to_string()), 36_000.0);
+        service.breaker.record_failure();
+
+        let read = async { (service.get_price("bitcoin").await.unwrap(), service.get_price("ethereum").await.unwrap()) };
+        let (prices, spot_fallbacks) = with_price_timestamp(Some(1_700_000_000), read).await;
+        assert_eq!(prices, (36_000.0, 3000.0));
+        assert_eq!(spot_fallbacks, vec!["ethereum".to_string()]);
+    }
+
+    #[test]
+    fn test_fiat_rates_are_rebased_onto_usd() {
+        let data = serde_json::json!({
+            "rates": {
+                "btc": { "value": 1.0, "type": "crypto" },
+                "usd": { "value": 60000.0, "type": "fiat" },
+                "eur": { "value": 54000.0, "type": "fiat" },
+            }
+        });
+
+        let rates = fiat_rates_per_usd(&data).unwrap();
+        assert_eq!(rates.get("usd"), Some(&1.0));
+        assert!((rates["eur"] - 0.9).abs() < 1e-12);
+        assert!(!rates.contains_key("btc"));
+    }
+}
@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// How a token's balance behaves as it earns yield, which decides whether balance
+/// growth since entry is P&L or just more tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceSemantics {
+    /// Balance only moves on transfers (ETH, USDC, LP tokens)
+    Static,
+    /// Balance grows in place as yield accrues (stETH, Aave aTokens and debt tokens)
+    Rebasing,
+    /// Fixed share count redeemable for a growing amount of underlying (wstETH, rETH,
+    /// vault shares); quantities and prices are taken in the underlying
+    ShareBased,
+}
+
+impl BalanceSemantics {
+    /// Semantics of well-known yield-bearing tokens; anything else is `Static`
+    pub fn for_symbol(symbol: &str) -> Self {
+        match symbol {
+            "stETH" | "eETH" | "OUSD" | "USDM" | "AMPL" => BalanceSemantics::Rebasing,
+            "wstETH" | "rETH" | "cbETH" | "weETH" | "sfrxETH" | "sDAI" | "sUSDe" | "sUSDS" => BalanceSemantics::ShareBased,
+            _ => BalanceSemantics::Static,
+        }
+    }
+}
+
+/// Value change since entry, split by cause
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct EntryPnl {
+    /// Price movement on the tokens held at entry
+    pub price_pnl: f64,
+    /// Balance growth since entry at the current price: interest or staking rewards
+    pub yield_usd: f64,
+}
+
+/// Price, quantity and time at which a position was first observed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PriceSnapshot {
+    pub entry_price_usd: f64,
+    /// Zero for snapshots recorded without a quantity
+    pub entry_quantity: f64,
+    pub first_seen: u64,
+}
+
+impl PriceSnapshot {
+    /// P&L from price movement since entry: `(current - entry) * quantity`
+    pub fn price_pnl(&self, current_price_usd: f64, quantity: f64) -> f64 {
+        (current_price_usd - self.entry_price_usd) * quantity
+    }
+
+    /// Split the change since entry into price movement and yield. For yield-bearing
+    /// balances the growth over the entry quantity is yield and only the entry quantity
+    /// is exposed to price, so accrual isn't reported as price appreciation. A static
+    /// balance never earns by growing, so its price P&L is on the current quantity.
+    pub fn attribute(&self, semantics: BalanceSemantics, current_price_usd: f64, quantity: f64) -> EntryPnl {
+        if semantics == BalanceSemantics::Static || self.entry_quantity <= 0.0 {
+            return EntryPnl { price_pnl: self.price_pnl(current_price_usd, quantity), yield_usd: 0.0 };
+        }
+
+        // A shrinking balance is a withdrawal, not negative yield
+        let held_since_entry = quantity.min(self.entry_quantity);
+        EntryPnl {
+            price_pnl: self.price_pnl(current_price_usd, held_since_entry),
+            yield_usd: (quantity - self.entry_quantity).max(0.0) * current_price_usd,
+        }
+    }
+
+    /// Days elapsed since the position was first observed
+    pub fn days_held(&self, now: u64) -> f64 {
+        now.saturating_sub(self.first_seen) as f64 / SECONDS_PER_DAY
+    }
+}
+
+/// Store of entry prices keyed by a stable position key.
+///
+/// Entries are written once, on first observation, and never overwritten so
+/// P&L accumulates from that point. When `PRICE_SNAPSHOTS_PATH` is set they are
+/// rewritten there as JSON whenever one is recorded and reloaded on startup;
+/// otherwise they last for the life of the process.
+#[derive(Debug, Default)]
+pub struct PriceSnapshotStore {
+    entries: RwLock<HashMap<String, PriceSnapshot>>,
+    path: Option<PathBuf>,
+    file_lock: Mutex<()>,
+}
+
+impl PriceSnapshotStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_path(path: PathBuf) -> Self {
+        Self { path: Some(path), ..Self::default() }
+    }
+
+    pub fn from_env() -> Self {
+        let store = match std::env::var("PRICE_SNAPSHOTS_PATH") {
+            Ok(path) if !path.is_empty() => Self::with_path(PathBuf::from(path)),
+            _ => Self::new(),
+        };
+        if let Err(e) = store.load() {
+            tracing::warn!("Failed to load price snapshots: {}", e);
+        }
+        store
+    }
+
+    /// Process-wide store shared by all adapters
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<PriceSnapshotStore>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::from_env())).clone()
+    }
+
+    pub fn get(&self, key: &str) -> Option<PriceSnapshot> {
+        self.entries.read().unwrap().get(key).copied()
+    }
+
+    /// Existing snapshot for `key`, or record `current_price_usd` and `quantity` as the entry.
+    /// An unknown (non-positive) price isn't recorded, so the entry waits for a real one.
+    pub fn get_or_record(&self, key: &str, current_price_usd: f64, quantity: f64, now: u64) -> PriceSnapshot {
+        if let Some(snapshot) = self.get(key) {
+            return snapshot;
+        }
+        if current_price_usd <= 0.0 {
+            return PriceSnapshot { entry_price_usd: current_price_usd, entry_quantity: quantity, first_seen: now };
+        }
+
+        let (snapshot, entries) = {
+            let mut entries = self.entries.write().unwrap();
+            let snapshot = *entries.entry(key.to_string()).or_insert(PriceSnapshot {
+                entry_price_usd: current_price_usd,
+                entry_quantity: quantity,
+                first_seen: now,
+            });
+            (snapshot, self.path.is_some().then(|| entries.clone()))
+        };
+        if let Some(entries) = entries {
+            if let Err(e) = self.save(&entries) {
+                tracing::warn!("Failed to persist price snapshots: {}", e);
+            }
+        }
+        snapshot
+    }
+
+    fn load(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let entries: HashMap<String, PriceSnapshot> = serde_json::from_str(&contents)?;
+        *self.entries.write().unwrap() = entries;
+        Ok(())
+    }
+
+    fn save(&self, entries: &HashMap<String, PriceSnapshot>) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let _guard = self.file_lock.lock().unwrap();
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, serde_json::to_vec_pretty(entries)?)?;
+        std::fs::rename(tmp_path, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_price_is_recorded_once() {
+        let store = PriceSnapshotStore::new();
+
+        let first = store.get_or_record("position", 2_000.0, 3.0, 1_000);
+        let later = store.get_or_record("position", 2_500.0, 3.0, 1_000 + 2 * 86_400);
+
+        assert_eq!(first, later);
+        assert_eq!(later.price_pnl(2_500.0, 3.0), 1_500.0);
+        assert_eq!(later.days_held(1_000 + 2 * 86_400), 2.0);
+    }
+
+    #[test]
+    fn test_entries_survive_a_restart_when_persisted() {
+        let path = std::env::temp_dir().join(format!("price_snapshots_{}.json", uuid::Uuid::new_v4()));
+        let recorded = PriceSnapshotStore::with_path(path.clone()).get_or_record("position", 2_000.0, 3.0, 1_000);
+
+        let reloaded = PriceSnapshotStore::with_path(path.clone());
+        reloaded.load().unwrap();
+        assert_eq!(reloaded.get("position"), Some(recorded));
+        assert_eq!(reloaded.get_or_record("position", 2_500.0, 1.0, 5_000), recorded);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_rebasing_growth_is_yield_not_price_gain() {
+        let entry = PriceSnapshot { entry_price_usd: 2_000.0, entry_quantity: 10.0, first_seen: 0 };
+
+        // 10 stETH rebased to 10.4 while ETH went from 2,000 to 2,100
+        let steth = entry.attribute(BalanceSemantics::Rebasing, 2_100.0, 10.4);
+        assert_eq!(steth.price_pnl, 1_000.0);
+        assert!((steth.yield_usd - 840.0).abs() < 1e-9);
+        assert!((steth.price_pnl + steth.yield_usd - (2_100.0 * 10.4 - 2_000.0 * 10.0)).abs() < 1e-9);
+
+        let naive = entry.attribute(BalanceSemantics::Static, 2_100.0, 10.4);
+        assert!((naive.price_pnl - 1_040.0).abs() < 1e-9);
+        assert_eq!(naive.yield_usd, 0.0);
+
+        let withdrawn = entry.attribute(BalanceSemantics::ShareBased, 2_100.0, 4.0);
+        assert_eq!(withdrawn, EntryPnl { price_pnl: 400.0, yield_usd: 0.0 });
+
+        assert_eq!(BalanceSemantics::for_symbol("stETH"), BalanceSemantics::Rebasing);
+        assert_eq!(BalanceSemantics::for_symbol("wstETH"), BalanceSemantics::ShareBased);
+        assert_eq!(BalanceSemantics::for_symbol("USDC"), BalanceSemantics::Static);
+    }
+}
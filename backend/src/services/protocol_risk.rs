@@ -0,0 +1,292 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use crate::adapters::filter::protocol_key;
+use crate::adapters::traits::{AdapterError, Position};
+
+/// Scores shipped with the service, used for protocols a configured source doesn't cover
+const BUNDLED_DATASET: &str = include_str!("../../data/protocol_risk.json");
+
+/// Risk of a protocol nothing is known about
+pub const DEFAULT_PROTOCOL_RISK: f64 = 0.5;
+
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// What's known about a protocol's safety, each in [0, 1]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtocolRiskFactors {
+    /// Share of deployed code covered by reputable audits; 1 is fully audited
+    pub audit_coverage: f64,
+    /// Severity of past exploits; 0 for none
+    pub exploit_history: f64,
+    /// How far control is spread beyond the team; 1 is immutable or fully on-chain governed
+    pub governance_decentralization: f64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+
+impl ProtocolRiskFactors {
+    /// Weighted risk in [0, 1]: missing audits weigh most, then exploits, then governance
+    pub fn risk_score(&self) -> f64 {
+        (0.4 * (1.0 - self.audit_coverage) + 0.35 * self.exploit_history + 0.25 * (1.0 - self.governance_decentralization))
+            .clamp(0.0, 1.0)
+    }
+
+    fn is_valid(&self) -> bool {
+        [self.audit_coverage, self.exploit_history, self.governance_decentralization]
+            .iter()
+            .all(|value| (0.0..=1.0).contains(value))
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Dataset {
+    #[serde(default)]
+    updated_at: Option<String>,
+    protocols: HashMap<String, ProtocolRiskFactors>,
+}
+
+impl Dataset {
+    /// Keys normalized like adapter protocol names; out-of-range entries are dropped
+    fn parse(body: &str) -> Result<Self, AdapterError> {
+        let dataset: Dataset = serde_json::from_str(body)
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid protocol risk dataset: {}", e)))?;
+        let protocols: HashMap<String, ProtocolRiskFactors> = dataset.protocols
+            .into_iter()
+            .filter(|(protocol, factors)| {
+                let valid = factors.is_valid();
+                if !valid {
+                    tracing::warn!("Ignoring protocol risk for {}: scores must be in [0, 1]", protocol);
+                }
+                valid
+            })
+            .map(|(protocol, factors)| (protocol_key(&protocol), factors))
+            .collect();
+        if protocols.is_empty() {
+            return Err(AdapterError::InvalidData("Protocol risk dataset has no valid entries".to_string()));
+        }
+        Ok(Self { updated_at: dataset.updated_at, protocols })
+    }
+}
+
+/// Where a protocol's risk score came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskDataSource {
+    Bundled,
+    File,
+    Remote,
+    /// Not in any dataset; scored `DEFAULT_PROTOCOL_RISK`
+    Default,
+}
+
+/// Configured dataset replacing bundled entries, reloaded periodically
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RiskDataOrigin {
+    File(PathBuf),
+    Remote(String),
+}
+
+impl RiskDataOrigin {
+    fn source(&self) -> RiskDataSource {
+        match self {
+            RiskDataOrigin::File(_) => RiskDataSource::File,
+            RiskDataOrigin::Remote(_) => RiskDataSource::Remote,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProtocolRisk {
+    pub protocol: String,
+    /// In [0, 1]
+    pub score: f64,
+    pub factors: Option<ProtocolRiskFactors>,
+    pub source: RiskDataSource,
+    /// When the dataset the score came from was last reviewed
+    pub dataset_updated_at: Option<String>,
+}
+
+/// Protocol risk scores from the bundled dataset, overridden by a JSON file
+/// (`PROTOCOL_RISK_PATH`) or endpoint (`PROTOCOL_RISK_URL`) when configured
+pub struct ProtocolRiskService {
+    bundled: Dataset,
+    origin: Option<RiskDataOrigin>,
+    refresh_interval: Duration,
+    configured: RwLock<Option<Dataset>>,
+    client: reqwest::Client,
+}
+
+impl ProtocolRiskService {
+    pub fn new(origin: Option<RiskDataOrigin>, refresh_interval: Duration) -> Self {
+        let bundled = Dataset::parse(BUNDLED_DATASET).unwrap_or_else(|e| {
+            tracing::error!("Bundled protocol risk dataset unusable: {}", e);
+            Dataset::default()
+        });
+        Self {
+            bundled,
+            origin,
+            refresh_interval,
+            configured: RwLock::new(None),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// `PROTOCOL_RISK_URL` takes precedence over `PROTOCOL_RISK_PATH`; reloaded every
+    /// `PROTOCOL_RISK_REFRESH_SECS` (default one hour)
+    pub fn from_env() -> Self {
+        let read = |key: &str| std::env::var(key).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let origin = read("PROTOCOL_RISK_URL")
+            .map(RiskDataOrigin::Remote)
+            .or_else(|| read("PROTOCOL_RISK_PATH").map(|path| RiskDataOrigin::File(PathBuf::from(path))));
+        let refresh_interval = read("PROTOCOL_RISK_REFRESH_SECS")
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_REFRESH_INTERVAL);
+        Self::new(origin, refresh_interval)
+    }
+
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<ProtocolRiskService>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::from_env())).clone()
+    }
+
+    /// Reload the configured dataset, returning how many protocols it scores. On failure
+    /// the previously loaded scores stay in use.
+    pub async fn refresh(&self) -> Result<usize, AdapterError> {
+        let Some(origin) = &self.origin else {
+            return Ok(0);
+        };
+        let body = match origin {
+            RiskDataOrigin::File(path) => tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| AdapterError::InvalidData(format!("Failed to read {}: {}", path.display(), e)))?,
+            RiskDataOrigin::Remote(url) => {
+                let response = self.client
+                    .get(url)
+                    .header("Accept", "application/json")
+                    .send()
+                    .await
+                    .map_err(|e| AdapterError::NetworkError(format!("HTTP request failed: {}", e)))?;
+                if !response.status().is_success() {
+                    return Err(AdapterError::NetworkError(format!("API returned status: {}", response.status())));
+                }
+                response
+                    .text()
+                    .await
+                    .map_err(|e| AdapterError::NetworkError(format!("Failed to read protocol risk dataset: {}", e)))?
+            }
+        };
+        let dataset = Dataset::parse(&body)?;
+        let count = dataset.protocols.len();
+        *self.configured.write().unwrap() = Some(dataset);
+        Ok(count)
+    }
+
+    /// Load the configured dataset now and then every refresh interval, in the background
+    pub fn spawn_refresh(self: Arc<Self>) {
+        let Some(origin) = self.origin.clone() else {
+            return;
+        };
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.refresh_interval);
+            loop {
+                ticker.tick().await;
+                match self.refresh().await {
+                    Ok(count) => tracing::info!("Loaded protocol risk scores for {} protocols from {:?}", count, origin),
+                    Err(e) => tracing::warn!("Protocol risk refresh from {:?} failed: {}", origin, e),
+                }
+            }
+        });
+    }
+
+    /// Risk of the protocol behind `protocol`, an adapter name or protocol key
+    pub fn get_protocol_risk(&self, protocol: &str) -> ProtocolRisk {
+        let key = protocol_key(protocol);
+        let configured = self.configured.read().unwrap();
+        let found = configured
+            .as_ref()
+            .and_then(|dataset| Some((dataset, dataset.protocols.get(&key)?, self.origin.as_ref()?.source())))
+            .or_else(|| Some((&self.bundled, self.bundled.protocols.get(&key)?, RiskDataSource::Bundled)));
+
+        match found {
+            Some((dataset, factors, source)) => ProtocolRisk {
+                protocol: key,
+                score: factors.risk_score(),
+                factors: Some(factors.clone()),
+                source,
+                dataset_updated_at: dataset.updated_at.clone(),
+            },
+            None => ProtocolRisk {
+                protocol: key,
+                score: DEFAULT_PROTOCOL_RISK,
+                factors: None,
+                source: RiskDataSource::Default,
+                dataset_updated_at: None,
+            },
+        }
+    }
+
+    /// Value-weighted protocol risk of a portfolio, with each protocol's score, largest
+    /// holding first
+    pub fn portfolio_risk(&self, positions: &[Position]) -> (f64, Vec<ProtocolRisk>) {
+        let mut holdings: Vec<(String, f64)> = Vec::new();
+        for position in positions {
+            let protocol = protocol_key(&position.protocol);
+            match holdings.iter_mut().find(|(p, _)| *p == protocol) {
+                Some((_, value)) => *value += position.value_usd.abs(),
+                None => holdings.push((protocol, position.value_usd.abs())),
+            }
+        }
+        holdings.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let total: f64 = holdings.iter().map(|(_, value)| value).sum();
+        let risks: Vec<ProtocolRisk> = holdings.iter().map(|(protocol, _)| self.get_protocol_risk(protocol)).collect();
+        let weighted = if total > 0.0 {
+            holdings.iter().zip(&risks).map(|((_, value), risk)| value * risk.score).sum::<f64>() / total
+        } else {
+            0.0
+        };
+        (weighted, risks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::filter::KNOWN_PROTOCOLS;
+
+    #[test]
+    fn test_bundled_dataset_covers_known_protocols() {
+        let service = ProtocolRiskService::new(None, DEFAULT_REFRESH_INTERVAL);
+        for protocol in KNOWN_PROTOCOLS {
+            assert_eq!(service.get_protocol_risk(protocol).source, RiskDataSource::Bundled, "{}", protocol);
+        }
+        assert!(service.get_protocol_risk("Uniswap V3").score < service.get_protocol_risk("erc4626").score);
+
+        let unknown = service.get_protocol_risk("new_fork");
+        assert_eq!((unknown.score, unknown.source), (DEFAULT_PROTOCOL_RISK, RiskDataSource::Default));
+    }
+
+    #[test]
+    fn test_configured_scores_override_bundled_ones() {
+        let service = ProtocolRiskService::new(Some(RiskDataOrigin::Remote("http://localhost".to_string())), DEFAULT_REFRESH_INTERVAL);
+        let dataset = Dataset::parse(r#"{"protocols": {
+            "Lido": {"audit_coverage": 0.0, "exploit_history": 1.0, "governance_decentralization": 0.0},
+            "gmx": {"audit_coverage": 2.0, "exploit_history": 0.0, "governance_decentralization": 0.0}
+        }}"#).unwrap();
+        *service.configured.write().unwrap() = Some(dataset);
+
+        let lido = service.get_protocol_risk("lido");
+        assert_eq!(lido.source, RiskDataSource::Remote);
+        assert!((lido.score - 1.0).abs() < 1e-12);
+        // The out-of-range entry was dropped, so the bundled score stands
+        assert_eq!(service.get_protocol_risk("gmx").source, RiskDataSource::Bundled);
+    }
+}
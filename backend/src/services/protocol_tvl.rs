@@ -0,0 +1,259 @@
+use futures::future;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::adapters::filter::protocol_key;
+use crate::adapters::traits::{AdapterError, Position};
+
+const DEFILLAMA_API_URL: &str = "https://api.llama.fi";
+
+/// DefiLlama updates protocol TVL roughly hourly
+const TVL_TTL: Duration = Duration::from_secs(1800);
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// DefiLlama slug for a protocol key, for protocols it tracks under a different name
+fn defillama_slug(protocol: &str) -> Option<&'static str> {
+    let slug = match protocol {
+        "aave_v3" => "aave-v3",
+        "uniswap_v3" => "uniswap-v3",
+        "uniswap_v2" => "uniswap-v2",
+        "lido" => "lido",
+        "rocket_pool" => "rocket-pool",
+        "ether_fi" => "ether.fi-stake",
+        "eigenlayer" => "eigenlayer",
+        "balancer_v2" => "balancer-v2",
+        "beefy" => "beefy",
+        "ethena" => "ethena-usde",
+        "frax" => "frax",
+        "spark" => "sparklend",
+        "makerdao" => "makerdao",
+        "convex" => "convex-finance",
+        "compound_v2" => "compound-v2",
+        "yearn_finance" => "yearn-finance",
+        "morpho_blue" => "morpho-blue",
+        "gmx" => "gmx",
+        "velodrome" => "velodrome-v2",
+        "aerodrome" => "aerodrome-v1",
+        _ => return None,
+    };
+    Some(slug)
+}
+
+#[derive(Debug, Deserialize)]
+struct TvlPoint {
+    date: i64,
+    #[serde(rename = "totalLiquidityUSD")]
+    total_liquidity_usd: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProtocolResponse {
+    tvl: Vec<TvlPoint>,
+}
+
+/// Share of a protocol's TVL above which a position is flagged as outsized
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarketShareLimit(pub f64);
+
+impl Default for MarketShareLimit {
+    fn default() -> Self {
+        Self(0.01)
+    }
+}
+
+impl MarketShareLimit {
+    /// Default of 1% overridden by `MARKET_SHARE_WARNING`, as a fraction of protocol TVL
+    pub fn from_env() -> Self {
+        std::env::var("MARKET_SHARE_WARNING")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Self)
+            .unwrap_or_default()
+    }
+}
+
+/// How the protocol holding a user's positions looks from the outside: its size, its
+/// recent trend and how much of it the user makes up
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarketContext {
+    pub protocol: String,
+    pub protocol_tvl_usd: f64,
+    /// Change in TVL over the last 30 days, in percent; `None` with too little history
+    pub tvl_30d_change_pct: Option<f64>,
+    /// Gross value the user holds in the protocol
+    pub your_position_usd: f64,
+    /// `your_position_usd` as a fraction of `protocol_tvl_usd`
+    pub your_share_of_tvl: f64,
+    pub warning: Option<String>,
+}
+
+impl MarketContext {
+    /// Context from a protocol's daily TVL history (unix seconds, USD), oldest first
+    pub fn from_tvl_history(
+        protocol: &str,
+        position_usd: f64,
+        history: &[(i64, f64)],
+        limit: MarketShareLimit,
+    ) -> Option<Self> {
+        let &(latest_date, protocol_tvl_usd) = history.last()?;
+        if protocol_tvl_usd <= 0.0 {
+            return None;
+        }
+
+        let month_ago = latest_date - 30 * SECONDS_PER_DAY;
+        let tvl_30d_change_pct = history
+            .iter()
+            .rev()
+            .find(|(date, _)| *date <= month_ago)
+            .filter(|(_, tvl)| *tvl > 0.0)
+            .map(|(_, tvl)| (protocol_tvl_usd - tvl) / tvl * 100.0);
+
+        let your_share_of_tvl = position_usd / protocol_tvl_usd;
+        let warning = (your_share_of_tvl > limit.0).then(|| {
+            format!(
+                "Your ${:.0} on {} is {:.2}% of its ${:.0} TVL; exiting could move the market against you",
+                position_usd,
+                protocol,
+                your_share_of_tvl * 100.0,
+                protocol_tvl_usd,
+            )
+        });
+
+        Some(Self {
+            protocol: protocol.to_string(),
+            protocol_tvl_usd,
+            tvl_30d_change_pct,
+            your_position_usd: position_usd,
+            your_share_of_tvl,
+            warning,
+        })
+    }
+}
+
+/// Protocol TVL history from DefiLlama, cached per protocol
+pub struct ProtocolTvlService {
+    client: reqwest::Client,
+    base_url: String,
+    cache: RwLock<HashMap<&'static str, (Arc<Vec<(i64, f64)>>, Instant)>>,
+}
+
+impl ProtocolTvlService {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            base_url: base_url.into(),
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Process-wide service; `DEFILLAMA_API_URL` overrides the public API
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<ProtocolTvlService>> = OnceLock::new();
+        GLOBAL
+            .get_or_init(|| {
+                let base_url = std::env::var("DEFILLAMA_API_URL").unwrap_or_else(|_| DEFILLAMA_API_URL.to_string());
+                Arc::new(Self::new(base_url))
+            })
+            .clone()
+    }
+
+    /// Daily TVL for a protocol key such as "lido", oldest first
+    pub async fn tvl_history(&self, protocol: &str) -> Result<Arc<Vec<(i64, f64)>>, AdapterError> {
+        let slug = defillama_slug(protocol)
+            .ok_or_else(|| AdapterError::InvalidData(format!("No DefiLlama listing for {}", protocol)))?;
+
+        let cached = self.cache.read().unwrap().get(slug).cloned();
+        if let Some((history, fetched_at)) = cached {
+            if fetched_at.elapsed() < TVL_TTL {
+                return Ok(history);
+            }
+        }
+
+        let url = format!("{}/protocol/{}", self.base_url, slug);
+        let response = self.client
+            .get(&url)
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| AdapterError::NetworkError(format!("HTTP request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(AdapterError::NetworkError(format!("API returned status: {}", response.status())));
+        }
+
+        let body: ProtocolResponse = response
+            .json()
+            .await
+            .map_err(|e| AdapterError::InvalidData(format!("Failed to parse TVL history: {}", e)))?;
+
+        let history: Arc<Vec<(i64, f64)>> = Arc::new(body.tvl.into_iter().map(|point| (point.date, point.total_liquidity_usd)).collect());
+        self.cache.write().unwrap().insert(slug, (Arc::clone(&history), Instant::now()));
+        Ok(history)
+    }
+
+    /// Market context for every protocol the positions are in, largest holding first.
+    /// Protocols DefiLlama doesn't list or that fail to load are skipped.
+    pub async fn market_context(&self, positions: &[Position], limit: MarketShareLimit) -> Vec<MarketContext> {
+        let mut holdings: Vec<(String, f64)> = Vec::new();
+        for position in positions {
+            let protocol = protocol_key(&position.protocol);
+            match holdings.iter_mut().find(|(p, _)| *p == protocol) {
+                Some((_, value)) => *value += position.value_usd.abs(),
+                None => holdings.push((protocol, position.value_usd.abs())),
+            }
+        }
+        holdings.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let contexts = holdings.iter().map(|(protocol, value)| async move {
+            match self.tvl_history(protocol).await {
+                Ok(history) => MarketContext::from_tvl_history(protocol, *value, &history, limit),
+                Err(e) => {
+                    tracing::debug!("No TVL for {}: {}", protocol, e);
+                    None
+                }
+            }
+        });
+
+        future::join_all(contexts).await.into_iter().flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(start_tvl: f64, end_tvl: f64) -> Vec<(i64, f64)> {
+        (0..=40)
+            .map(|day| {
+                let tvl = if day <= 10 { start_tvl } else { end_tvl };
+                (day * SECONDS_PER_DAY, tvl)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_whale_in_a_small_pool_is_warned() {
+        let context = MarketContext::from_tvl_history("frax", 500_000.0, &history(20_000_000.0, 10_000_000.0), MarketShareLimit::default())
+            .unwrap();
+
+        assert_eq!(context.protocol_tvl_usd, 10_000_000.0);
+        assert_eq!(context.tvl_30d_change_pct, Some(-50.0));
+        assert!((context.your_share_of_tvl - 0.05).abs() < 1e-12);
+        assert!(context.warning.unwrap().contains("5.00%"));
+    }
+
+    #[test]
+    fn test_small_position_or_short_history_has_no_warning() {
+        let context = MarketContext::from_tvl_history("lido", 10_000.0, &[(0, 1e10)], MarketShareLimit::default()).unwrap();
+        assert_eq!(context.tvl_30d_change_pct, None);
+        assert!(context.warning.is_none());
+
+        assert!(MarketContext::from_tvl_history("lido", 10_000.0, &[], MarketShareLimit::default()).is_none());
+    }
+}
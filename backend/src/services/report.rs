@@ -0,0 +1,213 @@
+use alloy::primitives::Address;
+use alloy::signers::{local::PrivateKeySigner, SignerSync};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::adapters::traits::{AdapterError, Position};
+
+/// Bumped whenever the hashed fields change, so old hashes stay reproducible
+pub const REPORT_VERSION: u32 = 2;
+
+/// Metadata describing when a position was read rather than what it held, left out so
+/// the same block always hashes the same
+const READ_TIME_METADATA: &[&str] = &["data_age_blocks", "stale", "confirmations"];
+
+/// A portfolio at one mainnet block, with a SHA-256 hash of its canonical JSON
+#[derive(Debug, Clone, Serialize)]
+pub struct PortfolioReport {
+    /// What `sha256` covers
+    pub report: serde_json::Value,
+    /// Hex SHA-256 of `report` serialized with sorted keys and no whitespace
+    pub sha256: String,
+    /// Server signature over the hash, when `REPORT_SIGNING_KEY` is configured
+    pub signature: Option<ReportSignature>,
+}
+
+/// EIP-191 `personal_sign` signature over the 32 hash bytes, checkable with `ecrecover`
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportSignature {
+    pub signer: String,
+    pub signature: String,
+    pub scheme: &'static str,
+}
+
+/// Server key that signs report hashes
+pub struct ReportSigner {
+    key: PrivateKeySigner,
+}
+
+impl ReportSigner {
+    pub fn new(private_key: &str) -> Result<Self, AdapterError> {
+        let key = PrivateKeySigner::from_str(private_key.trim())
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid report signing key: {}", e)))?;
+        Ok(Self { key })
+    }
+
+    /// Signer from `REPORT_SIGNING_KEY`; reports go unsigned when it's unset or invalid
+    pub fn from_env() -> Option<Self> {
+        let private_key = std::env::var("REPORT_SIGNING_KEY").ok().filter(|key| !key.trim().is_empty())?;
+        match Self::new(&private_key) {
+            Ok(signer) => Some(signer),
+            Err(e) => {
+                tracing::error!("Reports will be unsigned: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn global() -> Option<&'static ReportSigner> {
+        static GLOBAL: OnceLock<Option<ReportSigner>> = OnceLock::new();
+        GLOBAL.get_or_init(Self::from_env).as_ref()
+    }
+
+    pub fn address(&self) -> Address {
+        self.key.address()
+    }
+
+    fn sign(&self, digest: &[u8; 32]) -> Result<ReportSignature, AdapterError> {
+        let signature = self.key
+            .sign_message_sync(digest)
+            .map_err(|e| AdapterError::InvalidData(format!("Failed to sign report: {}", e)))?;
+        Ok(ReportSignature {
+            signer: format!("{:?}", self.address()),
+            signature: format!("0x{}", alloy::hex::encode(signature.as_bytes())),
+            scheme: "eip191",
+        })
+    }
+}
+
+/// JSON with object keys sorted and no insignificant whitespace
+pub fn canonical_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let fields: Vec<String> = entries
+                .into_iter()
+                .map(|(key, value)| format!("{}:{}", serde_json::Value::from(key.as_str()), canonical_json(value)))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        serde_json::Value::Array(items) => {
+            format!("[{}]", items.iter().map(canonical_json).collect::<Vec<_>>().join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Positions as of the block: read-time metadata dropped, `last_updated` set to the
+/// block's timestamp and sorted by ID
+fn report_positions(positions: &[Position], block_timestamp: u64) -> Vec<Position> {
+    let mut positions: Vec<Position> = positions
+        .iter()
+        .cloned()
+        .map(|mut position| {
+            if let Some(metadata) = position.metadata.as_object_mut() {
+                for key in READ_TIME_METADATA {
+                    metadata.remove(*key);
+                }
+            }
+            position.last_updated = block_timestamp;
+            position
+        })
+        .collect();
+    positions.sort_by(|a, b| a.id.cmp(&b.id));
+    positions
+}
+
+/// Report of `address`'s `positions` read at `block`, signed when `signer` is given.
+/// `unavailable_protocols` are those that couldn't be read at the block, and `spot_priced`
+/// the coin ids each protocol valued at the current price for want of one at the block.
+pub fn build_report(
+    address: Address,
+    block_number: u64,
+    block_timestamp: u64,
+    positions: &[Position],
+    mut unavailable_protocols: Vec<String>,
+    spot_priced: &BTreeMap<String, Vec<String>>,
+    signer: Option<&ReportSigner>,
+) -> Result<PortfolioReport, AdapterError> {
+    let positions = report_positions(positions, block_timestamp);
+    unavailable_protocols.sort();
+    unavailable_protocols.dedup();
+
+    let report = serde_json::json!({
+        "version": REPORT_VERSION,
+        "address": format!("{:?}", address),
+        "chain_id": 1,
+        "block_number": block_number,
+        "block_timestamp": block_timestamp,
+        "total_value_usd": positions.iter().map(|p| p.value_usd).sum::<f64>(),
+        "positions": positions,
+        "unavailable_protocols": unavailable_protocols,
+        "spot_priced": spot_priced,
+    });
+    let digest: [u8; 32] = Sha256::digest(canonical_json(&report).as_bytes()).into();
+    let signature = signer.map(|signer| signer.sign(&digest)).transpose()?;
+
+    Ok(PortfolioReport {
+        report,
+        sha256: alloy::hex::encode(digest),
+        signature,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(id: &str, value_usd: f64, last_updated: u64) -> Position {
+        Position {
+            id: id.to_string(),
+            protocol: "lido".to_string(),
+            position_type: "staking".to_string(),
+            pair: "stETH".to_string(),
+            value_usd,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({ "block_number": 100, "data_age_blocks": last_updated % 7, "stale": false }),
+            last_updated,
+        }
+    }
+
+    #[test]
+    fn test_canonical_json_sorts_keys() {
+        let value = serde_json::json!({ "b": [1, { "d": null, "c": "x" }], "a": 1.5 });
+        assert_eq!(canonical_json(&value), r#"{"a":1.5,"b":[1,{"c":"x","d":null}]}"#);
+    }
+
+    #[test]
+    fn test_report_hash_is_stable_for_a_block() {
+        let owner = Address::repeat_byte(0xaa);
+        let first = build_report(owner, 100, 1_700_000_000, &[position("b", 1.0, 5), position("a", 2.0, 5)], vec![], &BTreeMap::new(), None).unwrap();
+        let reread = build_report(owner, 100, 1_700_000_000, &[position("a", 2.0, 9), position("b", 1.0, 9)], vec![], &BTreeMap::new(), None).unwrap();
+        assert_eq!(first.sha256, reread.sha256);
+        assert_eq!(first.report["positions"][0]["id"], "a");
+        assert!(first.report["positions"][0]["metadata"].get("stale").is_none());
+
+        let later = build_report(owner, 101, 1_700_000_012, &[position("a", 2.0, 5)], vec![], &BTreeMap::new(), None).unwrap();
+        assert_ne!(first.sha256, later.sha256);
+
+        // Values that fell back to today's price are disclosed, and change the hash
+        let spot_priced = BTreeMap::from([("lido".to_string(), vec!["staked-ether".to_string()])]);
+        let fallback = build_report(owner, 100, 1_700_000_000, &[position("a", 2.0, 5), position("b", 1.0, 5)], vec![], &spot_priced, None).unwrap();
+        assert_eq!(fallback.report["spot_priced"]["lido"][0], "staked-ether");
+        assert_ne!(first.sha256, fallback.sha256);
+    }
+
+    #[test]
+    fn test_signature_recovers_to_the_server_key() {
+        let key = PrivateKeySigner::random();
+        let signer = ReportSigner { key: key.clone() };
+        let report = build_report(Address::ZERO, 1, 1, &[], vec!["gmx".to_string()], &BTreeMap::new(), Some(&signer)).unwrap();
+
+        let signature = report.signature.unwrap();
+        assert_eq!(signature.signer, format!("{:?}", key.address()));
+        let digest: [u8; 32] = alloy::hex::decode(&report.sha256).unwrap().try_into().unwrap();
+        let parsed = alloy::primitives::Signature::from_str(&signature.signature).unwrap();
+        assert_eq!(parsed.recover_address_from_msg(digest).unwrap(), key.address());
+    }
+}
@@ -0,0 +1,225 @@
+use alloy::primitives::Address;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+use crate::risk::RiskProfile;
+use crate::services::alert_feed::AlertFeedConfig;
+use crate::services::HealthFactorThresholds;
+
+/// Alert thresholds a wallet has set for itself. Unset ones follow its risk profile
+/// (health factor) or the service-wide `ALERT_*` configuration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlertThresholds {
+    pub health_factor_warning: Option<f64>,
+    pub health_factor_critical: Option<f64>,
+    /// Drop in a position's or the portfolio's value within a day, in percent
+    pub value_drop_percent: Option<f64>,
+    /// Portfolio risk score in [0, 1]
+    pub risk_score: Option<f64>,
+}
+
+impl AlertThresholds {
+    /// Reject thresholds that would never or always fire
+    pub fn validate(&self) -> Result<(), String> {
+        let in_range = |value: Option<f64>, min: f64, max: f64| value.map_or(true, |v| v.is_finite() && v > min && v <= max);
+
+        if !in_range(self.health_factor_warning, 1.0, 10.0) || !in_range(self.health_factor_critical, 1.0, 10.0) {
+            return Err("health factor thresholds must be above 1.0 and at most 10".to_string());
+        }
+        if let (Some(warning), Some(critical)) = (self.health_factor_warning, self.health_factor_critical) {
+            if critical >= warning {
+                return Err("health_factor_critical must be below health_factor_warning".to_string());
+            }
+        }
+        if !in_range(self.value_drop_percent, 0.0, 100.0) {
+            return Err("value_drop_percent must be above 0 and at most 100".to_string());
+        }
+        if !in_range(self.risk_score, 0.0, 1.0) {
+            return Err("risk_score must be above 0 and at most 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Everything a wallet has configured about how it is monitored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserRiskSettings {
+    pub risk_profile: RiskProfile,
+    #[serde(default)]
+    pub alert_thresholds: AlertThresholds,
+}
+
+impl UserRiskSettings {
+    /// Health factor bands from the profile, with the wallet's own overrides applied
+    pub fn health_thresholds(&self) -> HealthFactorThresholds {
+        let profile = self.risk_profile.health_thresholds();
+        HealthFactorThresholds {
+            warning: self.alert_thresholds.health_factor_warning.unwrap_or(profile.warning),
+            critical: self.alert_thresholds.health_factor_critical.unwrap_or(profile.critical),
+        }
+    }
+
+    /// Portfolio-level thresholds, falling back to `defaults` for unset ones
+    pub fn alert_config(&self, defaults: AlertFeedConfig) -> AlertFeedConfig {
+        AlertFeedConfig {
+            value_drop_percent: self.alert_thresholds.value_drop_percent.unwrap_or(defaults.value_drop_percent),
+            risk_score_threshold: self.alert_thresholds.risk_score.unwrap_or(defaults.risk_score_threshold),
+        }
+    }
+}
+
+/// Settings as written to disk, including files from before alert thresholds were
+/// stored, which held just the profile per wallet
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredSettings {
+    Current(UserRiskSettings),
+    ProfileOnly(RiskProfile),
+}
+
+impl From<StoredSettings> for UserRiskSettings {
+    fn from(stored: StoredSettings) -> Self {
+        match stored {
+            StoredSettings::Current(settings) => settings,
+            StoredSettings::ProfileOnly(risk_profile) => Self { risk_profile, ..Default::default() },
+        }
+    }
+}
+
+/// Risk profile and alert thresholds chosen by each wallet.
+///
+/// When `RISK_PROFILES_PATH` is set the whole map is rewritten there as JSON on every
+/// change and reloaded on startup.
+#[derive(Debug)]
+pub struct RiskProfileStore {
+    settings: RwLock<HashMap<Address, UserRiskSettings>>,
+    path: Option<PathBuf>,
+    file_lock: Mutex<()>,
+}
+
+impl RiskProfileStore {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            settings: RwLock::new(HashMap::new()),
+            path,
+            file_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let store = Self::new(std::env::var("RISK_PROFILES_PATH").ok().map(PathBuf::from));
+        if let Err(e) = store.load() {
+            tracing::warn!("Failed to load risk profiles: {}", e);
+        }
+        store
+    }
+
+    /// Process-wide store shared by all handlers
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<RiskProfileStore>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::from_env())).clone()
+    }
+
+    pub fn get(&self, address: Address) -> Option<RiskProfile> {
+        self.settings(address).map(|settings| settings.risk_profile)
+    }
+
+    pub fn settings(&self, address: Address) -> Option<UserRiskSettings> {
+        self.settings.read().unwrap().get(&address).copied()
+    }
+
+    /// Change the wallet's profile, keeping any alert thresholds it set
+    pub fn set(&self, address: Address, profile: RiskProfile) {
+        self.update(address, |settings| settings.risk_profile = profile);
+    }
+
+    /// Replace the wallet's alert thresholds, keeping its profile
+    pub fn set_alert_thresholds(&self, address: Address, thresholds: AlertThresholds) {
+        self.update(address, |settings| settings.alert_thresholds = thresholds);
+    }
+
+    fn update(&self, address: Address, change: impl FnOnce(&mut UserRiskSettings)) {
+        let snapshot = {
+            let mut settings = self.settings.write().unwrap();
+            change(settings.entry(address).or_default());
+            settings.clone()
+        };
+
+        if let Err(e) = self.save(&snapshot) {
+            tracing::warn!("Failed to persist risk profiles: {}", e);
+        }
+    }
+
+    fn load(&self) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let stored: HashMap<Address, StoredSettings> = serde_json::from_str(&contents)?;
+        *self.settings.write().unwrap() = stored.into_iter().map(|(address, s)| (address, s.into())).collect();
+        Ok(())
+    }
+
+    fn save(&self, settings: &HashMap<Address, UserRiskSettings>) -> std::io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let _guard = self.file_lock.lock().unwrap();
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, serde_json::to_vec_pretty(settings)?)?;
+        std::fs::rename(tmp_path, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_survive_reload() {
+        let path = std::env::temp_dir().join(format!("risk_profiles_{}.json", uuid::Uuid::new_v4()));
+        let wallet = Address::ZERO;
+
+        RiskProfileStore::new(Some(path.clone())).set(wallet, RiskProfile::Conservative);
+        let reloaded = RiskProfileStore::new(Some(path.clone()));
+        reloaded.load().unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(reloaded.get(wallet), Some(RiskProfile::Conservative));
+        assert_eq!(RiskProfileStore::new(None).get(wallet), None);
+    }
+
+    #[test]
+    fn test_profile_only_files_are_migrated() {
+        let path = std::env::temp_dir().join(format!("risk_profiles_{}.json", uuid::Uuid::new_v4()));
+        std::fs::write(&path, r#"{"0x0000000000000000000000000000000000000000": "aggressive"}"#).unwrap();
+
+        let store = RiskProfileStore::new(Some(path.clone()));
+        store.load().unwrap();
+        store.set_alert_thresholds(Address::ZERO, AlertThresholds { value_drop_percent: Some(15.0), ..Default::default() });
+        let reloaded = RiskProfileStore::new(Some(path.clone()));
+        reloaded.load().unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let settings = reloaded.settings(Address::ZERO).unwrap();
+        assert_eq!(settings.risk_profile, RiskProfile::Aggressive);
+        assert_eq!(settings.alert_config(AlertFeedConfig::default()).value_drop_percent, 15.0);
+        assert_eq!(settings.health_thresholds().warning, 1.2);
+    }
+
+    #[test]
+    fn test_thresholds_are_validated() {
+        let inverted = AlertThresholds { health_factor_warning: Some(1.2), health_factor_critical: Some(1.5), ..Default::default() };
+        assert!(inverted.validate().is_err());
+        assert!(AlertThresholds { value_drop_percent: Some(0.0), ..Default::default() }.validate().is_err());
+        assert!(AlertThresholds { value_drop_percent: Some(15.0), risk_score: Some(0.8), ..Default::default() }.validate().is_ok());
+    }
+}
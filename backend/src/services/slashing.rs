@@ -0,0 +1,245 @@
+use alloy::{
+    primitives::{address, keccak256, Address, U256},
+    sol,
+};
+
+use crate::adapters::traits::{AdapterError, Position};
+use crate::blockchain::EthereumClient;
+use crate::risk::position_risk_score;
+use crate::utils::{normalize_amount, ETH_DECIMALS};
+
+/// stETH, which emits `TokenRebased` on every accounting oracle report
+const LIDO_STETH: Address = address!("ae7ab96520DE3A18E5e111B5EaAb095312D7fE84");
+/// Rocket Pool's registry of current contract addresses
+const ROCKET_STORAGE: Address = address!("1d8f8f00cfa6758d7bE78336684788Fb0ee0Fa46");
+
+sol! {
+    interface ILidoRebase {
+        event TokenRebased(
+            uint256 indexed reportTimestamp,
+            uint256 timeElapsed,
+            uint256 preTotalShares,
+            uint256 preTotalEther,
+            uint256 postTotalShares,
+            uint256 postTotalEther,
+            uint256 sharesMintedAsFees
+        );
+    }
+
+    interface IRocketStorage {
+        function getAddress(bytes32 key) external view returns (address);
+    }
+
+    interface IRocketNetworkBalances {
+        event BalancesUpdated(
+            uint256 indexed block,
+            uint256 slotTimestamp,
+            uint256 totalEth,
+            uint256 stakingEth,
+            uint256 rethSupply,
+            uint256 blockTimestamp
+        );
+    }
+}
+
+/// A protocol oracle report: ETH per share of the staking token before and after it.
+/// A drop means validator penalties or slashings outweighed rewards over the period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OracleReport {
+    pub block: u64,
+    pub rate_before: f64,
+    pub rate_after: f64,
+}
+
+impl OracleReport {
+    /// Share-rate loss in basis points, 0 when the rate didn't fall
+    pub fn penalty_bps(&self) -> f64 {
+        if self.rate_before <= 0.0 {
+            return 0.0;
+        }
+        ((self.rate_before - self.rate_after) / self.rate_before * 10_000.0).max(0.0)
+    }
+}
+
+/// When oracle-reported penalties count as recent slashing, and what that does to risk
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlashingPolicy {
+    /// Share-rate loss in a single report, in basis points, that flags a position
+    pub penalty_threshold_bps: f64,
+    /// How far back reports are read
+    pub lookback_blocks: u64,
+    /// Added to a flagged position's risk score, which is capped at 1
+    pub risk_increase: f64,
+}
+
+impl Default for SlashingPolicy {
+    fn default() -> Self {
+        Self {
+            penalty_threshold_bps: 0.5,
+            // About a week of mainnet blocks, covering several daily reports
+            lookback_blocks: 50_400,
+            risk_increase: 0.25,
+        }
+    }
+}
+
+impl SlashingPolicy {
+    /// Defaults overridden by `SLASHING_PENALTY_THRESHOLD_BPS` / `SLASHING_LOOKBACK_BLOCKS`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            penalty_threshold_bps: std::env::var("SLASHING_PENALTY_THRESHOLD_BPS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.penalty_threshold_bps),
+            lookback_blocks: std::env::var("SLASHING_LOOKBACK_BLOCKS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.lookback_blocks),
+            ..defaults
+        }
+    }
+}
+
+/// Penalties found in a protocol's recent oracle reports
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlashingCheck {
+    pub reports_checked: usize,
+    pub penalized_reports: usize,
+    pub worst_penalty_bps: f64,
+    pub recent_slashing_detected: bool,
+}
+
+impl SlashingCheck {
+    pub fn from_reports(reports: &[OracleReport], policy: &SlashingPolicy) -> Self {
+        let penalties: Vec<f64> = reports.iter().map(OracleReport::penalty_bps).collect();
+        let worst_penalty_bps = penalties.iter().copied().fold(0.0, f64::max);
+
+        Self {
+            reports_checked: reports.len(),
+            penalized_reports: penalties.iter().filter(|bps| **bps > 0.0).count(),
+            worst_penalty_bps,
+            recent_slashing_detected: worst_penalty_bps > policy.penalty_threshold_bps,
+        }
+    }
+
+    /// Record the check in the position's metadata, raising its risk score when
+    /// slashing was detected
+    pub fn apply(&self, position: &mut Position, policy: &SlashingPolicy) {
+        let risk_score = if self.recent_slashing_detected {
+            Some((position_risk_score(position) + policy.risk_increase).min(1.0))
+        } else {
+            None
+        };
+
+        if let Some(metadata) = position.metadata.as_object_mut() {
+            metadata.insert("recent_slashing_detected".to_string(), serde_json::json!(self.recent_slashing_detected));
+            metadata.insert("recent_penalty_bps".to_string(), serde_json::json!(self.worst_penalty_bps));
+            metadata.insert("penalized_oracle_reports".to_string(), serde_json::json!(self.penalized_reports));
+            metadata.insert("oracle_reports_checked".to_string(), serde_json::json!(self.reports_checked));
+            if let Some(risk_score) = risk_score {
+                metadata.insert("risk_score".to_string(), serde_json::json!(risk_score));
+            }
+        }
+    }
+}
+
+fn share_rate(ether: U256, shares: U256) -> f64 {
+    let shares = normalize_amount(shares, ETH_DECIMALS);
+    if shares > 0.0 {
+        normalize_amount(ether, ETH_DECIMALS) / shares
+    } else {
+        0.0
+    }
+}
+
+/// Lido accounting oracle reports over the last `lookback_blocks`, from `TokenRebased`
+pub async fn lido_oracle_reports(client: &EthereumClient, lookback_blocks: u64) -> Result<Vec<OracleReport>, AdapterError> {
+    let head = client.block_number().await?;
+    let logs = client
+        .get_logs::<ILidoRebase::TokenRebased>(LIDO_STETH, head.saturating_sub(lookback_blocks), head)
+        .await?;
+
+    Ok(logs
+        .into_iter()
+        .map(|(block, rebase)| OracleReport {
+            block,
+            rate_before: share_rate(rebase.preTotalEther, rebase.preTotalShares),
+            rate_after: share_rate(rebase.postTotalEther, rebase.postTotalShares),
+        })
+        .collect())
+}
+
+/// Rocket Pool network balance reports over the last `lookback_blocks`, comparing the
+/// rETH rate of each `BalancesUpdated` with the one before it
+pub async fn rocket_pool_oracle_reports(client: &EthereumClient, lookback_blocks: u64) -> Result<Vec<OracleReport>, AdapterError> {
+    let key = keccak256(b"contract.addressrocketNetworkBalances");
+    let balances = client.call(ROCKET_STORAGE, &IRocketStorage::getAddressCall { key }).await?._0;
+    if balances == Address::ZERO {
+        return Err(AdapterError::ContractError("rocketNetworkBalances is not registered".to_string()));
+    }
+
+    let head = client.block_number().await?;
+    let logs = client
+        .get_logs::<IRocketNetworkBalances::BalancesUpdated>(balances, head.saturating_sub(lookback_blocks), head)
+        .await?;
+    let rates: Vec<(u64, f64)> = logs
+        .into_iter()
+        .map(|(block, update)| (block, share_rate(update.totalEth, update.rethSupply)))
+        .collect();
+
+    Ok(rates
+        .windows(2)
+        .map(|pair| OracleReport { block: pair[1].0, rate_before: pair[0].1, rate_after: pair[1].1 })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn staking_position() -> Position {
+        Position {
+            id: "steth".to_string(),
+            protocol: "lido".to_string(),
+            position_type: "staking".to_string(),
+            pair: "stETH/ETH".to_string(),
+            value_usd: 10_000.0,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({ "risk_score": 0.3 }),
+            last_updated: 0,
+        }
+    }
+
+    #[test]
+    fn test_negative_rebase_flags_position_and_raises_risk() {
+        let policy = SlashingPolicy::default();
+        let reports = [
+            OracleReport { block: 1, rate_before: 1.1500, rate_after: 1.1501 },
+            OracleReport { block: 2, rate_before: 1.1501, rate_after: 1.1499 },
+        ];
+
+        let check = SlashingCheck::from_reports(&reports, &policy);
+        assert_eq!(check.penalized_reports, 1);
+        assert!(check.recent_slashing_detected);
+
+        let mut position = staking_position();
+        check.apply(&mut position, &policy);
+        assert_eq!(position.metadata["recent_slashing_detected"], true);
+        assert!((position.metadata["risk_score"].as_f64().unwrap() - 0.55).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rewards_only_leave_risk_unchanged() {
+        let policy = SlashingPolicy::default();
+        let reports = [OracleReport { block: 1, rate_before: 1.15, rate_after: 1.1502 }];
+
+        let check = SlashingCheck::from_reports(&reports, &policy);
+        let mut position = staking_position();
+        check.apply(&mut position, &policy);
+
+        assert_eq!(position.metadata["recent_slashing_detected"], false);
+        assert_eq!(position.metadata["risk_score"], 0.3);
+    }
+}
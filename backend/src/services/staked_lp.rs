@@ -0,0 +1,212 @@
+use alloy::{
+    primitives::{Address, U256},
+    sol,
+};
+use futures::stream::{self, StreamExt};
+
+use crate::adapters::traits::AdapterError;
+use crate::blockchain::EthereumClient;
+use crate::services::IERC20;
+
+// Staking contract interfaces; reward getters are state-changing on some gauges but
+// are safe to eth_call
+sol! {
+    interface ILiquidityGauge {
+        function lp_token() external view returns (address);
+        function claimable_tokens(address addr) external returns (uint256);
+        function reward_count() external view returns (uint256);
+        function reward_tokens(uint256 index) external view returns (address);
+        function claimable_reward(address user, address token) external view returns (uint256);
+    }
+
+    interface IVeGauge {
+        function earned(address account) external view returns (uint256);
+    }
+
+    interface IMasterChef {
+        function userInfo(uint256 pid, address user) external view returns (uint256 amount, uint256 rewardDebt);
+        function pendingSushi(uint256 pid, address user) external view returns (uint256);
+        function pendingCake(uint256 pid, address user) external view returns (uint256);
+        function pendingReward(uint256 pid, address user) external view returns (uint256);
+    }
+}
+
+/// Name of a MasterChef fork's pending reward getter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PendingRewardFn {
+    PendingSushi,
+    PendingCake,
+    PendingReward,
+}
+
+/// How a staking contract reports deposits and rewards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StakingKind {
+    /// Curve / Balancer liquidity gauge: `balanceOf` receipts, `claimable_tokens` for the
+    /// emissions token (when given) and `claimable_reward` for extra reward tokens
+    LiquidityGauge { emissions_token: Option<Address> },
+    /// Velodrome / Aerodrome gauge: `balanceOf` and `earned` in a single reward token
+    VeGauge { reward_token: Address },
+    /// MasterChef pool: `userInfo(pid, user).amount` and a fork-specific pending getter
+    MasterChef { pool_id: u64, reward_token: Address, pending: PendingRewardFn },
+}
+
+/// A contract LP tokens can be staked in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StakingContract {
+    pub address: Address,
+    pub lp_token: Address,
+    pub kind: StakingKind,
+}
+
+/// Unclaimed reward in raw token units
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingReward {
+    pub token: Address,
+    pub amount: U256,
+}
+
+/// A user's stake in one staking contract, left to the adapter to value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StakedLp {
+    pub staking_contract: Address,
+    pub lp_token: Address,
+    /// LP tokens deposited, in LP token units
+    pub staked: U256,
+    pub rewards: Vec<PendingReward>,
+}
+
+impl StakedLp {
+    pub fn is_empty(&self) -> bool {
+        self.staked == U256::ZERO && self.rewards.iter().all(|reward| reward.amount == U256::ZERO)
+    }
+}
+
+/// Reads staked LP balances and pending rewards from gauges and MasterChef pools, for
+/// LP that doesn't show up in a wallet `balanceOf`
+pub struct StakedLpScanner {
+    client: EthereumClient,
+}
+
+impl StakedLpScanner {
+    pub fn new(client: EthereumClient) -> Self {
+        Self { client }
+    }
+
+    /// Stakes `user` holds in `contracts`, skipping empty ones; contracts that fail to
+    /// read are logged and skipped
+    pub async fn scan(&self, user: Address, contracts: &[StakingContract]) -> Vec<StakedLp> {
+        stream::iter(contracts)
+            .map(|contract| async move {
+                match self.read_stake(user, contract).await {
+                    Ok(stake) => Some(stake),
+                    Err(e) => {
+                        tracing::debug!("Failed to read stake in {:?}: {}", contract.address, e);
+                        None
+                    }
+                }
+            })
+            .buffer_unordered(16)
+            .filter_map(|stake| async move { stake.filter(|stake| !stake.is_empty()) })
+            .collect()
+            .await
+    }
+
+    /// Stake `user` holds in a single contract
+    pub async fn read_stake(&self, user: Address, contract: &StakingContract) -> Result<StakedLp, AdapterError> {
+        let (staked, rewards) = match &contract.kind {
+            StakingKind::LiquidityGauge { emissions_token } => {
+                self.read_liquidity_gauge(user, contract.address, *emissions_token).await?
+            }
+            StakingKind::VeGauge { reward_token } => {
+                let (staked, earned) = tokio::try_join!(
+                    self.client.call(contract.address, &IERC20::balanceOfCall { account: user }),
+                    self.client.call(contract.address, &IVeGauge::earnedCall { account: user }),
+                )?;
+                (staked._0, vec![PendingReward { token: *reward_token, amount: earned._0 }])
+            }
+            StakingKind::MasterChef { pool_id, reward_token, pending } => {
+                let pid = U256::from(*pool_id);
+                let info = self.client.call(contract.address, &IMasterChef::userInfoCall { pid, user }).await?;
+                let pending = match pending {
+                    PendingRewardFn::PendingSushi => {
+                        self.client.call(contract.address, &IMasterChef::pendingSushiCall { pid, user }).await?._0
+                    }
+                    PendingRewardFn::PendingCake => {
+                        self.client.call(contract.address, &IMasterChef::pendingCakeCall { pid, user }).await?._0
+                    }
+                    PendingRewardFn::PendingReward => {
+                        self.client.call(contract.address, &IMasterChef::pendingRewardCall { pid, user }).await?._0
+                    }
+                };
+                (info.amount, vec![PendingReward { token: *reward_token, amount: pending }])
+            }
+        };
+
+        Ok(StakedLp {
+            staking_contract: contract.address,
+            lp_token: contract.lp_token,
+            staked,
+            rewards: rewards.into_iter().filter(|reward| reward.amount > U256::ZERO).collect(),
+        })
+    }
+
+    async fn read_liquidity_gauge(
+        &self,
+        user: Address,
+        gauge: Address,
+        emissions_token: Option<Address>,
+    ) -> Result<(U256, Vec<PendingReward>), AdapterError> {
+        let staked = self.client.call(gauge, &IERC20::balanceOfCall { account: user }).await?._0;
+        if staked == U256::ZERO {
+            return Ok((staked, Vec::new()));
+        }
+
+        let mut rewards = Vec::new();
+        if let Some(token) = emissions_token {
+            let amount = self.client.call(gauge, &ILiquidityGauge::claimable_tokensCall { addr: user }).await?._0;
+            rewards.push(PendingReward { token, amount });
+        }
+
+        // Gauges without extra rewards may not implement reward_count
+        let reward_count = match self.client.call(gauge, &ILiquidityGauge::reward_countCall {}).await {
+            Ok(count) => count._0.saturating_to::<u64>(),
+            Err(_) => 0,
+        };
+        for index in 0..reward_count {
+            let token = self.client
+                .call(gauge, &ILiquidityGauge::reward_tokensCall { index: U256::from(index) })
+                .await?
+                ._0;
+            if token == Address::ZERO {
+                continue;
+            }
+            let amount = self.client
+                .call(gauge, &ILiquidityGauge::claimable_rewardCall { user, token })
+                .await?
+                ._0;
+            rewards.push(PendingReward { token, amount });
+        }
+
+        Ok((staked, rewards))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stake_with_only_rewards_is_not_empty() {
+        let mut stake = StakedLp {
+            staking_contract: Address::repeat_byte(1),
+            lp_token: Address::repeat_byte(2),
+            staked: U256::ZERO,
+            rewards: vec![PendingReward { token: Address::repeat_byte(3), amount: U256::ZERO }],
+        };
+        assert!(stake.is_empty());
+
+        stake.rewards[0].amount = U256::from(5);
+        assert!(!stake.is_empty());
+    }
+}
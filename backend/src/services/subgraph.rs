@@ -0,0 +1,238 @@
+use alloy::primitives::Address;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::adapters::traits::AdapterError;
+
+/// A single lending protocol event for a user's account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionEvent {
+    pub timestamp: u64,
+    pub event_type: String,
+    pub asset: String,
+    pub asset_address: String,
+    /// Raw token amount (not decimal-adjusted)
+    pub amount: String,
+    pub amount_usd: f64,
+    pub tx_hash: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubgraphAsset {
+    id: String,
+    symbol: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubgraphEvent {
+    hash: String,
+    timestamp: String,
+    amount: String,
+    #[serde(default, rename = "amountUSD")]
+    amount_usd: Option<String>,
+    asset: SubgraphAsset,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccountEvents {
+    #[serde(default)]
+    deposits: Vec<SubgraphEvent>,
+    #[serde(default)]
+    withdraws: Vec<SubgraphEvent>,
+    #[serde(default)]
+    borrows: Vec<SubgraphEvent>,
+    #[serde(default)]
+    repays: Vec<SubgraphEvent>,
+    #[serde(default)]
+    liquidates: Vec<SubgraphEvent>,
+}
+
+/// Subgraph ID configured for `protocol` on `chain_id`, read from e.g.
+/// `COMPOUND_V2_SUBGRAPH_ID_1`
+pub fn subgraph_id_from_env(protocol: &str, chain_id: u64) -> Option<String> {
+    std::env::var(format!("{}_SUBGRAPH_ID_{}", protocol.to_ascii_uppercase(), chain_id))
+        .ok()
+        .filter(|id| !id.trim().is_empty())
+}
+
+/// Minimal GraphQL client for The Graph subgraphs.
+///
+/// `fetch_account_events` targets the Messari standardized lending schema, which
+/// is deployed for Aave, Compound and most other lending protocols, so adapters
+/// only need to supply their subgraph ID.
+#[derive(Debug, Clone)]
+pub struct SubgraphClient {
+    endpoint: String,
+    http_client: reqwest::Client,
+}
+
+impl SubgraphClient {
+    const GATEWAY_URL: &'static str = "https://gateway.thegraph.com/api";
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+    const ACCOUNT_EVENTS_QUERY: &'static str = r#"
+        query AccountEvents($account: String!, $first: Int!) {
+            deposits(where: { account: $account }, first: $first, orderBy: timestamp, orderDirection: desc) {
+                hash timestamp amount amountUSD asset { id symbol }
+            }
+            withdraws(where: { account: $account }, first: $first, orderBy: timestamp, orderDirection: desc) {
+                hash timestamp amount amountUSD asset { id symbol }
+            }
+            borrows(where: { account: $account }, first: $first, orderBy: timestamp, orderDirection: desc) {
+                hash timestamp amount amountUSD asset { id symbol }
+            }
+            repays(where: { account: $account }, first: $first, orderBy: timestamp, orderDirection: desc) {
+                hash timestamp amount amountUSD asset { id symbol }
+            }
+            liquidates(where: { liquidatee: $account }, first: $first, orderBy: timestamp, orderDirection: desc) {
+                hash timestamp amount amountUSD asset { id symbol }
+            }
+        }
+    "#;
+
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            http_client: reqwest::Client::builder()
+                .timeout(Self::REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Client for a subgraph published on The Graph's decentralized network.
+    /// Uses `THE_GRAPH_API_KEY` when no key is given.
+    pub fn from_subgraph_id(subgraph_id: &str, api_key: Option<String>) -> Result<Self, AdapterError> {
+        let api_key = api_key
+            .or_else(|| std::env::var("THE_GRAPH_API_KEY").ok())
+            .ok_or_else(|| AdapterError::InvalidData("THE_GRAPH_API_KEY is not configured".to_string()))?;
+
+        Ok(Self::new(format!("{}/{}/subgraphs/id/{}", Self::GATEWAY_URL, api_key, subgraph_id)))
+    }
+
+    /// Run a GraphQL query and deserialize its `data` field
+    pub async fn query<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<T, AdapterError> {
+        let response = self.http_client
+            .post(&self.endpoint)
+            .json(&serde_json::json!({ "query": query, "variables": variables }))
+            .send()
+            .await
+            .map_err(|e| AdapterError::NetworkError(format!("Subgraph request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(AdapterError::NetworkError(format!("Subgraph returned status {}", response.status())));
+        }
+
+        let body: GraphQlResponse<T> = response
+            .json()
+            .await
+            .map_err(|e| AdapterError::InvalidData(format!("Invalid subgraph response: {}", e)))?;
+
+        if let Some(errors) = body.errors.filter(|errors| !errors.is_empty()) {
+            return Err(AdapterError::InvalidData(format!("Subgraph query failed: {:?}", errors)));
+        }
+
+        body.data
+            .ok_or_else(|| AdapterError::InvalidData("Subgraph response has no data".to_string()))
+    }
+
+    /// Supply, withdraw, borrow, repay and liquidation events for `account`,
+    /// oldest first, keeping only the `limit` most recent
+    pub async fn fetch_account_events(
+        &self,
+        account: Address,
+        limit: usize,
+    ) -> Result<Vec<TransactionEvent>, AdapterError> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let variables = serde_json::json!({
+            "account": format!("{:?}", account).to_lowercase(),
+            "first": limit.min(1000),
+        });
+
+        let events: AccountEvents = self.query(Self::ACCOUNT_EVENTS_QUERY, variables).await?;
+
+        Ok(Self::merge_events(events, limit))
+    }
+
+    fn merge_events(events: AccountEvents, limit: usize) -> Vec<TransactionEvent> {
+        let tagged = [
+            ("supply", events.deposits),
+            ("withdraw", events.withdraws),
+            ("borrow", events.borrows),
+            ("repay", events.repays),
+            ("liquidation", events.liquidates),
+        ];
+
+        let mut merged: Vec<TransactionEvent> = tagged
+            .into_iter()
+            .flat_map(|(event_type, events)| {
+                events.into_iter().map(move |event| TransactionEvent {
+                    timestamp: event.timestamp.parse().unwrap_or(0),
+                    event_type: event_type.to_string(),
+                    asset: event.asset.symbol,
+                    asset_address: event.asset.id,
+                    amount: event.amount,
+                    amount_usd: event.amount_usd
+                        .and_then(|usd| usd.parse().ok())
+                        .unwrap_or(0.0),
+                    tx_hash: event.hash,
+                })
+            })
+            .collect();
+
+        // Keep the most recent `limit` events, then present them chronologically
+        merged.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        merged.truncate(limit);
+        merged.reverse();
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(hash: &str, timestamp: u64) -> SubgraphEvent {
+        SubgraphEvent {
+            hash: hash.to_string(),
+            timestamp: timestamp.to_string(),
+            amount: "1000000".to_string(),
+            amount_usd: Some("1.0".to_string()),
+            asset: SubgraphAsset {
+                id: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".to_string(),
+                symbol: "USDC".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_merge_events_is_chronological_and_limited() {
+        let events = AccountEvents {
+            deposits: vec![event("0x01", 100), event("0x04", 400)],
+            withdraws: vec![event("0x03", 300)],
+            borrows: vec![event("0x02", 200)],
+            repays: vec![],
+            liquidates: vec![event("0x05", 500)],
+        };
+
+        let merged = SubgraphClient::merge_events(events, 3);
+
+        let hashes: Vec<&str> = merged.iter().map(|e| e.tx_hash.as_str()).collect();
+        assert_eq!(hashes, vec!["0x03", "0x04", "0x05"]);
+        assert_eq!(merged[2].event_type, "liquidation");
+        assert_eq!(merged[0].event_type, "withdraw");
+    }
+}
@@ -0,0 +1,107 @@
+use alloy::primitives::Address;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::adapters::traits::AdapterError;
+use crate::blockchain::EthereumClient;
+use crate::services::IERC20;
+
+/// Immutable ERC20 metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TokenMetadata {
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+}
+
+/// Memoizes `symbol()`, `name()` and `decimals()` per `(chain_id, token)`.
+///
+/// Token metadata never changes, so entries are kept for the life of the process.
+/// Failed lookups are not cached and will be retried on the next request.
+#[derive(Debug, Default)]
+pub struct TokenMetadataCache {
+    entries: RwLock<HashMap<(u64, Address), TokenMetadata>>,
+}
+
+impl TokenMetadataCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process-wide cache shared by all adapters
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<TokenMetadataCache>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::new())).clone()
+    }
+
+    pub fn get(&self, chain_id: u64, token: Address) -> Option<TokenMetadata> {
+        self.entries.read().unwrap().get(&(chain_id, token)).cloned()
+    }
+
+    pub fn insert(&self, chain_id: u64, token: Address, metadata: TokenMetadata) {
+        self.entries.write().unwrap().insert((chain_id, token), metadata);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Cached metadata, fetching all three fields in parallel on a miss
+    pub async fn get_or_fetch(
+        &self,
+        client: &EthereumClient,
+        chain_id: u64,
+        token: Address,
+    ) -> Result<TokenMetadata, AdapterError> {
+        if let Some(metadata) = self.get(chain_id, token) {
+            return Ok(metadata);
+        }
+
+        let (symbol, name, decimals) = tokio::try_join!(
+            client.call(token, &IERC20::symbolCall {}),
+            client.call(token, &IERC20::nameCall {}),
+            client.call(token, &IERC20::decimalsCall {}),
+        )?;
+
+        let metadata = TokenMetadata {
+            symbol: symbol._0,
+            name: name._0,
+            decimals: decimals._0,
+        };
+        self.insert(chain_id, token, metadata.clone());
+
+        Ok(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_are_keyed_by_chain() {
+        let cache = TokenMetadataCache::new();
+        let token = Address::repeat_byte(0x11);
+        let usdc = TokenMetadata {
+            symbol: "USDC".to_string(),
+            name: "USD Coin".to_string(),
+            decimals: 6,
+        };
+
+        cache.insert(1, token, usdc.clone());
+
+        assert_eq!(cache.get(1, token), Some(usdc));
+        assert_eq!(cache.get(42161, token), None);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_global_cache_is_shared() {
+        assert!(Arc::ptr_eq(&TokenMetadataCache::global(), &TokenMetadataCache::global()));
+    }
+}
@@ -0,0 +1,209 @@
+use alloy::primitives::Address;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use super::monitoring::AlertLevel;
+use crate::adapters::traits::Position;
+
+/// How far back a drop is measured: "drops 15% in a day"
+pub const VALUE_DROP_WINDOW_SECS: u64 = 24 * 3600;
+
+/// A position, or the whole portfolio, worth less than its peak over the last day by
+/// at least the wallet's `value_drop_percent`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValueDrop {
+    /// `None` for the portfolio as a whole
+    pub position_id: Option<String>,
+    pub protocol: Option<String>,
+    pub asset: Option<String>,
+    /// Highest value read within the window
+    pub peak_value_usd: f64,
+    pub current_value_usd: f64,
+    pub drop_percentage: f64,
+    /// Warning at the threshold, critical at twice it
+    pub level: AlertLevel,
+    /// The drop crossed into this level on this read, so it should be alerted
+    pub new: bool,
+}
+
+#[derive(Debug)]
+struct ValueSeries {
+    /// (unix seconds, value) reads within the window, oldest first
+    samples: VecDeque<(u64, f64)>,
+    level: AlertLevel,
+}
+
+impl ValueSeries {
+    fn new() -> Self {
+        Self { samples: VecDeque::new(), level: AlertLevel::Healthy }
+    }
+
+    /// Record a read and return the drop from the window's peak, in percent
+    fn record(&mut self, timestamp: u64, value_usd: f64) -> (f64, f64) {
+        while self.samples.front().is_some_and(|(at, _)| *at + VALUE_DROP_WINDOW_SECS < timestamp) {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((timestamp, value_usd));
+        let peak = self.samples.iter().map(|(_, value)| *value).fold(f64::MIN, f64::max);
+        let drop_percentage = if peak > 0.0 { (peak - value_usd) / peak * 100.0 } else { 0.0 };
+        (peak, drop_percentage)
+    }
+}
+
+#[derive(Debug, Default)]
+struct WalletSeries {
+    portfolio: Option<ValueSeries>,
+    positions: HashMap<String, ValueSeries>,
+}
+
+/// Rolling one-day value history of each monitored wallet and its positions, so a
+/// drop is caught however many reads it is spread over.
+///
+/// Kept in memory: after a restart drops are measured from the first read onwards.
+#[derive(Debug, Default)]
+pub struct ValueDropTracker {
+    wallets: Mutex<HashMap<Address, WalletSeries>>,
+}
+
+impl ValueDropTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process-wide tracker shared by the monitors
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<ValueDropTracker>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::new())).clone()
+    }
+
+    /// Record a read of `wallet` taken at `timestamp` and return every drop of at least
+    /// `threshold_percent` still in effect. A `partial` read skips the portfolio total
+    /// and keeps the history of positions it's missing, since neither was really lost.
+    pub fn observe(
+        &self,
+        wallet: Address,
+        positions: &[Position],
+        partial: bool,
+        threshold_percent: f64,
+        timestamp: u64,
+    ) -> Vec<ValueDrop> {
+        let mut wallets = self.wallets.lock().unwrap();
+        let series = wallets.entry(wallet).or_default();
+        // Debt and unpriced positions have no value that can drop
+        let held: Vec<&Position> = positions.iter().filter(|p| p.value_usd > 0.0 && !p.is_price_unknown()).collect();
+
+        if !partial {
+            series.positions.retain(|id, _| held.iter().any(|p| &p.id == id));
+        }
+
+        let mut drops = Vec::new();
+        for position in &held {
+            let history = series.positions.entry(position.id.clone()).or_insert_with(ValueSeries::new);
+            let (peak, drop_percentage) = history.record(timestamp, position.value_usd);
+            drops.extend(update_level(history, peak, position.value_usd, drop_percentage, threshold_percent).map(|(level, new)| ValueDrop {
+                position_id: Some(position.id.clone()),
+                protocol: Some(position.protocol.clone()),
+                asset: Some(position.pair.clone()),
+                peak_value_usd: peak,
+                current_value_usd: position.value_usd,
+                drop_percentage,
+                level,
+                new,
+            }));
+        }
+
+        if !partial {
+            let total: f64 = held.iter().map(|p| p.value_usd).sum();
+            let history = series.portfolio.get_or_insert_with(ValueSeries::new);
+            let (peak, drop_percentage) = history.record(timestamp, total);
+            drops.extend(update_level(history, peak, total, drop_percentage, threshold_percent).map(|(level, new)| ValueDrop {
+                position_id: None,
+                protocol: None,
+                asset: None,
+                peak_value_usd: peak,
+                current_value_usd: total,
+                drop_percentage,
+                level,
+                new,
+            }));
+        }
+
+        drops
+    }
+}
+
+// Band the drop and remember it; `Some((level, new))` while it's at or past the threshold
+fn update_level(history: &mut ValueSeries, peak: f64, current: f64, drop_percentage: f64, threshold_percent: f64) -> Option<(AlertLevel, bool)> {
+    let level = if peak <= 0.0 || current >= peak {
+        AlertLevel::Healthy
+    } else if drop_percentage >= 2.0 * threshold_percent {
+        AlertLevel::Critical
+    } else if drop_percentage >= threshold_percent {
+        AlertLevel::Warning
+    } else {
+        AlertLevel::Healthy
+    };
+    let new = level > history.level;
+    history.level = level;
+    (level != AlertLevel::Healthy).then_some((level, new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 3600;
+
+    fn position(id: &str, value_usd: f64) -> Position {
+        Position {
+            id: id.to_string(),
+            protocol: "uniswap_v3".to_string(),
+            position_type: "liquidity".to_string(),
+            pair: "PEPE/WETH".to_string(),
+            value_usd,
+            pnl_usd: 0.0,
+            pnl_percentage: 0.0,
+            metadata: serde_json::json!({}),
+            last_updated: 0,
+        }
+    }
+
+    #[test]
+    fn test_position_drop_over_a_day_is_alerted_once() {
+        let tracker = ValueDropTracker::new();
+        let stable = position("stable", 90_000.0);
+        let read = |value: f64, at: u64| tracker.observe(Address::ZERO, &[position("lp", value), stable.clone()], false, 15.0, at);
+
+        // 10% then another 6% spread over hours: no single read drops 15%
+        assert!(read(10_000.0, 0).is_empty());
+        assert!(read(9_000.0, 6 * HOUR).is_empty());
+        let drops = read(8_400.0, 12 * HOUR);
+        assert_eq!(drops.len(), 1);
+        assert_eq!(drops[0].position_id.as_deref(), Some("lp"));
+        assert_eq!(drops[0].level, AlertLevel::Warning);
+        assert!(drops[0].new && (drops[0].drop_percentage - 16.0).abs() < 1e-9);
+
+        // Still down, but already alerted
+        let drops = read(8_300.0, 13 * HOUR);
+        assert!(!drops[0].new);
+
+        // A day after the peak it has aged out of the window
+        assert!(read(8_300.0, 25 * HOUR).is_empty());
+    }
+
+    #[test]
+    fn test_portfolio_drop_and_partial_reads() {
+        let tracker = ValueDropTracker::new();
+        assert!(tracker.observe(Address::ZERO, &[position("a", 5_000.0), position("b", 5_000.0)], false, 10.0, 0).is_empty());
+
+        // A partial read missing "b" is neither a position nor a portfolio drop
+        assert!(tracker.observe(Address::ZERO, &[position("a", 5_000.0)], true, 10.0, HOUR).is_empty());
+
+        let drops = tracker.observe(Address::ZERO, &[position("a", 4_000.0), position("b", 4_000.0)], false, 10.0, 2 * HOUR);
+        assert_eq!(drops.len(), 3);
+        let portfolio = drops.iter().find(|drop| drop.position_id.is_none()).unwrap();
+        assert_eq!((portfolio.peak_value_usd, portfolio.current_value_usd), (10_000.0, 8_000.0));
+        assert_eq!(portfolio.level, AlertLevel::Critical);
+    }
+}
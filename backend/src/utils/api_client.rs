@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use reqwest::{RequestBuilder, Response, StatusCode};
+
+use crate::adapters::traits::AdapterError;
+
+/// Timeout and retry policy shared by every outbound HTTP API client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApiClientConfig {
+    /// Per-attempt request timeout
+    pub timeout: Duration,
+    /// Retries after the first attempt for transient failures
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled on each subsequent retry
+    pub backoff: Duration,
+}
+
+impl Default for ApiClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            max_retries: 2,
+            backoff: Duration::from_millis(250),
+        }
+    }
+}
+
+impl ApiClientConfig {
+    /// Configured from `API_TIMEOUT_SECS` (default 10), `API_MAX_RETRIES` (2) and
+    /// `API_RETRY_BACKOFF_MS` (250)
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<u64>().ok());
+
+        Self {
+            timeout: read("API_TIMEOUT_SECS").map(Duration::from_secs).unwrap_or(defaults.timeout),
+            max_retries: read("API_MAX_RETRIES").map(|v| v as u32).unwrap_or(defaults.max_retries),
+            backoff: read("API_RETRY_BACKOFF_MS").map(Duration::from_millis).unwrap_or(defaults.backoff),
+        }
+    }
+
+    pub fn build(self) -> Result<ApiClient, AdapterError> {
+        let inner = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .map_err(|e| AdapterError::NetworkError(format!("Failed to create HTTP client: {}", e)))?;
+
+        Ok(ApiClient { inner, config: self })
+    }
+
+    /// Backoff before retry number `retry` (zero-based)
+    fn delay(&self, retry: u32) -> Duration {
+        self.backoff.saturating_mul(2u32.saturating_pow(retry))
+    }
+}
+
+/// `reqwest::Client` that retries timeouts, connection errors, 429 and 5xx gateway
+/// responses according to its [`ApiClientConfig`]
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    inner: reqwest::Client,
+    config: ApiClientConfig,
+}
+
+impl ApiClient {
+    /// Client using the environment-configured policy
+    pub fn from_env() -> Result<Self, AdapterError> {
+        ApiClientConfig::from_env().build()
+    }
+
+    pub fn config(&self) -> ApiClientConfig {
+        self.config
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.inner.get(url)
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.inner.post(url)
+    }
+
+    /// Send `request`, retrying transient failures. Non-transient error statuses are
+    /// returned as a response for the caller to inspect.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, AdapterError> {
+        let mut retry = 0;
+
+        loop {
+            // Streaming bodies can't be replayed, so those requests get a single attempt
+            let attempt = request.try_clone();
+            let can_retry = attempt.is_some() && retry < self.config.max_retries;
+            let attempt = match attempt {
+                Some(attempt) => attempt,
+                None => return request.send().await.map_err(Self::network_error),
+            };
+
+            match attempt.send().await {
+                Ok(response) if can_retry && is_transient_status(response.status()) => {
+                    tracing::debug!(url = %response.url(), status = %response.status(), retry, "Retrying API request");
+                }
+                Ok(response) => return Ok(response),
+                Err(e) if can_retry && (e.is_timeout() || e.is_connect()) => {
+                    tracing::debug!(error = %e, retry, "Retrying API request");
+                }
+                Err(e) => return Err(Self::network_error(e)),
+            }
+
+            tokio::time::sleep(self.config.delay(retry)).await;
+            retry += 1;
+        }
+    }
+
+    fn network_error(e: reqwest::Error) -> AdapterError {
+        if e.is_timeout() {
+            AdapterError::Timeout(format!("API request timed out: {}", e))
+        } else {
+            AdapterError::NetworkError(format!("API request failed: {}", e))
+        }
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_per_retry() {
+        let config = ApiClientConfig {
+            timeout: Duration::from_secs(1),
+            max_retries: 3,
+            backoff: Duration::from_millis(100),
+        };
+
+        assert_eq!(config.delay(0), Duration::from_millis(100));
+        assert_eq!(config.delay(2), Duration::from_millis(400));
+    }
+
+    #[test]
+    fn test_only_gateway_and_rate_limit_statuses_are_transient() {
+        assert!(is_transient_status(StatusCode::BAD_GATEWAY));
+        assert!(is_transient_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(is_transient_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_transient_status(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(!is_transient_status(StatusCode::NOT_FOUND));
+    }
+}
@@ -0,0 +1,130 @@
+use std::sync::OnceLock;
+
+/// Significant digits kept for non-zero values too small to show at the configured precision
+const SMALL_VALUE_SIGNIFICANT_DIGITS: i32 = 3;
+/// `f64` carries no more than this many meaningful decimal places for small values
+const MAX_DECIMALS: usize = 18;
+
+/// Decimal places monetary values and token amounts are rounded to in API output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Precision {
+    pub usd_decimals: usize,
+    pub amount_decimals: usize,
+}
+
+impl Default for Precision {
+    fn default() -> Self {
+        Self {
+            usd_decimals: 2,
+            amount_decimals: 6,
+        }
+    }
+}
+
+impl Precision {
+    /// Defaults overridden by `USD_DECIMALS` / `TOKEN_AMOUNT_DECIMALS`
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let decimals = |key: &str, default: usize| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .map_or(default, |d| d.min(MAX_DECIMALS))
+        };
+        Self {
+            usd_decimals: decimals("USD_DECIMALS", defaults.usd_decimals),
+            amount_decimals: decimals("TOKEN_AMOUNT_DECIMALS", defaults.amount_decimals),
+        }
+    }
+
+    /// Process-wide precision, read from the environment once
+    pub fn global() -> Self {
+        static GLOBAL: OnceLock<Precision> = OnceLock::new();
+        *GLOBAL.get_or_init(Self::from_env)
+    }
+
+    /// USD value rounded for a JSON number field
+    pub fn usd(&self, value: f64) -> f64 {
+        round_to(value, self.usd_decimals)
+    }
+
+    /// USD value for fields the API emits as strings
+    pub fn format_usd(&self, value: f64) -> String {
+        format_decimal(value, self.usd_decimals)
+    }
+
+    /// Token amount rounded for a JSON number field
+    pub fn amount(&self, value: f64) -> f64 {
+        round_to(value, self.amount_decimals)
+    }
+
+    /// Token amount for fields the API emits as strings
+    pub fn format_amount(&self, value: f64) -> String {
+        format_decimal(value, self.amount_decimals)
+    }
+}
+
+/// Round half away from zero to `decimals` places. Non-zero values that would round to
+/// zero keep a few significant digits instead, so dust doesn't read as nothing.
+pub fn round_to(value: f64, decimals: usize) -> f64 {
+    let decimals = visible_decimals(value, decimals);
+    let factor = 10f64.powi(decimals as i32);
+    let scaled = value * factor;
+    if !scaled.is_finite() {
+        return value;
+    }
+    // Adding 0.0 turns -0.0 into 0.0
+    (scaled.round() / factor) + 0.0
+}
+
+/// Fixed-point string with `decimals` places, never in exponent notation. Tiny non-zero
+/// values keep a few significant digits, and non-finite values are written as 0.
+pub fn format_decimal(value: f64, decimals: usize) -> String {
+    if !value.is_finite() {
+        return format!("{:.*}", decimals, 0.0);
+    }
+
+    let places = visible_decimals(value, decimals);
+    let formatted = format!("{:.*}", places, round_to(value, decimals));
+    if places > decimals {
+        // Only the significant digits matter for dust
+        let trimmed = formatted.trim_end_matches('0');
+        return trimmed.to_string();
+    }
+    formatted
+}
+
+/// Decimal places needed to show `value`: `decimals`, or more when it is non-zero but
+/// smaller than the last place
+fn visible_decimals(value: f64, decimals: usize) -> usize {
+    let magnitude = value.abs();
+    if magnitude == 0.0 || !magnitude.is_finite() || magnitude >= 0.5 * 10f64.powi(-(decimals as i32)) {
+        return decimals;
+    }
+    let leading_zeros = (-magnitude.log10()).ceil() as i32 - 1;
+    ((leading_zeros + SMALL_VALUE_SIGNIFICANT_DIGITS).max(0) as usize).clamp(decimals, MAX_DECIMALS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_float_noise_is_rounded_away() {
+        assert_eq!(format_decimal(1000.0000000001, 2), "1000.00");
+        assert_eq!(format_decimal(0.1 + 0.2, 2), "0.30");
+        assert_eq!(format_decimal(-2.006, 2), "-2.01");
+        assert_eq!(format_decimal(-0.0, 2), "0.00");
+        assert_eq!(format_decimal(1e18, 2), "1000000000000000000.00");
+        assert_eq!(format_decimal(f64::NAN, 2), "0.00");
+        assert_eq!(round_to(1234.5678, 2), 1234.57);
+    }
+
+    #[test]
+    fn test_dust_keeps_significant_digits() {
+        assert_eq!(format_decimal(0.000123456, 2), "0.000123");
+        assert_eq!(format_decimal(-0.004, 2), "-0.004");
+        assert_eq!(round_to(0.000123456, 2), 0.000123);
+        assert_eq!(format_decimal(0.000123456, 6), "0.000123");
+    }
+}
@@ -0,0 +1,26 @@
+use alloy::primitives::U256;
+
+/// Decimals of ETH and the ETH-denominated tokens (stETH, rETH, eETH, ...) built on it
+pub const ETH_DECIMALS: u8 = 18;
+
+/// Raw on-chain token amount in whole tokens, e.g. 1_500_000 at 6 decimals is 1.5.
+/// Take `decimals` from the token itself (see `TokenMetadataCache`); assuming 18 misvalues
+/// USDC by 12 orders of magnitude.
+pub fn normalize_amount(raw: U256, decimals: u8) -> f64 {
+    let raw: f64 = raw.to_string().parse().unwrap_or(0.0);
+    raw / 10f64.powi(decimals as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amounts_scale_by_token_decimals() {
+        assert_eq!(normalize_amount(U256::from(1_500_000u64), 6), 1.5);
+        assert_eq!(normalize_amount(U256::from(250_000_000u64), 8), 2.5);
+        assert_eq!(normalize_amount(U256::from(3u64) * U256::from(10u64).pow(U256::from(18u64)), ETH_DECIMALS), 3.0);
+        assert_eq!(normalize_amount(U256::ZERO, 6), 0.0);
+        assert!(normalize_amount(U256::MAX, 18) > 1e59);
+    }
+}
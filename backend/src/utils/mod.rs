@@ -0,0 +1,13 @@
+pub mod api_client;
+pub mod fault_tolerance;
+pub mod format;
+pub mod math;
+pub mod pagination;
+pub mod retry;
+
+pub use api_client::{ApiClient, ApiClientConfig};
+pub use fault_tolerance::{CircuitBreaker, CircuitState};
+pub use format::Precision;
+pub use math::{normalize_amount, ETH_DECIMALS};
+pub use pagination::{PaginatedResponse, Pagination};
+pub use retry::{market_read_timeout, retry_call, with_timeout, RetryPolicy};
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+/// Position of a page within the full result set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pagination {
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+    pub has_more: bool,
+}
+
+/// One page of `items` plus where it sits in the full list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaginatedResponse<T> {
+    pub items: Vec<T>,
+    pub pagination: Pagination,
+}
+
+impl<T> PaginatedResponse<T> {
+    /// Slice `items` to `limit` entries starting at `offset`. Without a limit the page
+    /// runs to the end of the list.
+    pub fn paginate(items: Vec<T>, limit: Option<usize>, offset: usize) -> Self {
+        let total = items.len();
+        let limit = limit.unwrap_or(total);
+
+        let items: Vec<T> = items.into_iter().skip(offset).take(limit).collect();
+        let has_more = offset.saturating_add(items.len()) < total;
+
+        Self {
+            items,
+            pagination: Pagination {
+                total,
+                limit,
+                offset,
+                has_more,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate_reports_remaining_items() {
+        let page = PaginatedResponse::paginate((0..10).collect(), Some(4), 4);
+        assert_eq!(page.items, vec![4, 5, 6, 7]);
+        assert_eq!(page.pagination.total, 10);
+        assert!(page.pagination.has_more);
+
+        let last = PaginatedResponse::paginate((0..10).collect(), Some(4), 8);
+        assert_eq!(last.items, vec![8, 9]);
+        assert!(!last.pagination.has_more);
+    }
+
+    #[test]
+    fn test_paginate_without_limit_returns_rest() {
+        let page = PaginatedResponse::paginate(vec!["a", "b", "c"], None, 1);
+        assert_eq!(page.items, vec!["b", "c"]);
+        assert_eq!(page.pagination.limit, 3);
+
+        let past_end = PaginatedResponse::<u8>::paginate(vec![1, 2], Some(5), 10);
+        assert!(past_end.items.is_empty());
+        assert!(!past_end.pagination.has_more);
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::adapters::traits::AdapterError;
+
+/// Retry policy for individual contract calls within a multi-call fetch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt for transient failures
+    pub max_retries: u32,
+    /// Upper bound of the delay before the first retry; doubled on each subsequent retry
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Configured from `RPC_CALL_MAX_RETRIES` (default 2) and `RPC_CALL_RETRY_BACKOFF_MS` (200)
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let read = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<u64>().ok());
+
+        Self {
+            max_retries: read("RPC_CALL_MAX_RETRIES").map(|v| v as u32).unwrap_or(defaults.max_retries),
+            base_delay: read("RPC_CALL_RETRY_BACKOFF_MS").map(Duration::from_millis).unwrap_or(defaults.base_delay),
+            max_delay: defaults.max_delay,
+        }
+    }
+
+    /// No retries, for callers that already retry at a higher level
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `retry` (zero-based), scaled by `jitter` in `[0, 1]`.
+    /// Half the exponential delay is fixed and half is jittered, so concurrent callers
+    /// retrying against the same node spread out without retrying immediately.
+    fn delay(&self, retry: u32, jitter: f64) -> Duration {
+        let ceiling = self.base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        ceiling.mul_f64(0.5 + 0.5 * jitter.clamp(0.0, 1.0))
+    }
+}
+
+/// Run `call`, retrying transient transport failures (timeouts, rate limiting, RPC
+/// errors) with jittered exponential backoff. Reverts and decoding errors are
+/// returned immediately since retrying can't fix them.
+pub async fn retry_call<T, F, Fut>(policy: &RetryPolicy, mut call: F) -> Result<T, AdapterError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, AdapterError>>,
+{
+    let mut retry = 0;
+
+    loop {
+        match call().await {
+            Ok(value) => return Ok(value),
+            Err(e) if retry < policy.max_retries && e.kind().is_retryable() => {
+                let delay = policy.delay(retry, random_unit());
+                tracing::debug!(error = %e, retry, delay_ms = delay.as_millis() as u64, "Retrying contract call");
+                tokio::time::sleep(delay).await;
+                retry += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// How long one reserve or market of a multi-market account read may take before it's
+/// skipped, from `MARKET_READ_TIMEOUT_SECS` (default 10)
+pub fn market_read_timeout() -> Duration {
+    static TIMEOUT: OnceLock<Duration> = OnceLock::new();
+    *TIMEOUT.get_or_init(|| {
+        std::env::var("MARKET_READ_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .filter(|secs| *secs > 0)
+            .map_or(Duration::from_secs(10), Duration::from_secs)
+    })
+}
+
+/// Run `call`, failing with `AdapterError::Timeout` naming `what` if it takes longer
+/// than `timeout`
+pub async fn with_timeout<T, Fut>(timeout: Duration, what: &str, call: Fut) -> Result<T, AdapterError>
+where
+    Fut: Future<Output = Result<T, AdapterError>>,
+{
+    tokio::time::timeout(timeout, call)
+        .await
+        .unwrap_or_else(|_| Err(AdapterError::Timeout(format!("{} took longer than {:?}", what, timeout))))
+}
+
+/// Uniform-ish value in `[0, 1)` from the standard library's randomly seeded hasher
+fn random_unit() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transient_errors_are_retried_until_success() {
+        let attempts = AtomicU32::new(0);
+        let result = retry_call(&fast_policy(3), || async {
+            if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                Err(AdapterError::Timeout("slow node".to_string()))
+            } else {
+                Ok(42)
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_reverts_are_not_retried() {
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = retry_call(&fast_policy(3), || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(AdapterError::ContractError("execution reverted".to_string()))
+        })
+        .await;
+
+        assert!(matches!(result, Err(AdapterError::ContractError(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() {
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = retry_call(&fast_policy(2), || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(AdapterError::RpcError("connection reset".to_string()))
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_delay_is_jittered_and_capped() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+        };
+
+        assert_eq!(policy.delay(0, 0.0), Duration::from_millis(50));
+        assert_eq!(policy.delay(0, 1.0), Duration::from_millis(100));
+        assert_eq!(policy.delay(4, 1.0), Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn test_slow_calls_time_out() {
+        let slow = with_timeout(Duration::from_millis(5), "reserve", std::future::pending::<Result<(), AdapterError>>()).await;
+        assert!(matches!(slow, Err(AdapterError::Timeout(message)) if message.starts_with("reserve")));
+
+        let fast = with_timeout(Duration::from_secs(1), "reserve", async { Ok(1) }).await;
+        assert_eq!(fast.unwrap(), 1);
+    }
+}
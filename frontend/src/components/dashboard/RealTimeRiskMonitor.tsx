@@ -201,25 +201,26 @@ const RealTimeRiskMonitor: React.FC<RealTimeRiskMonitorProps> = ({ userAddress,
         {/* Risk Factors Breakdown */}
         <div className="grid grid-cols-2 md:grid-cols-3 lg:grid-cols-6 gap-4">
           {[
-            { key: 'liquidity_risk', label: 'Liquidity', value: riskMetrics?.liquidity_risk || 0 },
-            { key: 'volatility_risk', label: 'Volatility', value: riskMetrics?.volatility_risk || 0 },
-            { key: 'mev_risk', label: 'MEV', value: riskMetrics?.mev_risk || 0 },
-            { key: 'protocol_risk', label: 'Protocol', value: riskMetrics?.protocol_risk || 0 },
+            { key: 'liquidity_risk', label: 'Liquidity', value: riskMetrics?.liquidity_risk ?? null },
+            { key: 'volatility_risk', label: 'Volatility', value: riskMetrics?.volatility_risk ?? null },
+            { key: 'mev_risk', label: 'MEV', value: riskMetrics?.mev_risk ?? null },
+            { key: 'protocol_risk', label: 'Protocol', value: riskMetrics?.protocol_risk ?? null },
 
           ].map((factor) => (
             <div key={factor.key} className="bg-gray-800/50 rounded-lg p-3">
               <div className="text-xs text-gray-400 mb-1">{factor.label}</div>
-              <div className={`text-lg font-semibold ${getRiskColor(factor.value).split(' ')[0]}`}>
-                {Math.round(factor.value)}
+              <div className={`text-lg font-semibold ${factor.value === null ? 'text-gray-500' : getRiskColor(factor.value).split(' ')[0]}`}>
+                {factor.value === null ? 'N/A' : Math.round(factor.value)}
               </div>
               <div className="w-full bg-gray-700 rounded-full h-1 mt-2">
                 <div
                   className={`h-1 rounded-full transition-all duration-500 ${
+                    factor.value === null ? 'bg-gray-600' :
                     factor.value >= 80 ? 'bg-red-500' :
                     factor.value >= 60 ? 'bg-orange-500' :
                     factor.value >= 30 ? 'bg-yellow-500' : 'bg-green-500'
                   }`}
-                  style={{ width: `${factor.value}%` }}
+                  style={{ width: `${factor.value ?? 0}%` }}
                 ></div>
               </div>
             </div>
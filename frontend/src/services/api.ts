@@ -46,11 +46,14 @@ export interface ApiResponse<T> {
 
 // Risk Monitor specific interfaces
 export interface PortfolioRiskMetrics {
-  overall_risk: number;
-  liquidity_risk: number;
-  volatility_risk: number;
-  mev_risk: number;
-  protocol_risk: number;
+  // 'insufficient_data' when there is no wallet to score; the metrics are then null
+  status?: 'ok' | 'insufficient_data';
+  overall_risk: number | null;
+  liquidity_risk: number | null;
+  // Not estimated by the risk-metrics endpoint, which sends null
+  volatility_risk: number | null;
+  mev_risk: number | null;
+  protocol_risk: number | null;
   timestamp: string;
 }
 